@@ -0,0 +1,568 @@
+//! Parsing of simple Python constants, as found in the game's 'scripts/common/constants.py'
+//! when scripts are decompiled, and generation of the equivalent Rust constants.
+//!
+//! This is not a Python interpreter, only top-level assignments and assignments in
+//! the body of top-level classes are parsed, their value must be a literal or a simple
+//! integer expression of literals and previously defined constants, all other
+//! statements are ignored.
+
+use std::io::{self, Write};
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::generate_rust_identifier;
+use super::output::Output;
+
+
+/// All constants parsed from a Python module.
+#[derive(Debug, Default)]
+pub struct Constants {
+    /// Top-level constants.
+    pub values: Vec<Constant>,
+    /// Constants defined in the body of top-level classes, each class being a group.
+    pub groups: Vec<ConstantGroup>,
+}
+
+/// A group of constants defined in the body of a class.
+#[derive(Debug)]
+pub struct ConstantGroup {
+    pub name: String,
+    pub values: Vec<Constant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Constant {
+    pub name: String,
+    pub value: ConstantValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Constants {
+
+    /// Parse all constants of the given Python source code.
+    pub fn parse(source: &str) -> Self {
+
+        let mut constants = Self::default();
+
+        // The current class whose body is being parsed, with the indentation of its
+        // body, which is known after its first statement.
+        let mut class: Option<(ConstantGroup, Option<usize>)> = None;
+
+        for (indent, statement) in Statements::new(source) {
+
+            if indent == 0 {
+
+                if let Some((group, _)) = class.take() {
+                    constants.push_group(group);
+                }
+
+                if let Some(name) = parse_class_header(statement) {
+                    class = Some((ConstantGroup { name: name.to_string(), values: Vec::new() }, None));
+                } else if let Some(constant) = parse_assignment(statement, &constants, None) {
+                    push_constant(&mut constants.values, constant);
+                }
+
+            } else if let Some((group, body_indent)) = &mut class {
+                // Statements nested deeper than the class body, such as methods'
+                // bodies, are ignored.
+                if *body_indent.get_or_insert(indent) == indent {
+                    if let Some(constant) = parse_assignment(statement, &constants, Some(&group.values)) {
+                        push_constant(&mut group.values, constant);
+                    }
+                }
+            }
+
+        }
+
+        if let Some((group, _)) = class.take() {
+            constants.push_group(group);
+        }
+
+        constants
+
+    }
+
+    /// Return the names of all groups.
+    pub fn group_names(&self) -> HashSet<String> {
+        self.groups.iter()
+            .map(|group| group.name.clone())
+            .collect()
+    }
+
+    /// Internal function to add a parsed group, empty groups are ignored and a group
+    /// redefined replaces the previous one, like in Python.
+    fn push_group(&mut self, group: ConstantGroup) {
+        if group.values.is_empty() {
+            return;
+        }
+        self.groups.retain(|g| g.name != group.name);
+        self.groups.push(group);
+    }
+
+    /// Internal function to resolve a top-level name or a dotted name to a class member.
+    fn resolve(&self, name: &str) -> Option<&ConstantValue> {
+        if let Some((group_name, name)) = name.split_once('.') {
+            self.groups.iter()
+                .find(|group| group.name == group_name)
+                .and_then(|group| find_constant(&group.values, name))
+        } else {
+            find_constant(&self.values, name)
+        }
+    }
+
+}
+
+/// Internal function to add a constant, a constant redefined replaces its value.
+fn push_constant(values: &mut Vec<Constant>, constant: Constant) {
+    if let Some(prev) = values.iter_mut().find(|c| c.name == constant.name) {
+        prev.value = constant.value;
+    } else {
+        values.push(constant);
+    }
+}
+
+#[inline]
+fn find_constant<'a>(values: &'a [Constant], name: &str) -> Option<&'a ConstantValue> {
+    values.iter().find(|c| c.name == name).map(|c| &c.value)
+}
+
+/// Return the variants of a group if it can be generated as an enumeration: all its
+/// constants are integers with distinct values.
+fn group_enum_values(group: &ConstantGroup) -> Option<Vec<(&str, i64)>> {
+    let mut seen = HashSet::new();
+    group.values.iter()
+        .map(|c| match c.value {
+            ConstantValue::Int(n) if seen.insert(n) => Some((c.name.as_str(), n)),
+            _ => None,
+        })
+        .collect()
+}
+
+
+/// Generate the 'constants.rs' module of the parsed constants, groups of distinct
+/// integers are generated as enumerations, other groups as modules of constants.
+pub fn generate(mod_dir: &Path, constants: &Constants, output: &mut Output) -> io::Result<()> {
+
+    println!("== Writing constants...");
+    let constants_file = mod_dir.join("constants.rs");
+    let mut writer = Vec::new();
+    writeln!(writer, "#![allow(non_upper_case_globals)]")?;
+    writeln!(writer)?;
+
+    for constant in &constants.values {
+        write_constant(&mut writer, constant, "")?;
+    }
+
+    for group in &constants.groups {
+
+        writeln!(writer)?;
+
+        let name = generate_rust_identifier(&group.name);
+
+        if let Some(variants) = group_enum_values(group) {
+
+            writeln!(writer, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]")?;
+            writeln!(writer, "#[repr(i64)]")?;
+            writeln!(writer, "pub enum {name} {{")?;
+            for &(variant, value) in &variants {
+                writeln!(writer, "    {} = {value},", generate_rust_identifier(variant))?;
+            }
+            writeln!(writer, "}}")?;
+            writeln!(writer)?;
+
+            writeln!(writer, "impl {name} {{")?;
+            writeln!(writer, "    pub fn from_value(value: i64) -> Option<Self> {{")?;
+            writeln!(writer, "        Some(match value {{")?;
+            for &(variant, value) in &variants {
+                writeln!(writer, "            {value} => Self::{},", generate_rust_identifier(variant))?;
+            }
+            writeln!(writer, "            _ => return None,")?;
+            writeln!(writer, "        }})")?;
+            writeln!(writer, "    }}")?;
+            writeln!(writer, "    pub fn value(self) -> i64 {{ self as i64 }}")?;
+            writeln!(writer, "}}")?;
+
+        } else {
+            writeln!(writer, "pub mod {name} {{")?;
+            for constant in &group.values {
+                write_constant(&mut writer, constant, "    ")?;
+            }
+            writeln!(writer, "}}")?;
+        }
+
+    }
+
+    output.write(&constants_file, &writer)
+
+}
+
+fn write_constant(mut writer: impl Write, constant: &Constant, indent: &str) -> io::Result<()> {
+    let name = generate_rust_identifier(&constant.name);
+    match &constant.value {
+        ConstantValue::Int(n) => writeln!(writer, "{indent}pub const {name}: i64 = {n};"),
+        ConstantValue::Float(n) => writeln!(writer, "{indent}pub const {name}: f64 = {n:?};"),
+        ConstantValue::Bool(b) => writeln!(writer, "{indent}pub const {name}: bool = {b};"),
+        ConstantValue::Str(s) => writeln!(writer, "{indent}pub const {name}: &str = {s:?};"),
+    }
+}
+
+
+/// Iterator over the logical statements of a Python source, with their indentation,
+/// comments, blank lines and docstrings are skipped and statements spanning multiple
+/// lines are yielded as a single statement.
+struct Statements<'a> {
+    lines: std::str::Lines<'a>,
+}
+
+impl<'a> Statements<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { lines: source.lines() }
+    }
+}
+
+impl<'a> Iterator for Statements<'a> {
+
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        loop {
+
+            let line = self.lines.next()?;
+            let code = strip_comment(line);
+            let statement = code.trim();
+            if statement.is_empty() {
+                continue;
+            }
+
+            let indent = line.len() - line.trim_start().len();
+
+            // Multi-line statements are consumed and ignored, they are never simple
+            // constants: open brackets, explicit continuations or triple quotes.
+            let mut depth = bracket_depth(code);
+            let mut continued = statement.ends_with('\\');
+            let mut triple_quote = ["\"\"\"", "'''"].into_iter()
+                .find(|quote| statement.matches(quote).count() % 2 == 1);
+
+            if depth <= 0 && !continued && triple_quote.is_none() {
+                return Some((indent, statement));
+            }
+
+            while depth > 0 || continued || triple_quote.is_some() {
+                let line = self.lines.next()?;
+                if let Some(quote) = triple_quote {
+                    if line.matches(quote).count() % 2 == 1 {
+                        triple_quote = None;
+                    }
+                    continue;
+                }
+                let code = strip_comment(line);
+                depth += bracket_depth(code);
+                continued = code.trim_end().ends_with('\\');
+            }
+
+        }
+
+    }
+
+}
+
+/// Return the given line without its comment, if any, ignoring '#' in strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..index],
+            (None, _) => {}
+        }
+    }
+    line
+}
+
+/// Return the difference between opened and closed brackets in the given code,
+/// ignoring those in strings.
+fn bracket_depth(code: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in code.chars() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, _) => {}
+        }
+    }
+    depth
+}
+
+/// Parse a class header statement, returning the class name.
+fn parse_class_header(statement: &str) -> Option<&str> {
+    let rest = statement.strip_prefix("class")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let name_len = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+    let (name, rest) = rest.split_at(name_len);
+    if name.is_empty() || !rest.trim_end().ends_with(':') {
+        return None;
+    }
+    Some(name)
+}
+
+/// Parse a simple 'NAME = expression' statement, names in the expression are resolved
+/// from the current class' constants first, if any, and then the top-level constants.
+fn parse_assignment(statement: &str, constants: &Constants, class: Option<&[Constant]>) -> Option<Constant> {
+
+    let (name, expr) = statement.split_once('=')?;
+    let name = name.trim();
+    let expr = expr.trim();
+
+    // Excludes comparisons and augmented assignments, and annotated or multiple
+    // targets, which are not valid identifiers.
+    if expr.starts_with('=') || !is_ident(name) {
+        return None;
+    }
+
+    let tokens = tokenize(expr)?;
+    let mut parser = ExprParser {
+        tokens: &tokens,
+        pos: 0,
+        resolve: &|name: &str| {
+            class.and_then(|values| find_constant(values, name))
+                .or_else(|| constants.resolve(name))
+                .cloned()
+        },
+    };
+
+    let value = parser.parse_expr(0)?;
+    if parser.pos != tokens.len() {
+        return None;
+    }
+
+    Some(Constant { name: name.to_string(), value })
+
+}
+
+#[inline]
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_ident(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && s.chars().all(is_ident_char)
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Value(ConstantValue),
+    /// A name, possibly dotted.
+    Name(String),
+    /// An operator or a bracket.
+    Op(&'static str),
+}
+
+/// Internal function to split an expression into tokens, returning none if any token
+/// is not supported.
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+
+    const OPS: [&str; 11] = ["<<", ">>", "|", "&", "^", "+", "-", "*", "~", "(", ")"];
+
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+
+    while !rest.is_empty() {
+
+        if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(*op));
+            rest = &rest[op.len()..];
+        } else if rest.starts_with(['"', '\'']) {
+            let (s, len) = parse_string(rest)?;
+            tokens.push(Token::Value(ConstantValue::Str(s)));
+            rest = &rest[len..];
+        } else if rest.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            let len = rest.find(|c: char| !(is_ident_char(c) || c == '.')).unwrap_or(rest.len());
+            tokens.push(Token::Value(parse_number(&rest[..len])?));
+            rest = &rest[len..];
+        } else if rest.starts_with(is_ident_char) {
+            let len = rest.find(|c: char| !(is_ident_char(c) || c == '.')).unwrap_or(rest.len());
+            tokens.push(match &rest[..len] {
+                "True" => Token::Value(ConstantValue::Bool(true)),
+                "False" => Token::Value(ConstantValue::Bool(false)),
+                name => Token::Name(name.to_string()),
+            });
+            rest = &rest[len..];
+        } else {
+            return None;
+        }
+
+        rest = rest.trim_start();
+
+    }
+
+    Some(tokens)
+
+}
+
+/// Parse a number literal, integers may have a base prefix and underscores.
+fn parse_number(literal: &str) -> Option<ConstantValue> {
+
+    let digits = literal.replace('_', "");
+    let lower = digits.to_ascii_lowercase();
+    // Python 2 long suffix.
+    let lower = lower.strip_suffix('l').unwrap_or(&lower);
+
+    let int = if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(oct) = lower.strip_prefix("0o") {
+        i64::from_str_radix(oct, 8).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        lower.parse::<i64>().ok()
+    };
+
+    match int {
+        Some(n) => Some(ConstantValue::Int(n)),
+        None => lower.parse::<f64>().ok().map(ConstantValue::Float),
+    }
+
+}
+
+/// Parse a single-line string literal at the start of the given text, returning the
+/// string and the length of the literal. Prefixes are not supported.
+fn parse_string(text: &str) -> Option<(String, usize)> {
+
+    let quote = text.chars().next()?;
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '\\' | '\'' | '"' => escaped,
+                    _ => return None,
+                });
+            }
+            c if c == quote => return Some((value, index + 1)),
+            c => value.push(c),
+        }
+    }
+
+    None
+
+}
+
+/// Internal precedence-climbing parser of expressions, only integers support operators.
+struct ExprParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    resolve: &'a dyn Fn(&str) -> Option<ConstantValue>,
+}
+
+impl ExprParser<'_> {
+
+    /// Binary operators with their precedence, as in Python.
+    const BINARY_OPS: [(&'static str, u8); 8] = [
+        ("|", 1), ("^", 2), ("&", 3), ("<<", 4), (">>", 4), ("+", 5), ("-", 5), ("*", 6),
+    ];
+
+    fn parse_expr(&mut self, min_prec: u8) -> Option<ConstantValue> {
+
+        let tokens = self.tokens;
+        let mut lhs = self.parse_unary()?;
+
+        while let Some(Token::Op(op)) = tokens.get(self.pos) {
+
+            let Some(&(_, prec)) = Self::BINARY_OPS.iter().find(|(o, _)| o == op) else {
+                break;
+            };
+
+            if prec <= min_prec {
+                break;
+            }
+
+            self.pos += 1;
+            let rhs = self.parse_expr(prec)?;
+
+            let (ConstantValue::Int(a), ConstantValue::Int(b)) = (lhs, rhs) else {
+                return None;
+            };
+
+            lhs = ConstantValue::Int(match *op {
+                "|" => a | b,
+                "^" => a ^ b,
+                "&" => a & b,
+                "<<" => a.checked_shl(u32::try_from(b).ok()?)?,
+                ">>" => a.checked_shr(u32::try_from(b).ok()?)?,
+                "+" => a.checked_add(b)?,
+                "-" => a.checked_sub(b)?,
+                "*" => a.checked_mul(b)?,
+                _ => unreachable!(),
+            });
+
+        }
+
+        Some(lhs)
+
+    }
+
+    fn parse_unary(&mut self) -> Option<ConstantValue> {
+
+        let tokens = self.tokens;
+        let token = tokens.get(self.pos)?;
+        self.pos += 1;
+
+        match token {
+            Token::Value(value) => Some(value.clone()),
+            Token::Name(name) => (self.resolve)(name),
+            Token::Op("(") => {
+                let value = self.parse_expr(0)?;
+                match tokens.get(self.pos) {
+                    Some(Token::Op(")")) => {
+                        self.pos += 1;
+                        Some(value)
+                    }
+                    _ => None,
+                }
+            }
+            Token::Op("-") => match self.parse_unary()? {
+                ConstantValue::Int(n) => Some(ConstantValue::Int(n.checked_neg()?)),
+                ConstantValue::Float(n) => Some(ConstantValue::Float(-n)),
+                _ => None,
+            },
+            Token::Op("~") => match self.parse_unary()? {
+                ConstantValue::Int(n) => Some(ConstantValue::Int(!n)),
+                _ => None,
+            },
+            _ => None,
+        }
+
+    }
+
+}
@@ -0,0 +1,259 @@
+//! Comparison of the models of two resource trees, typically two game versions.
+//!
+//! Each added, removed or changed item is printed on its own line, prefixed by '+',
+//! '-' or '~' respectively, like the packed XML diff, and with a path of the item such
+//! as 'entity/Avatar/base_method/doCmdInt'.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use wgtk::res::{OverlayFilesystem, ResFilesystem};
+
+use crate::defs::model::{Entity, Interface, Method, Model, Property, Ty, TyKind};
+use crate::defs;
+use crate::{BootstrapDiffArgs, CliResult};
+
+use super::InterfaceMethods;
+
+
+/// Entrypoint of the diff subcommand.
+pub fn cmd_bootstrap_diff(args: BootstrapDiffArgs) -> CliResult<()> {
+
+    let old_model = load_model(&args.old_dir)?;
+    let new_model = load_model(&args.new_dir)?;
+
+    println!("== Comparing models...");
+    diff_types(&old_model, &new_model);
+    diff_interfaces("interface", &old_model.interfaces, &new_model.interfaces);
+    diff_interfaces("component", &old_model.components, &new_model.components);
+    diff_entities(&old_model, &new_model);
+
+    Ok(())
+
+}
+
+/// Load the model of the given resource directory, without any overlay.
+fn load_model(dir: &Path) -> CliResult<Model> {
+
+    let mut fs = OverlayFilesystem::new();
+    fs.push_fs(ResFilesystem::new_parallel(dir)
+        .map_err(|e| format!("Failed to open resource filesystem at {dir:?}, reason: {e}"))?);
+
+    defs::load(fs)
+        .map_err(|e| format!("Failed to load model at {dir:?}, reason: {e}"))
+
+}
+
+/// Compare all named types, anonymous types are compared where they are used.
+fn diff_types(old: &Model, new: &Model) {
+
+    let is_named = |ty: &&Ty| !ty.name().starts_with("ANON");
+    let old_tys = old.tys.iter().filter(is_named).collect::<Vec<_>>();
+    let new_tys = new.tys.iter().filter(is_named).collect::<Vec<_>>();
+
+    diff_named(&old_tys, &new_tys, |ty| ty.name(), |name, old_ty, new_ty| {
+        let path = format!("type/{name}");
+        match (old_ty, new_ty) {
+            (Some(old_ty), Some(new_ty)) => diff_value(&path, &ty_def(old_ty), &ty_def(new_ty)),
+            (Some(old_ty), None) => println!("- {path}: {}", ty_def(old_ty)),
+            (None, Some(new_ty)) => println!("+ {path}: {}", ty_def(new_ty)),
+            (None, None) => {}
+        }
+    });
+
+}
+
+/// Compare all interfaces or components, depending on the given kind.
+fn diff_interfaces(kind: &str, old: &[Interface], new: &[Interface]) {
+    diff_named(old, new, |interface| &interface.name, |name, old_interface, new_interface| {
+        let path = format!("{kind}/{name}");
+        match (old_interface, new_interface) {
+            (Some(old_interface), Some(new_interface)) => diff_interface(&path, old_interface, new_interface),
+            (Some(_), None) => println!("- {path}"),
+            (None, Some(_)) => println!("+ {path}"),
+            (None, None) => {}
+        }
+    });
+}
+
+/// Compare all entities, with their type ids and exposed ids of their methods.
+fn diff_entities(old: &Model, new: &Model) {
+    diff_named(&old.entities, &new.entities, |entity| &entity.interface.name, |name, old_entity, new_entity| {
+        let path = format!("entity/{name}");
+        match (old_entity, new_entity) {
+            (Some(old_entity), Some(new_entity)) => {
+                diff_value(&format!("{path}/id"), &format!("0x{:02X}", old_entity.id), &format!("0x{:02X}", new_entity.id));
+                diff_value(&format!("{path}/components"), &entity_components(old_entity), &entity_components(new_entity));
+                diff_interface(&path, &old_entity.interface, &new_entity.interface);
+                let apps: [(&str, InterfaceMethods); 3] = [
+                    ("client", |i| &i.client_methods),
+                    ("base", |i| &i.base_methods),
+                    ("cell", |i| &i.cell_methods),
+                ];
+                for (app, interface_methods) in apps {
+                    diff_exposed_ids(&path, app, old, old_entity, new, new_entity, interface_methods);
+                }
+            }
+            (Some(old_entity), None) => println!("- {path}: 0x{:02X}", old_entity.id),
+            (None, Some(new_entity)) => println!("+ {path}: 0x{:02X}", new_entity.id),
+            (None, None) => {}
+        }
+    });
+}
+
+/// Compare the implemented interfaces, properties and methods of an interface.
+fn diff_interface(path: &str, old: &Interface, new: &Interface) {
+
+    diff_value(&format!("{path}/implements"), &old.implements.join(", "), &new.implements.join(", "));
+
+    diff_named(&old.properties, &new.properties, |property| &property.name, |name, old_property, new_property| {
+        let path = format!("{path}/property/{name}");
+        match (old_property, new_property) {
+            (Some(old_property), Some(new_property)) => diff_value(&path, &property_def(old_property), &property_def(new_property)),
+            (Some(old_property), None) => println!("- {path}: {}", property_def(old_property)),
+            (None, Some(new_property)) => println!("+ {path}: {}", property_def(new_property)),
+            (None, None) => {}
+        }
+    });
+
+    diff_methods(path, "client_method", &old.client_methods, &new.client_methods);
+    diff_methods(path, "base_method", &old.base_methods, &new.base_methods);
+    diff_methods(path, "cell_method", &old.cell_methods, &new.cell_methods);
+
+}
+
+/// Compare the methods of an interface for one app.
+fn diff_methods(path: &str, kind: &str, old: &[Method], new: &[Method]) {
+    diff_named(old, new, |method| &method.name, |name, old_method, new_method| {
+        let path = format!("{path}/{kind}/{name}");
+        match (old_method, new_method) {
+            (Some(old_method), Some(new_method)) => diff_value(&path, &method_def(old_method), &method_def(new_method)),
+            (Some(old_method), None) => println!("- {path}: {}", method_def(old_method)),
+            (None, Some(new_method)) => println!("+ {path}: {}", method_def(new_method)),
+            (None, None) => {}
+        }
+    });
+}
+
+/// Compare the exposed ids of methods present in both versions of an entity, for one
+/// app, added and removed methods are already reported by the methods' comparison.
+fn diff_exposed_ids(
+    path: &str,
+    app: &str,
+    old_model: &Model,
+    old_entity: &Entity,
+    new_model: &Model,
+    new_entity: &Entity,
+    interface_methods: InterfaceMethods,
+) {
+
+    let old_ids = defs::exposed_methods(old_model, old_entity, interface_methods)
+        .into_iter()
+        .enumerate()
+        .map(|(exposed_id, exposed)| ((exposed.interface.name.as_str(), exposed.method.name.as_str()), exposed_id))
+        .collect::<HashMap<_, _>>();
+
+    let new_methods = defs::exposed_methods(new_model, new_entity, interface_methods);
+    for (new_id, exposed) in new_methods.iter().enumerate() {
+        let key = (exposed.interface.name.as_str(), exposed.method.name.as_str());
+        if let Some(&old_id) = old_ids.get(&key) {
+            diff_value(&format!("{path}/{app}_exposed_id/{}.{}", key.0, key.1), &old_id.to_string(), &new_id.to_string());
+        }
+    }
+
+}
+
+/// Call the given function for each name of the old and new items, in order of the old
+/// items first, with the old and new items of that name, if present.
+fn diff_named<'a, T>(
+    old: &'a [T],
+    new: &'a [T],
+    name: impl Fn(&'a T) -> &'a str,
+    mut func: impl FnMut(&'a str, Option<&'a T>, Option<&'a T>),
+) {
+
+    let old_items = old.iter().map(|item| (name(item), item)).collect::<HashMap<_, _>>();
+    let new_items = new.iter().map(|item| (name(item), item)).collect::<HashMap<_, _>>();
+
+    for item in old {
+        let item_name = name(item);
+        func(item_name, Some(item), new_items.get(item_name).copied());
+    }
+
+    for item in new {
+        let item_name = name(item);
+        if !old_items.contains_key(item_name) {
+            func(item_name, None, Some(item));
+        }
+    }
+
+}
+
+/// Print the change of a value if it's different.
+fn diff_value(path: &str, old: &str, new: &str) {
+    if old != new {
+        println!("~ {path}: {old} -> {new}");
+    }
+}
+
+/// Return the definition of the given type, named types used by it are only referenced.
+fn ty_def(ty: &Ty) -> String {
+    match ty.kind() {
+        TyKind::Alias(alias) => ty_ref(alias),
+        TyKind::Dict(dict) => {
+            let properties = dict.properties.iter()
+                .map(|prop| format!("{}: {}", prop.name, ty_ref(&prop.ty)))
+                .collect::<Vec<_>>();
+            let allow_none = if dict.allow_none { " AllowNone" } else { "" };
+            format!("FIXED_DICT{{{}}}{allow_none}", properties.join(", "))
+        }
+        TyKind::Array(seq) |
+        TyKind::Tuple(seq) => {
+            let kind = if matches!(ty.kind(), TyKind::Array(_)) { "ARRAY" } else { "TUPLE" };
+            match seq.size {
+                Some(size) => format!("{kind}<{}>[{size}]", ty_ref(&seq.ty)),
+                None => format!("{kind}<{}>", ty_ref(&seq.ty)),
+            }
+        }
+        TyKind::User(implemented_by) => format!("USER_TYPE({implemented_by})"),
+        _ => ty.name().to_string(),
+    }
+}
+
+/// Return a reference to the given type, anonymous types are defined inline.
+fn ty_ref(ty: &Ty) -> String {
+    if ty.name().starts_with("ANON") {
+        ty_def(ty)
+    } else {
+        ty.name().to_string()
+    }
+}
+
+fn property_def(property: &Property) -> String {
+    format!("{} ({:?})", ty_ref(&property.ty), property.flags)
+}
+
+fn method_def(method: &Method) -> String {
+
+    let args = method.args.iter()
+        .map(|arg| ty_ref(&arg.ty))
+        .collect::<Vec<_>>();
+
+    let exposed = if method.exposed_to_all_clients {
+        " exposed to all clients"
+    } else if method.exposed_to_own_client {
+        " exposed to own client"
+    } else {
+        ""
+    };
+
+    format!("({}){exposed}", args.join(", "))
+
+}
+
+fn entity_components(entity: &Entity) -> String {
+    entity.components.iter()
+        .map(|component| format!("{}: {}", component.name, component.ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
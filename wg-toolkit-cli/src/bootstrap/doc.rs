@@ -0,0 +1,289 @@
+//! Generation of a Markdown documentation of the model, with all entities, interfaces
+//! and types cross-linked.
+
+use std::io::{self, Write, BufWriter};
+use std::fs::{self, File};
+use std::path::Path;
+
+use crate::defs::model::{Entity, Interface, Method, Model, Property, PropertyFlags, Ty, TyKind};
+use crate::defs::{self, StreamSize};
+
+use super::InterfaceMethods;
+
+
+/// Generate the documentation of the model in a single 'model.md' file of the given
+/// directory.
+pub fn generate(dest_dir: &Path, model: &Model) -> io::Result<()> {
+
+    fs::create_dir_all(dest_dir)?;
+
+    println!("== Writing documentation...");
+    let doc_file = dest_dir.join("model.md");
+    let mut writer = BufWriter::new(File::create(&doc_file)?);
+
+    writeln!(writer, "# Entity definitions")?;
+    writeln!(writer)?;
+    writeln!(writer, "This file is generated by bootstrap command of the CLI.")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "## Entities")?;
+    writeln!(writer)?;
+    writeln!(writer, "| Type id | Entity |")?;
+    writeln!(writer, "| ------- | ------ |")?;
+    for entity in &model.entities {
+        writeln!(writer, "| 0x{:02X} | {} |", entity.id, entity_link(&entity.interface.name))?;
+    }
+    writeln!(writer)?;
+
+    for entity in &model.entities {
+        generate_entity(&mut writer, model, entity)?;
+    }
+
+    writeln!(writer, "## Interfaces")?;
+    writeln!(writer)?;
+    for interface in &model.interfaces {
+        writeln!(writer, "- {}", interface_link(&interface.name))?;
+    }
+    writeln!(writer)?;
+
+    for interface in &model.interfaces {
+        writeln!(writer, "<a id=\"interface-{}\"></a>", interface.name)?;
+        writeln!(writer, "### Interface {}", interface.name)?;
+        writeln!(writer)?;
+        generate_interface(&mut writer, interface)?;
+    }
+
+    if !model.components.is_empty() {
+
+        writeln!(writer, "## Components")?;
+        writeln!(writer)?;
+        for component in &model.components {
+            writeln!(writer, "- {}", component_link(&component.name))?;
+        }
+        writeln!(writer)?;
+
+        for component in &model.components {
+            writeln!(writer, "<a id=\"component-{}\"></a>", component.name)?;
+            writeln!(writer, "### Component {}", component.name)?;
+            writeln!(writer)?;
+            generate_interface(&mut writer, component)?;
+        }
+
+    }
+
+    generate_types(&mut writer, model)?;
+
+    writer.flush()
+
+}
+
+fn generate_entity(mut writer: impl Write, model: &Model, entity: &Entity) -> io::Result<()> {
+
+    writeln!(writer, "<a id=\"entity-{}\"></a>", entity.interface.name)?;
+    writeln!(writer, "### Entity {}", entity.interface.name)?;
+    writeln!(writer)?;
+    writeln!(writer, "Type id: 0x{:02X}", entity.id)?;
+    writeln!(writer)?;
+
+    generate_interface(&mut writer, &entity.interface)?;
+
+    if !entity.components.is_empty() {
+        writeln!(writer, "| Component | Type |")?;
+        writeln!(writer, "| --------- | ---- |")?;
+        for component in &entity.components {
+            writeln!(writer, "| {} | {} |", component.name, component_link(&component.ty))?;
+        }
+        writeln!(writer)?;
+    }
+
+    // Exposed ids are computed over the entity, its interfaces and components.
+    let apps: [(&str, InterfaceMethods); 3] = [
+        ("client", |i| &i.client_methods),
+        ("base", |i| &i.base_methods),
+        ("cell", |i| &i.cell_methods),
+    ];
+
+    for (app_name, interface_methods) in apps {
+
+        let methods = defs::exposed_methods(model, entity, interface_methods);
+        if methods.is_empty() {
+            continue;
+        }
+
+        writeln!(writer, "Exposed methods on {app_name}:")?;
+        writeln!(writer)?;
+        writeln!(writer, "| Exposed id | Method | Arguments | Stream size |")?;
+        writeln!(writer, "| ---------- | ------ | --------- | ----------- |")?;
+
+        for (exposed_id, method) in methods.iter().enumerate() {
+
+            let stream_size = match method.stream_size {
+                StreamSize::Fixed(len) => format!("{len}"),
+                StreamSize::Variable(header_size) => format!("{header_size:?}"),
+            };
+
+            // Methods of components are defined in the component type.
+            let interface_name = &method.interface.name;
+            let interface_ref = if model.components.iter().any(|c| &c.name == interface_name) {
+                component_link(interface_name)
+            } else {
+                interface_link(interface_name)
+            };
+
+            writeln!(writer, "| 0x{exposed_id:02X} | {}::{} | {} | {stream_size} |",
+                interface_ref,
+                method.method.name,
+                method_args(method.method))?;
+
+        }
+
+        writeln!(writer)?;
+
+    }
+
+    Ok(())
+
+}
+
+fn generate_interface(mut writer: impl Write, interface: &Interface) -> io::Result<()> {
+
+    if !interface.implements.is_empty() {
+        let implements = interface.implements.iter()
+            .map(|name| interface_link(name))
+            .collect::<Vec<_>>();
+        writeln!(writer, "Implements: {}", implements.join(", "))?;
+        writeln!(writer)?;
+    }
+
+    if !interface.properties.is_empty() {
+        writeln!(writer, "| Property | Type | Flags |")?;
+        writeln!(writer, "| -------- | ---- | ----- |")?;
+        for property in &interface.properties {
+            generate_property(&mut writer, property)?;
+        }
+        writeln!(writer)?;
+    }
+
+    let apps: [(&str, &[Method]); 3] = [
+        ("client", &interface.client_methods),
+        ("base", &interface.base_methods),
+        ("cell", &interface.cell_methods),
+    ];
+
+    for (app_name, methods) in apps {
+
+        if methods.is_empty() {
+            continue;
+        }
+
+        writeln!(writer, "Methods on {app_name}:")?;
+        writeln!(writer)?;
+        writeln!(writer, "| Method | Arguments | Exposed |")?;
+        writeln!(writer, "| ------ | --------- | ------- |")?;
+
+        for method in methods {
+            let exposed = match (method.exposed_to_all_clients, method.exposed_to_own_client) {
+                (true, _) => "all clients",
+                (false, true) => "own client",
+                (false, false) => "",
+            };
+            writeln!(writer, "| {} | {} | {exposed} |", method.name, method_args(method))?;
+        }
+
+        writeln!(writer)?;
+
+    }
+
+    Ok(())
+
+}
+
+fn generate_property(mut writer: impl Write, property: &Property) -> io::Result<()> {
+
+    let flags = match property.flags {
+        PropertyFlags::None => "",
+        PropertyFlags::Base => "BASE",
+        PropertyFlags::BaseAndClient => "BASE_AND_CLIENT",
+        PropertyFlags::OwnClient => "OWN_CLIENT",
+        PropertyFlags::CellPrivate => "CELL_PRIVATE",
+        PropertyFlags::CellPublic => "CELL_PUBLIC",
+        PropertyFlags::AllClients => "ALL_CLIENTS",
+    };
+
+    writeln!(writer, "| {} | {} | {flags} |", property.name, type_ref(&property.ty))
+
+}
+
+fn generate_types(mut writer: impl Write, model: &Model) -> io::Result<()> {
+
+    writeln!(writer, "## Types")?;
+    writeln!(writer)?;
+
+    for ty in model.tys.iter() {
+        match ty.kind() {
+            TyKind::Alias(alias_ty) => {
+                writeln!(writer, "<a id=\"type-{}\"></a>", ty.name())?;
+                writeln!(writer, "### Type {}", ty.name())?;
+                writeln!(writer)?;
+                writeln!(writer, "Alias of {}", type_ref(alias_ty))?;
+                writeln!(writer)?;
+            }
+            TyKind::Dict(ty_dict) => {
+                writeln!(writer, "<a id=\"type-{}\"></a>", ty.name())?;
+                writeln!(writer, "### Type {}", ty.name())?;
+                writeln!(writer)?;
+                if ty_dict.allow_none {
+                    writeln!(writer, "May be none.")?;
+                    writeln!(writer)?;
+                }
+                writeln!(writer, "| Field | Type |")?;
+                writeln!(writer, "| ----- | ---- |")?;
+                for prop in &ty_dict.properties {
+                    writeln!(writer, "| {} | {} |", prop.name, type_ref(&prop.ty))?;
+                }
+                writeln!(writer)?;
+            }
+            _ => {}  // Builtins and sequences are not documented.
+        }
+    }
+
+    Ok(())
+
+}
+
+/// Return the list of arguments' types of the given method, comma-separated.
+fn method_args(method: &Method) -> String {
+    method.args.iter()
+        .map(|arg| type_ref(&arg.ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Return a reference to the given type, linked if it is documented.
+fn type_ref(ty: &Ty) -> String {
+    match ty.kind() {
+        TyKind::Alias(_) |
+        TyKind::Dict(_) => format!("[{0}](#type-{0})", ty.name()),
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) => {
+            let kind = if matches!(ty.kind(), TyKind::Array(_)) { "ARRAY" } else { "TUPLE" };
+            match ty_seq.size {
+                Some(size) => format!("{kind}\\<{}\\>[{size}]", type_ref(&ty_seq.ty)),
+                None => format!("{kind}\\<{}\\>", type_ref(&ty_seq.ty)),
+            }
+        }
+        _ => ty.name().to_string(),
+    }
+}
+
+fn entity_link(name: &str) -> String {
+    format!("[{name}](#entity-{name})")
+}
+
+fn interface_link(name: &str) -> String {
+    format!("[{name}](#interface-{name})")
+}
+
+fn component_link(name: &str) -> String {
+    format!("[{name}](#component-{name})")
+}
@@ -0,0 +1,169 @@
+//! Export of the parsed model as JSON, for tools that need the entity definitions
+//! without parsing the generated code. Types are defined once, with anonymous ones,
+//! and referenced by their name everywhere else.
+
+use std::io::{self, Write, BufWriter};
+use std::fs::File;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::defs::model::{Entity, Interface, Method, Model, Property, PropertyFlags, Ty, TyKind, VariableHeaderSize};
+use crate::defs::{self, StreamSize};
+
+use super::InterfaceMethods;
+
+
+/// Apps with the methods of an interface for each of them.
+const APPS: [(&str, InterfaceMethods); 3] = [
+    ("client", |i| &i.client_methods),
+    ("base", |i| &i.base_methods),
+    ("cell", |i| &i.cell_methods),
+];
+
+
+/// Write the whole model to the given JSON file, with the exposed ids and stream sizes
+/// of each entity's methods.
+pub fn emit_model(path: &Path, model: &Model) -> io::Result<()> {
+
+    println!("== Writing model...");
+
+    let value = json!({
+        "types": model.tys.iter().map(json_ty).collect::<Vec<_>>(),
+        "interfaces": model.interfaces.iter().map(json_interface).collect::<Vec<_>>(),
+        "components": model.components.iter().map(json_interface).collect::<Vec<_>>(),
+        "entities": model.entities.iter().map(|entity| json_entity(model, entity)).collect::<Vec<_>>(),
+    });
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &value)?;
+    writeln!(writer)?;
+    writer.flush()
+
+}
+
+fn json_ty(ty: &Ty) -> Value {
+    match ty.kind() {
+        TyKind::Alias(alias_ty) => json!({
+            "name": ty.name(),
+            "kind": "ALIAS",
+            "type": alias_ty.name(),
+        }),
+        TyKind::Dict(ty_dict) => json!({
+            "name": ty.name(),
+            "kind": "FIXED_DICT",
+            "allow_none": ty_dict.allow_none,
+            "properties": ty_dict.properties.iter()
+                .map(|prop| json!({ "name": prop.name, "type": prop.ty.name() }))
+                .collect::<Vec<_>>(),
+        }),
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) => json!({
+            "name": ty.name(),
+            "kind": if matches!(ty.kind(), TyKind::Array(_)) { "ARRAY" } else { "TUPLE" },
+            "type": ty_seq.ty.name(),
+            "size": ty_seq.size,
+        }),
+        TyKind::User(implemented_by) => json!({
+            "name": ty.name(),
+            "kind": "USER_TYPE",
+            "implemented_by": implemented_by,
+        }),
+        _ => json!({
+            "name": ty.name(),
+            "kind": ty.name(),
+        }),
+    }
+}
+
+fn json_interface(interface: &Interface) -> Value {
+    json!({
+        "name": interface.name,
+        "implements": interface.implements,
+        "properties": interface.properties.iter().map(json_property).collect::<Vec<_>>(),
+        "temp_properties": interface.temp_properties,
+        "client_methods": interface.client_methods.iter().map(json_method).collect::<Vec<_>>(),
+        "base_methods": interface.base_methods.iter().map(json_method).collect::<Vec<_>>(),
+        "cell_methods": interface.cell_methods.iter().map(json_method).collect::<Vec<_>>(),
+    })
+}
+
+fn json_property(property: &Property) -> Value {
+
+    let flags = match property.flags {
+        PropertyFlags::None => None,
+        PropertyFlags::Base => Some("BASE"),
+        PropertyFlags::BaseAndClient => Some("BASE_AND_CLIENT"),
+        PropertyFlags::OwnClient => Some("OWN_CLIENT"),
+        PropertyFlags::CellPrivate => Some("CELL_PRIVATE"),
+        PropertyFlags::CellPublic => Some("CELL_PUBLIC"),
+        PropertyFlags::AllClients => Some("ALL_CLIENTS"),
+    };
+
+    json!({
+        "name": property.name,
+        "type": property.ty.name(),
+        "flags": flags,
+        "persistent": property.persistent,
+        "identifier": property.identifier,
+        "indexed": property.indexed,
+        "database_len": property.database_len,
+        "default": property.default,
+    })
+
+}
+
+fn json_method(method: &Method) -> Value {
+    json!({
+        "name": method.name,
+        "exposed_to_all_clients": method.exposed_to_all_clients,
+        "exposed_to_own_client": method.exposed_to_own_client,
+        "args": method.args.iter().map(|arg| arg.ty.name()).collect::<Vec<_>>(),
+        "stream_size": json_stream_size(defs::compute_method_stream_size(method)),
+    })
+}
+
+fn json_entity(model: &Model, entity: &Entity) -> Value {
+
+    let mut exposed = serde_json::Map::new();
+    for (app_name, interface_methods) in APPS {
+        let methods = defs::exposed_methods(model, entity, interface_methods)
+            .iter()
+            .enumerate()
+            .map(|(exposed_id, method)| json!({
+                "exposed_id": exposed_id,
+                "interface": method.interface.name,
+                "method": method.method.name,
+                "stream_size": json_stream_size(method.stream_size),
+            }))
+            .collect::<Vec<_>>();
+        exposed.insert(app_name.to_string(), Value::Array(methods));
+    }
+
+    json!({
+        "id": entity.id,
+        "parent": entity.parent,
+        "components": entity.components.iter()
+            .map(|component| json!({ "name": component.name, "type": component.ty }))
+            .collect::<Vec<_>>(),
+        "interface": json_interface(&entity.interface),
+        "exposed_methods": exposed,
+    })
+
+}
+
+/// Return the stream size, a fixed size in bytes or the size of the variable header.
+fn json_stream_size(stream_size: StreamSize) -> Value {
+    match stream_size {
+        StreamSize::Fixed(size) => json!({ "fixed": size }),
+        StreamSize::Variable(header_size) => {
+            let header_size = match header_size {
+                VariableHeaderSize::Variable8 => 1,
+                VariableHeaderSize::Variable16 => 2,
+                VariableHeaderSize::Variable24 => 3,
+                VariableHeaderSize::Variable32 => 4,
+            };
+            json!({ "variable": header_size })
+        }
+    }
+}
@@ -1,19 +1,26 @@
-use std::io::{self, Write, BufWriter};
-use std::collections::HashSet;
-use std::fs::{self, File};
-use std::cmp::Ordering;
+mod doc;
+mod schema;
+mod constants;
+mod patch;
+mod diff;
+mod export;
+mod output;
+
+use std::io::{self, Read, Write};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::borrow::Cow;
 use std::path::Path;
 
-use wgtk::res::ResFilesystem;
-use wgtk::pxml;
+use wgtk::res::{OverlayFilesystem, ResFilesystem};
 
-use crate::{BootstrapArgs, CliResult};
+use crate::defs::model::{Entity, Interface, Method, Model, Property, PropertyFlags, Ty, TyKind, VariableHeaderSize};
+use crate::defs::{self, StreamSize};
+use crate::{BootstrapArgs, BootstrapCommand, BootstrapFormat, CliResult};
 
-mod parse;
-mod model;
-
-use model::{Entity, Interface, Method, Model, PropertyFlags, Ty, TyKind, VariableHeaderSize};
+use self::constants::Constants;
+use self::patch::PatchFile;
+use self::output::Output;
 
 // NOTE: For the future, if python bytecode interpretation is needed to automatically
 // generate enumeration or try to gather function arguments' names, see:
@@ -23,78 +30,106 @@ use model::{Entity, Interface, Method, Model, PropertyFlags, Ty, TyKind, Variabl
 /// Entrypoint.
 pub fn cmd_bootstrap(args: BootstrapArgs) -> CliResult<()> {
 
-    let fs = ResFilesystem::new(args.dir)
-        .map_err(|e| format!("Failed to open resource filesystem, reason: {e}"))?;
-        
-    let model = load(fs)
-        .map_err(|e| format!("Failed to load model, reason: {e}"))?;
-    
-    let mut state = State::new();
-    generate(&args.dest, &model, &mut state)
-        .map_err(|e| format!("Failed to generate model, reason: {e}"))?;
-
-    Ok(())
-
-}
-
-fn load(fs: ResFilesystem) -> io::Result<Model> {
-
-    let mut model = Model::default();
-
-    println!("== Reading aliases...");
-    let alias_reader = fs.read("scripts/entity_defs/alias.xml")?;
-    let alias_elt = pxml::from_reader(alias_reader).unwrap();
-    parse::parse_aliases(&alias_elt, &mut model.tys);
-
-    println!("== Reading interfaces...");
-    for interface_file in fs.read_dir("scripts/entity_defs/interfaces")? {
-        
-        let interface_file = interface_file?;
-        let Some((interface_name, "")) = interface_file.name().split_once(".def") else {
-            continue;
-        };
+    match args.cmd {
+        Some(BootstrapCommand::Diff(args)) => return diff::cmd_bootstrap_diff(args),
+        None => {}
+    }
 
-        println!(" = {interface_name}");
+    // Both are required by clap when there is no subcommand.
+    let dir = args.dir.unwrap();
+    let dest = args.dest.unwrap();
 
-        let interface_reader = fs.read(interface_file.path())?;
-        let interface_elt = pxml::from_reader(interface_reader).unwrap();
-        let interface = parse::parse_interface(&interface_elt, &mut model.tys, interface_name.to_string());
-        model.interfaces.push(interface);
+    let patches = match &args.patches {
+        Some(patches_file) => {
+            let content = fs::read_to_string(patches_file)
+                .map_err(|e| format!("Failed to read patches file {patches_file:?}, reason: {e}"))?;
+            PatchFile::parse(&content)
+                .map_err(|e| format!("Failed to parse patches file {patches_file:?}, reason: {e}"))?
+        }
+        None => PatchFile::default(),
+    };
 
+    // Overlay directories have precedence over the game's resources.
+    let mut fs = OverlayFilesystem::new();
+    for overlay_dir in args.overlay {
+        fs.push_dir(overlay_dir);
     }
 
-    println!("== Reading entities...");
-    let entities_reader = fs.read("scripts/entities.xml")?;
-    let entities_elt = pxml::from_reader(entities_reader).unwrap();
-    let entities_elt = entities_elt.get_child("ClientServerEntities").unwrap().as_element().unwrap();
-    for (index, (entity_name, _)) in entities_elt.iter_children_all().enumerate() {
+    // All entity definitions are read, so all packages are indexed upfront.
+    fs.push_fs(ResFilesystem::new_parallel(dir)
+        .map_err(|e| format!("Failed to open resource filesystem, reason: {e}"))?);
+
+    // The game only ships compiled scripts, so constants are only available if the
+    // decompiled scripts are overlaid.
+    let constants = match fs.read("scripts/common/constants.py") {
+        Ok(mut reader) => {
+            let mut source = String::new();
+            reader.read_to_string(&mut source)
+                .map_err(|e| format!("Failed to read constants, reason: {e}"))?;
+            Constants::parse(&source)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Constants::default(),
+        Err(e) => return Err(format!("Failed to open constants, reason: {e}")),
+    };
         
-        println!(" = {entity_name}");
-        let entity_reader = fs.read(format!("scripts/entity_defs/{entity_name}.def"))?;
-        let entity_elt = pxml::from_reader(entity_reader).unwrap();
-        let entity = parse::parse_entity(&entity_elt, &mut model.tys, index + 1, entity_name.to_string());
-        model.entities.push(entity);
+    let model = defs::load(fs)
+        .map_err(|e| format!("Failed to load model, reason: {e}"))?;
 
+    if let Some(model_file) = &args.emit_model {
+        export::emit_model(model_file, &model)
+            .map_err(|e| format!("Failed to write model to {model_file:?}, reason: {e}"))?;
+    }
+    
+    match args.format {
+        BootstrapFormat::Rust => {
+            let handlers = if args.async_handlers {
+                Some(Handlers::Async)
+            } else if args.handlers {
+                Some(Handlers::Sync)
+            } else {
+                None
+            };
+            let mut state = State::new(!args.no_dedup_args, handlers, args.serde, constants.group_names(), patches, Output::new(args.check));
+            generate(&dest, &model, &constants, &mut state)
+                .map_err(|e| format!("Failed to generate model, reason: {e}"))?;
+
+            let changed = state.output.changed();
+            if args.check && !changed.is_empty() {
+                for path in changed {
+                    println!("~ {}", path.display());
+                }
+                return Err(format!("Generated code differs from {dest:?} in {} files", changed.len()));
+            }
+        }
+        BootstrapFormat::Markdown => {
+            doc::generate(&dest, &model)
+                .map_err(|e| format!("Failed to generate documentation, reason: {e}"))?;
+        }
+        BootstrapFormat::JsonSchema => {
+            schema::generate_json_schema(&dest, &model)
+                .map_err(|e| format!("Failed to generate JSON Schema, reason: {e}"))?;
+        }
+        BootstrapFormat::TypeScript => {
+            schema::generate_typescript(&dest, &model)
+                .map_err(|e| format!("Failed to generate TypeScript declarations, reason: {e}"))?;
+        }
     }
 
-    println!("== Types: {}", model.tys.count());
-
-    Ok(model)
+    Ok(())
 
 }
 
-fn generate(dest_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
-    generate_mod(dest_dir, model, state)
+fn generate(dest_dir: &Path, model: &Model, constants: &Constants, state: &mut State) -> io::Result<()> {
+    generate_mod(dest_dir, model, constants, state)
 }
 
-fn generate_mod(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
+fn generate_mod(mod_dir: &Path, model: &Model, constants: &Constants, state: &mut State) -> io::Result<()> {
 
-    let _ = fs::remove_dir_all(&mod_dir);
-    fs::create_dir_all(&mod_dir)?;
+    state.output.create_dir(mod_dir)?;
 
     println!("== Writing module...");
     let mod_file = mod_dir.join("mod.rs");
-    let mut writer = BufWriter::new(File::create(&mod_file)?);
+    let mut writer = Vec::new();
     writeln!(writer, "#![allow(non_camel_case_types, non_snake_case, unused)]")?;
     writeln!(writer)?;
     writeln!(writer, "//! This module is generated by bootstrap command of the CLI.")?;
@@ -102,7 +137,10 @@ fn generate_mod(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<
     writeln!(writer, "pub mod alias;")?;
     writeln!(writer)?;
 
-    generate_alias(mod_dir, model)?;
+    generate_alias(mod_dir, model, &mut *state)?;
+
+    writeln!(writer, "pub mod constants;")?;
+    constants::generate(mod_dir, constants, &mut state.output)?;
 
     // for app in &APPS {
     //     writeln!(writer, "pub mod {};", app.mod_name)?;
@@ -110,22 +148,31 @@ fn generate_mod(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<
     //     generate_app_mod(&app_mod_dir, app, model, &mut *state)?;
     // }
 
+    writeln!(writer, "pub mod args;")?;
     writeln!(writer, "pub mod interface;")?;
+    writeln!(writer, "pub mod component;")?;
     writeln!(writer, "pub mod entity;")?;
+    generate_args(mod_dir, model, &mut *state)?;
     generate_interfaces(mod_dir, model, &mut *state)?;
+    generate_components(mod_dir, model, &mut *state)?;
     generate_entities(mod_dir, model, &mut *state)?;
 
-    Ok(())
+    state.output.write(&mod_file, &writer)?;
+    // Files of previous generations that are no longer generated.
+    state.output.clean_dir(mod_dir)
+
 
 }
 
-fn generate_alias(mod_dir: &Path, model: &Model) -> io::Result<()> {
+fn generate_alias(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
 
     println!("== Writing aliases...");
     let alias_file = mod_dir.join("alias.rs");
-    let mut writer = BufWriter::new(File::create(&alias_file)?);
+    let mut writer = Vec::new();
+    let derives = state.derives();
 
     writeln!(writer, "pub use wgtk::net::codec::{{AutoString, Python, Mailbox}};")?;
+    writeln!(writer, "pub use wgtk::net::app::client::element::QueueType;")?;
     writeln!(writer, "pub use glam::{{Vec2, Vec3, Vec4}};")?;
     writeln!(writer)?;
 
@@ -147,7 +194,7 @@ fn generate_alias(mod_dir: &Path, model: &Model) -> io::Result<()> {
                 prev_dict = true;
                 writeln!(writer)?;
                 writeln!(writer, "wgtk::__struct_simple_codec! {{")?;
-                writeln!(writer, "    #[derive(Debug)]")?;
+                writeln!(writer, "    #[derive({derives})]")?;
                 writeln!(writer, "    pub struct {identifier} {{")?;
                 for prop in &ty_dict.properties {
                     let prop_identifier = generate_rust_identifier(&prop.name);
@@ -155,6 +202,24 @@ fn generate_alias(mod_dir: &Path, model: &Model) -> io::Result<()> {
                 }
                 writeln!(writer, "    }}")?;
                 writeln!(writer, "}}")?;
+                writeln!(writer, "wgtk::__impl_nested_property! {{")?;
+                writeln!(writer, "    {identifier} {{")?;
+                for prop in &ty_dict.properties {
+                    writeln!(writer, "        \"{}\" => {},", prop.name, generate_rust_identifier(&prop.name))?;
+                }
+                writeln!(writer, "    }}")?;
+                writeln!(writer, "}}")?;
+            }
+            TyKind::User(implemented_by) => {
+                let Some(rust_ty) = state.patches.user_type(ty.name()) else {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                        format!("user type {} implemented by {implemented_by} has no Rust type, it should be mapped in the patches file", ty.name())));
+                };
+                if prev_dict {
+                    writeln!(writer)?;
+                    prev_dict = false;
+                }
+                writeln!(writer, "pub type {identifier} = {rust_ty};")?;
             }
             TyKind::Array(_) |
             TyKind::Tuple(_) => {
@@ -166,7 +231,7 @@ fn generate_alias(mod_dir: &Path, model: &Model) -> io::Result<()> {
 
     }
 
-    Ok(())
+    state.output.write(&alias_file, &writer)
 
 }
 
@@ -201,6 +266,9 @@ fn generate_type_ref(ty: &Ty) -> Cow<'_, str> {
             return Cow::Owned(inline);
 
         }
+        TyKind::Dict(ty_dict) if ty_dict.allow_none => {
+            return Cow::Owned(format!("Option<{}>", generate_rust_identifier(ty.name())));
+        }
         _ => ty.name()
     })
 }
@@ -213,20 +281,113 @@ fn generate_rust_identifier(name: &str) -> Cow<'_, str> {
     }
 }
 
+/// Generate the method argument structures that are shared by multiple methods, this
+/// pass detects all exposed methods with the exact same arguments (names and types)
+/// and only generates a single structure for them, each method then being a type alias
+/// to it. This greatly reduces the amount of generated code. Nothing is shared if the
+/// deduplication is disabled, in which case the module is empty.
+fn generate_args(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
+
+    println!("== Writing shared arguments...");
+    let args_file = mod_dir.join("args.rs");
+    let mut writer = Vec::new();
+
+    writeln!(writer, "use super::alias::*;")?;
+    writeln!(writer)?;
+
+    if !state.dedup_args {
+        return state.output.write(&args_file, &writer);
+    }
+
+    // Count each distinct arguments' signature, in order of first appearance.
+    let mut signatures = Vec::new();
+    let mut counts = HashMap::<MethodArgs, usize>::new();
+
+    let interfaces = model.interfaces.iter()
+        .chain(&model.components)
+        .chain(model.entities.iter().map(|entity| &entity.interface));
+
+    for interface in interfaces {
+        for app_state in &state.apps {
+            for method in (app_state.interface_methods)(interface) {
+                if defs::is_method_exposed(method) {
+                    let args = generate_method_args(interface, method, &state.patches);
+                    let count = counts.entry(args.clone()).or_default();
+                    if *count == 0 {
+                        signatures.push(args);
+                    }
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    writeln!(writer, "wgtk::__struct_simple_codec! {{")?;
+    writeln!(writer)?;
+
+    let derives = state.derives();
+    for args in signatures {
+
+        if counts[&args] < 2 {
+            continue;
+        }
+
+        let struct_name = format!("Args_{}", state.shared_args.len());
+
+        writeln!(writer, "    #[derive({derives})]")?;
+        writeln!(writer, "    pub struct {struct_name} {{")?;
+        for (name, ty) in &args {
+            generate_method_arg(&mut writer, name, ty, &state.constant_groups)?;
+        }
+        writeln!(writer, "    }}")?;
+        writeln!(writer)?;
+
+        state.shared_args.insert(args, struct_name);
+
+    }
+
+    writeln!(writer, "}}")?;
+
+    state.output.write(&args_file, &writer)
+
+}
+
 fn generate_interfaces(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
 
     println!("== Writing interfaces...");
     let interface_file = mod_dir.join("interface.rs");
-    let mut writer = BufWriter::new(File::create(&interface_file)?);
+    let mut writer = Vec::new();
 
     writeln!(writer, "use super::alias::*;")?;
+    writeln!(writer, "use super::args::*;")?;
     writeln!(writer)?;
 
     for interface in &model.interfaces {
         generate_interface(&mut writer, model, interface, &mut *state)?;
     }
 
-    Ok(())
+    state.output.write(&interface_file, &writer)
+
+}
+
+/// Generate the component types, they are generated like interfaces, after them
+/// because components may implement interfaces.
+fn generate_components(mod_dir: &Path, model: &Model, state: &mut State) -> io::Result<()> {
+
+    println!("== Writing components...");
+    let component_file = mod_dir.join("component.rs");
+    let mut writer = Vec::new();
+
+    writeln!(writer, "use super::alias::*;")?;
+    writeln!(writer, "use super::args::*;")?;
+    writeln!(writer, "use super::interface::*;")?;
+    writeln!(writer)?;
+
+    for component in &model.components {
+        generate_interface(&mut writer, model, component, &mut *state)?;
+    }
+
+    state.output.write(&component_file, &writer)
 
 }
 
@@ -234,30 +395,33 @@ fn generate_entities(mod_dir: &Path, model: &Model, state: &mut State) -> io::Re
 
     println!("== Writing entities...");
     let entity_file = mod_dir.join("entity.rs");
-    let mut writer = BufWriter::new(File::create(&entity_file)?);
+    let mut writer = Vec::new();
 
-    writeln!(writer, "use wgtk::net::app::common::entity::{{Entity, SimpleEntity}};")?;
+    writeln!(writer, "use wgtk::net::app::common::entity::{{Entity, SimpleEntity, Properties, DirtyProperties}};")?;
+    writeln!(writer, "use wgtk::net::element::ElementLength;")?;
     writeln!(writer)?;
     writeln!(writer, "use super::alias::*;")?;
     writeln!(writer, "use super::interface::*;")?;
+    writeln!(writer, "use super::component::*;")?;
     writeln!(writer)?;
 
     for entity in &model.entities {
         generate_entity(&mut writer, model, entity, &mut *state)?;
     }
 
-    // writeln!(writer, "wgtk::__bootstrap_enum_entities! {{")?;
-    // writeln!(writer, "    /// Generic entity type enumeration allowing decoding of any entities.")?;
-    // writeln!(writer, "    #[derive(Debug)]")?;
-    // writeln!(writer, "    pub enum Generic: Generic_Client, Generic_Base, Generic_Cell {{")?;
-    // for entity in &model.entities {
-    //     writeln!(writer, "        {} = 0x{:02X},", entity.interface.name, entity.id)?;
-    // }
-    // writeln!(writer, "    }}")?;
-    // writeln!(writer, "}}")?;
-    // writeln!(writer)?;
+    // Generic entity type enumeration allowing decoding of any entities, and of their
+    // methods, from their type id.
+    writeln!(writer, "wgtk::__bootstrap_enum_entities! {{")?;
+    writeln!(writer, "    #[derive({})]", state.derives())?;
+    writeln!(writer, "    pub enum Generic: Generic_Client, Generic_Base, Generic_Cell {{")?;
+    for entity in &model.entities {
+        writeln!(writer, "        {} = 0x{:02X},", entity.interface.name, entity.id)?;
+    }
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
 
-    Ok(())
+    state.output.write(&entity_file, &writer)
 
 }
 
@@ -270,8 +434,15 @@ fn generate_entity(
 
     generate_interface(&mut writer, model, &entity.interface, state)?;
     
+    let derives = state.derives();
     for app_state in &mut state.apps {
-        generate_entity_methods(&mut writer, model, entity, app_state)?;
+        generate_entity_methods(&mut writer, model, entity, app_state, derives)?;
+    }
+
+    if let Some(handlers) = state.handlers {
+        for app_state in &state.apps {
+            generate_entity_handler(&mut writer, model, entity, app_state, handlers, &state.patches)?;
+        }
     }
     
     writeln!(writer, "impl {} {{", entity.interface.name)?;
@@ -286,81 +457,284 @@ fn generate_entity(
     writeln!(writer, "}}")?;
     writeln!(writer)?;
 
+    generate_entity_properties(&mut writer, model, entity, state)?;
+    generate_entity_data(&mut writer, model, entity, state)?;
+
+    if !entity.components.is_empty() {
+        generate_entity_components(&mut writer, entity, derives)?;
+    }
+
     Ok(())
 
 }
 
-fn generate_entity_methods(
+/// Generate the structure of all components of the entity, they are sent to the 
+/// client after the entity's properties and their count, in declaration order.
+fn generate_entity_components(
     mut writer: impl Write,
-    model: &Model, 
     entity: &Entity,
-    app_state: &mut AppState,
-)  -> io::Result<()> {
+    derives: &str,
+) -> io::Result<()> {
+
+    writeln!(writer, "wgtk::__struct_simple_codec! {{")?;
+    writeln!(writer, "    #[derive({derives})]")?;
+    writeln!(writer, "    pub struct {}_Components {{", entity.interface.name)?;
+    for component in &entity.components {
+        writeln!(writer, "        pub {}: {},", generate_rust_identifier(&component.name), component.ty)?;
+    }
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
 
-    /// An exposed method for the network protocol, this is used to list all exposed 
-    /// methods on an entity and then compute the methods' exposed ids by sorting them.
-    #[derive(Debug)]
-    struct ExposedMethod<'a> {
-        interface: &'a Interface,
-        method: &'a Method,
-        stream_size: StreamSize,
-    }
-
-    /// This method recursively register all methods for the entity in order to sort them
-    /// later depending on their arguments' size and then compute there exposed id for
-    /// the network protocol.
-    /// 
-    /// IMPORTANT: The initial order of the exposed method is really important because we
-    /// will use a stable sort, and some orders should not be changed.
-    fn add_internal_methods<'m>(
-        exposed_methods: &mut Vec<ExposedMethod<'m>>, 
-        model: &'m Model, 
-        interface: &'m Interface,
-        app_state: &mut AppState,
-    ) {
-
-        for interface_name in &interface.implements {
+    Ok(())
+
+}
+
+/// Generate the structures of the groups of properties of the entity, depending on
+/// their flags, in addition to the entity's structure of client-visible properties.
+/// Unlike the latter, properties of implemented interfaces are flattened, in the same
+/// order as they are streamed:
+/// - the base data, with properties stored on the base;
+/// - the cell data, with properties stored on the cell;
+/// - the cell client data, with properties of the cell visible to the client, which
+///   are sent on cell player creation.
+fn generate_entity_data(
+    mut writer: impl Write,
+    model: &Model,
+    entity: &Entity,
+    state: &State,
+) -> io::Result<()> {
+
+    let groups: [(&str, PropertyGroup); 3] = [
+        ("BaseData", |p| matches!(p.flags, PropertyFlags::Base | PropertyFlags::BaseAndClient)),
+        ("CellData", |p| matches!(p.flags, PropertyFlags::CellPrivate | PropertyFlags::CellPublic | PropertyFlags::OwnClient | PropertyFlags::AllClients)),
+        ("CellClientData", |p| matches!(p.flags, PropertyFlags::OwnClient | PropertyFlags::AllClients)),
+    ];
+
+    let derives = state.derives();
+
+    writeln!(writer, "wgtk::__struct_simple_codec! {{")?;
+    writeln!(writer)?;
+
+    for (suffix, filter) in groups {
+
+        let mut properties = Vec::new();
+        collect_entity_data_properties(model, &entity.interface, filter, &mut properties);
+
+        writeln!(writer, "    #[derive({derives})]")?;
+        writeln!(writer, "    pub struct {}_{suffix} {{", entity.interface.name)?;
+        for (interface, property) in properties {
+            let (name, ty) = generate_property_field(interface, property, &state.patches);
+            writeln!(writer, "        pub {name}: {ty},")?;
+        }
+        writeln!(writer, "    }}")?;
+        writeln!(writer)?;
+
+    }
+
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    Ok(())
+
+}
+
+/// Recursively collect the properties of the given interface, and of its implemented
+/// interfaces first, that are matching the given filter.
+fn collect_entity_data_properties<'m>(
+    model: &'m Model,
+    interface: &'m Interface,
+    filter: fn(&Property) -> bool,
+    properties: &mut Vec<(&'m Interface, &'m Property)>,
+) {
+
+    for interface_name in &interface.implements {
+
+        let interface = model.interfaces.iter()
+            .find(|i| &i.name == interface_name)
+            .expect("unknown implemented interface");
+
+        collect_entity_data_properties(model, interface, filter, properties);
+
+    }
+
+    for property in &interface.properties {
+        if filter(property) {
+            properties.push((interface, property));
+        }
+    }
+
+}
+
+/// Generate getters and setters of all client-visible properties of the entity, the
+/// setters mark the property as dirty, the enumeration of all properties with their 
+/// exposed index, used to decode updates, the navigation of nested properties and the
+/// implementation of properties encoding.
+fn generate_entity_properties(
+    mut writer: impl Write,
+    model: &Model,
+    entity: &Entity,
+    state: &State,
+) -> io::Result<()> {
+
+    let mut properties = Vec::new();
+    collect_entity_properties(model, &entity.interface, state, "", &mut properties);
+
+    let derives = state.derives();
+
+    writeln!(writer, "impl {} {{", entity.interface.name)?;
+    for (index, property) in properties.iter().enumerate() {
+        let ClientProperty { path, name, ty, .. } = property;
+        writeln!(writer, "    pub fn {name}(&self) -> &{ty} {{ &self.{path} }}")?;
+        writeln!(writer, "    pub fn set_{name}(&mut self, value: {ty}, dirty: &mut DirtyProperties) {{ self.{path} = value; dirty.mark({index}); }}", 
+            name = name.trim_start_matches("r#"))?;
+    }
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "wgtk::__enum_entity_properties! {{  // Client-visible properties")?;
+    writeln!(writer, "    #[derive({derives})]")?;
+    writeln!(writer, "    pub enum {}_Property {{", entity.interface.name)?;
+    for (index, property) in properties.iter().enumerate() {
+        let ClientProperty { name, ty, .. } = property;
+        match defs::compute_type_stream_size(&property.property.ty) {
+            Some(size) => writeln!(writer, "        {name}(0x{index:02X}, {ty}, {size}),")?,
+            None => writeln!(writer, "        {name}(0x{index:02X}, {ty}, var16),")?,
+        }
+    }
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "wgtk::__impl_nested_property! {{")?;
+    writeln!(writer, "    {} {{", entity.interface.name)?;
+    for property in &properties {
+        writeln!(writer, "        \"{}\" => {},", property.property.name, property.path)?;
+    }
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "impl Properties for {} {{", entity.interface.name)?;
+    writeln!(writer, "    type Property = {}_Property;", entity.interface.name)?;
+    writeln!(writer, "    fn property_length(index: u16) -> ElementLength {{")?;
+    writeln!(writer, "        match index {{")?;
+    for (index, property) in properties.iter().enumerate() {
+        if let Some(size) = defs::compute_type_stream_size(&property.property.ty) {
+            writeln!(writer, "            {index} => ElementLength::Fixed({size}),")?;
+        }
+    }
+    writeln!(writer, "            _ => ElementLength::Variable16,")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {{")?;
+    writeln!(writer, "        use wgtk::net::codec::Codec;")?;
+    writeln!(writer, "        match index {{")?;
+    for (index, property) in properties.iter().enumerate() {
+        writeln!(writer, "            {index} => Codec::<()>::write(&self.{}, write, &()),", property.path)?;
+    }
+    writeln!(writer, "            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!(\"invalid property index: {{index}}\"))),")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    Ok(())
+
+}
+
+/// A client-visible property of an entity, in exposed index order.
+struct ClientProperty<'m> {
+    /// The path of the field in the generated entity structure.
+    path: String,
+    /// Identifier of the generated field.
+    name: Cow<'m, str>,
+    /// Type of the generated field.
+    ty: Cow<'m, str>,
+    property: &'m Property,
+}
+
+/// Recursively collect the client-visible properties of the given interface, in the
+/// same order as the fields of the generated structure, which is the exposed order.
+fn collect_entity_properties<'m>(
+    model: &'m Model,
+    interface: &'m Interface,
+    state: &State,
+    prefix: &str,
+    properties: &mut Vec<ClientProperty<'m>>,
+) {
+
+    for interface_name in &interface.implements {
+        if !state.empty_interfaces.contains(interface_name) {
 
             let interface = model.interfaces.iter()
                 .find(|i| &i.name == interface_name)
                 .expect("unknown implemented interface");
 
-            add_internal_methods(exposed_methods, model, interface, &mut *app_state);
+            let prefix = format!("{prefix}i_{interface_name}.");
+            collect_entity_properties(model, interface, state, &prefix, properties);
 
         }
-        
-        for method in (app_state.interface_methods)(interface) {
-            if is_method_exposed(method) {
-                exposed_methods.push(ExposedMethod {
-                    interface,
-                    method,
-                    stream_size: compute_method_stream_size(method),
-                });
-            }
-        }
+    }
 
+    for property in &interface.properties {
+        if is_property_client(property) {
+            let (name, ty) = generate_property_field(interface, property, &state.patches);
+            properties.push(ClientProperty {
+                path: format!("{prefix}{name}"),
+                name,
+                ty,
+                property,
+            });
+        }
     }
 
-    let mut methods = Vec::new();
-    add_internal_methods(&mut methods, model, &entity.interface, &mut *app_state);
+}
+
+/// Return true if the property is visible to the client, only these are generated.
+fn is_property_client(property: &Property) -> bool {
+    matches!(property.flags, PropertyFlags::AllClients | PropertyFlags::OwnClient | PropertyFlags::BaseAndClient)
+}
+
+/// Return the identifier and type of the generated field of the given property, after
+/// applying builtin patches and then those of the patch file.
+fn generate_property_field<'m>(interface: &Interface, property: &'m Property, patches: &PatchFile) -> (Cow<'m, str>, Cow<'m, str>) {
+
+    let mut name = Cow::Borrowed("");
+    let mut ty = Cow::Borrowed("");
 
-    // We want to sort fixed methods first and variable last, and then sort between
-    // their configured fixed or variable size.
-    methods.sort_by(|a, b| {
-        match (a.stream_size, b.stream_size) {
-            (StreamSize::Variable(a_size), StreamSize::Variable(b_size)) => 
-                a_size.cmp(&b_size),
-            (StreamSize::Fixed(a_size), StreamSize::Fixed(b_size)) =>
-                a_size.cmp(&b_size),
-            (StreamSize::Fixed(_), StreamSize::Variable(_)) =>
-                Ordering::Less,
-            (StreamSize::Variable(_), StreamSize::Fixed(_)) =>
-                Ordering::Greater,
+    for patch in PATCHES {
+        if let Patch::InterfaceProperty(func) = patch {
+            (func)(&interface.name, &property.name, &mut name, &mut ty);
         }
-    });
+    }
+
+    patches.apply_property(&interface.name, &property.name, &mut name, &mut ty);
+
+    if name.is_empty() {
+        name = Cow::Borrowed(&property.name);
+    }
+
+    if ty.is_empty() {
+        ty = generate_type_ref(&property.ty);
+    }
+
+    (name, ty)
+
+}
+
+fn generate_entity_methods(
+    mut writer: impl Write,
+    model: &Model, 
+    entity: &Entity,
+    app_state: &mut AppState,
+    derives: &str,
+)  -> io::Result<()> {
+
+    let methods = defs::exposed_methods(model, entity, app_state.interface_methods);
 
     writeln!(writer, "wgtk::__enum_entity_methods! {{  // Entity methods on {}", app_state.name)?;
-    writeln!(writer, "    #[derive(Debug)]")?;
+    writeln!(writer, "    #[derive({derives})]")?;
     writeln!(writer, "    pub enum {}_{} {{", 
         entity.interface.name, app_state.suffix)?;
 
@@ -387,6 +761,108 @@ fn generate_entity_methods(
 
 }
 
+/// Generate the handler trait of the entity methods on the given app, with a default
+/// no-op implementation for each method, and the dispatch method on the methods'
+/// enumeration that calls the handler method of the decoded variant. Sync handler
+/// methods take each argument, async ones take the arguments' structure.
+fn generate_entity_handler(
+    mut writer: impl Write,
+    model: &Model, 
+    entity: &Entity,
+    app_state: &AppState,
+    handlers: Handlers,
+    patches: &PatchFile,
+)  -> io::Result<()> {
+
+    let methods = defs::exposed_methods(model, entity, app_state.interface_methods);
+    let entity_name = &entity.interface.name;
+    let suffix = app_state.suffix;
+
+    // The same method name may be present in multiple interfaces of the entity, in
+    // such case the handler method is prefixed by the interface name.
+    let mut name_counts = HashMap::<&str, usize>::new();
+    for method in &methods {
+        *name_counts.entry(method.method.name.as_str()).or_default() += 1;
+    }
+
+    let handler_names = methods.iter()
+        .map(|method| {
+            if name_counts[method.method.name.as_str()] > 1 {
+                format!("{}_{}", method.interface.name, method.method.name)
+            } else {
+                method.method.name.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match handlers {
+        Handlers::Sync => {
+
+            let method_args = methods.iter()
+                .map(|method| generate_method_args(method.interface, method.method, patches))
+                .collect::<Vec<_>>();
+
+            writeln!(writer, "/// Handler of entity methods on {}, see [`{entity_name}_{suffix}::dispatch`].", app_state.name)?;
+            writeln!(writer, "#[allow(unused_variables)]")?;
+            writeln!(writer, "pub trait {entity_name}{suffix}Handler {{")?;
+            for (args, handler_name) in method_args.iter().zip(&handler_names) {
+                let params = args.iter()
+                    .map(|(name, ty)| format!(", {name}: {ty}"))
+                    .collect::<String>();
+                writeln!(writer, "    fn {handler_name}(&mut self{params}) {{}}")?;
+            }
+            writeln!(writer, "}}")?;
+            writeln!(writer)?;
+
+            writeln!(writer, "impl {entity_name}_{suffix} {{")?;
+            writeln!(writer, "    /// Call the handler method of this entity method with its arguments.")?;
+            writeln!(writer, "    pub fn dispatch<H: {entity_name}{suffix}Handler>(self, handler: &mut H) {{")?;
+            writeln!(writer, "        match self {{")?;
+            for ((method, args), handler_name) in methods.iter().zip(&method_args).zip(&handler_names) {
+                let params = args.iter()
+                    .map(|(name, _)| format!("args.{name}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // Methods without arguments don't bind their structure.
+                let binding = if args.is_empty() { "_" } else { "args" };
+                writeln!(writer, "            Self::{}_{}({binding}) => handler.{handler_name}({params}),",
+                    method.interface.name, method.method.name)?;
+            }
+            writeln!(writer, "        }}")?;
+
+        }
+        Handlers::Async => {
+
+            writeln!(writer, "/// Async handler of entity methods on {}, see [`{entity_name}_{suffix}::dispatch`].", app_state.name)?;
+            writeln!(writer, "pub trait {entity_name}{suffix}Handler: Send {{")?;
+            for (method, handler_name) in methods.iter().zip(&handler_names) {
+                writeln!(writer, "    fn {handler_name}(&mut self, args: {}_{}) -> impl std::future::Future<Output = ()> + Send {{ async {{}} }}",
+                    method.interface.name, method.method.name)?;
+            }
+            writeln!(writer, "}}")?;
+            writeln!(writer)?;
+
+            writeln!(writer, "impl {entity_name}_{suffix} {{")?;
+            writeln!(writer, "    /// Call the handler method of this entity method.")?;
+            writeln!(writer, "    pub async fn dispatch<H: {entity_name}{suffix}Handler>(self, handler: &mut H) {{")?;
+            writeln!(writer, "        match self {{")?;
+            for (method, handler_name) in methods.iter().zip(&handler_names) {
+                writeln!(writer, "            Self::{}_{}(args) => handler.{handler_name}(args).await,",
+                    method.interface.name, method.method.name)?;
+            }
+            writeln!(writer, "        }}")?;
+
+        }
+    }
+
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    Ok(())
+
+}
+
 fn generate_interface(
     mut writer: impl Write, 
     model: &Model, 
@@ -399,8 +875,9 @@ fn generate_interface(
     writeln!(writer, "// ============================================== //")?;
     writeln!(writer)?;
     
+    let derives = state.derives();
     writeln!(writer, "wgtk::__struct_simple_codec! {{")?;
-    writeln!(writer, "    #[derive(Debug)]")?;
+    writeln!(writer, "    #[derive({derives})]")?;
     writeln!(writer, "    pub struct {} {{", interface.name)?;
     
     for interface_name in &interface.implements {
@@ -411,28 +888,10 @@ fn generate_interface(
 
     let mut count = 0;
     for property in &interface.properties {
-        if matches!(property.flags, PropertyFlags::AllClients | PropertyFlags::OwnClient | PropertyFlags::BaseAndClient) {
-
-            let mut name = Cow::Borrowed("");
-            let mut ty = Cow::Borrowed("");
-
-            for patch in PATCHES {
-                if let Patch::InterfaceProperty(func) = patch {
-                    (func)(&interface.name, &property.name, &mut name, &mut ty);
-                }
-            }
-
-            if name.is_empty() {
-                name = Cow::Borrowed(&property.name);
-            }
-
-            if ty.is_empty() {
-                ty = generate_type_ref(&property.ty);
-            }
-
+        if is_property_client(property) {
+            let (name, ty) = generate_property_field(interface, property, &state.patches);
             writeln!(writer, "        pub {name}: {ty},")?;
             count += 1;
-
         }
     }
 
@@ -444,8 +903,8 @@ fn generate_interface(
     writeln!(writer, "}}")?;
     writeln!(writer)?;
 
-    for app_state in &mut state.apps {
-        generate_interface_methods(&mut writer, model, interface, app_state)?;
+    for app_state in &state.apps {
+        generate_interface_methods(&mut writer, model, interface, app_state, state)?;
     }
 
     Ok(())
@@ -456,17 +915,21 @@ fn generate_interface_methods(
     mut writer: impl Write,
     _model: &Model, 
     interface: &Interface,
-    app_state: &mut AppState,
+    app_state: &AppState,
+    state: &State,
 )  -> io::Result<()> {
 
     let mut unique_names = HashSet::new();
+    let mut aliases = Vec::new();
     
     writeln!(writer, "wgtk::__struct_simple_codec! {{  // Methods on {}", app_state.name)?;
     writeln!(writer)?;
 
+    let derives = state.derives();
+
     for method in (app_state.interface_methods)(interface) {
 
-        if !is_method_exposed(method) {
+        if !defs::is_method_exposed(method) {
             continue;
         }
 
@@ -474,32 +937,19 @@ fn generate_interface_methods(
             panic!("function name present multiple times: {}", method.name);
         }
 
-        writeln!(writer, "    #[derive(Debug)]")?;
-        writeln!(writer, "    pub struct {}_{} {{", interface.name, method.name)?;
-
-        for (arg_idx, arg) in method.args.iter().enumerate() {
-
-            let mut name = Cow::Borrowed("");
-            let mut ty = Cow::Borrowed("");
-
-            for patch in PATCHES {
-                if let Patch::InterfaceMethodArg(func) = patch {
-                    (func)(&interface.name, &method.name, arg_idx, &mut name, &mut ty);
-                }
-            }
-
-            if name.is_empty() {
-                name = Cow::Owned(format!("a{arg_idx}"));
-            }
-
-            if ty.is_empty() {
-                ty = generate_type_ref(&arg.ty);
-            }
-
-            writeln!(writer, "        pub {name}: {ty},")?;
+        let args = generate_method_args(interface, method, &state.patches);
 
+        // Aliases can't be defined in the codec macro, so they are written after.
+        if let Some(struct_name) = state.shared_args.get(&args) {
+            aliases.push((&method.name, struct_name));
+            continue;
         }
 
+        writeln!(writer, "    #[derive({derives})]")?;
+        writeln!(writer, "    pub struct {}_{} {{", interface.name, method.name)?;
+        for (name, ty) in &args {
+            generate_method_arg(&mut writer, name, ty, &state.constant_groups)?;
+        }
         writeln!(writer, "    }}")?;
         writeln!(writer)?;
 
@@ -508,58 +958,77 @@ fn generate_interface_methods(
     writeln!(writer, "}}")?;
     writeln!(writer)?;
 
+    if !aliases.is_empty() {
+        for (method_name, struct_name) in aliases {
+            writeln!(writer, "pub type {}_{method_name} = {struct_name};", interface.name)?;
+        }
+        writeln!(writer)?;
+    }
+
     Ok(())
 
 }
 
-/// Return the stream size of this type, none if the type has no known size.
-fn compute_type_stream_size(ty: &Ty) -> Option<usize> {
-    match ty.kind() {
-        TyKind::Int8 | TyKind::UInt8 => Some(1),
-        TyKind::Int16 | TyKind::UInt16 => Some(2),
-        TyKind::Int32 | TyKind::UInt32 => Some(4),
-        TyKind::Int64 | TyKind::UInt64 => Some(8),
-        TyKind::Float32 => Some(4),
-        TyKind::Float64 => Some(8),
-        TyKind::Vector2 => Some(4 * 2),
-        TyKind::Vector3 => Some(4 * 3),
-        TyKind::Vector4 => Some(4 * 4),
-        TyKind::String => None,
-        TyKind::Python => None,
-        TyKind::Mailbox => None,  // TODO:
-        TyKind::Alias(ty) => 
-            compute_type_stream_size(ty),
-        TyKind::Dict(ty_dict) => 
-            ty_dict.properties.iter()
-                .map(|prop| compute_type_stream_size(&prop.ty))
-                .sum(),  // Using sum on Option: any None will result in a None.
-        TyKind::Array(ty_seq) |
-        TyKind::Tuple(ty_seq) => 
-            ty_seq.size.map(|len| len as usize)
-                .zip(compute_type_stream_size(&ty_seq.ty))
-                .map(|(len, element_size)| len * element_size)
-    }
-}
+/// Return the identifier and type of each generated field of the given method's
+/// arguments, after applying builtin patches and then those of the patch file.
+fn generate_method_args(interface: &Interface, method: &Method, patches: &PatchFile) -> MethodArgs {
 
-/// This returns the preferred stream size.
-fn compute_method_stream_size(method: &Method) -> StreamSize {
-    
-    let size = method.args.iter()
-        .map(|arg| compute_type_stream_size(&arg.ty))
-        .sum::<Option<usize>>();
+    let mut args = Vec::with_capacity(method.args.len());
+
+    for (arg_idx, arg) in method.args.iter().enumerate() {
+
+        let mut name = Cow::Borrowed("");
+        let mut ty = Cow::Borrowed("");
+
+        for patch in PATCHES {
+            if let Patch::InterfaceMethodArg(func) = patch {
+                (func)(&interface.name, &method.name, arg_idx, &mut name, &mut ty);
+            }
+        }
+
+        patches.apply_arg(&interface.name, &method.name, arg_idx, &mut name, &mut ty);
+
+        if name.is_empty() {
+            name = Cow::Owned(format!("a{arg_idx}"));
+        }
+
+        if ty.is_empty() {
+            ty = generate_type_ref(&arg.ty);
+        }
+
+        args.push((name.into_owned(), ty.into_owned()));
 
-    match size {
-        Some(size) => StreamSize::Fixed(size),
-        // TODO: Also return this is exposed and has sub msg id?
-        None => StreamSize::Variable(method.variable_header_size)
     }
 
+    args
+
 }
 
-fn is_method_exposed(method: &Method) -> bool {
-    method.exposed_to_all_clients || method.exposed_to_own_client
+/// Generate the field of a method's argument, an argument named after a group of
+/// constants, such as 'token_type' for 'TOKEN_TYPE', links to it in its documentation.
+fn generate_method_arg(
+    mut writer: impl Write,
+    name: &str,
+    ty: &str,
+    constant_groups: &HashSet<String>,
+) -> io::Result<()> {
+    let group_name = name.to_ascii_uppercase();
+    if constant_groups.contains(&group_name) {
+        writeln!(writer, "        /// See [`super::constants::{group_name}`].")?;
+    }
+    writeln!(writer, "        pub {name}: {ty},")
 }
 
+/// The identifier and type of each field generated for a method's arguments.
+type MethodArgs = Vec<(String, String)>;
+
+/// Accessor of the methods of an interface for one app, used to iterate the client,
+/// base and cell methods in the same way.
+type InterfaceMethods = fn(&Interface) -> &[Method];
+
+/// Filter of the properties of an entity that belong to a group of properties, see
+/// [`generate_entity_data`].
+type PropertyGroup = fn(&Property) -> bool;
 
 /// Internal state when bootstrapping.
 #[derive(Debug)]
@@ -567,9 +1036,30 @@ struct State {
     /// A set of interfaces without any fields (sizeof=0) for which it's useless to 
     /// generate variants.
     empty_interfaces: HashSet<String>,
+    /// True to share a single structure between methods with the same arguments.
+    dedup_args: bool,
+    /// The name of the shared structure generated for each arguments' signature.
+    shared_args: HashMap<MethodArgs, String>,
+    /// The kind of handler traits to generate for each entity's methods, if any.
+    handlers: Option<Handlers>,
+    /// Names of the groups of constants, see [`generate_method_arg`].
+    constant_groups: HashSet<String>,
+    /// True to derive serde traits on all generated structures and enumerations.
+    serde: bool,
+    /// Patches loaded from a file, applied after the builtin ones.
+    patches: PatchFile,
+    /// Destination of all generated files.
+    output: Output,
     apps: [AppState; 3],
 }
 
+/// Kind of the handler traits generated for entities' methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handlers {
+    Sync,
+    Async,
+}
+
 #[derive(Debug)]
 struct AppState {
     name: &'static str,
@@ -578,9 +1068,23 @@ struct AppState {
 }
 
 impl State {
-    fn new() -> Self {
+    fn new(
+        dedup_args: bool,
+        handlers: Option<Handlers>,
+        serde: bool,
+        constant_groups: HashSet<String>,
+        patches: PatchFile,
+        output: Output,
+    ) -> Self {
         Self { 
             empty_interfaces: HashSet::new(), 
+            dedup_args,
+            shared_args: HashMap::new(),
+            handlers,
+            constant_groups,
+            serde,
+            patches,
+            output,
             apps: [
                 AppState::new("client", "Client", |i| &i.client_methods),
                 AppState::new("base", "Base", |i| &i.base_methods),
@@ -588,6 +1092,16 @@ impl State {
             ],
         }
     }
+
+    /// Return the traits to derive on all generated structures and enumerations.
+    fn derives(&self) -> &'static str {
+        if self.serde {
+            "Debug, serde::Serialize, serde::Deserialize"
+        } else {
+            "Debug"
+        }
+    }
+
 }
 
 impl AppState {
@@ -600,13 +1114,6 @@ impl AppState {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum StreamSize {
-    Fixed(usize),
-    Variable(VariableHeaderSize),
-}
-
-
 #[derive(Debug, Clone)]
 #[allow(unused)]
 enum Patch {
@@ -614,7 +1121,8 @@ enum Patch {
     InterfaceMethodArg(fn(interface: &str, method: &str, index: usize, name: &mut Cow<str>, ty: &mut Cow<str>)),
 }
 
-/// Patches to apply when generating code for World of Tanks.
+/// Patches to apply when generating code for World of Tanks, see [`PatchFile`] for
+/// patches that can be adjusted without recompiling.
 const PATCHES: &[Patch] = &[
     Patch::InterfaceMethodArg(|interface, method, index, name, ty| {
         match (interface, method, index) {
@@ -772,7 +1280,10 @@ const PATCHES: &[Patch] = &[
                 "onEnqueued" | 
                 "onDequeued" | 
                 "onEnqueueFailure" | 
-                "onKickedFromQueue", 0) => *name = "queue_type".into(),
+                "onKickedFromQueue", 0) => {
+                *name = "queue_type".into();
+                *ty = "QueueType".into();
+            }
             ("Account", "onEnqueueFailure", 1) => *name = "error_code".into(),
             ("Account", "onEnqueueFailure", 2) => *name = "error_str".into(),
             ("Account", "onIGRTypeChanged" | "showGUI", 0) => {
@@ -0,0 +1,97 @@
+//! Output of generated files to a destination directory, files are only written if
+//! their content changed, so that modification times are preserved for build caches,
+//! or only compared to the files on disk in check mode.
+
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::io;
+
+
+/// Tracks the files generated in a directory and those that changed.
+#[derive(Debug)]
+pub struct Output {
+    /// True to only compare files, without touching the disk.
+    check: bool,
+    /// All files generated.
+    files: Vec<PathBuf>,
+    /// Files that have been added, modified or removed, or would have been in check
+    /// mode.
+    changed: Vec<PathBuf>,
+}
+
+impl Output {
+
+    pub fn new(check: bool) -> Self {
+        Self {
+            check,
+            files: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    /// Create the given directory, if not in check mode.
+    pub fn create_dir(&self, dir: &Path) -> io::Result<()> {
+        if !self.check {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Write the content of a generated file, only if it's different from the one on
+    /// disk, if not in check mode.
+    pub fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<()> {
+
+        self.files.push(path.to_path_buf());
+
+        match fs::read(path) {
+            Ok(prev_content) if prev_content == content => return Ok(()),
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        self.changed.push(path.to_path_buf());
+        if !self.check {
+            fs::write(path, content)?;
+        }
+
+        Ok(())
+
+    }
+
+    /// Remove all files of the given directory that have not been generated, if not in
+    /// check mode.
+    pub fn clean_dir(&mut self, dir: &Path) -> io::Result<()> {
+
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            // In check mode, all generated files are already missing.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for entry in read_dir {
+
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type()?.is_file() || self.files.contains(&path) {
+                continue;
+            }
+
+            self.changed.push(path.clone());
+            if !self.check {
+                fs::remove_file(&path)?;
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    /// Return the files that have been added, modified or removed.
+    pub fn changed(&self) -> &[PathBuf] {
+        &self.changed
+    }
+
+}
@@ -0,0 +1,134 @@
+//! Patches loaded from a TOML file, applied after the builtin patches, so that names
+//! and types of the generated code can be adjusted without recompiling.
+//!
+//! ```toml
+//! [[arg]]
+//! interface = "Account"
+//! method = "onKickedFromServer"
+//! index = 0
+//! name = "reason"
+//!
+//! [[arg]]
+//! interface = "ClientCommandsPort"
+//! method = "doCmd*"
+//! name = "arg{index}"
+//! type = "Python"
+//!
+//! [[property]]
+//! interface = "Account"
+//! property = "name"
+//! type = "AutoString"
+//!
+//! [[user_type]]
+//! name = "VEHICLE_DESCR"
+//! type = "my_crate::VehicleDescr"
+//! ```
+//!
+//! Interface, method and property names match exactly, or by prefix if they end with
+//! '*', a missing argument index matches all arguments, and '{index}' in a name is
+//! replaced by the argument index. User types of the model (USER_TYPE) have no known
+//! encoding, so each must be mapped to a Rust type implementing the codec traits.
+
+use std::borrow::Cow;
+
+use serde::Deserialize;
+
+
+/// All patches of a patch file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatchFile {
+    #[serde(default, rename = "arg")]
+    pub args: Vec<ArgPatch>,
+    #[serde(default, rename = "property")]
+    pub properties: Vec<PropertyPatch>,
+    #[serde(default, rename = "user_type")]
+    pub user_types: Vec<UserTypePatch>,
+}
+
+/// A patch of the name and type of a method's argument.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArgPatch {
+    pub interface: String,
+    pub method: String,
+    pub index: Option<usize>,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+}
+
+/// A patch of the name and type of an interface's property.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropertyPatch {
+    pub interface: String,
+    pub property: String,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+}
+
+/// The Rust type of a user type, referenced by the generated code.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserTypePatch {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl PatchFile {
+
+    /// Parse the patches of the given TOML document.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Apply all matching patches to the given method's argument.
+    pub fn apply_arg(&self, interface: &str, method: &str, index: usize, name: &mut Cow<str>, ty: &mut Cow<str>) {
+        for patch in &self.args {
+            if name_match(&patch.interface, interface)
+            && name_match(&patch.method, method)
+            && !matches!(patch.index, Some(i) if i != index) {
+                if let Some(patch_name) = &patch.name {
+                    *name = Cow::Owned(patch_name.replace("{index}", &index.to_string()));
+                }
+                if let Some(patch_ty) = &patch.ty {
+                    *ty = Cow::Owned(patch_ty.clone());
+                }
+            }
+        }
+    }
+
+    /// Apply all matching patches to the given interface's property.
+    pub fn apply_property(&self, interface: &str, property: &str, name: &mut Cow<str>, ty: &mut Cow<str>) {
+        for patch in &self.properties {
+            if name_match(&patch.interface, interface) && name_match(&patch.property, property) {
+                if let Some(patch_name) = &patch.name {
+                    *name = Cow::Owned(patch_name.clone());
+                }
+                if let Some(patch_ty) = &patch.ty {
+                    *ty = Cow::Owned(patch_ty.clone());
+                }
+            }
+        }
+    }
+
+    /// Return the Rust type mapped to the given user type, if any.
+    pub fn user_type(&self, name: &str) -> Option<&str> {
+        self.user_types.iter()
+            .find(|patch| patch.name == name)
+            .map(|patch| patch.ty.as_str())
+    }
+
+}
+
+/// Return true if the given name matches the pattern, exactly or by prefix if the
+/// pattern ends with '*'.
+fn name_match(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
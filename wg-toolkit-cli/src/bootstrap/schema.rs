@@ -0,0 +1,325 @@
+//! Export of the model as JSON Schema and TypeScript declarations, with the same
+//! structures as the generated Rust code: client-visible properties of interfaces and
+//! entities, and exposed methods of entities for each app.
+
+use std::io::{self, Write, BufWriter};
+use std::fs::{self, File};
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::defs::model::{Entity, Interface, Model, Property, PropertyFlags, Ty, TyKind};
+use crate::defs;
+
+use super::InterfaceMethods;
+
+
+/// Apps with the methods of an interface for each of them, the name is used as suffix.
+const APPS: [(&str, InterfaceMethods); 3] = [
+    ("Client", |i| &i.client_methods),
+    ("Base", |i| &i.base_methods),
+    ("Cell", |i| &i.cell_methods),
+];
+
+
+/// Generate the JSON Schema of the model in a 'model.schema.json' file of the given
+/// directory. All types, interfaces, entities and entity methods are definitions.
+pub fn generate_json_schema(dest_dir: &Path, model: &Model) -> io::Result<()> {
+
+    fs::create_dir_all(dest_dir)?;
+
+    println!("== Writing JSON Schema...");
+    let mut schema_defs = Map::new();
+
+    for ty in model.tys.iter() {
+        match ty.kind() {
+            TyKind::Alias(alias_ty) => {
+                schema_defs.insert(ty.name().to_string(), json_schema_ty(alias_ty));
+            }
+            TyKind::Dict(ty_dict) => {
+                let properties = ty_dict.properties.iter()
+                    .map(|prop| (prop.name.clone(), json_schema_ty(&prop.ty)))
+                    .collect::<Map<_, _>>();
+                schema_defs.insert(ty.name().to_string(), json_schema_object(properties));
+            }
+            _ => {}  // Builtins and sequences are inlined.
+        }
+    }
+
+    for interface in &model.interfaces {
+        schema_defs.insert(format!("interface.{}", interface.name), json_schema_interface(interface));
+    }
+
+    for entity in &model.entities {
+
+        schema_defs.insert(format!("entity.{}", entity.interface.name), json_schema_interface(&entity.interface));
+
+        for (app_suffix, interface_methods) in APPS {
+
+            let variants = defs::exposed_methods(model, entity, interface_methods)
+                .iter()
+                .enumerate()
+                .map(|(exposed_id, method)| {
+                    let args = method.method.args.iter()
+                        .map(|arg| json_schema_ty(&arg.ty))
+                        .collect::<Vec<_>>();
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            "method": { "const": format!("{}_{}", method.interface.name, method.method.name) },
+                            "exposed_id": { "const": exposed_id },
+                            "args": {
+                                "type": "array",
+                                "prefixItems": args,
+                                "minItems": args.len(),
+                                "maxItems": args.len(),
+                            },
+                        },
+                        "required": ["method", "exposed_id", "args"],
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            schema_defs.insert(format!("entity.{}_{app_suffix}", entity.interface.name), json!({ "oneOf": variants }));
+
+        }
+
+    }
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$comment": "This file is generated by bootstrap command of the CLI.",
+        "$defs": schema_defs,
+    });
+
+    let schema_file = dest_dir.join("model.schema.json");
+    let mut writer = BufWriter::new(File::create(&schema_file)?);
+    serde_json::to_writer_pretty(&mut writer, &schema)?;
+    writeln!(writer)?;
+    writer.flush()
+
+}
+
+/// Return the JSON Schema object of the client-visible properties of an interface,
+/// with all implemented interfaces.
+fn json_schema_interface(interface: &Interface) -> Value {
+
+    let properties = interface.properties.iter()
+        .filter(|property| is_property_client(property))
+        .map(|property| (property.name.clone(), json_schema_ty(&property.ty)))
+        .collect::<Map<_, _>>();
+
+    let mut all_of = interface.implements.iter()
+        .map(|name| json!({ "$ref": format!("#/$defs/interface.{name}") }))
+        .collect::<Vec<_>>();
+
+    all_of.push(json_schema_object(properties));
+    json!({ "allOf": all_of })
+
+}
+
+fn json_schema_object(properties: Map<String, Value>) -> Value {
+    let required = properties.keys().cloned().collect::<Vec<_>>();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn json_schema_ty(ty: &Ty) -> Value {
+
+    fn int(min: i64, max: u64) -> Value {
+        json!({ "type": "integer", "minimum": min, "maximum": max })
+    }
+
+    fn vector(len: usize) -> Value {
+        json!({ "type": "array", "items": { "type": "number" }, "minItems": len, "maxItems": len })
+    }
+
+    match ty.kind() {
+        TyKind::Int8 => int(i8::MIN as i64, i8::MAX as u64),
+        TyKind::Int16 => int(i16::MIN as i64, i16::MAX as u64),
+        TyKind::Int32 => int(i32::MIN as i64, i32::MAX as u64),
+        TyKind::Int64 => int(i64::MIN, i64::MAX as u64),
+        TyKind::UInt8 => int(0, u8::MAX as u64),
+        TyKind::UInt16 => int(0, u16::MAX as u64),
+        TyKind::UInt32 => int(0, u32::MAX as u64),
+        TyKind::UInt64 => int(0, u64::MAX),
+        TyKind::Float32 |
+        TyKind::Float64 => json!({ "type": "number" }),
+        TyKind::Vector2 => vector(2),
+        TyKind::Vector3 => vector(3),
+        TyKind::Vector4 => vector(4),
+        TyKind::String => json!({ "type": "string" }),
+        TyKind::Python => json!({ "description": "Pickled Python value" }),
+        TyKind::Mailbox => json!({
+            "description": "Entity mailbox",
+            "type": "object",
+            "properties": {
+                "entity_id": { "type": "integer" },
+                "addr": { "type": "string" },
+                "salt": { "type": "integer" },
+            },
+            "required": ["entity_id", "addr", "salt"],
+        }),
+        TyKind::User(implemented_by) => json!({ "description": format!("User type implemented by {implemented_by}") }),
+        TyKind::Dict(ty_dict) if ty_dict.allow_none => json!({
+            "anyOf": [{ "type": "null" }, { "$ref": format!("#/$defs/{}", ty.name()) }],
+        }),
+        TyKind::Alias(_) |
+        TyKind::Dict(_) => json!({ "$ref": format!("#/$defs/{}", ty.name()) }),
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) => {
+            let mut schema = json!({ "type": "array", "items": json_schema_ty(&ty_seq.ty) });
+            if let Some(size) = ty_seq.size {
+                schema["minItems"] = json!(size);
+                schema["maxItems"] = json!(size);
+            }
+            schema
+        }
+    }
+
+}
+
+/// Generate the TypeScript declarations of the model in a 'model.d.ts' file of the
+/// given directory. Interfaces and entities are declared in their own namespaces.
+pub fn generate_typescript(dest_dir: &Path, model: &Model) -> io::Result<()> {
+
+    fs::create_dir_all(dest_dir)?;
+
+    println!("== Writing TypeScript declarations...");
+    let ts_file = dest_dir.join("model.d.ts");
+    let mut writer = BufWriter::new(File::create(&ts_file)?);
+
+    writeln!(writer, "// This file is generated by bootstrap command of the CLI.")?;
+    writeln!(writer)?;
+    writeln!(writer, "export type Vec2 = [number, number];")?;
+    writeln!(writer, "export type Vec3 = [number, number, number];")?;
+    writeln!(writer, "export type Vec4 = [number, number, number, number];")?;
+    writeln!(writer, "/** Pickled Python value. */")?;
+    writeln!(writer, "export type Python = unknown;")?;
+    writeln!(writer, "/** Entity mailbox, the address is 'ip:port'. */")?;
+    writeln!(writer, "export interface Mailbox {{ entity_id: number; addr: string; salt: number; }}")?;
+    writeln!(writer)?;
+
+    for ty in model.tys.iter() {
+        match ty.kind() {
+            TyKind::Alias(alias_ty) => {
+                writeln!(writer, "export type {} = {};", ty.name(), ts_ty(alias_ty))?;
+            }
+            TyKind::Dict(ty_dict) => {
+                writeln!(writer, "export interface {} {{", ty.name())?;
+                for prop in &ty_dict.properties {
+                    writeln!(writer, "    {}: {};", ts_field_name(&prop.name), ts_ty(&prop.ty))?;
+                }
+                writeln!(writer, "}}")?;
+            }
+            _ => {}  // Builtins and sequences are inlined.
+        }
+    }
+
+    writeln!(writer)?;
+    writeln!(writer, "export namespace interfaces {{")?;
+    for interface in &model.interfaces {
+        generate_ts_interface(&mut writer, interface, "")?;
+    }
+    writeln!(writer, "}}")?;
+
+    writeln!(writer)?;
+    writeln!(writer, "export namespace entities {{")?;
+    for entity in &model.entities {
+        generate_ts_entity(&mut writer, model, entity)?;
+    }
+    writeln!(writer, "}}")?;
+
+    writer.flush()
+
+}
+
+fn generate_ts_interface(mut writer: impl Write, interface: &Interface, implements_prefix: &str) -> io::Result<()> {
+
+    write!(writer, "    export interface {}", interface.name)?;
+    if !interface.implements.is_empty() {
+        let implements = interface.implements.iter()
+            .map(|name| format!("{implements_prefix}{name}"))
+            .collect::<Vec<_>>();
+        write!(writer, " extends {}", implements.join(", "))?;
+    }
+    writeln!(writer, " {{")?;
+
+    for property in &interface.properties {
+        if is_property_client(property) {
+            writeln!(writer, "        {}: {};", ts_field_name(&property.name), ts_ty(&property.ty))?;
+        }
+    }
+
+    writeln!(writer, "    }}")
+
+}
+
+fn generate_ts_entity(mut writer: impl Write, model: &Model, entity: &Entity) -> io::Result<()> {
+
+    generate_ts_interface(&mut writer, &entity.interface, "interfaces.")?;
+    writeln!(writer, "    export const {}_TYPE_ID = 0x{:02X};", entity.interface.name, entity.id)?;
+
+    for (app_suffix, interface_methods) in APPS {
+
+        write!(writer, "    export type {}_{app_suffix} =", entity.interface.name)?;
+
+        let methods = defs::exposed_methods(model, entity, interface_methods);
+        if methods.is_empty() {
+            writeln!(writer, " never;")?;
+            continue;
+        }
+
+        writeln!(writer)?;
+        for (exposed_id, method) in methods.iter().enumerate() {
+            let args = method.method.args.iter()
+                .map(|arg| ts_ty(&arg.ty))
+                .collect::<Vec<_>>();
+            writeln!(writer, "        | {{ method: \"{}_{}\"; exposed_id: {exposed_id}; args: [{}] }}",
+                method.interface.name, method.method.name, args.join(", "))?;
+        }
+        writeln!(writer, "        ;")?;
+
+    }
+
+    Ok(())
+
+}
+
+fn ts_ty(ty: &Ty) -> String {
+    match ty.kind() {
+        TyKind::Int8 | TyKind::Int16 | TyKind::Int32 | TyKind::Int64 |
+        TyKind::UInt8 | TyKind::UInt16 | TyKind::UInt32 | TyKind::UInt64 |
+        TyKind::Float32 | TyKind::Float64 => "number".to_string(),
+        TyKind::Vector2 => "Vec2".to_string(),
+        TyKind::Vector3 => "Vec3".to_string(),
+        TyKind::Vector4 => "Vec4".to_string(),
+        TyKind::String => "string".to_string(),
+        TyKind::Python => "Python".to_string(),
+        TyKind::Mailbox => "Mailbox".to_string(),
+        TyKind::User(_) => "unknown".to_string(),
+        TyKind::Dict(ty_dict) if ty_dict.allow_none => format!("{} | null", ty.name()),
+        TyKind::Alias(_) |
+        TyKind::Dict(_) => ty.name().to_string(),
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) => format!("Array<{}>", ts_ty(&ty_seq.ty)),
+    }
+}
+
+/// Quote field names that are not valid identifiers.
+fn ts_field_name(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.to_string()
+    } else {
+        format!("{name:?}")
+    }
+}
+
+/// Return true if the property is visible to the client, these are the properties
+/// that are present in the generated Rust code.
+fn is_property_client(property: &Property) -> bool {
+    matches!(property.flags, PropertyFlags::AllClients | PropertyFlags::OwnClient | PropertyFlags::BaseAndClient)
+}
@@ -0,0 +1,248 @@
+//! Loading of the entity definitions from the game's resources, this is shared between
+//! the bootstrap code generator and the runtime interpreter.
+
+use std::cmp::Ordering;
+use std::io;
+
+use indexmap::IndexMap;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use wgtk::res::OverlayFilesystem;
+use wgtk::net::codec::Mailbox;
+use wgtk::pxml;
+
+pub mod parse;
+pub mod model;
+#[cfg(feature = "wot")]
+pub mod runtime;
+
+use model::{Entity, Interface, Method, Model, Ty, TyKind, VariableHeaderSize};
+
+
+/// Load the full model of entity definitions from the given resources filesystem.
+pub fn load(fs: OverlayFilesystem) -> io::Result<Model> {
+
+    let mut model = Model::default();
+
+    println!("== Reading aliases...");
+    let alias_reader = fs.read("scripts/entity_defs/alias.xml")?;
+    let alias_elt = pxml::from_reader(alias_reader).unwrap();
+    parse::parse_aliases(&alias_elt, &mut model.tys);
+
+    println!("== Reading interfaces...");
+    for interface_file in fs.read_dir("scripts/entity_defs/interfaces")? {
+
+        let interface_file = interface_file?;
+        let Some((interface_name, "")) = interface_file.name().split_once(".def") else {
+            continue;
+        };
+
+        println!(" = {interface_name}");
+
+        let interface_reader = fs.read(interface_file.path())?;
+        let interface_elt = pxml::from_reader(interface_reader).unwrap();
+        let interface = parse::parse_interface(&interface_elt, &mut model.tys, interface_name.to_string());
+        model.interfaces.push(interface);
+
+    }
+
+    println!("== Reading components...");
+    match fs.read_dir("scripts/entity_defs/components") {
+        Ok(read_dir) => {
+            for component_file in read_dir {
+
+                let component_file = component_file?;
+                let Some((component_name, "")) = component_file.name().split_once(".def") else {
+                    continue;
+                };
+
+                println!(" = {component_name}");
+
+                let component_reader = fs.read(component_file.path())?;
+                let component_elt = pxml::from_reader(component_reader).unwrap();
+                let component = parse::parse_interface(&component_elt, &mut model.tys, component_name.to_string());
+                model.components.push(component);
+
+            }
+        }
+        // Older clients have no component.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    println!("== Reading entities...");
+    let entities_reader = fs.read("scripts/entities.xml")?;
+    let entities = pxml::from_reader_as::<EntitiesFile, _>(entities_reader)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("invalid entities file: {e}")))?;
+    for (index, entity_name) in entities.client_server.keys().enumerate() {
+
+        println!(" = {entity_name}");
+        let entity_reader = fs.read(format!("scripts/entity_defs/{entity_name}.def"))?;
+        let entity_elt = pxml::from_reader(entity_reader).unwrap();
+        let entity = parse::parse_entity(&entity_elt, &mut model.tys, &mut model.components, index + 1, entity_name.to_string());
+
+        for component in &entity.components {
+            if !model.components.iter().any(|c| c.name == component.ty) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, 
+                    format!("unknown component type of {entity_name}.{}: {}", component.name, component.ty)));
+            }
+        }
+
+        model.entities.push(entity);
+
+    }
+
+    println!("== Types: {}", model.tys.count());
+
+    Ok(model)
+
+}
+
+/// The 'scripts/entities.xml' file, listing entity types in their id order.
+#[derive(Debug, Deserialize)]
+struct EntitiesFile {
+    #[serde(rename = "ClientServerEntities")]
+    client_server: IndexMap<String, IgnoredAny>,
+}
+
+/// An exposed method for the network protocol, this is used to list all exposed
+/// methods on an entity and then compute the methods' exposed ids by sorting them.
+#[derive(Debug)]
+pub struct ExposedMethod<'a> {
+    pub interface: &'a Interface,
+    pub method: &'a Method,
+    pub stream_size: StreamSize,
+}
+
+/// Return all exposed methods of the given entity, for the given app's methods, the
+/// index of each method in the returned vector is its exposed id.
+pub fn exposed_methods<'m>(
+    model: &'m Model,
+    entity: &'m Entity,
+    interface_methods: fn(&Interface) -> &[Method],
+) -> Vec<ExposedMethod<'m>> {
+
+    /// This method recursively register all methods for the entity in order to sort them
+    /// later depending on their arguments' size and then compute there exposed id for
+    /// the network protocol.
+    ///
+    /// IMPORTANT: The initial order of the exposed method is really important because we
+    /// will use a stable sort, and some orders should not be changed.
+    fn add_internal_methods<'m>(
+        exposed_methods: &mut Vec<ExposedMethod<'m>>,
+        model: &'m Model,
+        interface: &'m Interface,
+        interface_methods: fn(&Interface) -> &[Method],
+    ) {
+
+        for interface_name in &interface.implements {
+
+            let interface = model.interfaces.iter()
+                .find(|i| &i.name == interface_name)
+                .expect("unknown implemented interface");
+
+            add_internal_methods(exposed_methods, model, interface, interface_methods);
+
+        }
+
+        for method in interface_methods(interface) {
+            if is_method_exposed(method) {
+                exposed_methods.push(ExposedMethod {
+                    interface,
+                    method,
+                    stream_size: compute_method_stream_size(method),
+                });
+            }
+        }
+
+    }
+
+    let mut methods = Vec::new();
+    add_internal_methods(&mut methods, model, &entity.interface, interface_methods);
+
+    // Methods of components are exposed after the entity's ones, in declaration order.
+    for component in &entity.components {
+
+        let component_ty = model.components.iter()
+            .find(|c| c.name == component.ty)
+            .expect("unknown component type");
+
+        add_internal_methods(&mut methods, model, component_ty, interface_methods);
+
+    }
+
+    // We want to sort fixed methods first and variable last, and then sort between
+    // their configured fixed or variable size.
+    methods.sort_by(|a, b| {
+        match (a.stream_size, b.stream_size) {
+            (StreamSize::Variable(a_size), StreamSize::Variable(b_size)) =>
+                a_size.cmp(&b_size),
+            (StreamSize::Fixed(a_size), StreamSize::Fixed(b_size)) =>
+                a_size.cmp(&b_size),
+            (StreamSize::Fixed(_), StreamSize::Variable(_)) =>
+                Ordering::Less,
+            (StreamSize::Variable(_), StreamSize::Fixed(_)) =>
+                Ordering::Greater,
+        }
+    });
+
+    methods
+
+}
+
+/// Return the stream size of this type, none if the type has no known size.
+pub fn compute_type_stream_size(ty: &Ty) -> Option<usize> {
+    match ty.kind() {
+        TyKind::Int8 | TyKind::UInt8 => Some(1),
+        TyKind::Int16 | TyKind::UInt16 => Some(2),
+        TyKind::Int32 | TyKind::UInt32 => Some(4),
+        TyKind::Int64 | TyKind::UInt64 => Some(8),
+        TyKind::Float32 => Some(4),
+        TyKind::Float64 => Some(8),
+        TyKind::Vector2 => Some(4 * 2),
+        TyKind::Vector3 => Some(4 * 3),
+        TyKind::Vector4 => Some(4 * 4),
+        TyKind::String => None,
+        TyKind::Python => None,
+        TyKind::Mailbox => Some(Mailbox::SIZE),
+        TyKind::User(_) => None,
+        TyKind::Alias(ty) =>
+            compute_type_stream_size(ty),
+        TyKind::Dict(ty_dict) if ty_dict.allow_none => None,
+        TyKind::Dict(ty_dict) =>
+            ty_dict.properties.iter()
+                .map(|prop| compute_type_stream_size(&prop.ty))
+                .sum(),  // Using sum on Option: any None will result in a None.
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) =>
+            ty_seq.size.map(|len| len as usize)
+                .zip(compute_type_stream_size(&ty_seq.ty))
+                .map(|(len, element_size)| len * element_size)
+    }
+}
+
+/// This returns the preferred stream size.
+pub fn compute_method_stream_size(method: &Method) -> StreamSize {
+
+    let size = method.args.iter()
+        .map(|arg| compute_type_stream_size(&arg.ty))
+        .sum::<Option<usize>>();
+
+    match size {
+        Some(size) => StreamSize::Fixed(size),
+        // TODO: Also return this is exposed and has sub msg id?
+        None => StreamSize::Variable(method.variable_header_size)
+    }
+
+}
+
+pub fn is_method_exposed(method: &Method) -> bool {
+    method.exposed_to_all_clients || method.exposed_to_own_client
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StreamSize {
+    Fixed(usize),
+    Variable(VariableHeaderSize),
+}
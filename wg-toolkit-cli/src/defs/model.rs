@@ -137,11 +137,17 @@ pub enum TyKind {
     Dict(TyDict),
     Array(TySeq),
     Tuple(TySeq),
+    /// A custom type streamed by a Python class, given by its 'implementedBy' path, its
+    /// encoding is unknown and must be provided by a Rust type when generating code, or
+    /// by a codec of the [`UserTypes`](super::runtime::UserTypes) registry at runtime.
+    User(String),
 }
 
 #[derive(Debug, Default)]
 pub struct TyDict {
     pub properties: Vec<TyDictProp>,
+    /// True if the dict may be none, it's then prefixed by a presence flag.
+    pub allow_none: bool,
 }
 
 #[derive(Debug)]
@@ -182,6 +188,8 @@ pub struct Model {
     pub tys: TySystem,
     /// The list of all interfaces available.
     pub interfaces: Vec<Interface>,
+    /// The list of all component types available, they are defined like interfaces.
+    pub components: Vec<Interface>,
     /// The list of all entities available.
     pub entities: Vec<Entity>,
 }
@@ -191,6 +199,8 @@ pub struct Model {
 pub struct Entity {
     /// The actual storage for the entity, this has the same properties as an interface.
     pub interface: Interface,
+    /// Components attached to the entity and known by the client, in order.
+    pub components: Vec<Component>,
     /// An optional parent entity to import all properties from.
     #[allow(unused)]  // Not used for generation
     pub parent: Option<String>,
@@ -198,6 +208,15 @@ pub struct Entity {
     pub id: usize,
 }
 
+/// A component attached to an entity, declared in its 'ClientComponents' section.
+#[derive(Debug)]
+pub struct Component {
+    /// Name of the component on the entity.
+    pub name: String,
+    /// Name of the component type, one of the model's components.
+    pub ty: String,
+}
+
 /// Ref: https://github.com/v2v3v4/BigWorld-Engine-14.4.1/blob/main/programming/bigworld/lib/entitydef/entity_description.cpp
 #[derive(Debug)]
 pub struct Interface {
@@ -3,7 +3,7 @@
 use wgtk::pxml::{Element, Value};
 
 use super::model::{
-    Arg, Entity, Interface, Method, Property, PropertyFlags, Ty, TyDict, TyDictProp, TyKind, TySeq, TySystem, VariableHeaderSize
+    Arg, Component, Entity, Interface, Method, Property, PropertyFlags, Ty, TyDict, TyDictProp, TyKind, TySeq, TySystem, VariableHeaderSize
 };
 
 
@@ -59,12 +59,20 @@ pub fn parse_interface(elt: &Element, tys: &mut TySystem, name: String) -> Inter
 
 }
 
-pub fn parse_entity(elt: &Element, tys: &mut TySystem, id: usize, name: String) -> Entity {
+/// Parse an entity, component types defined inline in its 'ClientComponents' section
+/// are added to the given component types.
+pub fn parse_entity(elt: &Element, tys: &mut TySystem, component_tys: &mut Vec<Interface>, id: usize, name: String) -> Entity {
+
+    let mut components = Vec::new();
+    if let Some(Value::Element(components_elt)) = elt.get_child("ClientComponents") {
+        parse_components(&components_elt, &mut *tys, component_tys, &name, &mut components);
+    }
 
     let interface = parse_interface(elt, tys, name);
 
     let entity = Entity {
         interface,
+        components,
         id,
         parent: elt.get_child("Parent").and_then(Value::as_string).map(str::to_string),
     };
@@ -73,6 +81,30 @@ pub fn parse_entity(elt: &Element, tys: &mut TySystem, id: usize, name: String)
 
 }
 
+/// Parse the components of an entity, each component either references a component 
+/// type by its name, or defines it inline with the same sections as an interface, in
+/// which case the type is named after the entity and the component.
+pub fn parse_components(
+    elt: &Element, 
+    tys: &mut TySystem, 
+    component_tys: &mut Vec<Interface>, 
+    entity_name: &str, 
+    components: &mut Vec<Component>,
+) {
+    for (name, val) in elt.iter_children_all() {
+        let ty = match val {
+            Value::String(ty) => ty.trim().to_string(),
+            Value::Element(component_elt) => {
+                let ty = format!("{entity_name}_{name}");
+                component_tys.push(parse_interface(&component_elt, &mut *tys, ty.clone()));
+                ty
+            }
+            _ => panic!("unknown component def: {name}: {val:?}")
+        };
+        components.push(Component { name: name.clone(), ty });
+    }
+}
+
 pub fn parse_properties(elt: &Element, tys: &mut TySystem, properties: &mut Vec<Property>) {
     for (name, val) in elt.iter_children_all() {
         if let Value::Element(property_elt) = val {
@@ -83,7 +115,7 @@ pub fn parse_properties(elt: &Element, tys: &mut TySystem, properties: &mut Vec<
 
 pub fn parse_property(elt: &Element, tys: &mut TySystem, name: String) -> Property {
 
-    let ty_val = elt.get_child("Type").expect("property should contain a type");
+    let ty_val = elt.query_one("Type").unwrap_or_else(|e| panic!("invalid property {name}: {e}"));
     let ty = parse_ty(ty_val, &mut *tys, None);
 
     let flags = elt.get_child("Flags")
@@ -214,15 +246,21 @@ pub fn parse_ty(val: &Value, tys: &mut TySystem, alias_name: Option<String>) ->
         Value::Element(elt)
         if elt.value.as_string() == Some("FIXED_DICT") => {
             
-            let properties_elt = elt.get_child("Properties")
-                .and_then(Value::as_element)
-                .expect("fixed dict should have properties");
-
-            let mut dict = TyDict::default();
+            let properties_elt = elt.query_element("Properties")
+                .unwrap_or_else(|e| panic!("invalid fixed dict: {e}"));
+
+            let mut dict = TyDict {
+                allow_none: elt.get_child("AllowNone")
+                    .and_then(Value::as_boolean)
+                    .unwrap_or_default(),
+                ..TyDict::default()
+            };
             for (field_name, field_val) in properties_elt.iter_children_all() {
 
-                let field_elt = field_val.as_element().expect("field should be an element");
-                let type_val = field_elt.get_child("Type").expect("field should contain a type");
+                let field_elt = field_val.as_element()
+                    .unwrap_or_else(|| panic!("fixed dict field {field_name} should be an element"));
+                let type_val = field_elt.query_one("Type")
+                    .unwrap_or_else(|e| panic!("invalid fixed dict field {field_name}: {e}"));
                 let ty = parse_ty(type_val, tys, None);
 
                 dict.properties.push(TyDictProp {
@@ -235,6 +273,17 @@ pub fn parse_ty(val: &Value, tys: &mut TySystem, alias_name: Option<String>) ->
 
             tys.register(alias_name, TyKind::Dict(dict))
 
+        }
+        Value::Element(elt)
+        if elt.value.as_string() == Some("USER_TYPE") => {
+
+            let implemented_by = elt.query_one("implementedBy")
+                .ok()
+                .and_then(Value::as_string)
+                .unwrap_or_else(|| panic!("invalid user type: missing implementedBy"));
+
+            tys.register(alias_name, TyKind::User(implemented_by.trim().to_string()))
+
         }
         Value::Element(elt) => {
 
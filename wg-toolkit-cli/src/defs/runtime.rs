@@ -0,0 +1,635 @@
+//! Runtime interpreter of the entity definitions, used to decode entities and their
+//! methods dynamically, without the code generated by the bootstrap command.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::fmt;
+
+use indexmap::IndexMap;
+use glam::{Vec2, Vec3, Vec4};
+
+use wgtk::net::codec::{AutoString, Mailbox, Python, SimpleCodec};
+use wgtk::net::element::{Element, ElementIdRange, ElementLength};
+use wgtk::net::app::{base, client};
+use wgtk::util::io::{WgReadExt, WgWriteExt};
+
+use super::model::{Entity, Interface, Method, Model, PropertyFlags, Ty, TyKind, VariableHeaderSize};
+use super::StreamSize;
+
+
+/// Entity definitions prepared for decoding at runtime.
+#[derive(Debug)]
+pub struct RuntimeDefs {
+    /// All entity types, indexed by their type id minus one.
+    entities: Vec<RuntimeEntity>,
+}
+
+impl RuntimeDefs {
+
+    /// Prepare the given model for decoding at runtime, user types (USER_TYPE) are 
+    /// decoded with the codecs of the given registry, looked up by type name.
+    pub fn new(model: &Model, user_types: UserTypes) -> Self {
+        let user_types = Arc::new(user_types);
+        Self {
+            entities: model.entities.iter().map(|entity| {
+
+                let mut properties = Vec::new();
+                add_client_properties(&mut properties, model, &entity.interface);
+
+                let components = entity.components.iter().map(|component| {
+
+                    let component_ty = model.components.iter()
+                        .find(|c| c.name == component.ty)
+                        .expect("unknown component type");
+
+                    let mut properties = Vec::new();
+                    add_client_properties(&mut properties, model, component_ty);
+                    (component.name.clone(), properties)
+
+                }).collect();
+
+                RuntimeEntity {
+                    name: entity.interface.name.clone(),
+                    properties,
+                    components,
+                    client_methods: RuntimeMethods::new(model, entity, |i| &i.client_methods, client::element::id::ENTITY_METHOD, Arc::clone(&user_types)),
+                    base_methods: RuntimeMethods::new(model, entity, |i| &i.base_methods, base::element::id::BASE_ENTITY_METHOD, Arc::clone(&user_types)),
+                    user_types: Arc::clone(&user_types),
+                }
+
+            }).collect(),
+        }
+    }
+
+    /// Get an entity type from its type id, as sent in the network protocol.
+    pub fn entity(&self, type_id: u16) -> Option<&RuntimeEntity> {
+        self.entities.get(type_id.checked_sub(1)? as usize)
+    }
+
+    /// Get an entity type from its name.
+    pub fn entity_by_name(&self, name: &str) -> Option<&RuntimeEntity> {
+        self.entities.iter().find(|entity| entity.name == name)
+    }
+
+}
+
+/// Recursively add all properties sent to the client of the given interface, in the
+/// same order as the generated code.
+fn add_client_properties(properties: &mut Vec<(String, Ty)>, model: &Model, interface: &Interface) {
+
+    for interface_name in &interface.implements {
+
+        let interface = model.interfaces.iter()
+            .find(|i| &i.name == interface_name)
+            .expect("unknown implemented interface");
+
+        add_client_properties(&mut *properties, model, interface);
+
+    }
+
+    for property in &interface.properties {
+        if matches!(property.flags, PropertyFlags::AllClients | PropertyFlags::OwnClient | PropertyFlags::BaseAndClient) {
+            properties.push((property.name.clone(), property.ty.clone()));
+        }
+    }
+
+}
+
+/// An entity type prepared for decoding at runtime.
+#[derive(Debug)]
+pub struct RuntimeEntity {
+    /// Name of the entity type.
+    pub name: String,
+    /// All properties sent to the client when creating the entity, in order.
+    pub properties: Vec<(String, Ty)>,
+    /// All components with their properties sent to the client, after the entity's
+    /// properties, in order.
+    pub components: Vec<(String, Vec<(String, Ty)>)>,
+    /// Methods called on the client.
+    pub client_methods: RuntimeMethods,
+    /// Methods called on the base app.
+    pub base_methods: RuntimeMethods,
+    /// Codecs of user types.
+    user_types: Arc<UserTypes>,
+}
+
+/// All exposed methods of an entity type for a given app, this is used as the config
+/// for decoding [`RuntimeMethodCall`] elements.
+#[derive(Debug)]
+pub struct RuntimeMethods {
+    /// The element ids of the methods, the first one being the method with exposed id
+    /// zero.
+    ids: ElementIdRange,
+    /// All exposed methods, indexed by their exposed id.
+    methods: Vec<RuntimeMethod>,
+    /// Codecs of user types.
+    user_types: Arc<UserTypes>,
+}
+
+impl RuntimeMethods {
+
+    fn new(model: &Model, entity: &Entity, interface_methods: fn(&Interface) -> &[Method], ids: ElementIdRange, user_types: Arc<UserTypes>) -> Self {
+        Self {
+            ids,
+            user_types,
+            methods: super::exposed_methods(model, entity, interface_methods).into_iter()
+                .map(|exposed| RuntimeMethod {
+                    name: format!("{}::{}", exposed.interface.name, exposed.method.name),
+                    args: exposed.method.args.iter().map(|arg| arg.ty.clone()).collect(),
+                    length: match exposed.stream_size {
+                        StreamSize::Fixed(len) => ElementLength::Fixed(len as u32),
+                        StreamSize::Variable(VariableHeaderSize::Variable8) => ElementLength::Variable8,
+                        StreamSize::Variable(VariableHeaderSize::Variable16) => ElementLength::Variable16,
+                        StreamSize::Variable(VariableHeaderSize::Variable24) => ElementLength::Variable24,
+                        StreamSize::Variable(VariableHeaderSize::Variable32) => ElementLength::Variable32,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    /// Get a method from its exposed id.
+    pub fn get(&self, exposed_id: u16) -> Option<&RuntimeMethod> {
+        self.methods.get(exposed_id as usize)
+    }
+
+    /// Find a method from its full name, prefixed by its interface name, returning its
+    /// exposed id.
+    pub fn find(&self, name: &str) -> Option<(u16, &RuntimeMethod)> {
+        self.methods.iter()
+            .position(|method| method.name == name)
+            .map(|exposed_id| (exposed_id as u16, &self.methods[exposed_id]))
+    }
+
+    /// Iterate over all methods, in order of exposed id.
+    pub fn iter(&self) -> impl Iterator<Item = &RuntimeMethod> + '_ {
+        self.methods.iter()
+    }
+
+    /// Get the element id of a method from its exposed id, like the generated code this
+    /// doesn't support sub-ids.
+    fn element_id(&self, exposed_id: u16) -> io::Result<u8> {
+        if exposed_id >= self.ids.slots_count() as u16 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("method exposed id requires a sub-id: {exposed_id}")));
+        }
+        Ok(self.ids.first + exposed_id as u8)
+    }
+
+    /// Get a method from the element id it is called with.
+    fn get_by_element_id(&self, id: u8) -> io::Result<(u16, &RuntimeMethod)> {
+        id.checked_sub(self.ids.first)
+            .and_then(|exposed_id| Some((exposed_id as u16, self.get(exposed_id as u16)?)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid method element id: 0x{id:02X}")))
+    }
+
+}
+
+/// An exposed method prepared for decoding at runtime.
+#[derive(Debug)]
+pub struct RuntimeMethod {
+    /// Full name of the method, prefixed by its interface name.
+    pub name: String,
+    /// Type of all arguments.
+    pub args: Vec<Ty>,
+    /// Length of the method element.
+    pub length: ElementLength,
+}
+
+/// A value decoded at runtime from its type.
+#[derive(Debug)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    String(AutoString),
+    Python(Python),
+    Mailbox(Mailbox),
+    Seq(Vec<Value>),
+    Dict(IndexMap<String, Value>),
+    /// Raw bytes, such as user types streamed as a blob, see [`UserTypeCodec::BLOB`].
+    Blob(Vec<u8>),
+    /// The none value of a dict that allows none.
+    None,
+}
+
+/// A registry of codecs for user types (USER_TYPE), which are streamed by a Python 
+/// class and therefore have no encoding known from the definitions.
+#[derive(Debug, Default, Clone)]
+pub struct UserTypes {
+    /// Codecs by user type name.
+    codecs: HashMap<String, UserTypeCodec>,
+}
+
+impl UserTypes {
+
+    /// Create an empty registry, where no user type can be decoded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the codec of the user type with the given name, as defined in the 
+    /// aliases, replacing any previous one.
+    pub fn register(&mut self, name: impl Into<String>, codec: UserTypeCodec) {
+        self.codecs.insert(name.into(), codec);
+    }
+
+    /// Get the codec of the user type with the given name.
+    pub fn get(&self, name: &str) -> Option<&UserTypeCodec> {
+        self.codecs.get(name)
+    }
+
+    /// Internal function to get the codec of the given user type or return an error.
+    fn codec(&self, ty: &Ty, implemented_by: &str) -> io::Result<&UserTypeCodec> {
+        self.get(ty.name())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, format!("user type {} implemented by {implemented_by} has no registered codec", ty.name())))
+    }
+
+}
+
+/// The codec of a user type, reading and writing its values.
+#[derive(Clone, Copy)]
+pub struct UserTypeCodec {
+    /// Read a value of the user type.
+    pub read: fn(&mut dyn Read) -> io::Result<Value>,
+    /// Write a value of the user type, an error should be returned if the value is
+    /// not of the expected variant.
+    pub write: fn(&mut dyn Write, &Value) -> io::Result<()>,
+}
+
+impl UserTypeCodec {
+
+    /// A user type streamed as a variable-length blob, read as [`Value::Blob`].
+    pub const BLOB: Self = Self {
+        read: |read| Ok(Value::Blob(read.read_blob_variable()?)),
+        write: |write, value| match value {
+            Value::Blob(data) => write.write_blob_variable(data),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("value {value:?} is not a blob"))),
+        },
+    };
+
+    /// A user type streamed as a pickled Python value, read as [`Value::Python`].
+    pub const PYTHON: Self = Self {
+        read: |read| Ok(Value::Python(Python::read(read)?)),
+        write: |write, value| match value {
+            Value::Python(python) => python.write(write),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("value {value:?} is not a Python value"))),
+        },
+    };
+
+    /// A user type streamed as a string, read as [`Value::String`].
+    pub const STRING: Self = Self {
+        read: |read| Ok(Value::String(AutoString::read(read)?)),
+        write: |write, value| match value {
+            Value::String(s) => s.write(write),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("value {value:?} is not a string"))),
+        },
+    };
+
+    /// Get one of the builtin codecs from its name: 'blob', 'python' or 'string'.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "blob" => Self::BLOB,
+            "python" => Self::PYTHON,
+            "string" => Self::STRING,
+            _ => return None,
+        })
+    }
+
+}
+
+impl fmt::Debug for UserTypeCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserTypeCodec").finish_non_exhaustive()
+    }
+}
+
+/// Read a value of the given type, user types are read with their codec.
+pub fn read_value(read: &mut dyn Read, ty: &Ty, user_types: &UserTypes) -> io::Result<Value> {
+    Ok(match ty.kind() {
+        TyKind::Int8 => Value::Int(read.read_i8()? as i64),
+        TyKind::Int16 => Value::Int(read.read_i16()? as i64),
+        TyKind::Int32 => Value::Int(read.read_i32()? as i64),
+        TyKind::Int64 => Value::Int(read.read_i64()?),
+        TyKind::UInt8 => Value::UInt(read.read_u8()? as u64),
+        TyKind::UInt16 => Value::UInt(read.read_u16()? as u64),
+        TyKind::UInt32 => Value::UInt(read.read_u32()? as u64),
+        TyKind::UInt64 => Value::UInt(read.read_u64()?),
+        TyKind::Float32 => Value::Float(read.read_f32()? as f64),
+        TyKind::Float64 => Value::Float(read.read_f64()?),
+        TyKind::Vector2 => Value::Vec2(read.read_vec2()?),
+        TyKind::Vector3 => Value::Vec3(read.read_vec3()?),
+        TyKind::Vector4 => Value::Vec4(read.read_vec4()?),
+        TyKind::String => Value::String(AutoString::read(read)?),
+        TyKind::Python => Value::Python(Python::read(read)?),
+        TyKind::Mailbox => Value::Mailbox(Mailbox::read(read)?),
+        TyKind::User(implemented_by) => (user_types.codec(ty, implemented_by)?.read)(read)?,
+        TyKind::Alias(ty) => return read_value(read, ty, user_types),
+        TyKind::Dict(ty_dict) if ty_dict.allow_none && !read.read_bool()? => Value::None,
+        TyKind::Dict(ty_dict) => {
+            let mut dict = IndexMap::with_capacity(ty_dict.properties.len());
+            for prop in &ty_dict.properties {
+                dict.insert(prop.name.clone(), read_value(&mut *read, &prop.ty, user_types)?);
+            }
+            Value::Dict(dict)
+        }
+        TyKind::Array(ty_seq) |
+        TyKind::Tuple(ty_seq) => {
+            let len = match ty_seq.size {
+                Some(size) => size as usize,
+                None => read.read_packed_u24()? as usize,
+            };
+            let mut seq = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                seq.push(read_value(&mut *read, &ty_seq.ty, user_types)?);
+            }
+            Value::Seq(seq)
+        }
+    })
+}
+
+/// Write a value of the given type, the value must match the type, user types are
+/// written with their codec.
+pub fn write_value(write: &mut dyn Write, ty: &Ty, value: &Value, user_types: &UserTypes) -> io::Result<()> {
+
+    let mismatch = || io::Error::new(io::ErrorKind::InvalidData, format!("value {value:?} doesn't match type {:?}", ty.kind()));
+
+    match (ty.kind(), value) {
+        (TyKind::Int8, &Value::Int(n)) => write.write_i8(i8::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::Int16, &Value::Int(n)) => write.write_i16(i16::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::Int32, &Value::Int(n)) => write.write_i32(i32::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::Int64, &Value::Int(n)) => write.write_i64(n),
+        (TyKind::UInt8, &Value::UInt(n)) => write.write_u8(u8::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::UInt16, &Value::UInt(n)) => write.write_u16(u16::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::UInt32, &Value::UInt(n)) => write.write_u32(u32::try_from(n).map_err(|_| mismatch())?),
+        (TyKind::UInt64, &Value::UInt(n)) => write.write_u64(n),
+        (TyKind::Float32, &Value::Float(n)) => write.write_f32(n as f32),
+        (TyKind::Float64, &Value::Float(n)) => write.write_f64(n),
+        (TyKind::Vector2, &Value::Vec2(v)) => write.write_vec2(v),
+        (TyKind::Vector3, &Value::Vec3(v)) => write.write_vec3(v),
+        (TyKind::Vector4, &Value::Vec4(v)) => write.write_vec4(v),
+        (TyKind::String, Value::String(s)) => s.write(write),
+        (TyKind::Python, Value::Python(p)) => p.write(write),
+        (TyKind::Mailbox, Value::Mailbox(m)) => m.write(write),
+        (TyKind::User(implemented_by), value) => (user_types.codec(ty, implemented_by)?.write)(write, value),
+        (TyKind::Alias(ty), value) => write_value(write, ty, value, user_types),
+        (TyKind::Dict(ty_dict), Value::None) if ty_dict.allow_none => write.write_bool(false),
+        (TyKind::Dict(ty_dict), Value::Dict(dict)) => {
+            if ty_dict.allow_none {
+                write.write_bool(true)?;
+            }
+            for prop in &ty_dict.properties {
+                let value = dict.get(&prop.name)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("missing dict property: {}", prop.name)))?;
+                write_value(&mut *write, &prop.ty, value, user_types)?;
+            }
+            Ok(())
+        }
+        (TyKind::Array(ty_seq) | TyKind::Tuple(ty_seq), Value::Seq(seq)) => {
+            match ty_seq.size {
+                Some(size) if size as usize != seq.len() => return Err(mismatch()),
+                Some(_) => {}
+                None => write.write_packed_u24(seq.len() as u32)?,
+            }
+            for value in seq {
+                write_value(&mut *write, &ty_seq.ty, value, user_types)?;
+            }
+            Ok(())
+        }
+        _ => Err(mismatch()),
+    }
+
+}
+
+/// A method call decoded or encoded at runtime, for both client and base entity 
+/// methods, the exposed methods of the entity are given as config.
+#[derive(Debug)]
+pub struct RuntimeMethodCall {
+    /// The exposed id of the method.
+    pub exposed_id: u16,
+    /// Arguments of the method.
+    pub args: Vec<Value>,
+}
+
+impl Element<RuntimeMethods> for RuntimeMethodCall {
+
+    fn write_length(&self, config: &RuntimeMethods) -> io::Result<ElementLength> {
+        config.get(self.exposed_id)
+            .map(|method| method.length)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid method exposed id: {}", self.exposed_id)))
+    }
+
+    fn write(&self, write: &mut dyn Write, config: &RuntimeMethods) -> io::Result<u8> {
+
+        let method = config.get(self.exposed_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid method exposed id: {}", self.exposed_id)))?;
+
+        if method.args.len() != self.args.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("expected {} arguments, got {}", method.args.len(), self.args.len())));
+        }
+
+        for (ty, value) in method.args.iter().zip(&self.args) {
+            write_value(&mut *write, ty, value, &config.user_types)?;
+        }
+
+        config.element_id(self.exposed_id)
+
+    }
+
+    fn read_length(config: &RuntimeMethods, id: u8) -> io::Result<ElementLength> {
+        config.get_by_element_id(id).map(|(_, method)| method.length)
+    }
+
+    fn read(read: &mut dyn Read, config: &RuntimeMethods, _len: usize, id: u8) -> io::Result<Self> {
+        let (exposed_id, method) = config.get_by_element_id(id)?;
+        Ok(Self {
+            exposed_id,
+            args: method.args.iter()
+                .map(|ty| read_value(&mut *read, ty, &config.user_types))
+                .collect::<io::Result<_>>()?,
+        })
+    }
+
+}
+
+/// A property update decoded or encoded at runtime, the entity type of the updated
+/// entity is given as config.
+#[derive(Debug)]
+pub struct RuntimePropertyUpdate {
+    /// The exposed index of the property, in the entity's client properties.
+    pub exposed_id: u16,
+    /// New value of the property.
+    pub value: Value,
+}
+
+impl RuntimeEntity {
+
+    /// Get the exposed index, name and type of a client property from the element id
+    /// of its update.
+    fn property_by_element_id(&self, id: u8) -> io::Result<(u16, &(String, Ty))> {
+        id.checked_sub(client::element::id::ENTITY_PROPERTY.first)
+            .filter(|_| client::element::id::ENTITY_PROPERTY.contains(id))
+            .and_then(|exposed_id| Some((exposed_id as u16, self.properties.get(exposed_id as usize)?)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid property element id: 0x{id:02X}")))
+    }
+
+}
+
+/// Return the element length of a property update with the given type.
+fn property_length(ty: &Ty) -> ElementLength {
+    match super::compute_type_stream_size(ty) {
+        Some(size) => ElementLength::Fixed(size as u32),
+        None => ElementLength::Variable16,
+    }
+}
+
+impl Element<RuntimeEntity> for RuntimePropertyUpdate {
+
+    fn write_length(&self, config: &RuntimeEntity) -> io::Result<ElementLength> {
+        config.properties.get(self.exposed_id as usize)
+            .map(|(_, ty)| property_length(ty))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid property exposed id: {}", self.exposed_id)))
+    }
+
+    fn write(&self, write: &mut dyn Write, config: &RuntimeEntity) -> io::Result<u8> {
+
+        let (_, ty) = config.properties.get(self.exposed_id as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid property exposed id: {}", self.exposed_id)))?;
+
+        if self.exposed_id >= client::element::id::ENTITY_PROPERTY.slots_count() as u16 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("property exposed id requires a sub-id: {}", self.exposed_id)));
+        }
+
+        write_value(write, ty, &self.value, &config.user_types)?;
+        Ok(client::element::id::ENTITY_PROPERTY.first + self.exposed_id as u8)
+
+    }
+
+    fn read_length(config: &RuntimeEntity, id: u8) -> io::Result<ElementLength> {
+        config.property_by_element_id(id).map(|(_, (_, ty))| property_length(ty))
+    }
+
+    fn read(read: &mut dyn Read, config: &RuntimeEntity, _len: usize, id: u8) -> io::Result<Self> {
+        let (exposed_id, (_, ty)) = config.property_by_element_id(id)?;
+        Ok(Self {
+            exposed_id,
+            value: read_value(read, ty, &config.user_types)?,
+        })
+    }
+
+}
+
+/// The creation of the base player decoded at runtime, the entity type is given as
+/// config.
+#[derive(Debug)]
+pub struct RuntimeCreateBasePlayer {
+    /// The unique identifier of the entity being created.
+    pub entity_id: u32,
+    /// The entity type id.
+    pub entity_type_id: u16,
+    /// All properties of the entity.
+    pub properties: IndexMap<String, Value>,
+    /// The number of entity components.
+    pub entity_components_count: u8,
+    /// Properties of each entity component, in order.
+    pub components: IndexMap<String, IndexMap<String, Value>>,
+}
+
+impl Element<RuntimeEntity> for RuntimeCreateBasePlayer {
+
+    fn write_length(&self, config: &RuntimeEntity) -> io::Result<ElementLength> {
+        let _ = config;
+        Err(io::Error::new(io::ErrorKind::Unsupported, "runtime create base player write not supported"))
+    }
+
+    fn write(&self, write: &mut dyn Write, config: &RuntimeEntity) -> io::Result<u8> {
+        let _ = (write, config);
+        Err(io::Error::new(io::ErrorKind::Unsupported, "runtime create base player write not supported"))
+    }
+
+    fn read_length(_config: &RuntimeEntity, _id: u8) -> io::Result<ElementLength> {
+        Ok(ElementLength::Variable16)
+    }
+
+    fn read(read: &mut dyn Read, config: &RuntimeEntity, _len: usize, _id: u8) -> io::Result<Self> {
+
+        let entity_id = read.read_u32()?;
+        let entity_type_id = read.read_u16()?;
+        let _unk = read.read_blob_variable()?;
+
+        let mut properties = IndexMap::with_capacity(config.properties.len());
+        for (name, ty) in &config.properties {
+            properties.insert(name.clone(), read_value(&mut *read, ty, &config.user_types)?);
+        }
+
+        // Components unknown to the definitions are left undecoded.
+        let entity_components_count = read.read_u8()?;
+        let known_count = config.components.len().min(entity_components_count as usize);
+
+        let mut components = IndexMap::with_capacity(known_count);
+        for (component_name, component_properties) in &config.components[..known_count] {
+            let mut properties = IndexMap::with_capacity(component_properties.len());
+            for (name, ty) in component_properties {
+                properties.insert(name.clone(), read_value(&mut *read, ty, &config.user_types)?);
+            }
+            components.insert(component_name.clone(), properties);
+        }
+
+        Ok(Self {
+            entity_id,
+            entity_type_id,
+            properties,
+            entity_components_count,
+            components,
+        })
+
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use super::super::model::{TySeq, TySystem};
+    use super::*;
+
+    #[test]
+    fn user_types() {
+
+        let mut tys = TySystem::default();
+        let user_ty = tys.register(Some("VEHICLE_DESCR".to_string()), TyKind::User("items.VehicleDescr".to_string()));
+        let array_ty = tys.register(None, TyKind::Array(TySeq { ty: user_ty.clone(), size: None }));
+
+        let mut data = Vec::new();
+        data.write_packed_u24(2).unwrap();
+        data.write_blob_variable(b"first").unwrap();
+        data.write_blob_variable(b"second").unwrap();
+
+        // User types can't be read without a codec.
+        let err = read_value(&mut Cursor::new(&data), &array_ty, &UserTypes::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        let mut user_types = UserTypes::new();
+        user_types.register("VEHICLE_DESCR", UserTypeCodec::BLOB);
+
+        let value = read_value(&mut Cursor::new(&data), &array_ty, &user_types).unwrap();
+        let Value::Seq(seq) = &value else { panic!("expected a sequence: {value:?}") };
+        assert!(matches!(&seq[..], [Value::Blob(first), Value::Blob(second)] if first == b"first" && second == b"second"));
+
+        let mut written = Vec::new();
+        write_value(&mut written, &array_ty, &value, &user_types).unwrap();
+        assert_eq!(written, data);
+
+        // The value must match the codec.
+        let err = write_value(&mut Vec::new(), &user_ty, &Value::Int(0), &user_types).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    }
+
+}
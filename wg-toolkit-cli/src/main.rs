@@ -5,11 +5,14 @@ use std::net::SocketAddrV4;
 use std::process::ExitCode;
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 mod pxml;
 mod res;
 
+#[cfg(any(feature = "bootstrap", feature = "wot"))]
+mod defs;
+
 #[cfg(feature = "bootstrap")]
 mod bootstrap;
 
@@ -61,7 +64,10 @@ pub enum Command {
 /// (.xml), a packed XML file can be replaced by a clear XML file and will work the
 /// same.
 #[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct PackedXmlArgs {
+    #[command(subcommand)]
+    pub cmd: Option<PackedXmlCommand>,
     /// If specified, the packed XML is read from a file instead of stdin (fd 0).
     /// 
     /// This is essentially the same as piping 'cat' of the packed XML file into it. 
@@ -88,6 +94,53 @@ pub struct PackedXmlArgs {
     pub filter: Option<String>,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum PackedXmlCommand {
+    Diff(PackedXmlDiffArgs),
+    Unpack(PackedXmlUnpackArgs),
+    Pack(PackedXmlPackArgs),
+}
+
+/// Structurally compare two packed XML files.
+/// 
+/// Each added, removed or changed value is printed on its own line, prefixed by '+',
+/// '-' or '~' respectively, and with the path of the value using the same syntax as
+/// filters. Children with the same name are matched by their order of appearance.
+#[derive(Debug, Args)]
+pub struct PackedXmlDiffArgs {
+    /// Path to the old packed XML file.
+    pub old: PathBuf,
+    /// Path to the new packed XML file.
+    pub new: PathBuf,
+}
+
+/// Convert a packed XML file to a clear XML file.
+/// 
+/// The clear XML file can be edited and then packed again, or directly used in place
+/// of the packed XML file, the root tag is the file name.
+#[derive(Debug, Args)]
+pub struct PackedXmlUnpackArgs {
+    /// Path to the packed XML file.
+    pub input: PathBuf,
+    /// Path to the clear XML file to write, printed to stdout if not specified.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Convert a clear XML file to a packed XML file.
+/// 
+/// The type of each value is inferred from its text: 'true' or 'false' are booleans,
+/// numbers without fractional part are integers, whitespace-separated numbers with
+/// a fractional part are vectors, and anything else is a string.
+#[derive(Debug, Args)]
+pub struct PackedXmlPackArgs {
+    /// Path to the clear XML file.
+    pub input: PathBuf,
+    /// Path to the packed XML file to write, written to stdout if not specified.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
 /// Game resources virtual filesystem access (readonly).
 /// 
 /// The game resources are split in many directories under the game's resources (res/)
@@ -97,7 +150,8 @@ pub struct PackedXmlArgs {
 /// the same time.
 #[derive(Debug, Args)]
 pub struct ResArgs {
-    /// Path to the game's resource (res/) directory.
+    /// Path to the game's resource (res/) directory, or to the game's directory in
+    /// order to mount directories and packages listed in its 'paths.xml' file.
     pub dir: PathBuf,
     #[command(subcommand)]
     pub cmd: ResCommand
@@ -105,11 +159,17 @@ pub struct ResArgs {
 
 #[derive(Debug, Subcommand)]
 pub enum ResCommand {
+    #[command(visible_alias = "cat")]
     Read(ResReadArgs),
     #[command(name = "ls")]
     List(ResListArgs),
     #[command(name = "cp")]
     Copy(ResCopyArgs),
+    Extract(ResExtractArgs),
+    Manifest(ResManifestArgs),
+    Verify(ResVerifyArgs),
+    #[cfg(feature = "sqlite")]
+    Index(ResIndexArgs),
     #[cfg(feature = "dokan")]
     Dokan(ResDokanArgs),
 }
@@ -119,10 +179,16 @@ pub enum ResCommand {
 /// Like 'ls', this command may take some time to complete depending on where the file is
 /// located in packages, this command return as soon as possible so you may be lucky if
 /// the file is located in first opened packages.
+/// 
+/// Packed XML files are detected and printed as clear XML, unless raw output is
+/// requested.
 #[derive(Debug, Args)]
 pub struct ResReadArgs {
     /// Path to the file to read, no leading separator!
     pub path: String,
+    /// Print the file's content as-is, without converting packed XML.
+    #[arg(short, long)]
+    pub raw: bool,
 }
 
 /// List directory contents with optional recursion.
@@ -141,6 +207,10 @@ pub struct ResListArgs {
     /// recursion, for example '1' will show children of all root directories.
     #[arg(short, long)]
     pub recurse: Option<Option<u16>>,
+    /// Also print the file name of the package storing each file, or a dash for
+    /// files of native directories.
+    #[arg(short, long)]
+    pub package: bool,
 }
 
 /// Copy files and directories from resources.
@@ -160,6 +230,73 @@ pub struct ResCopyArgs {
     pub dest: PathBuf,
 }
 
+/// Extract all files matching a glob pattern to a native directory.
+/// 
+/// Files are extracted in parallel, keeping their path relative to the resources root
+/// in the destination directory. The pattern is matched against the full path of each
+/// file, '*' matches any characters within a path segment, '**' matches any number of
+/// segments and '?' matches exactly one character, for example 'scripts/**/*.def'.
+#[derive(Debug, Args)]
+pub struct ResExtractArgs {
+    /// Glob pattern of the files to extract, no leading separator!
+    pub pattern: String,
+    /// Destination directory, in your native filesystem, created if missing.
+    pub dest: PathBuf,
+    /// Number of files extracted in parallel, by default one per logical CPU.
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+    /// Convert packed XML files to clear XML files while extracting them.
+    #[arg(long)]
+    pub unpack_xml: bool,
+}
+
+/// Produce a manifest of all files with their size and hashes.
+/// 
+/// Every file under the given directory is listed on a line, sorted by path, with its
+/// size and CRC32, and optionally its SHA-256, separated by tabulations. This stable
+/// format can be used to fingerprint game installs and mod packages and to compare 
+/// them across machines with regular diff tools.
+#[derive(Debug, Args)]
+pub struct ResManifestArgs {
+    /// Path to the directory to list, no leading separator (empty for root)!
+    #[arg(default_value = "")]
+    pub path: String,
+    /// Also compute the SHA-256 of each file, this is slower.
+    #[arg(long)]
+    pub sha256: bool,
+}
+
+/// Verify the integrity of all packages.
+/// 
+/// Every file of every mounted package is fully read, and its size and CRC32 are 
+/// checked against the package's central directory. Each corrupted file is printed
+/// on a line with its package and the reason, separated by tabulations, packages that
+/// cannot be opened are printed with a dash as file name. The command fails if any
+/// corruption is found.
+#[derive(Debug, Args)]
+pub struct ResVerifyArgs {
+    /// Number of packages verified in parallel, by default one per logical CPU.
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
+/// Export the listing of all files into a SQLite database.
+/// 
+/// Every file under the given directory is inserted in the 'files' table with its
+/// directory, name, size and CRC32, and where it's stored: its native path, or its
+/// package (see the 'packages' table), name within the package and offsets of its 
+/// local header and data. This allows analyzing game content with SQL queries or
+/// external tools. The CRC32 of native files is computed, so they are fully read.
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Args)]
+pub struct ResIndexArgs {
+    /// Path to the database to write, an existing file is replaced.
+    pub output: PathBuf,
+    /// Path to the directory to index, no leading separator (empty for root)!
+    #[arg(long, default_value = "")]
+    pub path: String,
+}
+
 /// Start a Dokan (filesystem in userspace) that will make the virtual resource filesystem
 /// accessible as a regular filesystem to the OS, to later mounted.
 #[derive(Debug, Args)]
@@ -181,6 +318,8 @@ pub struct ResDokanArgs {
 /// 
 #[derive(Debug, Args)]
 pub struct WotArgs {
+    #[command(subcommand)]
+    pub cmd: Option<WotCommand>,
     /// The address where the login app should be bound.
     #[arg(long, default_value = "127.0.0.1:20016")]
     pub login_app: SocketAddrV4,
@@ -199,10 +338,287 @@ pub struct WotArgs {
     /// will keep this virtual client socket and the blowfish key and transfer it to the
     /// base app proxy, so that it will be able to continue spying on the communication
     /// without altering packets.
+    /// 
+    /// While running, an injection console reads commands from stdin, allowing raw
+    /// elements or entity method calls to be injected toward either side, type 'help'
+    /// for the list of commands.
     #[arg(long)]
     pub real_login_app: Option<SocketAddrV4>,
     #[arg(long, requires = "real_login_app")]
     pub real_pub_key_path: Option<PathBuf>,
+    /// The protocol version of login requests, which varies across game releases and
+    /// between the Wargaming and Lesta clients.
+    /// 
+    /// When emulating, login requests with another version are rejected. When 
+    /// proxying, the version of forwarded login requests is replaced by this one, the
+    /// version of each client is still detected and logged on login success.
+    #[arg(long)]
+    pub login_protocol: Option<u32>,
+    /// The digest of the entity definitions expected in login requests, hex-encoded.
+    /// 
+    /// When emulating, login requests with another digest are rejected. When proxying,
+    /// a mismatching digest is reported but the request is still forwarded as-is.
+    #[arg(long)]
+    pub login_digest: Option<String>,
+    /// The protocol variant spoken by the clients, when they don't use the PC layout.
+    /// 
+    /// Either "pc", "console", or a comma-separated list of flag remappings 
+    /// "flag:<pc>=<variant>" and element id shifts "ids:<first>-<last>:<shift>", for
+    /// example: "flag:0x0008=0x0800,ids:0x80-0x8F:+2".
+    #[arg(long)]
+    pub protocol_variant: Option<String>,
+    /// Number of base app proxy workers sharing the base app port.
+    /// 
+    /// When greater than one, the workers are bound using the SO_REUSEPORT socket
+    /// option (unix only) and each worker runs in its own thread, each client being
+    /// consistently routed to the same worker.
+    #[arg(long, default_value_t = 1, requires = "real_login_app")]
+    pub base_app_workers: usize,
+    /// Number of worker threads decoding and dumping the exchanged elements.
+    /// 
+    /// Decoding is done outside of the base app proxy threads so that it doesn't delay
+    /// forwarded packets, all bundles of a given client are decoded by the same worker
+    /// in order to preserve their ordering.
+    #[arg(long, default_value_t = 1, requires = "real_login_app")]
+    pub decode_workers: usize,
+    /// Enable the profiler of decoded elements.
+    /// 
+    /// The profiler records the decoding time and payload size of each element id
+    /// and entity method, a report of the slowest decoders is periodically logged by
+    /// each decode worker, and at the end of a capture.
+    #[arg(long)]
+    pub profile: bool,
+    /// Interval, in seconds, between two profiler reports.
+    #[arg(long, default_value_t = 60, requires = "profile")]
+    pub profile_interval: u64,
+    /// Interval, in seconds, between two reports of memory usage metrics.
+    /// 
+    /// When enabled, each base app proxy worker reports the number of peers and the
+    /// buffered state of its protocol trackers (fragments, reliable packets, acks),
+    /// and each decode worker reports its number of entities and partial resources,
+    /// this can be used to detect leaks in long-running proxies.
+    #[arg(long, requires = "real_login_app")]
+    pub metrics_interval: Option<u64>,
+    /// Number of recent packets kept for each peer, zero to disable.
+    /// 
+    /// When an element fails to decode, the recent packets of the peer are dumped,
+    /// with their header and a hexdump, into the dump directory, so that intermittent
+    /// decode failures can be diagnosed afterward.
+    #[arg(long, default_value_t = 32)]
+    pub packet_ring: usize,
+    /// Decode entities using the definitions of the given resources directory.
+    /// 
+    /// The entity definitions are loaded and interpreted at runtime instead of using
+    /// the code generated by the bootstrap command, this is slower but allows using
+    /// the proxy with a game version that has not been bootstrapped.
+    #[arg(long)]
+    pub entity_defs: Option<PathBuf>,
+    /// Decode a user type (USER_TYPE) of the entity definitions with a builtin codec,
+    /// given as NAME=CODEC, where the codec is 'blob', 'python' or 'string'.
+    /// 
+    /// User types are streamed by Python classes, so their encoding isn't known from
+    /// the definitions, entities and methods with a user type that has no codec can't
+    /// be decoded.
+    #[arg(long, requires = "entity_defs")]
+    pub user_type: Vec<String>,
+    /// Capture decoded elements as binary fixtures into the given directory.
+    /// 
+    /// The first occurrence of each distinct element (base player creation of each
+    /// entity type and each entity method) is saved as a versioned binary fixture, 
+    /// and a test module is generated alongside, round-tripping each fixture through
+    /// the generated decoders, this builds a regression corpus from real traffic.
+    /// This requires the generated code and can't be used with runtime definitions.
+    #[arg(long, conflicts_with = "entity_defs")]
+    pub fixtures: Option<PathBuf>,
+    /// Spill large resources being downloaded into the given directory.
+    /// 
+    /// Resources streamed by the server (such as sync payloads) are kept in memory 
+    /// until completed, with this option, those larger than 1 MiB are instead written
+    /// to disk, and they can be resumed if the proxy is restarted in the middle of 
+    /// the transfer. Unlike the dump directory, this directory is kept across runs.
+    #[arg(long)]
+    pub resource_spill_dir: Option<PathBuf>,
+    /// Record the decrypted packets of all clients into the given capture file.
+    /// 
+    /// The packets of each completed bundle, exchanged with the login app or the base
+    /// app in both directions, are recorded with their time, app, client address and
+    /// direction, so that the session can be analyzed again later, offline, with any
+    /// decoder of the network module.
+    #[arg(long, requires = "real_login_app", conflicts_with = "pcap")]
+    pub record: Option<PathBuf>,
+    /// Dump the decoded elements into the given SQLite database, in addition to the
+    /// dump directory.
+    /// 
+    /// Created entities, entity methods in both directions, property updates and
+    /// completed resources are inserted in their own table, each row with its time 
+    /// since the start of the dump, client address and direction, so that large 
+    /// sessions can be queried afterward. An existing database is replaced.
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    pub dump_db: Option<PathBuf>,
+    /// Dump the decrypted login parameters of each client into the dump directory.
+    /// 
+    /// This requires the private key, so that the login request can be decrypted, 
+    /// the password, blowfish key and digest are redacted unless secrets are also
+    /// requested to be dumped.
+    #[arg(long, requires_all = ["priv_key_path", "real_login_app"])]
+    pub dump_login_params: bool,
+    /// Also dump the secrets of the login parameters, without redaction.
+    #[arg(long, requires = "dump_login_params")]
+    pub dump_login_secrets: bool,
+    /// Decode a pcap or pcapng capture offline instead of running any application.
+    /// 
+    /// The capture is decoded using the same logic as the base app proxy, producing 
+    /// the same dumps as a live session, no socket is opened. This requires the 
+    /// session blowfish key and the address of the real base app in the capture.
+    #[arg(long, requires_all = ["pcap_blowfish_key", "pcap_base_app"])]
+    pub pcap: Option<PathBuf>,
+    /// The session blowfish key, hex-encoded, used to decrypt the capture.
+    #[arg(long, requires = "pcap")]
+    pub pcap_blowfish_key: Option<String>,
+    /// The address of the real base app in the capture, all datagrams exchanged on
+    /// its port are decoded.
+    #[arg(long, requires = "pcap")]
+    pub pcap_base_app: Option<SocketAddrV4>,
+    /// Emit the log as a JSON stream, one event per line, instead of text.
+    /// 
+    /// Each decoded entity method, property and created entity is an event with its
+    /// client address, direction, entity id and type, method or property name and
+    /// decoded value as separate fields, so the output can be filtered with tools 
+    /// such as jq, or ingested in a log pipeline.
+    #[arg(long)]
+    pub json: bool,
+    /// Run the proxy with an interactive terminal interface.
+    /// 
+    /// The interface shows the proxied peers, the entities of the selected peer and
+    /// a scrolling pane of the decoded entity methods and properties, which can be
+    /// filtered by entity or by entity type and method name. It replaces the 
+    /// injection console, and the log is written to 'proxy.log' instead.
+    #[arg(long, requires = "real_login_app", conflicts_with_all = ["pcap", "json"])]
+    pub tui: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WotCommand {
+    Dissect(WotDissectArgs),
+    ReplayPcap(WotReplayPcapArgs),
+    ReplayAnonymize(WotReplayAnonymizeArgs),
+    ReplayStats(WotReplayStatsArgs),
+    ReplayCapture(WotReplayCaptureArgs),
+    Ping(WotPingArgs),
+}
+
+/// Interactively dissect the packets of a raw capture.
+/// 
+/// The pcap or pcapng capture is loaded and presented as a tree of packets, their
+/// header and footer fields, and the elements of the bundles they complete with their
+/// decoded fields. The bytes of the selected node are highlighted in a hex pane, this
+/// is intended for byte-level exploration of the protocol.
+#[derive(Debug, Args)]
+pub struct WotDissectArgs {
+    /// Path to the pcap or pcapng capture.
+    pub file: PathBuf,
+    /// The address of the real base app in the capture, used to known the direction
+    /// of each packet, other packets are ignored.
+    #[arg(long)]
+    pub base_app: SocketAddrV4,
+    /// The session blowfish key, hex-encoded, used to decrypt the packets, packets
+    /// are assumed to be in clear if not provided.
+    #[arg(long)]
+    pub blowfish_key: Option<String>,
+}
+
+/// Convert the packet stream of a replay into a pcapng capture.
+/// 
+/// Each packet of the replay is written as an UDP datagram from the server to the
+/// client, with its replay header (length, type and clock) kept at the start of the
+/// payload. The capture time of each datagram is the clock of the packet, relative
+/// to the UNIX epoch.
+#[derive(Debug, Args)]
+pub struct WotReplayPcapArgs {
+    /// Path to the replay (.wotreplay).
+    pub file: PathBuf,
+    /// Path to the pcapng capture to write.
+    pub output: PathBuf,
+    /// The synthetic address of the server, source of all datagrams.
+    #[arg(long, default_value = "127.0.0.1:20017")]
+    pub server: SocketAddrV4,
+    /// The synthetic address of the client, destination of all datagrams.
+    #[arg(long, default_value = "127.0.0.1:20018")]
+    pub client: SocketAddrV4,
+}
+
+/// Pseudonymize the accounts of a replay, so that it can be shared.
+/// 
+/// Player names are replaced by pseudonyms of the same length, both in the JSON 
+/// blocks and in the packet stream, account and clan identifiers are replaced in the
+/// JSON blocks and clan tags are removed. The packet stream keeps its layout, a name
+/// is only replaced where it's prefixed by its length, as it's encoded in packets.
+#[derive(Debug, Args)]
+pub struct WotReplayAnonymizeArgs {
+    /// Path to the replay (.wotreplay).
+    pub file: PathBuf,
+    /// Path to the anonymized replay to write.
+    pub output: PathBuf,
+}
+
+/// Export statistics of all replays of a directory to CSV files.
+/// 
+/// Replays are processed in parallel and the following files are written to the
+/// output directory: 'battles.csv' with one row per replay, 'players.csv' with one
+/// row per vehicle, 'methods.csv' with the number of calls of each method for each
+/// entity and 'positions.csv' with all position updates of each vehicle, if enabled.
+/// Methods are identified by their exposed id, which can be resolved with the entity
+/// definitions of the game version.
+#[derive(Debug, Args)]
+pub struct WotReplayStatsArgs {
+    /// Path to the directory containing the replays (.wotreplay), not recursive.
+    pub dir: PathBuf,
+    /// Path to the output directory, created if needed.
+    pub output: PathBuf,
+    /// Also export all position updates, this produces large files.
+    #[arg(long)]
+    pub positions: bool,
+}
+
+/// Decode again a capture of decrypted packets recorded by the proxy.
+/// 
+/// The base app packets of the capture, as recorded with '--record', are reassembled
+/// into bundles and decoded offline with the same pipeline as the base app proxy,
+/// entity types are resolved with the generated entity definitions, or with the
+/// runtime ones if '--entity-defs' is given. Decoded methods and properties are logged.
+#[derive(Debug, Args)]
+pub struct WotReplayCaptureArgs {
+    /// Path to the capture recorded by the proxy.
+    pub file: PathBuf,
+    /// Emit the log as a JSON stream, one event per line, instead of text.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Ping login applications and report their latency and loss.
+/// 
+/// Each target is pinged in turn with the ping element of the login app, which is 
+/// how the client measures the latency of each periphery. This is a quick health 
+/// check of a server, or of a running proxy, that doesn't require logging in.
+#[derive(Debug, Args)]
+pub struct WotPingArgs {
+    /// The login apps to ping, either a socket address (host and port) or the name of
+    /// a periphery of the scripts config, all peripheries are pinged if none is given.
+    pub targets: Vec<String>,
+    /// Path to the game's scripts config (res/scripts_config.xml), its login hosts are
+    /// used to resolve peripheries by name (or short name).
+    #[arg(long)]
+    pub scripts_config: Option<PathBuf>,
+    /// Number of pings sent to each target.
+    #[arg(short, long, default_value_t = 4)]
+    pub count: u32,
+    /// Interval, in milliseconds, between two pings.
+    #[arg(long, default_value_t = 1000)]
+    pub interval: u64,
+    /// Time, in milliseconds, to wait for each reply before considering it lost.
+    #[arg(long, default_value_t = 2000)]
+    pub timeout: u64,
 }
 
 /// Internal developer command used for updating the code of wg-toolkit automatically
@@ -213,11 +629,90 @@ pub struct WotArgs {
 /// 
 ///   $ cargo run --no-default-features --features cli-bootstrap -- bootstrap D:/Games/WoT/res ./wg-toolkit-cli/src/wot/gen/
 #[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct BootstrapArgs {
+    #[command(subcommand)]
+    pub cmd: Option<BootstrapCommand>,
     /// Path to the game's resource (res/) directory.
-    pub dir: PathBuf,
+    #[arg(required = true)]
+    pub dir: Option<PathBuf>,
     /// Destination source code directory where all files will be generated.
-    pub dest: PathBuf,
+    #[arg(required = true)]
+    pub dest: Option<PathBuf>,
+    /// Loose resource directories overlaid on top of the game's resources, such as
+    /// unpacked mods, by decreasing precedence when repeated. Overlaying decompiled
+    /// scripts providing 'scripts/common/constants.py' also generates its constants.
+    #[arg(long)]
+    pub overlay: Vec<PathBuf>,
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = BootstrapFormat::Rust)]
+    pub format: BootstrapFormat,
+    /// Generate a distinct structure for each method's arguments, by default methods
+    /// with the exact same arguments share a single structure through type aliases,
+    /// which reduces compile time but makes the generated code harder to read.
+    #[arg(long)]
+    pub no_dedup_args: bool,
+    /// Also generate, for each entity and app, an async handler trait with one method
+    /// per entity method, and a dispatch method on the decoded methods' enumeration,
+    /// intended for async services built on top of the generated code.
+    #[arg(long)]
+    pub async_handlers: bool,
+    /// Also generate, for each entity and app, a handler trait with one method per
+    /// entity method taking each of its arguments, and a dispatch method on the decoded
+    /// methods' enumeration calling the handler method of the decoded variant.
+    #[arg(long, conflicts_with = "async_handlers")]
+    pub handlers: bool,
+    /// Also derive serde's Serialize and Deserialize on all generated types, so that
+    /// decoded data can be converted to and from JSON, the generated code then requires
+    /// the 'codec-serde' feature of wg-toolkit. Note that serde only supports fixed
+    /// arrays of up to 32 elements.
+    #[arg(long)]
+    pub serde: bool,
+    /// A TOML file of patches renaming or retyping method arguments and properties,
+    /// applied on top of the builtin patches, and mapping user types (USER_TYPE) to
+    /// Rust types, which is required to generate Rust code if the model has any.
+    #[arg(long)]
+    pub patches: Option<PathBuf>,
+    /// Also write the parsed model as JSON to the given file, with all types,
+    /// interfaces, components and entities, and the exposed ids and stream sizes of
+    /// entities' methods, a variable stream size is given by its header size in bytes.
+    #[arg(long)]
+    pub emit_model: Option<PathBuf>,
+    /// Don't write anything, only compare the generated Rust code with the files of the
+    /// destination directory, and exit with an error listing the files that differ,
+    /// this can be used to detect protocol changes against a pinned game version.
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BootstrapCommand {
+    Diff(BootstrapDiffArgs),
+}
+
+/// Compare the models of two game versions, reporting added, removed and changed types,
+/// interfaces, components, entities, properties and methods, and the shifts of entity
+/// type ids and methods' exposed ids.
+#[derive(Debug, Args)]
+pub struct BootstrapDiffArgs {
+    /// Path to the old game's resource (res/) directory.
+    pub old_dir: PathBuf,
+    /// Path to the new game's resource (res/) directory.
+    pub new_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BootstrapFormat {
+    /// Rust source code of the generated module, only files that changed are written
+    /// and other files of the destination directory are removed.
+    Rust,
+    /// Markdown documentation of all entities, interfaces and types, cross-linked.
+    Markdown,
+    /// JSON Schema definitions of all types, interfaces, entities and their methods.
+    JsonSchema,
+    /// TypeScript declarations of all types, interfaces, entities and their methods.
+    #[value(name = "typescript")]
+    TypeScript,
 }
 
 /// Type alias for a result that simply returns a string on error, this will be output
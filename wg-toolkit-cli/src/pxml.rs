@@ -1,14 +1,23 @@
 use std::collections::{hash_map, HashMap};
 use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
 use std::fs::File;
 
 use wgtk::pxml::{self, Element, Value};
+use wgtk::util::BytesFmt;
 
-use super::{CliResult, PackedXmlArgs};
+use super::{CliResult, PackedXmlArgs, PackedXmlCommand, PackedXmlDiffArgs, PackedXmlPackArgs, PackedXmlUnpackArgs};
 
 
 pub fn cmd_pxml(args: PackedXmlArgs) -> CliResult<()> {
 
+    match args.cmd {
+        Some(PackedXmlCommand::Diff(args)) => return cmd_pxml_diff(args),
+        Some(PackedXmlCommand::Unpack(args)) => return cmd_pxml_unpack(args),
+        Some(PackedXmlCommand::Pack(args)) => return cmd_pxml_pack(args),
+        None => {}
+    }
+
     let mut root_xml_tag = "root".to_string();
     let mut root_elt = match args.file {
         Some(path) => {
@@ -19,11 +28,7 @@ pub fn cmd_pxml(args: PackedXmlArgs) -> CliResult<()> {
                 }
             }
 
-            let file = File::open(&path)
-                .map_err(|e| format!("Failed to open file at {path:?}: {e}"))?;
-
-            pxml::from_reader(file)
-                .map_err(|e| format!("Failed to read Packed XML file at {path:?}: {e}"))?
+            read_file(&path)?
 
         }
         None => {
@@ -44,10 +49,7 @@ pub fn cmd_pxml(args: PackedXmlArgs) -> CliResult<()> {
 
     if args.raw {
         
-        let mut buf = Vec::new();
-        pxml::to_writer(Cursor::new(&mut buf), &root_elt)
-            .map_err(|e| format!("Failed to write Packed XML to buffer: {e}"))?;
-
+        let buf = pxml::to_bytes(&root_elt);
         io::stdout().write_all(&buf)
             .map_err(|e| format!("Failed to write Packed XML buffer to stdout: {e}"))?;
 
@@ -55,26 +57,194 @@ pub fn cmd_pxml(args: PackedXmlArgs) -> CliResult<()> {
 
     }
     
-    let mut indent = String::new();
+    let mut output = io::stdout().lock();
 
     if args.xml {
-        println!("<{root_xml_tag}>");
-        indent.push_str("  ");
+        pxml::to_xml_writer(&mut output, &root_elt, &root_xml_tag)
+            .map_err(|e| format!("Failed to write XML to stdout: {e}"))?;
+    } else {
+        let mut indent = String::new();
+        print_element(&mut output, &root_elt, &mut indent, false)
+            .and_then(|()| writeln!(output)) // Because 'print_element' don't print a line feed.
+            .map_err(|e| format!("Failed to write element to stdout: {e}"))?;
     }
 
-    // Print the whole root element.
-    print_element(&root_elt, &mut indent, false, args.xml);
+    Ok(())
 
-    if args.xml {
-        println!("</{root_xml_tag}>");
-    } else {
-        println!(); // Because 'print_element' don't print a line feed.
-    }
+}
+
+fn cmd_pxml_diff(args: PackedXmlDiffArgs) -> CliResult<()> {
+
+    let old_elt = read_file(&args.old)?;
+    let new_elt = read_file(&args.new)?;
+
+    let mut path = String::new();
+    diff_element(&old_elt, &new_elt, &mut path);
 
     Ok(())
 
 }
 
+fn cmd_pxml_unpack(args: PackedXmlUnpackArgs) -> CliResult<()> {
+
+    let element = read_file(&args.input)?;
+
+    let root_tag = args.input.file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or("root");
+
+    let xml = pxml::to_xml_string(&element, root_tag);
+    write_output(args.output.as_deref(), xml.as_bytes())
+
+}
+
+fn cmd_pxml_pack(args: PackedXmlPackArgs) -> CliResult<()> {
+
+    let path = &args.input;
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open file at {path:?}: {e}"))?;
+
+    let element = pxml::from_xml_reader(file)
+        .map_err(|e| format!("Failed to read XML file at {path:?}: {e}"))?;
+
+    write_output(args.output.as_deref(), &pxml::to_bytes(&element))
+
+}
+
+/// Write the given data to the output file, or to stdout if not specified.
+fn write_output(path: Option<&Path>, data: &[u8]) -> CliResult<()> {
+    match path {
+        Some(path) => std::fs::write(path, data)
+            .map_err(|e| format!("Failed to write file at {path:?}: {e}")),
+        None => io::stdout().write_all(data)
+            .map_err(|e| format!("Failed to write to stdout: {e}")),
+    }
+}
+
+fn read_file(path: &Path) -> CliResult<Box<Element>> {
+
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open file at {path:?}: {e}"))?;
+
+    pxml::from_reader(file)
+        .map_err(|e| format!("Failed to read Packed XML file at {path:?}: {e}"))
+
+}
+
+/// Print the differences between two elements, the given path is the one of the 
+/// compared elements and is restored when returning.
+fn diff_element(old: &Element, new: &Element, path: &mut String) {
+
+    // Empty string proper values are not printed, like when printing elements.
+    fn proper_value(element: &Element) -> Option<&Value> {
+        match &element.value {
+            Value::String(s) if s.is_empty() => None,
+            value => Some(value),
+        }
+    }
+
+    let rollback_len = path.len();
+    push_path(path, "^", 0);
+    diff_value(proper_value(old), proper_value(new), path);
+    path.truncate(rollback_len);
+
+    // Compute the ordered list of keys, keys of the old element first.
+    let mut keys = Vec::new();
+    for (key, _) in old.iter_children_all().chain(new.iter_children_all()) {
+        if !keys.contains(&key.as_str()) {
+            keys.push(key.as_str());
+        }
+    }
+
+    for key in keys {
+
+        let mut old_children = old.iter_children(key);
+        let mut new_children = new.iter_children(key);
+
+        for index in 0.. {
+
+            let old_child = old_children.next();
+            let new_child = new_children.next();
+            if old_child.is_none() && new_child.is_none() {
+                break;
+            }
+
+            push_path(path, key, index);
+            diff_value(old_child, new_child, path);
+            path.truncate(rollback_len);
+
+        }
+
+    }
+
+}
+
+/// Print the differences between two optional values at the given path, a value is
+/// absent if it has been added or removed.
+fn diff_value(old: Option<&Value>, new: Option<&Value>, path: &mut String) {
+
+    // Missing elements are compared against an empty element, so that each of their
+    // children is printed as added or removed.
+    let empty = Element::new();
+
+    match (old, new) {
+        (Some(Value::Element(old)), Some(Value::Element(new))) => diff_element(old, new, path),
+        (Some(Value::Element(old)), None) => diff_element(old, &empty, path),
+        (None, Some(Value::Element(new))) => diff_element(&empty, new, path),
+        (Some(old), Some(new)) if old == new => {}
+        (Some(Value::Element(old)), Some(new)) => {
+            diff_element(old, &empty, path);
+            diff_value(None, Some(new), path);
+        }
+        (Some(old), Some(Value::Element(new))) => {
+            diff_value(Some(old), None, path);
+            diff_element(&empty, new, path);
+        }
+        (Some(old), Some(new)) => println!("~ {path}: {} -> {}", ValueFmt(old), ValueFmt(new)),
+        (Some(old), None) => println!("- {path}: {}", ValueFmt(old)),
+        (None, Some(new)) => println!("+ {path}: {}", ValueFmt(new)),
+        (None, None) => {}
+    }
+
+}
+
+/// Push a child key to the given path, with its index if not the first child.
+fn push_path(path: &mut String, key: &str, index: usize) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(key);
+    if index != 0 {
+        path.push_str(&format!("[{index}]"));
+    }
+}
+
+/// Inline formatting of a non-element value.
+struct ValueFmt<'a>(&'a Value);
+
+impl std::fmt::Display for ValueFmt<'_> {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Value::Element(_) => f.write_str("<element>"),
+            Value::String(s) => write!(f, "{s:?}"),
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Vector(v) => {
+                for (i, comp) in v.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str("/")?;
+                    }
+                    write!(f, "{comp}")?;
+                }
+                Ok(())
+            }
+            Value::Raw(raw) => write!(f, "<raw type {}: {:X}>", raw.data_type, BytesFmt(&raw.data)),
+        }
+    }
+
+}
+
 fn apply_filter(element: &mut Element, filter: &str) -> CliResult<()> {
 
     let mut context = FilterContext::new(element);
@@ -139,7 +309,7 @@ fn apply_filter(element: &mut Element, filter: &str) -> CliResult<()> {
 
 /// Print an element and its children, children are printed
 /// prefixed with the given indent. No terminal line feed.
-fn print_element(element: &Element, indent: &mut String, new_line: bool, xml: bool) {
+fn print_element(output: &mut impl Write, element: &Element, indent: &mut String, new_line: bool) -> io::Result<()> {
 
     match &element.value {
         // If the value is an empty string, just do not print the value
@@ -148,89 +318,60 @@ fn print_element(element: &Element, indent: &mut String, new_line: bool, xml: bo
             // Incrementing indent is not really needed because the proper value 
             // should not be another element, but it can theoretically happen.
             indent.push_str("  ");
-            print_value(val, indent, xml);
+            print_value(&mut *output, val, indent)?;
             indent.truncate(indent.len() - 2);
         }
     }
     
     if new_line {
-        println!();
+        writeln!(output)?;
     }
 
     let rollback_len = indent.len();
     for (i, (child_key, child_value)) in element.iter_children_all().enumerate() {
         
-        if xml {
-            print!("{indent}<{child_key}>")
-        } else {
-            if i > 0 {
-                println!();
-            }
-            print!("{indent}{child_key}: ");
+        if i > 0 {
+            writeln!(output)?;
         }
+        write!(output, "{indent}{child_key}: ")?;
 
         indent.push_str("  ");
-        print_value(child_value, &mut *indent, xml);
+        print_value(&mut *output, child_value, &mut *indent)?;
         indent.truncate(rollback_len);
 
-        if xml {
-            println!("</{child_key}>");
-        }
-
     }
 
+    Ok(())
+
 }
 
 /// Print a Packed XML value inline -no terminal line feed-.
-fn print_value(value: &Value, indent: &mut String, xml: bool) {
-
-    let element = matches!(value, Value::Element(_));
-
-    if xml && !element {
-        print!("\t");
-    }
+fn print_value(output: &mut impl Write, value: &Value, indent: &mut String) -> io::Result<()> {
 
     match value {
-        Value::Element(element) => {
-            print_element(&element, indent, true, xml);
-            if xml {
-                print!("{}", &indent[..indent.len() - 2]);
-            }
-        }
-        Value::String(s) => {
-            if xml {
-                print!("{s}");
-            } else {
-                print!("{s:?}");
-            }
-        }
-        &Value::Integer(n) => print!("{n}"),
-        &Value::Boolean(b) => print!("{b}"),
+        Value::Element(element) => print_element(&mut *output, &element, indent, true)?,
+        Value::String(s) => write!(output, "{s:?}")?,
+        &Value::Integer(n) => write!(output, "{n}")?,
+        &Value::Boolean(b) => write!(output, "{b}")?,
         Value::Vector(v) => {
-            if v.len() == 12 && !xml {
-                // TODO: Support XML repr!
-                println!();
-                println!("{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[0], v[3], v[6], v[9]);
-                println!("{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[1], v[4], v[7], v[10]);
-                println!("{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[2], v[5], v[8], v[11]);
+            if v.len() == 12 {
+                writeln!(output)?;
+                writeln!(output, "{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[0], v[3], v[6], v[9])?;
+                writeln!(output, "{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[1], v[4], v[7], v[10])?;
+                writeln!(output, "{indent}| {:.02} | {:.02} | {:.02} | {:.02} |", v[2], v[5], v[8], v[11])?;
             } else {
                 for (i, &comp) in v.iter().enumerate() {
                     if i != 0 {
-                        if xml {
-                            print!(" ");
-                        } else {
-                            print!("/");
-                        }
+                        write!(output, "/")?;
                     }
-                    print!("{comp:.1}");
+                    write!(output, "{comp:.1}")?;
                 }
             }
         }
+        Value::Raw(raw) => write!(output, "<raw type {}: {:X}>", raw.data_type, BytesFmt(&raw.data))?,
     }
 
-    if xml && !element {
-        print!("\t");
-    }
+    Ok(())
 
 }
 
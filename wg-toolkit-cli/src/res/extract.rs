@@ -0,0 +1,266 @@
+//! Extraction of resource files matching a glob pattern to a native directory.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::io::{self, BufWriter, Read, Write};
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+
+use rayon::prelude::*;
+
+use wgtk::res::ResFilesystem;
+use wgtk::util::SizeFmt;
+use wgtk::pxml;
+
+use crate::{CliOptions, CliResult, ResExtractArgs};
+
+
+/// Width of the progress bar, in characters.
+const PROGRESS_WIDTH: usize = 30;
+
+
+pub(super) fn cmd_res_extract(opts: CliOptions, args: ResExtractArgs, fs: &ResFilesystem) -> CliResult<()> {
+
+    /// A file to extract, with its size.
+    struct ExtractFile {
+        path: String,
+        size: u64,
+    }
+
+    // Internal function to recursively collect all files matching the pattern.
+    fn collect_dir(fs: &ResFilesystem, dir_path: &str, pattern: &[&str], human: bool, files: &mut Vec<ExtractFile>) -> CliResult<()> {
+
+        let read_dir = fs.read_dir(dir_path)
+            .map_err(|e| format!("Failed to read directory '{dir_path}', reason: {e}"))?;
+
+        for entry in read_dir {
+
+            let entry = entry.map_err(|e| format!("Failed to read entry, reason: {e}"))?;
+            let entry_path = entry.path();
+
+            if entry.stat().is_dir() {
+                collect_dir(fs, &entry_path, pattern, human, files)?;
+                continue;
+            }
+
+            let segments = entry_path.split('/').collect::<Vec<_>>();
+            if glob_match(pattern, &segments) {
+
+                files.push(ExtractFile { path: entry_path, size: entry.stat().size() });
+
+                if human {
+                    eprint!("\r{} files found...", files.len());
+                }
+
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    let pattern = args.pattern.trim_matches('/');
+    let pattern_segments = pattern.split('/').collect::<Vec<_>>();
+
+    // Only walk the directory of the pattern's literal prefix.
+    let base_len = pattern_segments.iter()
+        .take(pattern_segments.len() - 1)
+        .take_while(|segment| !segment.contains(['*', '?']))
+        .count();
+
+    let base_path = pattern_segments[..base_len].join("/");
+
+    let mut files = Vec::new();
+
+    // The pattern may be the literal path to a single file.
+    if !pattern.contains(['*', '?']) {
+        if let Ok(stat) = fs.stat(pattern) {
+            if !stat.is_dir() {
+                files.push(ExtractFile { path: pattern.to_string(), size: stat.size() });
+            }
+        }
+    }
+
+    if files.is_empty() {
+        collect_dir(fs, &base_path, &pattern_segments, opts.human, &mut files)?;
+    }
+
+    if opts.human {
+        eprintln!("\r{} files found   ", files.len());
+    }
+
+    fs::create_dir_all(&args.dest)
+        .map_err(|e| format!("Failed to create destination directory {:?}, reason: {e}", args.dest))?;
+
+    let total_count = files.len();
+    let total_size = files.iter().map(|file| file.size).sum::<u64>();
+    let done_count = AtomicUsize::new(0);
+    let done_size = AtomicU64::new(0);
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build()
+        .map_err(|e| format!("Failed to create extraction workers, reason: {e}"))?;
+
+    pool.install(|| {
+        files.par_iter().try_for_each(|file| {
+
+            let dest_path = extract_dest_path(&args.dest, &file.path)?;
+            extract_file(fs, &file.path, &dest_path, args.unpack_xml)?;
+
+            let count = done_count.fetch_add(1, Ordering::Relaxed) + 1;
+            let size = done_size.fetch_add(file.size, Ordering::Relaxed) + file.size;
+
+            if opts.human {
+                let progress = if total_size == 0 { 1.0 } else { size as f64 / total_size as f64 };
+                let filled = ((progress * PROGRESS_WIDTH as f64) as usize).min(PROGRESS_WIDTH);
+                eprint!("\r[{}{}] {count}/{total_count} files, {}/{}   ",
+                    "#".repeat(filled), ".".repeat(PROGRESS_WIDTH - filled),
+                    SizeFmt(size), SizeFmt(total_size));
+            }
+
+            Ok::<_, String>(())
+
+        })
+    })?;
+
+    if opts.human {
+        eprintln!();
+    }
+
+    Ok(())
+
+}
+
+/// Resolve the native destination path of a resource file, the resource path is
+/// rejected if it contains any component that could escape the destination directory,
+/// such as parent directory, root directory or prefix components.
+fn extract_dest_path(dest: &Path, path: &str) -> CliResult<PathBuf> {
+
+    let mut dest_path = dest.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(name) => dest_path.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) =>
+                return Err(format!("Refusing to extract file '{path}' outside of destination directory")),
+        }
+    }
+
+    Ok(dest_path)
+
+}
+
+/// Extract a single file to the given destination path, creating its parent
+/// directories, packed XML files are converted to clear XML if requested.
+fn extract_file(fs: &ResFilesystem, path: &str, dest_path: &Path, unpack_xml: bool) -> CliResult<()> {
+
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {parent:?}, reason: {e}"))?;
+    }
+
+    let mut read_file = fs.read(path)
+        .map_err(|e| format!("Failed to read file '{path}', reason: {e}"))?;
+
+    let dest_file = File::create(dest_path)
+        .map_err(|e| format!("Failed to create file at {dest_path:?}, reason: {e}"))?;
+
+    let mut dest_file = BufWriter::new(dest_file);
+
+    if unpack_xml && path.ends_with(".xml") {
+
+        let mut data = Vec::new();
+        read_file.read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read file '{path}', reason: {e}"))?;
+
+        // Clear XML files are extracted as-is.
+        if data.starts_with(pxml::MAGIC) {
+
+            let element = pxml::from_bytes(&data)
+                .map_err(|e| format!("Failed to read Packed XML file '{path}', reason: {e}"))?;
+
+            let file_name = path.rsplit_once('/').map(|(_, s)| s).unwrap_or(path);
+            pxml::to_xml_writer(&mut dest_file, &element, file_name)
+                .map_err(|e| format!("Failed to write XML file at {dest_path:?}, reason: {e}"))?;
+
+        } else {
+            dest_file.write_all(&data)
+                .map_err(|e| format!("Failed to write file at {dest_path:?}, reason: {e}"))?;
+        }
+
+    } else {
+        io::copy(&mut read_file, &mut dest_file)
+            .map_err(|e| format!("Failed to extract file '{path}' to {dest_path:?}, reason: {e}"))?;
+    }
+
+    dest_file.flush()
+        .map_err(|e| format!("Failed to write file at {dest_path:?}, reason: {e}"))
+
+}
+
+/// Match path segments against glob pattern segments, a '**' segment matches any
+/// number of segments, '*' matches any characters within a segment and '?' matches
+/// exactly one character.
+fn glob_match(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=segments.len()).any(|skip| glob_match(rest, &segments[skip..]))
+        }
+        Some((first, rest)) => {
+            match segments.split_first() {
+                Some((segment, segments)) =>
+                    glob_match_segment(first.as_bytes(), segment.as_bytes()) && glob_match(rest, segments),
+                None => false,
+            }
+        }
+    }
+}
+
+/// Match a single path segment against a glob pattern segment.
+fn glob_match_segment(pattern: &[u8], segment: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => segment.is_empty(),
+        Some((b'*', rest)) => {
+            (0..=segment.len()).any(|skip| glob_match_segment(rest, &segment[skip..]))
+        }
+        Some((&c, rest)) => {
+            match segment.split_first() {
+                Some((&s, segment)) => (c == b'?' || c == s) && glob_match_segment(rest, segment),
+                None => false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn dest_path() {
+        let dest = Path::new("out");
+        assert_eq!(extract_dest_path(dest, "scripts/item_defs/vehicles.xml").unwrap(),
+            dest.join("scripts").join("item_defs").join("vehicles.xml"));
+        assert_eq!(extract_dest_path(dest, "./gui/flash.swf").unwrap(), dest.join("gui").join("flash.swf"));
+    }
+
+    #[test]
+    fn dest_path_slip() {
+        let dest = Path::new("out");
+        assert!(extract_dest_path(dest, "../../etc/passwd").is_err());
+        assert!(extract_dest_path(dest, "scripts/../../escape.xml").is_err());
+        assert!(extract_dest_path(dest, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match(&["scripts", "**", "*.xml"], &["scripts", "item_defs", "vehicles", "list.xml"]));
+        assert!(glob_match(&["scripts", "**", "*.xml"], &["scripts", "list.xml"]));
+        assert!(!glob_match(&["scripts", "*.xml"], &["scripts", "item_defs", "list.xml"]));
+        assert!(glob_match(&["gui", "?.swf"], &["gui", "a.swf"]));
+    }
+
+}
@@ -0,0 +1,199 @@
+//! Export of the resource filesystem listing into a SQLite database.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::io::Read;
+use std::fs;
+
+use rusqlite::{params, Connection};
+
+use wgtk::res::{ResFilesystem, ResLocation};
+
+use crate::{CliOptions, CliResult, ResIndexArgs};
+
+
+/// Schema of the database, packages are referenced by files.
+const SCHEMA: &str = "
+CREATE TABLE packages (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE
+);
+CREATE TABLE files (
+    path TEXT PRIMARY KEY,
+    dir TEXT NOT NULL,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    crc32 INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    native_path TEXT,
+    package_id INTEGER REFERENCES packages(id),
+    package_name TEXT,
+    header_offset INTEGER,
+    data_offset INTEGER
+);
+CREATE INDEX files_dir ON files(dir);
+CREATE INDEX files_package ON files(package_id);
+";
+
+
+pub(super) fn cmd_res_index(opts: CliOptions, args: ResIndexArgs, fs: &ResFilesystem) -> CliResult<()> {
+
+    /// State of the indexing, files are inserted in the transaction's connection.
+    struct Indexer<'a> {
+        tx: &'a Connection,
+        package_ids: HashMap<PathBuf, i64>,
+        count: usize,
+        human: bool,
+    }
+
+    impl Indexer<'_> {
+
+        fn index_dir(&mut self, fs: &ResFilesystem, dir_path: &str) -> CliResult<()> {
+
+            let read_dir = fs.read_dir(dir_path)
+                .map_err(|e| format!("Failed to read directory '{dir_path}', reason: {e}"))?;
+
+            for entry in read_dir {
+
+                let entry = entry.map_err(|e| format!("Failed to read entry, reason: {e}"))?;
+                let entry_path = entry.path();
+
+                if entry.stat().is_dir() {
+                    self.index_dir(fs, &entry_path)?;
+                } else {
+                    self.index_file(fs, dir_path, entry.name(), &entry_path, entry.stat().size())?;
+                }
+
+            }
+
+            Ok(())
+
+        }
+
+        fn index_file(&mut self, fs: &ResFilesystem, dir_path: &str, name: &str, path: &str, size: u64) -> CliResult<()> {
+
+            if self.human {
+                eprint!("\r{} files...", self.count);
+            }
+
+            let location = fs.locate(path)
+                .map_err(|e| format!("Failed to locate file '{path}', reason: {e}"))?;
+
+            let source;
+            let mut native_path = None;
+            let mut package_id = None;
+            let mut package_name = None;
+            let mut header_offset = None;
+            let mut data_offset = None;
+            let crc32;
+
+            // Native files have no declared CRC32, so it's computed.
+            match location {
+                ResLocation::Native { path: location_path } => {
+                    source = "native";
+                    native_path = Some(location_path.display().to_string());
+                    crc32 = compute_crc32(fs, path)?;
+                }
+                ResLocation::Dvpl { path: location_path } => {
+                    source = "dvpl";
+                    native_path = Some(location_path.display().to_string());
+                    crc32 = compute_crc32(fs, path)?;
+                }
+                ResLocation::Package { path: package_path, name, header_offset: header, data_offset: data, crc32: package_crc32 } => {
+                    source = "package";
+                    package_id = Some(self.package_id(package_path)?);
+                    package_name = Some(name);
+                    header_offset = Some(header as i64);
+                    data_offset = Some(data as i64);
+                    crc32 = package_crc32;
+                }
+            }
+
+            self.tx.prepare_cached("INSERT INTO files VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)")
+                .and_then(|mut stmt| stmt.execute(params![
+                    path, dir_path, name, size as i64, crc32 as i64, source,
+                    native_path, package_id, package_name, header_offset, data_offset,
+                ]))
+                .map_err(|e| format!("Failed to insert file '{path}', reason: {e}"))?;
+
+            self.count += 1;
+            Ok(())
+
+        }
+
+        /// Get the row id of the given package, inserting it if needed.
+        fn package_id(&mut self, package_path: PathBuf) -> CliResult<i64> {
+
+            if let Some(&id) = self.package_ids.get(&package_path) {
+                return Ok(id);
+            }
+
+            self.tx.execute("INSERT INTO packages (path) VALUES (?1)", params![package_path.display().to_string()])
+                .map_err(|e| format!("Failed to insert package {}, reason: {e}", package_path.display()))?;
+
+            let id = self.tx.last_insert_rowid();
+            self.package_ids.insert(package_path, id);
+            Ok(id)
+
+        }
+
+    }
+
+    // Internal function to compute the CRC32 of a whole file.
+    fn compute_crc32(fs: &ResFilesystem, path: &str) -> CliResult<u32> {
+
+        let mut read_file = fs.read(path)
+            .map_err(|e| format!("Failed to read file '{path}', reason: {e}"))?;
+
+        let mut crc32 = crc32fast::Hasher::new();
+        let mut buf = vec![0; 64 * 1024];
+
+        loop {
+            let len = read_file.read(&mut buf)
+                .map_err(|e| format!("Failed to read file '{path}', reason: {e}"))?;
+            if len == 0 {
+                break;
+            }
+            crc32.update(&buf[..len]);
+        }
+
+        Ok(crc32.finalize())
+
+    }
+
+    if args.output.exists() {
+        fs::remove_file(&args.output)
+            .map_err(|e| format!("Failed to remove existing database at {}, reason: {e}", args.output.display()))?;
+    }
+
+    let mut conn = Connection::open(&args.output)
+        .map_err(|e| format!("Failed to create database at {}, reason: {e}", args.output.display()))?;
+
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to create database schema, reason: {e}"))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start database transaction, reason: {e}"))?;
+
+    let path = args.path.strip_suffix('/').unwrap_or(&args.path);
+
+    let mut indexer = Indexer {
+        tx: &tx,
+        package_ids: HashMap::new(),
+        count: 0,
+        human: opts.human,
+    };
+
+    indexer.index_dir(fs, path)?;
+    let count = indexer.count;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit database transaction, reason: {e}"))?;
+
+    if opts.human {
+        eprintln!("\r{count} files");
+    }
+
+    Ok(())
+
+}
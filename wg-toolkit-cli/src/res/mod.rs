@@ -1,26 +1,56 @@
 #[cfg(feature = "dokan")]
 mod dokan;
+mod extract;
+mod verify;
+#[cfg(feature = "sqlite")]
+mod index;
 
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 use std::fs::File;
 
-use wgtk::res::{ResFilesystem, ResReadDir, ResReadFile};
+use wgtk::res::{ResFilesystem, ResLocation, ResReadDir, ResReadFile};
 use wgtk::util::SizeFmt;
+use wgtk::pxml;
 
-use crate::{CliOptions, CliResult, ResArgs, ResCommand, ResCopyArgs, ResListArgs, ResReadArgs};
+use sha2::{Digest, Sha256};
+
+use crate::{CliOptions, CliResult, ResArgs, ResCommand, ResCopyArgs, ResListArgs, ResManifestArgs, ResReadArgs};
 
 
 /// Entrypoint.
 pub fn cmd_res(opts: CliOptions, args: ResArgs) -> CliResult<()> {
 
-    let fs = ResFilesystem::new(args.dir)
-        .map_err(|e| format!("Failed to open resource filesystem, reason: {e}"))?;
+    // Packages are verified directly, without the filesystem.
+    if let ResCommand::Verify(verify_args) = args.cmd {
+        return verify::cmd_res_verify(opts, verify_args, args.dir);
+    }
+
+    // Commands walking the whole filesystem need all packages, so they are indexed
+    // upfront in parallel, other commands lazily open packages.
+    let walk_all = match &args.cmd {
+        ResCommand::Extract(_) => true,
+        ResCommand::Manifest(_) => true,
+        #[cfg(feature = "sqlite")]
+        ResCommand::Index(_) => true,
+        _ => false,
+    };
+
+    let fs = if walk_all {
+        ResFilesystem::new_parallel(args.dir)
+    } else {
+        ResFilesystem::new(args.dir)
+    }.map_err(|e| format!("Failed to open resource filesystem, reason: {e}"))?;
 
     match args.cmd {
         ResCommand::List(args) => cmd_res_list(opts, args, &fs),
         ResCommand::Read(args) => cmd_res_read(opts, args, &fs),
         ResCommand::Copy(args) => cmd_res_copy(opts, args, &fs),
+        ResCommand::Extract(args) => extract::cmd_res_extract(opts, args, &fs),
+        ResCommand::Manifest(args) => cmd_res_manifest(opts, args, &fs),
+        ResCommand::Verify(_) => unreachable!(),
+        #[cfg(feature = "sqlite")]
+        ResCommand::Index(args) => index::cmd_res_index(opts, args, &fs),
         #[cfg(feature = "dokan")]
         ResCommand::Dokan(args) => dokan::cmd_res_dokan(opts, args, &fs),
     }
@@ -35,7 +65,7 @@ fn cmd_res_list(opts: CliOptions, args: ResListArgs, fs: &ResFilesystem) -> CliR
     let mut indent = String::new();
     let mut output = io::stdout().lock();
 
-    print_dir(&mut output, fs, &mut indent, path, recurse, opts.human)
+    print_dir(&mut output, fs, &mut indent, path, recurse, opts.human, args.package)
         .map_err(|e| format!("Can't find '{path}' resource directory, reason: {e}"))?;
 
     Ok(())
@@ -58,8 +88,42 @@ fn cmd_res_read(opts: CliOptions, args: ResReadArgs, fs: &ResFilesystem) -> CliR
         print!("                     \r");
     }
 
-    io::copy(&mut read_file, &mut io::stdout().lock())
-        .map_err(|e| format!("Failed to print file content to stdout, reason: {e}"))?;
+    let mut output = io::stdout().lock();
+
+    // Peek the magic to detect packed XML files, these are printed as clear XML.
+    let mut magic = [0; 4];
+    let mut magic_len = 0;
+    if !args.raw {
+        while magic_len < magic.len() {
+            match read_file.read(&mut magic[magic_len..]) {
+                Ok(0) => break,
+                Ok(len) => magic_len += len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(format!("Failed to read file '{path}', reason: {e}")),
+            }
+        }
+    }
+
+    if &magic[..magic_len] == pxml::MAGIC {
+
+        let mut data = magic.to_vec();
+        read_file.read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read file '{path}', reason: {e}"))?;
+
+        let element = pxml::from_bytes(&data)
+            .map_err(|e| format!("Failed to read Packed XML file '{path}', reason: {e}"))?;
+
+        let file_name = path.rsplit_once('/').map(|(_, s)| s).unwrap_or(path);
+        pxml::to_xml_writer(&mut output, &element, file_name)
+            .map_err(|e| format!("Failed to print file content to stdout, reason: {e}"))?;
+
+    } else {
+
+        output.write_all(&magic[..magic_len])
+            .and_then(|()| io::copy(&mut read_file, &mut output))
+            .map_err(|e| format!("Failed to print file content to stdout, reason: {e}"))?;
+
+    }
 
     Ok(())
 
@@ -167,8 +231,109 @@ fn cmd_res_copy(_opts: CliOptions, args: ResCopyArgs, fs: &ResFilesystem) -> Cli
 
 }
 
+fn cmd_res_manifest(opts: CliOptions, args: ResManifestArgs, fs: &ResFilesystem) -> CliResult<()> {
+
+    /// A file of the manifest.
+    struct ManifestFile {
+        path: String,
+        size: u64,
+        crc32: u32,
+        sha256: Option<[u8; 32]>,
+    }
+
+    // Internal function to recursively hash all files of a directory.
+    fn hash_dir(fs: &ResFilesystem, dir_path: &str, sha256: bool, human: bool, files: &mut Vec<ManifestFile>) -> CliResult<()> {
+
+        let read_dir = fs.read_dir(dir_path)
+            .map_err(|e| format!("Failed to read directory '{dir_path}', reason: {e}"))?;
+
+        for entry in read_dir {
+
+            let entry = entry.map_err(|e| format!("Failed to read entry, reason: {e}"))?;
+            let entry_path = entry.path();
+
+            if entry.stat().is_dir() {
+                hash_dir(fs, &entry_path, sha256, human, files)?;
+                continue;
+            }
+
+            if human {
+                eprint!("\r{} files...", files.len());
+            }
+
+            let mut read_file = fs.read(&entry_path)
+                .map_err(|e| format!("Failed to read file '{entry_path}', reason: {e}"))?;
+
+            let mut crc32 = crc32fast::Hasher::new();
+            let mut sha256 = sha256.then(Sha256::new);
+            let mut size = 0u64;
+            let mut buf = vec![0; 64 * 1024];
+
+            loop {
+
+                let len = read_file.read(&mut buf)
+                    .map_err(|e| format!("Failed to read file '{entry_path}', reason: {e}"))?;
+
+                if len == 0 {
+                    break;
+                }
+
+                crc32.update(&buf[..len]);
+                if let Some(sha256) = &mut sha256 {
+                    sha256.update(&buf[..len]);
+                }
+
+                size += len as u64;
+
+            }
+
+            files.push(ManifestFile {
+                path: entry_path,
+                size,
+                crc32: crc32.finalize(),
+                sha256: sha256.map(|sha256| sha256.finalize().into()),
+            });
+
+        }
+
+        Ok(())
+
+    }
+
+    let path = args.path.strip_suffix('/').unwrap_or(&args.path);
+
+    let mut files = Vec::new();
+    hash_dir(fs, path, args.sha256, opts.human, &mut files)?;
+
+    if opts.human {
+        eprintln!("\r{} files", files.len());
+    }
+
+    // Sort by path bytes so that the order doesn't depend on the packages.
+    files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+    let mut output = io::stdout().lock();
+    for file in files {
+
+        let _ = write!(output, "{}\t{}\t{:08x}", file.path, file.size, file.crc32);
+
+        if let Some(sha256) = file.sha256 {
+            let _ = write!(output, "\t");
+            for byte in sha256 {
+                let _ = write!(output, "{byte:02x}");
+            }
+        }
+
+        let _ = writeln!(output);
+
+    }
+
+    Ok(())
+
+}
+
 /// Print directory content
-fn print_dir(output: &mut impl Write, fs: &ResFilesystem, indent: &mut String, dir_path: &str, recursion: u16, human: bool) -> io::Result<()> {
+fn print_dir(output: &mut impl Write, fs: &ResFilesystem, indent: &mut String, dir_path: &str, recursion: u16, human: bool, package: bool) -> io::Result<()> {
 
     if human && indent.is_empty() {
         let _ = write!(output, "Opening filesystem...\r");
@@ -201,15 +366,36 @@ fn print_dir(output: &mut impl Write, fs: &ResFilesystem, indent: &mut String, d
 
         if entry.stat().is_dir() {
             let _ = writeln!(output, "{indent}{}/", entry.name());
-        } else if human { 
-            let _ = writeln!(output, "{indent}{:<2$}  {}", entry.name(), SizeFmt(entry.stat().size()), max_size);
         } else {
-            let _ = writeln!(output, "{indent}{} {}", entry.name(), entry.stat().size());
+
+            if human { 
+                let _ = write!(output, "{indent}{:<2$}  {}", entry.name(), SizeFmt(entry.stat().size()), max_size);
+            } else {
+                let _ = write!(output, "{indent}{} {}", entry.name(), entry.stat().size());
+            }
+
+            if package {
+                // Native files have no package, print a dash to keep the columns.
+                let package_name = match fs.locate(&entry_path) {
+                    Ok(ResLocation::Package { path, .. }) => path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    _ => "-".to_string(),
+                };
+                if human {
+                    let _ = write!(output, "  {package_name}");
+                } else {
+                    let _ = write!(output, " {package_name}");
+                }
+            }
+
+            let _ = writeln!(output);
+
         }
 
         if recursion > 0 {
             indent.push_str("  ");
-            let _ = print_dir(output, fs, indent, &entry_path, recursion - 1, human);
+            let _ = print_dir(output, fs, indent, &entry_path, recursion - 1, human, package);
             indent.truncate(indent.len() - 2);
         }
 
@@ -0,0 +1,109 @@
+//! Integrity verification of all packages of a resource filesystem.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io;
+
+use rayon::prelude::*;
+
+use wgtk::res::package::PackageReader;
+use wgtk::res::paths::ResPaths;
+
+use crate::{CliOptions, CliResult, ResVerifyArgs};
+
+
+pub(super) fn cmd_res_verify(opts: CliOptions, args: ResVerifyArgs, dir: PathBuf) -> CliResult<()> {
+
+    /// Result of the verification of a package.
+    struct PackageReport {
+        /// Number of files verified.
+        count: usize,
+        /// Corrupted files with their error.
+        corrupted: Vec<(String, io::Error)>,
+    }
+
+    // Internal function to verify all files of a package.
+    fn verify_package(path: &Path) -> io::Result<PackageReport> {
+
+        let mut reader = PackageReader::new(File::open(path)?)?;
+        let mut corrupted = Vec::new();
+
+        for index in 0..reader.len() {
+            if let Err(e) = reader.verify(index) {
+                let name = reader.info_by_index(index).unwrap().name.to_string();
+                corrupted.push((name, e));
+            }
+        }
+
+        Ok(PackageReport { count: reader.len(), corrupted })
+
+    }
+
+    let paths = ResPaths::from_dir(&dir)
+        .map_err(|e| format!("Failed to resolve resource paths, reason: {e}"))?;
+
+    // Packages may be mounted multiple times with different roots.
+    let mut seen_packages = HashSet::new();
+    let packages = paths.packages.into_iter()
+        .map(|package| package.path)
+        .filter(|path| seen_packages.insert(path.clone()))
+        .collect::<Vec<_>>();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build()
+        .map_err(|e| format!("Failed to create verification workers, reason: {e}"))?;
+
+    let done_count = AtomicUsize::new(0);
+    let reports = pool.install(|| {
+        packages.par_iter().map(|path| {
+
+            let report = verify_package(path);
+
+            let count = done_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if opts.human {
+                eprint!("\r{count}/{} packages...", packages.len());
+            }
+
+            report
+
+        }).collect::<Vec<_>>()
+    });
+
+    if opts.human {
+        eprintln!();
+    }
+
+    let mut file_count = 0;
+    let mut error_count = 0;
+
+    // Reports are printed in the packages' order, regardless of the verification order.
+    for (path, report) in packages.iter().zip(reports) {
+        match report {
+            Ok(report) => {
+                file_count += report.count;
+                error_count += report.corrupted.len();
+                for (name, e) in report.corrupted {
+                    println!("{}\t{name}\t{e}", path.display());
+                }
+            }
+            Err(e) => {
+                error_count += 1;
+                println!("{}\t-\t{e}", path.display());
+            }
+        }
+    }
+
+    if opts.human {
+        eprintln!("{} packages, {file_count} files verified", packages.len());
+    }
+
+    if error_count != 0 {
+        return Err(format!("{error_count} corrupted files or packages"));
+    }
+
+    Ok(())
+
+}
@@ -0,0 +1,50 @@
+//! Interactive dissection of a raw capture, presenting each packet as a tree of its
+//! header and footer fields, and of the elements of the bundles it completes, with
+//! the bytes of each node shown in a hex pane.
+
+mod tree;
+mod ui;
+
+use std::net::{SocketAddr, SocketAddrV4};
+use std::io::BufReader;
+use std::fs::File;
+use std::path::Path;
+
+use blowfish::cipher::KeyInit;
+use blowfish::Blowfish;
+
+use wgtk::util::pcap::PcapReader;
+
+use crate::CliResult;
+
+
+/// Load the given pcap or pcapng capture and run the interactive dissector on all the
+/// packets exchanged with the given base app, packets are decrypted with the given
+/// blowfish key, if any.
+pub fn run(
+    pcap_path: &Path,
+    blowfish_key: Option<&[u8]>,
+    base_app_addr: SocketAddrV4,
+) -> CliResult<()> {
+
+    let blowfish = blowfish_key
+        .map(|key| Blowfish::new_from_slice(key)
+            .map_err(|_| format!("Invalid blowfish key length: {}", key.len())))
+        .transpose()?;
+
+    let pcap_file = File::open(pcap_path)
+        .map_err(|e| format!("Failed to open capture at {}: {e}", pcap_path.display()))?;
+
+    let reader = PcapReader::new(BufReader::new(pcap_file))
+        .map_err(|e| format!("Failed to read capture at {}: {e}", pcap_path.display()))?;
+
+    let tree = tree::build(reader, blowfish.as_ref(), SocketAddr::V4(base_app_addr))
+        .map_err(|e| format!("Failed to read capture at {}: {e}", pcap_path.display()))?;
+
+    if tree.roots.is_empty() {
+        return Err(format!("No packet exchanged with {base_app_addr} in the capture"));
+    }
+
+    ui::run(tree).map_err(|e| format!("Failed to run the terminal interface: {e}"))
+
+}
@@ -0,0 +1,359 @@
+//! The dissection tree of a capture, from packets down to the decoded fields of their
+//! elements, each node being associated to the bytes it has been decoded from.
+
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::rc::Rc;
+use std::fmt;
+use std::io;
+
+use blowfish::Blowfish;
+
+use wgtk::net::bundle::{Bundle, NextElementReader, ElementReader};
+use wgtk::net::element::{DebugElementUndefined, Element, SimpleElement};
+use wgtk::net::packet::{self, flags, FlagsFmt, Packet, PacketConfig};
+use wgtk::net::socket::decrypt_packet;
+use wgtk::net::app::proxy::{Decoder, PacketDirection};
+use wgtk::net::app::{base, client};
+
+use wgtk::util::pcap::{PcapError, PcapReader};
+
+
+/// The dissection tree, nodes are stored in a flat arena and reference their children
+/// by index.
+#[derive(Debug, Default)]
+pub struct Tree {
+    /// All nodes of the tree.
+    pub nodes: Vec<Node>,
+    /// Index of the root nodes, one per packet.
+    pub roots: Vec<usize>,
+}
+
+/// A node of the dissection tree.
+#[derive(Debug)]
+pub struct Node {
+    /// The label displayed in the tree.
+    pub label: String,
+    /// Depth of the node, zero for roots.
+    pub depth: usize,
+    /// The bytes displayed in the hex pane when this node is selected.
+    pub data: Rc<[u8]>,
+    /// The range of bytes in the data that this node has been decoded from.
+    pub range: Range<usize>,
+    /// Index of the children of this node.
+    pub children: Vec<usize>,
+    /// True if the children of this node are displayed.
+    pub expanded: bool,
+}
+
+impl Tree {
+
+    /// Push a new node as a child of the given parent, or as a root if none, and return
+    /// its index.
+    fn push(&mut self, parent: Option<usize>, label: impl Into<String>, data: &Rc<[u8]>, range: Range<usize>) -> usize {
+
+        let index = self.nodes.len();
+        let depth = parent.map_or(0, |parent| self.nodes[parent].depth + 1);
+
+        self.nodes.push(Node {
+            label: label.into(),
+            depth,
+            data: Rc::clone(data),
+            range,
+            children: Vec::new(),
+            expanded: false,
+        });
+
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(index),
+            None => self.roots.push(index),
+        }
+
+        index
+
+    }
+
+    /// Push a node for each line of the given debug value, with the same range.
+    fn push_debug(&mut self, parent: usize, value: &dyn fmt::Debug, data: &Rc<[u8]>, range: Range<usize>) {
+        for line in format!("{value:#?}").lines() {
+            self.push(Some(parent), line, data, range.clone());
+        }
+    }
+
+}
+
+/// Build the dissection tree of all packets exchanged with the given base app in the
+/// capture, packets are decrypted with the blowfish key if given.
+pub fn build<R: io::Read>(mut reader: PcapReader<R>, blowfish: Option<&Blowfish>, base_app_addr: SocketAddr) -> Result<Tree, PcapError> {
+
+    let mut tree = Tree::default();
+    let mut decoder = Decoder::new();
+    let mut start_time = None;
+
+    while let Some(datagram) = reader.next_datagram()? {
+
+        let (addr, direction) = if datagram.dst == base_app_addr {
+            (datagram.src, PacketDirection::Out)
+        } else if datagram.src == base_app_addr {
+            (datagram.dst, PacketDirection::In)
+        } else {
+            continue;
+        };
+
+        let start_time = *start_time.get_or_insert(datagram.time);
+        let time = datagram.time.saturating_sub(start_time);
+        let index = tree.roots.len();
+
+        let dir = match direction {
+            PacketDirection::Out => "->",
+            PacketDirection::In => "<-",
+        };
+
+        if datagram.data.len() < packet::PACKET_HEADER_LEN || datagram.data.len() > packet::PACKET_CAP {
+            let data = Rc::from(datagram.data);
+            tree.push(None, format!("#{index} {dir} {addr} +{time:?} invalid length"), &data, 0..0);
+            continue;
+        }
+
+        let mut packet = Packet::new();
+        packet.buf_mut()[..datagram.data.len()].copy_from_slice(&datagram.data);
+        packet.set_len(datagram.data.len());
+
+        if let Some(blowfish) = blowfish {
+            packet = match decrypt_packet(packet, blowfish) {
+                Ok(packet) => packet,
+                Err(packet) => {
+                    let data = Rc::from(packet.slice());
+                    tree.push(None, format!("#{index} {dir} {addr} +{time:?} invalid encryption"), &data, 0..0);
+                    continue;
+                }
+            };
+        }
+
+        let data = Rc::<[u8]>::from(packet.slice());
+        let root = tree.push(None, format!("#{index} {dir} {addr} +{time:?} len: {}", data.len()), &data, 0..data.len());
+        push_packet(&mut tree, root, &packet, &data);
+
+        // The packet is already decrypted, the decoder is only used to reassemble the
+        // fragmented bundles.
+        match decoder.decode(packet, addr, direction, None) {
+            Ok(Some(bundle)) => push_bundle(&mut tree, root, &bundle.bundle, direction),
+            Ok(None) => {}
+            Err(e) => {
+                tree.push(Some(root), format!("Error: {e}"), &data, 0..0);
+            }
+        }
+
+    }
+
+    Ok(tree)
+
+}
+
+/// Push the header and footer fields of the given packet.
+fn push_packet(tree: &mut Tree, parent: usize, packet: &Packet, data: &Rc<[u8]>) {
+
+    let flags = packet.read_flags();
+    tree.push(Some(parent), format!("Prefix: 0x{:08X}", packet.read_prefix()), data, 0..packet::PACKET_PREFIX_LEN);
+    tree.push(Some(parent), format!("Flags: 0x{flags:04X} {}", FlagsFmt(flags)), data, packet::PACKET_PREFIX_LEN..packet::PACKET_HEADER_LEN);
+
+    let mut config = PacketConfig::new();
+    if let Err(e) = packet.read_config(&mut config) {
+        tree.push(Some(parent), format!("Invalid footer: {e}"), data, packet::PACKET_HEADER_LEN..data.len());
+        return;
+    }
+
+    let footer_offset = config.footer_offset();
+    tree.push(Some(parent), format!("Body: {} bytes", footer_offset - packet::PACKET_HEADER_LEN), data, packet::PACKET_HEADER_LEN..footer_offset);
+
+    let footer = tree.push(Some(parent), format!("Footer: {} bytes", data.len() - footer_offset), data, footer_offset..data.len());
+
+    // The footer is read from its end, in the same order as the packet config.
+    let mut end = data.len();
+    let mut pop = |len: usize| {
+        let start = end.saturating_sub(len).max(footer_offset);
+        let range = start..end;
+        end = start;
+        range
+    };
+
+    if flags & flags::HAS_CHECKSUM != 0 {
+        let range = pop(4);
+        tree.push(Some(footer), format!("Checksum: 0x{:08X}", read_u32(data, &range)), data, range);
+    }
+
+    if flags & flags::HAS_PIGGYBACKS != 0 {
+        for (index, piggyback) in config.piggybacks().iter().enumerate() {
+            let range = pop(2 + piggyback.len() - packet::PACKET_PREFIX_LEN);
+            tree.push(Some(footer), format!("Piggyback #{index}: {} bytes", piggyback.len() - packet::PACKET_PREFIX_LEN), data, range);
+        }
+    }
+
+    if let Some((index, version)) = config.indexed_channel() {
+        tree.push(Some(footer), format!("Indexed channel: {index}, version: {version}"), data, pop(8));
+    }
+
+    if let Some(ack) = config.cumulative_ack() {
+        tree.push(Some(footer), format!("Cumulative ack: {ack}"), data, pop(4));
+    }
+
+    if flags & flags::HAS_ACKS != 0 {
+        tree.push(Some(footer), format!("Acks count: {}", config.single_acks().len()), data, pop(1));
+        for ack in config.single_acks() {
+            tree.push(Some(footer), format!("Ack: {ack}"), data, pop(4));
+        }
+    }
+
+    if flags & flags::HAS_SEQUENCE_NUMBER != 0 {
+        tree.push(Some(footer), format!("Sequence number: {}", config.sequence_num()), data, pop(4));
+    }
+
+    if let Some(num) = config.last_reliable_sequence_num() {
+        tree.push(Some(footer), format!("Last reliable sequence number: {num}"), data, pop(4));
+    }
+
+    if let Some(offset) = config.first_request_offset() {
+        tree.push(Some(footer), format!("First request offset: {offset}"), data, pop(2));
+    }
+
+    if let Some((first, last)) = config.sequence_range() {
+        tree.push(Some(footer), format!("Sequence range: {first}..={last}"), data, pop(8));
+    }
+
+}
+
+/// Push the elements of a bundle that has been completed by the packet, the bundle's
+/// data is the concatenation of the bodies of its packets.
+fn push_bundle(tree: &mut Tree, parent: usize, bundle: &Bundle, direction: PacketDirection) {
+
+    let mut data = Vec::new();
+    for packet in bundle.iter() {
+        let mut config = PacketConfig::new();
+        // Packets of a bundle have necessarily a valid config.
+        if packet.read_config(&mut config).is_ok() {
+            data.extend_from_slice(&packet.slice()[packet::PACKET_HEADER_LEN..config.footer_offset()]);
+        }
+    }
+
+    let data = Rc::<[u8]>::from(data);
+    let node = tree.push(Some(parent), format!("Bundle: {} packets, {} bytes", bundle.len(), data.len()), &data, 0..data.len());
+
+    let mut reader = bundle.element_reader();
+    loop {
+
+        let start_pos = reader.pos();
+        let Some(elt) = reader.next() else {
+            break;
+        };
+
+        let res = match elt {
+            NextElementReader::Element(elt) => {
+                let id = elt.id();
+                let res = match direction {
+                    PacketDirection::Out => read_out_element(elt),
+                    PacketDirection::In => read_in_element(elt),
+                };
+                res.map(|(name, value, next)| (format!("{name} (0x{id:02X})"), value, next))
+            }
+            NextElementReader::Reply(reply) => {
+                let request_id = reply.request_id();
+                reply.read_simple::<DebugElementUndefined<0>>()
+                    .map(|elt| (format!("Reply #{request_id}"), Box::new(elt) as Box<dyn fmt::Debug>, false))
+            }
+        };
+
+        let range = start_pos..reader.pos().min(data.len());
+        match res {
+            Ok((label, value, next)) => {
+                let elt_node = tree.push(Some(node), label, &data, range.clone());
+                tree.push_debug(elt_node, &*value, &data, range);
+                if !next {
+                    break;
+                }
+            }
+            Err(e) => {
+                tree.push(Some(node), format!("Error: {e}"), &data, start_pos..data.len());
+                break;
+            }
+        }
+
+    }
+
+}
+
+/// The result of reading an element: its name, its decoded value and true if the next
+/// elements can be read.
+type ElementRes = io::Result<(&'static str, Box<dyn fmt::Debug>, bool)>;
+
+/// Read a known element and box its value.
+fn read_known<E: Element<()> + fmt::Debug + 'static>(elt: ElementReader, name: &'static str) -> ElementRes {
+    let elt = elt.read_simple::<E>()?;
+    Ok((name, Box::new(elt.element), true))
+}
+
+/// Read an unknown element, its length being unknown, the next elements cannot be read.
+fn read_unknown(elt: ElementReader, name: &'static str) -> ElementRes {
+    let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
+    Ok((name, Box::new(elt.element), false))
+}
+
+fn read_out_element(elt: ElementReader) -> ElementRes {
+
+    use base::element::*;
+
+    match elt.id() {
+        LoginKey::ID => read_known::<LoginKey>(elt, "Login key"),
+        SessionKey::ID => read_known::<SessionKey>(elt, "Session key"),
+        EnableEntities::ID => read_known::<EnableEntities>(elt, "Enable entities"),
+        DisconnectClient::ID => read_known::<DisconnectClient>(elt, "Disconnect client"),
+        AvatarUpdateImplicit::ID => read_known::<AvatarUpdateImplicit>(elt, "Avatar update implicit"),
+        AvatarUpdateExplicit::ID => read_known::<AvatarUpdateExplicit>(elt, "Avatar update explicit"),
+        AvatarUpdateWardImplicit::ID => read_known::<AvatarUpdateWardImplicit>(elt, "Avatar update ward implicit"),
+        AvatarUpdateWardExplicit::ID => read_known::<AvatarUpdateWardExplicit>(elt, "Avatar update ward explicit"),
+        id if id::BASE_ENTITY_METHOD.contains(id) => read_unknown(elt, "Base entity method"),
+        _ => read_unknown(elt, "Unknown element"),
+    }
+
+}
+
+fn read_in_element(elt: ElementReader) -> ElementRes {
+
+    use client::element::*;
+
+    match elt.id() {
+        Authenticate::ID => read_known::<Authenticate>(elt, "Authenticate"),
+        BandwidthNotification::ID => read_known::<BandwidthNotification>(elt, "Bandwidth notification"),
+        UpdateFrequencyNotification::ID => read_known::<UpdateFrequencyNotification>(elt, "Update frequency notification"),
+        SetGameTime::ID => read_known::<SetGameTime>(elt, "Set game time"),
+        ResetEntities::ID => read_known::<ResetEntities>(elt, "Reset entities"),
+        TickSync::ID => read_known::<TickSync>(elt, "Tick sync"),
+        SelectPlayerEntity::ID => read_known::<SelectPlayerEntity>(elt, "Select player entity"),
+        ForcedPosition::ID => read_known::<ForcedPosition>(elt, "Forced position"),
+        EnterAoi::ID => read_known::<EnterAoi>(elt, "Enter AoI"),
+        EnterAoiOnVehicle::ID => read_known::<EnterAoiOnVehicle>(elt, "Enter AoI on vehicle"),
+        LeaveAoi::ID => read_known::<LeaveAoi>(elt, "Leave AoI"),
+        RelativePositionReference::ID => read_known::<RelativePositionReference>(elt, "Relative position reference"),
+        RelativePosition::ID => read_known::<RelativePosition>(elt, "Relative position"),
+        SetVehicle::ID => read_known::<SetVehicle>(elt, "Set vehicle"),
+        SelectAliasedEntity::ID => read_known::<SelectAliasedEntity>(elt, "Select aliased entity"),
+        SelectEntity::ID => read_known::<SelectEntity>(elt, "Select entity"),
+        id if id::AVATAR_UPDATE.contains(id) => read_known::<AvatarUpdate>(elt, "Avatar update"),
+        DetailedPosition::ID => read_known::<DetailedPosition>(elt, "Detailed position"),
+        ControlEntity::ID => read_known::<ControlEntity>(elt, "Control entity"),
+        ResourceHeader::ID => read_known::<ResourceHeader>(elt, "Resource header"),
+        ResourceFragment::ID => read_known::<ResourceFragment>(elt, "Resource fragment"),
+        LoggedOff::ID => read_known::<LoggedOff>(elt, "Logged off"),
+        CreateBasePlayerHeader::ID => read_unknown(elt, "Create base player"),
+        id if id::ENTITY_METHOD.contains(id) => read_unknown(elt, "Entity method"),
+        id if id::ENTITY_PROPERTY.contains(id) => read_unknown(elt, "Entity property"),
+        _ => read_unknown(elt, "Unknown element"),
+    }
+
+}
+
+/// Read a little endian u32 from the given range of data, zero if out of bounds.
+fn read_u32(data: &[u8], range: &Range<usize>) -> u32 {
+    data.get(range.clone())
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(0, u32::from_le_bytes)
+}
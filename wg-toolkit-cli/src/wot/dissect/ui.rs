@@ -0,0 +1,217 @@
+//! Terminal user interface of the dissector, showing the tree on the left and the hex
+//! pane of the selected node on the right.
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use super::tree::Tree;
+
+
+/// Number of bytes on each line of the hex pane.
+const HEX_LINE_LEN: usize = 16;
+/// Number of rows moved by page up and page down.
+const PAGE_LEN: usize = 20;
+
+/// State of the user interface.
+struct Ui {
+    tree: Tree,
+    /// Index of the nodes currently visible in the tree view, in display order.
+    rows: Vec<usize>,
+    /// State of the tree view, with the selected row.
+    list_state: ListState,
+}
+
+/// Run the user interface on the given tree until the user quits.
+pub fn run(tree: Tree) -> io::Result<()> {
+
+    let mut ui = Ui {
+        tree,
+        rows: Vec::new(),
+        list_state: ListState::default(),
+    };
+
+    ui.update_rows();
+    ui.list_state.select((!ui.rows.is_empty()).then_some(0));
+
+    let mut terminal = ratatui::init();
+    let res = ui.run(&mut terminal);
+    ratatui::restore();
+    res
+
+}
+
+impl Ui {
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::PageUp => self.move_selection(-(PAGE_LEN as isize)),
+                KeyCode::PageDown => self.move_selection(PAGE_LEN as isize),
+                KeyCode::Home => self.list_state.select_first(),
+                KeyCode::End => self.list_state.select_last(),
+                KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => self.set_expanded(true),
+                KeyCode::Left | KeyCode::Char('h') => self.set_expanded(false),
+                _ => {}
+            }
+
+        }
+    }
+
+    /// Recompute the visible rows from the expanded state of the nodes.
+    fn update_rows(&mut self) {
+
+        self.rows.clear();
+
+        // Depth-first traversal, children are pushed in reverse order to be popped in
+        // the right order.
+        let mut stack = self.tree.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            self.rows.push(index);
+            let node = &self.tree.nodes[index];
+            if node.expanded {
+                stack.extend(node.children.iter().rev().copied());
+            }
+        }
+
+    }
+
+    /// Return the index of the selected node, if any.
+    fn selected(&self) -> Option<usize> {
+        self.list_state.selected().and_then(|row| self.rows.get(row)).copied()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if let Some(row) = self.list_state.selected() {
+            let row = row.saturating_add_signed(delta).min(self.rows.len().saturating_sub(1));
+            self.list_state.select(Some(row));
+        }
+    }
+
+    /// Expand or collapse the selected node, collapsing a node that is already
+    /// collapsed selects its parent.
+    fn set_expanded(&mut self, expanded: bool) {
+
+        let Some(index) = self.selected() else {
+            return;
+        };
+
+        let node = &mut self.tree.nodes[index];
+        if !expanded && !node.expanded {
+
+            // Select the parent, which is the closest previous row with a lower depth.
+            let depth = node.depth;
+            let row = self.list_state.selected().unwrap();
+            if let Some(parent_row) = self.rows[..row].iter().rposition(|&i| self.tree.nodes[i].depth < depth) {
+                self.list_state.select(Some(parent_row));
+            }
+
+        } else if !node.children.is_empty() {
+            node.expanded = expanded;
+            self.update_rows();
+        }
+
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+
+        let [main_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let [tree_area, hex_area] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .areas(main_area);
+
+        let items = self.rows.iter().map(|&index| {
+            let node = &self.tree.nodes[index];
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => "  ",
+                (false, false) => "+ ",
+                (false, true) => "- ",
+            };
+            ListItem::new(format!("{}{marker}{}", "  ".repeat(node.depth), node.label))
+        }).collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .block(Block::bordered().title(format!(" Packets ({}) ", self.tree.roots.len())))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, tree_area, &mut self.list_state);
+
+        self.draw_hex(frame, hex_area);
+
+        frame.render_widget(Line::from(" q: quit, ↑↓: move, →: expand, ←: collapse").dim(), help_area);
+
+    }
+
+    /// Draw the hex pane of the selected node, highlighting the bytes it has been
+    /// decoded from.
+    fn draw_hex(&self, frame: &mut Frame, area: Rect) {
+
+        let Some(node) = self.selected().map(|index| &self.tree.nodes[index]) else {
+            frame.render_widget(Block::bordered().title(" Hex "), area);
+            return;
+        };
+
+        let data = &node.data[..];
+        let range = node.range.clone();
+
+        // Scroll so that the start of the range is visible, with some context.
+        let height = area.height.saturating_sub(2) as usize;
+        let first_line = (range.start / HEX_LINE_LEN).saturating_sub(2);
+
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+
+        let lines = data.chunks(HEX_LINE_LEN)
+            .enumerate()
+            .skip(first_line)
+            .take(height)
+            .map(|(line_index, line)| {
+
+                let offset = line_index * HEX_LINE_LEN;
+                let mut spans = vec![Span::raw(format!("{offset:08X} ")).dim()];
+
+                for (i, &byte) in line.iter().enumerate() {
+                    spans.push(Span::raw(" "));
+                    let span = Span::raw(format!("{byte:02X}"));
+                    spans.push(if range.contains(&(offset + i)) { span.style(highlight) } else { span });
+                }
+
+                spans.push(Span::raw("   ".repeat(HEX_LINE_LEN - line.len())));
+                spans.push(Span::raw("  "));
+
+                for (i, &byte) in line.iter().enumerate() {
+                    let c = if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' };
+                    let span = Span::raw(c.to_string());
+                    spans.push(if range.contains(&(offset + i)) { span.style(highlight) } else { span });
+                }
+
+                Line::from(spans)
+
+            })
+            .collect::<Vec<_>>();
+
+        let title = format!(" Hex ({} bytes, selected {}..{}) ", data.len(), range.start, range.end);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+
+    }
+
+}
@@ -13,6 +13,7 @@ use rsa::RsaPrivateKey;
 use blowfish::Blowfish;
 
 use wgtk::net::app::{login, base};
+use wgtk::net::variant::ProtocolVariant;
 
 use crate::CliResult;
 
@@ -21,6 +22,9 @@ pub fn run(
     login_app_addr: SocketAddrV4,
     base_app_addr: SocketAddrV4,
     encryption_key: Option<Arc<RsaPrivateKey>>,
+    protocol_version: Option<u32>,
+    digest: Option<[u8; 16]>,
+    protocol_variant: &ProtocolVariant,
 ) -> CliResult<()> {
 
     let mut login_app = login::App::new(login_app_addr.into())
@@ -30,9 +34,15 @@ pub fn run(
         login_app.set_encryption(encryption_key);
     }
 
-    let base_app = base::App::new(base_app_addr.into())
+    login_app.set_protocol_version(protocol_version);
+    login_app.set_expected_digest(digest);
+    login_app.set_protocol_variant(protocol_variant.clone());
+
+    let mut base_app = base::App::new(base_app_addr.into())
         .map_err(|e| format!("Failed to bind base app: {e}"))?;
 
+    base_app.set_protocol_variant(protocol_variant.clone());
+
     let shared = Arc::new(Shared {
         login_clients: Mutex::new(HashMap::new()),
     });
@@ -105,6 +115,7 @@ impl LoginThread {
                         warn!("Error: {}", error.error);
                     }
                 }
+                Event::Tick(_) => {}
                 Event::Ping(ping) => {
                     info!(addr = %ping.addr, "Ping-Pong: {:?}", ping.latency);
                 }
@@ -168,6 +179,10 @@ impl BaseThread {
                         warn!("Error: {}", error.error);
                     }
                 }
+                base::Event::Tick(_) => {}
+                base::Event::DeliveryFailure(failure) => {
+                    warn!(addr = %failure.addr, "Reliable packet not delivered: {}", failure.sequence_num);
+                }
                 base::Event::Login(login) => {
                     
                     let mut clients = self.shared.login_clients.lock().unwrap();
@@ -185,10 +200,21 @@ impl BaseThread {
                     }
                     
                     info!(addr = %login.addr, "Login #{}... Success", login.attempt_num);
-                    self.app.answer_login_success(login.addr, client.blowfish);
+                    if let Err(e) = self.app.answer_login_success(login.addr, client.blowfish) {
+                        warn!(addr = %login.addr, "Failed to answer login: {e}");
+                    }
 
                 }
-                
+                base::Event::EnableEntities(enable) => {
+                    info!(addr = %enable.addr, "Entities enabled");
+                }
+                base::Event::Disconnect(disconnect) => {
+                    info!(addr = %disconnect.addr, "Disconnected: {}", disconnect.reason);
+                }
+                base::Event::BaseMethod(method) => {
+                    info!(addr = %method.addr, "Base method #{} on entity {}", method.exposed_id, method.entity_id);
+                }
+
             }
 
             // // Proof of concept:
@@ -1,4 +1,5 @@
 pub use wgtk::net::codec::{AutoString, Python, Mailbox};
+pub use wgtk::net::app::client::element::QueueType;
 pub use glam::{Vec2, Vec3, Vec4};
 
 pub type BOOL = u8;
@@ -15,6 +16,12 @@ wgtk::__struct_simple_codec! {
         pub regionCCU: u32,
     }
 }
+wgtk::__impl_nested_property! {
+    SERVER_STATISTICS {
+        "clusterCCU" => clusterCCU,
+        "regionCCU" => regionCCU,
+    }
+}
 
 pub type QUEUE_INFO = Python;
 
@@ -25,6 +32,12 @@ wgtk::__struct_simple_codec! {
         pub players: u32,
     }
 }
+wgtk::__impl_nested_property! {
+    DEFAULT_QUEUE_INFO {
+        "classes" => classes,
+        "players" => players,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -32,6 +45,11 @@ wgtk::__struct_simple_codec! {
         pub queues: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    MAPS_TRAINING_QUEUE_INFO {
+        "queues" => queues,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -39,6 +57,11 @@ wgtk::__struct_simple_codec! {
         pub events: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    FUN_RANDOM_QUEUE_INFO {
+        "events" => events,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -49,6 +72,14 @@ wgtk::__struct_simple_codec! {
         pub roundStart: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    PUBLIC_ARENA_INFO {
+        "id" => id,
+        "typeID" => typeID,
+        "roundLength" => roundLength,
+        "roundStart" => roundStart,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -95,6 +126,50 @@ wgtk::__struct_simple_codec! {
         pub attackerType: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    ATTACK_RESULTS {
+        "targetID" => targetID,
+        "targetVehicleIndex" => targetVehicleIndex,
+        "targetTeam" => targetTeam,
+        "targetTypeCompDescr" => targetTypeCompDescr,
+        "targetIsTeamKiller" => targetIsTeamKiller,
+        "targetIsOnTheIgnoredBase" => targetIsOnTheIgnoredBase,
+        "targetIsOnTheCapturableBase" => targetIsOnTheCapturableBase,
+        "targetIsNotSpotted" => targetIsNotSpotted,
+        "targetMaxHealth" => targetMaxHealth,
+        "targetHealthBeforeDamage" => targetHealthBeforeDamage,
+        "enemiesNearTarget" => enemiesNearTarget,
+        "isRecoil" => isRecoil,
+        "reason" => reason,
+        "shellCompDescr" => shellCompDescr,
+        "hitIndirection" => hitIndirection,
+        "shotID" => shotID,
+        "numVehiclesAffected" => numVehiclesAffected,
+        "hitFlags" => hitFlags,
+        "crits" => crits,
+        "stunDuration" => stunDuration,
+        "allCrits" => allCrits,
+        "anyDeviceWasDamaged" => anyDeviceWasDamaged,
+        "damage" => damage,
+        "repairCost" => repairCost,
+        "critBonusFactor" => critBonusFactor,
+        "droppedCapturePoints" => droppedCapturePoints,
+        "trackAssistants" => trackAssistants,
+        "stunAssistants" => stunAssistants,
+        "smokeAssistants" => smokeAssistants,
+        "distance" => distance,
+        "targetInitialSpeed" => targetInitialSpeed,
+        "attackerInitialSpeed" => attackerInitialSpeed,
+        "attackerHullDamage" => attackerHullDamage,
+        "attackerKilledHimself" => attackerKilledHimself,
+        "attackerHealthBeforeDamage" => attackerHealthBeforeDamage,
+        "circularVisionRadius" => circularVisionRadius,
+        "attackerWasInvisible" => attackerWasInvisible,
+        "equipmentID" => equipmentID,
+        "isIronShieldDamage" => isIronShieldDamage,
+        "attackerType" => attackerType,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -109,6 +184,18 @@ wgtk::__struct_simple_codec! {
         pub extraData: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    PREBATTLE_INVITE {
+        "createTime" => createTime,
+        "type" => r#type,
+        "comment" => comment,
+        "creator" => creator,
+        "creatorBadges" => creatorBadges,
+        "creatorDBID" => creatorDBID,
+        "creatorClanAbbrev" => creatorClanAbbrev,
+        "extraData" => extraData,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -119,6 +206,14 @@ wgtk::__struct_simple_codec! {
         pub extendedResults: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    PREBATTLE_RESULTS {
+        "winner" => winner,
+        "finishReason" => finishReason,
+        "kickReason" => kickReason,
+        "extendedResults" => extendedResults,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -130,6 +225,15 @@ wgtk::__struct_simple_codec! {
         pub flags: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    PUBLIC_CHAT_CHANNEL_INFO {
+        "id" => id,
+        "channelName" => channelName,
+        "isReadOnly" => isReadOnly,
+        "isSecured" => isSecured,
+        "flags" => flags,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -139,6 +243,13 @@ wgtk::__struct_simple_codec! {
         pub accessFlags: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    PUBLIC_USERS_ROSTER_MEMBER_INFO {
+        "id" => id,
+        "nickName" => nickName,
+        "accessFlags" => accessFlags,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -156,6 +267,21 @@ wgtk::__struct_simple_codec! {
         pub flags: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    CHAT_ACTION_DATA {
+        "requestID" => requestID,
+        "action" => action,
+        "actionResponse" => actionResponse,
+        "time" => time,
+        "sentTime" => sentTime,
+        "channel" => channel,
+        "originator" => originator,
+        "originatorNickName" => originatorNickName,
+        "group" => group,
+        "data" => data,
+        "flags" => flags,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -167,6 +293,15 @@ wgtk::__struct_simple_codec! {
         pub strArg2: AutoString,
     }
 }
+wgtk::__impl_nested_property! {
+    GENERIC_MESSENGER_ARGS_chat2 {
+        "int32Arg1" => int32Arg1,
+        "int64Arg1" => int64Arg1,
+        "floatArg1" => floatArg1,
+        "strArg1" => strArg1,
+        "strArg2" => strArg2,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -176,6 +311,13 @@ wgtk::__struct_simple_codec! {
         pub vehicleType: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    DISCLOSE_EVENT {
+        "vehicleID" => vehicleID,
+        "playerName" => playerName,
+        "vehicleType" => vehicleType,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -185,6 +327,13 @@ wgtk::__struct_simple_codec! {
         pub grade: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    DOG_TAG_COMPONENT {
+        "id" => id,
+        "progress" => progress,
+        "grade" => grade,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -192,6 +341,11 @@ wgtk::__struct_simple_codec! {
         pub components: Vec<DOG_TAG_COMPONENT>,
     }
 }
+wgtk::__impl_nested_property! {
+    DOG_TAG {
+        "components" => components,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -201,6 +355,13 @@ wgtk::__struct_simple_codec! {
         pub showDogTagToKiller: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_DOG_TAG {
+        "dogTag" => dogTag,
+        "defaultDogTag" => defaultDogTag,
+        "showDogTagToKiller" => showDogTagToKiller,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -209,6 +370,12 @@ wgtk::__struct_simple_codec! {
         pub dogTag: DOG_TAG,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_DOG_TAG {
+        "vehicleId" => vehicleId,
+        "dogTag" => dogTag,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -217,6 +384,12 @@ wgtk::__struct_simple_codec! {
         pub showDogTagToKiller: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    DOG_TAG_SETTINGS {
+        "showVictimsDogTag" => showVictimsDogTag,
+        "showDogTagToKiller" => showDogTagToKiller,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -233,6 +406,20 @@ wgtk::__struct_simple_codec! {
         pub maxHealth: u16,
     }
 }
+wgtk::__impl_nested_property! {
+    PUBLIC_VEHICLE_INFO {
+        "name" => name,
+        "compDescr" => compDescr,
+        "outfit" => outfit,
+        "index" => index,
+        "team" => team,
+        "prebattleID" => prebattleID,
+        "marksOnGun" => marksOnGun,
+        "crewGroups" => crewGroups,
+        "commanderSkinID" => commanderSkinID,
+        "maxHealth" => maxHealth,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -262,6 +449,33 @@ wgtk::__struct_simple_codec! {
         pub prestigeGradeMarkID: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    ARENA_VEH_INFO {
+        "vehInvID" => vehInvID,
+        "vehCompDescr" => vehCompDescr,
+        "vehOutfit" => vehOutfit,
+        "vehAmmo" => vehAmmo,
+        "vehSetups" => vehSetups,
+        "vehSetupsIndexes" => vehSetupsIndexes,
+        "vehCrew" => vehCrew,
+        "vehCrewInvIDs" => vehCrewInvIDs,
+        "vehCrewSkins" => vehCrewSkins,
+        "marksOnGun" => marksOnGun,
+        "isRent" => isRent,
+        "activeRent" => activeRent,
+        "settings" => settings,
+        "enhancements" => enhancements,
+        "vehPerks" => vehPerks,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "vehPostProgression" => vehPostProgression,
+        "vehDisabledSetupSwitches" => vehDisabledSetupSwitches,
+        "isSuitableForReferralBonus" => isSuitableForReferralBonus,
+        "isElite" => isElite,
+        "prestigeLevel" => prestigeLevel,
+        "anonymizedPrestigeLevel" => anonymizedPrestigeLevel,
+        "prestigeGradeMarkID" => prestigeGradeMarkID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -283,6 +497,25 @@ wgtk::__struct_simple_codec! {
         pub postmortemMode: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    USER_EXTRA_SETTINGS {
+        "hideNonHistoric" => hideNonHistoric,
+        "dogTagsUserSettings" => dogTagsUserSettings,
+        "moduleRepairTimer" => moduleRepairTimer,
+        "minimapMinSpottingRange" => minimapMinSpottingRange,
+        "battleNotifier" => battleNotifier,
+        "additionalzoom" => additionalzoom,
+        "hpinminimap" => hpinminimap,
+        "hpinplayerspanels" => hpinplayerspanels,
+        "commandercam" => commandercam,
+        "contour" => contour,
+        "contourPenetrableZone" => contourPenetrableZone,
+        "contourImpenetrableZone" => contourImpenetrableZone,
+        "crewPerks" => crewPerks,
+        "mapsInDevelopment" => mapsInDevelopment,
+        "postmortemMode" => postmortemMode,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -324,6 +557,45 @@ wgtk::__struct_simple_codec! {
         pub componentsData: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    ARENA_ADDPLAYER_INFO {
+        "name" => name,
+        "attrs" => attrs,
+        "databaseID" => databaseID,
+        "centerID" => centerID,
+        "clanAbbrev" => clanAbbrev,
+        "clanDBID" => clanDBID,
+        "prebattle" => prebattle,
+        "isPrebattleCreator" => isPrebattleCreator,
+        "forbidInBattleInvitations" => forbidInBattleInvitations,
+        "arenaUniqueID" => arenaUniqueID,
+        "team" => team,
+        "tkillRating" => tkillRating,
+        "cybersportRating" => cybersportRating,
+        "globalRating" => globalRating,
+        "igrType" => igrType,
+        "potapovQuestIDs" => potapovQuestIDs,
+        "potapovQuestInfo" => potapovQuestInfo,
+        "vehiclesInfo" => vehiclesInfo,
+        "avatarAmmo" => avatarAmmo,
+        "needCheckPenalties" => needCheckPenalties,
+        "fairplayState" => fairplayState,
+        "battlesNum" => battlesNum,
+        "ranked" => ranked,
+        "group" => group,
+        "recruiterId" => recruiterId,
+        "premMask" => premMask,
+        "needsAnonymization" => needsAnonymization,
+        "wtr" => wtr,
+        "badges" => badges,
+        "overriddenBadge" => overriddenBadge,
+        "dogTag" => dogTag,
+        "userExtraSettings" => userExtraSettings,
+        "isSsrRecordEnabled" => isSsrRecordEnabled,
+        "isSsrPlayEnabled" => isSsrPlayEnabled,
+        "componentsData" => componentsData,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -334,6 +606,14 @@ wgtk::__struct_simple_codec! {
         pub observer: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    AVATAR_VEHICLE_ROSTER {
+        "vehicleID" => vehicleID,
+        "prebattleID" => prebattleID,
+        "team" => team,
+        "observer" => observer,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -363,6 +643,33 @@ wgtk::__struct_simple_codec! {
         pub attackerDualAccuracyState: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    ATTACKER_INFO {
+        "baseMB" => baseMB,
+        "receiveAttackResultsMB" => receiveAttackResultsMB,
+        "team" => team,
+        "position" => position,
+        "circularVisionRadius" => circularVisionRadius,
+        "health" => health,
+        "noOwner" => noOwner,
+        "attackerInitialSpeed" => attackerInitialSpeed,
+        "attackerWasInvisible" => attackerWasInvisible,
+        "attackerTypeCompactDescr" => attackerTypeCompactDescr,
+        "attackerVehicleIndex" => attackerVehicleIndex,
+        "attackerGunBurstCount" => attackerGunBurstCount,
+        "equipmentID" => equipmentID,
+        "attackerType" => attackerType,
+        "attackerSiegeState" => attackerSiegeState,
+        "attackerMasterVehID" => attackerMasterVehID,
+        "needsCount" => needsCount,
+        "damageDistributionLowerBound" => damageDistributionLowerBound,
+        "piercingDistributionLowerBound" => piercingDistributionLowerBound,
+        "damageDistributionUpperBound" => damageDistributionUpperBound,
+        "piercingDistributionUpperBound" => piercingDistributionUpperBound,
+        "criticalHitChanceBoost" => criticalHitChanceBoost,
+        "attackerDualAccuracyState" => attackerDualAccuracyState,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -372,6 +679,13 @@ wgtk::__struct_simple_codec! {
         pub attacker: ATTACKER_INFO,
     }
 }
+wgtk::__impl_nested_property! {
+    DESTRUCTIBLE_ATTACK_INFO {
+        "hitPoint" => hitPoint,
+        "shotID" => shotID,
+        "attacker" => attacker,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -384,6 +698,16 @@ wgtk::__struct_simple_codec! {
         pub lag: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    CLIENT_STATUS_STATISTICS {
+        "ping_lt_50" => ping_lt_50,
+        "ping_51_100" => ping_51_100,
+        "ping_101_150" => ping_101_150,
+        "ping_151_400" => ping_151_400,
+        "ping_gt_400" => ping_gt_400,
+        "lag" => lag,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -396,6 +720,16 @@ wgtk::__struct_simple_codec! {
         pub prebattleID: OBJECT_ID,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_SPATIAL_INFO {
+        "vehicleID" => vehicleID,
+        "team" => team,
+        "position" => position,
+        "isAlive" => isAlive,
+        "vehClass" => vehClass,
+        "prebattleID" => prebattleID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -405,6 +739,13 @@ wgtk::__struct_simple_codec! {
         pub gunShotsSpeed: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_SYNC_ATTRS {
+        "circularVisionRadius" => circularVisionRadius,
+        "gunPiercing" => gunPiercing,
+        "gunShotsSpeed" => gunShotsSpeed,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -412,6 +753,11 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    IS_OBSERVED_BY_ENEMY {
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -421,6 +767,13 @@ wgtk::__struct_simple_codec! {
         pub timeLeft: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    SIEGE_STATE_STATUS {
+        "status" => status,
+        "endTime" => endTime,
+        "timeLeft" => timeLeft,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -428,6 +781,11 @@ wgtk::__struct_simple_codec! {
         pub status: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    BURNOUT_WARNING {
+        "status" => status,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -437,6 +795,13 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    DUAL_GUN_STATUS_TIMES {
+        "baseTime" => baseTime,
+        "timeLeft" => timeLeft,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -445,6 +810,12 @@ wgtk::__struct_simple_codec! {
         pub times: DUAL_GUN_STATUS_TIMES,
     }
 }
+wgtk::__impl_nested_property! {
+    DUAL_GUN_STATUS {
+        "status" => status,
+        "times" => times,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -456,6 +827,15 @@ wgtk::__struct_simple_codec! {
         pub repairMode: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    DESTROYED_DEVICE_IS_REPAIRING {
+        "extraIndex" => extraIndex,
+        "progress" => progress,
+        "endTime" => endTime,
+        "timeLeft" => timeLeft,
+        "repairMode" => repairMode,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -463,6 +843,11 @@ wgtk::__struct_simple_codec! {
         pub status: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    IS_OTHER_VEHICLE_DAMAGED_DEVICES_VISIBLE {
+        "status" => status,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -470,6 +855,11 @@ wgtk::__struct_simple_codec! {
         pub status: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    BURNOUT_UNAVAILABLE {
+        "status" => status,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -478,6 +868,12 @@ wgtk::__struct_simple_codec! {
         pub times: Vec<f64>,
     }
 }
+wgtk::__impl_nested_property! {
+    OVERTURN_LEVEL {
+        "level" => level,
+        "times" => times,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -489,6 +885,15 @@ wgtk::__struct_simple_codec! {
         pub endTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    IN_AOE_ZONE_STATUS {
+        "zoneID" => zoneID,
+        "equipmentID" => equipmentID,
+        "team" => team,
+        "startTime" => startTime,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -497,6 +902,12 @@ wgtk::__struct_simple_codec! {
         pub times: Vec<f64>,
     }
 }
+wgtk::__impl_nested_property! {
+    DROWN_LEVEL {
+        "level" => level,
+        "times" => times,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -507,6 +918,14 @@ wgtk::__struct_simple_codec! {
         pub count: u16,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_EVENT {
+        "eventType" => eventType,
+        "targetID" => targetID,
+        "details" => details,
+        "count" => count,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -523,6 +942,20 @@ wgtk::__struct_simple_codec! {
         pub attackReasonExtID: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_EVENTS_SUMMARY {
+        "damage" => damage,
+        "trackAssist" => trackAssist,
+        "radioAssist" => radioAssist,
+        "stunAssist" => stunAssist,
+        "smokeAssist" => smokeAssist,
+        "inspireAssist" => inspireAssist,
+        "tankings" => tankings,
+        "lastKillerID" => lastKillerID,
+        "lastDeathReasonID" => lastDeathReasonID,
+        "attackReasonExtID" => attackReasonExtID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -533,6 +966,14 @@ wgtk::__struct_simple_codec! {
         pub mode: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    REMOTE_CAMERA_DATA {
+        "time" => time,
+        "shotPoint" => shotPoint,
+        "zoom" => zoom,
+        "mode" => mode,
+    }
+}
 
 pub type STUN_INFO = f64;
 
@@ -552,6 +993,21 @@ wgtk::__struct_simple_codec! {
         pub frontLineMainDirVecs: Vec<Vec2>,
     }
 }
+wgtk::__impl_nested_property! {
+    FRONT_LINE_DATA {
+        "columnWidth" => columnWidth,
+        "frontDropPerColumn" => frontDropPerColumn,
+        "outlierFraction" => outlierFraction,
+        "outlierVerticalDistance" => outlierVerticalDistance,
+        "intrusionVerticalTolerance" => intrusionVerticalTolerance,
+        "intrusionCheckExtendBounds" => intrusionCheckExtendBounds,
+        "defenderTeam" => defenderTeam,
+        "frontEdgeExtendColumns" => frontEdgeExtendColumns,
+        "frontLineIds" => frontLineIds,
+        "frontLineBounds" => frontLineBounds,
+        "frontLineMainDirVecs" => frontLineMainDirVecs,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -560,6 +1016,12 @@ wgtk::__struct_simple_codec! {
         pub compDescrs: Vec<Vec<i32>>,
     }
 }
+wgtk::__impl_nested_property! {
+    AVATAR_AMMO_VIEWS {
+        "vehTypeCompDescrs" => vehTypeCompDescrs,
+        "compDescrs" => compDescrs,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -568,6 +1030,12 @@ wgtk::__struct_simple_codec! {
         pub purchasedAbilities: Vec<i8>,
     }
 }
+wgtk::__impl_nested_property! {
+    AVATAR_AMMO_FOR_CELL {
+        "abilitiesIDs" => abilitiesIDs,
+        "purchasedAbilities" => purchasedAbilities,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -578,6 +1046,14 @@ wgtk::__struct_simple_codec! {
         pub boostersSetups: Vec<Vec<u32>>,
     }
 }
+wgtk::__impl_nested_property! {
+    POST_PROGRESSION_SETUPS {
+        "devicesSetups" => devicesSetups,
+        "eqsSetups" => eqsSetups,
+        "shellsSetups" => shellsSetups,
+        "boostersSetups" => boostersSetups,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -592,6 +1068,18 @@ wgtk::__struct_simple_codec! {
         pub vehDisabledSetupSwitches: Vec<u8>,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_AVAILABLE_VEHICLE {
+        "compDescr" => compDescr,
+        "crewCompactDescrs" => crewCompactDescrs,
+        "settings" => settings,
+        "vehSetups" => vehSetups,
+        "vehSetupsIndexes" => vehSetupsIndexes,
+        "vehPostProgression" => vehPostProgression,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "vehDisabledSetupSwitches" => vehDisabledSetupSwitches,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -600,6 +1088,12 @@ wgtk::__struct_simple_codec! {
         pub endOfCooldownPiT: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_COOLDOWN_ITEM {
+        "vehTypeCompDescr" => vehTypeCompDescr,
+        "endOfCooldownPiT" => endOfCooldownPiT,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -608,6 +1102,12 @@ wgtk::__struct_simple_codec! {
         pub isEnemyNear: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_ZONE {
+        "position" => position,
+        "isEnemyNear" => isEnemyNear,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -621,6 +1121,17 @@ wgtk::__struct_simple_codec! {
         pub vehSetupsIndexes: Vec<u8>,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_INFO {
+        "compDescr" => compDescr,
+        "respawnType" => respawnType,
+        "autoRespawnPiT" => autoRespawnPiT,
+        "manualRespawnPiT" => manualRespawnPiT,
+        "respawnZones" => respawnZones,
+        "chosenRespawnZone" => chosenRespawnZone,
+        "vehSetupsIndexes" => vehSetupsIndexes,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -629,6 +1140,12 @@ wgtk::__struct_simple_codec! {
         pub vehTypeCompDescrs: Vec<VEH_TYPE_CD>,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_LIMITED_VEHICLES {
+        "group" => group,
+        "vehTypeCompDescrs" => vehTypeCompDescrs,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -656,6 +1173,31 @@ wgtk::__struct_simple_codec! {
         pub prestigeGradeMarkID: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    RESPAWN_INFO_VEHICLE {
+        "compDescr" => compDescr,
+        "crewCompactDescrs" => crewCompactDescrs,
+        "commanderSkinID" => commanderSkinID,
+        "marksOnGun" => marksOnGun,
+        "index" => index,
+        "position" => position,
+        "yaw" => yaw,
+        "prevGroup" => prevGroup,
+        "group" => group,
+        "policyID" => policyID,
+        "onRespawnSettings" => onRespawnSettings,
+        "ammo" => ammo,
+        "outfit" => outfit,
+        "vehPerks" => vehPerks,
+        "vehSetups" => vehSetups,
+        "vehSetupsIndexes" => vehSetupsIndexes,
+        "vehPostProgression" => vehPostProgression,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "vehDisabledSetupSwitches" => vehDisabledSetupSwitches,
+        "prestigeLevel" => prestigeLevel,
+        "prestigeGradeMarkID" => prestigeGradeMarkID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -666,6 +1208,14 @@ wgtk::__struct_simple_codec! {
         pub inactivationDelay: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    BUFF_EFFECT {
+        "radius" => radius,
+        "startTime" => startTime,
+        "endTime" => endTime,
+        "inactivationDelay" => inactivationDelay,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -676,6 +1226,14 @@ wgtk::__struct_simple_codec! {
         pub attackReasonID: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    DOT_EFFECT {
+        "endTime" => endTime,
+        "period" => period,
+        "groupId" => groupId,
+        "attackReasonID" => attackReasonID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -687,6 +1245,15 @@ wgtk::__struct_simple_codec! {
         pub inactivationEndTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    BUFF_EFFECT_INACTIVATION {
+        "senderKey" => senderKey,
+        "startTime" => startTime,
+        "endTime" => endTime,
+        "inactivationStartTime" => inactivationStartTime,
+        "inactivationEndTime" => inactivationEndTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -699,6 +1266,16 @@ wgtk::__struct_simple_codec! {
         pub isInfluenceZone: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    HOT_EFFECT {
+        "senderKey" => senderKey,
+        "startTime" => startTime,
+        "endTime" => endTime,
+        "inactivationStartTime" => inactivationStartTime,
+        "inactivationEndTime" => inactivationEndTime,
+        "isInfluenceZone" => isInfluenceZone,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -712,6 +1289,17 @@ wgtk::__struct_simple_codec! {
         pub equipmentID: u16,
     }
 }
+wgtk::__impl_nested_property! {
+    INSPIRED_EFFECT {
+        "primary" => primary,
+        "startTime" => startTime,
+        "endTime" => endTime,
+        "inactivationStartTime" => inactivationStartTime,
+        "inactivationEndTime" => inactivationEndTime,
+        "inactivationSource" => inactivationSource,
+        "equipmentID" => equipmentID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -723,6 +1311,15 @@ wgtk::__struct_simple_codec! {
         pub expiring: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    SMOKE_INFO {
+        "smokeID" => smokeID,
+        "equipmentID" => equipmentID,
+        "endTime" => endTime,
+        "team" => team,
+        "expiring" => expiring,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -733,6 +1330,14 @@ wgtk::__struct_simple_codec! {
         pub aliveOnly: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_DETECTOR_INFO {
+        "detectorID" => detectorID,
+        "point" => point,
+        "radius" => radius,
+        "aliveOnly" => aliveOnly,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -743,6 +1348,14 @@ wgtk::__struct_simple_codec! {
         pub leftTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    COOLDOWN_INFO {
+        "id" => id,
+        "endTime" => endTime,
+        "baseTime" => baseTime,
+        "leftTime" => leftTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -780,6 +1393,41 @@ wgtk::__struct_simple_codec! {
         pub prestigeGradeMarkID: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    CLIENT_VEHICLE_PUBLIC_INFO {
+        "vehID" => vehID,
+        "name" => name,
+        "realName" => realName,
+        "compDescr" => compDescr,
+        "team" => team,
+        "isAlive" => isAlive,
+        "isAvatarReady" => isAvatarReady,
+        "isTeamKiller" => isTeamKiller,
+        "accountDBID" => accountDBID,
+        "clanAbbrev" => clanAbbrev,
+        "clanDBID" => clanDBID,
+        "prebattleID" => prebattleID,
+        "isPrebattleCreator" => isPrebattleCreator,
+        "forbidInBattleInvitations" => forbidInBattleInvitations,
+        "events" => events,
+        "igrType" => igrType,
+        "potapovQuestIDs" => potapovQuestIDs,
+        "potapovQuestInfo" => potapovQuestInfo,
+        "ranked" => ranked,
+        "outfit" => outfit,
+        "sessionID" => sessionID,
+        "wtr" => wtr,
+        "badges" => badges,
+        "overriddenBadge" => overriddenBadge,
+        "maxHealth" => maxHealth,
+        "vehPostProgression" => vehPostProgression,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "botDisplayStatus" => botDisplayStatus,
+        "prestigeLevel" => prestigeLevel,
+        "anonymizedPrestigeLevel" => anonymizedPrestigeLevel,
+        "prestigeGradeMarkID" => prestigeGradeMarkID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -790,6 +1438,14 @@ wgtk::__struct_simple_codec! {
         pub sessionLimit: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    PLAY_LIMITS {
+        "curfew" => curfew,
+        "weeklyPlayLimit" => weeklyPlayLimit,
+        "dailyPlayLimit" => dailyPlayLimit,
+        "sessionLimit" => sessionLimit,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -798,6 +1454,12 @@ wgtk::__struct_simple_codec! {
         pub restrictionReasonID: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_CHAT_RESTRICTION {
+        "isBattleChatDisabled" => isBattleChatDisabled,
+        "restrictionReasonID" => restrictionReasonID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -807,6 +1469,13 @@ wgtk::__struct_simple_codec! {
         pub waveDuration: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_IN_DEATHZONE {
+        "vehicleId" => vehicleId,
+        "nextStrikeTime" => nextStrikeTime,
+        "waveDuration" => waveDuration,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -816,6 +1485,13 @@ wgtk::__struct_simple_codec! {
         pub reason: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    TIME_WITH_REASON {
+        "endTime" => endTime,
+        "totalTime" => totalTime,
+        "reason" => reason,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -826,6 +1502,14 @@ wgtk::__struct_simple_codec! {
         pub lifeTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    PERK_INFO_HUD {
+        "perkID" => perkID,
+        "state" => state,
+        "coolDown" => coolDown,
+        "lifeTime" => lifeTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -834,6 +1518,12 @@ wgtk::__struct_simple_codec! {
         pub endTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    PERK_INFO_RIBBON {
+        "perkID" => perkID,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -845,6 +1535,15 @@ wgtk::__struct_simple_codec! {
         pub typeCD: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    TOURNAMEMT_PARTICIPANT {
+        "databaseID" => databaseID,
+        "name" => name,
+        "role" => role,
+        "teamID" => teamID,
+        "typeCD" => typeCD,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -855,6 +1554,14 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    STEALTH_RADAR_INFO {
+        "equipmentID" => equipmentID,
+        "isActive" => isActive,
+        "duration" => duration,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -864,6 +1571,13 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    REGENERATION_KIT_INFO {
+        "isActive" => isActive,
+        "duration" => duration,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -876,6 +1590,16 @@ wgtk::__struct_simple_codec! {
         pub deathReasonExtID: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_HEALTH_INFO {
+        "id" => id,
+        "health" => health,
+        "deathReasonID" => deathReasonID,
+        "isCrewActive" => isCrewActive,
+        "isRespawnActive" => isRespawnActive,
+        "deathReasonExtID" => deathReasonExtID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -885,6 +1609,13 @@ wgtk::__struct_simple_codec! {
         pub dispersion: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    GUN_MARKER {
+        "gunPosition" => gunPosition,
+        "shotVector" => shotVector,
+        "dispersion" => dispersion,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -896,6 +1627,15 @@ wgtk::__struct_simple_codec! {
         pub angVelocity: Vec3,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_ATTACKER_SPOTTED {
+        "position" => position,
+        "rotation" => rotation,
+        "gunAngles" => gunAngles,
+        "velocity" => velocity,
+        "angVelocity" => angVelocity,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -904,6 +1644,12 @@ wgtk::__struct_simple_codec! {
         pub vehicleType: AutoString,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_ATTACKER_UNSPOTTED {
+        "health" => health,
+        "vehicleType" => vehicleType,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -913,6 +1659,13 @@ wgtk::__struct_simple_codec! {
         pub unspottedData: KILL_CAM_ATTACKER_UNSPOTTED,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_ATTACKER {
+        "attackerID" => attackerID,
+        "spottedData" => spottedData,
+        "unspottedData" => unspottedData,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -927,6 +1680,18 @@ wgtk::__struct_simple_codec! {
         pub causeOfDeath: AutoString,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_VICTIM {
+        "position" => position,
+        "rotation" => rotation,
+        "health" => health,
+        "relativeArmor" => relativeArmor,
+        "victimIsNotSpotted" => victimIsNotSpotted,
+        "siegeState" => siegeState,
+        "damageStickers" => damageStickers,
+        "causeOfDeath" => causeOfDeath,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -964,6 +1729,41 @@ wgtk::__struct_simple_codec! {
         pub hasDistanceFalloff: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_PROJECTILE_UNSPOTTED {
+        "shotID" => shotID,
+        "trajectoryData" => trajectoryData,
+        "gravity" => gravity,
+        "impactPoint" => impactPoint,
+        "isExplosion" => isExplosion,
+        "explosionRadius" => explosionRadius,
+        "nominalArmor" => nominalArmor,
+        "angleGain" => angleGain,
+        "hitAngleCos" => hitAngleCos,
+        "triNormal" => triNormal,
+        "hasProjectilePierced" => hasProjectilePierced,
+        "hasNonPiercedDamage" => hasNonPiercedDamage,
+        "ricochetCount" => ricochetCount,
+        "ricochetAngleCos" => ricochetAngleCos,
+        "effectiveShellDamage" => effectiveShellDamage,
+        "damageRandomizationFactor" => damageRandomizationFactor,
+        "is2CaliberRuleActive" => is2CaliberRuleActive,
+        "is3CaliberRuleActive" => is3CaliberRuleActive,
+        "impactType" => impactType,
+        "maxPenetrationAngle" => maxPenetrationAngle,
+        "armorProtectionHE" => armorProtectionHE,
+        "spallLinerProtectionHE" => spallLinerProtectionHE,
+        "velocity" => velocity,
+        "shellCompDescr" => shellCompDescr,
+        "segments" => segments,
+        "piercingPower" => piercingPower,
+        "nominalPiercingPower" => nominalPiercingPower,
+        "shellDamageBurstHE" => shellDamageBurstHE,
+        "distanceLossHE" => distanceLossHE,
+        "averageDamageOfShell" => averageDamageOfShell,
+        "hasDistanceFalloff" => hasDistanceFalloff,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -972,6 +1772,12 @@ wgtk::__struct_simple_codec! {
         pub damageDistanceModifier: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_PROJECTILE_SPOTTED {
+        "distanceOfShot" => distanceOfShot,
+        "damageDistanceModifier" => damageDistanceModifier,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -980,6 +1786,12 @@ wgtk::__struct_simple_codec! {
         pub spottedData: KILL_CAM_PROJECTILE_SPOTTED,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_PROJECTILE {
+        "unspottedData" => unspottedData,
+        "spottedData" => spottedData,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -990,6 +1802,14 @@ wgtk::__struct_simple_codec! {
         pub statusCode: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    KILL_CAM_DATA {
+        "attacker" => attacker,
+        "victim" => victim,
+        "projectile" => projectile,
+        "statusCode" => statusCode,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -999,6 +1819,13 @@ wgtk::__struct_simple_codec! {
         pub isDebris: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    TRACK_STATE {
+        "isBroken" => isBroken,
+        "hitPoint" => hitPoint,
+        "isDebris" => isDebris,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1013,6 +1840,18 @@ wgtk::__struct_simple_codec! {
         pub attackReasonID: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_HIT_DIRECTION {
+        "hitDirYaw" => hitDirYaw,
+        "attackerID" => attackerID,
+        "damage" => damage,
+        "crits" => crits,
+        "isBlocked" => isBlocked,
+        "isShellHE" => isShellHE,
+        "damagedID" => damagedID,
+        "attackReasonID" => attackReasonID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1026,6 +1865,17 @@ wgtk::__struct_simple_codec! {
         pub clipTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_CLIP_RELOAD_TIME {
+        "endTime" => endTime,
+        "baseTime" => baseTime,
+        "timeLeft" => timeLeft,
+        "firstTime" => firstTime,
+        "stunned" => stunned,
+        "isBoostApplicable" => isBoostApplicable,
+        "clipTime" => clipTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1036,6 +1886,14 @@ wgtk::__struct_simple_codec! {
         pub clipTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_GUN_RELOAD_TIME {
+        "endTime" => endTime,
+        "baseTime" => baseTime,
+        "timeLeft" => timeLeft,
+        "clipTime" => clipTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1046,6 +1904,14 @@ wgtk::__struct_simple_codec! {
         pub rotationSpeed: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    OWN_VEHICLE_POSITION {
+        "position" => position,
+        "direction" => direction,
+        "speed" => speed,
+        "rotationSpeed" => rotationSpeed,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1053,6 +1919,11 @@ wgtk::__struct_simple_codec! {
         pub targetID: OBJECT_ID,
     }
 }
+wgtk::__impl_nested_property! {
+    TARGET_VEHICLE_ID {
+        "targetID" => targetID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1062,6 +1933,13 @@ wgtk::__struct_simple_codec! {
         pub cooldowns: Vec<COOLDOWN_INFO>,
     }
 }
+wgtk::__impl_nested_property! {
+    DUAL_GUN_STATE {
+        "activeGun" => activeGun,
+        "gunStates" => gunStates,
+        "cooldowns" => cooldowns,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1075,6 +1953,17 @@ wgtk::__struct_simple_codec! {
         pub index: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_AMMO {
+        "compactDescr" => compactDescr,
+        "quantity" => quantity,
+        "quantityInClip" => quantityInClip,
+        "previousStage" => previousStage,
+        "endTime" => endTime,
+        "totalTime" => totalTime,
+        "index" => index,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1086,6 +1975,15 @@ wgtk::__struct_simple_codec! {
         pub damageExtIndex: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_DAMAGE_INFO {
+        "extraIndex" => extraIndex,
+        "damageIndex" => damageIndex,
+        "entityID" => entityID,
+        "equipmentID" => equipmentID,
+        "damageExtIndex" => damageExtIndex,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1094,6 +1992,12 @@ wgtk::__struct_simple_codec! {
         pub isOn: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_OPTIONAL_DEVICE_STATUS {
+        "deviceID" => deviceID,
+        "isOn" => isOn,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1103,6 +2007,13 @@ wgtk::__struct_simple_codec! {
         pub value: i32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLE_SETTING {
+        "vehicleID" => vehicleID,
+        "code" => code,
+        "value" => value,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1118,6 +2029,19 @@ wgtk::__struct_simple_codec! {
         pub aimingTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    TARGETING_INFO {
+        "turretYaw" => turretYaw,
+        "gunPitch" => gunPitch,
+        "maxTurretRotationSpeed" => maxTurretRotationSpeed,
+        "maxGunRotationSpeed" => maxGunRotationSpeed,
+        "shotDispMultiplierFactor" => shotDispMultiplierFactor,
+        "gunShotDispersionFactorsTurretRotation" => gunShotDispersionFactorsTurretRotation,
+        "chassisShotDispersionFactorsMovement" => chassisShotDispersionFactorsMovement,
+        "chassisShotDispersionFactorsRotation" => chassisShotDispersionFactorsRotation,
+        "aimingTime" => aimingTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1126,6 +2050,12 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_EVENTS {
+        "events" => events,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1138,6 +2068,16 @@ wgtk::__struct_simple_codec! {
         pub actionDuration: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    WELCOME_TO_SECTOR {
+        "sectorID" => sectorID,
+        "groupID" => groupID,
+        "groupState" => groupState,
+        "goodGroup" => goodGroup,
+        "actionTime" => actionTime,
+        "actionDuration" => actionDuration,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1145,6 +2085,11 @@ wgtk::__struct_simple_codec! {
         pub sectorID: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    SECTOR_SHOOTING {
+        "sectorID" => sectorID,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1160,6 +2105,19 @@ wgtk::__struct_simple_codec! {
         pub isEndOfFlight: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    PLANE_TRAJECTORY {
+        "equipmentID" => equipmentID,
+        "team" => team,
+        "curTime" => curTime,
+        "curPos" => curPos,
+        "curDir" => curDir,
+        "nextTime" => nextTime,
+        "nextPos" => nextPos,
+        "nextDir" => nextDir,
+        "isEndOfFlight" => isEndOfFlight,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1171,6 +2129,15 @@ wgtk::__struct_simple_codec! {
         pub startTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    FIRE_INFO {
+        "deviceExtraIndex" => deviceExtraIndex,
+        "notificationIndex" => notificationIndex,
+        "attackerID" => attackerID,
+        "equipmentID" => equipmentID,
+        "startTime" => startTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1179,6 +2146,12 @@ wgtk::__struct_simple_codec! {
         pub finishTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    DEBUFF_INFO {
+        "attackReasonID" => attackReasonID,
+        "finishTime" => finishTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1189,6 +2162,14 @@ wgtk::__struct_simple_codec! {
         pub reuseCount: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    ROCKET_ACCELERATION_STATE_STATUS {
+        "status" => status,
+        "endTime" => endTime,
+        "timeLeft" => timeLeft,
+        "reuseCount" => reuseCount,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1201,6 +2182,16 @@ wgtk::__struct_simple_codec! {
         pub maxShotDispersion: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    AUTO_SHOOT_GUN_STATE_STATUS {
+        "state" => state,
+        "stateActivationTime" => stateActivationTime,
+        "dispersionFactor" => dispersionFactor,
+        "updateTime" => updateTime,
+        "shotDispersionPerSec" => shotDispersionPerSec,
+        "maxShotDispersion" => maxShotDispersion,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1210,6 +2201,13 @@ wgtk::__struct_simple_codec! {
         pub isPercentage: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    GOODIE_RESOURCE {
+        "type" => r#type,
+        "value" => value,
+        "isPercentage" => isPercentage,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1220,6 +2218,14 @@ wgtk::__struct_simple_codec! {
         pub expirations: Python,
     }
 }
+wgtk::__impl_nested_property! {
+    GOODIE_STATE_INFO {
+        "state" => state,
+        "finishTime" => finishTime,
+        "count" => count,
+        "expirations" => expirations,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1231,6 +2237,15 @@ wgtk::__struct_simple_codec! {
         pub stateInfo: GOODIE_STATE_INFO,
     }
 }
+wgtk::__impl_nested_property! {
+    BATTLE_GOODIE_RECORD {
+        "goodieID" => goodieID,
+        "lifetime" => lifetime,
+        "useby" => useby,
+        "resource" => resource,
+        "stateInfo" => stateInfo,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1239,6 +2254,12 @@ wgtk::__struct_simple_codec! {
         pub progress: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    ROLE_EQUIPMENT_STATE {
+        "level" => level,
+        "progress" => progress,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1248,6 +2269,13 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    STATUS_WITH_TIME_INTERVAL {
+        "statusID" => statusID,
+        "startTime" => startTime,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1256,6 +2284,12 @@ wgtk::__struct_simple_codec! {
         pub endTime: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    TIME_INTERVAL {
+        "startTime" => startTime,
+        "endTime" => endTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1265,6 +2299,13 @@ wgtk::__struct_simple_codec! {
         pub isSourceVehicle: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    STATE_WITH_TIME_INTERVAL {
+        "stateID" => stateID,
+        "timeInterval" => timeInterval,
+        "isSourceVehicle" => isSourceVehicle,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1276,6 +2317,15 @@ wgtk::__struct_simple_codec! {
         pub stage: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    VISUAL_SCRIPT_EQUIPMENT_STATE {
+        "quantity" => quantity,
+        "endTime" => endTime,
+        "totalTime" => totalTime,
+        "prevStage" => prevStage,
+        "stage" => stage,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1283,6 +2333,11 @@ wgtk::__struct_simple_codec! {
         pub stage: u8,
     }
 }
+wgtk::__impl_nested_property! {
+    VISUAL_SCRIPT_EQUIPMENT_PUBLIC_STATE {
+        "stage" => stage,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1293,6 +2348,14 @@ wgtk::__struct_simple_codec! {
         pub isElite: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    SPAWN_AVAILABLE_VEHICLE {
+        "compDescr" => compDescr,
+        "settings" => settings,
+        "isRent" => isRent,
+        "isElite" => isElite,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1301,6 +2364,12 @@ wgtk::__struct_simple_codec! {
         pub number: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    SPAWN_POINT_INFO {
+        "vehicleID" => vehicleID,
+        "number" => number,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1309,6 +2378,12 @@ wgtk::__struct_simple_codec! {
         pub position: Vec2,
     }
 }
+wgtk::__impl_nested_property! {
+    SPAWN_KEY_POINT {
+        "guid" => guid,
+        "position" => position,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1318,6 +2393,13 @@ wgtk::__struct_simple_codec! {
         pub placed: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    TEAM_SPAWN_KEY_POINT {
+        "vehID" => vehID,
+        "guid" => guid,
+        "placed" => placed,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1326,6 +2408,12 @@ wgtk::__struct_simple_codec! {
         pub state: BOOL,
     }
 }
+wgtk::__impl_nested_property! {
+    GAME_OBJECT_STATE {
+        "id" => id,
+        "state" => state,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1335,6 +2423,13 @@ wgtk::__struct_simple_codec! {
         pub end: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    GAME_OBJECT_ACTIVATION {
+        "id" => id,
+        "start" => start,
+        "end" => end,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1346,6 +2441,15 @@ wgtk::__struct_simple_codec! {
         pub segments: Vec<Vec<u16>>,
     }
 }
+wgtk::__impl_nested_property! {
+    POLYGON_TRIGGER_PARAMS {
+        "step" => step,
+        "origin" => origin,
+        "dimensions" => dimensions,
+        "verts" => verts,
+        "segments" => segments,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1354,6 +2458,12 @@ wgtk::__struct_simple_codec! {
         pub position: Vec2,
     }
 }
+wgtk::__impl_nested_property! {
+    SPAWNGROUP_INFO {
+        "name" => name,
+        "position" => position,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1364,6 +2474,14 @@ wgtk::__struct_simple_codec! {
         pub lockedLives: i8,
     }
 }
+wgtk::__impl_nested_property! {
+    TEAM_LIVES {
+        "vehicleID" => vehicleID,
+        "lives" => lives,
+        "usedLives" => usedLives,
+        "lockedLives" => lockedLives,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1375,6 +2493,15 @@ wgtk::__struct_simple_codec! {
         pub numVehiclesAffected: i16,
     }
 }
+wgtk::__impl_nested_property! {
+    DEATH_INFO {
+        "victimID" => victimID,
+        "killerID" => killerID,
+        "reasonID" => reasonID,
+        "equipmentID" => equipmentID,
+        "numVehiclesAffected" => numVehiclesAffected,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1413,6 +2540,42 @@ wgtk::__struct_simple_codec! {
         pub __generation: u32,
     }
 }
+wgtk::__impl_nested_property! {
+    VEHICLES_INFO {
+        "vehicleID" => vehicleID,
+        "isAlive" => isAlive,
+        "outfitCD" => outfitCD,
+        "compDescr" => compDescr,
+        "fakeName" => fakeName,
+        "name" => name,
+        "team" => team,
+        "isAvatarReady" => isAvatarReady,
+        "isTeamKiller" => isTeamKiller,
+        "accountDBID" => accountDBID,
+        "clanAbbrev" => clanAbbrev,
+        "clanDBID" => clanDBID,
+        "prebattleID" => prebattleID,
+        "isPrebattleCreator" => isPrebattleCreator,
+        "forbidInBattleInvitations" => forbidInBattleInvitations,
+        "igrType" => igrType,
+        "avatarSessionID" => avatarSessionID,
+        "overriddenBadge" => overriddenBadge,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "botDisplayStatus" => botDisplayStatus,
+        "teamPanelMode" => teamPanelMode,
+        "maxHealth" => maxHealth,
+        "prestigeLevel" => prestigeLevel,
+        "prestigeGradeMarkID" => prestigeGradeMarkID,
+        "vehPostProgression" => vehPostProgression,
+        "personalMissionIDs" => personalMissionIDs,
+        "personalMissionInfo" => personalMissionInfo,
+        "events" => events,
+        "badges" => badges,
+        "ranked" => ranked,
+        "deathInfo" => deathInfo,
+        "__generation" => __generation,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1421,6 +2584,12 @@ wgtk::__struct_simple_codec! {
         pub zoomLevel: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    PVE_MINIMAP_DATA {
+        "minimapBorders" => minimapBorders,
+        "zoomLevel" => zoomLevel,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1432,6 +2601,15 @@ wgtk::__struct_simple_codec! {
         pub lastVisiblePosition: Vec3,
     }
 }
+wgtk::__impl_nested_property! {
+    PVE_TARGET_MARKER {
+        "settingId" => settingId,
+        "targetId" => targetId,
+        "visibleStyle" => visibleStyle,
+        "invisibleStyle" => invisibleStyle,
+        "lastVisiblePosition" => lastVisiblePosition,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1445,6 +2623,17 @@ wgtk::__struct_simple_codec! {
         pub killCount: i16,
     }
 }
+wgtk::__impl_nested_property! {
+    COMP7_PLAYER_STATS {
+        "damageDone" => damageDone,
+        "damageBlocked" => damageBlocked,
+        "damageAssisted" => damageAssisted,
+        "spottedCount" => spottedCount,
+        "shotCount" => shotCount,
+        "hitCount" => hitCount,
+        "killCount" => killCount,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1458,6 +2647,17 @@ wgtk::__struct_simple_codec! {
         pub progress: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    COMP7_EQUIPMENT {
+        "name" => name,
+        "compactDescr" => compactDescr,
+        "stage" => stage,
+        "endTime" => endTime,
+        "totalTime" => totalTime,
+        "level" => level,
+        "progress" => progress,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1471,6 +2671,17 @@ wgtk::__struct_simple_codec! {
         pub switchTotalTime: f64,
     }
 }
+wgtk::__impl_nested_property! {
+    GUN_RELOAD_INFO {
+        "activeGun" => activeGun,
+        "clipSize" => clipSize,
+        "clipLeft" => clipLeft,
+        "reloadEndTime" => reloadEndTime,
+        "reloadTotalTime" => reloadTotalTime,
+        "switchEndTime" => switchEndTime,
+        "switchTotalTime" => switchTotalTime,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1509,6 +2720,42 @@ wgtk::__struct_simple_codec! {
         pub playerStats: COMP7_PLAYER_STATS,
     }
 }
+wgtk::__impl_nested_property! {
+    COMP7_VEHICLE_INFO {
+        "vehicleID" => vehicleID,
+        "accountDBID" => accountDBID,
+        "name" => name,
+        "fakeName" => fakeName,
+        "team" => team,
+        "clanDBID" => clanDBID,
+        "clanAbbrev" => clanAbbrev,
+        "outfitCD" => outfitCD,
+        "compDescr" => compDescr,
+        "maxHealth" => maxHealth,
+        "curHealth" => curHealth,
+        "isAlive" => isAlive,
+        "deathInfo" => deathInfo,
+        "gunReloadInfo" => gunReloadInfo,
+        "criticalDevices" => criticalDevices,
+        "destroyedDevices" => destroyedDevices,
+        "injuredTankmen" => injuredTankmen,
+        "vehicleAmmoList" => vehicleAmmoList,
+        "consumableEquipment" => consumableEquipment,
+        "deviceEquipment" => deviceEquipment,
+        "boosterEquipment" => boosterEquipment,
+        "poiEquipment" => poiEquipment,
+        "roleEquipment" => roleEquipment,
+        "isInspired" => isInspired,
+        "isHealing" => isHealing,
+        "isStunned" => isStunned,
+        "isOnFire" => isOnFire,
+        "isVisible" => isVisible,
+        "isAmmoBayDestroyed" => isAmmoBayDestroyed,
+        "attackerSetOnFire" => attackerSetOnFire,
+        "attackerAmmoBayDestroyed" => attackerAmmoBayDestroyed,
+        "playerStats" => playerStats,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1519,6 +2766,14 @@ wgtk::__struct_simple_codec! {
         pub invader: OBJECT_ID,
     }
 }
+wgtk::__impl_nested_property! {
+    COMP7_POI_INFO {
+        "id" => id,
+        "status" => status,
+        "type" => r#type,
+        "invader" => invader,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1527,6 +2782,12 @@ wgtk::__struct_simple_codec! {
         pub capturePoints: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    INVADER_CAPTURE_POINTS {
+        "vehicleID" => vehicleID,
+        "capturePoints" => capturePoints,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1540,6 +2801,17 @@ wgtk::__struct_simple_codec! {
         pub invaderCapturePoints: Vec<INVADER_CAPTURE_POINTS>,
     }
 }
+wgtk::__impl_nested_property! {
+    COMP7_BASE_INFO {
+        "id" => id,
+        "baseID" => baseID,
+        "teamID" => teamID,
+        "status" => status,
+        "capturePoints" => capturePoints,
+        "captureTimeLeft" => captureTimeLeft,
+        "invaderCapturePoints" => invaderCapturePoints,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1547,6 +2819,11 @@ wgtk::__struct_simple_codec! {
         pub equipment: Vec<AutoString>,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON79 {
+        "equipment" => equipment,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1555,6 +2832,12 @@ wgtk::__struct_simple_codec! {
         pub width: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON172 {
+        "points" => points,
+        "width" => width,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1563,6 +2846,12 @@ wgtk::__struct_simple_codec! {
         pub size: Vec3,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON174 {
+        "position" => position,
+        "size" => size,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1571,6 +2860,12 @@ wgtk::__struct_simple_codec! {
         pub radius: Vec3,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON176 {
+        "position" => position,
+        "radius" => radius,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1581,6 +2876,14 @@ wgtk::__struct_simple_codec! {
         pub textSize: f32,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON178 {
+        "position" => position,
+        "text" => text,
+        "color" => color,
+        "textSize" => textSize,
+    }
+}
 
 wgtk::__struct_simple_codec! {
     #[derive(Debug)]
@@ -1594,3 +2897,14 @@ wgtk::__struct_simple_codec! {
         pub texts: Vec<ANON178>,
     }
 }
+wgtk::__impl_nested_property! {
+    ANON180 {
+        "name" => name,
+        "version" => version,
+        "destroyTime" => destroyTime,
+        "lines" => lines,
+        "cubes" => cubes,
+        "spheres" => spheres,
+        "texts" => texts,
+    }
+}
@@ -1,4 +1,5 @@
-use wgtk::net::app::common::entity::{Entity, SimpleEntity};
+use wgtk::net::app::common::entity::{Entity, SimpleEntity, Properties, DirtyProperties};
+use wgtk::net::element::ElementLength;
 
 use super::alias::*;
 use super::interface::*;
@@ -28,24 +29,24 @@ wgtk::__struct_simple_codec! {  // Methods on client
 
     #[derive(Debug)]
     pub struct Account_onEnqueued {
-        pub queue_type: u8,
+        pub queue_type: QueueType,
     }
 
     #[derive(Debug)]
     pub struct Account_onEnqueueFailure {
-        pub queue_type: u8,
+        pub queue_type: QueueType,
         pub error_code: u8,
         pub error_str: AutoString,
     }
 
     #[derive(Debug)]
     pub struct Account_onDequeued {
-        pub queue_type: u8,
+        pub queue_type: QueueType,
     }
 
     #[derive(Debug)]
     pub struct Account_onKickedFromQueue {
-        pub queue_type: u8,
+        pub queue_type: QueueType,
     }
 
     #[derive(Debug)]
@@ -369,6 +370,56 @@ impl SimpleEntity for Account {
     type CellMethod = Account_Cell;
 }
 
+impl Account {
+    pub fn requiredVersion_12610(&self) -> &AutoString { &self.i_AccountVersion.requiredVersion_12610 }
+    pub fn set_requiredVersion_12610(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.i_AccountVersion.requiredVersion_12610 = value; dirty.mark(0); }
+    pub fn name(&self) -> &AutoString { &self.name }
+    pub fn set_name(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.name = value; dirty.mark(1); }
+    pub fn incarnationID(&self) -> &u64 { &self.incarnationID }
+    pub fn set_incarnationID(&mut self, value: u64, dirty: &mut DirtyProperties) { self.incarnationID = value; dirty.mark(2); }
+    pub fn initialServerSettings(&self) -> &Python { &self.initialServerSettings }
+    pub fn set_initialServerSettings(&mut self, value: Python, dirty: &mut DirtyProperties) { self.initialServerSettings = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Account_Property {
+        requiredVersion_12610(0x00, AutoString, var16),
+        name(0x01, AutoString, var16),
+        incarnationID(0x02, u64, 8),
+        initialServerSettings(0x03, Python, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Account {
+        "requiredVersion_12610" => i_AccountVersion.requiredVersion_12610,
+        "name" => name,
+        "incarnationID" => incarnationID,
+        "initialServerSettings" => initialServerSettings,
+    }
+}
+
+impl Properties for Account {
+    type Property = Account_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            2 => ElementLength::Fixed(8),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.i_AccountVersion.requiredVersion_12610, write, &()),
+            1 => Codec::<()>::write(&self.name, write, &()),
+            2 => Codec::<()>::write(&self.incarnationID, write, &()),
+            3 => Codec::<()>::write(&self.initialServerSettings, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======              Avatar              ====== //
 // ============================================== //
@@ -1065,6 +1116,197 @@ impl SimpleEntity for Avatar {
     type CellMethod = Avatar_Cell;
 }
 
+impl Avatar {
+    pub fn remoteCamera(&self) -> &REMOTE_CAMERA_DATA { &self.i_AvatarObserver.remoteCamera }
+    pub fn set_remoteCamera(&mut self, value: REMOTE_CAMERA_DATA, dirty: &mut DirtyProperties) { self.i_AvatarObserver.remoteCamera = value; dirty.mark(0); }
+    pub fn isObserverFPV(&self) -> &BOOL { &self.i_AvatarObserver.isObserverFPV }
+    pub fn set_isObserverFPV(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.i_AvatarObserver.isObserverFPV = value; dirty.mark(1); }
+    pub fn numOfObservers(&self) -> &u8 { &self.i_AvatarObserver.numOfObservers }
+    pub fn set_numOfObservers(&mut self, value: u8, dirty: &mut DirtyProperties) { self.i_AvatarObserver.numOfObservers = value; dirty.mark(2); }
+    pub fn name(&self) -> &AutoString { &self.name }
+    pub fn set_name(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.name = value; dirty.mark(3); }
+    pub fn sessionID(&self) -> &AutoString { &self.sessionID }
+    pub fn set_sessionID(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.sessionID = value; dirty.mark(4); }
+    pub fn arenaUniqueID(&self) -> &u64 { &self.arenaUniqueID }
+    pub fn set_arenaUniqueID(&mut self, value: u64, dirty: &mut DirtyProperties) { self.arenaUniqueID = value; dirty.mark(5); }
+    pub fn arenaTypeID(&self) -> &i32 { &self.arenaTypeID }
+    pub fn set_arenaTypeID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.arenaTypeID = value; dirty.mark(6); }
+    pub fn arenaBonusType(&self) -> &u8 { &self.arenaBonusType }
+    pub fn set_arenaBonusType(&mut self, value: u8, dirty: &mut DirtyProperties) { self.arenaBonusType = value; dirty.mark(7); }
+    pub fn arenaGuiType(&self) -> &u8 { &self.arenaGuiType }
+    pub fn set_arenaGuiType(&mut self, value: u8, dirty: &mut DirtyProperties) { self.arenaGuiType = value; dirty.mark(8); }
+    pub fn arenaExtraData(&self) -> &Python { &self.arenaExtraData }
+    pub fn set_arenaExtraData(&mut self, value: Python, dirty: &mut DirtyProperties) { self.arenaExtraData = value; dirty.mark(9); }
+    pub fn weatherPresetID(&self) -> &u8 { &self.weatherPresetID }
+    pub fn set_weatherPresetID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.weatherPresetID = value; dirty.mark(10); }
+    pub fn denunciationsLeft(&self) -> &i16 { &self.denunciationsLeft }
+    pub fn set_denunciationsLeft(&mut self, value: i16, dirty: &mut DirtyProperties) { self.denunciationsLeft = value; dirty.mark(11); }
+    pub fn clientCtx(&self) -> &AutoString { &self.clientCtx }
+    pub fn set_clientCtx(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.clientCtx = value; dirty.mark(12); }
+    pub fn tkillIsSuspected(&self) -> &BOOL { &self.tkillIsSuspected }
+    pub fn set_tkillIsSuspected(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.tkillIsSuspected = value; dirty.mark(13); }
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(14); }
+    pub fn playerVehicleID(&self) -> &OBJECT_ID { &self.playerVehicleID }
+    pub fn set_playerVehicleID(&mut self, value: OBJECT_ID, dirty: &mut DirtyProperties) { self.playerVehicleID = value; dirty.mark(15); }
+    pub fn isObserverBothTeams(&self) -> &BOOL { &self.isObserverBothTeams }
+    pub fn set_isObserverBothTeams(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isObserverBothTeams = value; dirty.mark(16); }
+    pub fn observableTeamID(&self) -> &u8 { &self.observableTeamID }
+    pub fn set_observableTeamID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.observableTeamID = value; dirty.mark(17); }
+    pub fn isGunLocked(&self) -> &BOOL { &self.isGunLocked }
+    pub fn set_isGunLocked(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isGunLocked = value; dirty.mark(18); }
+    pub fn ownVehicleGear(&self) -> &u8 { &self.ownVehicleGear }
+    pub fn set_ownVehicleGear(&mut self, value: u8, dirty: &mut DirtyProperties) { self.ownVehicleGear = value; dirty.mark(19); }
+    pub fn ownVehicleAuxPhysicsData(&self) -> &u64 { &self.ownVehicleAuxPhysicsData }
+    pub fn set_ownVehicleAuxPhysicsData(&mut self, value: u64, dirty: &mut DirtyProperties) { self.ownVehicleAuxPhysicsData = value; dirty.mark(20); }
+    pub fn ownVehicleHullAimingPitchPacked(&self) -> &u16 { &self.ownVehicleHullAimingPitchPacked }
+    pub fn set_ownVehicleHullAimingPitchPacked(&mut self, value: u16, dirty: &mut DirtyProperties) { self.ownVehicleHullAimingPitchPacked = value; dirty.mark(21); }
+    pub fn ammoViews(&self) -> &AVATAR_AMMO_VIEWS { &self.ammoViews }
+    pub fn set_ammoViews(&mut self, value: AVATAR_AMMO_VIEWS, dirty: &mut DirtyProperties) { self.ammoViews = value; dirty.mark(22); }
+    pub fn customizationDisplayType(&self) -> &u8 { &self.customizationDisplayType }
+    pub fn set_customizationDisplayType(&mut self, value: u8, dirty: &mut DirtyProperties) { self.customizationDisplayType = value; dirty.mark(23); }
+    pub fn playLimits(&self) -> &PLAY_LIMITS { &self.playLimits }
+    pub fn set_playLimits(&mut self, value: PLAY_LIMITS, dirty: &mut DirtyProperties) { self.playLimits = value; dirty.mark(24); }
+    pub fn battleChatRestriction(&self) -> &BATTLE_CHAT_RESTRICTION { &self.battleChatRestriction }
+    pub fn set_battleChatRestriction(&mut self, value: BATTLE_CHAT_RESTRICTION, dirty: &mut DirtyProperties) { self.battleChatRestriction = value; dirty.mark(25); }
+    pub fn goodiesSnapshot(&self) -> &Vec<BATTLE_GOODIE_RECORD> { &self.goodiesSnapshot }
+    pub fn set_goodiesSnapshot(&mut self, value: Vec<BATTLE_GOODIE_RECORD>, dirty: &mut DirtyProperties) { self.goodiesSnapshot = value; dirty.mark(26); }
+    pub fn shouldSendKillcamSimulationData(&self) -> &BOOL { &self.shouldSendKillcamSimulationData }
+    pub fn set_shouldSendKillcamSimulationData(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.shouldSendKillcamSimulationData = value; dirty.mark(27); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Avatar_Property {
+        remoteCamera(0x00, REMOTE_CAMERA_DATA, 22),
+        isObserverFPV(0x01, BOOL, 1),
+        numOfObservers(0x02, u8, 1),
+        name(0x03, AutoString, var16),
+        sessionID(0x04, AutoString, var16),
+        arenaUniqueID(0x05, u64, 8),
+        arenaTypeID(0x06, i32, 4),
+        arenaBonusType(0x07, u8, 1),
+        arenaGuiType(0x08, u8, 1),
+        arenaExtraData(0x09, Python, var16),
+        weatherPresetID(0x0A, u8, 1),
+        denunciationsLeft(0x0B, i16, 2),
+        clientCtx(0x0C, AutoString, var16),
+        tkillIsSuspected(0x0D, BOOL, 1),
+        team(0x0E, u8, 1),
+        playerVehicleID(0x0F, OBJECT_ID, 4),
+        isObserverBothTeams(0x10, BOOL, 1),
+        observableTeamID(0x11, u8, 1),
+        isGunLocked(0x12, BOOL, 1),
+        ownVehicleGear(0x13, u8, 1),
+        ownVehicleAuxPhysicsData(0x14, u64, 8),
+        ownVehicleHullAimingPitchPacked(0x15, u16, 2),
+        ammoViews(0x16, AVATAR_AMMO_VIEWS, var16),
+        customizationDisplayType(0x17, u8, 1),
+        playLimits(0x18, PLAY_LIMITS, 16),
+        battleChatRestriction(0x19, BATTLE_CHAT_RESTRICTION, 2),
+        goodiesSnapshot(0x1A, Vec<BATTLE_GOODIE_RECORD>, var16),
+        shouldSendKillcamSimulationData(0x1B, BOOL, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Avatar {
+        "remoteCamera" => i_AvatarObserver.remoteCamera,
+        "isObserverFPV" => i_AvatarObserver.isObserverFPV,
+        "numOfObservers" => i_AvatarObserver.numOfObservers,
+        "name" => name,
+        "sessionID" => sessionID,
+        "arenaUniqueID" => arenaUniqueID,
+        "arenaTypeID" => arenaTypeID,
+        "arenaBonusType" => arenaBonusType,
+        "arenaGuiType" => arenaGuiType,
+        "arenaExtraData" => arenaExtraData,
+        "weatherPresetID" => weatherPresetID,
+        "denunciationsLeft" => denunciationsLeft,
+        "clientCtx" => clientCtx,
+        "tkillIsSuspected" => tkillIsSuspected,
+        "team" => team,
+        "playerVehicleID" => playerVehicleID,
+        "isObserverBothTeams" => isObserverBothTeams,
+        "observableTeamID" => observableTeamID,
+        "isGunLocked" => isGunLocked,
+        "ownVehicleGear" => ownVehicleGear,
+        "ownVehicleAuxPhysicsData" => ownVehicleAuxPhysicsData,
+        "ownVehicleHullAimingPitchPacked" => ownVehicleHullAimingPitchPacked,
+        "ammoViews" => ammoViews,
+        "customizationDisplayType" => customizationDisplayType,
+        "playLimits" => playLimits,
+        "battleChatRestriction" => battleChatRestriction,
+        "goodiesSnapshot" => goodiesSnapshot,
+        "shouldSendKillcamSimulationData" => shouldSendKillcamSimulationData,
+    }
+}
+
+impl Properties for Avatar {
+    type Property = Avatar_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(22),
+            1 => ElementLength::Fixed(1),
+            2 => ElementLength::Fixed(1),
+            5 => ElementLength::Fixed(8),
+            6 => ElementLength::Fixed(4),
+            7 => ElementLength::Fixed(1),
+            8 => ElementLength::Fixed(1),
+            10 => ElementLength::Fixed(1),
+            11 => ElementLength::Fixed(2),
+            13 => ElementLength::Fixed(1),
+            14 => ElementLength::Fixed(1),
+            15 => ElementLength::Fixed(4),
+            16 => ElementLength::Fixed(1),
+            17 => ElementLength::Fixed(1),
+            18 => ElementLength::Fixed(1),
+            19 => ElementLength::Fixed(1),
+            20 => ElementLength::Fixed(8),
+            21 => ElementLength::Fixed(2),
+            23 => ElementLength::Fixed(1),
+            24 => ElementLength::Fixed(16),
+            25 => ElementLength::Fixed(2),
+            27 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.i_AvatarObserver.remoteCamera, write, &()),
+            1 => Codec::<()>::write(&self.i_AvatarObserver.isObserverFPV, write, &()),
+            2 => Codec::<()>::write(&self.i_AvatarObserver.numOfObservers, write, &()),
+            3 => Codec::<()>::write(&self.name, write, &()),
+            4 => Codec::<()>::write(&self.sessionID, write, &()),
+            5 => Codec::<()>::write(&self.arenaUniqueID, write, &()),
+            6 => Codec::<()>::write(&self.arenaTypeID, write, &()),
+            7 => Codec::<()>::write(&self.arenaBonusType, write, &()),
+            8 => Codec::<()>::write(&self.arenaGuiType, write, &()),
+            9 => Codec::<()>::write(&self.arenaExtraData, write, &()),
+            10 => Codec::<()>::write(&self.weatherPresetID, write, &()),
+            11 => Codec::<()>::write(&self.denunciationsLeft, write, &()),
+            12 => Codec::<()>::write(&self.clientCtx, write, &()),
+            13 => Codec::<()>::write(&self.tkillIsSuspected, write, &()),
+            14 => Codec::<()>::write(&self.team, write, &()),
+            15 => Codec::<()>::write(&self.playerVehicleID, write, &()),
+            16 => Codec::<()>::write(&self.isObserverBothTeams, write, &()),
+            17 => Codec::<()>::write(&self.observableTeamID, write, &()),
+            18 => Codec::<()>::write(&self.isGunLocked, write, &()),
+            19 => Codec::<()>::write(&self.ownVehicleGear, write, &()),
+            20 => Codec::<()>::write(&self.ownVehicleAuxPhysicsData, write, &()),
+            21 => Codec::<()>::write(&self.ownVehicleHullAimingPitchPacked, write, &()),
+            22 => Codec::<()>::write(&self.ammoViews, write, &()),
+            23 => Codec::<()>::write(&self.customizationDisplayType, write, &()),
+            24 => Codec::<()>::write(&self.playLimits, write, &()),
+            25 => Codec::<()>::write(&self.battleChatRestriction, write, &()),
+            26 => Codec::<()>::write(&self.goodiesSnapshot, write, &()),
+            27 => Codec::<()>::write(&self.shouldSendKillcamSimulationData, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======            ArenaInfo             ====== //
 // ============================================== //
@@ -1125,6 +1367,41 @@ impl SimpleEntity for ArenaInfo {
     type CellMethod = ArenaInfo_Cell;
 }
 
+impl ArenaInfo {
+    pub fn planeTrajectory(&self) -> &PLANE_TRAJECTORY { &self.i_PlaneTrajectoryArenaInfo.planeTrajectory }
+    pub fn set_planeTrajectory(&mut self, value: PLANE_TRAJECTORY, dirty: &mut DirtyProperties) { self.i_PlaneTrajectoryArenaInfo.planeTrajectory = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ArenaInfo_Property {
+        planeTrajectory(0x00, PLANE_TRAJECTORY, 60),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ArenaInfo {
+        "planeTrajectory" => i_PlaneTrajectoryArenaInfo.planeTrajectory,
+    }
+}
+
+impl Properties for ArenaInfo {
+    type Property = ArenaInfo_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(60),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.i_PlaneTrajectoryArenaInfo.planeTrajectory, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======      ClientSelectableObject      ====== //
 // ============================================== //
@@ -1182,6 +1459,73 @@ impl SimpleEntity for ClientSelectableObject {
     type CellMethod = ClientSelectableObject_Cell;
 }
 
+impl ClientSelectableObject {
+    pub fn modelName(&self) -> &AutoString { &self.modelName }
+    pub fn set_modelName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName = value; dirty.mark(0); }
+    pub fn selectionId(&self) -> &AutoString { &self.selectionId }
+    pub fn set_selectionId(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.selectionId = value; dirty.mark(1); }
+    pub fn mouseOverSoundName(&self) -> &AutoString { &self.mouseOverSoundName }
+    pub fn set_mouseOverSoundName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.mouseOverSoundName = value; dirty.mark(2); }
+    pub fn isOver3DSound(&self) -> &BOOL { &self.isOver3DSound }
+    pub fn set_isOver3DSound(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isOver3DSound = value; dirty.mark(3); }
+    pub fn clickSoundName(&self) -> &AutoString { &self.clickSoundName }
+    pub fn set_clickSoundName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.clickSoundName = value; dirty.mark(4); }
+    pub fn isClick3DSound(&self) -> &BOOL { &self.isClick3DSound }
+    pub fn set_isClick3DSound(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isClick3DSound = value; dirty.mark(5); }
+    pub fn edgeMode(&self) -> &u8 { &self.edgeMode }
+    pub fn set_edgeMode(&mut self, value: u8, dirty: &mut DirtyProperties) { self.edgeMode = value; dirty.mark(6); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableObject_Property {
+        modelName(0x00, AutoString, var16),
+        selectionId(0x01, AutoString, var16),
+        mouseOverSoundName(0x02, AutoString, var16),
+        isOver3DSound(0x03, BOOL, 1),
+        clickSoundName(0x04, AutoString, var16),
+        isClick3DSound(0x05, BOOL, 1),
+        edgeMode(0x06, u8, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableObject {
+        "modelName" => modelName,
+        "selectionId" => selectionId,
+        "mouseOverSoundName" => mouseOverSoundName,
+        "isOver3DSound" => isOver3DSound,
+        "clickSoundName" => clickSoundName,
+        "isClick3DSound" => isClick3DSound,
+        "edgeMode" => edgeMode,
+    }
+}
+
+impl Properties for ClientSelectableObject {
+    type Property = ClientSelectableObject_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            3 => ElementLength::Fixed(1),
+            5 => ElementLength::Fixed(1),
+            6 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.modelName, write, &()),
+            1 => Codec::<()>::write(&self.selectionId, write, &()),
+            2 => Codec::<()>::write(&self.mouseOverSoundName, write, &()),
+            3 => Codec::<()>::write(&self.isOver3DSound, write, &()),
+            4 => Codec::<()>::write(&self.clickSoundName, write, &()),
+            5 => Codec::<()>::write(&self.isClick3DSound, write, &()),
+            6 => Codec::<()>::write(&self.edgeMode, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          HangarVehicle           ====== //
 // ============================================== //
@@ -1232,6 +1576,35 @@ impl SimpleEntity for HangarVehicle {
     type CellMethod = HangarVehicle_Cell;
 }
 
+impl HangarVehicle {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum HangarVehicle_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    HangarVehicle {
+    }
+}
+
+impl Properties for HangarVehicle {
+    type Property = HangarVehicle_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======             Vehicle              ====== //
 // ============================================== //
@@ -1473,6 +1846,316 @@ impl SimpleEntity for Vehicle {
     type CellMethod = Vehicle_Cell;
 }
 
+impl Vehicle {
+    pub fn remoteCamera(&self) -> &REMOTE_CAMERA_DATA { &self.i_VehicleObserver.remoteCamera }
+    pub fn set_remoteCamera(&mut self, value: REMOTE_CAMERA_DATA, dirty: &mut DirtyProperties) { self.i_VehicleObserver.remoteCamera = value; dirty.mark(0); }
+    pub fn steeringAngles(&self) -> &Vec<u8> { &self.i_Wheels.steeringAngles }
+    pub fn set_steeringAngles(&mut self, value: Vec<u8>, dirty: &mut DirtyProperties) { self.i_Wheels.steeringAngles = value; dirty.mark(1); }
+    pub fn wheelsScroll(&self) -> &Vec<u8> { &self.i_Wheels.wheelsScroll }
+    pub fn set_wheelsScroll(&mut self, value: Vec<u8>, dirty: &mut DirtyProperties) { self.i_Wheels.wheelsScroll = value; dirty.mark(2); }
+    pub fn wheelsState(&self) -> &u64 { &self.i_Wheels.wheelsState }
+    pub fn set_wheelsState(&mut self, value: u64, dirty: &mut DirtyProperties) { self.i_Wheels.wheelsState = value; dirty.mark(3); }
+    pub fn burnoutLevel(&self) -> &u8 { &self.i_Wheels.burnoutLevel }
+    pub fn set_burnoutLevel(&mut self, value: u8, dirty: &mut DirtyProperties) { self.i_Wheels.burnoutLevel = value; dirty.mark(4); }
+    pub fn perkEffects(&self) -> &ANON79 { &self.i_Perks_Vehicle.perkEffects }
+    pub fn set_perkEffects(&mut self, value: ANON79, dirty: &mut DirtyProperties) { self.i_Perks_Vehicle.perkEffects = value; dirty.mark(5); }
+    pub fn perks(&self) -> &Vec<PERK_INFO_HUD> { &self.i_Perks_Vehicle.perks }
+    pub fn set_perks(&mut self, value: Vec<PERK_INFO_HUD>, dirty: &mut DirtyProperties) { self.i_Perks_Vehicle.perks = value; dirty.mark(6); }
+    pub fn perksRibbonNotify(&self) -> &Vec<PERK_INFO_RIBBON> { &self.i_Perks_Vehicle.perksRibbonNotify }
+    pub fn set_perksRibbonNotify(&mut self, value: Vec<PERK_INFO_RIBBON>, dirty: &mut DirtyProperties) { self.i_Perks_Vehicle.perksRibbonNotify = value; dirty.mark(7); }
+    pub fn isStrafing(&self) -> &BOOL { &self.isStrafing }
+    pub fn set_isStrafing(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isStrafing = value; dirty.mark(8); }
+    pub fn postmortemViewPointName(&self) -> &AutoString { &self.postmortemViewPointName }
+    pub fn set_postmortemViewPointName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.postmortemViewPointName = value; dirty.mark(9); }
+    pub fn isHidden(&self) -> &BOOL { &self.isHidden }
+    pub fn set_isHidden(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isHidden = value; dirty.mark(10); }
+    pub fn physicsMode(&self) -> &u8 { &self.physicsMode }
+    pub fn set_physicsMode(&mut self, value: u8, dirty: &mut DirtyProperties) { self.physicsMode = value; dirty.mark(11); }
+    pub fn siegeState(&self) -> &u8 { &self.siegeState }
+    pub fn set_siegeState(&mut self, value: u8, dirty: &mut DirtyProperties) { self.siegeState = value; dirty.mark(12); }
+    pub fn gunAnglesPacked(&self) -> &u16 { &self.gunAnglesPacked }
+    pub fn set_gunAnglesPacked(&mut self, value: u16, dirty: &mut DirtyProperties) { self.gunAnglesPacked = value; dirty.mark(13); }
+    pub fn publicInfo(&self) -> &PUBLIC_VEHICLE_INFO { &self.publicInfo }
+    pub fn set_publicInfo(&mut self, value: PUBLIC_VEHICLE_INFO, dirty: &mut DirtyProperties) { self.publicInfo = value; dirty.mark(14); }
+    pub fn health(&self) -> &i16 { &self.health }
+    pub fn set_health(&mut self, value: i16, dirty: &mut DirtyProperties) { self.health = value; dirty.mark(15); }
+    pub fn isCrewActive(&self) -> &BOOL { &self.isCrewActive }
+    pub fn set_isCrewActive(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isCrewActive = value; dirty.mark(16); }
+    pub fn engineMode(&self) -> &Box<[u8; 2]> { &self.engineMode }
+    pub fn set_engineMode(&mut self, value: Box<[u8; 2]>, dirty: &mut DirtyProperties) { self.engineMode = value; dirty.mark(17); }
+    pub fn damageStickers(&self) -> &Vec<u64> { &self.damageStickers }
+    pub fn set_damageStickers(&mut self, value: Vec<u64>, dirty: &mut DirtyProperties) { self.damageStickers = value; dirty.mark(18); }
+    pub fn publicStateModifiers(&self) -> &Vec<EXTRA_ID> { &self.publicStateModifiers }
+    pub fn set_publicStateModifiers(&mut self, value: Vec<EXTRA_ID>, dirty: &mut DirtyProperties) { self.publicStateModifiers = value; dirty.mark(19); }
+    pub fn stunInfo(&self) -> &STUN_INFO { &self.stunInfo }
+    pub fn set_stunInfo(&mut self, value: STUN_INFO, dirty: &mut DirtyProperties) { self.stunInfo = value; dirty.mark(20); }
+    pub fn crewCompactDescrs(&self) -> &Vec<AutoString> { &self.crewCompactDescrs }
+    pub fn set_crewCompactDescrs(&mut self, value: Vec<AutoString>, dirty: &mut DirtyProperties) { self.crewCompactDescrs = value; dirty.mark(21); }
+    pub fn enhancements(&self) -> &Python { &self.enhancements }
+    pub fn set_enhancements(&mut self, value: Python, dirty: &mut DirtyProperties) { self.enhancements = value; dirty.mark(22); }
+    pub fn setups(&self) -> &Python { &self.setups }
+    pub fn set_setups(&mut self, value: Python, dirty: &mut DirtyProperties) { self.setups = value; dirty.mark(23); }
+    pub fn setupsIndexes(&self) -> &Python { &self.setupsIndexes }
+    pub fn set_setupsIndexes(&mut self, value: Python, dirty: &mut DirtyProperties) { self.setupsIndexes = value; dirty.mark(24); }
+    pub fn customRoleSlotTypeId(&self) -> &u8 { &self.customRoleSlotTypeId }
+    pub fn set_customRoleSlotTypeId(&mut self, value: u8, dirty: &mut DirtyProperties) { self.customRoleSlotTypeId = value; dirty.mark(25); }
+    pub fn vehPerks(&self) -> &Python { &self.vehPerks }
+    pub fn set_vehPerks(&mut self, value: Python, dirty: &mut DirtyProperties) { self.vehPerks = value; dirty.mark(26); }
+    pub fn vehPostProgression(&self) -> &Vec<i32> { &self.vehPostProgression }
+    pub fn set_vehPostProgression(&mut self, value: Vec<i32>, dirty: &mut DirtyProperties) { self.vehPostProgression = value; dirty.mark(27); }
+    pub fn disabledSwitches(&self) -> &Vec<i32> { &self.disabledSwitches }
+    pub fn set_disabledSwitches(&mut self, value: Vec<i32>, dirty: &mut DirtyProperties) { self.disabledSwitches = value; dirty.mark(28); }
+    pub fn avatarID(&self) -> &OBJECT_ID { &self.avatarID }
+    pub fn set_avatarID(&mut self, value: OBJECT_ID, dirty: &mut DirtyProperties) { self.avatarID = value; dirty.mark(29); }
+    pub fn masterVehID(&self) -> &u32 { &self.masterVehID }
+    pub fn set_masterVehID(&mut self, value: u32, dirty: &mut DirtyProperties) { self.masterVehID = value; dirty.mark(30); }
+    pub fn arenaTypeID(&self) -> &i32 { &self.arenaTypeID }
+    pub fn set_arenaTypeID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.arenaTypeID = value; dirty.mark(31); }
+    pub fn arenaBonusType(&self) -> &u8 { &self.arenaBonusType }
+    pub fn set_arenaBonusType(&mut self, value: u8, dirty: &mut DirtyProperties) { self.arenaBonusType = value; dirty.mark(32); }
+    pub fn arenaUniqueID(&self) -> &u64 { &self.arenaUniqueID }
+    pub fn set_arenaUniqueID(&mut self, value: u64, dirty: &mut DirtyProperties) { self.arenaUniqueID = value; dirty.mark(33); }
+    pub fn inspiringEffect(&self) -> &BUFF_EFFECT { &self.inspiringEffect }
+    pub fn set_inspiringEffect(&mut self, value: BUFF_EFFECT, dirty: &mut DirtyProperties) { self.inspiringEffect = value; dirty.mark(34); }
+    pub fn healingEffect(&self) -> &BUFF_EFFECT { &self.healingEffect }
+    pub fn set_healingEffect(&mut self, value: BUFF_EFFECT, dirty: &mut DirtyProperties) { self.healingEffect = value; dirty.mark(35); }
+    pub fn dotEffect(&self) -> &DOT_EFFECT { &self.dotEffect }
+    pub fn set_dotEffect(&mut self, value: DOT_EFFECT, dirty: &mut DirtyProperties) { self.dotEffect = value; dirty.mark(36); }
+    pub fn inspired(&self) -> &INSPIRED_EFFECT { &self.inspired }
+    pub fn set_inspired(&mut self, value: INSPIRED_EFFECT, dirty: &mut DirtyProperties) { self.inspired = value; dirty.mark(37); }
+    pub fn healing(&self) -> &BUFF_EFFECT_INACTIVATION { &self.healing }
+    pub fn set_healing(&mut self, value: BUFF_EFFECT_INACTIVATION, dirty: &mut DirtyProperties) { self.healing = value; dirty.mark(38); }
+    pub fn healOverTime(&self) -> &HOT_EFFECT { &self.healOverTime }
+    pub fn set_healOverTime(&mut self, value: HOT_EFFECT, dirty: &mut DirtyProperties) { self.healOverTime = value; dirty.mark(39); }
+    pub fn debuff(&self) -> &i32 { &self.debuff }
+    pub fn set_debuff(&mut self, value: i32, dirty: &mut DirtyProperties) { self.debuff = value; dirty.mark(40); }
+    pub fn isSpeedCapturing(&self) -> &BOOL { &self.isSpeedCapturing }
+    pub fn set_isSpeedCapturing(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isSpeedCapturing = value; dirty.mark(41); }
+    pub fn isBlockingCapture(&self) -> &BOOL { &self.isBlockingCapture }
+    pub fn set_isBlockingCapture(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isBlockingCapture = value; dirty.mark(42); }
+    pub fn dogTag(&self) -> &BATTLE_DOG_TAG { &self.dogTag }
+    pub fn set_dogTag(&mut self, value: BATTLE_DOG_TAG, dirty: &mut DirtyProperties) { self.dogTag = value; dirty.mark(43); }
+    pub fn isMyVehicle(&self) -> &BOOL { &self.isMyVehicle }
+    pub fn set_isMyVehicle(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isMyVehicle = value; dirty.mark(44); }
+    pub fn quickShellChangerFactor(&self) -> &f32 { &self.quickShellChangerFactor }
+    pub fn set_quickShellChangerFactor(&mut self, value: f32, dirty: &mut DirtyProperties) { self.quickShellChangerFactor = value; dirty.mark(45); }
+    pub fn onRespawnReloadTimeFactor(&self) -> &f32 { &self.onRespawnReloadTimeFactor }
+    pub fn set_onRespawnReloadTimeFactor(&mut self, value: f32, dirty: &mut DirtyProperties) { self.onRespawnReloadTimeFactor = value; dirty.mark(46); }
+    pub fn ownVehiclePosition(&self) -> &OWN_VEHICLE_POSITION { &self.ownVehiclePosition }
+    pub fn set_ownVehiclePosition(&mut self, value: OWN_VEHICLE_POSITION, dirty: &mut DirtyProperties) { self.ownVehiclePosition = value; dirty.mark(47); }
+    pub fn enableExternalRespawn(&self) -> &BOOL { &self.enableExternalRespawn }
+    pub fn set_enableExternalRespawn(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.enableExternalRespawn = value; dirty.mark(48); }
+    pub fn botDisplayStatus(&self) -> &u8 { &self.botDisplayStatus }
+    pub fn set_botDisplayStatus(&mut self, value: u8, dirty: &mut DirtyProperties) { self.botDisplayStatus = value; dirty.mark(49); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Vehicle_Property {
+        remoteCamera(0x00, REMOTE_CAMERA_DATA, 22),
+        steeringAngles(0x01, Vec<u8>, var16),
+        wheelsScroll(0x02, Vec<u8>, var16),
+        wheelsState(0x03, u64, 8),
+        burnoutLevel(0x04, u8, 1),
+        perkEffects(0x05, ANON79, var16),
+        perks(0x06, Vec<PERK_INFO_HUD>, var16),
+        perksRibbonNotify(0x07, Vec<PERK_INFO_RIBBON>, var16),
+        isStrafing(0x08, BOOL, 1),
+        postmortemViewPointName(0x09, AutoString, var16),
+        isHidden(0x0A, BOOL, 1),
+        physicsMode(0x0B, u8, 1),
+        siegeState(0x0C, u8, 1),
+        gunAnglesPacked(0x0D, u16, 2),
+        publicInfo(0x0E, PUBLIC_VEHICLE_INFO, var16),
+        health(0x0F, i16, 2),
+        isCrewActive(0x10, BOOL, 1),
+        engineMode(0x11, Box<[u8; 2]>, 2),
+        damageStickers(0x12, Vec<u64>, var16),
+        publicStateModifiers(0x13, Vec<EXTRA_ID>, var16),
+        stunInfo(0x14, STUN_INFO, 8),
+        crewCompactDescrs(0x15, Vec<AutoString>, var16),
+        enhancements(0x16, Python, var16),
+        setups(0x17, Python, var16),
+        setupsIndexes(0x18, Python, var16),
+        customRoleSlotTypeId(0x19, u8, 1),
+        vehPerks(0x1A, Python, var16),
+        vehPostProgression(0x1B, Vec<i32>, var16),
+        disabledSwitches(0x1C, Vec<i32>, var16),
+        avatarID(0x1D, OBJECT_ID, 4),
+        masterVehID(0x1E, u32, 4),
+        arenaTypeID(0x1F, i32, 4),
+        arenaBonusType(0x20, u8, 1),
+        arenaUniqueID(0x21, u64, 8),
+        inspiringEffect(0x22, BUFF_EFFECT, 24),
+        healingEffect(0x23, BUFF_EFFECT, 24),
+        dotEffect(0x24, DOT_EFFECT, 14),
+        inspired(0x25, INSPIRED_EFFECT, 36),
+        healing(0x26, BUFF_EFFECT_INACTIVATION, var16),
+        healOverTime(0x27, HOT_EFFECT, var16),
+        debuff(0x28, i32, 4),
+        isSpeedCapturing(0x29, BOOL, 1),
+        isBlockingCapture(0x2A, BOOL, 1),
+        dogTag(0x2B, BATTLE_DOG_TAG, var16),
+        isMyVehicle(0x2C, BOOL, 1),
+        quickShellChangerFactor(0x2D, f32, 4),
+        onRespawnReloadTimeFactor(0x2E, f32, 4),
+        ownVehiclePosition(0x2F, OWN_VEHICLE_POSITION, 32),
+        enableExternalRespawn(0x30, BOOL, 1),
+        botDisplayStatus(0x31, u8, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Vehicle {
+        "remoteCamera" => i_VehicleObserver.remoteCamera,
+        "steeringAngles" => i_Wheels.steeringAngles,
+        "wheelsScroll" => i_Wheels.wheelsScroll,
+        "wheelsState" => i_Wheels.wheelsState,
+        "burnoutLevel" => i_Wheels.burnoutLevel,
+        "perkEffects" => i_Perks_Vehicle.perkEffects,
+        "perks" => i_Perks_Vehicle.perks,
+        "perksRibbonNotify" => i_Perks_Vehicle.perksRibbonNotify,
+        "isStrafing" => isStrafing,
+        "postmortemViewPointName" => postmortemViewPointName,
+        "isHidden" => isHidden,
+        "physicsMode" => physicsMode,
+        "siegeState" => siegeState,
+        "gunAnglesPacked" => gunAnglesPacked,
+        "publicInfo" => publicInfo,
+        "health" => health,
+        "isCrewActive" => isCrewActive,
+        "engineMode" => engineMode,
+        "damageStickers" => damageStickers,
+        "publicStateModifiers" => publicStateModifiers,
+        "stunInfo" => stunInfo,
+        "crewCompactDescrs" => crewCompactDescrs,
+        "enhancements" => enhancements,
+        "setups" => setups,
+        "setupsIndexes" => setupsIndexes,
+        "customRoleSlotTypeId" => customRoleSlotTypeId,
+        "vehPerks" => vehPerks,
+        "vehPostProgression" => vehPostProgression,
+        "disabledSwitches" => disabledSwitches,
+        "avatarID" => avatarID,
+        "masterVehID" => masterVehID,
+        "arenaTypeID" => arenaTypeID,
+        "arenaBonusType" => arenaBonusType,
+        "arenaUniqueID" => arenaUniqueID,
+        "inspiringEffect" => inspiringEffect,
+        "healingEffect" => healingEffect,
+        "dotEffect" => dotEffect,
+        "inspired" => inspired,
+        "healing" => healing,
+        "healOverTime" => healOverTime,
+        "debuff" => debuff,
+        "isSpeedCapturing" => isSpeedCapturing,
+        "isBlockingCapture" => isBlockingCapture,
+        "dogTag" => dogTag,
+        "isMyVehicle" => isMyVehicle,
+        "quickShellChangerFactor" => quickShellChangerFactor,
+        "onRespawnReloadTimeFactor" => onRespawnReloadTimeFactor,
+        "ownVehiclePosition" => ownVehiclePosition,
+        "enableExternalRespawn" => enableExternalRespawn,
+        "botDisplayStatus" => botDisplayStatus,
+    }
+}
+
+impl Properties for Vehicle {
+    type Property = Vehicle_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(22),
+            3 => ElementLength::Fixed(8),
+            4 => ElementLength::Fixed(1),
+            8 => ElementLength::Fixed(1),
+            10 => ElementLength::Fixed(1),
+            11 => ElementLength::Fixed(1),
+            12 => ElementLength::Fixed(1),
+            13 => ElementLength::Fixed(2),
+            15 => ElementLength::Fixed(2),
+            16 => ElementLength::Fixed(1),
+            17 => ElementLength::Fixed(2),
+            20 => ElementLength::Fixed(8),
+            25 => ElementLength::Fixed(1),
+            29 => ElementLength::Fixed(4),
+            30 => ElementLength::Fixed(4),
+            31 => ElementLength::Fixed(4),
+            32 => ElementLength::Fixed(1),
+            33 => ElementLength::Fixed(8),
+            34 => ElementLength::Fixed(24),
+            35 => ElementLength::Fixed(24),
+            36 => ElementLength::Fixed(14),
+            37 => ElementLength::Fixed(36),
+            40 => ElementLength::Fixed(4),
+            41 => ElementLength::Fixed(1),
+            42 => ElementLength::Fixed(1),
+            44 => ElementLength::Fixed(1),
+            45 => ElementLength::Fixed(4),
+            46 => ElementLength::Fixed(4),
+            47 => ElementLength::Fixed(32),
+            48 => ElementLength::Fixed(1),
+            49 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.i_VehicleObserver.remoteCamera, write, &()),
+            1 => Codec::<()>::write(&self.i_Wheels.steeringAngles, write, &()),
+            2 => Codec::<()>::write(&self.i_Wheels.wheelsScroll, write, &()),
+            3 => Codec::<()>::write(&self.i_Wheels.wheelsState, write, &()),
+            4 => Codec::<()>::write(&self.i_Wheels.burnoutLevel, write, &()),
+            5 => Codec::<()>::write(&self.i_Perks_Vehicle.perkEffects, write, &()),
+            6 => Codec::<()>::write(&self.i_Perks_Vehicle.perks, write, &()),
+            7 => Codec::<()>::write(&self.i_Perks_Vehicle.perksRibbonNotify, write, &()),
+            8 => Codec::<()>::write(&self.isStrafing, write, &()),
+            9 => Codec::<()>::write(&self.postmortemViewPointName, write, &()),
+            10 => Codec::<()>::write(&self.isHidden, write, &()),
+            11 => Codec::<()>::write(&self.physicsMode, write, &()),
+            12 => Codec::<()>::write(&self.siegeState, write, &()),
+            13 => Codec::<()>::write(&self.gunAnglesPacked, write, &()),
+            14 => Codec::<()>::write(&self.publicInfo, write, &()),
+            15 => Codec::<()>::write(&self.health, write, &()),
+            16 => Codec::<()>::write(&self.isCrewActive, write, &()),
+            17 => Codec::<()>::write(&self.engineMode, write, &()),
+            18 => Codec::<()>::write(&self.damageStickers, write, &()),
+            19 => Codec::<()>::write(&self.publicStateModifiers, write, &()),
+            20 => Codec::<()>::write(&self.stunInfo, write, &()),
+            21 => Codec::<()>::write(&self.crewCompactDescrs, write, &()),
+            22 => Codec::<()>::write(&self.enhancements, write, &()),
+            23 => Codec::<()>::write(&self.setups, write, &()),
+            24 => Codec::<()>::write(&self.setupsIndexes, write, &()),
+            25 => Codec::<()>::write(&self.customRoleSlotTypeId, write, &()),
+            26 => Codec::<()>::write(&self.vehPerks, write, &()),
+            27 => Codec::<()>::write(&self.vehPostProgression, write, &()),
+            28 => Codec::<()>::write(&self.disabledSwitches, write, &()),
+            29 => Codec::<()>::write(&self.avatarID, write, &()),
+            30 => Codec::<()>::write(&self.masterVehID, write, &()),
+            31 => Codec::<()>::write(&self.arenaTypeID, write, &()),
+            32 => Codec::<()>::write(&self.arenaBonusType, write, &()),
+            33 => Codec::<()>::write(&self.arenaUniqueID, write, &()),
+            34 => Codec::<()>::write(&self.inspiringEffect, write, &()),
+            35 => Codec::<()>::write(&self.healingEffect, write, &()),
+            36 => Codec::<()>::write(&self.dotEffect, write, &()),
+            37 => Codec::<()>::write(&self.inspired, write, &()),
+            38 => Codec::<()>::write(&self.healing, write, &()),
+            39 => Codec::<()>::write(&self.healOverTime, write, &()),
+            40 => Codec::<()>::write(&self.debuff, write, &()),
+            41 => Codec::<()>::write(&self.isSpeedCapturing, write, &()),
+            42 => Codec::<()>::write(&self.isBlockingCapture, write, &()),
+            43 => Codec::<()>::write(&self.dogTag, write, &()),
+            44 => Codec::<()>::write(&self.isMyVehicle, write, &()),
+            45 => Codec::<()>::write(&self.quickShellChangerFactor, write, &()),
+            46 => Codec::<()>::write(&self.onRespawnReloadTimeFactor, write, &()),
+            47 => Codec::<()>::write(&self.ownVehiclePosition, write, &()),
+            48 => Codec::<()>::write(&self.enableExternalRespawn, write, &()),
+            49 => Codec::<()>::write(&self.botDisplayStatus, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======        AreaDestructibles         ====== //
 // ============================================== //
@@ -1527,6 +2210,55 @@ impl SimpleEntity for AreaDestructibles {
     type CellMethod = AreaDestructibles_Cell;
 }
 
+impl AreaDestructibles {
+    pub fn destroyedModules(&self) -> &Vec<Box<[u8; 3]>> { &self.destroyedModules }
+    pub fn set_destroyedModules(&mut self, value: Vec<Box<[u8; 3]>>, dirty: &mut DirtyProperties) { self.destroyedModules = value; dirty.mark(0); }
+    pub fn destroyedFragiles(&self) -> &Vec<Box<[u8; 3]>> { &self.destroyedFragiles }
+    pub fn set_destroyedFragiles(&mut self, value: Vec<Box<[u8; 3]>>, dirty: &mut DirtyProperties) { self.destroyedFragiles = value; dirty.mark(1); }
+    pub fn fallenColumns(&self) -> &Vec<Box<[u8; 3]>> { &self.fallenColumns }
+    pub fn set_fallenColumns(&mut self, value: Vec<Box<[u8; 3]>>, dirty: &mut DirtyProperties) { self.fallenColumns = value; dirty.mark(2); }
+    pub fn fallenTrees(&self) -> &Vec<Box<[u8; 5]>> { &self.fallenTrees }
+    pub fn set_fallenTrees(&mut self, value: Vec<Box<[u8; 5]>>, dirty: &mut DirtyProperties) { self.fallenTrees = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum AreaDestructibles_Property {
+        destroyedModules(0x00, Vec<Box<[u8; 3]>>, var16),
+        destroyedFragiles(0x01, Vec<Box<[u8; 3]>>, var16),
+        fallenColumns(0x02, Vec<Box<[u8; 3]>>, var16),
+        fallenTrees(0x03, Vec<Box<[u8; 5]>>, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    AreaDestructibles {
+        "destroyedModules" => destroyedModules,
+        "destroyedFragiles" => destroyedFragiles,
+        "fallenColumns" => fallenColumns,
+        "fallenTrees" => fallenTrees,
+    }
+}
+
+impl Properties for AreaDestructibles {
+    type Property = AreaDestructibles_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.destroyedModules, write, &()),
+            1 => Codec::<()>::write(&self.destroyedFragiles, write, &()),
+            2 => Codec::<()>::write(&self.fallenColumns, write, &()),
+            3 => Codec::<()>::write(&self.fallenTrees, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          OfflineEntity           ====== //
 // ============================================== //
@@ -1577,6 +2309,35 @@ impl SimpleEntity for OfflineEntity {
     type CellMethod = OfflineEntity_Cell;
 }
 
+impl OfflineEntity {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum OfflineEntity_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    OfflineEntity {
+    }
+}
+
+impl Properties for OfflineEntity {
+    type Property = OfflineEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======              Flock               ====== //
 // ============================================== //
@@ -1642,6 +2403,123 @@ impl SimpleEntity for Flock {
     type CellMethod = Flock_Cell;
 }
 
+impl Flock {
+    pub fn modelName(&self) -> &AutoString { &self.modelName }
+    pub fn set_modelName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName = value; dirty.mark(0); }
+    pub fn modelName2(&self) -> &AutoString { &self.modelName2 }
+    pub fn set_modelName2(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName2 = value; dirty.mark(1); }
+    pub fn modelCount(&self) -> &u8 { &self.modelCount }
+    pub fn set_modelCount(&mut self, value: u8, dirty: &mut DirtyProperties) { self.modelCount = value; dirty.mark(2); }
+    pub fn yawSpeed(&self) -> &f32 { &self.yawSpeed }
+    pub fn set_yawSpeed(&mut self, value: f32, dirty: &mut DirtyProperties) { self.yawSpeed = value; dirty.mark(3); }
+    pub fn pitchSpeed(&self) -> &f32 { &self.pitchSpeed }
+    pub fn set_pitchSpeed(&mut self, value: f32, dirty: &mut DirtyProperties) { self.pitchSpeed = value; dirty.mark(4); }
+    pub fn rollSpeed(&self) -> &f32 { &self.rollSpeed }
+    pub fn set_rollSpeed(&mut self, value: f32, dirty: &mut DirtyProperties) { self.rollSpeed = value; dirty.mark(5); }
+    pub fn animSpeedMin(&self) -> &f32 { &self.animSpeedMin }
+    pub fn set_animSpeedMin(&mut self, value: f32, dirty: &mut DirtyProperties) { self.animSpeedMin = value; dirty.mark(6); }
+    pub fn animSpeedMax(&self) -> &f32 { &self.animSpeedMax }
+    pub fn set_animSpeedMax(&mut self, value: f32, dirty: &mut DirtyProperties) { self.animSpeedMax = value; dirty.mark(7); }
+    pub fn height(&self) -> &f32 { &self.height }
+    pub fn set_height(&mut self, value: f32, dirty: &mut DirtyProperties) { self.height = value; dirty.mark(8); }
+    pub fn radius(&self) -> &f32 { &self.radius }
+    pub fn set_radius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.radius = value; dirty.mark(9); }
+    pub fn deadZoneRadius(&self) -> &f32 { &self.deadZoneRadius }
+    pub fn set_deadZoneRadius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.deadZoneRadius = value; dirty.mark(10); }
+    pub fn speedAtBottom(&self) -> &f32 { &self.speedAtBottom }
+    pub fn set_speedAtBottom(&mut self, value: f32, dirty: &mut DirtyProperties) { self.speedAtBottom = value; dirty.mark(11); }
+    pub fn speedAtTop(&self) -> &f32 { &self.speedAtTop }
+    pub fn set_speedAtTop(&mut self, value: f32, dirty: &mut DirtyProperties) { self.speedAtTop = value; dirty.mark(12); }
+    pub fn decisionTime(&self) -> &f32 { &self.decisionTime }
+    pub fn set_decisionTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.decisionTime = value; dirty.mark(13); }
+    pub fn flyAroundCenter(&self) -> &BOOL { &self.flyAroundCenter }
+    pub fn set_flyAroundCenter(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.flyAroundCenter = value; dirty.mark(14); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Flock_Property {
+        modelName(0x00, AutoString, var16),
+        modelName2(0x01, AutoString, var16),
+        modelCount(0x02, u8, 1),
+        yawSpeed(0x03, f32, 4),
+        pitchSpeed(0x04, f32, 4),
+        rollSpeed(0x05, f32, 4),
+        animSpeedMin(0x06, f32, 4),
+        animSpeedMax(0x07, f32, 4),
+        height(0x08, f32, 4),
+        radius(0x09, f32, 4),
+        deadZoneRadius(0x0A, f32, 4),
+        speedAtBottom(0x0B, f32, 4),
+        speedAtTop(0x0C, f32, 4),
+        decisionTime(0x0D, f32, 4),
+        flyAroundCenter(0x0E, BOOL, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Flock {
+        "modelName" => modelName,
+        "modelName2" => modelName2,
+        "modelCount" => modelCount,
+        "yawSpeed" => yawSpeed,
+        "pitchSpeed" => pitchSpeed,
+        "rollSpeed" => rollSpeed,
+        "animSpeedMin" => animSpeedMin,
+        "animSpeedMax" => animSpeedMax,
+        "height" => height,
+        "radius" => radius,
+        "deadZoneRadius" => deadZoneRadius,
+        "speedAtBottom" => speedAtBottom,
+        "speedAtTop" => speedAtTop,
+        "decisionTime" => decisionTime,
+        "flyAroundCenter" => flyAroundCenter,
+    }
+}
+
+impl Properties for Flock {
+    type Property = Flock_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(4),
+            4 => ElementLength::Fixed(4),
+            5 => ElementLength::Fixed(4),
+            6 => ElementLength::Fixed(4),
+            7 => ElementLength::Fixed(4),
+            8 => ElementLength::Fixed(4),
+            9 => ElementLength::Fixed(4),
+            10 => ElementLength::Fixed(4),
+            11 => ElementLength::Fixed(4),
+            12 => ElementLength::Fixed(4),
+            13 => ElementLength::Fixed(4),
+            14 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.modelName, write, &()),
+            1 => Codec::<()>::write(&self.modelName2, write, &()),
+            2 => Codec::<()>::write(&self.modelCount, write, &()),
+            3 => Codec::<()>::write(&self.yawSpeed, write, &()),
+            4 => Codec::<()>::write(&self.pitchSpeed, write, &()),
+            5 => Codec::<()>::write(&self.rollSpeed, write, &()),
+            6 => Codec::<()>::write(&self.animSpeedMin, write, &()),
+            7 => Codec::<()>::write(&self.animSpeedMax, write, &()),
+            8 => Codec::<()>::write(&self.height, write, &()),
+            9 => Codec::<()>::write(&self.radius, write, &()),
+            10 => Codec::<()>::write(&self.deadZoneRadius, write, &()),
+            11 => Codec::<()>::write(&self.speedAtBottom, write, &()),
+            12 => Codec::<()>::write(&self.speedAtTop, write, &()),
+            13 => Codec::<()>::write(&self.decisionTime, write, &()),
+            14 => Codec::<()>::write(&self.flyAroundCenter, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           FlockExotic            ====== //
 // ============================================== //
@@ -1713,6 +2591,158 @@ impl SimpleEntity for FlockExotic {
     type CellMethod = FlockExotic_Cell;
 }
 
+impl FlockExotic {
+    pub fn animSpeedMax(&self) -> &f32 { &self.animSpeedMax }
+    pub fn set_animSpeedMax(&mut self, value: f32, dirty: &mut DirtyProperties) { self.animSpeedMax = value; dirty.mark(0); }
+    pub fn animSpeedMin(&self) -> &f32 { &self.animSpeedMin }
+    pub fn set_animSpeedMin(&mut self, value: f32, dirty: &mut DirtyProperties) { self.animSpeedMin = value; dirty.mark(1); }
+    pub fn modelCount(&self) -> &u8 { &self.modelCount }
+    pub fn set_modelCount(&mut self, value: u8, dirty: &mut DirtyProperties) { self.modelCount = value; dirty.mark(2); }
+    pub fn modelName(&self) -> &AutoString { &self.modelName }
+    pub fn set_modelName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName = value; dirty.mark(3); }
+    pub fn modelName2(&self) -> &AutoString { &self.modelName2 }
+    pub fn set_modelName2(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName2 = value; dirty.mark(4); }
+    pub fn speed(&self) -> &f32 { &self.speed }
+    pub fn set_speed(&mut self, value: f32, dirty: &mut DirtyProperties) { self.speed = value; dirty.mark(5); }
+    pub fn initSpeedRandom(&self) -> &Vec2 { &self.initSpeedRandom }
+    pub fn set_initSpeedRandom(&mut self, value: Vec2, dirty: &mut DirtyProperties) { self.initSpeedRandom = value; dirty.mark(6); }
+    pub fn speedRandom(&self) -> &Vec2 { &self.speedRandom }
+    pub fn set_speedRandom(&mut self, value: Vec2, dirty: &mut DirtyProperties) { self.speedRandom = value; dirty.mark(7); }
+    pub fn accelerationTime(&self) -> &f32 { &self.accelerationTime }
+    pub fn set_accelerationTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.accelerationTime = value; dirty.mark(8); }
+    pub fn triggerRadius(&self) -> &f32 { &self.triggerRadius }
+    pub fn set_triggerRadius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.triggerRadius = value; dirty.mark(9); }
+    pub fn explosionRadius(&self) -> &Vec2 { &self.explosionRadius }
+    pub fn set_explosionRadius(&mut self, value: Vec2, dirty: &mut DirtyProperties) { self.explosionRadius = value; dirty.mark(10); }
+    pub fn spawnRadius(&self) -> &f32 { &self.spawnRadius }
+    pub fn set_spawnRadius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.spawnRadius = value; dirty.mark(11); }
+    pub fn spawnHeight(&self) -> &f32 { &self.spawnHeight }
+    pub fn set_spawnHeight(&mut self, value: f32, dirty: &mut DirtyProperties) { self.spawnHeight = value; dirty.mark(12); }
+    pub fn flightRadius(&self) -> &f32 { &self.flightRadius }
+    pub fn set_flightRadius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.flightRadius = value; dirty.mark(13); }
+    pub fn flightHeight(&self) -> &f32 { &self.flightHeight }
+    pub fn set_flightHeight(&mut self, value: f32, dirty: &mut DirtyProperties) { self.flightHeight = value; dirty.mark(14); }
+    pub fn flightAngleMin(&self) -> &f32 { &self.flightAngleMin }
+    pub fn set_flightAngleMin(&mut self, value: f32, dirty: &mut DirtyProperties) { self.flightAngleMin = value; dirty.mark(15); }
+    pub fn flightAngleMax(&self) -> &f32 { &self.flightAngleMax }
+    pub fn set_flightAngleMax(&mut self, value: f32, dirty: &mut DirtyProperties) { self.flightAngleMax = value; dirty.mark(16); }
+    pub fn flightOffsetFromOrigin(&self) -> &f32 { &self.flightOffsetFromOrigin }
+    pub fn set_flightOffsetFromOrigin(&mut self, value: f32, dirty: &mut DirtyProperties) { self.flightOffsetFromOrigin = value; dirty.mark(17); }
+    pub fn lifeTime(&self) -> &f32 { &self.lifeTime }
+    pub fn set_lifeTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.lifeTime = value; dirty.mark(18); }
+    pub fn respawnTime(&self) -> &f32 { &self.respawnTime }
+    pub fn set_respawnTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.respawnTime = value; dirty.mark(19); }
+    pub fn flightSound(&self) -> &AutoString { &self.flightSound }
+    pub fn set_flightSound(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.flightSound = value; dirty.mark(20); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum FlockExotic_Property {
+        animSpeedMax(0x00, f32, 4),
+        animSpeedMin(0x01, f32, 4),
+        modelCount(0x02, u8, 1),
+        modelName(0x03, AutoString, var16),
+        modelName2(0x04, AutoString, var16),
+        speed(0x05, f32, 4),
+        initSpeedRandom(0x06, Vec2, 8),
+        speedRandom(0x07, Vec2, 8),
+        accelerationTime(0x08, f32, 4),
+        triggerRadius(0x09, f32, 4),
+        explosionRadius(0x0A, Vec2, 8),
+        spawnRadius(0x0B, f32, 4),
+        spawnHeight(0x0C, f32, 4),
+        flightRadius(0x0D, f32, 4),
+        flightHeight(0x0E, f32, 4),
+        flightAngleMin(0x0F, f32, 4),
+        flightAngleMax(0x10, f32, 4),
+        flightOffsetFromOrigin(0x11, f32, 4),
+        lifeTime(0x12, f32, 4),
+        respawnTime(0x13, f32, 4),
+        flightSound(0x14, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    FlockExotic {
+        "animSpeedMax" => animSpeedMax,
+        "animSpeedMin" => animSpeedMin,
+        "modelCount" => modelCount,
+        "modelName" => modelName,
+        "modelName2" => modelName2,
+        "speed" => speed,
+        "initSpeedRandom" => initSpeedRandom,
+        "speedRandom" => speedRandom,
+        "accelerationTime" => accelerationTime,
+        "triggerRadius" => triggerRadius,
+        "explosionRadius" => explosionRadius,
+        "spawnRadius" => spawnRadius,
+        "spawnHeight" => spawnHeight,
+        "flightRadius" => flightRadius,
+        "flightHeight" => flightHeight,
+        "flightAngleMin" => flightAngleMin,
+        "flightAngleMax" => flightAngleMax,
+        "flightOffsetFromOrigin" => flightOffsetFromOrigin,
+        "lifeTime" => lifeTime,
+        "respawnTime" => respawnTime,
+        "flightSound" => flightSound,
+    }
+}
+
+impl Properties for FlockExotic {
+    type Property = FlockExotic_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(1),
+            5 => ElementLength::Fixed(4),
+            6 => ElementLength::Fixed(8),
+            7 => ElementLength::Fixed(8),
+            8 => ElementLength::Fixed(4),
+            9 => ElementLength::Fixed(4),
+            10 => ElementLength::Fixed(8),
+            11 => ElementLength::Fixed(4),
+            12 => ElementLength::Fixed(4),
+            13 => ElementLength::Fixed(4),
+            14 => ElementLength::Fixed(4),
+            15 => ElementLength::Fixed(4),
+            16 => ElementLength::Fixed(4),
+            17 => ElementLength::Fixed(4),
+            18 => ElementLength::Fixed(4),
+            19 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.animSpeedMax, write, &()),
+            1 => Codec::<()>::write(&self.animSpeedMin, write, &()),
+            2 => Codec::<()>::write(&self.modelCount, write, &()),
+            3 => Codec::<()>::write(&self.modelName, write, &()),
+            4 => Codec::<()>::write(&self.modelName2, write, &()),
+            5 => Codec::<()>::write(&self.speed, write, &()),
+            6 => Codec::<()>::write(&self.initSpeedRandom, write, &()),
+            7 => Codec::<()>::write(&self.speedRandom, write, &()),
+            8 => Codec::<()>::write(&self.accelerationTime, write, &()),
+            9 => Codec::<()>::write(&self.triggerRadius, write, &()),
+            10 => Codec::<()>::write(&self.explosionRadius, write, &()),
+            11 => Codec::<()>::write(&self.spawnRadius, write, &()),
+            12 => Codec::<()>::write(&self.spawnHeight, write, &()),
+            13 => Codec::<()>::write(&self.flightRadius, write, &()),
+            14 => Codec::<()>::write(&self.flightHeight, write, &()),
+            15 => Codec::<()>::write(&self.flightAngleMin, write, &()),
+            16 => Codec::<()>::write(&self.flightAngleMax, write, &()),
+            17 => Codec::<()>::write(&self.flightOffsetFromOrigin, write, &()),
+            18 => Codec::<()>::write(&self.lifeTime, write, &()),
+            19 => Codec::<()>::write(&self.respawnTime, write, &()),
+            20 => Codec::<()>::write(&self.flightSound, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======              Login               ====== //
 // ============================================== //
@@ -1783,6 +2813,40 @@ impl SimpleEntity for Login {
     type CellMethod = Login_Cell;
 }
 
+impl Login {
+    pub fn accountDBID_s(&self) -> &AutoString { &self.accountDBID_s }
+    pub fn set_accountDBID_s(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.accountDBID_s = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Login_Property {
+        accountDBID_s(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Login {
+        "accountDBID_s" => accountDBID_s,
+    }
+}
+
+impl Properties for Login {
+    type Property = Login_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.accountDBID_s, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          DetachedTurret          ====== //
 // ============================================== //
@@ -1853,6 +2917,63 @@ impl SimpleEntity for DetachedTurret {
     type CellMethod = DetachedTurret_Cell;
 }
 
+impl DetachedTurret {
+    pub fn vehicleCompDescr(&self) -> &AutoString { &self.vehicleCompDescr }
+    pub fn set_vehicleCompDescr(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.vehicleCompDescr = value; dirty.mark(0); }
+    pub fn outfitCD(&self) -> &AutoString { &self.outfitCD }
+    pub fn set_outfitCD(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.outfitCD = value; dirty.mark(1); }
+    pub fn isUnderWater(&self) -> &BOOL { &self.isUnderWater }
+    pub fn set_isUnderWater(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isUnderWater = value; dirty.mark(2); }
+    pub fn isCollidingWithWorld(&self) -> &BOOL { &self.isCollidingWithWorld }
+    pub fn set_isCollidingWithWorld(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isCollidingWithWorld = value; dirty.mark(3); }
+    pub fn vehicleID(&self) -> &i32 { &self.vehicleID }
+    pub fn set_vehicleID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.vehicleID = value; dirty.mark(4); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum DetachedTurret_Property {
+        vehicleCompDescr(0x00, AutoString, var16),
+        outfitCD(0x01, AutoString, var16),
+        isUnderWater(0x02, BOOL, 1),
+        isCollidingWithWorld(0x03, BOOL, 1),
+        vehicleID(0x04, i32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    DetachedTurret {
+        "vehicleCompDescr" => vehicleCompDescr,
+        "outfitCD" => outfitCD,
+        "isUnderWater" => isUnderWater,
+        "isCollidingWithWorld" => isCollidingWithWorld,
+        "vehicleID" => vehicleID,
+    }
+}
+
+impl Properties for DetachedTurret {
+    type Property = DetachedTurret_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(1),
+            4 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.vehicleCompDescr, write, &()),
+            1 => Codec::<()>::write(&self.outfitCD, write, &()),
+            2 => Codec::<()>::write(&self.isUnderWater, write, &()),
+            3 => Codec::<()>::write(&self.isCollidingWithWorld, write, &()),
+            4 => Codec::<()>::write(&self.vehicleID, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         DebugDrawEntity          ====== //
 // ============================================== //
@@ -1904,6 +3025,40 @@ impl SimpleEntity for DebugDrawEntity {
     type CellMethod = DebugDrawEntity_Cell;
 }
 
+impl DebugDrawEntity {
+    pub fn drawObjects(&self) -> &Vec<ANON180> { &self.drawObjects }
+    pub fn set_drawObjects(&mut self, value: Vec<ANON180>, dirty: &mut DirtyProperties) { self.drawObjects = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum DebugDrawEntity_Property {
+        drawObjects(0x00, Vec<ANON180>, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    DebugDrawEntity {
+        "drawObjects" => drawObjects,
+    }
+}
+
+impl Properties for DebugDrawEntity {
+    type Property = DebugDrawEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.drawObjects, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======   ClientSelectableCameraObject   ====== //
 // ============================================== //
@@ -1954,6 +3109,35 @@ impl SimpleEntity for ClientSelectableCameraObject {
     type CellMethod = ClientSelectableCameraObject_Cell;
 }
 
+impl ClientSelectableCameraObject {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableCameraObject_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableCameraObject {
+    }
+}
+
+impl Properties for ClientSelectableCameraObject {
+    type Property = ClientSelectableCameraObject_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======  ClientSelectableCameraVehicle   ====== //
 // ============================================== //
@@ -2005,6 +3189,40 @@ impl SimpleEntity for ClientSelectableCameraVehicle {
     type CellMethod = ClientSelectableCameraVehicle_Cell;
 }
 
+impl ClientSelectableCameraVehicle {
+    pub fn modelName(&self) -> &AutoString { &self.modelName }
+    pub fn set_modelName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.modelName = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableCameraVehicle_Property {
+        modelName(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableCameraVehicle {
+        "modelName" => modelName,
+    }
+}
+
+impl Properties for ClientSelectableCameraVehicle {
+    type Property = ClientSelectableCameraVehicle_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.modelName, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======  ClientSelectableWebLinksOpener  ====== //
 // ============================================== //
@@ -2056,6 +3274,40 @@ impl SimpleEntity for ClientSelectableWebLinksOpener {
     type CellMethod = ClientSelectableWebLinksOpener_Cell;
 }
 
+impl ClientSelectableWebLinksOpener {
+    pub fn url(&self) -> &AutoString { &self.url }
+    pub fn set_url(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.url = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableWebLinksOpener_Property {
+        url(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableWebLinksOpener {
+        "url" => url,
+    }
+}
+
+impl Properties for ClientSelectableWebLinksOpener {
+    type Property = ClientSelectableWebLinksOpener_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.url, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======    ClientSelectableEasterEgg     ====== //
 // ============================================== //
@@ -2110,6 +3362,56 @@ impl SimpleEntity for ClientSelectableEasterEgg {
     type CellMethod = ClientSelectableEasterEgg_Cell;
 }
 
+impl ClientSelectableEasterEgg {
+    pub fn imageName(&self) -> &AutoString { &self.imageName }
+    pub fn set_imageName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.imageName = value; dirty.mark(0); }
+    pub fn multiLanguageSupport(&self) -> &BOOL { &self.multiLanguageSupport }
+    pub fn set_multiLanguageSupport(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.multiLanguageSupport = value; dirty.mark(1); }
+    pub fn outlineModelName(&self) -> &AutoString { &self.outlineModelName }
+    pub fn set_outlineModelName(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.outlineModelName = value; dirty.mark(2); }
+    pub fn animationSequence(&self) -> &AutoString { &self.animationSequence }
+    pub fn set_animationSequence(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.animationSequence = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableEasterEgg_Property {
+        imageName(0x00, AutoString, var16),
+        multiLanguageSupport(0x01, BOOL, 1),
+        outlineModelName(0x02, AutoString, var16),
+        animationSequence(0x03, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableEasterEgg {
+        "imageName" => imageName,
+        "multiLanguageSupport" => multiLanguageSupport,
+        "outlineModelName" => outlineModelName,
+        "animationSequence" => animationSequence,
+    }
+}
+
+impl Properties for ClientSelectableEasterEgg {
+    type Property = ClientSelectableEasterEgg_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            1 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.imageName, write, &()),
+            1 => Codec::<()>::write(&self.multiLanguageSupport, write, &()),
+            2 => Codec::<()>::write(&self.outlineModelName, write, &()),
+            3 => Codec::<()>::write(&self.animationSequence, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           EmptyEntity            ====== //
 // ============================================== //
@@ -2160,6 +3462,35 @@ impl SimpleEntity for EmptyEntity {
     type CellMethod = EmptyEntity_Cell;
 }
 
+impl EmptyEntity {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum EmptyEntity_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    EmptyEntity {
+    }
+}
+
+impl Properties for EmptyEntity {
+    type Property = EmptyEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======     LimitedVisibilityEntity      ====== //
 // ============================================== //
@@ -2210,6 +3541,35 @@ impl SimpleEntity for LimitedVisibilityEntity {
     type CellMethod = LimitedVisibilityEntity_Cell;
 }
 
+impl LimitedVisibilityEntity {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum LimitedVisibilityEntity_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    LimitedVisibilityEntity {
+    }
+}
+
+impl Properties for LimitedVisibilityEntity {
+    type Property = LimitedVisibilityEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======             HeroTank             ====== //
 // ============================================== //
@@ -2263,6 +3623,53 @@ impl SimpleEntity for HeroTank {
     type CellMethod = HeroTank_Cell;
 }
 
+impl HeroTank {
+    pub fn markerHeightFactor(&self) -> &f32 { &self.markerHeightFactor }
+    pub fn set_markerHeightFactor(&mut self, value: f32, dirty: &mut DirtyProperties) { self.markerHeightFactor = value; dirty.mark(0); }
+    pub fn vehicleTurretYaw(&self) -> &f32 { &self.vehicleTurretYaw }
+    pub fn set_vehicleTurretYaw(&mut self, value: f32, dirty: &mut DirtyProperties) { self.vehicleTurretYaw = value; dirty.mark(1); }
+    pub fn vehicleGunPitch(&self) -> &f32 { &self.vehicleGunPitch }
+    pub fn set_vehicleGunPitch(&mut self, value: f32, dirty: &mut DirtyProperties) { self.vehicleGunPitch = value; dirty.mark(2); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum HeroTank_Property {
+        markerHeightFactor(0x00, f32, 4),
+        vehicleTurretYaw(0x01, f32, 4),
+        vehicleGunPitch(0x02, f32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    HeroTank {
+        "markerHeightFactor" => markerHeightFactor,
+        "vehicleTurretYaw" => vehicleTurretYaw,
+        "vehicleGunPitch" => vehicleGunPitch,
+    }
+}
+
+impl Properties for HeroTank {
+    type Property = HeroTank_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.markerHeightFactor, write, &()),
+            1 => Codec::<()>::write(&self.vehicleTurretYaw, write, &()),
+            2 => Codec::<()>::write(&self.vehicleGunPitch, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           PlatoonTank            ====== //
 // ============================================== //
@@ -2317,6 +3724,59 @@ impl SimpleEntity for PlatoonTank {
     type CellMethod = PlatoonTank_Cell;
 }
 
+impl PlatoonTank {
+    pub fn markerHeightFactor(&self) -> &f32 { &self.markerHeightFactor }
+    pub fn set_markerHeightFactor(&mut self, value: f32, dirty: &mut DirtyProperties) { self.markerHeightFactor = value; dirty.mark(0); }
+    pub fn vehicleTurretYaw(&self) -> &f32 { &self.vehicleTurretYaw }
+    pub fn set_vehicleTurretYaw(&mut self, value: f32, dirty: &mut DirtyProperties) { self.vehicleTurretYaw = value; dirty.mark(1); }
+    pub fn vehicleGunPitch(&self) -> &f32 { &self.vehicleGunPitch }
+    pub fn set_vehicleGunPitch(&mut self, value: f32, dirty: &mut DirtyProperties) { self.vehicleGunPitch = value; dirty.mark(2); }
+    pub fn slotIndex(&self) -> &i32 { &self.slotIndex }
+    pub fn set_slotIndex(&mut self, value: i32, dirty: &mut DirtyProperties) { self.slotIndex = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum PlatoonTank_Property {
+        markerHeightFactor(0x00, f32, 4),
+        vehicleTurretYaw(0x01, f32, 4),
+        vehicleGunPitch(0x02, f32, 4),
+        slotIndex(0x03, i32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    PlatoonTank {
+        "markerHeightFactor" => markerHeightFactor,
+        "vehicleTurretYaw" => vehicleTurretYaw,
+        "vehicleGunPitch" => vehicleGunPitch,
+        "slotIndex" => slotIndex,
+    }
+}
+
+impl Properties for PlatoonTank {
+    type Property = PlatoonTank_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(4),
+            3 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.markerHeightFactor, write, &()),
+            1 => Codec::<()>::write(&self.vehicleTurretYaw, write, &()),
+            2 => Codec::<()>::write(&self.vehicleGunPitch, write, &()),
+            3 => Codec::<()>::write(&self.slotIndex, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         PlatoonLighting          ====== //
 // ============================================== //
@@ -2368,6 +3828,40 @@ impl SimpleEntity for PlatoonLighting {
     type CellMethod = PlatoonLighting_Cell;
 }
 
+impl PlatoonLighting {
+    pub fn animationStateMachine(&self) -> &AutoString { &self.animationStateMachine }
+    pub fn set_animationStateMachine(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.animationStateMachine = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum PlatoonLighting_Property {
+        animationStateMachine(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    PlatoonLighting {
+        "animationStateMachine" => animationStateMachine,
+    }
+}
+
+impl Properties for PlatoonLighting {
+    type Property = PlatoonLighting_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.animationStateMachine, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======            SectorBase            ====== //
 // ============================================== //
@@ -2430,6 +3924,107 @@ impl SimpleEntity for SectorBase {
     type CellMethod = SectorBase_Cell;
 }
 
+impl SectorBase {
+    pub fn isActive(&self) -> &BOOL { &self.isActive }
+    pub fn set_isActive(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isActive = value; dirty.mark(0); }
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(1); }
+    pub fn baseID(&self) -> &u8 { &self.baseID }
+    pub fn set_baseID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.baseID = value; dirty.mark(2); }
+    pub fn sectorID(&self) -> &u8 { &self.sectorID }
+    pub fn set_sectorID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.sectorID = value; dirty.mark(3); }
+    pub fn maxPoints(&self) -> &f32 { &self.maxPoints }
+    pub fn set_maxPoints(&mut self, value: f32, dirty: &mut DirtyProperties) { self.maxPoints = value; dirty.mark(4); }
+    pub fn pointsPercentage(&self) -> &u8 { &self.pointsPercentage }
+    pub fn set_pointsPercentage(&mut self, value: u8, dirty: &mut DirtyProperties) { self.pointsPercentage = value; dirty.mark(5); }
+    pub fn capturingStopped(&self) -> &BOOL { &self.capturingStopped }
+    pub fn set_capturingStopped(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.capturingStopped = value; dirty.mark(6); }
+    pub fn onDamageCooldownTime(&self) -> &f32 { &self.onDamageCooldownTime }
+    pub fn set_onDamageCooldownTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.onDamageCooldownTime = value; dirty.mark(7); }
+    pub fn radius(&self) -> &f32 { &self.radius }
+    pub fn set_radius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.radius = value; dirty.mark(8); }
+    pub fn isCaptured(&self) -> &BOOL { &self.isCaptured }
+    pub fn set_isCaptured(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isCaptured = value; dirty.mark(9); }
+    pub fn invadersCount(&self) -> &u8 { &self.invadersCount }
+    pub fn set_invadersCount(&mut self, value: u8, dirty: &mut DirtyProperties) { self.invadersCount = value; dirty.mark(10); }
+    pub fn expectedCaptureTime(&self) -> &f32 { &self.expectedCaptureTime }
+    pub fn set_expectedCaptureTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.expectedCaptureTime = value; dirty.mark(11); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum SectorBase_Property {
+        isActive(0x00, BOOL, 1),
+        team(0x01, u8, 1),
+        baseID(0x02, u8, 1),
+        sectorID(0x03, u8, 1),
+        maxPoints(0x04, f32, 4),
+        pointsPercentage(0x05, u8, 1),
+        capturingStopped(0x06, BOOL, 1),
+        onDamageCooldownTime(0x07, f32, 4),
+        radius(0x08, f32, 4),
+        isCaptured(0x09, BOOL, 1),
+        invadersCount(0x0A, u8, 1),
+        expectedCaptureTime(0x0B, f32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    SectorBase {
+        "isActive" => isActive,
+        "team" => team,
+        "baseID" => baseID,
+        "sectorID" => sectorID,
+        "maxPoints" => maxPoints,
+        "pointsPercentage" => pointsPercentage,
+        "capturingStopped" => capturingStopped,
+        "onDamageCooldownTime" => onDamageCooldownTime,
+        "radius" => radius,
+        "isCaptured" => isCaptured,
+        "invadersCount" => invadersCount,
+        "expectedCaptureTime" => expectedCaptureTime,
+    }
+}
+
+impl Properties for SectorBase {
+    type Property = SectorBase_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(1),
+            1 => ElementLength::Fixed(1),
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(1),
+            4 => ElementLength::Fixed(4),
+            5 => ElementLength::Fixed(1),
+            6 => ElementLength::Fixed(1),
+            7 => ElementLength::Fixed(4),
+            8 => ElementLength::Fixed(4),
+            9 => ElementLength::Fixed(1),
+            10 => ElementLength::Fixed(1),
+            11 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.isActive, write, &()),
+            1 => Codec::<()>::write(&self.team, write, &()),
+            2 => Codec::<()>::write(&self.baseID, write, &()),
+            3 => Codec::<()>::write(&self.sectorID, write, &()),
+            4 => Codec::<()>::write(&self.maxPoints, write, &()),
+            5 => Codec::<()>::write(&self.pointsPercentage, write, &()),
+            6 => Codec::<()>::write(&self.capturingStopped, write, &()),
+            7 => Codec::<()>::write(&self.onDamageCooldownTime, write, &()),
+            8 => Codec::<()>::write(&self.radius, write, &()),
+            9 => Codec::<()>::write(&self.isCaptured, write, &()),
+            10 => Codec::<()>::write(&self.invadersCount, write, &()),
+            11 => Codec::<()>::write(&self.expectedCaptureTime, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======              Sector              ====== //
 // ============================================== //
@@ -2496,6 +4091,95 @@ impl SimpleEntity for Sector {
     type CellMethod = Sector_Cell;
 }
 
+impl Sector {
+    pub fn groupID(&self) -> &u8 { &self.groupID }
+    pub fn set_groupID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.groupID = value; dirty.mark(0); }
+    pub fn sectorID(&self) -> &u8 { &self.sectorID }
+    pub fn set_sectorID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.sectorID = value; dirty.mark(1); }
+    pub fn playerGroup(&self) -> &u8 { &self.playerGroup }
+    pub fn set_playerGroup(&mut self, value: u8, dirty: &mut DirtyProperties) { self.playerGroup = value; dirty.mark(2); }
+    pub fn IDInPlayerGroup(&self) -> &u8 { &self.IDInPlayerGroup }
+    pub fn set_IDInPlayerGroup(&mut self, value: u8, dirty: &mut DirtyProperties) { self.IDInPlayerGroup = value; dirty.mark(3); }
+    pub fn lengthX(&self) -> &f32 { &self.lengthX }
+    pub fn set_lengthX(&mut self, value: f32, dirty: &mut DirtyProperties) { self.lengthX = value; dirty.mark(4); }
+    pub fn lengthZ(&self) -> &f32 { &self.lengthZ }
+    pub fn set_lengthZ(&mut self, value: f32, dirty: &mut DirtyProperties) { self.lengthZ = value; dirty.mark(5); }
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(6); }
+    pub fn state(&self) -> &u8 { &self.state }
+    pub fn set_state(&mut self, value: u8, dirty: &mut DirtyProperties) { self.state = value; dirty.mark(7); }
+    pub fn transitionTime(&self) -> &f32 { &self.transitionTime }
+    pub fn set_transitionTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.transitionTime = value; dirty.mark(8); }
+    pub fn endOfTransitionPeriod(&self) -> &f32 { &self.endOfTransitionPeriod }
+    pub fn set_endOfTransitionPeriod(&mut self, value: f32, dirty: &mut DirtyProperties) { self.endOfTransitionPeriod = value; dirty.mark(9); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Sector_Property {
+        groupID(0x00, u8, 1),
+        sectorID(0x01, u8, 1),
+        playerGroup(0x02, u8, 1),
+        IDInPlayerGroup(0x03, u8, 1),
+        lengthX(0x04, f32, 4),
+        lengthZ(0x05, f32, 4),
+        team(0x06, u8, 1),
+        state(0x07, u8, 1),
+        transitionTime(0x08, f32, 4),
+        endOfTransitionPeriod(0x09, f32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Sector {
+        "groupID" => groupID,
+        "sectorID" => sectorID,
+        "playerGroup" => playerGroup,
+        "IDInPlayerGroup" => IDInPlayerGroup,
+        "lengthX" => lengthX,
+        "lengthZ" => lengthZ,
+        "team" => team,
+        "state" => state,
+        "transitionTime" => transitionTime,
+        "endOfTransitionPeriod" => endOfTransitionPeriod,
+    }
+}
+
+impl Properties for Sector {
+    type Property = Sector_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(1),
+            1 => ElementLength::Fixed(1),
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(1),
+            4 => ElementLength::Fixed(4),
+            5 => ElementLength::Fixed(4),
+            6 => ElementLength::Fixed(1),
+            7 => ElementLength::Fixed(1),
+            8 => ElementLength::Fixed(4),
+            9 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.groupID, write, &()),
+            1 => Codec::<()>::write(&self.sectorID, write, &()),
+            2 => Codec::<()>::write(&self.playerGroup, write, &()),
+            3 => Codec::<()>::write(&self.IDInPlayerGroup, write, &()),
+            4 => Codec::<()>::write(&self.lengthX, write, &()),
+            5 => Codec::<()>::write(&self.lengthZ, write, &()),
+            6 => Codec::<()>::write(&self.team, write, &()),
+            7 => Codec::<()>::write(&self.state, write, &()),
+            8 => Codec::<()>::write(&self.transitionTime, write, &()),
+            9 => Codec::<()>::write(&self.endOfTransitionPeriod, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======        DestructibleEntity        ====== //
 // ============================================== //
@@ -2579,6 +4263,87 @@ impl SimpleEntity for DestructibleEntity {
     type CellMethod = DestructibleEntity_Cell;
 }
 
+impl DestructibleEntity {
+    pub fn isActive(&self) -> &BOOL { &self.isActive }
+    pub fn set_isActive(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isActive = value; dirty.mark(0); }
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(1); }
+    pub fn destructibleEntityID(&self) -> &u8 { &self.destructibleEntityID }
+    pub fn set_destructibleEntityID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.destructibleEntityID = value; dirty.mark(2); }
+    pub fn health(&self) -> &f32 { &self.health }
+    pub fn set_health(&mut self, value: f32, dirty: &mut DirtyProperties) { self.health = value; dirty.mark(3); }
+    pub fn maxHealth(&self) -> &f32 { &self.maxHealth }
+    pub fn set_maxHealth(&mut self, value: f32, dirty: &mut DirtyProperties) { self.maxHealth = value; dirty.mark(4); }
+    pub fn isDestructibleDestroyed(&self) -> &BOOL { &self.isDestructibleDestroyed }
+    pub fn set_isDestructibleDestroyed(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isDestructibleDestroyed = value; dirty.mark(5); }
+    pub fn typeID(&self) -> &u8 { &self.typeID }
+    pub fn set_typeID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.typeID = value; dirty.mark(6); }
+    pub fn linkedMapActivities(&self) -> &AutoString { &self.linkedMapActivities }
+    pub fn set_linkedMapActivities(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.linkedMapActivities = value; dirty.mark(7); }
+    pub fn damageStickers(&self) -> &Vec<u64> { &self.damageStickers }
+    pub fn set_damageStickers(&mut self, value: Vec<u64>, dirty: &mut DirtyProperties) { self.damageStickers = value; dirty.mark(8); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum DestructibleEntity_Property {
+        isActive(0x00, BOOL, 1),
+        team(0x01, u8, 1),
+        destructibleEntityID(0x02, u8, 1),
+        health(0x03, f32, 4),
+        maxHealth(0x04, f32, 4),
+        isDestructibleDestroyed(0x05, BOOL, 1),
+        typeID(0x06, u8, 1),
+        linkedMapActivities(0x07, AutoString, var16),
+        damageStickers(0x08, Vec<u64>, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    DestructibleEntity {
+        "isActive" => isActive,
+        "team" => team,
+        "destructibleEntityID" => destructibleEntityID,
+        "health" => health,
+        "maxHealth" => maxHealth,
+        "isDestructibleDestroyed" => isDestructibleDestroyed,
+        "typeID" => typeID,
+        "linkedMapActivities" => linkedMapActivities,
+        "damageStickers" => damageStickers,
+    }
+}
+
+impl Properties for DestructibleEntity {
+    type Property = DestructibleEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(1),
+            1 => ElementLength::Fixed(1),
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(4),
+            4 => ElementLength::Fixed(4),
+            5 => ElementLength::Fixed(1),
+            6 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.isActive, write, &()),
+            1 => Codec::<()>::write(&self.team, write, &()),
+            2 => Codec::<()>::write(&self.destructibleEntityID, write, &()),
+            3 => Codec::<()>::write(&self.health, write, &()),
+            4 => Codec::<()>::write(&self.maxHealth, write, &()),
+            5 => Codec::<()>::write(&self.isDestructibleDestroyed, write, &()),
+            6 => Codec::<()>::write(&self.typeID, write, &()),
+            7 => Codec::<()>::write(&self.linkedMapActivities, write, &()),
+            8 => Codec::<()>::write(&self.damageStickers, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         StepRepairPoint          ====== //
 // ============================================== //
@@ -2631,6 +4396,47 @@ impl SimpleEntity for StepRepairPoint {
     type CellMethod = StepRepairPoint_Cell;
 }
 
+impl StepRepairPoint {
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(0); }
+    pub fn radius(&self) -> &f32 { &self.radius }
+    pub fn set_radius(&mut self, value: f32, dirty: &mut DirtyProperties) { self.radius = value; dirty.mark(1); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum StepRepairPoint_Property {
+        team(0x00, u8, 1),
+        radius(0x01, f32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    StepRepairPoint {
+        "team" => team,
+        "radius" => radius,
+    }
+}
+
+impl Properties for StepRepairPoint {
+    type Property = StepRepairPoint_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(1),
+            1 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.team, write, &()),
+            1 => Codec::<()>::write(&self.radius, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          ProtectionZone          ====== //
 // ============================================== //
@@ -2686,6 +4492,65 @@ impl SimpleEntity for ProtectionZone {
     type CellMethod = ProtectionZone_Cell;
 }
 
+impl ProtectionZone {
+    pub fn zoneID(&self) -> &u8 { &self.zoneID }
+    pub fn set_zoneID(&mut self, value: u8, dirty: &mut DirtyProperties) { self.zoneID = value; dirty.mark(0); }
+    pub fn lengthX(&self) -> &f32 { &self.lengthX }
+    pub fn set_lengthX(&mut self, value: f32, dirty: &mut DirtyProperties) { self.lengthX = value; dirty.mark(1); }
+    pub fn lengthZ(&self) -> &f32 { &self.lengthZ }
+    pub fn set_lengthZ(&mut self, value: f32, dirty: &mut DirtyProperties) { self.lengthZ = value; dirty.mark(2); }
+    pub fn team(&self) -> &u8 { &self.team }
+    pub fn set_team(&mut self, value: u8, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(3); }
+    pub fn isActive(&self) -> &BOOL { &self.isActive }
+    pub fn set_isActive(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isActive = value; dirty.mark(4); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ProtectionZone_Property {
+        zoneID(0x00, u8, 1),
+        lengthX(0x01, f32, 4),
+        lengthZ(0x02, f32, 4),
+        team(0x03, u8, 1),
+        isActive(0x04, BOOL, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ProtectionZone {
+        "zoneID" => zoneID,
+        "lengthX" => lengthX,
+        "lengthZ" => lengthZ,
+        "team" => team,
+        "isActive" => isActive,
+    }
+}
+
+impl Properties for ProtectionZone {
+    type Property = ProtectionZone_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(1),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(4),
+            3 => ElementLength::Fixed(1),
+            4 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.zoneID, write, &()),
+            1 => Codec::<()>::write(&self.lengthX, write, &()),
+            2 => Codec::<()>::write(&self.lengthZ, write, &()),
+            3 => Codec::<()>::write(&self.team, write, &()),
+            4 => Codec::<()>::write(&self.isActive, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           HangarPoster           ====== //
 // ============================================== //
@@ -2738,6 +4603,47 @@ impl SimpleEntity for HangarPoster {
     type CellMethod = HangarPoster_Cell;
 }
 
+impl HangarPoster {
+    pub fn minAlpha(&self) -> &f32 { &self.minAlpha }
+    pub fn set_minAlpha(&mut self, value: f32, dirty: &mut DirtyProperties) { self.minAlpha = value; dirty.mark(0); }
+    pub fn maxAlphaDistance(&self) -> &f32 { &self.maxAlphaDistance }
+    pub fn set_maxAlphaDistance(&mut self, value: f32, dirty: &mut DirtyProperties) { self.maxAlphaDistance = value; dirty.mark(1); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum HangarPoster_Property {
+        minAlpha(0x00, f32, 4),
+        maxAlphaDistance(0x01, f32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    HangarPoster {
+        "minAlpha" => minAlpha,
+        "maxAlphaDistance" => maxAlphaDistance,
+    }
+}
+
+impl Properties for HangarPoster {
+    type Property = HangarPoster_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.minAlpha, write, &()),
+            1 => Codec::<()>::write(&self.maxAlphaDistance, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======             TeamInfo             ====== //
 // ============================================== //
@@ -2805,6 +4711,41 @@ impl SimpleEntity for TeamInfo {
     type CellMethod = TeamInfo_Cell;
 }
 
+impl TeamInfo {
+    pub fn teamID(&self) -> &i32 { &self.teamID }
+    pub fn set_teamID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.teamID = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum TeamInfo_Property {
+        teamID(0x00, i32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    TeamInfo {
+        "teamID" => teamID,
+    }
+}
+
+impl Properties for TeamInfo {
+    type Property = TeamInfo_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.teamID, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======            AvatarInfo            ====== //
 // ============================================== //
@@ -2856,6 +4797,41 @@ impl SimpleEntity for AvatarInfo {
     type CellMethod = AvatarInfo_Cell;
 }
 
+impl AvatarInfo {
+    pub fn avatarID(&self) -> &OBJECT_ID { &self.avatarID }
+    pub fn set_avatarID(&mut self, value: OBJECT_ID, dirty: &mut DirtyProperties) { self.avatarID = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum AvatarInfo_Property {
+        avatarID(0x00, OBJECT_ID, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    AvatarInfo {
+        "avatarID" => avatarID,
+    }
+}
+
+impl Properties for AvatarInfo {
+    type Property = AvatarInfo_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.avatarID, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======        ArenaObserverInfo         ====== //
 // ============================================== //
@@ -2906,6 +4882,35 @@ impl SimpleEntity for ArenaObserverInfo {
     type CellMethod = ArenaObserverInfo_Cell;
 }
 
+impl ArenaObserverInfo {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ArenaObserverInfo_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ArenaObserverInfo {
+    }
+}
+
+impl Properties for ArenaObserverInfo {
+    type Property = ArenaObserverInfo_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           AreaOfEffect           ====== //
 // ============================================== //
@@ -2968,6 +4973,59 @@ impl SimpleEntity for AreaOfEffect {
     type CellMethod = AreaOfEffect_Cell;
 }
 
+impl AreaOfEffect {
+    pub fn vehicleID(&self) -> &i32 { &self.vehicleID }
+    pub fn set_vehicleID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.vehicleID = value; dirty.mark(0); }
+    pub fn equipmentID(&self) -> &i32 { &self.equipmentID }
+    pub fn set_equipmentID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.equipmentID = value; dirty.mark(1); }
+    pub fn launchTime(&self) -> &f64 { &self.launchTime }
+    pub fn set_launchTime(&mut self, value: f64, dirty: &mut DirtyProperties) { self.launchTime = value; dirty.mark(2); }
+    pub fn strikeTime(&self) -> &f64 { &self.strikeTime }
+    pub fn set_strikeTime(&mut self, value: f64, dirty: &mut DirtyProperties) { self.strikeTime = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum AreaOfEffect_Property {
+        vehicleID(0x00, i32, 4),
+        equipmentID(0x01, i32, 4),
+        launchTime(0x02, f64, 8),
+        strikeTime(0x03, f64, 8),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    AreaOfEffect {
+        "vehicleID" => vehicleID,
+        "equipmentID" => equipmentID,
+        "launchTime" => launchTime,
+        "strikeTime" => strikeTime,
+    }
+}
+
+impl Properties for AreaOfEffect {
+    type Property = AreaOfEffect_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(8),
+            3 => ElementLength::Fixed(8),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.vehicleID, write, &()),
+            1 => Codec::<()>::write(&self.equipmentID, write, &()),
+            2 => Codec::<()>::write(&self.launchTime, write, &()),
+            3 => Codec::<()>::write(&self.strikeTime, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======           AttackBomber           ====== //
 // ============================================== //
@@ -3018,6 +5076,35 @@ impl SimpleEntity for AttackBomber {
     type CellMethod = AttackBomber_Cell;
 }
 
+impl AttackBomber {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum AttackBomber_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    AttackBomber {
+    }
+}
+
+impl Properties for AttackBomber {
+    type Property = AttackBomber_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======       AttackArtilleryFort        ====== //
 // ============================================== //
@@ -3069,6 +5156,41 @@ impl SimpleEntity for AttackArtilleryFort {
     type CellMethod = AttackArtilleryFort_Cell;
 }
 
+impl AttackArtilleryFort {
+    pub fn team(&self) -> &i32 { &self.team }
+    pub fn set_team(&mut self, value: i32, dirty: &mut DirtyProperties) { self.team = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum AttackArtilleryFort_Property {
+        team(0x00, i32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    AttackArtilleryFort {
+        "team" => team,
+    }
+}
+
+impl Properties for AttackArtilleryFort {
+    type Property = AttackArtilleryFort_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.team, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======        PersonalDeathZone         ====== //
 // ============================================== //
@@ -3119,6 +5241,35 @@ impl SimpleEntity for PersonalDeathZone {
     type CellMethod = PersonalDeathZone_Cell;
 }
 
+impl PersonalDeathZone {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum PersonalDeathZone_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    PersonalDeathZone {
+    }
+}
+
+impl Properties for PersonalDeathZone {
+    type Property = PersonalDeathZone_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======   ClientSelectableRankedObject   ====== //
 // ============================================== //
@@ -3169,6 +5320,35 @@ impl SimpleEntity for ClientSelectableRankedObject {
     type CellMethod = ClientSelectableRankedObject_Cell;
 }
 
+impl ClientSelectableRankedObject {
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableRankedObject_Property {
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableRankedObject {
+    }
+}
+
+impl Properties for ClientSelectableRankedObject {
+    type Property = ClientSelectableRankedObject_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         SimulatedVehicle         ====== //
 // ============================================== //
@@ -3237,6 +5417,138 @@ impl SimpleEntity for SimulatedVehicle {
     type CellMethod = SimulatedVehicle_Cell;
 }
 
+impl SimulatedVehicle {
+    pub fn publicInfo(&self) -> &PUBLIC_VEHICLE_INFO { &self.publicInfo }
+    pub fn set_publicInfo(&mut self, value: PUBLIC_VEHICLE_INFO, dirty: &mut DirtyProperties) { self.publicInfo = value; dirty.mark(0); }
+    pub fn isPlayerVehicle(&self) -> &BOOL { &self.isPlayerVehicle }
+    pub fn set_isPlayerVehicle(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isPlayerVehicle = value; dirty.mark(1); }
+    pub fn realVehicleID(&self) -> &OBJECT_ID { &self.realVehicleID }
+    pub fn set_realVehicleID(&mut self, value: OBJECT_ID, dirty: &mut DirtyProperties) { self.realVehicleID = value; dirty.mark(2); }
+    pub fn simulationData_position(&self) -> &Vec3 { &self.simulationData_position }
+    pub fn set_simulationData_position(&mut self, value: Vec3, dirty: &mut DirtyProperties) { self.simulationData_position = value; dirty.mark(3); }
+    pub fn simulationData_rotation(&self) -> &Vec3 { &self.simulationData_rotation }
+    pub fn set_simulationData_rotation(&mut self, value: Vec3, dirty: &mut DirtyProperties) { self.simulationData_rotation = value; dirty.mark(4); }
+    pub fn simulationData_velocity(&self) -> &Vec3 { &self.simulationData_velocity }
+    pub fn set_simulationData_velocity(&mut self, value: Vec3, dirty: &mut DirtyProperties) { self.simulationData_velocity = value; dirty.mark(5); }
+    pub fn simulationData_angVelocity(&self) -> &Vec3 { &self.simulationData_angVelocity }
+    pub fn set_simulationData_angVelocity(&mut self, value: Vec3, dirty: &mut DirtyProperties) { self.simulationData_angVelocity = value; dirty.mark(6); }
+    pub fn simulationData_simulationType(&self) -> &AutoString { &self.simulationData_simulationType }
+    pub fn set_simulationData_simulationType(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.simulationData_simulationType = value; dirty.mark(7); }
+    pub fn simulationData_health(&self) -> &i16 { &self.simulationData_health }
+    pub fn set_simulationData_health(&mut self, value: i16, dirty: &mut DirtyProperties) { self.simulationData_health = value; dirty.mark(8); }
+    pub fn simulationData_engineMode(&self) -> &Box<[u8; 2]> { &self.simulationData_engineMode }
+    pub fn set_simulationData_engineMode(&mut self, value: Box<[u8; 2]>, dirty: &mut DirtyProperties) { self.simulationData_engineMode = value; dirty.mark(9); }
+    pub fn simulationData_gunAngles(&self) -> &Vec2 { &self.simulationData_gunAngles }
+    pub fn set_simulationData_gunAngles(&mut self, value: Vec2, dirty: &mut DirtyProperties) { self.simulationData_gunAngles = value; dirty.mark(10); }
+    pub fn simulationData_turretAndGunSpeed(&self) -> &Vec2 { &self.simulationData_turretAndGunSpeed }
+    pub fn set_simulationData_turretAndGunSpeed(&mut self, value: Vec2, dirty: &mut DirtyProperties) { self.simulationData_turretAndGunSpeed = value; dirty.mark(11); }
+    pub fn simulationData_damageStickers(&self) -> &Vec<u64> { &self.simulationData_damageStickers }
+    pub fn set_simulationData_damageStickers(&mut self, value: Vec<u64>, dirty: &mut DirtyProperties) { self.simulationData_damageStickers = value; dirty.mark(12); }
+    pub fn simulationData_brokenTracks(&self) -> &Vec<TRACK_STATE> { &self.simulationData_brokenTracks }
+    pub fn set_simulationData_brokenTracks(&mut self, value: Vec<TRACK_STATE>, dirty: &mut DirtyProperties) { self.simulationData_brokenTracks = value; dirty.mark(13); }
+    pub fn simulationData_siegeState(&self) -> &BOOL { &self.simulationData_siegeState }
+    pub fn set_simulationData_siegeState(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.simulationData_siegeState = value; dirty.mark(14); }
+    pub fn simulationData_wheelsState(&self) -> &u16 { &self.simulationData_wheelsState }
+    pub fn set_simulationData_wheelsState(&mut self, value: u16, dirty: &mut DirtyProperties) { self.simulationData_wheelsState = value; dirty.mark(15); }
+    pub fn simulationData_wheelsSteering(&self) -> &Vec<f32> { &self.simulationData_wheelsSteering }
+    pub fn set_simulationData_wheelsSteering(&mut self, value: Vec<f32>, dirty: &mut DirtyProperties) { self.simulationData_wheelsSteering = value; dirty.mark(16); }
+    pub fn simulationData_tracksInAir(&self) -> &Box<[BOOL; 2]> { &self.simulationData_tracksInAir }
+    pub fn set_simulationData_tracksInAir(&mut self, value: Box<[BOOL; 2]>, dirty: &mut DirtyProperties) { self.simulationData_tracksInAir = value; dirty.mark(17); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum SimulatedVehicle_Property {
+        publicInfo(0x00, PUBLIC_VEHICLE_INFO, var16),
+        isPlayerVehicle(0x01, BOOL, 1),
+        realVehicleID(0x02, OBJECT_ID, 4),
+        simulationData_position(0x03, Vec3, 12),
+        simulationData_rotation(0x04, Vec3, 12),
+        simulationData_velocity(0x05, Vec3, 12),
+        simulationData_angVelocity(0x06, Vec3, 12),
+        simulationData_simulationType(0x07, AutoString, var16),
+        simulationData_health(0x08, i16, 2),
+        simulationData_engineMode(0x09, Box<[u8; 2]>, 2),
+        simulationData_gunAngles(0x0A, Vec2, 8),
+        simulationData_turretAndGunSpeed(0x0B, Vec2, 8),
+        simulationData_damageStickers(0x0C, Vec<u64>, var16),
+        simulationData_brokenTracks(0x0D, Vec<TRACK_STATE>, var16),
+        simulationData_siegeState(0x0E, BOOL, 1),
+        simulationData_wheelsState(0x0F, u16, 2),
+        simulationData_wheelsSteering(0x10, Vec<f32>, var16),
+        simulationData_tracksInAir(0x11, Box<[BOOL; 2]>, 2),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    SimulatedVehicle {
+        "publicInfo" => publicInfo,
+        "isPlayerVehicle" => isPlayerVehicle,
+        "realVehicleID" => realVehicleID,
+        "simulationData_position" => simulationData_position,
+        "simulationData_rotation" => simulationData_rotation,
+        "simulationData_velocity" => simulationData_velocity,
+        "simulationData_angVelocity" => simulationData_angVelocity,
+        "simulationData_simulationType" => simulationData_simulationType,
+        "simulationData_health" => simulationData_health,
+        "simulationData_engineMode" => simulationData_engineMode,
+        "simulationData_gunAngles" => simulationData_gunAngles,
+        "simulationData_turretAndGunSpeed" => simulationData_turretAndGunSpeed,
+        "simulationData_damageStickers" => simulationData_damageStickers,
+        "simulationData_brokenTracks" => simulationData_brokenTracks,
+        "simulationData_siegeState" => simulationData_siegeState,
+        "simulationData_wheelsState" => simulationData_wheelsState,
+        "simulationData_wheelsSteering" => simulationData_wheelsSteering,
+        "simulationData_tracksInAir" => simulationData_tracksInAir,
+    }
+}
+
+impl Properties for SimulatedVehicle {
+    type Property = SimulatedVehicle_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            1 => ElementLength::Fixed(1),
+            2 => ElementLength::Fixed(4),
+            3 => ElementLength::Fixed(12),
+            4 => ElementLength::Fixed(12),
+            5 => ElementLength::Fixed(12),
+            6 => ElementLength::Fixed(12),
+            8 => ElementLength::Fixed(2),
+            9 => ElementLength::Fixed(2),
+            10 => ElementLength::Fixed(8),
+            11 => ElementLength::Fixed(8),
+            14 => ElementLength::Fixed(1),
+            15 => ElementLength::Fixed(2),
+            17 => ElementLength::Fixed(2),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.publicInfo, write, &()),
+            1 => Codec::<()>::write(&self.isPlayerVehicle, write, &()),
+            2 => Codec::<()>::write(&self.realVehicleID, write, &()),
+            3 => Codec::<()>::write(&self.simulationData_position, write, &()),
+            4 => Codec::<()>::write(&self.simulationData_rotation, write, &()),
+            5 => Codec::<()>::write(&self.simulationData_velocity, write, &()),
+            6 => Codec::<()>::write(&self.simulationData_angVelocity, write, &()),
+            7 => Codec::<()>::write(&self.simulationData_simulationType, write, &()),
+            8 => Codec::<()>::write(&self.simulationData_health, write, &()),
+            9 => Codec::<()>::write(&self.simulationData_engineMode, write, &()),
+            10 => Codec::<()>::write(&self.simulationData_gunAngles, write, &()),
+            11 => Codec::<()>::write(&self.simulationData_turretAndGunSpeed, write, &()),
+            12 => Codec::<()>::write(&self.simulationData_damageStickers, write, &()),
+            13 => Codec::<()>::write(&self.simulationData_brokenTracks, write, &()),
+            14 => Codec::<()>::write(&self.simulationData_siegeState, write, &()),
+            15 => Codec::<()>::write(&self.simulationData_wheelsState, write, &()),
+            16 => Codec::<()>::write(&self.simulationData_wheelsSteering, write, &()),
+            17 => Codec::<()>::write(&self.simulationData_tracksInAir, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ====== ClientSelectableHangarsSwitcher  ====== //
 // ============================================== //
@@ -3288,6 +5600,40 @@ impl SimpleEntity for ClientSelectableHangarsSwitcher {
     type CellMethod = ClientSelectableHangarsSwitcher_Cell;
 }
 
+impl ClientSelectableHangarsSwitcher {
+    pub fn destHangar(&self) -> &AutoString { &self.destHangar }
+    pub fn set_destHangar(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.destHangar = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ClientSelectableHangarsSwitcher_Property {
+        destHangar(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ClientSelectableHangarsSwitcher {
+        "destHangar" => destHangar,
+    }
+}
+
+impl Properties for ClientSelectableHangarsSwitcher {
+    type Property = ClientSelectableHangarsSwitcher_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.destHangar, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         StaticDeathZone          ====== //
 // ============================================== //
@@ -3371,6 +5717,62 @@ impl SimpleEntity for StaticDeathZone {
     type CellMethod = StaticDeathZone_Cell;
 }
 
+impl StaticDeathZone {
+    pub fn zoneId(&self) -> &AutoString { &self.zoneId }
+    pub fn set_zoneId(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.zoneId = value; dirty.mark(0); }
+    pub fn isActive(&self) -> &BOOL { &self.isActive }
+    pub fn set_isActive(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isActive = value; dirty.mark(1); }
+    pub fn vehiclesUnderFire(&self) -> &Vec<VEHICLE_IN_DEATHZONE> { &self.vehiclesUnderFire }
+    pub fn set_vehiclesUnderFire(&mut self, value: Vec<VEHICLE_IN_DEATHZONE>, dirty: &mut DirtyProperties) { self.vehiclesUnderFire = value; dirty.mark(2); }
+    pub fn maskingPolygonsCount(&self) -> &u8 { &self.maskingPolygonsCount }
+    pub fn set_maskingPolygonsCount(&mut self, value: u8, dirty: &mut DirtyProperties) { self.maskingPolygonsCount = value; dirty.mark(3); }
+    pub fn proximityMarkerStyle(&self) -> &AutoString { &self.proximityMarkerStyle }
+    pub fn set_proximityMarkerStyle(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.proximityMarkerStyle = value; dirty.mark(4); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum StaticDeathZone_Property {
+        zoneId(0x00, AutoString, var16),
+        isActive(0x01, BOOL, 1),
+        vehiclesUnderFire(0x02, Vec<VEHICLE_IN_DEATHZONE>, var16),
+        maskingPolygonsCount(0x03, u8, 1),
+        proximityMarkerStyle(0x04, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    StaticDeathZone {
+        "zoneId" => zoneId,
+        "isActive" => isActive,
+        "vehiclesUnderFire" => vehiclesUnderFire,
+        "maskingPolygonsCount" => maskingPolygonsCount,
+        "proximityMarkerStyle" => proximityMarkerStyle,
+    }
+}
+
+impl Properties for StaticDeathZone {
+    type Property = StaticDeathZone_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            1 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.zoneId, write, &()),
+            1 => Codec::<()>::write(&self.isActive, write, &()),
+            2 => Codec::<()>::write(&self.vehiclesUnderFire, write, &()),
+            3 => Codec::<()>::write(&self.maskingPolygonsCount, write, &()),
+            4 => Codec::<()>::write(&self.proximityMarkerStyle, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======            BasicMine             ====== //
 // ============================================== //
@@ -3428,6 +5830,77 @@ impl SimpleEntity for BasicMine {
     type CellMethod = BasicMine_Cell;
 }
 
+impl BasicMine {
+    pub fn equipmentID(&self) -> &u32 { &self.equipmentID }
+    pub fn set_equipmentID(&mut self, value: u32, dirty: &mut DirtyProperties) { self.equipmentID = value; dirty.mark(0); }
+    pub fn ownerVehicleID(&self) -> &u32 { &self.ownerVehicleID }
+    pub fn set_ownerVehicleID(&mut self, value: u32, dirty: &mut DirtyProperties) { self.ownerVehicleID = value; dirty.mark(1); }
+    pub fn isDetonated(&self) -> &BOOL { &self.isDetonated }
+    pub fn set_isDetonated(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isDetonated = value; dirty.mark(2); }
+    pub fn isActivated(&self) -> &BOOL { &self.isActivated }
+    pub fn set_isActivated(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isActivated = value; dirty.mark(3); }
+    pub fn activationTimeDelay(&self) -> &u32 { &self.activationTimeDelay }
+    pub fn set_activationTimeDelay(&mut self, value: u32, dirty: &mut DirtyProperties) { self.activationTimeDelay = value; dirty.mark(4); }
+    pub fn mineNumber(&self) -> &u8 { &self.mineNumber }
+    pub fn set_mineNumber(&mut self, value: u8, dirty: &mut DirtyProperties) { self.mineNumber = value; dirty.mark(5); }
+    pub fn isMarkerEnabled(&self) -> &BOOL { &self.isMarkerEnabled }
+    pub fn set_isMarkerEnabled(&mut self, value: BOOL, dirty: &mut DirtyProperties) { self.isMarkerEnabled = value; dirty.mark(6); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum BasicMine_Property {
+        equipmentID(0x00, u32, 4),
+        ownerVehicleID(0x01, u32, 4),
+        isDetonated(0x02, BOOL, 1),
+        isActivated(0x03, BOOL, 1),
+        activationTimeDelay(0x04, u32, 4),
+        mineNumber(0x05, u8, 1),
+        isMarkerEnabled(0x06, BOOL, 1),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    BasicMine {
+        "equipmentID" => equipmentID,
+        "ownerVehicleID" => ownerVehicleID,
+        "isDetonated" => isDetonated,
+        "isActivated" => isActivated,
+        "activationTimeDelay" => activationTimeDelay,
+        "mineNumber" => mineNumber,
+        "isMarkerEnabled" => isMarkerEnabled,
+    }
+}
+
+impl Properties for BasicMine {
+    type Property = BasicMine_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(1),
+            3 => ElementLength::Fixed(1),
+            4 => ElementLength::Fixed(4),
+            5 => ElementLength::Fixed(1),
+            6 => ElementLength::Fixed(1),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.equipmentID, write, &()),
+            1 => Codec::<()>::write(&self.ownerVehicleID, write, &()),
+            2 => Codec::<()>::write(&self.isDetonated, write, &()),
+            3 => Codec::<()>::write(&self.isActivated, write, &()),
+            4 => Codec::<()>::write(&self.activationTimeDelay, write, &()),
+            5 => Codec::<()>::write(&self.mineNumber, write, &()),
+            6 => Codec::<()>::write(&self.isMarkerEnabled, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======         ApplicationPoint         ====== //
 // ============================================== //
@@ -3482,6 +5955,59 @@ impl SimpleEntity for ApplicationPoint {
     type CellMethod = ApplicationPoint_Cell;
 }
 
+impl ApplicationPoint {
+    pub fn vehicleID(&self) -> &i32 { &self.vehicleID }
+    pub fn set_vehicleID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.vehicleID = value; dirty.mark(0); }
+    pub fn equipmentID(&self) -> &i32 { &self.equipmentID }
+    pub fn set_equipmentID(&mut self, value: i32, dirty: &mut DirtyProperties) { self.equipmentID = value; dirty.mark(1); }
+    pub fn launchTime(&self) -> &f32 { &self.launchTime }
+    pub fn set_launchTime(&mut self, value: f32, dirty: &mut DirtyProperties) { self.launchTime = value; dirty.mark(2); }
+    pub fn level(&self) -> &i32 { &self.level }
+    pub fn set_level(&mut self, value: i32, dirty: &mut DirtyProperties) { self.level = value; dirty.mark(3); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum ApplicationPoint_Property {
+        vehicleID(0x00, i32, 4),
+        equipmentID(0x01, i32, 4),
+        launchTime(0x02, f32, 4),
+        level(0x03, i32, 4),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    ApplicationPoint {
+        "vehicleID" => vehicleID,
+        "equipmentID" => equipmentID,
+        "launchTime" => launchTime,
+        "level" => level,
+    }
+}
+
+impl Properties for ApplicationPoint {
+    type Property = ApplicationPoint_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            0 => ElementLength::Fixed(4),
+            1 => ElementLength::Fixed(4),
+            2 => ElementLength::Fixed(4),
+            3 => ElementLength::Fixed(4),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.vehicleID, write, &()),
+            1 => Codec::<()>::write(&self.equipmentID, write, &()),
+            2 => Codec::<()>::write(&self.launchTime, write, &()),
+            3 => Codec::<()>::write(&self.level, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          NetworkEntity           ====== //
 // ============================================== //
@@ -3572,6 +6098,61 @@ impl SimpleEntity for NetworkEntity {
     type CellMethod = NetworkEntity_Cell;
 }
 
+impl NetworkEntity {
+    pub fn unique_id(&self) -> &AutoString { &self.unique_id }
+    pub fn set_unique_id(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.unique_id = value; dirty.mark(0); }
+    pub fn prefab_path(&self) -> &AutoString { &self.prefab_path }
+    pub fn set_prefab_path(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.prefab_path = value; dirty.mark(1); }
+    pub fn scale(&self) -> &Vec3 { &self.scale }
+    pub fn set_scale(&mut self, value: Vec3, dirty: &mut DirtyProperties) { self.scale = value; dirty.mark(2); }
+    pub fn goState(&self) -> &Vec<GAME_OBJECT_STATE> { &self.goState }
+    pub fn set_goState(&mut self, value: Vec<GAME_OBJECT_STATE>, dirty: &mut DirtyProperties) { self.goState = value; dirty.mark(3); }
+    pub fn name(&self) -> &AutoString { &self.name }
+    pub fn set_name(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.name = value; dirty.mark(4); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum NetworkEntity_Property {
+        unique_id(0x00, AutoString, var16),
+        prefab_path(0x01, AutoString, var16),
+        scale(0x02, Vec3, 12),
+        goState(0x03, Vec<GAME_OBJECT_STATE>, var16),
+        name(0x04, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    NetworkEntity {
+        "unique_id" => unique_id,
+        "prefab_path" => prefab_path,
+        "scale" => scale,
+        "goState" => goState,
+        "name" => name,
+    }
+}
+
+impl Properties for NetworkEntity {
+    type Property = NetworkEntity_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            2 => ElementLength::Fixed(12),
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.unique_id, write, &()),
+            1 => Codec::<()>::write(&self.prefab_path, write, &()),
+            2 => Codec::<()>::write(&self.scale, write, &()),
+            3 => Codec::<()>::write(&self.goState, write, &()),
+            4 => Codec::<()>::write(&self.name, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
 // ============================================== //
 // ======          Comp7Lighting           ====== //
 // ============================================== //
@@ -3623,3 +6204,86 @@ impl SimpleEntity for Comp7Lighting {
     type CellMethod = Comp7Lighting_Cell;
 }
 
+impl Comp7Lighting {
+    pub fn animationStateMachine(&self) -> &AutoString { &self.animationStateMachine }
+    pub fn set_animationStateMachine(&mut self, value: AutoString, dirty: &mut DirtyProperties) { self.animationStateMachine = value; dirty.mark(0); }
+}
+
+wgtk::__enum_entity_properties! {  // Client-visible properties
+    #[derive(Debug)]
+    pub enum Comp7Lighting_Property {
+        animationStateMachine(0x00, AutoString, var16),
+    }
+}
+
+wgtk::__impl_nested_property! {
+    Comp7Lighting {
+        "animationStateMachine" => animationStateMachine,
+    }
+}
+
+impl Properties for Comp7Lighting {
+    type Property = Comp7Lighting_Property;
+    fn property_length(index: u16) -> ElementLength {
+        match index {
+            _ => ElementLength::Variable16,
+        }
+    }
+    fn write_property(&self, index: u16, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+        use wgtk::net::codec::Codec;
+        match index {
+            0 => Codec::<()>::write(&self.animationStateMachine, write, &()),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid property index: {index}"))),
+        }
+    }
+}
+
+wgtk::__bootstrap_enum_entities! {
+    #[derive(Debug)]
+    pub enum Generic: Generic_Client, Generic_Base, Generic_Cell {
+        Account = 0x01,
+        Avatar = 0x02,
+        ArenaInfo = 0x03,
+        ClientSelectableObject = 0x04,
+        HangarVehicle = 0x05,
+        Vehicle = 0x06,
+        AreaDestructibles = 0x07,
+        OfflineEntity = 0x08,
+        Flock = 0x09,
+        FlockExotic = 0x0A,
+        Login = 0x0B,
+        DetachedTurret = 0x0C,
+        DebugDrawEntity = 0x0D,
+        ClientSelectableCameraObject = 0x0E,
+        ClientSelectableCameraVehicle = 0x0F,
+        ClientSelectableWebLinksOpener = 0x10,
+        ClientSelectableEasterEgg = 0x11,
+        EmptyEntity = 0x12,
+        LimitedVisibilityEntity = 0x13,
+        HeroTank = 0x14,
+        PlatoonTank = 0x15,
+        PlatoonLighting = 0x16,
+        SectorBase = 0x17,
+        Sector = 0x18,
+        DestructibleEntity = 0x19,
+        StepRepairPoint = 0x1A,
+        ProtectionZone = 0x1B,
+        HangarPoster = 0x1C,
+        TeamInfo = 0x1D,
+        AvatarInfo = 0x1E,
+        ArenaObserverInfo = 0x1F,
+        AreaOfEffect = 0x20,
+        AttackBomber = 0x21,
+        AttackArtilleryFort = 0x22,
+        PersonalDeathZone = 0x23,
+        ClientSelectableRankedObject = 0x24,
+        SimulatedVehicle = 0x25,
+        ClientSelectableHangarsSwitcher = 0x26,
+        StaticDeathZone = 0x27,
+        BasicMine = 0x28,
+        ApplicationPoint = 0x29,
+        NetworkEntity = 0x2A,
+        Comp7Lighting = 0x2B,
+    }
+}
+
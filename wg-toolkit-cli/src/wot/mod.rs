@@ -3,26 +3,136 @@
 pub mod gen;
 pub mod proxy;
 pub mod emulator;
+pub mod dissect;
+pub mod replay;
+pub mod stats;
+pub mod ping;
 
-use std::sync::Arc;
-use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::fs::{self, File};
 
 use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
 use rsa::{RsaPrivateKey, RsaPublicKey};
 
+use wgtk::net::variant::ProtocolVariant;
+
 use tracing::level_filters::LevelFilter;
 
-use crate::{CliResult, WotArgs};
+use crate::{CliResult, WotArgs, WotCommand, WotReplayCaptureArgs};
+use crate::defs::runtime::{UserTypeCodec, UserTypes};
 
 
 /// Entrypoint.
 pub fn cmd_wot(args: WotArgs) -> CliResult<()> {
 
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::builder()
-            .with_default_directive(LevelFilter::TRACE.into())
-            .from_env_lossy())
-        .init();
+    match &args.cmd {
+        // The dissector takes the whole terminal, so it must be run before the tracing
+        // subscriber is installed.
+        Some(WotCommand::Dissect(dissect_args)) => {
+
+            let blowfish_key = dissect_args.blowfish_key.as_deref()
+                .map(|key| parse_hex(key).ok_or_else(|| format!("Invalid hex blowfish key: {key}")))
+                .transpose()?;
+
+            return dissect::run(&dissect_args.file, blowfish_key.as_deref(), dissect_args.base_app);
+
+        }
+        Some(WotCommand::ReplayPcap(replay_args)) => {
+            return replay::run_to_pcap(&replay_args.file, &replay_args.output, replay_args.server, replay_args.client);
+        }
+        Some(WotCommand::ReplayAnonymize(replay_args)) => {
+            return replay::run_anonymize(&replay_args.file, &replay_args.output);
+        }
+        Some(WotCommand::ReplayStats(stats_args)) => {
+            return stats::run(&stats_args.dir, &stats_args.output, stats_args.positions);
+        }
+        Some(WotCommand::Ping(ping_args)) => {
+            return ping::run(&ping_args.targets, ping_args.scripts_config.as_deref(), ping_args.count, 
+                Duration::from_millis(ping_args.interval), Duration::from_millis(ping_args.timeout));
+        }
+        Some(WotCommand::ReplayCapture(_)) | None => {}
+    }
+
+    let env_filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive(LevelFilter::TRACE.into())
+        .from_env_lossy();
+
+    // The proxy and the capture playback can emit their log as a JSON stream, one 
+    // event per line.
+    let json = args.json || matches!(&args.cmd, Some(WotCommand::ReplayCapture(WotReplayCaptureArgs { json: true, .. })));
+    if json {
+        tracing_subscriber::fmt()
+            .json()
+            .with_env_filter(env_filter)
+            .init();
+    } else if args.tui {
+        // The terminal interface takes the whole terminal, so the log goes to a file.
+        let log_file = File::create("proxy.log")
+            .map_err(|e| format!("Failed to create log file: {e}"))?;
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_ansi(false)
+            .with_writer(Mutex::new(log_file))
+            .init();
+    } else {
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .init();
+    }
+
+    let profile_interval = args.profile.then(|| Duration::from_secs(args.profile_interval));
+    let metrics_interval = args.metrics_interval.map(Duration::from_secs);
+
+    #[cfg(feature = "sqlite")]
+    let database_path = args.dump_db.as_deref();
+    #[cfg(not(feature = "sqlite"))]
+    let database_path = None;
+
+    let user_types = parse_user_types(&args.user_type)?;
+
+    let options = proxy::ProxyOptions {
+        profile_interval,
+        metrics_interval,
+        packet_ring_len: args.packet_ring,
+        entity_defs: args.entity_defs.as_deref(),
+        user_types: &user_types,
+        fixtures_dir: args.fixtures.as_deref(),
+        resource_spill_dir: args.resource_spill_dir.as_deref(),
+        record_path: args.record.as_deref(),
+        database_path,
+        tui: args.tui,
+    };
+
+    if let Some(WotCommand::ReplayCapture(capture_args)) = &args.cmd {
+        return proxy::run_capture(&capture_args.file, &options);
+    }
+
+    if let Some(pcap_path) = args.pcap.as_deref() {
+
+        // Unwrap because these are required by the pcap argument.
+        let blowfish_key = args.pcap_blowfish_key.as_deref().unwrap();
+        let base_app_addr = args.pcap_base_app.unwrap();
+
+        let blowfish_key = parse_hex(blowfish_key)
+            .ok_or_else(|| format!("Invalid hex blowfish key: {blowfish_key}"))?;
+
+        return proxy::run_pcap(pcap_path, &blowfish_key, base_app_addr, &options);
+
+    }
+
+    let login_digest = match args.login_digest.as_deref() {
+        Some(login_digest) => Some(parse_hex(login_digest)
+            .and_then(|digest| <[u8; 16]>::try_from(digest).ok())
+            .ok_or_else(|| format!("Invalid hex login digest, expected 16 bytes: {login_digest}"))?),
+        None => None,
+    };
+
+    let protocol_variant = match args.protocol_variant.as_deref() {
+        Some(protocol_variant) => protocol_variant.parse::<ProtocolVariant>()
+            .map_err(|e| e.to_string())?,
+        None => ProtocolVariant::pc(),
+    };
 
     // Start by decoding the private key...
     let encryption_key;
@@ -40,6 +150,12 @@ pub fn cmd_wot(args: WotArgs) -> CliResult<()> {
 
     if let Some(real_login_app) = args.real_login_app {
 
+        let login_params_dump = match (args.dump_login_params, args.dump_login_secrets) {
+            (false, _) => None,
+            (true, false) => Some(proxy::LoginParamsDump::Redacted),
+            (true, true) => Some(proxy::LoginParamsDump::Full),
+        };
+
         let real_encryption_key;
         if let Some(pub_key_path) = args.real_pub_key_path.as_deref() {
             
@@ -55,10 +171,46 @@ pub fn cmd_wot(args: WotArgs) -> CliResult<()> {
             real_encryption_key = None;
         }
         
-        proxy::run(args.login_app, real_login_app, args.base_app, encryption_key, real_encryption_key)
+        proxy::run(&options, proxy::LiveOptions {
+            login_app_addr: args.login_app,
+            real_login_app_addr: real_login_app,
+            base_app_addr: args.base_app,
+            base_app_workers: args.base_app_workers,
+            decode_workers: args.decode_workers,
+            login_protocol_version: args.login_protocol,
+            login_digest,
+            protocol_variant,
+            login_params_dump,
+            encryption_key,
+            real_encryption_key,
+        })
         
     } else {
-        emulator::run(args.login_app, args.base_app, encryption_key)
+        emulator::run(args.login_app, args.base_app, encryption_key, args.login_protocol, login_digest, &protocol_variant)
     }
 
 }
+
+/// Parse the codecs of user types, each given as NAME=CODEC.
+fn parse_user_types(user_types: &[String]) -> CliResult<UserTypes> {
+    let mut registry = UserTypes::new();
+    for user_type in user_types {
+        let (name, codec) = user_type.split_once('=')
+            .ok_or_else(|| format!("Invalid user type, expected NAME=CODEC: {user_type}"))?;
+        let codec = UserTypeCodec::from_name(codec)
+            .ok_or_else(|| format!("Unknown user type codec, expected 'blob', 'python' or 'string': {codec}"))?;
+        registry.register(name, codec);
+    }
+    Ok(registry)
+}
+
+/// Parse an hex-encoded string of bytes, returning none if invalid.
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
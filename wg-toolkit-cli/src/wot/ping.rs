@@ -0,0 +1,220 @@
+//! Ping of login applications, reporting their latency and loss.
+
+use std::net::{SocketAddr, SocketAddrV4, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use std::path::Path;
+use std::fs::File;
+use std::io;
+
+use wgtk::net::bundle::{Bundle, NextElementReader, RequestTracker, TrackedReply};
+use wgtk::net::app::login::element::Ping;
+use wgtk::net::socket::PacketSocket;
+use wgtk::net::proto::Protocol;
+use wgtk::pxml::{self, Value};
+
+use crate::CliResult;
+
+
+/// A login app to ping, with the name used to report it.
+#[derive(Debug)]
+struct Target {
+    name: String,
+    addr: SocketAddr,
+}
+
+/// Ping statistics of a login app.
+#[derive(Debug, Default)]
+struct PingStats {
+    sent: u32,
+    latencies: Vec<Duration>,
+}
+
+/// Ping each target in turn, a target is either a socket address or the name of a
+/// periphery in the given scripts config. All peripheries of the scripts config are
+/// pinged if no target is given.
+pub fn run(targets: &[String], scripts_config: Option<&Path>, count: u32, interval: Duration, timeout: Duration) -> CliResult<()> {
+
+    let peripheries = match scripts_config {
+        Some(path) => read_peripheries(path)?,
+        None => Vec::new(),
+    };
+
+    let targets = if targets.is_empty() {
+        if peripheries.is_empty() {
+            return Err("No target to ping, give an address or a scripts config with peripheries".to_string());
+        }
+        peripheries
+    } else {
+        targets.iter()
+            .map(|target| resolve_target(target, &peripheries))
+            .collect::<CliResult<Vec<_>>>()?
+    };
+
+    for target in &targets {
+
+        println!("== Pinging {} ({})...", target.name, target.addr);
+
+        let stats = ping(target.addr, count, interval, timeout)
+            .map_err(|e| format!("Failed to ping {}, reason: {e}", target.name))?;
+
+        let received = stats.latencies.len() as u32;
+        let loss = (stats.sent - received) as f32 / stats.sent.max(1) as f32 * 100.0;
+        println!("{} sent, {received} received, {loss:.0}% loss", stats.sent);
+
+        if let (Some(min), Some(max)) = (stats.latencies.iter().min(), stats.latencies.iter().max()) {
+            let avg = stats.latencies.iter().sum::<Duration>() / received;
+            println!("latency min/avg/max = {:.1}/{:.1}/{:.1} ms",
+                min.as_secs_f32() * 1000.0,
+                avg.as_secs_f32() * 1000.0,
+                max.as_secs_f32() * 1000.0);
+        }
+
+    }
+
+    Ok(())
+
+}
+
+/// Send the given number of pings to the login app, one after the other.
+fn ping(addr: SocketAddr, count: u32, interval: Duration, timeout: Duration) -> io::Result<PingStats> {
+
+    let socket = PacketSocket::bind(SocketAddr::V4(SocketAddrV4::new([0, 0, 0, 0].into(), 0)))?;
+    let mut protocol = Protocol::new();
+    let mut tracker = RequestTracker::<Ping>::new();
+    let mut bundle = Bundle::new();
+    let mut stats = PingStats::default();
+
+    for num in 0..count {
+
+        if num != 0 {
+            std::thread::sleep(interval);
+        }
+
+        // Request ids are given by the tracker, the ping number wraps.
+        bundle.clear();
+        let request_id = bundle.element_writer().add_request(&mut tracker, Ping { num: num as u8 }).request_id();
+        tracker.register::<Ping>(request_id);
+        protocol.off_channel(addr).prepare(&mut bundle, false);
+
+        let time = Instant::now();
+        socket.send_bundle_without_encryption(&bundle, addr)?;
+        stats.sent += 1;
+
+        match wait_reply(&socket, &mut protocol, &mut tracker, addr, request_id, time, timeout)? {
+            Some(latency) => {
+                println!("reply from {addr}: num={} time={:.1} ms", num as u8, latency.as_secs_f32() * 1000.0);
+                stats.latencies.push(latency);
+            }
+            None => {
+                tracker.cancel(request_id);
+                println!("no reply from {addr}: num={}", num as u8);
+            }
+        }
+
+    }
+
+    Ok(stats)
+
+}
+
+/// Wait for the reply to the given request, sent at the given time, until the 
+/// timeout, returning its latency. Replies to previous requests are ignored.
+fn wait_reply(socket: &PacketSocket, protocol: &mut Protocol, tracker: &mut RequestTracker<Ping>, addr: SocketAddr, request_id: u32, time: Instant, timeout: Duration) -> io::Result<Option<Duration>> {
+
+    let deadline = time + timeout;
+
+    loop {
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+
+        socket.set_recv_timeout(Some(deadline - now))?;
+        let (packet, from) = match socket.recv_without_encryption() {
+            Ok(ret) => ret,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
+            Err(e) => return Err(e),
+        };
+
+        if from != addr {
+            continue;
+        }
+
+        let latency = time.elapsed();
+        let Some(mut channel) = protocol.accept(packet, from) else {
+            continue;
+        };
+
+        while let Some(bundle) = channel.next_bundle() {
+            let mut reader = bundle.element_reader();
+            while let Some(NextElementReader::Reply(reply)) = reader.next() {
+                let reply_request_id = reply.request_id();
+                let reply = tracker.read_reply(from, reply)?;
+                if reply_request_id == request_id && matches!(reply, TrackedReply::Known(_)) {
+                    return Ok(Some(latency));
+                }
+            }
+        }
+
+    }
+
+}
+
+/// Resolve a target from its socket address, or from the name of a periphery.
+fn resolve_target(target: &str, peripheries: &[Target]) -> CliResult<Target> {
+
+    if let Some(periphery) = peripheries.iter().find(|periphery| periphery.name.eq_ignore_ascii_case(target)) {
+        return Ok(Target { name: periphery.name.clone(), addr: periphery.addr });
+    }
+
+    let addr = resolve_addr(target)
+        .ok_or_else(|| format!("Invalid target, not an address nor a known periphery: {target}"))?;
+
+    Ok(Target { name: target.to_string(), addr })
+
+}
+
+/// Resolve the first IPv4 address of the given host and port.
+fn resolve_addr(addr: &str) -> Option<SocketAddr> {
+    addr.to_socket_addrs().ok()?.find(SocketAddr::is_ipv4)
+}
+
+/// Read all peripheries declared as login hosts in the given scripts config, this is
+/// the game's 'scripts_config.xml' packed XML file. Peripheries are named after their
+/// short name, if any.
+fn read_peripheries(path: &Path) -> CliResult<Vec<Target>> {
+
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open scripts config at {}, reason: {e}", path.display()))?;
+
+    let root_elt = pxml::from_reader(file)
+        .map_err(|e| format!("Failed to read scripts config at {}, reason: {e}", path.display()))?;
+
+    let Some(login_elt) = root_elt.get_child("login").and_then(Value::as_element) else {
+        return Ok(Vec::new());
+    };
+
+    let mut peripheries = Vec::new();
+    for host_elt in login_elt.iter_children("host").filter_map(Value::as_element) {
+
+        let Some(url) = host_elt.get_child("url").and_then(Value::as_string) else {
+            continue;
+        };
+
+        let name = host_elt.get_child("short_name")
+            .or_else(|| host_elt.get_child("name"))
+            .and_then(Value::as_string)
+            .unwrap_or(url);
+
+        let addr = resolve_addr(url.trim())
+            .ok_or_else(|| format!("Failed to resolve periphery {name} at {url}"))?;
+
+        peripheries.push(Target { name: name.to_string(), addr });
+
+    }
+
+    Ok(peripheries)
+
+}
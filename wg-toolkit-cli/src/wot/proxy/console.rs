@@ -0,0 +1,513 @@
+//! Interactive console reading commands from the standard input, used to inject raw
+//! elements or entity method calls toward either side of the proxied peers, and to
+//! request snapshots of their decoded entities.
+
+use std::io::{self, BufRead, Read, Write};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use glam::{Vec2, Vec3, Vec4};
+use indexmap::IndexMap;
+use tracing::{info, warn};
+
+use wgtk::net::element::{Element, ElementLength};
+use wgtk::net::codec::{AutoString, Python, SimpleCodec};
+use wgtk::net::app::proxy::PacketDirection;
+use wgtk::net::bundle::Bundle;
+use wgtk::util::AsciiFmt;
+
+use crate::defs::runtime::{RuntimeMethods, RuntimeMethodCall, Value};
+use crate::defs::model::{Ty, TyKind};
+use crate::wot::parse_hex;
+
+use super::Shared;
+
+
+const HELP: &str = "\
+Commands:
+  help                                        Show this help.
+  peers                                       List the peers available for injection.
+  methods <entity> <in|out>                   List the methods of an entity type.
+  raw <addr> <in|out> <id> [hex]              Inject a raw element, the data must include
+                                              the length header, if any.
+  request <addr> <in|out> <id> [hex]          Inject a raw element as a request, its reply
+                                              is logged when received.
+  call <addr> <in|out> <entity> <method> ...  Inject an entity method call, methods are
+                                              named <Interface>::<method>.
+  snapshot <addr>                             Dump the decoded entities of a peer.
+Direction 'in' is toward the client, 'out' is toward the real base app.
+Arguments: 12, -3, 1.5, \"string\", (x, y, z), [a, b], {field: value}, None, True.";
+
+
+/// The console, running in its own thread.
+#[derive(Debug)]
+pub struct Console {
+    pub shared: Arc<Shared>,
+}
+
+impl Console {
+
+    pub fn run(self) {
+
+        info!("Injection console ready, type 'help' for commands");
+
+        for line in io::stdin().lock().lines() {
+
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("Failed to read console: {e}");
+                    break;
+                }
+            };
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = self.run_command(line) {
+                warn!("Console: {e}");
+            }
+
+        }
+
+    }
+
+    fn run_command(&self, line: &str) -> Result<(), String> {
+
+        let (cmd, rest) = split_word(line);
+
+        match cmd {
+            "help" => {
+                let _ = writeln!(io::stdout(), "{HELP}");
+            }
+            "peers" => {
+                let injectors = self.shared.injectors.lock().unwrap();
+                let _ = writeln!(io::stdout(), "{} peers", injectors.len());
+                for addr in injectors.keys() {
+                    let _ = writeln!(io::stdout(), "  {addr}");
+                }
+            }
+            "methods" => {
+
+                let (entity_name, rest) = split_word(rest);
+                let (direction, _) = split_word(rest);
+                let methods = self.methods(entity_name, parse_direction(direction)?)?;
+
+                for (exposed_id, method) in methods.iter().enumerate() {
+                    let args = method.args.iter().map(Ty::name).collect::<Vec<_>>();
+                    let _ = writeln!(io::stdout(), "  #{exposed_id} {}({})", method.name, args.join(", "));
+                }
+
+            }
+            "raw" => {
+
+                let (addr, rest) = split_word(rest);
+                let (direction, rest) = split_word(rest);
+                let (id, rest) = split_word(rest);
+
+                let id = u8::from_str_radix(id.trim_start_matches("0x"), 16)
+                    .map_err(|_| format!("Invalid element id: {id}"))?;
+
+                let data = rest.split_whitespace().collect::<String>();
+                let data = parse_hex(&data).ok_or_else(|| format!("Invalid hex data: {data}"))?;
+
+                let mut bundle = Bundle::new();
+                bundle.element_writer().write_simple(RawElement { 
+                    id, 
+                    length: ElementLength::Fixed(data.len() as u32), 
+                    data,
+                });
+                self.inject(addr, direction, bundle)?;
+
+            }
+            "request" => {
+
+                let (addr, rest) = split_word(rest);
+                let (direction, rest) = split_word(rest);
+                let (id, rest) = split_word(rest);
+
+                let peer_addr = addr.parse::<SocketAddr>().map_err(|_| format!("Invalid peer address: {addr}"))?;
+                let id = u8::from_str_radix(id.trim_start_matches("0x"), 16)
+                    .map_err(|_| format!("Invalid element id: {id}"))?;
+
+                let data = rest.split_whitespace().collect::<String>();
+                let data = parse_hex(&data).ok_or_else(|| format!("Invalid hex data: {data}"))?;
+
+                // The request id is allocated by the tracker of the peer, so that the
+                // decode worker can recognize its reply.
+                let mut bundle = Bundle::new();
+                let request_id = self.shared.with_request_tracker(peer_addr, |tracker| {
+                    let handle = bundle.element_writer().add_request(tracker, RawElement { 
+                        id, 
+                        length: ElementLength::Fixed(data.len() as u32), 
+                        data,
+                    });
+                    tracker.register_with(handle.request_id(), move |reply| {
+                        reply.read_simple::<InjectedReply>().map(|reply| Box::new(reply) as _)
+                    });
+                    handle.request_id()
+                });
+
+                if let Err(e) = self.inject(addr, direction, bundle) {
+                    self.shared.with_request_tracker(peer_addr, |tracker| tracker.cancel(request_id));
+                    return Err(e);
+                }
+
+                info!(%peer_addr, "Injected request #{request_id}");
+
+            }
+            "call" => {
+
+                let (addr, rest) = split_word(rest);
+                let (direction, rest) = split_word(rest);
+                let (entity_name, rest) = split_word(rest);
+                let (method_name, rest) = split_word(rest);
+
+                let methods = self.methods(entity_name, parse_direction(direction)?)?;
+                let (exposed_id, method) = methods.find(method_name)
+                    .ok_or_else(|| format!("Unknown method: {method_name}"))?;
+
+                let mut parser = ValueParser { input: rest };
+                let args = method.args.iter()
+                    .map(|ty| parser.parse(ty))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                parser.expect_end()?;
+
+                // The call is encoded before being written to the bundle, because the 
+                // bundle writer doesn't support encoding errors.
+                let call = RuntimeMethodCall { exposed_id, args };
+                let mut data = Vec::new();
+                let length = call.write_length(methods).map_err(|e| format!("Invalid call: {e}"))?;
+                let id = call.write(&mut data, methods).map_err(|e| format!("Invalid call: {e}"))?;
+
+                let mut bundle = Bundle::new();
+                bundle.element_writer().write_simple(RawElement { id, length, data });
+                self.inject(addr, direction, bundle)?;
+
+            }
+            "snapshot" => {
+
+                let (addr, _) = split_word(rest);
+                let addr = addr.parse::<SocketAddr>().map_err(|_| format!("Invalid peer address: {addr}"))?;
+
+                if !self.shared.injectors.lock().unwrap().contains_key(&addr) {
+                    return Err(format!("Unknown peer: {addr}"));
+                }
+
+                // The snapshot is dumped by the decode worker of the peer.
+                self.shared.snapshot_requests.lock().unwrap().insert(addr);
+                info!(%addr, "Requested entity store snapshot");
+
+            }
+            _ => return Err(format!("Unknown command: {cmd}, type 'help' for commands")),
+        }
+
+        Ok(())
+
+    }
+
+    /// Get the methods of the given entity type, called in the given direction.
+    fn methods(&self, entity_name: &str, direction: PacketDirection) -> Result<&RuntimeMethods, String> {
+
+        let defs = self.shared.defs.as_ref()
+            .ok_or_else(|| "Entity definitions are required, see --entity-defs".to_string())?;
+
+        let entity = defs.entity_by_name(entity_name)
+            .ok_or_else(|| format!("Unknown entity type: {entity_name}"))?;
+
+        Ok(match direction {
+            PacketDirection::In => &entity.client_methods,
+            PacketDirection::Out => &entity.base_methods,
+        })
+
+    }
+
+    /// Inject the given bundle toward the given peer.
+    fn inject(&self, addr: &str, direction: &str, bundle: Bundle) -> Result<(), String> {
+
+        let addr = addr.parse::<SocketAddr>().map_err(|_| format!("Invalid peer address: {addr}"))?;
+        let direction = parse_direction(direction)?;
+
+        // Clone the injector to not keep the lock while injecting.
+        let injector = self.shared.injectors.lock().unwrap()
+            .get(&addr)
+            .cloned()
+            .ok_or_else(|| format!("Unknown peer: {addr}"))?;
+
+        if !injector.inject(addr, direction, bundle) {
+            self.shared.injectors.lock().unwrap().remove(&addr);
+            return Err(format!("Proxy of peer {addr} is no longer running"));
+        }
+
+        info!(%addr, "Injected {direction:?}");
+        Ok(())
+
+    }
+
+}
+
+/// The raw reply to an injected request.
+struct InjectedReply(Vec<u8>);
+
+impl SimpleCodec for InjectedReply {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_all(&self.0)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        let mut data = Vec::new();
+        read.read_to_end(&mut data)?;
+        Ok(Self(data))
+    }
+
+}
+
+impl fmt::Debug for InjectedReply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InjectedReply").field(&AsciiFmt(&self.0)).finish()
+    }
+}
+
+/// A raw element with its data written as-is.
+#[derive(Debug)]
+struct RawElement {
+    id: u8,
+    length: ElementLength,
+    data: Vec<u8>,
+}
+
+impl Element<()> for RawElement {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Ok(self.length)
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+        write.write_all(&self.data)?;
+        Ok(self.id)
+    }
+
+    fn read_length(_config: &(), _id: u8) -> io::Result<ElementLength> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw element read not supported"))
+    }
+
+    fn read(_read: &mut dyn Read, _config: &(), _len: usize, _id: u8) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw element read not supported"))
+    }
+
+}
+
+/// Split the first whitespace-separated word of the given string, returning the word
+/// and the remaining string.
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(index) => (&s[..index], &s[index..]),
+        None => (s, ""),
+    }
+}
+
+fn parse_direction(s: &str) -> Result<PacketDirection, String> {
+    match s {
+        "in" => Ok(PacketDirection::In),
+        "out" => Ok(PacketDirection::Out),
+        _ => Err(format!("Invalid direction: {s}, expected 'in' or 'out'")),
+    }
+}
+
+/// A parser of typed values from their textual representation.
+struct ValueParser<'a> {
+    input: &'a str,
+}
+
+impl<'a> ValueParser<'a> {
+
+    /// Parse a value of the given type.
+    fn parse(&mut self, ty: &Ty) -> Result<Value, String> {
+        Ok(match ty.kind() {
+            TyKind::Int8 | TyKind::Int16 | TyKind::Int32 | TyKind::Int64 => Value::Int(self.parse_token()?),
+            TyKind::UInt8 | TyKind::UInt16 | TyKind::UInt32 | TyKind::UInt64 => Value::UInt(self.parse_token()?),
+            TyKind::Float32 | TyKind::Float64 => Value::Float(self.parse_token()?),
+            TyKind::Vector2 => {
+                let [x, y] = self.parse_floats()?;
+                Value::Vec2(Vec2::new(x, y))
+            }
+            TyKind::Vector3 => {
+                let [x, y, z] = self.parse_floats()?;
+                Value::Vec3(Vec3::new(x, y, z))
+            }
+            TyKind::Vector4 => {
+                let [x, y, z, w] = self.parse_floats()?;
+                Value::Vec4(Vec4::new(x, y, z, w))
+            }
+            TyKind::String => Value::String(AutoString::String(self.parse_string()?)),
+            TyKind::Python => Value::Python(Python::encode(&self.parse_python()?)
+                .map_err(|e| format!("Invalid python value: {e}"))?),
+            TyKind::Mailbox => return Err("Mailbox arguments are not supported".to_string()),
+            TyKind::User(_) => return Err("User type arguments are not supported".to_string()),
+            TyKind::Alias(ty) => return self.parse(ty),
+            TyKind::Dict(ty_dict) if ty_dict.allow_none && self.peek() != Some('{') => {
+                match self.parse_word()? {
+                    "None" => Value::None,
+                    word => return Err(format!("Invalid dict: {word}, expected '{{' or 'None'")),
+                }
+            }
+            TyKind::Dict(ty_dict) => {
+                self.expect('{')?;
+                let mut dict = IndexMap::with_capacity(ty_dict.properties.len());
+                while !self.eat('}') {
+                    let name = self.parse_word()?;
+                    self.expect(':')?;
+                    let prop = ty_dict.properties.iter()
+                        .find(|prop| prop.name == name)
+                        .ok_or_else(|| format!("Unknown dict property: {name}"))?;
+                    dict.insert(prop.name.clone(), self.parse(&prop.ty)?);
+                    self.eat(',');
+                }
+                Value::Dict(dict)
+            }
+            TyKind::Array(ty_seq) |
+            TyKind::Tuple(ty_seq) => {
+                self.expect('[')?;
+                let mut seq = Vec::new();
+                while !self.eat(']') {
+                    seq.push(self.parse(&ty_seq.ty)?);
+                    self.eat(',');
+                }
+                Value::Seq(seq)
+            }
+        })
+    }
+
+    /// Parse a python literal, only none, booleans, numbers, strings and lists.
+    fn parse_python(&mut self) -> Result<serde_pickle::Value, String> {
+
+        if self.peek() == Some('"') {
+            return self.parse_string().map(serde_pickle::Value::String);
+        }
+
+        if self.eat('[') {
+            let mut list = Vec::new();
+            while !self.eat(']') {
+                list.push(self.parse_python()?);
+                self.eat(',');
+            }
+            return Ok(serde_pickle::Value::List(list));
+        }
+
+        let word = self.parse_word()?;
+        Ok(match word {
+            "None" => serde_pickle::Value::None,
+            "True" => serde_pickle::Value::Bool(true),
+            "False" => serde_pickle::Value::Bool(false),
+            _ => {
+                if let Ok(n) = word.parse::<i64>() {
+                    serde_pickle::Value::I64(n)
+                } else if let Ok(n) = word.parse::<f64>() {
+                    serde_pickle::Value::F64(n)
+                } else {
+                    return Err(format!("Invalid python literal: {word}"));
+                }
+            }
+        })
+
+    }
+
+    /// Parse a parenthesized list of floats.
+    fn parse_floats<const N: usize>(&mut self) -> Result<[f32; N], String> {
+        self.expect('(')?;
+        let mut values = [0.0; N];
+        for (i, value) in values.iter_mut().enumerate() {
+            if i != 0 {
+                self.expect(',')?;
+            }
+            *value = self.parse_token()?;
+        }
+        self.expect(')')?;
+        Ok(values)
+    }
+
+    /// Parse a quoted string, with backslash escapes, or a single word.
+    fn parse_string(&mut self) -> Result<String, String> {
+
+        if !self.eat('"') {
+            return self.parse_word().map(str::to_string);
+        }
+
+        let mut string = String::new();
+        let mut chars = self.input.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.input = &self.input[index + 1..];
+                    return Ok(string);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, c)) => string.push(c),
+                    None => break,
+                }
+                c => string.push(c),
+            }
+        }
+
+        Err("Unterminated string".to_string())
+
+    }
+
+    /// Parse a word and then parse it with [`str::parse`].
+    fn parse_token<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let word = self.parse_word()?;
+        word.parse().map_err(|_| format!("Invalid {}: {word}", std::any::type_name::<T>()))
+    }
+
+    /// Parse a word, ending at a whitespace or a delimiter.
+    fn parse_word(&mut self) -> Result<&'a str, String> {
+        self.input = self.input.trim_start();
+        let end = self.input.find(|c: char| c.is_whitespace() || ",:()[]{}\"".contains(c))
+            .unwrap_or(self.input.len());
+        if end == 0 {
+            return Err(format!("Expected a value at: {:?}", self.input));
+        }
+        let (word, rest) = self.input.split_at(end);
+        self.input = rest;
+        Ok(word)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.input = self.input.trim_start();
+        self.input.chars().next()
+    }
+
+    /// Consume the given character if it's the next one, returning true if so.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.input = &self.input[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{c}' at: {:?}", self.input))
+        }
+    }
+
+    /// Ensure that all the input has been parsed.
+    fn expect_end(&mut self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(format!("Unexpected arguments: {:?}", self.input)),
+        }
+    }
+
+}
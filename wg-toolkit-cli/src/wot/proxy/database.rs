@@ -0,0 +1,121 @@
+//! Structured dump of the decoded elements into a SQLite database, shared by all
+//! decode workers.
+
+use std::net::SocketAddr;
+use std::time::Instant;
+use std::path::Path;
+use std::fs;
+
+use rusqlite::{params, Connection};
+
+use wgtk::net::app::proxy::PacketDirection;
+
+
+/// Schema of the database, each table has the time of the row in microseconds since
+/// the start of the dump.
+const SCHEMA: &str = "
+CREATE TABLE entities (
+    time INTEGER NOT NULL,
+    addr TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    entity_type TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE methods (
+    time INTEGER NOT NULL,
+    addr TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    entity_type TEXT NOT NULL,
+    call TEXT NOT NULL
+);
+CREATE TABLE properties (
+    time INTEGER NOT NULL,
+    addr TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    entity_type TEXT NOT NULL,
+    path TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE resources (
+    time INTEGER NOT NULL,
+    resource_id INTEGER NOT NULL,
+    crc32 INTEGER NOT NULL,
+    len INTEGER NOT NULL,
+    data TEXT
+);
+CREATE INDEX entities_entity ON entities(entity_id);
+CREATE INDEX methods_entity ON methods(entity_id);
+CREATE INDEX properties_entity ON properties(entity_id);
+";
+
+
+/// The dump database, rows are inserted as soon as elements are decoded.
+#[derive(Debug)]
+pub struct Database {
+    conn: Connection,
+    /// The time of the start of the dump, row times are relative to it.
+    start: Instant,
+}
+
+impl Database {
+
+    /// Create the database at the given path, an existing file is replaced, the dump
+    /// starts now.
+    pub fn create(path: &Path) -> rusqlite::Result<Self> {
+
+        // Ignore the error if the file doesn't exist, opening will fail otherwise.
+        let _ = fs::remove_file(path);
+
+        let conn = Connection::open(path)?;
+        // Rows are inserted one by one, so we don't wait for each one to be synced.
+        conn.pragma_update(None, "synchronous", "OFF")?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn,
+            start: Instant::now(),
+        })
+
+    }
+
+    /// Return the time of a row in microseconds since the start of the dump.
+    fn row_time(&self, time: Instant) -> i64 {
+        time.saturating_duration_since(self.start).as_micros().try_into().unwrap_or(i64::MAX)
+    }
+
+    /// Insert a created entity with its debug representation.
+    pub fn insert_entity(&self, time: Instant, addr: SocketAddr, entity_id: u32, entity_type: &str, data: &str) -> rusqlite::Result<()> {
+        self.conn.prepare_cached("INSERT INTO entities VALUES (?1, ?2, ?3, ?4, ?5)")?
+            .execute(params![self.row_time(time), addr.to_string(), entity_id, entity_type, data])?;
+        Ok(())
+    }
+
+    /// Insert a method call on an entity, in the given direction, the call is the 
+    /// debug representation of the method with its arguments.
+    pub fn insert_method(&self, time: Instant, addr: SocketAddr, direction: PacketDirection, entity_id: u32, entity_type: &str, call: &str) -> rusqlite::Result<()> {
+        let direction = match direction {
+            PacketDirection::Out => "out",
+            PacketDirection::In => "in",
+        };
+        self.conn.prepare_cached("INSERT INTO methods VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?
+            .execute(params![self.row_time(time), addr.to_string(), direction, entity_id, entity_type, call])?;
+        Ok(())
+    }
+
+    /// Insert a property change of an entity, the path is the name of the property, 
+    /// followed by the path of the changed value for nested changes.
+    pub fn insert_property(&self, time: Instant, addr: SocketAddr, entity_id: u32, entity_type: &str, path: &str, value: &str) -> rusqlite::Result<()> {
+        self.conn.prepare_cached("INSERT INTO properties VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?
+            .execute(params![self.row_time(time), addr.to_string(), entity_id, entity_type, path, value])?;
+        Ok(())
+    }
+
+    /// Insert a completed resource, with its decoded data if it could be decoded.
+    pub fn insert_resource(&self, time: Instant, resource_id: u16, crc32: u32, len: usize, data: Option<&str>) -> rusqlite::Result<()> {
+        self.conn.prepare_cached("INSERT INTO resources VALUES (?1, ?2, ?3, ?4, ?5)")?
+            .execute(params![self.row_time(time), resource_id, crc32, len as i64, data])?;
+        Ok(())
+    }
+
+}
@@ -0,0 +1,215 @@
+//! Capture of decoded elements as binary fixtures, along with a generated test module
+//! that round-trips each of them through the generated decoders.
+
+use std::io::{self, Read, Write};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::fs::{self, File};
+
+use wgtk::net::element::{Element, ElementLength};
+use wgtk::net::bundle::ElementReader;
+
+
+/// Magic at the start of each fixture file.
+pub const FIXTURE_MAGIC: &[u8; 6] = b"WGTKFX";
+
+/// Version of the fixture file format, incremented on each incompatible change, the
+/// generated test module rejects fixtures of another version.
+pub const FIXTURE_VERSION: u16 = 1;
+
+/// Name of the generated test module in the fixtures directory.
+const MODULE_FILE: &str = "mod.rs";
+
+
+/// The fixtures directory, each distinct kind of element is only saved once, the
+/// first time it is captured.
+#[derive(Debug)]
+pub struct Fixtures {
+    dir: PathBuf,
+    /// The name of all saved fixtures, associated to the path of the element type used
+    /// to decode it from the test module.
+    saved: Mutex<BTreeMap<String, String>>,
+}
+
+impl Fixtures {
+
+    /// Create the fixtures directory if needed, fixtures already present in the
+    /// directory are kept but not included in the regenerated test module.
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            saved: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Capture the raw data of the given element, without advancing the reader, if no
+    /// fixture with the same name has been captured yet. The element type path is the
+    /// type used to decode it in the generated test module, relative to the generated
+    /// module's imports.
+    ///
+    /// Elements of undefined length are never captured because their data can't be
+    /// delimited.
+    pub fn capture<E: Element<()>>(&self, name: String, element_ty: String, elt: &mut ElementReader) -> Result<(), FixtureError> {
+
+        let mut saved = self.saved.lock().unwrap();
+        if saved.contains_key(&name) {
+            return Ok(());
+        }
+
+        let id = elt.id();
+        if matches!(E::read_length(&(), id).map_err(FixtureError::Decode)?, ElementLength::Undefined) {
+            return Ok(());
+        }
+
+        let raw = elt.read_simple_stable::<RawElement<E>>().map_err(FixtureError::Decode)?;
+
+        let mut writer = File::create(self.dir.join(format!("{name}.bin")))?;
+        writer.write_all(FIXTURE_MAGIC)?;
+        writer.write_all(&FIXTURE_VERSION.to_le_bytes())?;
+        writer.write_all(&[id])?;
+        writer.write_all(&raw.element.data)?;
+
+        saved.insert(name, element_ty);
+        self.generate_module(&saved)?;
+        Ok(())
+
+    }
+
+    /// Regenerate the whole test module with all fixtures saved so far.
+    fn generate_module(&self, saved: &BTreeMap<String, String>) -> io::Result<()> {
+
+        let mut writer = File::create(self.dir.join(MODULE_FILE))?;
+
+        writeln!(writer, "//! Round-trip tests of the fixtures captured by the proxy.")?;
+        writeln!(writer, "//! ")?;
+        writeln!(writer, "//! This file is generated by the proxy of the CLI, it's intended to be copied")?;
+        writeln!(writer, "//! with its fixtures as a 'fixtures' module next to the 'gen' module.")?;
+        writeln!(writer)?;
+        writeln!(writer, "#![allow(non_snake_case)]")?;
+        writeln!(writer)?;
+        writeln!(writer, "use std::io::Cursor;")?;
+        writeln!(writer)?;
+        writeln!(writer, "use wgtk::net::element::Element;")?;
+        writeln!(writer, "use wgtk::net::app::common::entity::Entity;")?;
+        writeln!(writer, "use wgtk::net::app::{{base, client}};")?;
+        writeln!(writer)?;
+        writeln!(writer, "use super::gen::entity;")?;
+        writeln!(writer)?;
+        writeln!(writer)?;
+        writeln!(writer, "fn round_trip<E: Element<()>>(fixture: &[u8]) {{")?;
+        writeln!(writer, "    assert_eq!(&fixture[..{}], {:?}, \"invalid fixture magic\");", FIXTURE_MAGIC.len(), ByteStr(FIXTURE_MAGIC))?;
+        writeln!(writer, "    let version = u16::from_le_bytes([fixture[{}], fixture[{}]]);", FIXTURE_MAGIC.len(), FIXTURE_MAGIC.len() + 1)?;
+        writeln!(writer, "    assert_eq!(version, {FIXTURE_VERSION}, \"unsupported fixture version\");")?;
+        writeln!(writer, "    let id = fixture[{}];", FIXTURE_MAGIC.len() + 2)?;
+        writeln!(writer, "    let data = &fixture[{}..];", FIXTURE_MAGIC.len() + 3)?;
+        writeln!(writer, "    let element = E::read(&mut Cursor::new(data), &(), data.len(), id).expect(\"failed to decode\");")?;
+        writeln!(writer, "    let mut written = Vec::new();")?;
+        writeln!(writer, "    let written_id = element.write(&mut written, &()).expect(\"failed to encode\");")?;
+        writeln!(writer, "    assert_eq!(written_id, id);")?;
+        writeln!(writer, "    assert_eq!(written, data);")?;
+        writeln!(writer, "}}")?;
+
+        for (name, element_ty) in saved {
+            writeln!(writer)?;
+            writeln!(writer, "#[test]")?;
+            writeln!(writer, "fn {name}() {{")?;
+            writeln!(writer, "    round_trip::<{element_ty}>(include_bytes!(\"{name}.bin\"));")?;
+            writeln!(writer, "}}")?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/// Error while capturing a fixture, the element's data may be invalid, in which case
+/// it can't be decoded either, or the fixture may not be saved because of an IO error
+/// on the fixtures directory, which doesn't prevent decoding the element.
+#[derive(Debug, thiserror::Error)]
+pub enum FixtureError {
+    /// The element's data can't be read.
+    #[error("decode error: {0}")]
+    Decode(io::Error),
+    /// The fixture can't be written to the fixtures directory.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// An element that only captures the raw data of an element of type `E`, its length
+/// being read just like `E`.
+struct RawElement<E> {
+    data: Vec<u8>,
+    _phantom: PhantomData<E>,
+}
+
+impl<E: Element<()>> Element<()> for RawElement<E> {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw element write not supported"))
+    }
+
+    fn write(&self, _write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw element write not supported"))
+    }
+
+    fn read_length(config: &(), id: u8) -> io::Result<ElementLength> {
+        E::read_length(config, id)
+    }
+
+    fn read(read: &mut dyn Read, _config: &(), _len: usize, _id: u8) -> io::Result<Self> {
+        let mut data = Vec::new();
+        read.read_to_end(&mut data)?;
+        Ok(Self { data, _phantom: PhantomData })
+    }
+
+}
+
+/// Format a byte string as a Rust byte string literal.
+struct ByteStr<'a>(&'a [u8]);
+
+impl std::fmt::Debug for ByteStr<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("b\"")?;
+        for &b in self.0 {
+            write!(f, "{}", std::ascii::escape_default(b))?;
+        }
+        f.write_str("\"")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use wgtk::net::app::client::element::BandwidthNotification;
+    use wgtk::net::bundle::{Bundle, NextElementReader};
+
+    use super::*;
+
+    #[test]
+    fn capture_io_error() {
+
+        let dir = std::env::temp_dir().join(format!("wgtk-fixtures-{}", std::process::id()));
+        let fixtures = Fixtures::new(&dir).unwrap();
+
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(BandwidthNotification { bps: 1234 });
+
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(mut elt)) = reader.next() else { panic!("expected an element") };
+
+        // The fixtures directory is no longer writable, this is not a decode error.
+        fs::remove_dir_all(&dir).unwrap();
+        let res = fixtures.capture::<BandwidthNotification>("bandwidth".to_string(), "BandwidthNotification".to_string(), &mut elt);
+        assert!(matches!(res, Err(FixtureError::Io(_))), "{res:?}");
+
+        // The reader has not been advanced and the element can still be decoded.
+        assert_eq!(elt.read_simple::<BandwidthNotification>().unwrap().element.bps, 1234);
+
+    }
+
+}
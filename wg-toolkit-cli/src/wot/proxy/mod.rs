@@ -1,107 +1,601 @@
 //! Proxy login and base app used for debugging exchanged messages.
 
+mod profile;
+mod ring;
+mod record;
+mod console;
+mod fixtures;
+mod tui;
+#[cfg(feature = "sqlite")]
+mod database;
+
 use std::net::{SocketAddr, SocketAddrV4};
 use std::{fmt, fs, io, thread};
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
-use std::io::Write;
+use std::time::{Duration, Instant};
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::io::{BufReader, Write};
 use std::fs::File;
 
-use tracing::{error, info, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
 
 use flate2::read::ZlibDecoder;
+use blowfish::cipher::KeyInit;
 use blowfish::Blowfish;
 use rsa::{RsaPrivateKey, RsaPublicKey};
 
-use wgtk::net::element::{DebugElementUndefined, DebugElementVariable16, SimpleElement};
-use wgtk::net::bundle::{Bundle, NextElementReader, ElementReader};
+use wgtk::net::element::{ElementLength, RawElement, SimpleElement};
+use wgtk::net::bundle::{Bundle, NextElementReader, ElementReader, RequestTracker, TrackedReply};
+use wgtk::net::sniff;
 
 use wgtk::net::app::{login, base, client, proxy};
-use wgtk::net::app::common::entity::Entity;
+use wgtk::net::app::common::entity::{Entity, EntityStore, EntityTypeVisitor, Method, NestedProperty, Properties, Property};
 use wgtk::net::app::proxy::PacketDirection;
-
-use wgtk::util::io::serde_pickle_de_options;
-
+use wgtk::net::app::proxy::bulk::{BulkDecoder, BulkPacket};
+use wgtk::net::app::client::resource::{ResourceDownloader, ResourceEvent, ResourceCompleteEvent};
+use wgtk::net::packet::PacketPool;
+use wgtk::net::codec::Codec;
+use wgtk::net::variant::ProtocolVariant;
+use wgtk::net::capture::{CaptureApp, CaptureReader, CaptureError};
+use wgtk::net::import::{PcapImporter, ImportError};
+
+use wgtk::util::pcap::PcapReader;
+
+use wgtk::util::pickle;
+use wgtk::util::AsciiFmt;
+use wgtk::res::ResFilesystem;
+
+use crate::defs::runtime::{RuntimeCreateBasePlayer, RuntimeDefs, RuntimeEntity, RuntimeMethodCall, RuntimePropertyUpdate, UserTypes};
+use crate::defs;
 use crate::CliResult;
 use super::gen;
 
+use profile::{Profiler, ProfileKey};
+use ring::PacketRing;
+use record::Recorder;
+use console::Console;
+use fixtures::{Fixtures, FixtureError};
+use tui::{TuiEvent, CallKind};
+#[cfg(feature = "sqlite")]
+use database::Database;
+
+
+/// Maximum number of bundles waiting to be decoded by each decode worker, bundles are
+/// not decoded if the queue is full, so that forwarding is never delayed.
+const DECODE_QUEUE_CAP: usize = 4096;
+
+/// Interval at which decode workers poll their resources when idle, in order to expire
+/// partial resources even if no bundle is received.
+const DECODE_IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of datagrams of a capture decoded in parallel in a single batch.
+const PCAP_BATCH_LEN: usize = 4096;
+
+/// The first request id allocated for requests injected by the console, ids are taken
+/// in the upper half so that they don't collide with the ids allocated by the client.
+const INJECTED_REQUEST_FIRST_ID: u32 = 0x8000_0000;
+
+/// The tracker of requests injected toward a peer, replies are decoded to be logged.
+type ReplyTracker = RequestTracker<Box<dyn fmt::Debug + Send>>;
+
+/// Options of the proxy common to the live proxy and to the offline decoding of 
+/// captures, they control how bundles are decoded and where they are dumped.
+#[derive(Debug, Clone, Copy)]
+pub struct ProxyOptions<'a> {
+    /// Interval between profiler reports, if the profiler is enabled.
+    pub profile_interval: Option<Duration>,
+    /// Interval between memory usage metrics reports, if enabled, only for the live 
+    /// proxy.
+    pub metrics_interval: Option<Duration>,
+    /// Number of recent packets kept for each peer and dumped on decode errors.
+    pub packet_ring_len: usize,
+    /// Entity definitions to interpret at runtime instead of the generated code.
+    pub entity_defs: Option<&'a Path>,
+    /// Codecs of the user types of the runtime entity definitions.
+    pub user_types: &'a UserTypes,
+    /// Fixtures directory where decoded elements are captured.
+    pub fixtures_dir: Option<&'a Path>,
+    /// Directory where large partial resources are spilled.
+    pub resource_spill_dir: Option<&'a Path>,
+    /// Capture where decrypted packets are recorded, only for the live proxy.
+    pub record_path: Option<&'a Path>,
+    /// Database where decoded elements are dumped.
+    pub database_path: Option<&'a Path>,
+    /// Run the terminal interface, only for the live proxy.
+    pub tui: bool,
+}
 
-pub fn run(
-    login_app_addr: SocketAddrV4,
-    real_login_app_addr: SocketAddrV4,
-    base_app_addr: SocketAddrV4,
-    encryption_key: Option<Arc<RsaPrivateKey>>,
-    real_encryption_key: Option<Arc<RsaPublicKey>>,
-) -> CliResult<()> {
+impl ProxyOptions<'_> {
+
+    /// Return these options without those only supported by the live proxy.
+    fn offline(&self) -> Self {
+        Self {
+            metrics_interval: None,
+            record_path: None,
+            tui: false,
+            ..*self
+        }
+    }
+
+}
+
+/// Options specific to the live proxy, forwarding between real clients and the real
+/// login and base apps.
+#[derive(Debug)]
+pub struct LiveOptions {
+    pub login_app_addr: SocketAddrV4,
+    pub real_login_app_addr: SocketAddrV4,
+    pub base_app_addr: SocketAddrV4,
+    pub base_app_workers: usize,
+    pub decode_workers: usize,
+    pub login_protocol_version: Option<u32>,
+    pub login_digest: Option<[u8; 16]>,
+    pub protocol_variant: ProtocolVariant,
+    pub login_params_dump: Option<LoginParamsDump>,
+    pub encryption_key: Option<Arc<RsaPrivateKey>>,
+    pub real_encryption_key: Option<Arc<RsaPublicKey>>,
+}
+
+pub fn run(options: &ProxyOptions, live: LiveOptions) -> CliResult<()> {
+
+    if live.decode_workers == 0 {
+        return Err("At least one decode worker is required".to_string());
+    }
 
-    let mut login_app = login::proxy::App::new(login_app_addr.into(), real_login_app_addr.into(), real_encryption_key)
+    let mut login_app = login::proxy::App::new(live.login_app_addr.into(), live.real_login_app_addr.into(), live.real_encryption_key)
         .map_err(|e| format!("Failed to bind login app: {e}"))?;
     
-    if let Some(encryption_key) = encryption_key {
+    if let Some(encryption_key) = live.encryption_key {
         login_app.set_encryption(encryption_key);
     }
 
-    login_app.set_forced_base_app_addr(base_app_addr);
+    login_app.set_forced_base_app_addr(live.base_app_addr);
+    login_app.set_protocol_version(live.login_protocol_version);
+    login_app.set_expected_digest(live.login_digest);
+    login_app.set_protocol_variant(live.protocol_variant.clone());
+
+    let mut base_apps = if live.base_app_workers > 1 {
+        proxy::App::new_workers(live.base_app_addr.into(), live.base_app_workers)
+            .map_err(|e| format!("Failed to bind base app workers: {e}"))?
+    } else {
+        vec![proxy::App::new(live.base_app_addr.into())
+            .map_err(|e| format!("Failed to bind base app: {e}"))?]
+    };
 
-    let base_app = proxy::App::new(base_app_addr.into())
-        .map_err(|e| format!("Failed to bind base app: {e}"))?;
+    for base_app in &mut base_apps {
+        base_app.set_protocol_variant(live.protocol_variant.clone());
+    }
 
-    let dump_dir = PathBuf::from("proxy-dump");
-    let _ = fs::remove_dir_all(&dump_dir);
-    fs::create_dir_all(&dump_dir).map_err(|e| format!("Failed to create proxy dump directory: {e}"))?;
+    let (tui_sender, tui_receiver) = match options.tui {
+        true => {
+            let (sender, receiver) = crossbeam_channel::bounded(tui::EVENT_QUEUE_CAP);
+            (Some(sender), Some(receiver))
+        }
+        false => (None, None),
+    };
 
-    let shared = Arc::new(Shared {
-        dump_dir,
-        pending_clients: Mutex::new(HashMap::new()),
-    });
+    let shared = Arc::new(Shared::new(options, tui_sender)?);
 
     let login_thread = LoginThread {
         app: login_app,
         shared: Arc::clone(&shared),
+        login_params_dump: live.login_params_dump,
     };
 
-    let base_thread = BaseThread {
-        app: base_app,
-        shared,
-        next_tick: None,
-        entities: HashMap::new(),
-        selected_entity_id: None,
-        player_entity_id: None,
-        partial_resources: HashMap::new(),
+    let (decode_pool, decode_receivers) = DecodePool::new(live.decode_workers);
+
+    let console = Console {
+        shared: Arc::clone(&shared),
     };
-    
+
     thread::scope(move |scope| {
         scope.spawn(move || login_thread.run());
-        scope.spawn(move || base_thread.run());
+        // The terminal interface replaces the console, which reads the terminal.
+        if tui_receiver.is_none() {
+            scope.spawn(move || console.run());
+        }
+        for (worker, receiver) in decode_receivers.into_iter().enumerate() {
+            let base_thread = BaseThread::new(Arc::clone(&shared));
+            scope.spawn(move || base_thread.run(receiver, worker));
+        }
+        for (worker, base_app) in base_apps.into_iter().enumerate() {
+            let socket_thread = SocketThread {
+                shared: Arc::clone(&shared),
+                decode_pool: decode_pool.clone(),
+            };
+            scope.spawn(move || socket_thread.run(base_app, worker));
+        }
+        if let Some(tui_receiver) = tui_receiver {
+            // Other threads never stop, so the whole process exits with the interface.
+            let code = match tui::run(Arc::clone(&shared), tui_receiver) {
+                Ok(()) => 0,
+                Err(e) => {
+                    eprintln!("Failed to run the terminal interface: {e}");
+                    1
+                }
+            };
+            std::process::exit(code);
+        }
     });
 
     Ok(())
 
 }
 
+/// Run the base app decoding logic offline on a pcap or pcapng capture, the given 
+/// blowfish key is the session key given to the login app by the client, and the base
+/// app address is the address of the real base app in the capture, its port is used
+/// to know the direction of each packet.
+pub fn run_pcap(
+    pcap_path: &Path,
+    blowfish_key: &[u8],
+    base_app_addr: SocketAddrV4,
+    options: &ProxyOptions,
+) -> CliResult<()> {
+
+    let blowfish = Blowfish::new_from_slice(blowfish_key)
+        .map_err(|_| format!("Invalid blowfish key length: {}", blowfish_key.len()))?;
+
+    let pcap_file = File::open(pcap_path)
+        .map_err(|e| format!("Failed to open capture at {}: {e}", pcap_path.display()))?;
+
+    let reader = PcapReader::new(BufReader::new(pcap_file))
+        .map_err(|e| format!("Failed to read capture at {}: {e}", pcap_path.display()))?;
+
+    let shared = Arc::new(Shared::new(&options.offline(), None)?);
+
+    let mut importer = PcapImporter::new(reader);
+    importer.add_server_port(base_app_addr.port(), Some(Arc::new(blowfish)));
+
+    BaseThread::new(shared).run_pcap(importer, SocketAddr::V4(base_app_addr))
+        .map_err(|e| format!("Failed to read capture at {}: {e}", pcap_path.display()))
+
+}
+
+/// Run the base app decoding logic offline on a capture of decrypted packets that has
+/// been recorded by the proxy, no decryption is needed and the direction of each packet
+/// is already known.
+pub fn run_capture(capture_path: &Path, options: &ProxyOptions) -> CliResult<()> {
+
+    let capture_file = File::open(capture_path)
+        .map_err(|e| format!("Failed to open capture at {}: {e}", capture_path.display()))?;
+
+    let reader = CaptureReader::new(BufReader::new(capture_file))
+        .map_err(|e| format!("Failed to read capture at {}: {e}", capture_path.display()))?;
+
+    let shared = Arc::new(Shared::new(&options.offline(), None)?);
+
+    BaseThread::new(shared).run_capture(reader)
+        .map_err(|e| format!("Failed to read capture at {}: {e}", capture_path.display()))
+
+}
+
+/// Create the dump directory, after removing any previous one.
+fn create_dump_dir() -> CliResult<PathBuf> {
+    let dump_dir = PathBuf::from("proxy-dump");
+    let _ = fs::remove_dir_all(&dump_dir);
+    fs::create_dir_all(&dump_dir).map_err(|e| format!("Failed to create proxy dump directory: {e}"))?;
+    Ok(dump_dir)
+}
+
+/// Create the recorder of decrypted packets into the capture at the given path, if any.
+fn create_recorder(path: Option<&Path>) -> CliResult<Option<Mutex<Recorder>>> {
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let recorder = Recorder::create(path)
+        .map_err(|e| format!("Failed to create capture at {}: {e}", path.display()))?;
+
+    info!("Recording packets into: {}", path.display());
+    Ok(Some(Mutex::new(recorder)))
+
+}
+
+/// Create the database where decoded elements are dumped, if any.
+#[cfg(feature = "sqlite")]
+fn create_database(path: Option<&Path>) -> CliResult<Option<Mutex<Database>>> {
+
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let database = Database::create(path)
+        .map_err(|e| format!("Failed to create dump database at {}: {e}", path.display()))?;
+
+    info!("Dumping decoded elements into: {}", path.display());
+    Ok(Some(Mutex::new(database)))
+
+}
+
+/// Load the entity definitions from the given resources directory, if any, in order to
+/// decode entities at runtime instead of using the generated code.
+fn load_entity_defs(dir: Option<&Path>, user_types: &UserTypes) -> CliResult<Option<RuntimeDefs>> {
+
+    let Some(dir) = dir else {
+        return Ok(None);
+    };
+
+    let fs = ResFilesystem::new(dir)
+        .map_err(|e| format!("Failed to open resources at {}: {e}", dir.display()))?;
+
+    let model = defs::load(fs.into())
+        .map_err(|e| format!("Failed to load entity definitions: {e}"))?;
+
+    info!("Using runtime entity definitions: {} entity types", model.entities.len());
+    Ok(Some(RuntimeDefs::new(&model, user_types.clone())))
+
+}
+
+/// Open the fixtures directory, if enabled.
+fn load_fixtures(dir: Option<&Path>) -> CliResult<Option<Fixtures>> {
+
+    let Some(dir) = dir else {
+        return Ok(None);
+    };
+
+    let fixtures = Fixtures::new(dir)
+        .map_err(|e| format!("Failed to create fixtures directory at {}: {e}", dir.display()))?;
+
+    info!("Capturing fixtures into: {}", dir.display());
+    Ok(Some(fixtures))
+
+}
+
+
+/// How the decrypted login parameters of each client should be dumped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginParamsDump {
+    /// The password, blowfish key and digest are replaced by their length.
+    Redacted,
+    /// All parameters are dumped as-is, including secrets.
+    Full,
+}
 
 #[derive(Debug)]
 struct LoginThread {
     app: login::proxy::App,
     shared: Arc<Shared>,
+    /// If enabled, the decrypted login parameters are dumped in the dump directory.
+    login_params_dump: Option<LoginParamsDump>,
+}
+
+/// Write the given login request parameters, secrets are redacted unless the full dump
+/// is requested. The decrypted parameters blob is also written in hex, with secrets
+/// zeroed when redacted, so that its exact layout can be studied.
+fn write_login_params(mut write: impl Write, event: &login::proxy::LoginRequestEvent, dump: LoginParamsDump) -> io::Result<()> {
+
+    let request = &event.request;
+
+    let password = match dump {
+        LoginParamsDump::Redacted => format!("<redacted, {} bytes>", request.password.len()),
+        LoginParamsDump::Full => format!("{:?}", request.password),
+    };
+
+    let blowfish_key = match dump {
+        LoginParamsDump::Redacted => format!("<redacted, {} bytes>", request.blowfish_key.len()),
+        LoginParamsDump::Full => format!("{:02X?}", request.blowfish_key),
+    };
+
+    let digest = match (dump, request.digest) {
+        (_, None) => "none".to_string(),
+        (LoginParamsDump::Redacted, Some(digest)) => format!("<redacted, {} bytes>", digest.len()),
+        (LoginParamsDump::Full, Some(digest)) => format!("{digest:02X?}"),
+    };
+
+    let params = match dump {
+        LoginParamsDump::Redacted => redact_login_params(event)?,
+        LoginParamsDump::Full => event.params.data.clone(),
+    };
+
+    writeln!(write, "protocol: {}", request.protocol)?;
+    writeln!(write, "username: {:?}", request.username)?;
+    writeln!(write, "password: {password}")?;
+    writeln!(write, "blowfish_key: {blowfish_key}")?;
+    writeln!(write, "context: {:?}", request.context)?;
+    writeln!(write, "digest: {digest}")?;
+    writeln!(write, "nonce: 0x{:08X}", request.nonce)?;
+    writeln!(write, "params: {} bytes", params.len())?;
+
+    for line in params.chunks(16) {
+        writeln!(write, "  {:02X?}", line)?;
+    }
+
+    Ok(())
+
+}
+
+/// Return the decrypted parameters blob of the given login request with the password,
+/// blowfish key and digest zeroed. The request is encoded again with zeroed secrets of
+/// the same lengths, so the layout is preserved, and any trailing bytes that are not
+/// decoded are kept as-is.
+fn redact_login_params(event: &login::proxy::LoginRequestEvent) -> io::Result<Vec<u8>> {
+
+    let request = login::element::LoginRequest {
+        password: "\0".repeat(event.request.password.len()),
+        blowfish_key: vec![0; event.request.blowfish_key.len()],
+        digest: event.request.digest.map(|_| [0; 16]),
+        ..event.request.clone()
+    };
+
+    // The unencrypted codec writes the protocol and encryption flag first.
+    let mut data = Vec::new();
+    Codec::write(&request, &mut data, &())?;
+    data.drain(..5);
+
+    if let Some(trailing) = event.params.data.get(data.len()..) {
+        data.extend_from_slice(trailing);
+    }
+
+    Ok(data)
+
+}
+
+/// The thread running a base app proxy, forwarding packets and sending the received
+/// bundles to the decode pool.
+#[derive(Debug)]
+struct SocketThread {
+    shared: Arc<Shared>,
+    decode_pool: DecodePool,
 }
 
+/// The decoding state of the base app, running in a decode worker when live, or on 
+/// the main thread when reading a capture.
 #[derive(Debug)]
 struct BaseThread {
-    app: proxy::App,
     shared: Arc<Shared>,
+    /// Decoding state of each client connection handled by this thread.
+    sessions: HashMap<SocketAddr, Session>,
+    /// The directory where large partial resources of this thread are spilled, if 
+    /// enabled, each session has its own sub-directory.
+    resource_spill_dir: Option<PathBuf>,
+    /// The optional profiler of decoded elements.
+    profiler: Option<Profiler>,
+    /// The optional ring buffer of recent packets, dumped on decode errors.
+    packet_ring: Option<PacketRing>,
+    /// Number of packet ring dumps, used to name the dump files.
+    packet_ring_dumps: u32,
+    /// The time of the event being currently processed, this is the real time when
+    /// live, or the capture time when reading a capture.
+    now: Instant,
+}
+
+/// The decoding state of a single client connection, each client has its own session
+/// so that clients connected simultaneously don't corrupt each other's state.
+#[derive(Debug)]
+struct Session {
     next_tick: Option<u8>,
-    entities: HashMap<u32, &'static EntityType>,
+    /// Type id of all known entities.
+    entities: HashMap<u32, u16>,
+    /// Entity ids of aliases given to entities entering the AoI.
+    entity_aliases: HashMap<u8, u32>,
+    /// Store of decoded entities, only when decoded with generated entity types, 
+    /// property updates are applied to it.
+    entity_store: EntityStore,
     selected_entity_id: Option<u32>,
     player_entity_id: Option<u32>,
-    partial_resources: HashMap<u16, PartialResource>,
+    resource_downloader: ResourceDownloader,
+}
+
+/// A pool of decode workers, each client is consistently dispatched to the same worker
+/// in order to decode its bundles in order.
+#[derive(Debug, Clone)]
+struct DecodePool {
+    senders: Vec<Sender<DecodeJob>>,
+    hasher: RandomState,
+}
+
+/// A bundle to be decoded by a decode worker.
+#[derive(Debug)]
+struct DecodeJob {
+    bundle: proxy::BundleEvent,
+    /// The time the bundle has been received at.
+    time: Instant,
+    /// The pool to give the bundle's packets back to once decoded.
+    pool: PacketPool,
 }
 
 #[derive(Debug)]
 struct Shared {
     dump_dir: PathBuf,
     pending_clients: Mutex<HashMap<SocketAddr, PendingClient>>,
+    /// Injectors of all peers bound to a base app proxy worker, used by the console.
+    injectors: Mutex<HashMap<SocketAddr, proxy::Injector>>,
+    /// Trackers of the requests injected toward each peer by the console, replies are
+    /// decoded by the decode worker of the peer, untracked replies are logged raw.
+    request_trackers: Mutex<HashMap<SocketAddr, ReplyTracker>>,
+    /// Interval between profiler reports, if the profiler is enabled.
+    profile_interval: Option<Duration>,
+    /// Interval between memory usage metrics reports, if enabled.
+    metrics_interval: Option<Duration>,
+    /// Number of recent packets kept for each peer and dumped on decode errors, zero
+    /// to disable.
+    packet_ring_len: usize,
+    /// Entity definitions interpreted at runtime, replacing the generated code.
+    defs: Option<RuntimeDefs>,
+    /// Fixtures directory where decoded elements are captured, if enabled.
+    fixtures: Option<Fixtures>,
+    /// Directory where large partial resources are spilled, if enabled.
+    resource_spill_dir: Option<PathBuf>,
+    /// Recorder of decrypted packets, if enabled.
+    recorder: Option<Mutex<Recorder>>,
+    /// Connections for which a snapshot of the entity store has been requested.
+    snapshot_requests: Mutex<HashSet<SocketAddr>>,
+    /// Database where decoded elements are dumped, if enabled.
+    #[cfg(feature = "sqlite")]
+    database: Option<Mutex<Database>>,
+    /// Sender of events to the terminal interface, if enabled.
+    tui: Option<Sender<TuiEvent>>,
+}
+
+impl Shared {
+
+    /// Create the state shared by all threads from the given options, the terminal
+    /// interface is enabled if an event sender is given.
+    fn new(options: &ProxyOptions, tui: Option<Sender<TuiEvent>>) -> CliResult<Self> {
+        Ok(Self {
+            dump_dir: create_dump_dir()?,
+            pending_clients: Mutex::new(HashMap::new()),
+            injectors: Mutex::new(HashMap::new()),
+            request_trackers: Mutex::new(HashMap::new()),
+            profile_interval: options.profile_interval,
+            metrics_interval: options.metrics_interval,
+            packet_ring_len: options.packet_ring_len,
+            defs: load_entity_defs(options.entity_defs, options.user_types)?,
+            fixtures: load_fixtures(options.fixtures_dir)?,
+            resource_spill_dir: options.resource_spill_dir.map(Path::to_path_buf),
+            recorder: create_recorder(options.record_path)?,
+            snapshot_requests: Mutex::new(HashSet::new()),
+            #[cfg(feature = "sqlite")]
+            database: create_database(options.database_path)?,
+            tui,
+        })
+    }
+
+    /// Run the given function with the tracker of requests of the given peer.
+    fn with_request_tracker<T>(&self, addr: SocketAddr, func: impl FnOnce(&mut ReplyTracker) -> T) -> T {
+        let mut trackers = self.request_trackers.lock().unwrap();
+        let tracker = trackers.entry(addr).or_insert_with(|| {
+            let mut tracker = RequestTracker::new();
+            tracker.set_next_request_id(INJECTED_REQUEST_FIRST_ID);
+            tracker
+        });
+        func(tracker)
+    }
+
+    /// Get the name of the given entity type, none if the entity type is unknown.
+    fn entity_type_name(&self, type_id: u16) -> Option<&str> {
+        match &self.defs {
+            Some(defs) => defs.entity(type_id).map(|entity| &entity.name[..]),
+            None => gen::entity::Generic::type_name(type_id),
+        }
+    }
+
+    /// Send the event built by the given function to the terminal interface, if
+    /// enabled, the event is dropped if the interface doesn't keep up.
+    fn tui_event(&self, event: impl FnOnce() -> TuiEvent) {
+        if let Some(sender) = &self.tui {
+            let _ = sender.try_send(event());
+        }
+    }
+
+    /// Insert rows in the dump database with the given function, if enabled, errors
+    /// are only logged because the dump directory is still written.
+    #[cfg(feature = "sqlite")]
+    fn dump_db(&self, insert: impl FnOnce(&Database) -> rusqlite::Result<()>) {
+        if let Some(database) = &self.database {
+            if let Err(e) = insert(&database.lock().unwrap()) {
+                warn!("Failed to insert into dump database: {e}");
+            }
+        }
+    }
+
 }
 
 #[derive(Debug)]
@@ -110,128 +604,617 @@ struct PendingClient {
     blowfish: Arc<Blowfish>,
 }
 
-/// Describe a partial resource being download, a header must have been sent.
-#[derive(Debug)]
-struct PartialResource {
-    /// The byte description sent in the resource header.
-    description: Vec<u8>,
-    /// The next sequence number expected, any other sequence number abort the download
-    /// with an error.
-    sequence_num: u8,
-    /// The full assembled data.
-    data: Vec<u8>,
-}
-
 impl LoginThread {
 
     #[instrument(name = "login", skip_all)]
     fn run(mut self) {
 
-        use login::proxy::Event;
+        use login::proxy::Event;
+        use login::element::LoginError;
+
+        info!("Running on: {}", self.app.addr().unwrap());
+        
+        if self.app.has_encryption() {
+            info!("Encryption enabled");
+        }
+
+        self.app.set_bundle_events(self.shared.recorder.is_some());
+
+        loop {
+            match self.app.poll() {
+                Event::IoError(error) => {
+                    if let Some(addr) = error.addr {
+                        warn!(%addr, "Error: {}", error.error);
+                    } else {
+                        warn!("Error: {}", error.error);
+                    }
+                }
+                Event::Ping(ping) => {
+                    info!(addr = %ping.addr, "Ping-Pong: {:?}", ping.latency);
+                }
+                Event::LoginRequest(request) => {
+                    info!(addr = %request.addr, "Login request: {} (protocol: {})", request.request.username, request.request.protocol);
+                    if let Some(dump) = self.login_params_dump {
+                        self.dump_login_params(&request, dump);
+                    }
+                }
+                Event::LoginDigestMismatch(mismatch) => {
+                    warn!(addr = %mismatch.addr, "Login digest mismatch: {:02X?}, expected: {:02X?}", mismatch.digest, mismatch.expected_digest);
+                }
+                Event::LoginSuccess(success) => {
+                    info!(addr = %success.addr, "Login success (protocol: {})", success.protocol_version);
+                    self.shared.pending_clients.lock().unwrap().insert(success.addr, PendingClient { 
+                        base_app_addr: success.real_base_app_addr,
+                        blowfish: success.blowfish, 
+                    });
+                }
+                Event::Bundle(bundle) => {
+                    if let Some(recorder) = &self.shared.recorder {
+                        let res = recorder.lock().unwrap().record(CaptureApp::Login, bundle.addr, bundle.direction, &bundle.bundle, Instant::now());
+                        if let Err(e) = res {
+                            warn!(addr = %bundle.addr, "Failed to record bundle: {e}");
+                        }
+                    }
+                }
+                Event::LoginError(error) => {
+                    match error.error {
+                        LoginError::BadProtocolVersion => {
+                            warn!(addr = %error.addr, "Login error: {:?}, the real login app expects another protocol version, see --login-protocol", error.error);
+                        }
+                        LoginError::BadDigest => {
+                            warn!(addr = %error.addr, "Login error: {:?}, the real login app expects other entity definitions", error.error);
+                        }
+                        _ => {
+                            info!(addr = %error.addr, "Login error: {:?}", error.error);
+                        }
+                    }
+                }
+            }
+        }
+
+    }
+
+    /// Dump the decrypted login parameters of a client, in order to study the exact
+    /// login payload of the client version.
+    fn dump_login_params(&self, request: &login::proxy::LoginRequestEvent, dump: LoginParamsDump) {
+
+        let addr = request.addr;
+        let dump_name = addr.to_string().replace(['.', ':'], "_");
+        let dump_file = self.shared.dump_dir.join(format!("login_{dump_name}.txt"));
+
+        match File::create(&dump_file).and_then(|file| write_login_params(file, request, dump)) {
+            Ok(()) => info!(%addr, "Login parameters dumped to: {}", dump_file.display()),
+            Err(e) => warn!(%addr, "Failed to dump login parameters to {}: {e}", dump_file.display()),
+        }
+
+    }
+
+}
+
+impl SocketThread {
+
+    #[instrument(name = "base", skip(self, app))]
+    fn run(self, mut app: proxy::App, worker: usize) {
+
+        use proxy::Event;
+
+        info!("Running on: {}", app.addr().unwrap());
+
+        let mut last_metrics = Instant::now();
+
+        loop {
+
+            match app.poll() {
+                Event::IoError(error) => {
+                    if let Some(addr) = error.addr {
+                        warn!(%addr, "Error: {}", error.error);
+                    } else {
+                        warn!("Error: {}", error.error);
+                    }
+                }
+                Event::Rejection(rejection) => {
+                    if let Some(pending_client) = self.shared.pending_clients.lock().unwrap().remove(&rejection.addr) {
+                        
+                        info!("Rejection of known peer: {} (to {})", rejection.addr, pending_client.base_app_addr);
+                        
+                        app.bind_peer(
+                            rejection.addr, 
+                            SocketAddr::V4(pending_client.base_app_addr), 
+                            Some(pending_client.blowfish),
+                            None).unwrap();
+
+                        self.shared.injectors.lock().unwrap().insert(rejection.addr, app.injector());
+
+                    } else {
+                        warn!("Rejection of unknown peer: {}", rejection.addr);
+                    }
+                }
+                Event::Bundle(bundle) => {
+                    let addr = bundle.addr;
+                    let time = Instant::now();
+                    if let Some(recorder) = &self.shared.recorder {
+                        if let Err(e) = recorder.lock().unwrap().record(CaptureApp::Base, addr, bundle.direction, &bundle.bundle, time) {
+                            warn!(%addr, "Failed to record bundle: {e}");
+                        }
+                    }
+                    let pool = app.packet_pool().clone();
+                    match self.decode_pool.dispatch(DecodeJob { bundle, time, pool }) {
+                        Ok(()) => {}
+                        Err(TrySendError::Full(job)) => {
+                            warn!(%addr, "Decode queue full, bundle will not be decoded: ({:?}) {} packets", 
+                                job.bundle.direction, job.bundle.bundle.len());
+                        }
+                        Err(TrySendError::Disconnected(job)) => {
+                            error!(%addr, "Decode worker stopped, bundle will not be decoded: ({:?}) {} packets", 
+                                job.bundle.direction, job.bundle.bundle.len());
+                        }
+                    }
+                }
+            }
+
+            if let Some(interval) = self.shared.metrics_interval {
+                if last_metrics.elapsed() >= interval {
+                    last_metrics = Instant::now();
+                    let stats = app.stats();
+                    info!("Metrics: {} peers, out: {:?}, in: {:?}", 
+                        stats.peers, stats.decoder.out_protocol, stats.decoder.in_protocol);
+                }
+            }
+
+        }
+
+    }
+
+}
+
+impl DecodePool {
+
+    /// Create a new decode pool with the given number of workers, returning the pool
+    /// and the receiver of each worker.
+    fn new(workers: usize) -> (Self, Vec<Receiver<DecodeJob>>) {
+        let (senders, receivers) = (0..workers)
+            .map(|_| crossbeam_channel::bounded(DECODE_QUEUE_CAP))
+            .unzip();
+        (Self { senders, hasher: RandomState::new() }, receivers)
+    }
+
+    /// Dispatch the given job to the worker associated to its client, the job is given
+    /// back if the worker's queue is full or if the worker has stopped.
+    fn dispatch(&self, job: DecodeJob) -> Result<(), TrySendError<DecodeJob>> {
+        let index = self.hasher.hash_one(job.bundle.addr) % self.senders.len() as u64;
+        self.senders[index as usize].try_send(job)
+    }
+
+}
+
+impl Session {
+
+    /// Create a new session for the given client, if given, large partial resources
+    /// are spilled in a sub-directory of the given directory, because resource ids are
+    /// only unique to a connection.
+    fn new(addr: SocketAddr, resource_spill_dir: Option<&Path>) -> Self {
+
+        let mut resource_downloader = ResourceDownloader::new();
+        if let Some(dir) = resource_spill_dir {
+            let dir = dir.join(addr.to_string().replace(['.', ':'], "_"));
+            if let Err(e) = resource_downloader.set_spill_dir(Some(dir.clone())) {
+                warn!(%addr, "Failed to create resource spill directory at {}: {e}", dir.display());
+            }
+        }
+
+        Self {
+            next_tick: None,
+            entities: HashMap::new(),
+            entity_aliases: HashMap::new(),
+            entity_store: EntityStore::new(),
+            selected_entity_id: None,
+            player_entity_id: None,
+            resource_downloader,
+        }
+
+    }
+
+}
+
+impl BaseThread {
+
+    fn new(shared: Arc<Shared>) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            resource_spill_dir: None,
+            profiler: shared.profile_interval.map(Profiler::new),
+            packet_ring: (shared.packet_ring_len != 0).then(|| PacketRing::new(shared.packet_ring_len)),
+            packet_ring_dumps: 0,
+            now: Instant::now(),
+            shared,
+        }
+    }
+
+    #[instrument(name = "decode", skip(self, receiver))]
+    fn run(mut self, receiver: Receiver<DecodeJob>, worker: usize) {
+
+        self.init_resource_spill(worker);
+        let mut last_metrics = Instant::now();
+
+        loop {
+
+            match receiver.recv_timeout(DECODE_IDLE_INTERVAL) {
+                Ok(job) => {
+                    self.now = job.time;
+                    self.read_bundle(&job.bundle);
+                    job.pool.put_all(job.bundle.bundle.into_iter());
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.now = Instant::now();
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            self.poll_resources();
+            self.poll_snapshots();
+
+            if let Some(interval) = self.shared.metrics_interval {
+                if last_metrics.elapsed() >= interval {
+                    last_metrics = Instant::now();
+                    let sessions = self.sessions.values();
+                    info!("Metrics: {} sessions, {} entities, {} partial resources of {} bytes", 
+                        sessions.len(),
+                        sessions.clone().map(|session| session.entities.len()).sum::<usize>(), 
+                        sessions.clone().map(|session| session.resource_downloader.len()).sum::<usize>(), 
+                        sessions.map(|session| session.resource_downloader.buffered_len()).sum::<usize>());
+                }
+            }
+
+            if let Some(profiler) = &mut self.profiler {
+                let shared = &self.shared;
+                profiler.poll_report(move |type_id| shared.entity_type_name(type_id));
+            }
+
+        }
+
+    }
+
+    #[instrument(name = "base", skip_all)]
+    fn run_pcap<R: io::Read>(mut self, mut importer: PcapImporter<R>, base_app_addr: SocketAddr) -> io::Result<()> {
+
+        info!("Reading capture with base app: {base_app_addr}");
+        self.init_resource_spill(0);
+
+        let mut decoder = BulkDecoder::new();
+        let mut peers = HashSet::new();
+        let mut start_time = None;
+
+        // Datagrams are decoded in parallel by batches, the capture time of each 
+        // datagram is kept to process the decoded bundles at the right time.
+        let mut batch = Vec::with_capacity(PCAP_BATCH_LEN);
+        let mut batch_times = Vec::with_capacity(PCAP_BATCH_LEN);
+
+        loop {
+
+            let packet = match importer.next_packet() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(ImportError::Packet(addr, e)) => {
+                    warn!(%addr, "Invalid packet: {e}");
+                    continue;
+                }
+                Err(ImportError::Pcap(e)) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+
+            // Use the capture time to expire partial resources, relative to the first
+            // datagram because instants cannot be created from absolute times.
+            let start_time = *start_time.get_or_insert((Instant::now(), packet.time));
+            batch_times.push(start_time.0 + packet.time.saturating_sub(start_time.1));
+
+            if peers.insert(packet.addr) {
+                decoder.set_cipher(packet.addr, packet.cipher);
+            }
+
+            batch.push(BulkPacket { packet: packet.packet, addr: packet.addr, direction: packet.direction });
+            if batch.len() >= PCAP_BATCH_LEN {
+                self.read_pcap_batch(&mut decoder, &mut batch, &mut batch_times);
+            }
+
+        }
+
+        self.read_pcap_batch(&mut decoder, &mut batch, &mut batch_times);
+
+        info!("End of capture");
+
+        if let Some(profiler) = &mut self.profiler {
+            let shared = &self.shared;
+            profiler.report(move |type_id| shared.entity_type_name(type_id));
+        }
+
+        Ok(())
+
+    }
+
+    #[instrument(name = "base", skip_all)]
+    fn run_capture<R: io::Read>(mut self, reader: CaptureReader<R>) -> Result<(), CaptureError> {
+
+        info!("Reading capture of decrypted packets");
+        self.init_resource_spill(0);
+
+        // Packets are already decrypted, so no cipher is set on the decoder.
+        let mut decoder = BulkDecoder::new();
+        let start_time = Instant::now();
+
+        let mut batch = Vec::with_capacity(PCAP_BATCH_LEN);
+        let mut batch_times = Vec::with_capacity(PCAP_BATCH_LEN);
+
+        for record in reader {
+
+            let record = record?;
+            // Login packets are recorded with the session, but are not decoded here.
+            if record.app != CaptureApp::Base {
+                continue;
+            }
+
+            batch_times.push(start_time + record.time);
+            batch.push(BulkPacket { packet: record.packet, addr: record.addr, direction: record.direction });
+
+            if batch.len() >= PCAP_BATCH_LEN {
+                self.read_pcap_batch(&mut decoder, &mut batch, &mut batch_times);
+            }
+
+        }
+
+        self.read_pcap_batch(&mut decoder, &mut batch, &mut batch_times);
+
+        info!("End of capture");
+
+        if let Some(profiler) = &mut self.profiler {
+            let shared = &self.shared;
+            profiler.report(move |type_id| shared.entity_type_name(type_id));
+        }
+
+        Ok(())
+
+    }
+
+    /// Decode the given batch of packets from a capture and read the decoded bundles in
+    /// order, the batch is then cleared.
+    fn read_pcap_batch(&mut self, decoder: &mut BulkDecoder, batch: &mut Vec<BulkPacket>, batch_times: &mut Vec<Instant>) {
+
+        // Keep the peer of each packet, to report the packets that can't be decoded.
+        let batch_addrs = batch.iter().map(|packet| packet.addr).collect::<Vec<_>>();
+
+        for (index, res) in decoder.decode_batch(batch.drain(..)) {
+
+            self.now = batch_times[index];
+
+            match res {
+                Ok(bundle) => self.read_bundle(&bundle),
+                Err(e) => warn!(addr = %batch_addrs[index], "Error while decoding packet: {e}"),
+            }
+
+            self.poll_resources();
+
+            if let Some(profiler) = &mut self.profiler {
+                let shared = &self.shared;
+                profiler.poll_report(move |type_id| shared.entity_type_name(type_id));
+            }
+
+        }
+
+        // Use the time of the last datagram, in case no bundle was completed.
+        if let Some(&time) = batch_times.last() {
+            self.now = time;
+            self.poll_resources();
+        }
+
+        batch_times.clear();
+
+    }
+
+    fn read_bundle(&mut self, bundle: &proxy::BundleEvent) {
+
+        if let Some(packet_ring) = &mut self.packet_ring {
+            for packet in bundle.bundle.iter() {
+                packet_ring.push(bundle.addr, bundle.direction, packet, self.now);
+            }
+        }
+
+        let res = match bundle.direction {
+            PacketDirection::Out => self.read_out_bundle(&bundle.bundle, bundle.addr),
+            PacketDirection::In => self.read_in_bundle(&bundle.bundle, bundle.addr),
+        };
+
+        if let Err(e) = res {
+            error!(addr = %bundle.addr, "Error while reading bundle: ({:?}) {e}", bundle.direction);
+            self.dump_packet_ring(bundle.addr);
+        }
+
+    }
+
+    /// Dump the recent packets of the given peer after a decode error, if enabled.
+    fn dump_packet_ring(&mut self, addr: SocketAddr) {
+
+        let Some(packet_ring) = &self.packet_ring else {
+            return;
+        };
+
+        let dump_name = addr.to_string().replace(['.', ':'], "_");
+        let dump_file = self.shared.dump_dir.join(format!("error_{dump_name}_{}.txt", self.packet_ring_dumps));
+        self.packet_ring_dumps += 1;
+
+        match packet_ring.dump(addr, &dump_file) {
+            Ok(()) => info!(%addr, "Recent packets dumped to: {}", dump_file.display()),
+            Err(e) => warn!(%addr, "Failed to dump recent packets to {}: {e}", dump_file.display()),
+        }
+
+    }
+
+    /// Enable spilling of large partial resources if requested, each decode worker has
+    /// its own sub-directory, in which each session has its own sub-directory.
+    fn init_resource_spill(&mut self, worker: usize) {
+
+        let Some(dir) = self.shared.resource_spill_dir.as_deref() else {
+            return;
+        };
+
+        let dir = dir.join(format!("worker_{worker}"));
+        info!("Spilling large resources to: {}", dir.display());
+        self.resource_spill_dir = Some(dir);
+
+    }
+
+    /// Return the session of the given client, it's created on first use.
+    fn session(&mut self, addr: SocketAddr) -> &mut Session {
+        let resource_spill_dir = self.resource_spill_dir.as_deref();
+        self.sessions.entry(addr).or_insert_with(|| Session::new(addr, resource_spill_dir))
+    }
+
+    /// Forget the given peer once it has disconnected or has been logged off, its
+    /// injector is removed so that the console no longer injects toward it, and its
+    /// session, request tracker and recent packets are forgotten.
+    fn disconnect(&mut self, addr: SocketAddr) {
+        self.sessions.remove(&addr);
+        self.shared.injectors.lock().unwrap().remove(&addr);
+        self.shared.request_trackers.lock().unwrap().remove(&addr);
+        self.shared.snapshot_requests.lock().unwrap().remove(&addr);
+        if let Some(packet_ring) = &mut self.packet_ring {
+            packet_ring.remove(addr);
+        }
+    }
+
+    /// Poll all resource downloader events of all sessions, this also abandon all 
+    /// partial resources that are expired or over capacity.
+    fn poll_resources(&mut self) {
 
-        info!("Running on: {}", self.app.addr().unwrap());
-        
-        if self.app.has_encryption() {
-            info!("Encryption enabled");
+        let mut events = Vec::new();
+        for (&addr, session) in &mut self.sessions {
+            while let Some(event) = session.resource_downloader.poll(self.now) {
+                events.push((addr, event));
+            }
         }
 
-        loop {
-            match self.app.poll() {
-                Event::IoError(error) => {
-                    if let Some(addr) = error.addr {
-                        warn!(%addr, "Error: {}", error.error);
-                    } else {
-                        warn!("Error: {}", error.error);
-                    }
-                }
-                Event::Ping(ping) => {
-                    info!(addr = %ping.addr, "Ping-Pong: {:?}", ping.latency);
+        for (addr, event) in events {
+            match event {
+                ResourceEvent::Complete(complete) => self.read_resource(addr, complete),
+                ResourceEvent::Resume(resume) => {
+                    info!(%addr, "<- Resource resumed: {}, len: {}", resume.id, resume.len);
                 }
-                Event::LoginSuccess(success) => {
-                    info!(addr = %success.addr, "Login success");
-                    self.shared.pending_clients.lock().unwrap().insert(success.addr, PendingClient { 
-                        base_app_addr: success.real_base_app_addr,
-                        blowfish: success.blowfish, 
-                    });
+                ResourceEvent::Abandon(abandon) => {
+                    warn!(%addr, "<- Resource abandoned: {}, len: {}, reason: {:?}", 
+                        abandon.id, abandon.len, abandon.reason);
                 }
-                Event::LoginError(error) => {
-                    info!(addr = %error.addr, "Login error: {:?}", error.error);
+                ResourceEvent::Error(error) => {
+                    warn!(%addr, "<- Resource error: {}, {}", error.id, error.error);
                 }
             }
         }
 
     }
 
-}
+    fn read_resource(&mut self, addr: SocketAddr, complete: ResourceCompleteEvent) {
 
-impl BaseThread {
+        let res_id = complete.id;
+        let crc32 = complete.description.crc32;
+        info!(%addr, "<- Resource completed: {res_id}, len: {}, crc32: 0x{crc32:08X}", complete.data.len());
 
-    #[instrument(name = "base", skip_all)]
-    fn run(mut self) {
+        // TODO: The full data looks like to be a zlib-compressed pickle.
+        // TODO: onCmdResponse for requested SYNC use RES_SUCCESS=0, RES_STREAM=1, RES_CACHE=2 for result_id
+        //       When RES_STREAM is used, then a resource (header+fragment) is expected with the associated request_id.
 
-        use proxy::Event;
+        match pickle::from_reader(ZlibDecoder::new(&complete.data[..])) {
+            Ok(val) => {
+                
+                let dump_file = self.shared.dump_dir.join(format!("res_{crc32:08x}.txt"));
+                info!(%addr, "<- Saving resource to: {}", dump_file.display());
 
-        info!("Running on: {}", self.app.addr().unwrap());
+                let mut dump_writer = File::create(dump_file).unwrap();
+                write!(dump_writer, "{val}").unwrap();
 
-        loop {
-            match self.app.poll() {
-                Event::IoError(error) => {
-                    if let Some(addr) = error.addr {
-                        warn!(%addr, "Error: {}", error.error);
-                    } else {
-                        warn!("Error: {}", error.error);
-                    }
-                }
-                Event::Rejection(rejection) => {
-                    if let Some(pending_client) = self.shared.pending_clients.lock().unwrap().remove(&rejection.addr) {
-                        
-                        info!("Rejection of known peer: {} (to {})", rejection.addr, pending_client.base_app_addr);
-                        
-                        self.app.bind_peer(
-                            rejection.addr, 
-                            SocketAddr::V4(pending_client.base_app_addr), 
-                            Some(pending_client.blowfish),
-                            None).unwrap();
+                #[cfg(feature = "sqlite")]
+                self.shared.dump_db(|db| db.insert_resource(self.now, res_id, crc32, complete.data.len(), Some(&val.to_string())));
 
-                    } else {
-                        warn!("Rejection of unknown peer: {}", rejection.addr);
-                    }
-                }
-                Event::Bundle(bundle) => {
-                    
-                    let res = match bundle.direction {
-                        PacketDirection::Out => self.read_out_bundle(bundle.bundle, bundle.addr),
-                        PacketDirection::In => self.read_in_bundle(bundle.bundle, bundle.addr),
-                    };
-
-                    if let Err(e) = res {
-                        error!(addr = %bundle.addr, "Error while reading bundle: ({:?}) {e}", bundle.direction);
-                    }
+            }
+            Err(e) => {
+
+                warn!(%addr, "<- Resource: pickle error: {e}");
+
+                let raw_file = self.shared.dump_dir.join(format!("res_{crc32:08x}.raw"));
+                info!(%addr, "<- Saving resource to: {}", raw_file.display());
+
+                let mut raw_writer = File::create(raw_file).unwrap();
+                std::io::copy(&mut ZlibDecoder::new(&complete.data[..]), &mut raw_writer).unwrap();
+
+                #[cfg(feature = "sqlite")]
+                self.shared.dump_db(|db| db.insert_resource(self.now, res_id, crc32, complete.data.len(), None));
 
-                }
-                    
             }
         }
 
     }
 
-    fn read_out_bundle(&mut self, bundle: Bundle, addr: SocketAddr) -> io::Result<()> {
+    /// Return the profiling key of the given element if profiling is enabled, this must
+    /// be called before reading the element because the selected entity may change.
+    fn profile_key(&self, addr: SocketAddr, direction: PacketDirection, id: u8) -> Option<ProfileKey> {
+
+        if self.profiler.is_none() {
+            return None;
+        }
+
+        let entity_type = self.sessions.get(&addr).and_then(|session| {
+            let entity_id = match direction {
+                PacketDirection::Out if base::element::id::BASE_ENTITY_METHOD.contains(id) => session.player_entity_id,
+                PacketDirection::In if client::element::id::ENTITY_METHOD.contains(id) => session.selected_entity_id,
+                _ => None,
+            }?;
+            session.entities.get(&entity_id).copied()
+        });
+
+        Some(ProfileKey {
+            direction,
+            id,
+            entity_type,
+        })
+
+    }
+
+    /// Record the decoding of an element in the profiler, if enabled.
+    fn record_profile(&mut self, key: Option<ProfileKey>, start_time: Instant, len: usize) {
+        if let (Some(profiler), Some(key)) = (&mut self.profiler, key) {
+            profiler.record(key, start_time.elapsed(), len);
+        }
+    }
+
+    fn read_out_bundle(&mut self, bundle: &Bundle, addr: SocketAddr) -> io::Result<()> {
 
         let mut reader = bundle.element_reader();
-        while let Some(elt) = reader.next() {
+        loop {
+
+            let start_pos = reader.pos();
+            let Some(elt) = reader.next() else {
+                break;
+            };
+
             match elt {
                 NextElementReader::Element(elt) => {
-                    if !self.read_out_element(elt, addr)? {
+                    let profile_key = self.profile_key(addr, PacketDirection::Out, elt.id());
+                    let start_time = Instant::now();
+                    let next = self.read_out_element(elt, addr)?;
+                    self.record_profile(profile_key, start_time, reader.pos() - start_pos);
+                    if !next {
                         break;
                     }
                 }
                 NextElementReader::Reply(reply) => {
                     let request_id = reply.request_id();
-                    let _elt = reply.read_simple::<()>()?;
-                    warn!(%addr, "-> Reply #{request_id}");
-                    break;
+                    match self.shared.with_request_tracker(addr, |tracker| tracker.read_reply(addr, reply))? {
+                        TrackedReply::Known(data) => {
+                            info!(%addr, "-> Reply #{request_id}: {data:?}");
+                        }
+                        TrackedReply::Unknown(data) => {
+                            warn!(%addr, "-> Reply #{request_id} (untracked): {:?}", AsciiFmt(&data));
+                            log_sniff(addr, &data);
+                        }
+                    }
                 }
             }
+
         }
 
         Ok(())
@@ -254,26 +1237,45 @@ impl BaseThread {
             }
             DisconnectClient::ID => {
                 let dc = elt.read_simple::<DisconnectClient>()?;
-                info!(%addr, "-> Disconnect: 0x{:02X}", dc.element.reason);
+                info!(%addr, "-> Disconnect: {}", dc.element.reason);
+                self.disconnect(addr);
+            }
+            AvatarUpdateImplicit::ID => {
+                let au = elt.read_simple::<AvatarUpdateImplicit>()?;
+                debug!(%addr, "-> Avatar update: {:?}", au.element);
+            }
+            AvatarUpdateExplicit::ID => {
+                let au = elt.read_simple::<AvatarUpdateExplicit>()?;
+                debug!(%addr, "-> Avatar update: {:?}", au.element);
+            }
+            AvatarUpdateWardImplicit::ID => {
+                let au = elt.read_simple::<AvatarUpdateWardImplicit>()?;
+                debug!(%addr, "-> Avatar update ward: {:?}", au.element);
+            }
+            AvatarUpdateWardExplicit::ID => {
+                let au = elt.read_simple::<AvatarUpdateWardExplicit>()?;
+                debug!(%addr, "-> Avatar update ward: {:?}", au.element);
             }
             id if id::BASE_ENTITY_METHOD.contains(id) => {
 
                 // Account::doCmdInt3 (AccountCommands.CMD_SYNC_DATA), exposed id: 0x0E, message id: 0x95
 
-                if let Some(entity_id) = self.player_entity_id {
+                let session = self.session(addr);
+                if let Some(entity_id) = session.player_entity_id {
                     // Unwrap because selected entity should exist!
-                    let entity_type = *self.entities.get(&entity_id).unwrap();
-                    return (entity_type.base_entity_method)(&mut *self, addr, entity_id, elt);
+                    let type_id = *session.entities.get(&entity_id).unwrap();
+                    return self.dispatch_base_entity_method(addr, entity_id, type_id, elt);
                 }
 
-                let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
+                let elt = elt.read_borrowed::<RawElement, _>(&ElementLength::Undefined)?;
                 warn!(%addr, "-> Base entity method (unknown selected entity): msg#{} {:?} (request: {:?})", id - id::BASE_ENTITY_METHOD.first, elt.element, elt.request_id);
                 return Ok(false);
 
             }
             id => {
-                let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
+                let elt = elt.read_borrowed::<RawElement, _>(&ElementLength::Undefined)?;
                 error!(%addr, "-> Element #{id} {:?} (request: {:?})", elt.element, elt.request_id);
+                log_sniff(addr, &elt.element.data);
                 return Ok(false);
             }
         }
@@ -282,23 +1284,40 @@ impl BaseThread {
 
     }
 
-    fn read_in_bundle(&mut self, bundle: Bundle, addr: SocketAddr) -> io::Result<()> {
+    fn read_in_bundle(&mut self, bundle: &Bundle, addr: SocketAddr) -> io::Result<()> {
 
         let mut reader = bundle.element_reader();
-        while let Some(elt) = reader.next() {
+        loop {
+
+            let start_pos = reader.pos();
+            let Some(elt) = reader.next() else {
+                break;
+            };
+
             match elt {
                 NextElementReader::Element(elt) => {
-                    if !self.read_in_element(elt, addr)? {
+                    let profile_key = self.profile_key(addr, PacketDirection::In, elt.id());
+                    let start_time = Instant::now();
+                    let next = self.read_in_element(elt, addr)?;
+                    self.record_profile(profile_key, start_time, reader.pos() - start_pos);
+                    if !next {
                         break;
                     }
                 }
                 NextElementReader::Reply(reply) => {
                     let request_id = reply.request_id();
-                    let _elt = reply.read_simple::<()>()?;
-                    warn!(%addr, "<- Reply #{request_id}");
-                    break;
+                    match self.shared.with_request_tracker(addr, |tracker| tracker.read_reply(addr, reply))? {
+                        TrackedReply::Known(data) => {
+                            info!(%addr, "<- Reply #{request_id}: {data:?}");
+                        }
+                        TrackedReply::Unknown(data) => {
+                            warn!(%addr, "<- Reply #{request_id} (untracked): {:?}", AsciiFmt(&data));
+                            log_sniff(addr, &data);
+                        }
+                    }
                 }
             }
+
         }
 
         Ok(())
@@ -316,55 +1335,66 @@ impl BaseThread {
             }
             TickSync::ID => {
                 let ts = elt.read_simple::<TickSync>()?;
-                if let Some(next_tick) = self.next_tick {
+                let session = self.session(addr);
+                if let Some(next_tick) = session.next_tick {
                     if next_tick != ts.element.tick {
                         warn!(%addr, "<- Tick missed, expected {next_tick}, got {}", ts.element.tick);
                     }
                 }
-                self.next_tick = Some(ts.element.tick.wrapping_add(1));
+                session.next_tick = Some(ts.element.tick.wrapping_add(1));
             }
             ResetEntities::ID => {
 
                 let re = elt.read_simple::<ResetEntities>()?;
+                let session = self.session(addr);
 
                 info!(%addr, "<- Reset entities, keep player on base: {}, entities: {}", 
-                    re.element.keep_player_on_base, self.entities.len());
+                    re.element.keep_player_on_base, session.entities.len());
 
                 // Don't delete player entity if requested...
                 let mut player_entity = None;
                 if re.element.keep_player_on_base {
-                    if let Some(player_entity_id) = self.player_entity_id {
-                        player_entity = Some(self.entities.remove_entry(&player_entity_id).unwrap());
+                    if let Some(player_entity_id) = session.player_entity_id {
+                        player_entity = Some(session.entities.remove_entry(&player_entity_id).unwrap());
                     }
                 }
                 
-                self.entities.clear();
-                self.player_entity_id = None;
+                session.entities.clear();
+                session.entity_aliases.clear();
+                session.entity_store.retain(|entity_id| matches!(player_entity, Some((player_entity_id, _)) if player_entity_id == entity_id));
+                session.player_entity_id = None;
+                session.selected_entity_id = None;
                 
                 // Restore player entity!
                 if let Some((player_entity_id, player_entity)) = player_entity {
-                    self.entities.insert(player_entity_id, player_entity);
-                    self.player_entity_id = Some(player_entity_id);
+                    session.entities.insert(player_entity_id, player_entity);
+                    session.player_entity_id = Some(player_entity_id);
                 }
 
+                let keep = session.player_entity_id;
+                self.shared.tui_event(|| TuiEvent::EntitiesReset { addr, keep });
+
             }
             LoggedOff::ID => {
                 let lo = elt.read_simple::<LoggedOff>()?;
-                info!(%addr, "<- Logged off: 0x{:02X}", lo.element.reason);
+                info!(%addr, "<- Logged off: {}", lo.element.reason);
+                self.disconnect(addr);
             }
             CreateBasePlayerHeader::ID => {
 
                 let cbp = elt.read_simple_stable::<CreateBasePlayerHeader>()?;
 
-                if let Some(entity_type) = cbp.element.entity_type_id.checked_sub(1).and_then(|i| ENTITY_TYPES.get(i as usize)) {
-                    self.entities.insert(cbp.element.entity_id, entity_type);
-                    self.player_entity_id = Some(cbp.element.entity_id);
-                    return (entity_type.create_base_player)(&mut *self, addr, elt);
+                let type_id = cbp.element.entity_type_id;
+                if self.shared.entity_type_name(type_id).is_some() {
+                    let session = self.session(addr);
+                    session.entities.insert(cbp.element.entity_id, type_id);
+                    session.player_entity_id = Some(cbp.element.entity_id);
+                    return self.dispatch_create_base_player(addr, type_id, elt);
                 }
 
-                self.player_entity_id = None;
+                self.session(addr).player_entity_id = None;
                 // It's possible to skip it because its len is variable.
-                let dbg = elt.read_simple::<DebugElementVariable16<0>>()?;
+                let dbg = elt.read_borrowed::<RawElement, _>(&ElementLength::Variable16)?;
                 warn!(%addr, "<- Create base player with invalid entity type id: 0x{:02X}, {:?}", 
                     cbp.element.entity_type_id, dbg.element);
 
@@ -375,12 +1405,71 @@ impl BaseThread {
             }
             SelectPlayerEntity::ID => {
                 let _spe = elt.read_simple::<SelectPlayerEntity>()?;
-                if let Some(player_entity_id) = self.player_entity_id {
+                let session = self.session(addr);
+                if let Some(player_entity_id) = session.player_entity_id {
                     info!(%addr, "<- Select player entity: {player_entity_id}");
                 } else {
                     warn!(%addr, "<- Select player entity: no player entity")
                 }
-                self.selected_entity_id = self.player_entity_id;
+                session.selected_entity_id = session.player_entity_id;
+            }
+            SelectEntity::ID => {
+                let se = elt.read_simple::<SelectEntity>()?;
+                self.select_entity(addr, se.element.entity_id);
+            }
+            SelectAliasedEntity::ID => {
+                let sae = elt.read_simple::<SelectAliasedEntity>()?;
+                let session = self.session(addr);
+                if let Some(&entity_id) = session.entity_aliases.get(&sae.element.alias) {
+                    self.select_entity(addr, entity_id);
+                } else {
+                    warn!(%addr, "<- Select aliased entity: unknown alias {}", sae.element.alias);
+                    session.selected_entity_id = None;
+                }
+            }
+            EnterAoi::ID => {
+                let ea = elt.read_simple::<EnterAoi>()?;
+                debug!(%addr, "<- Enter AoI: {}, alias: {}", ea.element.entity_id, ea.element.alias);
+                self.session(addr).entity_aliases.insert(ea.element.alias, ea.element.entity_id);
+                self.shared.tui_event(|| TuiEvent::EntityEnter { addr, entity_id: ea.element.entity_id, entity_type: None });
+            }
+            EnterAoiOnVehicle::ID => {
+                let ea = elt.read_simple::<EnterAoiOnVehicle>()?;
+                debug!(%addr, "<- Enter AoI: {} on vehicle {}, alias: {}", ea.element.entity_id, ea.element.vehicle_entity_id, ea.element.alias);
+                self.session(addr).entity_aliases.insert(ea.element.alias, ea.element.entity_id);
+                self.shared.tui_event(|| TuiEvent::EntityEnter { addr, entity_id: ea.element.entity_id, entity_type: None });
+            }
+            LeaveAoi::ID => {
+                let la = elt.read_simple::<LeaveAoi>()?;
+                debug!(%addr, "<- Leave AoI: {:?}", la.element);
+                // The left entity id is the first field.
+                if let Some(entity_id) = la.element.data.first_chunk::<4>() {
+                    let entity_id = u32::from_le_bytes(*entity_id);
+                    self.shared.tui_event(|| TuiEvent::EntityLeave { addr, entity_id });
+                }
+            }
+            id if id::AVATAR_UPDATE.contains(id) => {
+                let au = elt.read_simple::<AvatarUpdate>()?;
+                debug!(%addr, "<- Avatar update: {:?}", au.element);
+            }
+            DetailedPosition::ID => {
+                let dp = elt.read_simple::<DetailedPosition>()?;
+                debug!(%addr, "<- Detailed position: {:?}", dp.element);
+            }
+            ControlEntity::ID => {
+                let ce = elt.read_simple::<ControlEntity>()?;
+                info!(%addr, "<- Control entity: {}, on: {}", ce.element.entity_id, ce.element.on);
+            }
+            SetVehicle::ID => {
+                let sv = elt.read_simple::<SetVehicle>()?;
+                debug!(%addr, "<- Set vehicle: {} on {}", sv.element.passenger_entity_id, sv.element.vehicle_entity_id);
+            }
+            RelativePositionReference::ID => {
+                let _rpr = elt.read_simple::<RelativePositionReference>()?;
+            }
+            RelativePosition::ID => {
+                let rp = elt.read_simple::<RelativePosition>()?;
+                debug!(%addr, "<- Relative position: {}", rp.element.position);
             }
             ResourceHeader::ID => {
 
@@ -388,136 +1477,110 @@ impl BaseThread {
                 info!(%addr, "<- Resource header: {}", rh.element.id);
 
                 // Intentionally overwrite any previous downloading resource!
-                self.partial_resources.insert(rh.element.id, PartialResource {
-                    description: rh.element.description,
-                    sequence_num: 0,
-                    data: Vec::new(),
-                });
+                let now = self.now;
+                self.session(addr).resource_downloader.handle_header(rh.element, now);
 
             }
             ResourceFragment::ID => {
-
                 let rf = elt.read_simple::<ResourceFragment>()?;
-                let res_id = rf.element.id;
+                info!(%addr, "<- Resource fragment: {}, len: {}, sequence number: {}", 
+                    rf.element.id, rf.element.data.len(), rf.element.sequence_num);
+                let now = self.now;
+                self.session(addr).resource_downloader.handle_fragment(rf.element, now);
+            }
+            id if id::ENTITY_METHOD.contains(id) => {
 
-                let Some(partial_resource) = self.partial_resources.get_mut(&res_id) else {
-                    warn!(%addr, "<- Resource fragment: {res_id}, len: {}, missing header", rf.element.data.len());
-                    return Ok(true);
-                };
+                // Account::msg#37 = onClanInfoReceived
+                // Account::msg#39 = showGUI
 
-                if rf.element.sequence_num != partial_resource.sequence_num {
-                    // Just forgetting about the resource!
-                    warn!(%addr, "<- Resource fragment: {res_id}, len: {}, invalid sequence number, expected {}, got {}", 
-                    rf.element.data.len(), partial_resource.sequence_num, rf.element.sequence_num);
-                    let _ = self.partial_resources.remove(&res_id);
-                    return Ok(true);
+                let session = self.session(addr);
+                if let Some(entity_id) = session.selected_entity_id {
+                    // Unwrap because selected entity should exist!
+                    let type_id = *session.entities.get(&entity_id).unwrap();
+                    return self.dispatch_entity_method(addr, entity_id, type_id, elt);
                 }
 
-                partial_resource.sequence_num += 1;
-                partial_resource.data.extend_from_slice(&rf.element.data);
-                info!(%addr, "<- Resource fragment: {res_id}, len: {}, sequence number: {}", 
-                    rf.element.data.len(), partial_resource.sequence_num);
-                
-                // Process the finished fragment!
-                if rf.element.last {
-
-                    let resource = self.partial_resources.remove(&rf.element.id).unwrap();
-                    
-                    // See: scripts/client/game.py#L223
-                    let (total_len, crc32) = match serde_pickle::value_from_reader(&resource.description[..], serde_pickle_de_options()) {
-                        Ok(serde_pickle::Value::Tuple(values)) if values.len() == 2 => {
-                            if let &[serde_pickle::Value::I64(total_len), serde_pickle::Value::I64(crc32)] = &values[..] {
-                                (total_len as u32, crc32 as u32)
-                            } else {
-                                warn!(%addr, "<- Invalid resource description: unexpected values: {values:?}");
-                                return Ok(true);
-                            }
-                        }
-                        Ok(v) => {
-                            warn!(%addr, "<- Invalid resource description: python: {v}");
-                            return Ok(true);
-                        }
-                        Err(e) => {
-                            warn!(%addr, "<- Invalid resource description: {e}");
-                            return Ok(true);
-                        }
-                    };
-
-                    let actual_total_len = resource.data.len();
-                    if actual_total_len != total_len as usize {
-                        warn!(%addr, "<- Invalid resource length, expected: {total_len}, got: {actual_total_len}");
-                        return Ok(true);
-                    }
-
-                    let actual_crc32 = crc32fast::hash(&resource.data);
-                    if actual_crc32 != crc32 {
-                        warn!(%addr, "<- Invalid resource crc32, expected: 0x{crc32:08X}, got: 0x{actual_crc32:08X}");
-                        return Ok(true);
-                    }
-
-                    info!(%addr, "<- Resource completed: {res_id}, len: {actual_total_len}, crc32: 0x{crc32:08X}");
-
-                    // TODO: The full data looks like to be a zlib-compressed pickle.
-                    // TODO: onCmdResponse for requested SYNC use RES_SUCCESS=0, RES_STREAM=1, RES_CACHE=2 for result_id
-                    //       When RES_STREAM is used, then a resource (header+fragment) is expected with the associated request_id.
-
-                    match serde_pickle::value_from_reader(ZlibDecoder::new(&resource.data[..]), serde_pickle_de_options()) {
-                        Ok(val) => {
-                            
-                            let dump_file = self.shared.dump_dir.join(format!("res_{crc32:08x}.txt"));
-                            info!(%addr, "<- Saving resource to: {}", dump_file.display());
-
-                            let mut dump_writer = File::create(dump_file).unwrap();
-                            write!(dump_writer, "{val}").unwrap();
-
-                        }
-                        Err(e) => {
-
-                            warn!(%addr, "<- Resource: python error: {e}");
-
-                            // FIXME: It appears that the current serde-pickle impl doesn't
-                            // support recursive structures, however the structure that is 
-                            // initially requested with 'CMD_SYNC_DATA' contains some.
-                            // FIXME: The resource that is received by the from the chat
-                            // command contains a "deque" object, which cannot be parsed
-                            // so we get a "unresolved global reference" error.
+                let elt = elt.read_borrowed::<RawElement, _>(&ElementLength::Undefined)?;
+                warn!(%addr, "<- Entity method (unknown selected entity): msg#{} {:?} (request: {:?})", id - id::ENTITY_METHOD.first, elt.element, elt.request_id);
+                return Ok(false);
 
-                            let raw_file = self.shared.dump_dir.join(format!("res_{crc32:08x}.raw"));
-                            info!(%addr, "<- Saving resource to: {}", raw_file.display());
+            }
+            NestedEntityProperty::ID => {
+
+                let nep = elt.read_simple::<NestedEntityProperty>()?;
+                let shared = Arc::clone(&self.shared);
+                #[cfg(feature = "sqlite")]
+                let now = self.now;
+                let Some((entity_id, type_id, state)) = self.selected_entity_state(addr) else {
+                    warn!(%addr, "<- Nested entity property (unknown selected entity state): {:?}", nep.element);
+                    return Ok(false);
+                };
 
-                            let mut raw_writer = File::create(raw_file).unwrap();
-                            std::io::copy(&mut ZlibDecoder::new(&resource.data[..]), &mut raw_writer).unwrap();
+                let change = nep.element.apply(state)?;
+                info!(%addr, direction = "in", entity_id, entity_type = shared.entity_type_name(type_id).unwrap_or_default(), 
+                    property = %change.path, value = ?change.value, "<- Entity property");
+
+                #[cfg(feature = "sqlite")]
+                shared.dump_db(|db| db.insert_property(now, addr, entity_id, shared.entity_type_name(type_id).unwrap_or_default(), 
+                    change.path.to_string().trim_start_matches('.'), &format!("{:#?}", change.value)));
+                shared.tui_event(|| TuiEvent::Call {
+                    addr, 
+                    direction: PacketDirection::In, 
+                    kind: CallKind::Property, 
+                    entity_id, 
+                    entity_type: shared.entity_type_name(type_id).unwrap_or_default().to_string(), 
+                    name: change.path.to_string().trim_start_matches('.').to_string(), 
+                    value: format!("{:?}", change.value),
+                });
 
-                        }
-                    }
+            }
+            SliceEntityProperty::ID => {
+
+                let sep = elt.read_simple::<SliceEntityProperty>()?;
+                let shared = Arc::clone(&self.shared);
+                #[cfg(feature = "sqlite")]
+                let now = self.now;
+                let Some((entity_id, type_id, state)) = self.selected_entity_state(addr) else {
+                    warn!(%addr, "<- Slice entity property (unknown selected entity state): {:?}", sep.element);
+                    return Ok(false);
+                };
 
-                }
+                let change = sep.element.apply(state)?;
+                info!(%addr, direction = "in", entity_id, entity_type = shared.entity_type_name(type_id).unwrap_or_default(), 
+                    property = %change.path, value = ?change.value, "<- Entity property");
+
+                #[cfg(feature = "sqlite")]
+                shared.dump_db(|db| db.insert_property(now, addr, entity_id, shared.entity_type_name(type_id).unwrap_or_default(), 
+                    change.path.to_string().trim_start_matches('.'), &format!("{:#?}", change.value)));
+                shared.tui_event(|| TuiEvent::Call {
+                    addr, 
+                    direction: PacketDirection::In, 
+                    kind: CallKind::Property, 
+                    entity_id, 
+                    entity_type: shared.entity_type_name(type_id).unwrap_or_default().to_string(), 
+                    name: change.path.to_string().trim_start_matches('.').to_string(), 
+                    value: format!("{:?}", change.value),
+                });
 
             }
-            id if id::ENTITY_METHOD.contains(id) => {
-
-                // Account::msg#37 = onClanInfoReceived
-                // Account::msg#39 = showGUI
+            id if id::ENTITY_PROPERTY.contains(id) => {
 
-                if let Some(entity_id) = self.selected_entity_id {
+                let session = self.session(addr);
+                if let Some(entity_id) = session.selected_entity_id {
                     // Unwrap because selected entity should exist!
-                    let entity_type = *self.entities.get(&entity_id).unwrap();
-                    return (entity_type.entity_method)(&mut *self, addr, entity_id, elt);
+                    let type_id = *session.entities.get(&entity_id).unwrap();
+                    return self.dispatch_entity_property(addr, entity_id, type_id, elt);
                 }
 
-                let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
-                warn!(%addr, "<- Entity method (unknown selected entity): msg#{} {:?} (request: {:?})", id - id::ENTITY_METHOD.first, elt.element, elt.request_id);
+                let elt = elt.read_borrowed::<RawElement, _>(&ElementLength::Undefined)?;
+                warn!(%addr, "<- Entity property (unknown selected entity): msg#{} {:?} (request: {:?})", id - id::ENTITY_PROPERTY.first, elt.element, elt.request_id);
                 return Ok(false);
 
             }
-            id if id::ENTITY_PROPERTY.contains(id) => {
-                let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
-                warn!(%addr, "<- Entity property: msg#{} {:?} (request: {:?})", id - id::ENTITY_PROPERTY.first, elt.element, elt.request_id);
-                return Ok(false);
-            }
             id => {
-                let elt = elt.read_simple::<DebugElementUndefined<0>>()?;
+                let elt = elt.read_borrowed::<RawElement, _>(&ElementLength::Undefined)?;
                 error!(%addr, "<- Element #{id} {:?} (request: {:?})", elt.element, elt.request_id);
+                log_sniff(addr, &elt.element.data);
                 return Ok(false);
             }
         }
@@ -526,83 +1589,507 @@ impl BaseThread {
 
     }
 
-    fn read_create_base_player<E>(&mut self, addr: SocketAddr, elt: ElementReader) -> io::Result<bool>
-    where E: Entity + fmt::Debug,
+    /// Select the given entity as the target of subsequent entity methods, only known
+    /// entities can be selected.
+    fn select_entity(&mut self, addr: SocketAddr, entity_id: u32) {
+        let session = self.session(addr);
+        if session.entities.contains_key(&entity_id) {
+            debug!(%addr, "<- Select entity: {entity_id}");
+            session.selected_entity_id = Some(entity_id);
+        } else {
+            debug!(%addr, "<- Select entity: {entity_id} (unknown type)");
+            session.selected_entity_id = None;
+        }
+    }
+
+    /// Return the id, type id and decoded state of the selected entity, if known.
+    fn selected_entity_state(&mut self, addr: SocketAddr) -> Option<(u32, u16, &mut dyn NestedProperty)> {
+        let session = self.sessions.get_mut(&addr)?;
+        let entity_id = session.selected_entity_id?;
+        let type_id = session.entity_store.type_id(entity_id)?;
+        Some((entity_id, type_id, session.entity_store.get_nested_mut(entity_id)?))
+    }
+
+    /// Dump the entity store of each connection for which a snapshot has been requested
+    /// from the console, connections of other workers are left pending.
+    fn poll_snapshots(&mut self) {
+
+        let mut requests = self.shared.snapshot_requests.lock().unwrap();
+        if requests.is_empty() {
+            return;
+        }
+
+        for (&addr, session) in &self.sessions {
+            if requests.remove(&addr) {
+                let store = &session.entity_store;
+                let dump_file = self.shared.dump_dir.join(format!("snapshot_{}_{}.txt", addr.ip(), addr.port()));
+                match File::create(&dump_file).and_then(|mut file| write!(file, "{store:#?}")) {
+                    Ok(()) => info!(%addr, "Entity store snapshot: {} entities, {}", store.len(), dump_file.display()),
+                    Err(e) => warn!(%addr, "Failed to write entity store snapshot to {}: {e}", dump_file.display()),
+                }
+            }
+        }
+
+    }
+
+    /// Read the creation of the base player, the entity type must be known.
+    fn dispatch_create_base_player(&mut self, addr: SocketAddr, type_id: u16, elt: ElementReader) -> io::Result<bool> {
+        let shared = Arc::clone(&self.shared);
+        match &shared.defs {
+            Some(defs) => self.read_runtime_create_base_player(addr, defs.entity(type_id).unwrap(), elt),
+            None => (static_entity_type(type_id).unwrap().create_base_player)(self, addr, elt),
+        }
+    }
+
+    /// Read an entity method called on the client, the entity type must be known.
+    fn dispatch_entity_method(&mut self, addr: SocketAddr, entity_id: u32, type_id: u16, elt: ElementReader) -> io::Result<bool> {
+        let shared = Arc::clone(&self.shared);
+        match &shared.defs {
+            Some(defs) => self.read_runtime_entity_method(addr, entity_id, defs.entity(type_id).unwrap(), elt),
+            None => (static_entity_type(type_id).unwrap().entity_method)(self, addr, entity_id, elt),
+        }
+    }
+
+    /// Read a property update of an entity, the entity type must be known.
+    fn dispatch_entity_property(&mut self, addr: SocketAddr, entity_id: u32, type_id: u16, elt: ElementReader) -> io::Result<bool> {
+        let shared = Arc::clone(&self.shared);
+        match &shared.defs {
+            Some(defs) => self.read_runtime_entity_property(addr, entity_id, defs.entity(type_id).unwrap(), elt),
+            None => (static_entity_type(type_id).unwrap().entity_property)(self, addr, entity_id, elt),
+        }
+    }
+
+    /// Read an entity method called on the base app, the entity type must be known.
+    fn dispatch_base_entity_method(&mut self, addr: SocketAddr, entity_id: u32, type_id: u16, elt: ElementReader) -> io::Result<bool> {
+        let shared = Arc::clone(&self.shared);
+        match &shared.defs {
+            Some(defs) => self.read_runtime_base_entity_method(addr, entity_id, defs.entity(type_id).unwrap(), elt),
+            None => (static_entity_type(type_id).unwrap().base_entity_method)(self, addr, entity_id, elt),
+        }
+    }
+
+    fn read_create_base_player<E>(&mut self, addr: SocketAddr, mut elt: ElementReader) -> io::Result<bool>
+    where E: Entity + NestedProperty + Send + 'static,
     {
 
         use client::element::CreateBasePlayer;
 
+        if let Some(fixtures) = &self.shared.fixtures {
+            let name = static_entity_name::<E>();
+            let res = fixtures.capture::<CreateBasePlayer<E>>(
+                format!("{name}_create_base_player"),
+                format!("client::element::CreateBasePlayer<entity::{name}>"), 
+                &mut elt);
+            check_fixture(addr, res)?;
+        }
+
         let cbp = elt.read_simple::<CreateBasePlayer<E>>()?;
 
         let dump_file = self.shared.dump_dir.join(format!("entity_{}.txt", cbp.element.entity_id));
         let mut dump_writer = File::create(&dump_file)?;
         write!(dump_writer, "{:#?}", cbp.element.entity_data)?;
 
-        info!(%addr, "<- Create base player: ({}) {}", cbp.element.entity_id, dump_file.display());
+        info!(%addr, direction = "in", entity_id = cbp.element.entity_id, entity_type = static_entity_name::<E>(), 
+            dump = %dump_file.display(), "<- Create base player");
+
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_entity(self.now, addr, cbp.element.entity_id, 
+            static_entity_name::<E>(), &format!("{:#?}", cbp.element.entity_data)));
+        self.shared.tui_event(|| TuiEvent::EntityEnter { 
+            addr, 
+            entity_id: cbp.element.entity_id, 
+            entity_type: Some(static_entity_name::<E>().to_string()),
+        });
+
+        let type_id = cbp.element.entity_type_id;
+        self.session(addr).entity_store.insert(cbp.element.entity_id, type_id, *cbp.element.entity_data);
 
         Ok(true)
 
     }
 
-    fn read_entity_method<E>(&mut self, addr: SocketAddr, entity_id: u32, elt: ElementReader) -> io::Result<bool>
+    fn read_entity_method<E>(&mut self, addr: SocketAddr, entity_id: u32, mut elt: ElementReader) -> io::Result<bool>
     where 
         E: Entity,
         E::ClientMethod: fmt::Debug,
     {
         use client::element::EntityMethod;
+        if let Some(fixtures) = &self.shared.fixtures {
+            let name = static_entity_name::<E>();
+            let res = fixtures.capture::<EntityMethod<E::ClientMethod>>(
+                format!("{name}_client_method_{:02X}", elt.id()),
+                format!("client::element::EntityMethod<<entity::{name} as Entity>::ClientMethod>"),
+                &mut elt);
+            check_fixture(addr, res)?;
+        }
         let em = elt.read_simple::<EntityMethod<E::ClientMethod>>()?;
-        info!(%addr, "<- Entity method: ({entity_id}) {:?}", em.element.inner);
+        info!(%addr, direction = "in", entity_id, entity_type = static_entity_name::<E>(), 
+            method = em.element.inner.name(), args = ?em.element.inner, "<- Entity method");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_method(self.now, addr, PacketDirection::In, entity_id, 
+            static_entity_name::<E>(), &format!("{:?}", em.element.inner)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::In, 
+            kind: CallKind::Method, 
+            entity_id, 
+            entity_type: static_entity_name::<E>().to_string(), 
+            name: em.element.inner.name().to_string(), 
+            value: format!("{:?}", em.element.inner),
+        });
+        Ok(true)
+    }
+
+    fn read_entity_property<E>(&mut self, addr: SocketAddr, entity_id: u32, mut elt: ElementReader) -> io::Result<bool>
+    where 
+        E: Properties,
+        E::Property: fmt::Debug,
+    {
+        use client::element::EntityPropertyUpdate;
+        if let Some(fixtures) = &self.shared.fixtures {
+            let name = static_entity_name::<E>();
+            let res = fixtures.capture::<EntityPropertyUpdate<E::Property>>(
+                format!("{name}_property_{:02X}", elt.id()),
+                format!("client::element::EntityPropertyUpdate<<entity::{name} as Properties>::Property>"),
+                &mut elt);
+            check_fixture(addr, res)?;
+        }
+        let epu = elt.read_simple::<EntityPropertyUpdate<E::Property>>()?;
+        info!(%addr, direction = "in", entity_id, entity_type = static_entity_name::<E>(), 
+            property = epu.element.inner.name(), value = ?epu.element.inner, "<- Entity property");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_property(self.now, addr, entity_id, 
+            static_entity_name::<E>(), epu.element.inner.name(), &format!("{:#?}", epu.element.inner)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::In, 
+            kind: CallKind::Property, 
+            entity_id, 
+            entity_type: static_entity_name::<E>().to_string(), 
+            name: epu.element.inner.name().to_string(), 
+            value: format!("{:?}", epu.element.inner),
+        });
+
+        // Also update the decoded state, so that later nested changes are consistent.
+        self.session(addr).entity_store.apply_property(entity_id, &epu.element.inner)?;
+
         Ok(true)
     }
 
-    fn read_base_entity_method<E>(&mut self, addr: SocketAddr, entity_id: u32, elt: ElementReader) -> io::Result<bool>
+    fn read_base_entity_method<E>(&mut self, addr: SocketAddr, entity_id: u32, mut elt: ElementReader) -> io::Result<bool>
     where 
         E: Entity,
         E::BaseMethod: fmt::Debug,
     {
         use base::element::BaseEntityMethod;
+        if let Some(fixtures) = &self.shared.fixtures {
+            let name = static_entity_name::<E>();
+            let res = fixtures.capture::<BaseEntityMethod<E::BaseMethod>>(
+                format!("{name}_base_method_{:02X}", elt.id()),
+                format!("base::element::BaseEntityMethod<<entity::{name} as Entity>::BaseMethod>"),
+                &mut elt);
+            check_fixture(addr, res)?;
+        }
         let em = elt.read_simple::<BaseEntityMethod<E::BaseMethod>>()?;
-        info!(%addr, "-> Base entity method: ({entity_id}) {:?}", em.element.inner);
+        info!(%addr, direction = "out", entity_id, entity_type = static_entity_name::<E>(), 
+            method = em.element.inner.name(), args = ?em.element.inner, "-> Base entity method");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_method(self.now, addr, PacketDirection::Out, entity_id, 
+            static_entity_name::<E>(), &format!("{:?}", em.element.inner)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::Out, 
+            kind: CallKind::Method, 
+            entity_id, 
+            entity_type: static_entity_name::<E>().to_string(), 
+            name: em.element.inner.name().to_string(), 
+            value: format!("{:?}", em.element.inner),
+        });
+        Ok(true)
+    }
+
+    fn read_runtime_create_base_player(&mut self, addr: SocketAddr, entity: &RuntimeEntity, elt: ElementReader) -> io::Result<bool> {
+
+        let cbp = elt.read::<RuntimeCreateBasePlayer, _>(entity)?;
+
+        let dump_file = self.shared.dump_dir.join(format!("entity_{}.txt", cbp.element.entity_id));
+        let mut dump_writer = File::create(&dump_file)?;
+        write!(dump_writer, "{}: {:#?}", entity.name, cbp.element.properties)?;
+        if !cbp.element.components.is_empty() {
+            write!(dump_writer, "\ncomponents: {:#?}", cbp.element.components)?;
+        }
+
+        // Components unknown to the definitions are counted but not decoded.
+        let undecoded_components = cbp.element.entity_components_count as usize - cbp.element.components.len();
+        if undecoded_components != 0 {
+            write!(dump_writer, "\nundecoded components: {undecoded_components}")?;
+        }
+
+        info!(%addr, direction = "in", entity_id = cbp.element.entity_id, entity_type = %entity.name, 
+            entity_type_id = cbp.element.entity_type_id, components = cbp.element.entity_components_count,
+            dump = %dump_file.display(), "<- Create base player");
+
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_entity(self.now, addr, cbp.element.entity_id, 
+            &entity.name, &format!("{:#?}", cbp.element.properties)));
+        self.shared.tui_event(|| TuiEvent::EntityEnter { 
+            addr, 
+            entity_id: cbp.element.entity_id, 
+            entity_type: Some(entity.name.clone()),
+        });
+
+        Ok(true)
+
+    }
+
+    fn read_runtime_entity_method(&mut self, addr: SocketAddr, entity_id: u32, entity: &RuntimeEntity, elt: ElementReader) -> io::Result<bool> {
+        let em = elt.read::<RuntimeMethodCall, _>(&entity.client_methods)?;
+        // Unwrap because the exposed id has been checked when reading.
+        let method = entity.client_methods.get(em.element.exposed_id).unwrap();
+        info!(%addr, direction = "in", entity_id, entity_type = %entity.name, 
+            method = %method.name, args = ?em.element.args, "<- Entity method");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_method(self.now, addr, PacketDirection::In, entity_id, 
+            &entity.name, &format!("{}{:?}", method.name, em.element.args)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::In, 
+            kind: CallKind::Method, 
+            entity_id, 
+            entity_type: entity.name.clone(), 
+            name: method.name.clone(), 
+            value: format!("{:?}", em.element.args),
+        });
+        Ok(true)
+    }
+
+    fn read_runtime_entity_property(&mut self, addr: SocketAddr, entity_id: u32, entity: &RuntimeEntity, elt: ElementReader) -> io::Result<bool> {
+        let epu = elt.read::<RuntimePropertyUpdate, _>(entity)?;
+        // Index is valid because the exposed id has been checked when reading.
+        let (name, _) = &entity.properties[epu.element.exposed_id as usize];
+        info!(%addr, direction = "in", entity_id, entity_type = %entity.name, 
+            property = %name, value = ?epu.element.value, "<- Entity property");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_property(self.now, addr, entity_id, 
+            &entity.name, name, &format!("{:#?}", epu.element.value)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::In, 
+            kind: CallKind::Property, 
+            entity_id, 
+            entity_type: entity.name.clone(), 
+            name: name.clone(), 
+            value: format!("{:?}", epu.element.value),
+        });
+        Ok(true)
+    }
+
+    fn read_runtime_base_entity_method(&mut self, addr: SocketAddr, entity_id: u32, entity: &RuntimeEntity, elt: ElementReader) -> io::Result<bool> {
+        let em = elt.read::<RuntimeMethodCall, _>(&entity.base_methods)?;
+        // Unwrap because the exposed id has been checked when reading.
+        let method = entity.base_methods.get(em.element.exposed_id).unwrap();
+        info!(%addr, direction = "out", entity_id, entity_type = %entity.name, 
+            method = %method.name, args = ?em.element.args, "-> Base entity method");
+        #[cfg(feature = "sqlite")]
+        self.shared.dump_db(|db| db.insert_method(self.now, addr, PacketDirection::Out, entity_id, 
+            &entity.name, &format!("{}{:?}", method.name, em.element.args)));
+        self.shared.tui_event(|| TuiEvent::Call {
+            addr, 
+            direction: PacketDirection::Out, 
+            kind: CallKind::Method, 
+            entity_id, 
+            entity_type: entity.name.clone(), 
+            name: method.name.clone(), 
+            value: format!("{:?}", em.element.args),
+        });
         Ok(true)
     }
 
 }
 
+/// Log the best guesses of the sniffer for the data of an unknown element.
+fn log_sniff(addr: SocketAddr, data: &[u8]) {
+    for guess in sniff::sniff(data) {
+        info!(%addr, "   Guess: {guess}");
+    }
+}
+
 /// Represent an entity type and its associated static functions.
 #[derive(Debug)]
 struct EntityType {
     create_base_player: fn(&mut BaseThread, SocketAddr, ElementReader) -> io::Result<bool>,
     entity_method: fn(&mut BaseThread, SocketAddr, u32, ElementReader) -> io::Result<bool>,
+    entity_property: fn(&mut BaseThread, SocketAddr, u32, ElementReader) -> io::Result<bool>,
     base_entity_method: fn(&mut BaseThread, SocketAddr, u32, ElementReader) -> io::Result<bool>,
 }
 
 impl EntityType {
 
-    const fn new<E>() -> Self
+    fn new<E>() -> Self
     where
-        E: Entity + fmt::Debug,
+        E: Entity + Properties + NestedProperty + Send + 'static,
         E::ClientMethod: fmt::Debug,
         E::BaseMethod: fmt::Debug,
+        E::Property: fmt::Debug,
     {
         Self {
             create_base_player: BaseThread::read_create_base_player::<E>,
             entity_method: BaseThread::read_entity_method::<E>,
+            entity_property: BaseThread::read_entity_property::<E>,
             base_entity_method: BaseThread::read_base_entity_method::<E>,
         }
     }
 
 }
 
-const ENTITY_TYPES: &[EntityType] = &[
-    EntityType::new::<gen::entity::Account>(),
-    EntityType::new::<gen::entity::Avatar>(),
-    EntityType::new::<gen::entity::ArenaInfo>(),
-    EntityType::new::<gen::entity::ClientSelectableObject>(),
-    EntityType::new::<gen::entity::HangarVehicle>(),
-    EntityType::new::<gen::entity::Vehicle>(),
-    EntityType::new::<gen::entity::AreaDestructibles>(),
-    EntityType::new::<gen::entity::OfflineEntity>(),
-    EntityType::new::<gen::entity::Flock>(),
-    EntityType::new::<gen::entity::FlockExotic>(),
-    EntityType::new::<gen::entity::Login>(),
-];
+/// Visitor returning the entity type of the visited generated entity.
+struct EntityTypeBuilder;
+
+impl EntityTypeVisitor for EntityTypeBuilder {
+
+    type Output = EntityType;
+
+    fn visit<E>(self) -> EntityType
+    where
+        E: Entity + Properties + NestedProperty + Send + 'static,
+        E::ClientMethod: fmt::Debug,
+        E::BaseMethod: fmt::Debug,
+        E::CellMethod: fmt::Debug,
+        E::Property: fmt::Debug,
+    {
+        EntityType::new::<E>()
+    }
+
+}
+
+/// Get the name of a generated entity type, which is the last segment of its path.
+fn static_entity_name<E>() -> &'static str {
+    let path = std::any::type_name::<E>();
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Check the result of a fixture capture, only the decode errors are returned because
+/// failing to save a fixture doesn't prevent decoding the element, it's only logged.
+fn check_fixture(addr: SocketAddr, res: Result<(), FixtureError>) -> io::Result<()> {
+    match res {
+        Ok(()) => Ok(()),
+        Err(FixtureError::Decode(e)) => Err(e),
+        Err(FixtureError::Io(e)) => {
+            warn!(%addr, "Failed to save fixture: {e}");
+            Ok(())
+        }
+    }
+}
+
+/// Get the generated entity type from its type id.
+fn static_entity_type(type_id: u16) -> Option<EntityType> {
+    gen::entity::Generic::visit_type(type_id, EntityTypeBuilder)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn login_request_event() -> login::proxy::LoginRequestEvent {
+
+        let request = login::element::LoginRequest {
+            protocol: 1234,
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            blowfish_key: vec![0x42; 16],
+            context: "ctx".to_string(),
+            digest: Some([0x24; 16]),
+            nonce: 0x11223344,
+        };
+
+        // Unknown trailing bytes must be kept, they are what the dump is for.
+        let mut data = Vec::new();
+        Codec::write(&request, &mut data, &()).unwrap();
+        data.drain(..5);
+        data.extend_from_slice(&[0xAB, 0xCD]);
+
+        login::proxy::LoginRequestEvent {
+            addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 1234)),
+            params: login::element::LoginParams { protocol: request.protocol, data },
+            request,
+        }
+
+    }
+
+    #[test]
+    fn login_params_dump() {
+
+        let event = login_request_event();
+        assert_eq!(event.params.decode().unwrap().password, "secret");
+
+        let redacted = redact_login_params(&event).unwrap();
+        assert_eq!(redacted.len(), event.params.data.len());
+        assert!(redacted.ends_with(&[0xAB, 0xCD]));
+        assert!(!redacted.windows(6).any(|w| w == b"secret"));
+        assert!(!redacted.windows(16).any(|w| w == [0x42; 16] || w == [0x24; 16]));
+
+        let decoded = login::element::LoginParams { protocol: 1234, data: redacted }.decode().unwrap();
+        assert_eq!(decoded.username, "user");
+        assert_eq!(decoded.password, "\0".repeat(6));
+        assert_eq!(decoded.context, "ctx");
+        assert_eq!(decoded.nonce, 0x11223344);
+
+        let mut output = Vec::new();
+        write_login_params(&mut output, &event, LoginParamsDump::Redacted).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("password: <redacted, 6 bytes>"));
+        assert!(!output.contains("secret") && !output.contains("42, 42"));
+
+        let mut output = Vec::new();
+        write_login_params(&mut output, &event, LoginParamsDump::Full).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("password: \"secret\""));
+        assert!(output.contains(&format!("params: {} bytes", event.params.data.len())));
+
+    }
+
+    #[test]
+    fn disconnect() {
+
+        let shared = Arc::new(Shared {
+            dump_dir: std::env::temp_dir().join(format!("wgtk-proxy-dump-{}", std::process::id())),
+            pending_clients: Mutex::new(HashMap::new()),
+            injectors: Mutex::new(HashMap::new()),
+            request_trackers: Mutex::new(HashMap::new()),
+            profile_interval: None,
+            metrics_interval: None,
+            packet_ring_len: 0,
+            defs: None,
+            fixtures: None,
+            resource_spill_dir: None,
+            recorder: None,
+            snapshot_requests: Mutex::new(HashSet::new()),
+            #[cfg(feature = "sqlite")]
+            database: None,
+            tui: None,
+        });
+
+        let addr0 = SocketAddr::from((Ipv4Addr::LOCALHOST, 1000));
+        let addr1 = SocketAddr::from((Ipv4Addr::LOCALHOST, 1001));
+
+        let mut thread = BaseThread::new(Arc::clone(&shared));
+        for addr in [addr0, addr1] {
+            thread.session(addr).player_entity_id = Some(1);
+            shared.with_request_tracker(addr, |_| ());
+        }
+
+        thread.disconnect(addr0);
+        assert!(!thread.sessions.contains_key(&addr0));
+        assert!(!shared.request_trackers.lock().unwrap().contains_key(&addr0));
+
+        // Other sessions are kept.
+        assert_eq!(thread.sessions[&addr1].player_entity_id, Some(1));
+        assert!(shared.request_trackers.lock().unwrap().contains_key(&addr1));
+
+    }
+
+}
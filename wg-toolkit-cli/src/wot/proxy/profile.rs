@@ -0,0 +1,149 @@
+//! Profiler of the decoding time and payload size of elements.
+
+use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+use std::num::TryFromIntError;
+
+use tracing::{info, warn};
+
+use wgtk::net::app::proxy::PacketDirection;
+
+
+/// A profiler recording the decoding time and payload size of each element, keyed by
+/// element id and entity type, and periodically reporting the collected statistics.
+#[derive(Debug)]
+pub struct Profiler {
+    /// Statistics for each profiled key.
+    stats: HashMap<ProfileKey, ProfileStats>,
+    /// Interval between two reports.
+    interval: Duration,
+    /// The time of the last report, or creation of the profiler.
+    last_report: Instant,
+}
+
+/// Identify a kind of element being profiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProfileKey {
+    /// Direction of the element.
+    pub direction: PacketDirection,
+    /// The element id.
+    pub id: u8,
+    /// For entity methods, the type id of the entity the method is called on.
+    pub entity_type: Option<u16>,
+}
+
+/// Statistics collected for a kind of element.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileStats {
+    /// Number of elements decoded.
+    pub count: u64,
+    /// Total time spent decoding the elements.
+    pub total_time: Duration,
+    /// Longest time spent decoding a single element.
+    pub max_time: Duration,
+    /// Total length of the elements' payload.
+    pub total_len: u64,
+}
+
+impl Profiler {
+
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            stats: HashMap::new(),
+            interval,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Record the decoding of an element.
+    pub fn record(&mut self, key: ProfileKey, time: Duration, len: usize) {
+        let stats = self.stats.entry(key).or_default();
+        stats.count += 1;
+        stats.total_time += time;
+        stats.max_time = stats.max_time.max(time);
+        stats.total_len += len as u64;
+    }
+
+    /// Report the statistics if the report interval has elapsed since last one.
+    pub fn poll_report<'a>(&mut self, entity_type_name: impl Fn(u16) -> Option<&'a str>) {
+        if self.last_report.elapsed() >= self.interval {
+            self.report(entity_type_name);
+        }
+    }
+
+    /// Report the statistics collected since the profiler's creation, the elements
+    /// are sorted by descending total decoding time. The given function is used to
+    /// get the name of entity types.
+    pub fn report<'a>(&mut self, entity_type_name: impl Fn(u16) -> Option<&'a str>) {
+
+        self.last_report = Instant::now();
+
+        let mut stats = self.stats.iter().collect::<Vec<_>>();
+        stats.sort_by_key(|(_, stat)| Reverse(stat.total_time));
+
+        let total_time = stats.iter().map(|(_, s)| s.total_time).sum::<Duration>();
+        info!("Profiler report: {} kinds of elements, total decode time: {total_time:?}", stats.len());
+
+        for (key, stats) in stats {
+
+            let dir = match key.direction {
+                PacketDirection::Out => "->",
+                PacketDirection::In => "<-",
+            };
+
+            let mean_time = match stats.mean_time() {
+                Ok(mean_time) => mean_time,
+                Err(e) => {
+                    warn!("{dir} Element #{}: count: {}, can't compute mean time: {e}", key.id, stats.count);
+                    continue;
+                }
+            };
+
+            if let Some(entity_type) = key.entity_type.and_then(&entity_type_name) {
+                info!("{dir} {entity_type} element #{}: count: {}, total: {:?}, mean: {mean_time:?}, max: {:?}, len: {}",
+                    key.id, stats.count, stats.total_time, stats.max_time, stats.total_len);
+            } else {
+                info!("{dir} Element #{}: count: {}, total: {:?}, mean: {mean_time:?}, max: {:?}, len: {}",
+                    key.id, stats.count, stats.total_time, stats.max_time, stats.total_len);
+            }
+
+        }
+
+    }
+
+}
+
+impl ProfileStats {
+
+    /// Return the mean time spent decoding an element, zero if no element has been 
+    /// decoded, an error is returned if the count doesn't fit the duration divisor.
+    pub fn mean_time(&self) -> Result<Duration, TryFromIntError> {
+        Ok(self.total_time / u32::try_from(self.count.max(1))?)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn mean_time() {
+
+        let mut stats = ProfileStats::default();
+        assert_eq!(stats.mean_time().unwrap(), Duration::ZERO);
+
+        stats.count = 4;
+        stats.total_time = Duration::from_millis(10);
+        assert_eq!(stats.mean_time().unwrap(), Duration::from_micros(2500));
+
+        // The count would have been truncated to 1 when cast.
+        stats.count = u32::MAX as u64 + 2;
+        assert!(stats.mean_time().is_err());
+
+    }
+
+}
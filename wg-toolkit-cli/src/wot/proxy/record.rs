@@ -0,0 +1,59 @@
+//! Recording of the decrypted packets of all peers into a capture.
+
+use std::io::{self, BufWriter};
+use std::time::{Duration, Instant};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::fs::File;
+
+use wgtk::net::app::proxy::PacketDirection;
+use wgtk::net::capture::{CaptureApp, CaptureWriter};
+use wgtk::net::bundle::Bundle;
+
+
+/// Interval between two flushes of the capture file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A recorder of the decrypted packets of each completed bundle, shared by the login
+/// app proxy and all base app proxy workers.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: CaptureWriter<BufWriter<File>>,
+    /// The time of the start of the capture, record times are relative to it.
+    start: Instant,
+    /// The last time the capture file has been flushed.
+    last_flush: Instant,
+}
+
+impl Recorder {
+
+    /// Create the capture file at the given path, the capture starts now.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let now = Instant::now();
+        Ok(Self {
+            writer: CaptureWriter::new(BufWriter::new(File::create(path)?))?,
+            start: now,
+            last_flush: now,
+        })
+    }
+
+    /// Record all packets of the given bundle of a client, received from the given app
+    /// at the given time. The capture is regularly flushed so that it can be read even
+    /// if the proxy is killed.
+    pub fn record(&mut self, app: CaptureApp, addr: SocketAddr, direction: PacketDirection, bundle: &Bundle, time: Instant) -> io::Result<()> {
+
+        let time_since_start = time.saturating_duration_since(self.start);
+        for packet in bundle.iter() {
+            self.writer.write_packet(time_since_start, app, addr, direction, packet)?;
+        }
+
+        if time.saturating_duration_since(self.last_flush) >= FLUSH_INTERVAL {
+            self.last_flush = time;
+            self.writer.flush()?;
+        }
+
+        Ok(())
+
+    }
+
+}
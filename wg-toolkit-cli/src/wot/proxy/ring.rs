@@ -0,0 +1,175 @@
+//! Ring buffer of the recent packets of each peer, dumped when a decode error occurs.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use std::path::Path;
+use std::fs::File;
+
+use wgtk::net::app::proxy::PacketDirection;
+use wgtk::net::packet::Packet;
+
+
+/// Number of bytes on each line of the hexdump.
+const HEXDUMP_LINE_LEN: usize = 16;
+
+/// Time after which the packets of a peer are forgotten if no other packet has been
+/// pushed for it, this is also the minimum interval between two prunings.
+const PEER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A ring buffer keeping the last packets received for each peer, so that they can be
+/// dumped after a decode error in order to diagnose it.
+#[derive(Debug)]
+pub struct PacketRing {
+    /// Maximum number of packets kept for each peer.
+    cap: usize,
+    /// The recent packets of each peer, the most recent last.
+    peers: HashMap<SocketAddr, VecDeque<RingPacket>>,
+    /// The time of the last pruning of timed out peers.
+    last_prune: Option<Instant>,
+}
+
+/// A packet kept in the ring buffer.
+#[derive(Debug)]
+struct RingPacket {
+    /// The time the packet has been received.
+    time: Instant,
+    /// Direction of the packet.
+    direction: PacketDirection,
+    /// The decrypted packet.
+    packet: Packet,
+}
+
+impl PacketRing {
+
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            peers: HashMap::new(),
+            last_prune: None,
+        }
+    }
+
+    /// Push a packet of the given peer, the oldest packet of this peer is forgotten if
+    /// the capacity is reached.
+    pub fn push(&mut self, addr: SocketAddr, direction: PacketDirection, packet: &Packet, time: Instant) {
+
+        let packets = self.peers.entry(addr).or_default();
+        if packets.len() >= self.cap {
+            packets.pop_front();
+        }
+
+        packets.push_back(RingPacket {
+            time,
+            direction,
+            packet: packet.clone(),
+        });
+
+        self.prune(time);
+
+    }
+
+    /// Forget all packets of the given peer, when it has disconnected.
+    pub fn remove(&mut self, addr: SocketAddr) {
+        self.peers.remove(&addr);
+    }
+
+    /// Forget the packets of peers that timed out, at most once per timeout.
+    fn prune(&mut self, now: Instant) {
+
+        if self.last_prune.is_some_and(|last_prune| now.saturating_duration_since(last_prune) < PEER_TIMEOUT) {
+            return;
+        }
+
+        self.last_prune = Some(now);
+        self.peers.retain(|_, packets| {
+            packets.back().is_some_and(|p| now.saturating_duration_since(p.time) < PEER_TIMEOUT)
+        });
+
+    }
+
+    /// Dump all recent packets of the given peer to a file at the given path, with a
+    /// header for each packet and its hexdump.
+    pub fn dump(&self, addr: SocketAddr, path: &Path) -> io::Result<()> {
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        let packets = self.peers.get(&addr);
+
+        writeln!(writer, "Peer: {addr}, packets: {}", packets.map_or(0, VecDeque::len))?;
+
+        let first_time = packets.and_then(VecDeque::front).map(|p| p.time);
+        for (index, ring_packet) in packets.into_iter().flatten().enumerate() {
+
+            let dir = match ring_packet.direction {
+                PacketDirection::Out => "->",
+                PacketDirection::In => "<-",
+            };
+
+            // Unwrap because there is at least this packet.
+            let time = ring_packet.time.saturating_duration_since(first_time.unwrap());
+            writeln!(writer)?;
+            writeln!(writer, "#{index} {dir} +{time:?} {:?}", ring_packet.packet)?;
+
+            for (line_index, line) in ring_packet.packet.slice().chunks(HEXDUMP_LINE_LEN).enumerate() {
+
+                write!(writer, "{:08X} ", line_index * HEXDUMP_LINE_LEN)?;
+
+                for i in 0..HEXDUMP_LINE_LEN {
+                    match line.get(i) {
+                        Some(byte) => write!(writer, " {byte:02X}")?,
+                        None => write!(writer, "   ")?,
+                    }
+                }
+
+                let ascii = line.iter()
+                    .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+                    .collect::<String>();
+
+                writeln!(writer, "  |{ascii}|")?;
+
+            }
+
+        }
+
+        writer.flush()
+
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn prune() {
+
+        let addr0 = SocketAddr::from((Ipv4Addr::LOCALHOST, 1000));
+        let addr1 = SocketAddr::from((Ipv4Addr::LOCALHOST, 1001));
+        let packet = Packet::new();
+        let start = Instant::now();
+
+        let mut ring = PacketRing::new(2);
+        ring.push(addr0, PacketDirection::Out, &packet, start);
+        ring.push(addr1, PacketDirection::Out, &packet, start);
+        ring.push(addr1, PacketDirection::In, &packet, start + PEER_TIMEOUT / 2);
+        ring.push(addr1, PacketDirection::In, &packet, start + PEER_TIMEOUT / 2);
+        assert_eq!(ring.peers.len(), 2);
+        assert_eq!(ring.peers[&addr1].len(), 2);
+
+        // Only the first peer timed out.
+        ring.push(addr1, PacketDirection::In, &packet, start + PEER_TIMEOUT);
+        assert!(!ring.peers.contains_key(&addr0));
+        assert!(ring.peers.contains_key(&addr1));
+
+        ring.remove(addr1);
+        assert!(ring.peers.is_empty());
+
+    }
+
+}
@@ -0,0 +1,448 @@
+//! Terminal user interface of the proxy, replacing the log and the console, showing
+//! the proxied peers, the entities of the selected peer and a scrolling pane of the
+//! decoded entity methods and properties, which can be filtered.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::sync::Arc;
+use std::io;
+
+use crossbeam_channel::Receiver;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use wgtk::net::app::proxy::PacketDirection;
+
+use super::Shared;
+
+
+/// Maximum number of events waiting to be displayed, events are dropped if the user
+/// interface doesn't keep up, so that decoding is never delayed.
+pub const EVENT_QUEUE_CAP: usize = 16384;
+/// Maximum number of calls kept in the calls pane, the oldest ones are dropped.
+const CALLS_CAP: usize = 10000;
+/// Interval between two redraws when no key is pressed.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+/// Number of rows scrolled by page up and page down.
+const PAGE_LEN: usize = 20;
+
+
+/// An event sent by the decode workers to the user interface.
+#[derive(Debug)]
+pub enum TuiEvent {
+    /// An entity has been created or entered the AoI of the peer, its type is known
+    /// only for created entities.
+    EntityEnter {
+        addr: SocketAddr,
+        entity_id: u32,
+        entity_type: Option<String>,
+    },
+    /// An entity has left the AoI of the peer.
+    EntityLeave {
+        addr: SocketAddr,
+        entity_id: u32,
+    },
+    /// All entities of the peer have been reset, except the given one if any.
+    EntitiesReset {
+        addr: SocketAddr,
+        keep: Option<u32>,
+    },
+    /// An entity method has been called, or a property has been updated.
+    Call {
+        addr: SocketAddr,
+        direction: PacketDirection,
+        kind: CallKind,
+        entity_id: u32,
+        entity_type: String,
+        /// Name of the method or path of the property.
+        name: String,
+        /// Debug representation of the arguments or the property value.
+        value: String,
+    },
+}
+
+/// Kind of a call event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Method,
+    Property,
+}
+
+/// The pane having the keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Peers,
+    Entities,
+}
+
+/// A peer and its known entities, with their type if known.
+#[derive(Debug)]
+struct Peer {
+    addr: SocketAddr,
+    entities: BTreeMap<u32, Option<String>>,
+}
+
+/// A call displayed in the calls pane.
+#[derive(Debug)]
+struct Call {
+    addr: SocketAddr,
+    direction: PacketDirection,
+    kind: CallKind,
+    entity_id: u32,
+    entity_type: String,
+    name: String,
+    value: String,
+}
+
+/// State of the user interface.
+struct Tui {
+    shared: Arc<Shared>,
+    receiver: Receiver<TuiEvent>,
+    /// Known peers, in the order they have been seen.
+    peers: Vec<Peer>,
+    calls: VecDeque<Call>,
+    focus: Focus,
+    peers_state: ListState,
+    entities_state: ListState,
+    /// If set, only the calls of this entity of the selected peer are displayed.
+    entity_filter: Option<u32>,
+    /// Only the calls with an entity type or name containing this text are displayed.
+    filter: String,
+    /// True while the text filter is being edited.
+    editing: bool,
+    /// Number of rows scrolled up from the most recent call, zero to follow.
+    scroll: usize,
+}
+
+/// Run the user interface on the events of the given receiver until the user quits.
+pub fn run(shared: Arc<Shared>, receiver: Receiver<TuiEvent>) -> io::Result<()> {
+
+    let mut tui = Tui {
+        shared,
+        receiver,
+        peers: Vec::new(),
+        calls: VecDeque::new(),
+        focus: Focus::Peers,
+        peers_state: ListState::default(),
+        entities_state: ListState::default(),
+        entity_filter: None,
+        filter: String::new(),
+        editing: false,
+        scroll: 0,
+    };
+
+    let mut terminal = ratatui::init();
+    let res = tui.run(&mut terminal);
+    ratatui::restore();
+    res
+
+}
+
+impl Tui {
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+
+            self.receive_events();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(REFRESH_INTERVAL)? {
+                continue;
+            }
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if self.editing {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.editing = false,
+                    KeyCode::Backspace => { self.filter.pop(); }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('/') => self.editing = true,
+                KeyCode::Tab => {
+                    self.focus = match self.focus {
+                        Focus::Peers => Focus::Entities,
+                        Focus::Entities => Focus::Peers,
+                    };
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Enter => self.toggle_entity_filter(),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_add(PAGE_LEN),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(PAGE_LEN),
+                KeyCode::End => self.scroll = 0,
+                _ => {}
+            }
+
+        }
+    }
+
+    /// Receive all pending events from the decode workers.
+    fn receive_events(&mut self) {
+        while let Ok(event) = self.receiver.try_recv() {
+            self.handle_event(event);
+        }
+    }
+
+    fn handle_event(&mut self, event: TuiEvent) {
+        match event {
+            TuiEvent::EntityEnter { addr, entity_id, entity_type } => {
+                let entity = self.peer_mut(addr).entities.entry(entity_id).or_default();
+                if entity_type.is_some() {
+                    *entity = entity_type;
+                }
+            }
+            TuiEvent::EntityLeave { addr, entity_id } => {
+                self.peer_mut(addr).entities.remove(&entity_id);
+            }
+            TuiEvent::EntitiesReset { addr, keep } => {
+                self.peer_mut(addr).entities.retain(|&entity_id, _| Some(entity_id) == keep);
+            }
+            TuiEvent::Call { addr, direction, kind, entity_id, entity_type, name, value } => {
+
+                // Entities that are called are known to exist, with their type.
+                self.peer_mut(addr).entities.insert(entity_id, Some(entity_type.clone()));
+
+                if self.calls.len() == CALLS_CAP {
+                    self.calls.pop_front();
+                }
+
+                self.calls.push_back(Call { addr, direction, kind, entity_id, entity_type, name, value });
+
+            }
+        }
+    }
+
+    /// Get the given peer, adding it if not yet known, the first peer is selected.
+    fn peer_mut(&mut self, addr: SocketAddr) -> &mut Peer {
+
+        let index = match self.peers.iter().position(|peer| peer.addr == addr) {
+            Some(index) => index,
+            None => {
+                self.peers.push(Peer { addr, entities: BTreeMap::new() });
+                if self.peers_state.selected().is_none() {
+                    self.peers_state.select(Some(0));
+                }
+                self.peers.len() - 1
+            }
+        };
+
+        &mut self.peers[index]
+
+    }
+
+    /// Return the selected peer, if any.
+    fn selected_peer(&self) -> Option<&Peer> {
+        self.peers_state.selected().and_then(|index| self.peers.get(index))
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+
+        let (state, len) = match self.focus {
+            Focus::Peers => (&mut self.peers_state, self.peers.len()),
+            Focus::Entities => {
+                let len = self.peers_state.selected()
+                    .and_then(|index| self.peers.get(index))
+                    .map_or(0, |peer| peer.entities.len());
+                (&mut self.entities_state, len)
+            }
+        };
+
+        if len == 0 {
+            state.select(None);
+            return;
+        }
+
+        let row = state.selected().unwrap_or(0).saturating_add_signed(delta).min(len - 1);
+        state.select(Some(row));
+
+        // The entities of another peer are not the same.
+        if self.focus == Focus::Peers {
+            self.entities_state.select(None);
+            self.entity_filter = None;
+        }
+
+    }
+
+    /// Filter the calls by the selected entity, or remove the filter if that entity
+    /// is already filtered.
+    fn toggle_entity_filter(&mut self) {
+
+        if self.focus != Focus::Entities {
+            return;
+        }
+
+        let entity_id = self.entities_state.selected()
+            .zip(self.selected_peer())
+            .and_then(|(row, peer)| peer.entities.keys().nth(row))
+            .copied();
+
+        self.entity_filter = if self.entity_filter == entity_id { None } else { entity_id };
+        self.scroll = 0;
+
+    }
+
+    /// Return true if the given call should be displayed with the current filters.
+    fn is_call_visible(&self, call: &Call, peer_addr: Option<SocketAddr>) -> bool {
+
+        if peer_addr.is_some_and(|addr| addr != call.addr) {
+            return false;
+        }
+
+        if self.entity_filter.is_some_and(|entity_id| entity_id != call.entity_id) {
+            return false;
+        }
+
+        if self.filter.is_empty() {
+            return true;
+        }
+
+        let filter = self.filter.to_lowercase();
+        call.entity_type.to_lowercase().contains(&filter) || call.name.to_lowercase().contains(&filter)
+
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+
+        let [main_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let [side_area, calls_area] = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .areas(main_area);
+
+        let [peers_area, entities_area] = Layout::vertical([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .areas(side_area);
+
+        self.draw_peers(frame, peers_area);
+        self.draw_entities(frame, entities_area);
+        self.draw_calls(frame, calls_area);
+
+        let help = if self.editing {
+            Line::from(vec![Span::raw(" Filter: "), Span::raw(&self.filter), Span::raw("_")])
+        } else {
+            Line::from(" q: quit, tab: switch pane, ↑↓: move, enter: filter entity, /: filter name, pgup/pgdn/end: scroll").dim()
+        };
+
+        frame.render_widget(help, help_area);
+
+    }
+
+    fn focused_block(&self, focus: Focus, title: String) -> Block<'static> {
+        let block = Block::bordered().title(title);
+        if self.focus == focus {
+            block.border_style(Style::new().fg(Color::Yellow))
+        } else {
+            block
+        }
+    }
+
+    fn draw_peers(&mut self, frame: &mut Frame, area: Rect) {
+
+        let injectors = self.shared.injectors.lock().unwrap();
+        let items = self.peers.iter().map(|peer| {
+            // Peers are only connected while they can be injected to.
+            let line = format!("{} ({} entities)", peer.addr, peer.entities.len());
+            if injectors.contains_key(&peer.addr) {
+                ListItem::new(line)
+            } else {
+                ListItem::new(line).dim()
+            }
+        }).collect::<Vec<_>>();
+        drop(injectors);
+
+        let list = List::new(items)
+            .block(self.focused_block(Focus::Peers, format!(" Peers ({}) ", self.peers.len())))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, area, &mut self.peers_state);
+
+    }
+
+    fn draw_entities(&mut self, frame: &mut Frame, area: Rect) {
+
+        let items = self.selected_peer()
+            .map(|peer| peer.entities.iter().map(|(&entity_id, entity_type)| {
+                let line = format!("{entity_id} {}", entity_type.as_deref().unwrap_or("?"));
+                if self.entity_filter == Some(entity_id) {
+                    ListItem::new(line).bold()
+                } else {
+                    ListItem::new(line)
+                }
+            }).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let title = format!(" Entities ({}) ", items.len());
+        let list = List::new(items)
+            .block(self.focused_block(Focus::Entities, title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, area, &mut self.entities_state);
+
+    }
+
+    fn draw_calls(&mut self, frame: &mut Frame, area: Rect) {
+
+        let peer_addr = self.selected_peer().map(|peer| peer.addr);
+        let visible = self.calls.iter()
+            .filter(|call| self.is_call_visible(call, peer_addr))
+            .collect::<Vec<_>>();
+
+        // Scroll is clamped so that the first page is still full.
+        let height = area.height.saturating_sub(2) as usize;
+        self.scroll = self.scroll.min(visible.len().saturating_sub(height));
+        let end = visible.len() - self.scroll;
+        let start = end.saturating_sub(height);
+
+        let lines = visible[start..end].iter().map(|call| {
+
+            let (arrow, color) = match (call.direction, call.kind) {
+                (PacketDirection::In, CallKind::Method) => ("<-", Color::Cyan),
+                (PacketDirection::Out, CallKind::Method) => ("->", Color::Green),
+                (_, CallKind::Property) => ("<-", Color::Magenta),
+            };
+
+            Line::from(vec![
+                Span::raw(format!("{arrow} ")).fg(color),
+                Span::raw(format!("({}) ", call.entity_id)).dim(),
+                Span::raw(format!("{} ", call.entity_type)),
+                Span::raw(format!("{} ", call.name)).fg(color),
+                Span::raw(&call.value[..]),
+            ])
+
+        }).collect::<Vec<_>>();
+
+        let mut title = format!(" Calls ({}", visible.len());
+        if let Some(entity_id) = self.entity_filter {
+            title.push_str(&format!(", entity {entity_id}"));
+        }
+        if !self.filter.is_empty() {
+            title.push_str(&format!(", filter '{}'", self.filter));
+        }
+        if self.scroll != 0 {
+            title.push_str(&format!(", scrolled {}", self.scroll));
+        }
+        title.push_str(") ");
+
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+
+    }
+
+}
@@ -0,0 +1,440 @@
+//! Conversion and anonymization of replays.
+
+use std::io::{BufReader, BufWriter};
+use std::net::{SocketAddr, SocketAddrV4};
+use std::collections::HashMap;
+use std::path::Path;
+use std::fs::File;
+
+use serde_json::{Map, Value};
+
+use wgtk::util::pcap::{PcapWriter, UdpDatagram};
+use wgtk::replay::{self, Replay};
+
+use crate::CliResult;
+
+
+/// Keys of JSON values that are always player names.
+const NAME_KEYS: &[&str] = &["playerName", "realName"];
+/// Keys of JSON values that are account or clan identifiers.
+const ID_KEYS: &[&str] = &["playerID", "accountDBID", "clanDBID"];
+/// Key of the clan tag, its presence in an object also means that its 'name' is the
+/// name of a player.
+const CLAN_KEY: &str = "clanAbbrev";
+
+/// Convert the replay at the given path to a pcapng capture, each packet is written
+/// as a datagram from the server to the client.
+pub fn run_to_pcap(
+    replay_path: &Path,
+    pcap_path: &Path,
+    server_addr: SocketAddrV4,
+    client_addr: SocketAddrV4,
+) -> CliResult<()> {
+
+    let replay = read_replay(replay_path)?;
+
+    let pcap_file = File::create(pcap_path)
+        .map_err(|e| format!("Failed to create capture at {}: {e}", pcap_path.display()))?;
+
+    let mut writer = PcapWriter::new(BufWriter::new(pcap_file))
+        .map_err(|e| format!("Failed to write capture at {}: {e}", pcap_path.display()))?;
+
+    let mut count = 0usize;
+    for packet in replay.packets() {
+
+        writer.write_datagram(&UdpDatagram {
+            time: packet.time(),
+            src: SocketAddr::V4(server_addr),
+            dst: SocketAddr::V4(client_addr),
+            data: packet.raw.to_vec(),
+        }).map_err(|e| format!("Failed to write capture at {}: {e}", pcap_path.display()))?;
+
+        count += 1;
+
+    }
+
+    writer.into_inner()
+        .map_err(|e| format!("Failed to write capture at {}: {e}", pcap_path.display()))?;
+
+    println!("Converted {count} packets to {}", pcap_path.display());
+    Ok(())
+
+}
+
+/// Pseudonymize the accounts of the replay at the given path and write the result to
+/// the given output path.
+pub fn run_anonymize(replay_path: &Path, output_path: &Path) -> CliResult<()> {
+
+    let mut replay = read_replay(replay_path)?;
+    let (anonymizer, replaced_count) = anonymize(&mut replay)?;
+
+    let output_file = File::create(output_path)
+        .map_err(|e| format!("Failed to create replay at {}: {e}", output_path.display()))?;
+
+    replay::to_writer(BufWriter::new(output_file), &replay)
+        .map_err(|e| format!("Failed to write replay at {}: {e}", output_path.display()))?;
+
+    println!("Pseudonymized {} players and {} identifiers, {replaced_count} occurrences replaced in the packet stream", 
+        anonymizer.names.len(), anonymizer.ids.len());
+
+    Ok(())
+
+}
+
+/// Pseudonymize the accounts of the given replay, returning the anonymizer and the 
+/// number of replacements in the packet stream.
+fn anonymize(replay: &mut Replay) -> CliResult<(Anonymizer, usize)> {
+
+    let mut blocks = replay.blocks.iter()
+        .enumerate()
+        .map(|(index, block)| serde_json::from_str::<Value>(block)
+            .map_err(|e| format!("Failed to parse JSON block #{index} of the replay: {e}")))
+        .collect::<CliResult<Vec<_>>>()?;
+
+    let mut anonymizer = Anonymizer::default();
+    for block in &blocks {
+        anonymizer.collect(block);
+    }
+
+    for (block, raw_block) in blocks.iter_mut().zip(&mut replay.blocks) {
+        anonymizer.rewrite(block);
+        // Unwrap because a JSON value can always be serialized.
+        *raw_block = serde_json::to_string(block).unwrap();
+    }
+
+    let (stream, replaced_count) = anonymizer.rewrite_stream(replay);
+    replay.stream = stream;
+
+    Ok((anonymizer, replaced_count))
+
+}
+
+pub fn read_replay(replay_path: &Path) -> CliResult<Replay> {
+
+    let replay_file = File::open(replay_path)
+        .map_err(|e| format!("Failed to open replay at {}: {e}", replay_path.display()))?;
+
+    replay::from_reader(BufReader::new(replay_file))
+        .map_err(|e| format!("Failed to read replay at {}: {e}", replay_path.display()))
+
+}
+
+/// The mapping of real names and identifiers to their pseudonyms.
+#[derive(Debug, Default)]
+struct Anonymizer {
+    names: HashMap<String, String>,
+    ids: HashMap<u64, u64>,
+}
+
+impl Anonymizer {
+
+    /// Collect all names and identifiers of the given JSON value.
+    fn collect(&mut self, value: &Value) {
+        match value {
+            Value::Object(object) => {
+
+                let player = object.contains_key(CLAN_KEY);
+
+                for (key, value) in object {
+                    if NAME_KEYS.contains(&key.as_str()) || (player && key == "name") {
+                        if let Some(name) = value.as_str() {
+                            self.add_name(name);
+                        }
+                    } else if ID_KEYS.contains(&key.as_str()) {
+                        if let Some(id) = value.as_u64() {
+                            self.add_id(id);
+                        }
+                    }
+                    self.collect(value);
+                }
+
+            }
+            Value::Array(array) => array.iter().for_each(|value| self.collect(value)),
+            _ => {}
+        }
+    }
+
+    fn add_name(&mut self, name: &str) {
+        if !name.is_empty() && !self.names.contains_key(name) {
+            let pseudonym = make_pseudonym(name.len(), self.names.len());
+            self.names.insert(name.to_string(), pseudonym);
+        }
+    }
+
+    fn add_id(&mut self, id: u64) {
+        // Zero is used for no clan.
+        if id != 0 && !self.ids.contains_key(&id) {
+            let pseudonym = self.ids.len() as u64 + 1;
+            self.ids.insert(id, pseudonym);
+        }
+    }
+
+    /// Rewrite all names and identifiers of the given JSON value, including the keys 
+    /// of objects that are identifiers.
+    fn rewrite(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+
+                let player = object.contains_key(CLAN_KEY);
+                let mut new_object = Map::with_capacity(object.len());
+
+                for (mut key, mut value) in std::mem::take(object) {
+
+                    if NAME_KEYS.contains(&key.as_str()) || (player && key == "name") {
+                        if let Some(pseudonym) = value.as_str().and_then(|name| self.names.get(name)) {
+                            value = Value::String(pseudonym.clone());
+                        }
+                    } else if ID_KEYS.contains(&key.as_str()) {
+                        if let Some(&pseudonym) = value.as_u64().and_then(|id| self.ids.get(&id)) {
+                            value = Value::from(pseudonym);
+                        }
+                    } else if key == CLAN_KEY {
+                        value = Value::String(String::new());
+                    } else {
+                        self.rewrite(&mut value);
+                    }
+
+                    if let Some(pseudonym) = self.rewrite_id_str(&key) {
+                        key = pseudonym;
+                    }
+
+                    new_object.insert(key, value);
+
+                }
+
+                *object = new_object;
+
+            }
+            Value::Array(array) => array.iter_mut().for_each(|value| self.rewrite(value)),
+            // Identifiers can also be found outside of their keys, in lists of accounts
+            // for example, or as strings.
+            Value::Number(number) => {
+                if let Some(&pseudonym) = number.as_u64().and_then(|id| self.ids.get(&id)) {
+                    *value = Value::from(pseudonym);
+                }
+            }
+            Value::String(string) => {
+                if let Some(pseudonym) = self.rewrite_id_str(string) {
+                    *string = pseudonym;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Return the pseudonym of the given string if it's a decimal identifier.
+    fn rewrite_id_str(&self, s: &str) -> Option<String> {
+        s.parse::<u64>().ok()
+            .and_then(|id| self.ids.get(&id))
+            .map(|pseudonym| pseudonym.to_string())
+    }
+
+    /// Rewrite the packet stream of the given replay, returning the new stream and the
+    /// number of replacements. Names are replaced where they are prefixed by their 
+    /// length on 8 or 32 bits, that is rewritten with the pseudonym's length, and 
+    /// identifiers are replaced wherever their little-endian encoding is found. The 
+    /// length of each packet is recomputed from its rewritten payload.
+    fn rewrite_stream(&self, replay: &Replay) -> (Vec<u8>, usize) {
+
+        // Longest names first, so that a name that contains another is replaced first.
+        let mut names = self.names.iter().collect::<Vec<_>>();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+        // Identifiers are encoded on 32 bits if they fit, this also replaces them when
+        // encoded on 64 bits because the pseudonyms are always smaller.
+        let ids = self.ids.iter()
+            .map(|(&id, &pseudonym)| match u32::try_from(id) {
+                Ok(id) => (id.to_le_bytes().to_vec(), (pseudonym as u32).to_le_bytes().to_vec()),
+                Err(_) => (id.to_le_bytes().to_vec(), pseudonym.to_le_bytes().to_vec()),
+            })
+            .collect::<Vec<_>>();
+
+        let mut stream = Vec::with_capacity(replay.stream.len());
+        let mut stream_len = 0;
+        let mut count = 0;
+
+        for packet in replay.packets() {
+
+            stream_len += packet.raw.len();
+
+            let header_offset = stream.len();
+            stream.extend_from_slice(&packet.raw[..packet.raw.len() - packet.data.len()]);
+
+            let payload_offset = stream.len();
+            let data = packet.data;
+            let mut index = 0;
+
+            'data: while index < data.len() {
+
+                let rest = &data[index..];
+
+                for (name, pseudonym) in &names {
+
+                    let name = name.as_bytes();
+                    let len_u32 = (name.len() as u32).to_le_bytes();
+                    
+                    if rest.starts_with(&len_u32) && rest[4..].starts_with(name) {
+                        stream.extend_from_slice(&(pseudonym.len() as u32).to_le_bytes());
+                        stream.extend_from_slice(pseudonym.as_bytes());
+                        index += 4 + name.len();
+                        count += 1;
+                        continue 'data;
+                    }
+
+                    if name.len() < 0xFF && rest[0] == name.len() as u8 && rest[1..].starts_with(name) {
+                        stream.push(pseudonym.len() as u8);
+                        stream.extend_from_slice(pseudonym.as_bytes());
+                        index += 1 + name.len();
+                        count += 1;
+                        continue 'data;
+                    }
+
+                }
+
+                for (id, pseudonym) in &ids {
+                    if rest.starts_with(id) {
+                        stream.extend_from_slice(pseudonym);
+                        index += id.len();
+                        count += 1;
+                        continue 'data;
+                    }
+                }
+
+                stream.push(rest[0]);
+                index += 1;
+
+            }
+
+            let len = (stream.len() - payload_offset) as u32;
+            stream[header_offset..header_offset + 4].copy_from_slice(&len.to_le_bytes());
+
+        }
+
+        // Keep any truncated packet at the end of the stream.
+        stream.extend_from_slice(&replay.stream[stream_len..]);
+
+        (stream, count)
+
+    }
+
+}
+
+/// Make a pseudonym of the given length in bytes, unique for each index as long as 
+/// the length allows it.
+fn make_pseudonym(len: usize, index: usize) -> String {
+
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let mut pseudonym = vec![b'0'; len];
+    let mut n = index;
+    for byte in pseudonym.iter_mut().rev() {
+        *byte = DIGITS[n % 36];
+        n /= 36;
+    }
+
+    if let Some(first) = pseudonym.first_mut() {
+        if len > 1 && n == 0 && *first == b'0' {
+            *first = b'P';
+        }
+    }
+
+    // Unwrap because all bytes are ASCII.
+    String::from_utf8(pseudonym).unwrap()
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const NAME: &str = "SomePlayer";
+    const ID: u64 = 523_456_789;
+
+    fn push_packet(stream: &mut Vec<u8>, kind: u32, data: &[u8]) {
+        stream.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        stream.extend_from_slice(&kind.to_le_bytes());
+        stream.extend_from_slice(&1.5f32.to_le_bytes());
+        stream.extend_from_slice(data);
+    }
+
+    #[test]
+    fn anonymize_round_trip() {
+
+        let block = format!(r#"{{
+            "playerName": "{NAME}",
+            "playerID": {ID},
+            "vehicles": {{
+                "12": {{ "name": "{NAME}", "clanAbbrev": "TAG", "accountDBID": {ID} }}
+            }},
+            "friends": [{ID}, "{ID}"],
+            "{ID}": {{ "team": 1 }}
+        }}"#);
+
+        let mut data = Vec::new();
+        data.push(NAME.len() as u8);
+        data.extend_from_slice(NAME.as_bytes());
+        data.extend_from_slice(&(ID as u32).to_le_bytes());
+        data.extend_from_slice(&(NAME.len() as u32).to_le_bytes());
+        data.extend_from_slice(NAME.as_bytes());
+        data.extend_from_slice(&ID.to_le_bytes());
+
+        let mut stream = Vec::new();
+        push_packet(&mut stream, 8, &data);
+        push_packet(&mut stream, 10, b"unrelated");
+
+        let mut replay = Replay {
+            blocks: vec![block],
+            unknown: [1, 2, 3, 4, 5, 6, 7, 8],
+            stream,
+        };
+
+        let (anonymizer, count) = anonymize(&mut replay).unwrap();
+        assert_eq!(anonymizer.names.len(), 1);
+        assert_eq!(anonymizer.ids.len(), 1);
+        assert_eq!(count, 4);
+
+        let pseudonym = anonymizer.names[NAME].clone();
+        let pseudonym_id = anonymizer.ids[&ID];
+
+        let mut raw = Vec::new();
+        replay::to_writer(&mut raw, &replay).unwrap();
+        let replay = replay::from_reader(&raw[..]).unwrap();
+
+        assert_eq!(replay.unknown, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(replay.blocks.len(), 1);
+        assert!(!replay.blocks[0].contains(NAME));
+        assert!(!replay.blocks[0].contains(&ID.to_string()));
+
+        let block = serde_json::from_str::<Value>(&replay.blocks[0]).unwrap();
+        assert_eq!(block["playerName"], Value::from(pseudonym.as_str()));
+        assert_eq!(block["playerID"], Value::from(pseudonym_id));
+        let vehicle = &block["vehicles"]["12"];
+        assert_eq!(vehicle["name"], Value::from(pseudonym.as_str()));
+        assert_eq!(vehicle["clanAbbrev"], Value::from(""));
+        assert_eq!(vehicle["accountDBID"], Value::from(pseudonym_id));
+        assert_eq!(block["friends"][0], Value::from(pseudonym_id));
+        assert_eq!(block["friends"][1], Value::from(pseudonym_id.to_string()));
+        assert_eq!(block[pseudonym_id.to_string()]["team"], Value::from(1));
+
+        let packets = replay.packets().collect::<Vec<_>>();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[1].kind, 10);
+        assert_eq!(packets[1].data, b"unrelated");
+
+        let mut expected = Vec::new();
+        expected.push(pseudonym.len() as u8);
+        expected.extend_from_slice(pseudonym.as_bytes());
+        expected.extend_from_slice(&(pseudonym_id as u32).to_le_bytes());
+        expected.extend_from_slice(&(pseudonym.len() as u32).to_le_bytes());
+        expected.extend_from_slice(pseudonym.as_bytes());
+        expected.extend_from_slice(&pseudonym_id.to_le_bytes());
+
+        assert_eq!(packets[0].kind, 8);
+        assert_eq!(packets[0].clock, 1.5);
+        assert_eq!(packets[0].data, &expected[..]);
+
+    }
+
+}
@@ -0,0 +1,371 @@
+//! Batch export of replay statistics to CSV files.
+
+use std::io::{self, BufWriter, Write};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::fs::{self, File};
+
+use glam::Vec3;
+use rayon::prelude::*;
+use serde_json::Value;
+
+use wgtk::replay::{kind, Replay};
+
+use crate::CliResult;
+
+use super::replay::read_replay;
+
+
+/// Process all replays of the given directory and write the CSV files in the output
+/// directory.
+pub fn run(dir: &Path, output_dir: &Path, positions: bool) -> CliResult<()> {
+
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {e}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wotreplay"))
+        .collect::<Vec<_>>();
+
+    // Sorted so that the output is stable.
+    paths.sort();
+
+    let stats = paths.par_iter()
+        .map(|path| compute_stats(path, positions))
+        .collect::<Vec<_>>();
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create directory {}: {e}", output_dir.display()))?;
+
+    let mut writers = Writers::new(output_dir, positions)
+        .map_err(|e| format!("Failed to create CSV files in {}: {e}", output_dir.display()))?;
+
+    let mut error_count = 0;
+    for stats in &stats {
+        match stats {
+            Ok(stats) => {
+                writers.write(stats)
+                    .map_err(|e| format!("Failed to write CSV files in {}: {e}", output_dir.display()))?;
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                error_count += 1;
+            }
+        }
+    }
+
+    writers.flush()
+        .map_err(|e| format!("Failed to write CSV files in {}: {e}", output_dir.display()))?;
+
+    println!("Exported {} replays, {error_count} failed", stats.len() - error_count);
+    Ok(())
+
+}
+
+/// Statistics of a single replay.
+#[derive(Debug)]
+struct ReplayStats {
+    file: String,
+    map_name: String,
+    date_time: String,
+    client_version: String,
+    packet_count: usize,
+    duration: f32,
+    /// Statistics of each entity, by entity id.
+    entities: BTreeMap<u32, EntityStats>,
+}
+
+/// Statistics of a single entity of a replay.
+#[derive(Debug, Default)]
+struct EntityStats {
+    /// The vehicle information from the replay description, if this entity is one of
+    /// the battle's vehicles.
+    vehicle: Option<VehicleInfo>,
+    /// Number of calls of each method, by method id.
+    method_calls: BTreeMap<u32, usize>,
+    /// All position updates, with their clock.
+    positions: Vec<(f32, Vec3)>,
+    position_count: usize,
+    /// The last position, used to compute the distance.
+    last_position: Option<Vec3>,
+    distance: f32,
+}
+
+#[derive(Debug)]
+struct VehicleInfo {
+    name: String,
+    vehicle_type: String,
+    team: i64,
+}
+
+fn compute_stats(path: &Path, positions: bool) -> CliResult<ReplayStats> {
+    let replay = read_replay(path)?;
+    let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    replay_stats(file, &replay, positions)
+        .map_err(|e| format!("Failed to parse description of replay at {}: {e}", path.display()))
+}
+
+/// Compute the statistics of the given replay, the file name is only used to identify
+/// the replay in the output.
+fn replay_stats(file: String, replay: &Replay, positions: bool) -> serde_json::Result<ReplayStats> {
+
+    let description = replay.blocks.first()
+        .map(|block| serde_json::from_str::<Value>(block))
+        .transpose()?
+        .unwrap_or(Value::Null);
+
+    let json_str = |key: &str| description.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let mut stats = ReplayStats {
+        file,
+        map_name: json_str("mapName"),
+        date_time: json_str("dateTime"),
+        client_version: json_str("clientVersionFromExe"),
+        packet_count: 0,
+        duration: 0.0,
+        entities: BTreeMap::new(),
+    };
+
+    // Vehicles are keyed by their entity id.
+    if let Some(vehicles) = description.get("vehicles").and_then(Value::as_object) {
+        for (entity_id, vehicle) in vehicles {
+            if let Ok(entity_id) = entity_id.parse::<u32>() {
+                let vehicle_str = |key: &str| vehicle.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+                stats.entities.entry(entity_id).or_default().vehicle = Some(VehicleInfo {
+                    name: vehicle_str("name"),
+                    vehicle_type: vehicle_str("vehicleType"),
+                    team: vehicle.get("team").and_then(Value::as_i64).unwrap_or_default(),
+                });
+            }
+        }
+    }
+
+    for packet in replay.packets() {
+
+        stats.packet_count += 1;
+        stats.duration = stats.duration.max(packet.clock);
+
+        let read_u32 = |offset: usize| packet.data.get(offset..offset + 4)
+            .map(|buf| u32::from_le_bytes(buf.try_into().unwrap()));
+        let read_f32 = |offset: usize| read_u32(offset).map(f32::from_bits);
+
+        match packet.kind {
+            kind::ENTITY_METHOD => {
+                if let (Some(entity_id), Some(method_id)) = (read_u32(0), read_u32(4)) {
+                    let entity = stats.entities.entry(entity_id).or_default();
+                    *entity.method_calls.entry(method_id).or_default() += 1;
+                }
+            }
+            kind::POSITION => {
+                if let (Some(entity_id), Some(x), Some(y), Some(z)) = (read_u32(0), read_f32(12), read_f32(16), read_f32(20)) {
+
+                    let entity = stats.entities.entry(entity_id).or_default();
+                    let pos = Vec3::new(x, y, z);
+
+                    if let Some(last_position) = entity.last_position {
+                        entity.distance += last_position.distance(pos);
+                    }
+
+                    entity.last_position = Some(pos);
+                    entity.position_count += 1;
+
+                    if positions {
+                        entity.positions.push((packet.clock, pos));
+                    }
+
+                }
+            }
+            _ => {}
+        }
+
+    }
+
+    Ok(stats)
+
+}
+
+/// All CSV writers.
+struct Writers {
+    battles: BufWriter<File>,
+    players: BufWriter<File>,
+    methods: BufWriter<File>,
+    positions: Option<BufWriter<File>>,
+}
+
+impl Writers {
+
+    fn new(output_dir: &Path, positions: bool) -> io::Result<Self> {
+
+        let create = |name: &str, header: &str| -> io::Result<BufWriter<File>> {
+            let mut writer = BufWriter::new(File::create(output_dir.join(name))?);
+            writeln!(writer, "{header}")?;
+            Ok(writer)
+        };
+
+        Ok(Self {
+            battles: create("battles.csv", "file,map,date_time,client_version,packets,duration,vehicles")?,
+            players: create("players.csv", "file,entity_id,name,vehicle_type,team,method_calls,position_updates,distance")?,
+            methods: create("methods.csv", "file,entity_id,method_id,calls")?,
+            positions: positions.then(|| create("positions.csv", "file,entity_id,clock,x,y,z")).transpose()?,
+        })
+
+    }
+
+    fn write(&mut self, stats: &ReplayStats) -> io::Result<()> {
+
+        let file = CsvField(&stats.file);
+        let vehicle_count = stats.entities.values().filter(|e| e.vehicle.is_some()).count();
+
+        writeln!(self.battles, "{file},{},{},{},{},{:.3},{vehicle_count}",
+            CsvField(&stats.map_name),
+            CsvField(&stats.date_time),
+            CsvField(&stats.client_version),
+            stats.packet_count,
+            stats.duration)?;
+
+        for (entity_id, entity) in &stats.entities {
+
+            if let Some(vehicle) = &entity.vehicle {
+                writeln!(self.players, "{file},{entity_id},{},{},{},{},{},{:.3}",
+                    CsvField(&vehicle.name),
+                    CsvField(&vehicle.vehicle_type),
+                    vehicle.team,
+                    entity.method_calls.values().sum::<usize>(),
+                    entity.position_count,
+                    entity.distance)?;
+            }
+
+            for (method_id, calls) in &entity.method_calls {
+                writeln!(self.methods, "{file},{entity_id},{method_id},{calls}")?;
+            }
+
+            if let Some(positions) = &mut self.positions {
+                for (clock, pos) in &entity.positions {
+                    writeln!(positions, "{file},{entity_id},{clock:.3},{:.3},{:.3},{:.3}", pos.x, pos.y, pos.z)?;
+                }
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.battles.flush()?;
+        self.players.flush()?;
+        self.methods.flush()?;
+        if let Some(positions) = &mut self.positions {
+            positions.flush()?;
+        }
+        Ok(())
+    }
+
+}
+
+/// A CSV field, quoted if needed.
+struct CsvField<'a>(&'a str);
+
+impl std::fmt::Display for CsvField<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.contains([',', '"', '\n', '\r']) {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn push_packet(stream: &mut Vec<u8>, kind: u32, clock: f32, data: &[u8]) {
+        stream.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        stream.extend_from_slice(&kind.to_le_bytes());
+        stream.extend_from_slice(&clock.to_le_bytes());
+        stream.extend_from_slice(data);
+    }
+
+    fn push_position(stream: &mut Vec<u8>, clock: f32, entity_id: u32, pos: [f32; 3]) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&entity_id.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        for coord in pos {
+            data.extend_from_slice(&coord.to_le_bytes());
+        }
+        push_packet(stream, kind::POSITION, clock, &data);
+    }
+
+    fn push_method(stream: &mut Vec<u8>, clock: f32, entity_id: u32, method_id: u32) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&entity_id.to_le_bytes());
+        data.extend_from_slice(&method_id.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        push_packet(stream, kind::ENTITY_METHOD, clock, &data);
+    }
+
+    #[test]
+    fn export() {
+
+        let block = r#"{
+            "mapName": "01_karelia",
+            "dateTime": "16.10.2026 13:00:00",
+            "clientVersionFromExe": "1, 0, 0, 0",
+            "vehicles": {
+                "12": { "name": "Some, Player", "vehicleType": "ussr:R04_T-34", "team": 1 },
+                "13": { "name": "Other", "vehicleType": "germany:G04_PzVI_Tiger_I", "team": 2 }
+            }
+        }"#;
+
+        let mut stream = Vec::new();
+        push_position(&mut stream, 1.0, 12, [0.0, 0.0, 0.0]);
+        push_position(&mut stream, 2.0, 12, [3.0, 0.0, 4.0]);
+        push_method(&mut stream, 2.5, 12, 7);
+        push_method(&mut stream, 3.0, 12, 7);
+        push_method(&mut stream, 3.5, 13, 2);
+        // Truncated packets are ignored.
+        push_packet(&mut stream, kind::POSITION, 4.0, &[0; 8]);
+
+        let replay = Replay {
+            blocks: vec![block.to_string()],
+            unknown: [0; 8],
+            stream,
+        };
+
+        let stats = replay_stats("battle.wotreplay".to_string(), &replay, true).unwrap();
+        assert_eq!(stats.map_name, "01_karelia");
+        assert_eq!(stats.packet_count, 6);
+        assert_eq!(stats.duration, 4.0);
+
+        let entity = &stats.entities[&12];
+        assert_eq!(entity.position_count, 2);
+        assert_eq!(entity.distance, 5.0);
+        assert_eq!(entity.method_calls[&7], 2);
+        assert_eq!(stats.entities[&13].method_calls[&2], 1);
+
+        let dir = std::env::temp_dir().join(format!("wgtk-replay-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut writers = Writers::new(&dir, true).unwrap();
+        writers.write(&stats).unwrap();
+        writers.flush().unwrap();
+        drop(writers);
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        let battles = read("battles.csv");
+        let players = read("players.csv");
+        let methods = read("methods.csv");
+        let positions = read("positions.csv");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(battles.lines().nth(1), Some("battle.wotreplay,01_karelia,16.10.2026 13:00:00,\"1, 0, 0, 0\",6,4.000,2"));
+        assert_eq!(players.lines().nth(1), Some("battle.wotreplay,12,\"Some, Player\",ussr:R04_T-34,1,2,2,5.000"));
+        assert_eq!(players.lines().count(), 3);
+        assert_eq!(methods.lines().skip(1).collect::<Vec<_>>(), ["battle.wotreplay,12,7,2", "battle.wotreplay,13,2,1"]);
+        assert_eq!(positions.lines().count(), 3);
+
+    }
+
+}
@@ -15,3 +15,4 @@ pub mod model;
 pub mod res;
 
 pub mod net;
+pub mod replay;
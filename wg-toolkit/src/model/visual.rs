@@ -62,8 +62,8 @@ pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Box<Visual>, DeError> {
 
 fn read_node(element: &Element) -> Option<Node> {
     
-    let identifier = element.get_child("identifier")?.as_string()?;
-    let transform = element.get_child("transform")?.as_affine3()?;
+    let identifier = element.get_string("identifier")?;
+    let transform = element.get_affine3("transform")?;
     
     let mut children = Vec::new();
     for child in element.iter_children("node") {
@@ -83,25 +83,25 @@ fn read_node(element: &Element) -> Option<Node> {
 
 fn read_render_set(element: &Element) -> Option<RenderSet> {
 
-    let node = element.get_child("node")?.as_string()?;
-    let treat_as_world_space_object = element.get_child("treatAsWorldSpaceObject")?.as_boolean()?;
+    let node = element.get_string("node")?;
+    let treat_as_world_space_object = element.get_bool("treatAsWorldSpaceObject")?;
 
-    let geometry_elt = element.get_child("geometry")?.as_element()?;
-    let geometry_vertices = geometry_elt.get_child("vertices")?.as_string()?;
-    let geometry_indices = geometry_elt.get_child("primitive")?.as_string()?;
+    let geometry_elt = element.get_element("geometry")?;
+    let geometry_vertices = geometry_elt.get_string("vertices")?;
+    let geometry_indices = geometry_elt.get_string("primitive")?;
 
     let mut primitive_groups = SmallVec::new();
     for group_val in geometry_elt.iter_children("primitiveGroup") {
         if let Value::Element(group_elt) = group_val {
             
             let group_index = group_elt.value.as_integer()? as u32;
-            let group_origin = group_elt.get_child("groupOrigin")?.as_vec3()?;
+            let group_origin = group_elt.get_vec3("groupOrigin")?;
 
-            let mat_elt = group_elt.get_child("material")?.as_element()?;
-            let mat_identifier = mat_elt.get_child("identifier")?.as_string()?;
-            let mat_collision_flags = mat_elt.get_child("collisionFlags")?.as_integer()? as u32;
-            let mat_kind = mat_elt.get_child("materialKind")?.as_integer()? as u32;
-            let mat_fx = mat_elt.get_child("fx")?.as_string()?;
+            let mat_elt = group_elt.get_element("material")?;
+            let mat_identifier = mat_elt.get_string("identifier")?;
+            let mat_collision_flags = mat_elt.get_i64("collisionFlags")? as u32;
+            let mat_kind = mat_elt.get_i64("materialKind")? as u32;
+            let mat_fx = mat_elt.get_string("fx")?;
 
             let mut mat_properties = HashMap::new();
             for prop_val in mat_elt.iter_children("property") {
@@ -4,8 +4,13 @@
 //! replies to such elements if they are requests.
 
 use std::io::{self, Read, Write};
+use std::fmt;
+
+use glam::Vec3;
 
 use crate::net::element::{ElementLength, Element, SimpleElement};
+use crate::net::codec::SimpleCodec;
+use crate::util::io::{WgReadExt, WgWriteExt};
 use crate::net::app::common::entity::Method;
 
 
@@ -18,10 +23,10 @@ pub mod id {
     pub const SESSION_KEY: u8                   = 0x01;
 
     // Just from source code, to check that it's right id:
-    // pub const AVATAR_UPDATE_IMPLICIT: u8        = 0x02;
-    // pub const AVATAR_UPDATE_EXPLICIT: u8        = 0x03;
-    // pub const AVATAR_UPDATE_WARD_IMPLICIT: u8   = 0x04;
-    // pub const AVATAR_UPDATE_WARD_EXPLICIT: u8   = 0x05;
+    pub const AVATAR_UPDATE_IMPLICIT: u8        = 0x02;
+    pub const AVATAR_UPDATE_EXPLICIT: u8        = 0x03;
+    pub const AVATAR_UPDATE_WARD_IMPLICIT: u8   = 0x04;
+    pub const AVATAR_UPDATE_WARD_EXPLICIT: u8   = 0x05;
     // pub const ACK_PHYSICS_CORRECTION: u8        = 0x06;
     // pub const ACK_WARD_PHYSICS_CORRECTION: u8   = 0x07;
     // pub const REQUEST_ENTITY_UPDATE: u8         = 0x08;
@@ -43,6 +48,7 @@ crate::__struct_simple_codec! {
     /// This element is usually a request, in such case a [`SessionKey`] must be sent as 
     /// a reply, which is the server session key (not the same as login key).
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct LoginKey {
         /// The login key that was sent by the login application, part of the  element
         /// [`super::login::LoginSuccess`].
@@ -67,6 +73,7 @@ crate::__struct_simple_codec! {
     /// - Sent by the client on login (and apparently randomly after login) to return 
     ///   the session key that was sent by the server in the initial reply (first case).
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct SessionKey {
         /// The server session key.
         pub session_key: u32,
@@ -79,10 +86,85 @@ impl SimpleElement for SessionKey {
 }
 
 
+crate::__struct_simple_codec! {
+    /// Sent by the client with the position and direction of the entity it controls,
+    /// its player entity, while staying in the same space and vehicle. The direction
+    /// is packed with one byte per angle. The layout of this and
+    /// following avatar updates are taken from BigWorld.
+    #[derive(Debug, Clone)]
+    pub struct AvatarUpdateImplicit {
+        pub position: Vec3,
+        pub yaw: i8,
+        pub pitch: i8,
+        pub roll: i8,
+        /// Sequence number of this update, given back by the server as reference.
+        pub ref_num: u8,
+    }
+
+    /// Sent by the client with the position and direction of its player entity, 
+    /// like [`AvatarUpdateImplicit`] but explicitly giving the space and vehicle.
+    #[derive(Debug, Clone)]
+    pub struct AvatarUpdateExplicit {
+        pub space_id: u32,
+        pub vehicle_entity_id: u32,
+        pub position: Vec3,
+        pub yaw: i8,
+        pub pitch: i8,
+        pub roll: i8,
+        pub on_ground: bool,
+        pub ref_num: u8,
+    }
+
+    /// Like [`AvatarUpdateImplicit`] but for another entity controlled by the client.
+    #[derive(Debug, Clone)]
+    pub struct AvatarUpdateWardImplicit {
+        pub ward_entity_id: u32,
+        pub position: Vec3,
+        pub yaw: i8,
+        pub pitch: i8,
+        pub roll: i8,
+    }
+
+    /// Like [`AvatarUpdateExplicit`] but for another entity controlled by the client.
+    #[derive(Debug, Clone)]
+    pub struct AvatarUpdateWardExplicit {
+        pub ward_entity_id: u32,
+        pub space_id: u32,
+        pub vehicle_entity_id: u32,
+        pub position: Vec3,
+        pub yaw: i8,
+        pub pitch: i8,
+        pub roll: i8,
+        pub on_ground: bool,
+    }
+}
+
+impl SimpleElement for AvatarUpdateImplicit {
+    const ID: u8 = id::AVATAR_UPDATE_IMPLICIT;
+    const LEN: ElementLength = ElementLength::Fixed(16);
+}
+
+impl SimpleElement for AvatarUpdateExplicit {
+    const ID: u8 = id::AVATAR_UPDATE_EXPLICIT;
+    const LEN: ElementLength = ElementLength::Fixed(25);
+}
+
+impl SimpleElement for AvatarUpdateWardImplicit {
+    const ID: u8 = id::AVATAR_UPDATE_WARD_IMPLICIT;
+    const LEN: ElementLength = ElementLength::Fixed(19);
+}
+
+impl SimpleElement for AvatarUpdateWardExplicit {
+    const ID: u8 = id::AVATAR_UPDATE_WARD_EXPLICIT;
+    const LEN: ElementLength = ElementLength::Fixed(28);
+}
+
+
 crate::__struct_simple_codec! {
     /// This is sent by the client to the base application as an acknowledgment of a
     /// reset entity request sent to the client.
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct EnableEntities {}
 }
 
@@ -96,8 +178,9 @@ crate::__struct_simple_codec! {
     /// This is sent by the client to the base application as an acknowledgment of a
     /// reset entity request sent to the client.
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct DisconnectClient {
-        pub reason: u8,
+        pub reason: DisconnectReason,
     }
 }
 
@@ -106,6 +189,65 @@ impl SimpleElement for DisconnectClient {
     const LEN: ElementLength = ElementLength::Fixed(1);
 }
 
+/// Reason of a client disconnection, known values are derived from BigWorld's client
+/// disconnect reasons, other values are kept as unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisconnectReason {
+    /// The client requested the disconnection, this is the usual reason.
+    ClientRequested,
+    /// The client has timed out.
+    Timeout,
+    /// The client has exceeded the rate limits.
+    RateLimitsExceeded,
+    /// Unknown reason code.
+    Unknown(u8),
+}
+
+impl From<u8> for DisconnectReason {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::ClientRequested,
+            1 => Self::Timeout,
+            2 => Self::RateLimitsExceeded,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<DisconnectReason> for u8 {
+    fn from(value: DisconnectReason) -> Self {
+        match value {
+            DisconnectReason::ClientRequested => 0,
+            DisconnectReason::Timeout => 1,
+            DisconnectReason::RateLimitsExceeded => 2,
+            DisconnectReason::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClientRequested => f.write_str("client requested"),
+            Self::Timeout => f.write_str("timeout"),
+            Self::RateLimitsExceeded => f.write_str("rate limits exceeded"),
+            Self::Unknown(value) => write!(f, "unknown (0x{value:02X})"),
+        }
+    }
+}
+
+impl SimpleCodec for DisconnectReason {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_u8((*self).into())
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        read.read_u8().map(Self::from)
+    }
+
+}
+
 
 /// Codec for a base entity method call.
 ///
@@ -118,7 +260,7 @@ pub struct BaseEntityMethod<M: Method> {
 impl<M: Method> Element<()> for BaseEntityMethod<M> {
 
     fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
-        Ok(ElementLength::Variable16)
+        Ok(self.inner.write_length())
     }
 
     fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
@@ -129,8 +271,11 @@ impl<M: Method> Element<()> for BaseEntityMethod<M> {
         Ok(id::BASE_ENTITY_METHOD.first + exposed_id as u8)
     }
 
-    fn read_length(_config: &(), _id: u8) -> io::Result<ElementLength> {
-        Ok(ElementLength::Variable16)
+    fn read_length(_config: &(), id: u8) -> io::Result<ElementLength> {
+        if !id::BASE_ENTITY_METHOD.contains(id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected base entity method element id: {id:02X}")));
+        }
+        Ok(M::read_length((id - id::BASE_ENTITY_METHOD.first) as u16))
     }
 
     fn read(read: &mut dyn Read, _config: &(), _len: usize, id: u8) -> io::Result<Self> {
@@ -144,3 +289,27 @@ impl<M: Method> Element<()> for BaseEntityMethod<M> {
     }
 
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disconnect_reason() {
+        for code in 0..=u8::MAX {
+
+            let mut data = Vec::new();
+            DisconnectReason::from(code).write(&mut data).unwrap();
+            assert_eq!(data, [code]);
+
+            let reason = DisconnectReason::read(&mut &data[..]).unwrap();
+            assert_eq!(u8::from(reason), code);
+            assert_eq!(matches!(reason, DisconnectReason::Unknown(_)), code > 2);
+
+        }
+        assert_eq!(DisconnectReason::Timeout.to_string(), "timeout");
+    }
+
+}
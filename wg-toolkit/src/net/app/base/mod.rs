@@ -2,12 +2,12 @@
 
 pub mod element;
 
-use core::fmt;
 use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::num::Wrapping;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::io;
 
 use blowfish::Blowfish;
@@ -16,14 +16,22 @@ use rand::rngs::OsRng;
 use rand::RngCore;
 
 use crate::net::bundle::{Bundle, NextElementReader, ElementReader};
-use crate::net::element::SimpleElement;
+use crate::net::element::{ElementLength, RawElement, SimpleElement};
 use crate::net::socket::PacketSocket;
-use crate::net::proto::Protocol;
-
-use super::common::entity::Entity;
+#[cfg(feature = "tokio")]
+use crate::net::socket::AsyncPacketSocket;
+use crate::net::packet::Packet;
+use crate::net::proto::{PacketPolicy, Protocol, RetransmitEvent, RetransmitPolicy};
+use crate::net::seq::Seq;
+use crate::net::variant::ProtocolVariant;
+
+use super::client::resource::ResourceUploader;
+use super::client::element::{LoggedOff, LoggedOffReason};
+use super::tick::{is_timeout, TickEvent, TickScheduler};
+use super::common::entity::{Entity, Method};
 use super::io_invalid_data;
 
-use element::{LoginKey, SessionKey};
+use element::{LoginKey, SessionKey, EnableEntities, DisconnectClient, DisconnectReason};
 
 
 /// The base application.
@@ -42,25 +50,50 @@ pub struct App {
     /// Map of clients.
     clients: HashMap<SocketAddr, Client>,
     /// Map of all currently alive entities.
-    entities: HashMap<u32, EntityGeneric>,
+    entities: HashMap<u32, EntityInfo>,
     /// The next id for entities, this is wrapping around and we ensure that the same id
     /// isn't used twice!
     entities_next_id: Wrapping<u32>,
+    /// Used to split streamed resources into elements.
+    resource_uploader: ResourceUploader,
+    /// The optional tick scheduler.
+    tick: Option<TickScheduler>,
+    /// The receive timeout set by the last poll, for the next tick or retransmission.
+    recv_timeout: Option<Duration>,
 }
 
 impl App {
 
     pub fn new(addr: SocketAddr) -> io::Result<Self> {
-        Ok(Self {
-            socket: PacketSocket::bind(addr)?,
-            protocol: Protocol::new(),
+        Ok(Self::with_socket(PacketSocket::bind(addr)?))
+    }
+
+    /// Create a new base application on the given address, backed by an async socket,
+    /// the app should then be polled with [`Self::poll_async`]. This must be called 
+    /// from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn new_async(addr: SocketAddr) -> io::Result<Self> {
+        Ok(Self::with_socket(AsyncPacketSocket::bind(addr)?.into_socket()))
+    }
+
+    /// Create a new base application running on the given socket, this can be used to
+    /// run the application on another transport than UDP.
+    pub fn with_socket(socket: PacketSocket) -> Self {
+        let mut protocol = Protocol::new();
+        protocol.set_retransmit_policy(Some(RetransmitPolicy::default()));
+        Self {
+            socket,
+            protocol,
             events: VecDeque::new(),
             bundle: Bundle::new(),
             pending_clients: HashMap::new(),
             clients: HashMap::new(),
             entities: HashMap::new(),
             entities_next_id: Wrapping(OsRng.next_u32()),
-        })
+            resource_uploader: ResourceUploader::new(),
+            tick: None,
+            recv_timeout: None,
+        }
     }
 
     /// Get the address this app is bound to.
@@ -68,47 +101,183 @@ impl App {
         self.socket.addr()
     }
 
+    /// Set the tick scheduler of this application, when set, [`Event::Tick`] events
+    /// are returned by [`Self::poll`] when ticks are due.
+    pub fn set_tick_scheduler(&mut self, tick: Option<TickScheduler>) -> io::Result<()> {
+        if tick.is_none() {
+            self.socket.set_recv_timeout(None)?;
+        }
+        self.tick = tick;
+        Ok(())
+    }
+
+    /// Set the protocol variant used to read and write packets and elements, the PC
+    /// client's variant is used by default.
+    pub fn set_protocol_variant(&mut self, variant: ProtocolVariant) {
+        self.bundle.set_variant(&variant);
+        self.protocol.set_variant(variant);
+    }
+
+    /// Set the policy of outgoing packets for peers without a specific policy.
+    pub fn set_default_packet_policy(&mut self, policy: PacketPolicy) {
+        self.protocol.set_default_policy(policy);
+    }
+
+    /// Set a specific policy of outgoing packets to the given peer, none to use the 
+    /// default policy again.
+    pub fn set_peer_packet_policy(&mut self, addr: SocketAddr, policy: Option<PacketPolicy>) {
+        self.protocol.set_peer_policy(addr, policy);
+    }
+
+    /// Set the retransmission policy of reliable packets, none to disable it. The 
+    /// default policy is enabled, reliable packets that are not acknowledged after all
+    /// retries are reported with [`Event::DeliveryFailure`] events.
+    pub fn set_retransmit_policy(&mut self, policy: Option<RetransmitPolicy>) {
+        self.protocol.set_retransmit_policy(policy);
+    }
+
     /// Poll for the next event of this login app, blocking.
     pub fn poll(&mut self) -> Event {
         loop {
 
-            // Empty the events before.
-            while let Some(event) = self.events.pop_front() {
+            if let Some(event) = self.poll_pending() {
+                return event;
+            }
+
+            let res = self.socket.recv();
+            if let Some(event) = self.handle_recv(res) {
+                return event;
+            }
+
+        }
+    }
+
+    /// Poll for the next event of this base app, asynchronously, the app should have
+    /// been created with [`Self::new_async`], or any non-blocking socket.
+    #[cfg(feature = "tokio")]
+    pub async fn poll_async(&mut self) -> Event {
+        loop {
+
+            if let Some(event) = self.poll_pending() {
                 return event;
             }
 
-            let (packet, addr) = match self.socket.recv() {
-                Ok(ret) => ret,
+            match super::recv_ready(&self.socket, false, self.recv_timeout).await {
+                Ok(true) => {}
+                Ok(false) => continue,
                 Err(error) => return Event::IoError(IoErrorEvent { error, addr: None }),
-            };
+            }
 
-            let Some(mut channel) = self.protocol.accept(packet, addr) else {
-                continue;
+            let res = match self.socket.recv() {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => continue,
+                res => res,
             };
 
-            let Some(bundle) = channel.next_bundle() else {
-                continue;
-            };
+            if let Some(event) = self.handle_recv(res) {
+                return event;
+            }
 
-            // Fully read the bundle to determine how to handle that client.
-            let mut reader = bundle.element_reader();
-            while let Some(reader) = reader.next() {
-                match reader {
-                    NextElementReader::Element(elt) => {
-                        if let Err(error) = self.handle_element(addr, elt) {
-                            return Event::IoError(IoErrorEvent { error, addr: Some(addr) });
-                        }
-                    }
-                    NextElementReader::Reply(reply) => {
-                        return Event::IoError(IoErrorEvent {
-                            error: io_invalid_data(format_args!("unexpected reply #{}", reply.request_id())),
-                            addr: Some(addr),
-                        });
+        }
+    }
+
+    /// Return the next event that is not related to a received packet, if any.
+    fn poll_pending(&mut self) -> Option<Event> {
+
+        // Empty the events before.
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+
+        let now = Instant::now();
+        let mut timeout = None;
+
+        // Report bundles that have not been completed in time.
+        if let Some((addr, error)) = self.protocol.poll_fragment_timeout(now) {
+            let error = io::Error::new(io::ErrorKind::InvalidData, error);
+            return Some(Event::IoError(IoErrorEvent { error, addr: Some(addr) }));
+        }
+
+        // Drive the tick scheduler, if any.
+        if let Some(tick) = &mut self.tick {
+            if let Some(event) = tick.poll(now) {
+                return Some(Event::Tick(event));
+            }
+            timeout = Some(tick.timeout(now));
+        }
+
+        // Resend reliable packets that have not been acknowledged in time.
+        while let Some(event) = self.protocol.poll_retransmit(now) {
+            match event {
+                RetransmitEvent::Resend { addr, packet, cipher } => {
+                    if let Err(error) = self.socket.send_with_cipher(&packet, addr, cipher.as_deref()) {
+                        return Some(Event::IoError(IoErrorEvent { error, addr: Some(addr) }));
                     }
                 }
+                RetransmitEvent::Failed { addr, sequence_num } => {
+                    return Some(Event::DeliveryFailure(DeliveryFailureEvent { addr, sequence_num }));
+                }
             }
+        }
+
+        if let Some(retransmit_timeout) = self.protocol.retransmit_timeout(now) {
+            timeout = Some(timeout.map_or(retransmit_timeout, |timeout| timeout.min(retransmit_timeout)));
+        }
 
+        // The receive timeout is adjusted so that we wake up in time for the next tick
+        // or retransmission, it's only reset if previously set.
+        if timeout.is_some() || self.recv_timeout.is_some() {
+            self.recv_timeout = timeout;
+            if let Err(error) = self.socket.set_recv_timeout(timeout) {
+                return Some(Event::IoError(IoErrorEvent { error, addr: None }));
+            }
         }
+
+        None
+
+    }
+
+    /// Handle the result of receiving a packet, returning an event if any.
+    fn handle_recv(&mut self, res: io::Result<(Packet, SocketAddr)>) -> Option<Event> {
+
+        let (packet, addr) = match res {
+            Ok(ret) => ret,
+            Err(error) if self.recv_timeout.is_some() && is_timeout(&error) => return None,
+            Err(error) => return Some(Event::IoError(IoErrorEvent { error, addr: None })),
+        };
+
+        let mut channel = self.protocol.accept(packet, addr)?;
+        let bundle = channel.next_bundle()?;
+
+        // The bundle's packets are recycled once handled.
+        let event = self.handle_bundle(&bundle, addr);
+        self.socket.pool().put_all(bundle.into_iter());
+        event
+
+    }
+
+    /// Handle a received bundle, returning an event if any.
+    fn handle_bundle(&mut self, bundle: &Bundle, addr: SocketAddr) -> Option<Event> {
+
+        // Fully read the bundle to determine how to handle that client.
+        let mut reader = bundle.element_reader();
+        while let Some(reader) = reader.next() {
+            match reader {
+                NextElementReader::Element(elt) => {
+                    if let Err(error) = self.handle_element(addr, elt) {
+                        return Some(Event::IoError(IoErrorEvent { error, addr: Some(addr) }));
+                    }
+                }
+                NextElementReader::Reply(reply) => {
+                    return Some(Event::IoError(IoErrorEvent {
+                        error: io_invalid_data(format_args!("unexpected reply #{}", reply.request_id())),
+                        addr: Some(addr),
+                    }));
+                }
+            }
+        }
+
+        None
+
     }
 
     /// Handle an element read from the given address.
@@ -116,6 +285,9 @@ impl App {
         match reader.id() {
             LoginKey::ID => self.handle_client_auth(addr, reader),
             SessionKey::ID => self.handle_client_session_key(addr, reader),
+            EnableEntities::ID => self.handle_enable_entities(addr, reader),
+            DisconnectClient::ID => self.handle_disconnect_client(addr, reader),
+            id if element::id::BASE_ENTITY_METHOD.contains(id) => self.handle_base_method(addr, reader),
             id => Err(io_invalid_data(format_args!("unexpected element #{id}"))),
         }
     }
@@ -139,27 +311,164 @@ impl App {
     }
 
     fn handle_client_session_key(&mut self, addr: SocketAddr, reader: ElementReader) -> io::Result<()> {
-        let _ = (addr, reader);
+
+        let session_key = reader.read_simple::<SessionKey>()?.element.session_key;
+        let client = self.clients.get(&addr)
+            .ok_or_else(|| io_invalid_data(format_args!("session key from unknown client")))?;
+
+        if client.session_key != session_key {
+            return Err(io_invalid_data(format_args!("invalid session key: 0x{session_key:08X}")));
+        }
+
         Ok(())
+
+    }
+
+    fn handle_enable_entities(&mut self, addr: SocketAddr, reader: ElementReader) -> io::Result<()> {
+
+        reader.read_simple::<EnableEntities>()?;
+        if !self.clients.contains_key(&addr) {
+            return Err(io_invalid_data(format_args!("enable entities from unknown client")));
+        }
+
+        self.events.push_back(Event::EnableEntities(EnableEntitiesEvent { addr }));
+        Ok(())
+
+    }
+
+    fn handle_disconnect_client(&mut self, addr: SocketAddr, reader: ElementReader) -> io::Result<()> {
+
+        let reason = reader.read_simple::<DisconnectClient>()?.element.reason;
+        if !self.remove_client(addr) {
+            return Err(io_invalid_data(format_args!("disconnect from unknown client")));
+        }
+
+        self.events.push_back(Event::Disconnect(DisconnectEvent { addr, reason }));
+        Ok(())
+
+    }
+
+    fn handle_base_method(&mut self, addr: SocketAddr, reader: ElementReader) -> io::Result<()> {
+
+        // Like entity methods sent to the client, sub message ids are not supported, so
+        // the element id directly maps to the exposed id.
+        let exposed_id = (reader.id() - element::id::BASE_ENTITY_METHOD.first) as u16;
+
+        // Base methods are always called on the player entity of the client.
+        let entity_id = self.clients.get(&addr)
+            .and_then(|client| client.player_entity_id)
+            .ok_or_else(|| io_invalid_data(format_args!("base method from client without player entity")))?;
+
+        let entity = self.entities.get(&entity_id)
+            .ok_or_else(|| io_invalid_data(format_args!("base method on unknown entity: {entity_id}")))?;
+
+        let raw = reader.read::<RawElement, _>(&(entity.base_method_length)(exposed_id))?;
+
+        self.events.push_back(Event::BaseMethod(BaseMethodEvent {
+            addr,
+            entity_id,
+            exposed_id,
+            data: raw.element.data.into_owned(),
+        }));
+
+        Ok(())
+
     }
 
     /// Accept the login of the given user, in response to [`Event::Login`], giving the
-    /// blowfish key that will be used for encryption.
+    /// blowfish key that has been given to the client by the login app. The client's
+    /// channel is created and all further packets exchanged with the client are
+    /// encrypted, the session key is then sent in reply to the client.
     /// 
     /// This returns true if the client hasn't been answered yet.
-    pub fn answer_login_success(&mut self, addr: SocketAddr, _blowfish: Arc<Blowfish>) -> bool {
+    pub fn answer_login_success(&mut self, addr: SocketAddr, blowfish: Arc<Blowfish>) -> io::Result<bool> {
         
-        let Some(_request_id) = self.pending_clients.remove(&addr) else {
-            return false;
+        let Some(request_id) = self.pending_clients.remove(&addr) else {
+            return Ok(false);
         };
 
+        let session_key = OsRng.next_u32();
+
+        self.clients.insert(addr, Client {
+            session_key,
+            player_entity_id: None,
+        });
+
+        self.bundle.clear();
+        self.bundle.element_writer().write_simple_reply(SessionKey { session_key }, request_id);
+        let mut channel = self.protocol.create_channel(addr, None);
+        channel.set_cipher(Some(blowfish));
+        channel.send(&self.socket, &mut self.bundle, true)?;
+
+        Ok(true)
+
+    }
+
+    /// Disconnect the given client, it's notified that it has been logged off, then 
+    /// its channel is closed and all its entities are destroyed. This returns false if
+    /// the client was not logged in.
+    pub fn disconnect(&mut self, addr: SocketAddr, reason: LoggedOffReason) -> io::Result<bool> {
+
+        if !self.clients.contains_key(&addr) {
+            return Ok(false);
+        }
+
+        self.bundle.clear();
+        self.bundle.element_writer().write_simple(LoggedOff { reason });
+        let res = self.protocol.channel(addr, None).send(&self.socket, &mut self.bundle, false);
+
+        // The client is removed even if the notification failed.
+        self.remove_client(addr);
+        res.map(|_| true)
+
+    }
+
+    /// Forget everything about the given client, returning false if not logged in.
+    fn remove_client(&mut self, addr: SocketAddr) -> bool {
 
+        if self.clients.remove(&addr).is_none() {
+            return false;
+        }
+
+        self.entities.retain(|_, entity| entity.addr != addr);
+        self.protocol.close_channel(addr, None);
         true
 
     }
 
-    /// Create an entity and return the handle to manage it.
-    pub fn create_entity<E: Entity + 'static>(&mut self, entity: E) -> Handle<E> {
+    /// Stream a resource to the given client, the resource is split into a header and
+    /// fragments that are sent reliably on the client's channel, the client then 
+    /// reassembles and checks it. The allocated resource id is returned.
+    pub fn stream_resource(&mut self, addr: SocketAddr, data: &[u8]) -> io::Result<u16> {
+
+        use super::client::element::{ResourceHeader, ResourceFragment};
+
+        let upload = self.resource_uploader.upload(data);
+        let id = upload.header.id;
+
+        self.bundle.clear();
+        let mut writer = self.bundle.element_writer();
+        writer.write_simple::<ResourceHeader>(upload.header);
+        for fragment in upload.fragments {
+            writer.write_simple::<ResourceFragment>(fragment);
+        }
+
+        self.protocol.channel(addr, None).send(&self.socket, &mut self.bundle, true)?;
+
+        Ok(id)
+
+    }
+
+    /// Create the player entity of the given logged in client, the entity type id is
+    /// the index of the entity type in the game's entity definitions. Any previous
+    /// player entity of this client is destroyed. The client should answer with an
+    /// [`Event::EnableEntities`] event.
+    pub fn create_base_player<E: Entity>(&mut self, addr: SocketAddr, entity_type_id: u16, entity: E) -> io::Result<Handle<E>> {
+
+        use super::client::element::CreateBasePlayer;
+
+        let client = self.clients.get_mut(&addr)
+            .ok_or_else(|| io_invalid_data(format_args!("client is not logged in")))?;
 
         // Generate a new unique entity id.
         let entity_id = loop {
@@ -170,20 +479,100 @@ impl App {
             }
         };
 
-        // self.entities.insert(entity_id, EntityGeneric {
-        //     wrapper: Box::new(EntityWrapperImpl {
-        //         inner: entity,
-        //     })
-        // });
+        if let Some(prev_entity_id) = client.player_entity_id.replace(entity_id) {
+            self.entities.remove(&prev_entity_id);
+        }
+
+        self.entities.insert(entity_id, EntityInfo {
+            addr,
+            entity_type_id,
+            base_method_length: <E::BaseMethod as Method>::read_length,
+        });
+
+        self.bundle.clear();
+        self.bundle.element_writer().write_simple(CreateBasePlayer {
+            entity_id,
+            entity_type_id,
+            entity_data: Box::new(entity),
+            entity_components_count: 0,
+        });
+
+        self.protocol.channel(addr, None).send(&self.socket, &mut self.bundle, true)?;
+
+        Ok(Handle { entity_id, _phantom: PhantomData })
+
+    }
+
+    /// Ask the given client to reset all of its entities, optionally keeping its 
+    /// player entity, the client should answer with an [`Event::EnableEntities`] event.
+    pub fn reset_entities(&mut self, addr: SocketAddr, keep_player_on_base: bool) -> io::Result<()> {
+
+        use super::client::element::ResetEntities;
+
+        if !self.clients.contains_key(&addr) {
+            return Err(io_invalid_data(format_args!("client is not logged in")));
+        }
+
+        self.bundle.clear();
+        self.bundle.element_writer().write_simple(ResetEntities { keep_player_on_base });
+        self.protocol.channel(addr, None).send(&self.socket, &mut self.bundle, true)?;
+
+        Ok(())
+
+    }
+
+    /// Destroy the given entity, it's forgotten by the application and its handle 
+    /// becomes invalid, this returns false if the entity did not exist.
+    pub fn destroy_entity<E: Entity>(&mut self, handle: Handle<E>) -> bool {
+
+        let Some(entity) = self.entities.remove(&handle.entity_id) else {
+            return false;
+        };
+
+        if let Some(client) = self.clients.get_mut(&entity.addr) {
+            if client.player_entity_id == Some(handle.entity_id) {
+                client.player_entity_id = None;
+            }
+        }
 
-        todo!()
+        true
 
     }
 
-    /// Call a method on an entity present on the given client address and its handle.
-    pub fn call_method<E: Entity>(&mut self, addr: SocketAddr, handle: Handle<E>, method: E::ClientMethod) {
-        let _ = (addr, handle, method);
-        todo!()
+    /// Return the entity type id of the given entity, if it exists.
+    pub fn entity_type_id(&self, handle: GenericHandle) -> Option<u16> {
+        self.entities.get(&handle.entity_id).map(|entity| entity.entity_type_id)
+    }
+
+    /// Call a method on the client side of the given entity, the entity should be the
+    /// player entity of its client.
+    /// 
+    /// FIXME: Non-player entities are not yet supported because they need to be 
+    /// created in the client's area of interest.
+    pub fn call_method<E: Entity>(&mut self, handle: Handle<E>, method: E::ClientMethod) -> io::Result<()> {
+
+        use super::client::element::{SelectPlayerEntity, EntityMethod};
+
+        let addr = self.entities.get(&handle.entity_id)
+            .map(|entity| entity.addr)
+            .ok_or_else(|| io_invalid_data(format_args!("unknown entity: {}", handle.entity_id)))?;
+
+        let is_player = self.clients.get(&addr)
+            .is_some_and(|client| client.player_entity_id == Some(handle.entity_id));
+
+        if !is_player {
+            return Err(io_invalid_data(format_args!("entity {} is not a player entity", handle.entity_id)));
+        }
+
+        self.bundle.clear();
+        let mut writer = self.bundle.element_writer();
+        writer.write_simple(SelectPlayerEntity {});
+        writer.write_simple(EntityMethod { inner: method });
+
+        self.protocol.channel(addr, None).send(&self.socket, &mut self.bundle, true)?;
+
+        Ok(())
+
     }
 
 }
@@ -192,8 +581,12 @@ impl App {
 #[derive(Debug)]
 pub enum Event {
     IoError(IoErrorEvent),
+    Tick(TickEvent),
+    DeliveryFailure(DeliveryFailureEvent),
     Login(LoginEvent),
-    // BaseMethod(BaseMethodEvent),
+    EnableEntities(EnableEntitiesEvent),
+    Disconnect(DisconnectEvent),
+    BaseMethod(BaseMethodEvent),
 }
 
 /// Some IO error happened internally and optionally related to a client.
@@ -205,6 +598,15 @@ pub struct IoErrorEvent {
     pub addr: Option<SocketAddr>,
 }
 
+/// A reliable packet sent to a client has not been acknowledged after all retries.
+#[derive(Debug)]
+pub struct DeliveryFailureEvent {
+    /// The address of the client the packet was sent to.
+    pub addr: SocketAddr,
+    /// The sequence number of the packet.
+    pub sequence_num: Seq,
+}
+
 /// A client is trying to connect.
 #[derive(Debug)]
 pub struct LoginEvent {
@@ -216,55 +618,111 @@ pub struct LoginEvent {
     pub attempt_num: u8,
 }
 
+/// A client has enabled its entities, after its player entity has been created or its
+/// entities have been reset.
+#[derive(Debug)]
+pub struct EnableEntitiesEvent {
+    /// The address of the client.
+    pub addr: SocketAddr,
+}
+
+/// A client has disconnected, it has been forgotten with all its entities.
+#[derive(Debug)]
+pub struct DisconnectEvent {
+    /// The address of the client.
+    pub addr: SocketAddr,
+    /// The reason given by the client.
+    pub reason: DisconnectReason,
+}
+
+/// A client has called a base method on its player entity, the method is kept raw 
+/// because the entity type is not known by the application, it can be decoded with
+/// [`Self::read`] using the base method type of the entity.
 #[derive(Debug)]
 pub struct BaseMethodEvent {
+    /// The address of the client.
     pub addr: SocketAddr,
+    /// The player entity of the client.
     pub entity_id: u32,
+    /// Exposed id of the method.
+    pub exposed_id: u16,
+    /// Raw arguments of the method.
+    pub data: Vec<u8>,
+}
+
+impl BaseMethodEvent {
+
+    /// Decode the method with the given base method type of the entity.
+    pub fn read<M: Method>(&self) -> io::Result<M> {
+        M::read(&mut &self.data[..], self.exposed_id)
+    }
 
 }
 
 /// A typed handle to an entity in the base app, potentially present on client side.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct Handle<E> {
     entity_id: u32,
     _phantom: PhantomData<*const E>,
 }
 
-/// A untyped handle to an entity in the base app, potentially present on client side.
-#[derive(Debug, Clone, Copy)]
-pub struct GenericHandle {
-    entity_id: u32,
-}
-
-/// An active logged in client in the base application.
-#[derive(Debug)]
-struct Client {
-    /// The session key for this client.
-    session_key: u32,
-    /// The blowfish key for encryption of this client's packets.
-    blowfish: Arc<Blowfish>,
-}
+impl<E> Handle<E> {
 
+    /// The unique identifier of the entity.
+    #[inline]
+    pub fn entity_id(&self) -> u32 {
+        self.entity_id
+    }
 
+    /// Erase the type of this handle.
+    #[inline]
+    pub fn generic(&self) -> GenericHandle {
+        GenericHandle { entity_id: self.entity_id }
+    }
 
-struct EntityGeneric {
-    // wrapper: Box<dyn EntityWrapper>,
 }
 
-impl fmt::Debug for EntityGeneric {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("EntityGeneric").finish()
+// Implemented manually because the entity type doesn't need to be clone.
+impl<E> Clone for Handle<E> {
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-trait EntityWrapper {
+impl<E> Copy for Handle<E> {}
 
+/// A untyped handle to an entity in the base app, potentially present on client side.
+#[derive(Debug, Clone, Copy)]
+pub struct GenericHandle {
+    entity_id: u32,
 }
 
-struct EntityWrapperImpl<E: Entity> {
-    inner: E,
+impl GenericHandle {
+
+    /// The unique identifier of the entity.
+    #[inline]
+    pub fn entity_id(&self) -> u32 {
+        self.entity_id
+    }
+
 }
 
-impl<E: Entity> EntityWrapper for EntityWrapperImpl<E> {
+/// An active logged in client in the base application.
+#[derive(Debug)]
+struct Client {
+    /// The session key for this client.
+    session_key: u32,
+    /// The player entity of this client, if created.
+    player_entity_id: Option<u32>,
+}
 
+/// Information about an alive entity, the entity itself is owned by the user.
+#[derive(Debug)]
+struct EntityInfo {
+    /// The client owning this entity.
+    addr: SocketAddr,
+    /// The entity type id.
+    entity_type_id: u16,
+    /// The decode length of the base methods of the entity type, by exposed id.
+    base_method_length: fn(u16) -> ElementLength,
 }
@@ -13,7 +13,8 @@ use crate::util::io::{WgReadExt, WgWriteExt};
 use crate::net::codec::SimpleCodec;
 use crate::util::AsciiFmt;
 
-use crate::net::app::common::entity::{Entity, Method};
+use crate::net::app::common::entity::{Entity, Method, NestedProperty, Properties, Property, PropertyPath, PropertyPathSegment};
+use crate::net::app::common::data::{PackedXYZ, PackedXZ, PackedYPR, unpack_angle, unpack_half_angle};
 
 
 /// Internal module containing all raw elements numerical ids.
@@ -100,6 +101,7 @@ pub mod id {
     pub const SET_CELL_APP_EXT_ADDRESS: u8                              = 0x4C;  // VAR 2 (1.26.1.1 handler: 143327F20)
     pub const LAST_PROXY_MESSAGE_AFTER_DIRECT_CELL_APP_CONNECTION: u8   = 0x4D;  // FIXED 0 (1.26.1.1 handler: 143327F68)
     
+    pub const AVATAR_UPDATE: ElementIdRange     = ElementIdRange::new(0x29, 0x40);
     pub const ENTITY_METHOD: ElementIdRange     = ElementIdRange::new(0x4E, 0xA6);  // CALLBACK 0 (1.26.1.1 handler: 143327F80)
     pub const ENTITY_PROPERTY: ElementIdRange   = ElementIdRange::new(0xA7, 0xFE);  // CALLBACK 0 (1.26.1.1 handler: 143327FA8)
 
@@ -108,6 +110,7 @@ pub mod id {
 
 crate::__struct_simple_codec! {
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct Authenticate {
         pub key: u32,
     }
@@ -121,6 +124,7 @@ impl SimpleElement for Authenticate {
 
 crate::__struct_simple_codec! {
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct BandwidthNotification {
         pub bps: u32,
     }
@@ -137,6 +141,7 @@ crate::__struct_simple_codec! {
     /// the the client, and also give the server game time (exactly the
     /// same as [`SetGameTime`] element, but inlined here).
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct UpdateFrequencyNotification {
         /// The frequency in hertz.
         pub frequency: u8,
@@ -156,6 +161,7 @@ impl SimpleElement for UpdateFrequencyNotification {
 crate::__struct_simple_codec! {
     /// The server informs us of the current (server) game time.
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct SetGameTime {
         /// The server game time.
         pub game_time: u32,
@@ -171,6 +177,7 @@ impl SimpleElement for SetGameTime {
 crate::__struct_simple_codec! {
     /// The server wants to resets the entities in the Area of Interest (AoI).
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct ResetEntities {
         pub keep_player_on_base: bool,
     }
@@ -283,14 +290,46 @@ pub type CellAppSuspended = DebugElementFixed<{ id::CELL_APP_SUSPENDED }, 0>;
 pub type CellAppResumed = DebugElementFixed<{ id::CELL_APP_RESUMED }, 0>;
 
 pub type ClientSuspensionDetectionEnabled = DebugElementFixed<{ id::CLIENT_SUSPENSION_DETECTION_ENABLED }, 4>;
-pub type EnterAoi = DebugElementFixed<{ id::ENTER_AOI }, 5>;
-pub type EnterAoiOnVehicle = DebugElementFixed<{ id::ENTER_AOI_ON_VEHICLE }, 9>;
+
+
+crate::__struct_simple_codec! {
+    /// An entity enters the Area of Interest (AoI) of the client, the entity is given
+    /// an alias that can later be used to select it or update its position.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct EnterAoi {
+        pub entity_id: u32,
+        pub alias: u8,
+    }
+
+    /// An entity enters the Area of Interest (AoI) of the client while being on a
+    /// vehicle, see [`EnterAoi`].
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct EnterAoiOnVehicle {
+        pub entity_id: u32,
+        pub vehicle_entity_id: u32,
+        pub alias: u8,
+    }
+}
+
+impl SimpleElement for EnterAoi {
+    const ID: u8 = id::ENTER_AOI;
+    const LEN: ElementLength = ElementLength::Fixed(5);
+}
+
+impl SimpleElement for EnterAoiOnVehicle {
+    const ID: u8 = id::ENTER_AOI_ON_VEHICLE;
+    const LEN: ElementLength = ElementLength::Fixed(9);
+}
+
 pub type LeaveAoi = DebugElementVariable16<{ id::LEAVE_AOI }>;
 
 
 crate::__struct_simple_codec! {
     /// It is used as a timestamp for the elements in a bundle.
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct TickSync {
         pub tick: u8,
     }
@@ -303,17 +342,82 @@ impl SimpleElement for TickSync {
 
 
 pub type TickSyncPeriodic = DebugElementFixed<{ id::TICK_SYNC_PERIODIC }, 2>;
-pub type RelativePositionReference = DebugElementFixed<{ id::RELATIVE_POSITION_REFERENCE }, 1>;
-pub type RelativePosition = DebugElementFixed<{ id::RELATIVE_POSITION }, 12>;
-pub type SetVehicle = DebugElementFixed<{ id::SET_VEHICLE }, 8>;
-pub type SelectAliasedEntity = DebugElementFixed<{ id::SELECT_ALIASED_ENTITY }, 1>;
-pub type SelectEntity = DebugElementFixed<{ id::SELECT_ENTITY }, 4>;
+
+
+crate::__struct_simple_codec! {
+    /// Sent by the server with the sequence number of the last position sent by the 
+    /// client that has been received, positions of subsequent avatar updates are then
+    /// relative to this reference.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct RelativePositionReference {
+        pub sequence_num: u8,
+    }
+
+    /// Sent by the server with the reference position of subsequent avatar updates.
+    #[derive(Debug, Clone)]
+    pub struct RelativePosition {
+        pub position: Vec3,
+    }
+
+    /// A passenger entity has boarded a vehicle entity, or alighted if the vehicle
+    /// entity id is zero.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct SetVehicle {
+        pub passenger_entity_id: u32,
+        pub vehicle_entity_id: u32,
+    }
+
+    /// Sent by the server to inform that subsequent elements will target the entity
+    /// with the given alias, as given when it entered the client's AoI.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct SelectAliasedEntity {
+        pub alias: u8,
+    }
+
+    /// Sent by the server to inform that subsequent elements will target the entity
+    /// with the given id.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct SelectEntity {
+        pub entity_id: u32,
+    }
+}
+
+impl SimpleElement for RelativePositionReference {
+    const ID: u8 = id::RELATIVE_POSITION_REFERENCE;
+    const LEN: ElementLength = ElementLength::Fixed(1);
+}
+
+impl SimpleElement for RelativePosition {
+    const ID: u8 = id::RELATIVE_POSITION;
+    const LEN: ElementLength = ElementLength::Fixed(12);
+}
+
+impl SimpleElement for SetVehicle {
+    const ID: u8 = id::SET_VEHICLE;
+    const LEN: ElementLength = ElementLength::Fixed(8);
+}
+
+impl SimpleElement for SelectAliasedEntity {
+    const ID: u8 = id::SELECT_ALIASED_ENTITY;
+    const LEN: ElementLength = ElementLength::Fixed(1);
+}
+
+impl SimpleElement for SelectEntity {
+    const ID: u8 = id::SELECT_ENTITY;
+    const LEN: ElementLength = ElementLength::Fixed(4);
+}
+
 
 
 crate::__struct_simple_codec! {
     /// Sent by the server to inform that subsequent elements will target
     /// the player entity.
     #[derive(Debug, Default, Clone, Copy)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct SelectPlayerEntity {}
 }
 
@@ -359,9 +463,214 @@ pub type NrlData = DebugElementVariable16<{ id::NRL_DATA }>;
 pub type NrlMsgToClient = DebugElementVariable16<{ id::NRL_MSG_TO_CLIENT }>;
 pub type NrlUnreliableMsgToClient = DebugElementVariable16<{ id::NRL_UNRELIABLE_MSG_TO_CLIENT }>;
 
-// TODO: Avatar update
 
-pub type ControlEntity = DebugElementFixed<{ id::CONTROL_ENTITY }, 5>;
+/// An update of the position and direction of an entity in the client's AoI, there
+/// are 24 elements for this, one for each combination of entity identification, 
+/// position and direction precision. The position is relative to the last
+/// [`RelativePosition`] and both position and direction are kept packed.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub struct AvatarUpdate {
+    /// The entity being updated.
+    pub id: AvatarUpdateId,
+    /// The packed position of the entity.
+    pub pos: AvatarUpdatePos,
+    /// The packed direction of the entity.
+    pub dir: AvatarUpdateDir,
+}
+
+/// Identification of the entity in an [`AvatarUpdate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub enum AvatarUpdateId {
+    /// The entity is identified by its id.
+    NoAlias(u32),
+    /// The entity is identified by the alias given when it entered the AoI.
+    Alias(u8),
+}
+
+/// Packed position in an [`AvatarUpdate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub enum AvatarUpdatePos {
+    /// The full position, packed on 5 bytes (x, z, then y).
+    Full(PackedXYZ),
+    /// The position on ground, without the y coordinate, packed on 3 bytes (x, z).
+    OnGround(PackedXZ),
+    /// The position is unchanged.
+    None,
+}
+
+/// Packed direction in an [`AvatarUpdate`], each angle is packed on a single byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub enum AvatarUpdateDir {
+    YawPitchRoll(i8, i8, i8),
+    YawPitch(i8, i8),
+    Yaw(i8),
+    /// The direction is unchanged.
+    None,
+}
+
+impl AvatarUpdatePos {
+
+    /// Unpack the position relative to the given reference position, none if the 
+    /// position is unchanged. For positions on ground, the reference's y coordinate
+    /// is kept.
+    pub fn unpack_relative(self, reference: Vec3) -> Option<Vec3> {
+        match self {
+            AvatarUpdatePos::Full(packed) => Some(packed.unpack_relative(reference)),
+            AvatarUpdatePos::OnGround(packed) => Some(packed.unpack_relative(reference)),
+            AvatarUpdatePos::None => None,
+        }
+    }
+
+}
+
+impl AvatarUpdateDir {
+
+    /// Unpack the yaw, pitch and roll angles, in radians, missing angles are none.
+    pub fn unpack(self) -> (Option<f32>, Option<f32>, Option<f32>) {
+        match self {
+            AvatarUpdateDir::YawPitchRoll(yaw, pitch, roll) => {
+                let (yaw, pitch, roll) = PackedYPR { yaw, pitch, roll }.unpack();
+                (Some(yaw), Some(pitch), Some(roll))
+            }
+            AvatarUpdateDir::YawPitch(yaw, pitch) => (Some(unpack_angle(yaw)), Some(unpack_half_angle(pitch)), None),
+            AvatarUpdateDir::Yaw(yaw) => (Some(unpack_angle(yaw)), None, None),
+            AvatarUpdateDir::None => (None, None, None),
+        }
+    }
+
+}
+
+impl AvatarUpdate {
+
+    /// Return the length of the avatar update element with the given id, which must be
+    /// within [`id::AVATAR_UPDATE`].
+    pub fn elt_len(elt_id: u8) -> usize {
+        let index = elt_id - id::AVATAR_UPDATE.first;
+        let id_len = if index / 12 == 0 { 4 } else { 1 };
+        let pos_len = [5, 3, 0][(index % 12 / 4) as usize];
+        let dir_len = [3, 2, 1, 0][(index % 4) as usize];
+        id_len + pos_len + dir_len
+    }
+
+    /// Return the element id to use for this update.
+    pub fn elt_id(&self) -> u8 {
+        let id_index = match self.id {
+            AvatarUpdateId::NoAlias(_) => 0,
+            AvatarUpdateId::Alias(_) => 1,
+        };
+        let pos_index = match self.pos {
+            AvatarUpdatePos::Full(_) => 0,
+            AvatarUpdatePos::OnGround(_) => 1,
+            AvatarUpdatePos::None => 2,
+        };
+        let dir_index = match self.dir {
+            AvatarUpdateDir::YawPitchRoll(..) => 0,
+            AvatarUpdateDir::YawPitch(..) => 1,
+            AvatarUpdateDir::Yaw(_) => 2,
+            AvatarUpdateDir::None => 3,
+        };
+        id::AVATAR_UPDATE.first + id_index * 12 + pos_index * 4 + dir_index
+    }
+
+}
+
+impl Element<()> for AvatarUpdate {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Ok(ElementLength::Fixed(Self::elt_len(self.elt_id()) as u32))
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+
+        match self.id {
+            AvatarUpdateId::NoAlias(entity_id) => write.write_u32(entity_id)?,
+            AvatarUpdateId::Alias(alias) => write.write_u8(alias)?,
+        }
+
+        match self.pos {
+            AvatarUpdatePos::Full(packed) => SimpleCodec::write(&packed, &mut *write)?,
+            AvatarUpdatePos::OnGround(packed) => SimpleCodec::write(&packed, &mut *write)?,
+            AvatarUpdatePos::None => {}
+        }
+
+        match self.dir {
+            AvatarUpdateDir::YawPitchRoll(yaw, pitch, roll) => {
+                write.write_i8(yaw)?;
+                write.write_i8(pitch)?;
+                write.write_i8(roll)?;
+            }
+            AvatarUpdateDir::YawPitch(yaw, pitch) => {
+                write.write_i8(yaw)?;
+                write.write_i8(pitch)?;
+            }
+            AvatarUpdateDir::Yaw(yaw) => write.write_i8(yaw)?,
+            AvatarUpdateDir::None => {}
+        }
+
+        Ok(self.elt_id())
+
+    }
+
+    fn read_length(_config: &(), id: u8) -> io::Result<ElementLength> {
+        if !id::AVATAR_UPDATE.contains(id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected avatar update element id: {id:02X}")));
+        }
+        Ok(ElementLength::Fixed(Self::elt_len(id) as u32))
+    }
+
+    fn read(read: &mut dyn Read, _config: &(), _len: usize, elt_id: u8) -> io::Result<Self> {
+
+        if !id::AVATAR_UPDATE.contains(elt_id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected avatar update element id: {elt_id:02X}")));
+        }
+
+        let index = elt_id - id::AVATAR_UPDATE.first;
+
+        let id = match index / 12 {
+            0 => AvatarUpdateId::NoAlias(read.read_u32()?),
+            _ => AvatarUpdateId::Alias(read.read_u8()?),
+        };
+
+        let pos = match index % 12 / 4 {
+            0 => AvatarUpdatePos::Full(SimpleCodec::read(&mut *read)?),
+            1 => AvatarUpdatePos::OnGround(SimpleCodec::read(&mut *read)?),
+            _ => AvatarUpdatePos::None,
+        };
+
+        let dir = match index % 4 {
+            0 => AvatarUpdateDir::YawPitchRoll(read.read_i8()?, read.read_i8()?, read.read_i8()?),
+            1 => AvatarUpdateDir::YawPitch(read.read_i8()?, read.read_i8()?),
+            2 => AvatarUpdateDir::Yaw(read.read_i8()?),
+            _ => AvatarUpdateDir::None,
+        };
+
+        Ok(Self { id, pos, dir })
+
+    }
+
+}
+
+
+crate::__struct_simple_codec! {
+    /// The client is given, or is withdrawn, the control of the given entity, the 
+    /// client then sends the position of this entity to the server.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+    pub struct ControlEntity {
+        pub entity_id: u32,
+        pub on: bool,
+    }
+}
+
+impl SimpleElement for ControlEntity {
+    const ID: u8 = id::CONTROL_ENTITY;
+    const LEN: ElementLength = ElementLength::Fixed(5);
+}
+
 pub type VoiceData = DebugElementVariable16<{ id::VOICE_DATA }>;
 pub type RestoreClient = DebugElementVariable16<{ id::RESTORE_CLIENT }>;
 pub type SwitchBaseApp = DebugElementFixed<{ id::SWITCH_BASE_APP }, 9>;
@@ -457,8 +766,9 @@ crate::__struct_simple_codec! {
     /// Sent by the server to inform that subsequent elements will target
     /// the player entity.
     #[derive(Debug, Default, Clone, Copy)]
+    #[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
     pub struct LoggedOff {
-        pub reason: u8,
+        pub reason: LoggedOffReason,
     }
 }
 
@@ -467,11 +777,336 @@ impl SimpleElement for LoggedOff {
     const LEN: ElementLength = ElementLength::Fixed(1);
 }
 
+/// Reason of the client being logged off by the server, other values than the known 
+/// ones are kept as unknown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoggedOffReason {
+    /// Regular log off, this is the value observed when the server terminates the 
+    /// session without a specific reason.
+    #[default]
+    Normal,
+    /// Unknown reason code.
+    Unknown(u8),
+}
+
+impl From<u8> for LoggedOffReason {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Normal,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<LoggedOffReason> for u8 {
+    fn from(value: LoggedOffReason) -> Self {
+        match value {
+            LoggedOffReason::Normal => 0,
+            LoggedOffReason::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for LoggedOffReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => f.write_str("normal"),
+            Self::Unknown(value) => write!(f, "unknown (0x{value:02X})"),
+        }
+    }
+}
+
+impl SimpleCodec for LoggedOffReason {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_u8((*self).into())
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        read.read_u8().map(Self::from)
+    }
+
+}
+
+/// Type of the battle queue given to the account's queue methods (enqueued, dequeued,
+/// enqueue failure and kicked from queue), known values are derived from the game's
+/// `QUEUE_TYPE` constants, other values are kept as unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueType {
+    /// Random battles.
+    Randoms,
+    /// Company battles.
+    Companies,
+    /// Volunteers battles.
+    Volunteers,
+    /// Event battles.
+    EventBattles,
+    /// Unit assembler, for squads and teams.
+    UnitAssembler,
+    /// Tutorial battles.
+    Tutorial,
+    /// Sandbox battles, for new players.
+    Sandbox,
+    /// Unknown queue type code.
+    Unknown(u8),
+}
+
+impl From<u8> for QueueType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Randoms,
+            2 => Self::Companies,
+            3 => Self::Volunteers,
+            4 => Self::EventBattles,
+            5 => Self::UnitAssembler,
+            6 => Self::Tutorial,
+            7 => Self::Sandbox,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<QueueType> for u8 {
+    fn from(value: QueueType) -> Self {
+        match value {
+            QueueType::Randoms => 1,
+            QueueType::Companies => 2,
+            QueueType::Volunteers => 3,
+            QueueType::EventBattles => 4,
+            QueueType::UnitAssembler => 5,
+            QueueType::Tutorial => 6,
+            QueueType::Sandbox => 7,
+            QueueType::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for QueueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Randoms => f.write_str("randoms"),
+            Self::Companies => f.write_str("companies"),
+            Self::Volunteers => f.write_str("volunteers"),
+            Self::EventBattles => f.write_str("event battles"),
+            Self::UnitAssembler => f.write_str("unit assembler"),
+            Self::Tutorial => f.write_str("tutorial"),
+            Self::Sandbox => f.write_str("sandbox"),
+            Self::Unknown(value) => write!(f, "unknown (0x{value:02X})"),
+        }
+    }
+}
+
+impl SimpleCodec for QueueType {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_u8((*self).into())
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        read.read_u8().map(Self::from)
+    }
+
+}
+
+
+
+crate::__struct_simple_codec! {
+    /// The detailed position and direction of the selected entity, unpacked.
+    #[derive(Debug, Clone)]
+    pub struct DetailedPosition {
+        pub position: Vec3,
+        pub direction: Vec3,
+    }
+}
+
+impl SimpleElement for DetailedPosition {
+    const ID: u8 = id::DETAILED_POSITION;
+    const LEN: ElementLength = ElementLength::Fixed(24);
+}
+
+
+
+/// A change of a nested property of the selected entity, such as a field of a dict or
+/// an element of an array. The path of the change is bit-packed and the number of bits
+/// of each index depends on the current size of its parent, so the change can only be
+/// decoded when applied to the current state of the entity, see [`Self::apply`].
+#[derive(Clone)]
+pub struct NestedEntityProperty {
+    pub data: Vec<u8>,
+}
+
+impl NestedEntityProperty {
+
+    /// Apply this change to the given entity, returning the path of the changed 
+    /// property, relative to the entity, and its new value.
+    pub fn apply<'e>(&self, entity: &'e mut dyn NestedProperty) -> io::Result<NestedPropertyChange<'e>> {
+
+        let mut bits = BitReader::new(&self.data);
+        let (mut path, parent) = read_nested_path(&mut bits, entity)?;
+
+        let index = bits.read(bits_required(parent.nested_len()))? as usize;
+        let (segment, child) = parent.nested_child_mut(index)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid nested property index: {index}")))?;
+
+        path.0.push(segment);
+        child.nested_read(&mut &self.data[bits.byte_len()..])?;
+
+        Ok(NestedPropertyChange { path, value: child })
+
+    }
+
+}
+
+impl SimpleCodec for NestedEntityProperty {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_blob(&self.data)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        Ok(Self { data: read.read_blob_to_end()? })
+    }
+
+}
+
+impl SimpleElement for NestedEntityProperty {
+    const ID: u8 = id::NESTED_ENTITY_PROPERTY;
+    const LEN: ElementLength = ElementLength::Variable16;
+}
+
+impl fmt::Debug for NestedEntityProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NestedEntityProperty")
+            .field("data", &AsciiFmt(&self.data))
+            .finish()
+    }
+}
+
+/// A change of a slice of a nested array property of the selected entity, the path is
+/// encoded like [`NestedEntityProperty`], followed by the range of the replaced 
+/// elements, the new elements follow the path until the end of the element.
+#[derive(Clone)]
+pub struct SliceEntityProperty {
+    pub data: Vec<u8>,
+}
+
+impl SliceEntityProperty {
+
+    /// Apply this change to the given entity, returning the path of the changed slice,
+    /// relative to the entity, and the whole array after the change.
+    pub fn apply<'e>(&self, entity: &'e mut dyn NestedProperty) -> io::Result<NestedPropertyChange<'e>> {
+
+        let mut bits = BitReader::new(&self.data);
+        let (mut path, array) = read_nested_path(&mut bits, entity)?;
+
+        // The range's end is inclusive of the array's length.
+        let index_bits = bits_required(array.nested_len() + 1);
+        let start = bits.read(index_bits)? as usize;
+        let end = bits.read(index_bits)? as usize;
+
+        array.nested_read_slice(start, end, &mut &self.data[bits.byte_len()..])?;
+        path.0.push(PropertyPathSegment::Slice(start, end));
+
+        Ok(NestedPropertyChange { path, value: array })
+
+    }
+
+}
+
+impl SimpleCodec for SliceEntityProperty {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_blob(&self.data)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        Ok(Self { data: read.read_blob_to_end()? })
+    }
+
+}
+
+impl SimpleElement for SliceEntityProperty {
+    const ID: u8 = id::SLICE_ENTITY_PROPERTY;
+    const LEN: ElementLength = ElementLength::Variable16;
+}
+
+impl fmt::Debug for SliceEntityProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SliceEntityProperty")
+            .field("data", &AsciiFmt(&self.data))
+            .finish()
+    }
+}
+
+/// A nested property change that has been applied to an entity.
+#[derive(Debug)]
+pub struct NestedPropertyChange<'e> {
+    /// Path of the changed property, relative to the entity.
+    pub path: PropertyPath,
+    /// The new value of the changed property, for slices this is the whole array.
+    pub value: &'e dyn NestedProperty,
+}
+
+/// Read the path of a nested change, each index of the path is prefixed by a set bit,
+/// and the path is terminated by a cleared bit. The path's last property is returned,
+/// it's the parent of the changed property, or the array for slice changes.
+fn read_nested_path<'e>(bits: &mut BitReader, entity: &'e mut dyn NestedProperty) -> io::Result<(PropertyPath, &'e mut dyn NestedProperty)> {
+
+    let mut path = PropertyPath::default();
+    let mut current = entity;
+
+    while bits.read(1)? != 0 {
+        let index = bits.read(bits_required(current.nested_len()))? as usize;
+        let (segment, child) = current.nested_child_mut(index)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid nested property index: {index}")))?;
+        path.0.push(segment);
+        current = child;
+    }
 
-pub type DetailedPosition = DebugElementFixed<{ id::DETAILED_POSITION }, 24>;
+    Ok((path, current))
+
+}
+
+/// Return the number of bits required to encode an index among the given number of
+/// values.
+fn bits_required(count: usize) -> u32 {
+    match count {
+        0 | 1 => 0,
+        count => usize::BITS - (count - 1).leading_zeros(),
+    }
+}
+
+/// A reader of bits, most significant bits of each byte first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Number of bits already read.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Read the given number of bits, at most 32.
+    fn read(&mut self, count: u32) -> io::Result<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = *self.data.get(self.pos / 8)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of nested property path"))?;
+            value = (value << 1) | ((byte >> (7 - self.pos % 8)) & 1) as u32;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    /// Return the number of bytes used by the bits already read.
+    fn byte_len(&self) -> usize {
+        self.pos.div_ceil(8)
+    }
 
-pub type NestedEntityProperty = DebugElementVariable16<{ id::NESTED_ENTITY_PROPERTY }>;
-pub type SliceEntityProperty = DebugElementVariable16<{ id::SLICE_ENTITY_PROPERTY }>;
+}
 pub type UpdateEntity = DebugElementVariable16<{ id::UPDATE_ENTITY }>;
 pub type SetCellAppExtAddress = DebugElementVariable16<{ id::SET_CELL_APP_EXT_ADDRESS }>;
 pub type LastProxyMessageAfterDirectCellAppConnection = DebugElementVariable16<{ id::LAST_PROXY_MESSAGE_AFTER_DIRECT_CELL_APP_CONNECTION }>;
@@ -519,3 +1154,121 @@ impl<M: Method> Element<()> for EntityMethod<M> {
     }
 
 }
+
+/// Codec for a property update of an entity, the given entity type should be the one
+/// of the entity being updated, and the index is the property's exposed index. Only
+/// encoding is supported because the entity is borrowed.
+/// FIXME: Like entity methods, this doesn't support sub message id.
+#[derive(Debug)]
+pub struct EntityProperty<'a, E: Properties> {
+    pub entity: &'a E,
+    pub index: u16,
+}
+
+impl<E: Properties> Element<()> for EntityProperty<'_, E> {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Ok(E::property_length(self.index))
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+        if self.index >= id::ENTITY_PROPERTY.slots_count() as u16 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("missing support for sub-id of property index: {}", self.index)));
+        }
+        self.entity.write_property(self.index, write)?;
+        Ok(id::ENTITY_PROPERTY.first + self.index as u8)
+    }
+
+    fn read_length(_config: &(), _id: u8) -> io::Result<ElementLength> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "entity property read not supported"))
+    }
+
+    fn read(_read: &mut dyn Read, _config: &(), _len: usize, _id: u8) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "entity property read not supported"))
+    }
+
+}
+
+
+/// Codec for a property update of an entity, decoded with the property type of the
+/// entity being updated, as opposed to [`EntityProperty`] this supports decoding.
+/// FIXME: Like entity methods, this doesn't support sub message id.
+#[derive(Debug, Clone)]
+pub struct EntityPropertyUpdate<P: Property> {
+    pub inner: P,
+}
+
+impl<P: Property> Element<()> for EntityPropertyUpdate<P> {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Ok(self.inner.write_length())
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+        let exposed_id = self.inner.write(write)?;
+        if exposed_id >= id::ENTITY_PROPERTY.slots_count() as u16 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "missing support for sub-id"));
+        }
+        Ok(id::ENTITY_PROPERTY.first + exposed_id as u8)
+    }
+
+    fn read_length(_config: &(), id: u8) -> io::Result<ElementLength> {
+        if !id::ENTITY_PROPERTY.contains(id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected entity property element id: {id:02X}")));
+        }
+        Ok(P::read_length((id - id::ENTITY_PROPERTY.first) as u16))
+    }
+
+    fn read(read: &mut dyn Read, _config: &(), _len: usize, id: u8) -> io::Result<Self> {
+        if !id::ENTITY_PROPERTY.contains(id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected entity property element id: {id:02X}")));
+        }
+        let inner = P::read(read, (id - id::ENTITY_PROPERTY.first) as u16)?;
+        Ok(Self {
+            inner,
+        })
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Check that every code is decoded and encoded back to itself, and that known 
+    /// codes are not decoded as unknown.
+    fn check_codes<T>(known: &[u8])
+    where
+        T: SimpleCodec + From<u8> + Into<u8> + Copy + fmt::Debug + fmt::Display,
+    {
+        for code in 0..=u8::MAX {
+
+            let mut data = Vec::new();
+            T::from(code).write(&mut data).unwrap();
+            assert_eq!(data, [code]);
+
+            let value = T::read(&mut &data[..]).unwrap();
+            assert_eq!(value.into(), code);
+
+            let unknown = value.to_string().starts_with("unknown");
+            assert_eq!(unknown, !known.contains(&code), "{value:?}");
+
+        }
+    }
+
+    #[test]
+    fn logged_off_reason() {
+        check_codes::<LoggedOffReason>(&[0]);
+        assert_eq!(LoggedOffReason::from(0x12).to_string(), "unknown (0x12)");
+    }
+
+    #[test]
+    fn queue_type() {
+        check_codes::<QueueType>(&[1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(QueueType::from(1), QueueType::Randoms);
+        assert_eq!(QueueType::from(0), QueueType::Unknown(0));
+    }
+
+}
@@ -1,4 +1,5 @@
 //! Client application implemented by the client.
 
 pub mod element;
+pub mod resource;
 
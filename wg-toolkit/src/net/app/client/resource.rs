@@ -0,0 +1,669 @@
+//! Downstream resource streaming, where the server sends a resource header followed
+//! by many fragments that are reassembled by the client. Both the downloading half,
+//! used by the client, and the uploading half, used by the server, are provided.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::util::io::{serde_pickle_de_options, serde_pickle_ser_options};
+
+use super::element::{ResourceFragment, ResourceHeader};
+
+
+/// The default time after which a partial resource that received no new fragment is
+/// abandoned.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// The default maximum number of bytes that can be buffered across all partial
+/// resources.
+pub const DEFAULT_MAX_LEN: usize = 64 * 1024 * 1024;
+
+/// The default number of bytes after which a partial resource is spilled to disk, if
+/// a spill directory is set.
+pub const DEFAULT_SPILL_THRESHOLD: usize = 1024 * 1024;
+
+/// The default maximum length of the data of each fragment when uploading.
+pub const DEFAULT_FRAGMENT_LEN: usize = 8 * 1024;
+
+/// The maximum length of the data of each fragment, because the fragment element has
+/// a 16-bit variable length, minus the fragment's header (id, sequence num, last).
+pub const MAX_FRAGMENT_LEN: usize = u16::MAX as usize - 4;
+
+
+/// A component that reassembles resources from their header and fragments elements,
+/// checking the sequence number of each fragment, and the length and CRC32 of the
+/// complete resource against its description. Completed, abandoned or invalid
+/// resources are returned as events by [`Self::poll`].
+#[derive(Debug)]
+pub struct ResourceDownloader {
+    /// Resources being downloaded, a header must have been received.
+    partials: HashMap<u16, PartialResource>,
+    /// Total number of bytes currently buffered in partial resources.
+    partials_len: usize,
+    /// Time after which a partial resource is abandoned.
+    ttl: Duration,
+    /// Maximum number of bytes buffered in partial resources.
+    max_len: usize,
+    /// The directory where large partial resources are spilled, if enabled.
+    spill_dir: Option<PathBuf>,
+    /// Number of bytes after which a partial resource is spilled.
+    spill_threshold: usize,
+    /// Queue of events to be polled.
+    events: VecDeque<ResourceEvent>,
+}
+
+/// Describe a partial resource being download, a header must have been sent.
+#[derive(Debug)]
+struct PartialResource {
+    /// The byte description sent in the resource header.
+    description: Vec<u8>,
+    /// The next sequence number expected, any other sequence number abort the download.
+    sequence_num: u8,
+    /// The full assembled data, empty if spilled.
+    data: Vec<u8>,
+    /// The file where the data is spilled, if the resource was too large.
+    spill: Option<SpillFile>,
+    /// The last time the header or a fragment has been received for this resource.
+    last_update: Instant,
+}
+
+/// A partial resource spilled to disk, the file starts with the next sequence number
+/// expected, followed by the data assembled so far. The file is named after the 
+/// resource id, length and CRC32 so that it can be resumed.
+#[derive(Debug)]
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+    /// Length of the data in the file, excluding the sequence number.
+    len: usize,
+}
+
+impl PartialResource {
+
+    /// Return the number of bytes assembled so far, in memory or spilled.
+    fn len(&self) -> usize {
+        match &self.spill {
+            Some(spill) => spill.len,
+            None => self.data.len(),
+        }
+    }
+
+}
+
+impl ResourceDownloader {
+
+    pub fn new() -> Self {
+        Self {
+            partials: HashMap::new(),
+            partials_len: 0,
+            ttl: DEFAULT_TTL,
+            max_len: DEFAULT_MAX_LEN,
+            spill_dir: None,
+            spill_threshold: DEFAULT_SPILL_THRESHOLD,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Set the directory where large partial resources are spilled, none to keep all
+    /// partial resources in memory, which is the default. Spilled resources are not
+    /// accounted in the buffered length, and they can be resumed by another downloader
+    /// using the same directory, for example after a restart, when a fragment is 
+    /// received without prior header.
+    pub fn set_spill_dir(&mut self, dir: Option<PathBuf>) -> io::Result<()> {
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir)?;
+        }
+        self.spill_dir = dir;
+        Ok(())
+    }
+
+    /// Set the number of bytes after which a partial resource is spilled to disk, if a
+    /// spill directory is set, defaults to [`DEFAULT_SPILL_THRESHOLD`].
+    pub fn set_spill_threshold(&mut self, spill_threshold: usize) {
+        self.spill_threshold = spill_threshold;
+    }
+
+    /// Set the time after which a partial resource that received no new fragment is
+    /// abandoned, defaults to [`DEFAULT_TTL`].
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    /// Set the maximum number of bytes that can be buffered across all partial
+    /// resources, when exceeded, the oldest partial resources are abandoned until it
+    /// fits again, defaults to [`DEFAULT_MAX_LEN`].
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+    }
+
+    /// Return the number of resources currently being downloaded.
+    pub fn len(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Return true if no resource is currently being downloaded.
+    pub fn is_empty(&self) -> bool {
+        self.partials.is_empty()
+    }
+
+    /// Return the total number of bytes currently buffered.
+    pub fn buffered_len(&self) -> usize {
+        self.partials_len
+    }
+
+    /// Handle a resource header, starting a new download, any previous download with
+    /// the same id is abandoned.
+    pub fn handle_header(&mut self, header: ResourceHeader, now: Instant) {
+
+        let prev = self.partials.insert(header.id, PartialResource {
+            description: header.description,
+            sequence_num: 0,
+            data: Vec::new(),
+            spill: None,
+            last_update: now,
+        });
+
+        if let Some(prev) = prev {
+            self.abandon(header.id, prev, ResourceAbandonReason::Overwritten);
+        }
+
+        // A new header restarts the download, so any spilled resource with the same id
+        // that could have been resumed is now stale.
+        self.remove_spill_files(header.id);
+
+    }
+
+    /// Handle a resource fragment, if this is the last fragment then the resource is
+    /// checked and a completion event (or error) is queued.
+    pub fn handle_fragment(&mut self, fragment: ResourceFragment, now: Instant) {
+
+        let id = fragment.id;
+
+        if !self.partials.contains_key(&id) {
+            match self.resume(id, now) {
+                Some(partial) => {
+                    self.events.push_back(ResourceEvent::Resume(ResourceResumeEvent {
+                        id,
+                        len: partial.len(),
+                    }));
+                    self.partials.insert(id, partial);
+                }
+                None => {
+                    self.events.push_back(ResourceEvent::Error(ResourceErrorEvent {
+                        id,
+                        error: ResourceError::MissingHeader,
+                    }));
+                    return;
+                }
+            }
+        }
+
+        // Unwrap because it has been checked or inserted just before.
+        let partial = self.partials.get_mut(&id).unwrap();
+
+        if fragment.sequence_num != partial.sequence_num {
+            let partial = self.partials.remove(&id).unwrap();
+            self.abandon(id, partial, ResourceAbandonReason::InvalidSequence);
+            return;
+        }
+
+        partial.sequence_num = partial.sequence_num.wrapping_add(1);
+        partial.last_update = now;
+
+        if let Some(spill) = &mut partial.spill {
+            if let Err(e) = spill.append(partial.sequence_num, &fragment.data) {
+                let partial = self.partials.remove(&id).unwrap();
+                remove_spill(partial);
+                self.events.push_back(ResourceEvent::Error(ResourceErrorEvent {
+                    id,
+                    error: ResourceError::Io(e.to_string()),
+                }));
+                return;
+            }
+        } else {
+
+            partial.data.extend_from_slice(&fragment.data);
+            self.partials_len += fragment.data.len();
+
+            // Spilling is not critical, the resource is kept in memory on error.
+            if !fragment.last && partial.data.len() >= self.spill_threshold {
+                if let Some(spill_dir) = &self.spill_dir {
+                    if let Ok(spill) = SpillFile::create(spill_dir, id, partial) {
+                        self.partials_len -= partial.data.len();
+                        partial.data = Vec::new();
+                        partial.spill = Some(spill);
+                    }
+                }
+            }
+
+        }
+
+        if fragment.last {
+            let partial = self.partials.remove(&id).unwrap();
+            self.partials_len -= partial.data.len();
+            self.events.push_back(match complete(partial) {
+                Ok((description, data)) => ResourceEvent::Complete(ResourceCompleteEvent { id, description, data }),
+                Err(error) => ResourceEvent::Error(ResourceErrorEvent { id, error }),
+            });
+        }
+
+    }
+
+    /// Try to resume a partial resource spilled with the given id, returning none if
+    /// there is no spill directory or no such resource.
+    fn resume(&self, id: u16, now: Instant) -> Option<PartialResource> {
+
+        let spill_dir = self.spill_dir.as_deref()?;
+        let prefix = format!("{id:04X}_");
+
+        for entry in fs::read_dir(spill_dir).ok()?.flatten() {
+
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else { continue };
+            let Some(key) = file_name.strip_prefix(&prefix).and_then(|key| key.strip_suffix(SPILL_EXTENSION)) else { continue };
+
+            let Some((len, crc32)) = key.split_once('_') else { continue };
+            let (Ok(len), Ok(crc32)) = (u32::from_str_radix(len, 16), u32::from_str_radix(crc32, 16)) else { continue };
+
+            let path = entry.path();
+            let Ok(spill) = SpillFile::open(path) else { continue };
+
+            let mut sequence_num = [0; 1];
+            let mut file = &spill.file;
+            if file.seek(SeekFrom::Start(0)).and_then(|_| file.read_exact(&mut sequence_num)).is_err() {
+                continue;
+            }
+
+            return Some(PartialResource {
+                description: ResourceDescription { len, crc32 }.to_bytes(),
+                sequence_num: sequence_num[0],
+                data: Vec::new(),
+                spill: Some(spill),
+                last_update: now,
+            });
+
+        }
+
+        None
+
+    }
+
+    /// Remove all spilled resources with the given id.
+    fn remove_spill_files(&self, id: u16) {
+
+        let Some(spill_dir) = self.spill_dir.as_deref() else { return };
+        let Ok(read_dir) = fs::read_dir(spill_dir) else { return };
+        let prefix = format!("{id:04X}_");
+
+        for entry in read_dir.flatten() {
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else { continue };
+            if file_name.starts_with(&prefix) && file_name.ends_with(SPILL_EXTENSION) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+
+    }
+
+    /// Poll the next event, this first abandon all partial resources that are expired
+    /// or over capacity. This should be called repeatedly until it returns none.
+    pub fn poll(&mut self, now: Instant) -> Option<ResourceEvent> {
+
+        let expired_ids = self.partials.iter()
+            .filter(|(_, partial)| now.saturating_duration_since(partial.last_update) >= self.ttl)
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        for id in expired_ids {
+            let partial = self.partials.remove(&id).unwrap();
+            self.abandon(id, partial, ResourceAbandonReason::Expired);
+        }
+
+        while self.partials_len > self.max_len {
+            let Some(oldest_id) = self.partials.iter()
+                .min_by_key(|(_, partial)| partial.last_update)
+                .map(|(&id, _)| id) else { break };
+            let partial = self.partials.remove(&oldest_id).unwrap();
+            self.abandon(oldest_id, partial, ResourceAbandonReason::OverCapacity);
+        }
+
+        self.events.pop_front()
+
+    }
+
+    /// Internal function to account for a removed partial resource and queue the
+    /// abandon event.
+    fn abandon(&mut self, id: u16, partial: PartialResource, reason: ResourceAbandonReason) {
+        self.partials_len -= partial.data.len();
+        self.events.push_back(ResourceEvent::Abandon(ResourceAbandonEvent {
+            id,
+            len: partial.len(),
+            reason,
+        }));
+        remove_spill(partial);
+    }
+
+}
+
+impl Default for ResourceDownloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A component that splits resources into a header and fragments elements, allocating
+/// a new resource id for each uploaded resource.
+#[derive(Debug)]
+pub struct ResourceUploader {
+    /// The next resource id to allocate.
+    next_id: u16,
+    /// Maximum length of the data of each fragment.
+    fragment_len: usize,
+}
+
+impl ResourceUploader {
+
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            fragment_len: DEFAULT_FRAGMENT_LEN,
+        }
+    }
+
+    /// Set the maximum length of the data of each fragment, defaults to 
+    /// [`DEFAULT_FRAGMENT_LEN`], the length is clamped to [`MAX_FRAGMENT_LEN`].
+    pub fn set_fragment_len(&mut self, fragment_len: usize) {
+        self.fragment_len = fragment_len.clamp(1, MAX_FRAGMENT_LEN);
+    }
+
+    /// Split the given resource data into a header and its fragments, with a newly
+    /// allocated resource id. The elements should be sent in order, the header first.
+    pub fn upload(&mut self, data: &[u8]) -> ResourceUpload {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        ResourceUpload::new(id, data, self.fragment_len)
+    }
+
+}
+
+impl Default for ResourceUploader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A resource that has been split into its header and fragments elements.
+#[derive(Debug, Clone)]
+pub struct ResourceUpload {
+    /// The header to send first.
+    pub header: ResourceHeader,
+    /// The fragments to send in order after the header, the last one has its last flag
+    /// set, there is always at least one fragment.
+    pub fragments: Vec<ResourceFragment>,
+}
+
+impl ResourceUpload {
+
+    /// Split the given resource data into a header and its fragments, given the id of 
+    /// the resource and the maximum length of the data of each fragment.
+    pub fn new(id: u16, data: &[u8], fragment_len: usize) -> Self {
+
+        let description = ResourceDescription {
+            len: data.len() as u32,
+            crc32: crc32fast::hash(data),
+        };
+
+        let fragment_len = fragment_len.clamp(1, MAX_FRAGMENT_LEN);
+        let mut fragments = data.chunks(fragment_len)
+            .enumerate()
+            .map(|(i, chunk)| ResourceFragment {
+                id,
+                sequence_num: i as u8,
+                last: false,
+                data: chunk.to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        // An empty resource still needs its last fragment.
+        if fragments.is_empty() {
+            fragments.push(ResourceFragment {
+                id,
+                sequence_num: 0,
+                last: false,
+                data: Vec::new(),
+            });
+        }
+
+        fragments.last_mut().unwrap().last = true;
+
+        Self {
+            header: ResourceHeader {
+                id,
+                description: description.to_bytes(),
+            },
+            fragments,
+        }
+
+    }
+
+}
+
+/// Extension of the spilled partial resources' files.
+const SPILL_EXTENSION: &str = ".part";
+
+impl SpillFile {
+
+    /// Create the spill file of the given partial resource, writing its sequence number
+    /// and data, the description must be valid because it's used in the file name.
+    fn create(spill_dir: &Path, id: u16, partial: &PartialResource) -> io::Result<Self> {
+
+        let description = ResourceDescription::from_bytes(&partial.description)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let path = spill_dir.join(format!("{id:04X}_{:08X}_{:08X}{SPILL_EXTENSION}", description.len, description.crc32));
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+
+        let res = file.write_all(&[partial.sequence_num]).and_then(|_| file.write_all(&partial.data));
+        if let Err(e) = res {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+
+        Ok(Self { path, file, len: partial.data.len() })
+
+    }
+
+    /// Open an existing spill file.
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let len = (file.metadata()?.len() as usize).saturating_sub(1);
+        Ok(Self { path, file, len })
+    }
+
+    /// Append data to the spill file and update the next sequence number expected.
+    fn append(&mut self, sequence_num: u8, data: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(data)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&[sequence_num])?;
+        self.len += data.len();
+        Ok(())
+    }
+
+    /// Read back all the data of the spill file.
+    fn read_data(&mut self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.len);
+        self.file.seek(SeekFrom::Start(1))?;
+        self.file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+}
+
+/// Remove the spill file of a partial resource, if spilled.
+fn remove_spill(partial: PartialResource) {
+    if let Some(spill) = partial.spill {
+        drop(spill.file);
+        let _ = fs::remove_file(&spill.path);
+    }
+}
+
+/// Check a completed resource against its description, the spill file is removed.
+fn complete(mut partial: PartialResource) -> Result<(ResourceDescription, Vec<u8>), ResourceError> {
+
+    if let Some(mut spill) = partial.spill.take() {
+        let data = spill.read_data();
+        drop(spill.file);
+        let _ = fs::remove_file(&spill.path);
+        partial.data = data.map_err(|e| ResourceError::Io(e.to_string()))?;
+    }
+
+    let description = ResourceDescription::from_bytes(&partial.description)?;
+
+    let actual_len = partial.data.len();
+    if actual_len != description.len as usize {
+        return Err(ResourceError::InvalidLength { expected: description.len, actual: actual_len });
+    }
+
+    let actual_crc32 = crc32fast::hash(&partial.data);
+    if actual_crc32 != description.crc32 {
+        return Err(ResourceError::InvalidCrc32 { expected: description.crc32, actual: actual_crc32 });
+    }
+
+    Ok((description, partial.data))
+
+}
+
+/// The description of a resource, sent in the resource header as a pickled tuple.
+///
+/// See: scripts/client/game.py#L223
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceDescription {
+    /// Total length of the resource data.
+    pub len: u32,
+    /// CRC32 of the resource data.
+    pub crc32: u32,
+}
+
+impl ResourceDescription {
+
+    /// Parse the pickled description.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ResourceError> {
+        match serde_pickle::value_from_reader(bytes, serde_pickle_de_options()) {
+            Ok(serde_pickle::Value::Tuple(values)) => {
+                if let &[serde_pickle::Value::I64(len), serde_pickle::Value::I64(crc32)] = &values[..] {
+                    Ok(Self { len: len as u32, crc32: crc32 as u32 })
+                } else {
+                    Err(ResourceError::InvalidDescription(format!("unexpected values: {values:?}")))
+                }
+            }
+            Ok(v) => Err(ResourceError::InvalidDescription(format!("python: {v}"))),
+            Err(e) => Err(ResourceError::InvalidDescription(e.to_string())),
+        }
+    }
+
+    /// Encode the description to its pickled form.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let value = serde_pickle::Value::Tuple(vec![
+            serde_pickle::Value::I64(self.len as i64),
+            serde_pickle::Value::I64(self.crc32 as i64),
+        ]);
+        // Unwrap because serializing integers should not fail.
+        serde_pickle::value_to_vec(&value, serde_pickle_ser_options()).unwrap()
+    }
+
+}
+
+/// An event returned by the resource downloader.
+#[derive(Debug)]
+pub enum ResourceEvent {
+    Complete(ResourceCompleteEvent),
+    Resume(ResourceResumeEvent),
+    Abandon(ResourceAbandonEvent),
+    Error(ResourceErrorEvent),
+}
+
+/// A resource has been completely downloaded and checked.
+#[derive(Debug)]
+pub struct ResourceCompleteEvent {
+    /// The resource id.
+    pub id: u16,
+    /// The resource description that has been checked.
+    pub description: ResourceDescription,
+    /// The full resource data.
+    pub data: Vec<u8>,
+}
+
+/// A partial resource spilled to disk has been resumed after a fragment has been 
+/// received without prior header.
+#[derive(Debug)]
+pub struct ResourceResumeEvent {
+    /// The resource id.
+    pub id: u16,
+    /// Number of bytes that were already spilled.
+    pub len: usize,
+}
+
+/// A partial resource has been abandoned before being completed.
+#[derive(Debug)]
+pub struct ResourceAbandonEvent {
+    /// The resource id.
+    pub id: u16,
+    /// Number of bytes that were already buffered.
+    pub len: usize,
+    /// The reason for abandoning.
+    pub reason: ResourceAbandonReason,
+}
+
+/// The reason for abandoning a partial resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceAbandonReason {
+    /// No fragment has been received for too long.
+    Expired,
+    /// The total buffered bytes exceeded the cap, and this was the oldest resource.
+    OverCapacity,
+    /// A new header has been received for the same resource id.
+    Overwritten,
+    /// A fragment has been received with an unexpected sequence number.
+    InvalidSequence,
+}
+
+/// A resource fragment or a completed resource is invalid.
+#[derive(Debug)]
+pub struct ResourceErrorEvent {
+    /// The resource id.
+    pub id: u16,
+    /// The error.
+    pub error: ResourceError,
+}
+
+/// Errors that can happen while downloading a resource.
+#[derive(Debug, Clone, Error)]
+pub enum ResourceError {
+    /// A fragment has been received without prior header.
+    #[error("missing header")]
+    MissingHeader,
+    /// The description in the header is invalid.
+    #[error("invalid description: {0}")]
+    InvalidDescription(String),
+    /// The completed resource doesn't have the length given in description.
+    #[error("invalid length, expected: {expected}, got: {actual}")]
+    InvalidLength {
+        expected: u32,
+        actual: usize,
+    },
+    /// The completed resource doesn't have the CRC32 given in description.
+    #[error("invalid crc32, expected: 0x{expected:08X}, got: 0x{actual:08X}")]
+    InvalidCrc32 {
+        expected: u32,
+        actual: u32,
+    },
+    /// The spill file of the resource could not be written or read back.
+    #[error("io error: {0}")]
+    Io(String),
+}
@@ -1,13 +1,11 @@
 //! Definition of common data types that can be transferred as entity or method calls.
 
 use std::io::{self, Read, Write};
-use std::borrow::Cow;
-use std::fmt;
 
 pub use glam::{Vec2, Vec3, Vec4};
 
-use crate::util::io::{WgReadExt, WgWriteExt, serde_pickle_de_options, serde_pickle_ser_options};
-use crate::util::AsciiFmt;
+use crate::util::io::{WgReadExt, WgWriteExt};
+use crate::net::codec::SimpleCodec;
 
 
 /// Represent an element data type
@@ -48,101 +46,29 @@ impl DataType for String {
 }
 
 
-/// The string data type used by default for all STRING types, it will try to 
-#[derive(Clone)]
-pub enum AutoString {
-    String(String),
-    Python(serde_pickle::Value),
-    Raw(Vec<u8>),
-}
-
-impl fmt::Debug for AutoString {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::String(string) => f.debug_tuple("Utf8").field(string).finish(),
-            Self::Python(value) => f.debug_tuple("Python").field(&format_args!("{value}")).finish(),
-            Self::Raw(bytes) => f.debug_tuple("Raw").field(&AsciiFmt(&bytes)).finish(),
-        }
-    }
-}
-
-impl DataType for AutoString {
-
-    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
-        write.write_blob_variable(&*(match self {
-            AutoString::String(v) => Cow::Borrowed(v.as_bytes()),
-            AutoString::Python(v) => Cow::Owned(serde_pickle::value_to_vec(v, serde_pickle_ser_options()).unwrap()),
-            AutoString::Raw(v) => Cow::Borrowed(&v[..]),
-        }))
-    }
-
-    fn read(read: &mut dyn Read) -> io::Result<Self> {
-        
-        let raw = read.read_blob_variable()?;
-
-        if let Ok(v) = serde_pickle::value_from_reader(&raw[..], serde_pickle_de_options()) {
-            return Ok(Self::Python(v));
-        }
-
-        match String::from_utf8(raw) {
-            Ok(s) => Ok(Self::String(s)),
-            Err(e) => Ok(Self::Raw(e.into_bytes())),
-        }
-
-    }
-
-}
-
-
-/// The Python builtin data type.
-pub struct Python {
-    /// Internal pickle value.
-    pub value: serde_pickle::Value,
-}
-
-impl DataType for Python {
+/// The string, Python and mailbox types are the ones of the codec module, they are
+/// data types through their simple codec.
+pub use crate::net::codec::{AutoString, Python, Mailbox};
 
-    #[inline(always)]
-    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
-        write.write_python_pickle(&self.value)
-    }
+macro_rules! impl_simple_codec {
+    ($($ty:ty),*) => {
+        $(impl DataType for $ty {
 
-    #[inline(always)]
-    fn read(read: &mut dyn Read) -> io::Result<Self> {
-        read.read_python_pickle().map(|value| Self { value })
-    }
-
-}
-
-impl fmt::Debug for Python {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("Python")
-            .field(&format_args!("{}", self.value))
-            .finish()
-    }
-}
+            #[inline]
+            fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+                SimpleCodec::write(self, write)
+            }
 
+            #[inline]
+            fn read(read: &mut dyn Read) -> io::Result<Self> {
+                <Self as SimpleCodec>::read(read)
+            }
 
-/// The mailbox type used sparingly in method calls.
-#[derive(Debug)]
-pub struct Mailbox {
-    pub entity_id: u32,
-    pub address: (), // TODO: 
+        })*
+    };
 }
 
-impl DataType for Mailbox {
-
-    #[inline]
-    fn write(&self, _write: &mut dyn Write) -> io::Result<()> {
-        Err(io::Error::new(io::ErrorKind::InvalidData, "mailbox write not yet supported"))
-    }
-
-    #[inline]
-    fn read(_read: &mut dyn Read) -> io::Result<Self> {
-        Err(io::Error::new(io::ErrorKind::InvalidData, "mailbox write not yet supported"))
-    }
-
-}
+impl_simple_codec!(AutoString, Python, Mailbox);
 
 
 macro_rules! impl_builtin_copy {
@@ -225,6 +151,215 @@ impl<D: DataType> DataType for Vec<D> {
 }
 
 
+/// A packed x and z coordinates, each coordinate is packed on 12 bits: 1 sign bit, 3
+/// exponent bits and 8 mantissa bits, this is the BigWorld format used for positions
+/// on ground. Coordinates are in range (-510, 510), and are clamped if out of range,
+/// the precision decreases with the distance to zero, from 1/128 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub struct PackedXZ(pub [u8; 3]);
+
+impl PackedXZ {
+
+    /// Pack the given x and z coordinates.
+    pub fn pack(x: f32, z: f32) -> Self {
+        let data = (pack_float(x, 3) << 12) | pack_float(z, 3);
+        Self([(data >> 16) as u8, (data >> 8) as u8, data as u8])
+    }
+
+    /// Unpack the x and z coordinates.
+    pub fn unpack(self) -> (f32, f32) {
+        let data = u32::from_be_bytes([0, self.0[0], self.0[1], self.0[2]]);
+        (unpack_float(data >> 12, 3), unpack_float(data & 0xFFF, 3))
+    }
+
+    /// Unpack the coordinates relative to the given reference position, the y 
+    /// coordinate of the reference is kept.
+    pub fn unpack_relative(self, reference: Vec3) -> Vec3 {
+        let (x, z) = self.unpack();
+        Vec3::new(reference.x + x, reference.y, reference.z + z)
+    }
+
+}
+
+/// A packed position, the x and z coordinates are packed like [`PackedXZ`], and the 
+/// y coordinate is packed on 16 bits: 1 sign bit, 4 exponent bits and 11 mantissa 
+/// bits, so it's in range (-131070, 131070).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub struct PackedXYZ {
+    pub xz: PackedXZ,
+    pub y: [u8; 2],
+}
+
+impl PackedXYZ {
+
+    /// Pack the given position.
+    pub fn pack(pos: Vec3) -> Self {
+        let y = pack_float(pos.y, 4) as u16;
+        Self {
+            xz: PackedXZ::pack(pos.x, pos.z),
+            y: y.to_be_bytes(),
+        }
+    }
+
+    /// Unpack the position.
+    pub fn unpack(self) -> Vec3 {
+        let (x, z) = self.xz.unpack();
+        let y = unpack_float(u16::from_be_bytes(self.y) as u32, 4);
+        Vec3::new(x, y, z)
+    }
+
+    /// Unpack the position relative to the given reference position.
+    pub fn unpack_relative(self, reference: Vec3) -> Vec3 {
+        reference + self.unpack()
+    }
+
+}
+
+/// A packed direction, each angle is packed on a single byte, yaw and roll are in
+/// range [-pi, pi) with a precision of pi/128 and pitch is in range [-pi/2, pi/2] 
+/// with a precision of pi/254.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub struct PackedYPR {
+    pub yaw: i8,
+    pub pitch: i8,
+    pub roll: i8,
+}
+
+impl PackedYPR {
+
+    /// Pack the given yaw, pitch and roll angles, in radians.
+    pub fn pack(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self {
+            yaw: pack_angle(yaw),
+            pitch: pack_half_angle(pitch),
+            roll: pack_angle(roll),
+        }
+    }
+
+    /// Unpack the yaw, pitch and roll angles, in radians.
+    pub fn unpack(self) -> (f32, f32, f32) {
+        (unpack_angle(self.yaw), unpack_half_angle(self.pitch), unpack_angle(self.roll))
+    }
+
+}
+
+/// Pack an angle in range [-pi, pi) on a single byte.
+pub fn pack_angle(angle: f32) -> i8 {
+    ((angle * 128.0) / std::f32::consts::PI + 0.5).floor() as i32 as i8
+}
+
+/// Unpack an angle packed with [`pack_angle`].
+pub fn unpack_angle(packed: i8) -> f32 {
+    packed as f32 * std::f32::consts::PI / 128.0
+}
+
+/// Pack an angle in range [-pi/2, pi/2] on a single byte.
+pub fn pack_half_angle(angle: f32) -> i8 {
+    ((angle * 254.0) / std::f32::consts::PI + 0.5).floor() as i32 as i8
+}
+
+/// Unpack an angle packed with [`pack_half_angle`].
+pub fn unpack_half_angle(packed: i8) -> f32 {
+    packed as f32 * std::f32::consts::PI / 254.0
+}
+
+/// Pack a float on 1 sign bit, the given number of exponent bits and 8 mantissa bits
+/// for 3 exponent bits, or 11 for 4 exponent bits, the returned value has the sign bit 
+/// on its highest bit. The absolute value is offset by 2 so that its exponent is in
+/// the range that can be represented.
+fn pack_float(value: f32, exp_bits: u32) -> u32 {
+
+    // Number of bits of the packed value, without the sign.
+    let bits = if exp_bits == 3 { 11 } else { 15 };
+    let max = (1 << bits) - 1;
+
+    let sign = value.is_sign_negative() as u32;
+    let offset = (value.abs() + 2.0).to_bits();
+
+    // The exponent of the offset value is at least 1 (2.0), its highest bit is always
+    // set, the value overflows if any other high bit is set.
+    let overflow_mask = 0x3F80_0000 & !(((1 << exp_bits) - 1) << 23);
+    let packed = if value.is_nan() {
+        0
+    } else if offset & overflow_mask != 0 {
+        max
+    } else {
+        (offset >> (23 + exp_bits - bits)) & max
+    };
+
+    (sign << bits) | packed
+
+}
+
+/// Unpack a float packed with [`pack_float`].
+fn unpack_float(packed: u32, exp_bits: u32) -> f32 {
+
+    let bits = if exp_bits == 3 { 11 } else { 15 };
+    let max = (1 << bits) - 1;
+
+    let value = f32::from_bits(0x4000_0000 | ((packed & max) << (23 + exp_bits - bits))) - 2.0;
+    if (packed >> bits) & 1 != 0 {
+        -value
+    } else {
+        value
+    }
+
+}
+
+impl SimpleCodec for PackedXZ {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_all(&self.0)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        let mut data = [0; 3];
+        read.read_exact(&mut data)?;
+        Ok(Self(data))
+    }
+
+}
+
+impl SimpleCodec for PackedXYZ {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        SimpleCodec::write(&self.xz, &mut *write)?;
+        write.write_all(&self.y)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        let xz = <PackedXZ as SimpleCodec>::read(&mut *read)?;
+        let mut y = [0; 2];
+        read.read_exact(&mut y)?;
+        Ok(Self { xz, y })
+    }
+
+}
+
+impl SimpleCodec for PackedYPR {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_i8(self.yaw)?;
+        write.write_i8(self.pitch)?;
+        write.write_i8(self.roll)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        Ok(Self {
+            yaw: read.read_i8()?,
+            pitch: read.read_i8()?,
+            roll: read.read_i8()?,
+        })
+    }
+
+}
+
+impl_simple_codec!(PackedXZ, PackedXYZ, PackedYPR);
+
+
 /// This macro can be used to create simple aggregation of structures with all fields of
 /// type [`DataType`], the structure is both defined and trait is implemented.
 #[macro_export]
@@ -258,3 +393,62 @@ macro_rules! __bootstrap_struct_data_type {
         )*
     };
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn packed_xz() {
+
+        assert_eq!(PackedXZ::pack(0.0, 0.0), PackedXZ([0, 0, 0]));
+        assert_eq!(PackedXZ::pack(0.0, 0.0).unpack(), (0.0, 0.0));
+
+        // Values out of range are clamped.
+        let (x, z) = PackedXZ::pack(1000.0, -1000.0).unpack();
+        assert!(x > 500.0 && x < 510.0);
+        assert!(z < -500.0 && z > -510.0);
+
+        for i in -500..500 {
+            let value = i as f32 + 0.25;
+            let (x, z) = PackedXZ::pack(value, -value).unpack();
+            // Precision is 1/128 near zero, up to 1 near the limits.
+            let precision = (value.abs() + 2.0) / 128.0;
+            assert!((x - value).abs() <= precision, "{value} != {x}");
+            assert!((z + value).abs() <= precision, "{value} != {z}");
+        }
+
+    }
+
+    #[test]
+    fn packed_xyz() {
+
+        let reference = Vec3::new(100.0, 20.0, -100.0);
+        for i in -500..500 {
+            let pos = Vec3::new(i as f32 * 0.5, i as f32 * 100.0, -(i as f32));
+            let unpacked = PackedXYZ::pack(pos).unpack_relative(reference) - reference;
+            assert!((unpacked.y - pos.y).abs() <= (pos.y.abs() + 2.0) / 2048.0, "{pos} != {unpacked}");
+        }
+
+    }
+
+    #[test]
+    fn packed_ypr() {
+
+        use std::f32::consts::PI;
+
+        assert_eq!(PackedYPR::pack(0.0, 0.0, 0.0), PackedYPR::default());
+        assert_eq!(PackedYPR::pack(-PI, -PI / 2.0, PI / 2.0), PackedYPR { yaw: -128, pitch: -127, roll: 64 });
+
+        for i in -127..128 {
+            let angle = i as f32 * PI / 128.0;
+            let (yaw, _, roll) = PackedYPR::pack(angle, 0.0, angle).unpack();
+            assert!((yaw - angle).abs() < 1e-5);
+            assert!((roll - angle).abs() < 1e-5);
+        }
+
+    }
+
+}
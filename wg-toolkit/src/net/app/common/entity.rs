@@ -1,7 +1,12 @@
-use std::io::{self, Read, Write};
+use std::io::{self, Cursor, Read, Write};
+use std::collections::HashMap;
+use std::any::Any;
+use std::fmt;
+
+use glam::{Vec2, Vec3, Vec4};
 
 use crate::net::element::ElementLength;
-use crate::net::codec::Codec;
+use crate::net::codec::{AutoString, Codec, Mailbox, Python};
 
 
 /// Abstract type representing an entity type.
@@ -54,6 +59,9 @@ impl<E: SimpleEntity> Entity for E {
 /// Abstract type representing a method for an entity.
 pub trait Method: Sized {
 
+    /// Return the name of the method.
+    fn name(&self) -> &'static str;
+
     /// Return the preferred encoding length of this method, when sub message id is used
     /// this is just ignored.
     fn write_length(&self) -> ElementLength;
@@ -69,6 +77,424 @@ pub trait Method: Sized {
 
 }
 
+/// Abstract type representing the new value of a client-visible property of an entity,
+/// this is typically an enumeration of all properties of an entity type.
+pub trait Property: Sized {
+
+    /// Return the name of the property.
+    fn name(&self) -> &'static str;
+
+    /// Return the preferred encoding length of this property value.
+    fn write_length(&self) -> ElementLength;
+
+    /// Encode the property value into the given writer, returning its exposed index.
+    fn write(&self, write: &mut dyn Write) -> io::Result<u16>;
+
+    /// Return the decode length for the given exposed index.
+    fn read_length(exposed_id: u16) -> ElementLength;
+
+    /// Decode the property value from the given reader and its exposed index.
+    fn read(read: &mut dyn Read, exposed_id: u16) -> io::Result<Self>;
+
+}
+
+/// Abstract type for entities with client-visible properties that can be encoded one by
+/// one, identified by their exposed index, this is used to send property updates.
+pub trait Properties {
+
+    /// The property enum type associated to this entity, used to decode updates.
+    type Property: Property;
+
+    /// Return the preferred encoding length of the property with the given exposed index.
+    fn property_length(index: u16) -> ElementLength;
+
+    /// Encode the property with the given exposed index into the given writer.
+    fn write_property(&self, index: u16, write: &mut dyn Write) -> io::Result<()>;
+
+}
+
+/// A visitor of an entity type only known at runtime from its type id, this allows
+/// calling generic code with the concrete entity type, see [`__bootstrap_enum_entities`].
+/// 
+/// [`__bootstrap_enum_entities`]: crate::__bootstrap_enum_entities
+pub trait EntityTypeVisitor {
+
+    /// The value returned by the visitor.
+    type Output;
+
+    /// Visit the concrete entity type.
+    fn visit<E>(self) -> Self::Output
+    where
+        E: Entity + Properties + NestedProperty + Send + 'static,
+        E::ClientMethod: fmt::Debug,
+        E::BaseMethod: fmt::Debug,
+        E::CellMethod: fmt::Debug,
+        E::Property: fmt::Debug;
+
+}
+
+/// A set of client-visible properties of an entity that have been changed since last
+/// sent to the client, identified by their exposed index.
+#[derive(Debug, Clone, Default)]
+pub struct DirtyProperties {
+    bits: Vec<u64>,
+}
+
+impl DirtyProperties {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the property with the given exposed index as changed.
+    pub fn mark(&mut self, index: u16) {
+        let word = index as usize / 64;
+        if word >= self.bits.len() {
+            self.bits.resize(word + 1, 0);
+        }
+        self.bits[word] |= 1 << (index % 64);
+    }
+
+    /// Return true if the property with the given exposed index has been changed.
+    pub fn is_dirty(&self, index: u16) -> bool {
+        self.bits.get(index as usize / 64).is_some_and(|word| word & (1 << (index % 64)) != 0)
+    }
+
+    /// Return true if no property has been changed.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&word| word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+
+    /// Take all changed properties' exposed indices, in increasing order, the set is 
+    /// cleared.
+    pub fn take(&mut self) -> impl Iterator<Item = u16> {
+        std::mem::take(&mut self.bits).into_iter()
+            .enumerate()
+            .flat_map(|(word_index, word)| (0..64u16)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| word_index as u16 * 64 + bit))
+    }
+
+}
+
+/// A property value that can be the target of nested property changes, such changes
+/// are identified by a path of indices into the children of the values, dicts have
+/// their fields as children and arrays have their elements. Entities also implement
+/// this trait, their children being their client-visible properties, in exposed order.
+pub trait NestedProperty: fmt::Debug {
+
+    /// Return the number of children of this value, this is zero for leaf values.
+    fn nested_len(&self) -> usize {
+        0
+    }
+
+    /// Return the path segment and a mutable reference to the child at the given index,
+    /// none if there is no such child.
+    fn nested_child_mut(&mut self, index: usize) -> Option<(PropertyPathSegment, &mut dyn NestedProperty)> {
+        let _ = index;
+        None
+    }
+
+    /// Decode a new value from the given reader, replacing this one.
+    fn nested_read(&mut self, read: &mut dyn Read) -> io::Result<()>;
+
+    /// Decode new children from the given reader until its end, replacing the children
+    /// in the given range. This is only supported by arrays of variable size.
+    fn nested_read_slice(&mut self, start: usize, end: usize, read: &mut dyn Read) -> io::Result<()> {
+        let _ = (start, end, read);
+        Err(io::Error::new(io::ErrorKind::Unsupported, "slice change of a value that is not an array"))
+    }
+
+}
+
+macro_rules! impl_nested_leaf {
+    ( $($ty:ty),* ) => {
+        $(
+            impl NestedProperty for $ty {
+                fn nested_read(&mut self, read: &mut dyn Read) -> io::Result<()> {
+                    *self = Codec::<()>::read(read, &())?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_nested_leaf!(bool, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, Vec2, Vec3, Vec4, AutoString, Python, Mailbox);
+
+impl<T: NestedProperty + Codec<()>> NestedProperty for Vec<T> {
+
+    fn nested_len(&self) -> usize {
+        self.len()
+    }
+
+    fn nested_child_mut(&mut self, index: usize) -> Option<(PropertyPathSegment, &mut dyn NestedProperty)> {
+        self.get_mut(index).map(|child| (PropertyPathSegment::Index(index), child as &mut dyn NestedProperty))
+    }
+
+    fn nested_read(&mut self, read: &mut dyn Read) -> io::Result<()> {
+        *self = Codec::<()>::read(read, &())?;
+        Ok(())
+    }
+
+    fn nested_read_slice(&mut self, start: usize, end: usize, read: &mut dyn Read) -> io::Result<()> {
+
+        if start > end || end > self.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid slice {start}..{end} of array of length {}", self.len())));
+        }
+
+        // New elements are read until the end of the data.
+        let mut data = Vec::new();
+        read.read_to_end(&mut data)?;
+
+        let mut cursor = Cursor::new(&data[..]);
+        let mut elements = Vec::new();
+        while (cursor.position() as usize) < data.len() {
+            elements.push(T::read(&mut cursor, &())?);
+        }
+
+        self.splice(start..end, elements);
+        Ok(())
+
+    }
+
+}
+
+impl<const LEN: usize, T: NestedProperty + Codec<()>> NestedProperty for Box<[T; LEN]> {
+
+    fn nested_len(&self) -> usize {
+        LEN
+    }
+
+    fn nested_child_mut(&mut self, index: usize) -> Option<(PropertyPathSegment, &mut dyn NestedProperty)> {
+        self.get_mut(index).map(|child| (PropertyPathSegment::Index(index), child as &mut dyn NestedProperty))
+    }
+
+    fn nested_read(&mut self, read: &mut dyn Read) -> io::Result<()> {
+        *self = Codec::<()>::read(read, &())?;
+        Ok(())
+    }
+
+}
+
+/// A segment in the path of a nested property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyPathSegment {
+    /// A named field of a dict, or a property of an entity.
+    Field(&'static str),
+    /// An element of an array.
+    Index(usize),
+    /// A range of elements of an array, only at the end of a path.
+    Slice(usize, usize),
+}
+
+/// The path of a nested property, relative to its entity, this is displayed like the
+/// property would be accessed in Python, such as `.arena.players[3].health`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropertyPath(pub Vec<PropertyPathSegment>);
+
+impl fmt::Display for PropertyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0 {
+            match *segment {
+                PropertyPathSegment::Field(name) => write!(f, ".{name}")?,
+                PropertyPathSegment::Index(index) => write!(f, "[{index}]")?,
+                PropertyPathSegment::Slice(start, end) => write!(f, "[{start}:{end}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A store of decoded entities, identified by their entity id, this holds the current
+/// state of each entity and property updates can be applied to it. Entities can be of
+/// any type, and are retrieved by their concrete type.
+#[derive(Default)]
+pub struct EntityStore {
+    entities: HashMap<u32, StoredEntity>,
+}
+
+/// Internal entity stored in the entity store.
+struct StoredEntity {
+    type_id: u16,
+    entity: Box<dyn AnyEntity>,
+}
+
+/// Internal trait for type-erased entities, allowing downcast to the concrete type.
+trait AnyEntity: NestedProperty + Send {
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn as_nested(&self) -> &dyn NestedProperty;
+
+    fn as_nested_mut(&mut self) -> &mut dyn NestedProperty;
+
+}
+
+impl<E: NestedProperty + Send + 'static> AnyEntity for E {
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_nested(&self) -> &dyn NestedProperty {
+        self
+    }
+
+    fn as_nested_mut(&mut self) -> &mut dyn NestedProperty {
+        self
+    }
+
+}
+
+impl EntityStore {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert an entity with the given id and type id, replacing any previous entity
+    /// with the same id.
+    pub fn insert<E>(&mut self, entity_id: u32, type_id: u16, entity: E)
+    where
+        E: NestedProperty + Send + 'static,
+    {
+        self.entities.insert(entity_id, StoredEntity { type_id, entity: Box::new(entity) });
+    }
+
+    /// Remove the entity with the given id, returning true if it existed.
+    pub fn remove(&mut self, entity_id: u32) -> bool {
+        self.entities.remove(&entity_id).is_some()
+    }
+
+    /// Only keep the entities for which the given predicate returns true.
+    pub fn retain(&mut self, mut predicate: impl FnMut(u32) -> bool) {
+        self.entities.retain(|&entity_id, _| predicate(entity_id));
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Return true if an entity with the given id exists.
+    pub fn contains(&self, entity_id: u32) -> bool {
+        self.entities.contains_key(&entity_id)
+    }
+
+    /// Get the type id of the entity with the given id.
+    pub fn type_id(&self, entity_id: u32) -> Option<u16> {
+        self.entities.get(&entity_id).map(|stored| stored.type_id)
+    }
+
+    /// Get the entity with the given id, none if it doesn't exist or if it has another
+    /// type than the requested one.
+    pub fn get<E: 'static>(&self, entity_id: u32) -> Option<&E> {
+        self.entities.get(&entity_id)?.entity.as_any().downcast_ref()
+    }
+
+    /// Get the entity with the given id, none if it doesn't exist or if it has another
+    /// type than the requested one.
+    pub fn get_mut<E: 'static>(&mut self, entity_id: u32) -> Option<&mut E> {
+        self.entities.get_mut(&entity_id)?.entity.as_any_mut().downcast_mut()
+    }
+
+    /// Get the entity with the given id, regardless of its type, this can be used to
+    /// apply nested property changes.
+    pub fn get_nested_mut(&mut self, entity_id: u32) -> Option<&mut dyn NestedProperty> {
+        Some(self.entities.get_mut(&entity_id)?.entity.as_nested_mut())
+    }
+
+    /// Apply a property update to the entity with the given id, the property type must
+    /// be the one of the entity type. False is returned if the entity doesn't exist.
+    pub fn apply_property<P: Property>(&mut self, entity_id: u32, property: &P) -> io::Result<bool> {
+
+        let Some(entity) = self.get_nested_mut(entity_id) else {
+            return Ok(false);
+        };
+
+        // The property is re-encoded in order to be decoded into the entity's field.
+        let mut data = Vec::new();
+        let exposed_id = property.write(&mut data)?;
+        let (_, field) = entity.nested_child_mut(exposed_id as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid property exposed id: {exposed_id}")))?;
+
+        field.nested_read(&mut &data[..])?;
+        Ok(true)
+
+    }
+
+    /// Iterate over all entities, with their id, type id and current state, in no
+    /// particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u16, &dyn NestedProperty)> + '_ {
+        self.entities.iter().map(|(&entity_id, stored)| (entity_id, stored.type_id, stored.entity.as_nested()))
+    }
+
+}
+
+impl fmt::Debug for EntityStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entities = self.iter().collect::<Vec<_>>();
+        entities.sort_by_key(|&(entity_id, _, _)| entity_id);
+        f.debug_map()
+            .entries(entities.into_iter().map(|(entity_id, _, entity)| (entity_id, entity)))
+            .finish()
+    }
+}
+
+/// This macro can be used to implement [`NestedProperty`] for a structure, given the
+/// name and the field path of each of its children, in order, the structure must 
+/// implement [`Codec`] without config. This is used for generated dicts and entities.
+#[macro_export]
+macro_rules! __impl_nested_property {
+    (
+        $(
+            $struct_name:ident {
+                $( $child_name:literal => $($child_field:ident).+ ),*
+                $(,)?
+            }
+        )*
+    ) => {
+        $(
+            impl $crate::net::app::common::entity::NestedProperty for $struct_name {
+                fn nested_len(&self) -> usize {
+                    let names: &[&'static str] = &[$($child_name),*];
+                    names.len()
+                }
+                fn nested_child_mut(&mut self, index: usize) -> Option<($crate::net::app::common::entity::PropertyPathSegment, &mut dyn $crate::net::app::common::entity::NestedProperty)> {
+                    use $crate::net::app::common::entity::{NestedProperty, PropertyPathSegment};
+                    let names: &[&'static str] = &[$($child_name),*];
+                    let children: Vec<&mut dyn NestedProperty> = vec![$(&mut self.$($child_field).+ as &mut dyn NestedProperty),*];
+                    let child = children.into_iter().nth(index)?;
+                    Some((PropertyPathSegment::Field(names[index]), child))
+                }
+                fn nested_read(&mut self, read: &mut dyn std::io::Read) -> std::io::Result<()> {
+                    use $crate::net::codec::Codec;
+                    *self = Codec::<()>::read(read, &())?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
 /// This macro can be used to generate an enumeration capable of encoding and decoding
 /// an arbitrary number of methods, the enumeration implements the [`Method`] trait, and
 /// all methods should 
@@ -95,6 +521,12 @@ macro_rules! __enum_entity_methods {
             }
 
             impl $crate::net::app::common::entity::Method for $enum_name {
+                fn name(&self) -> &'static str {
+                    match self {
+                        $( Self::$method_name (_) => stringify!($method_name), )*
+                        _ => unreachable!()
+                    }
+                }
                 fn write_length(&self) -> $crate::net::element::ElementLength {
                     match self {
                         $( Self::$method_name (_) => $crate::__enum_entity_methods!(__length; $method_length), )*
@@ -125,3 +557,216 @@ macro_rules! __enum_entity_methods {
         )*
     };
 }
+
+/// This macro can be used to generate an enumeration of all client-visible properties 
+/// of an entity, each variant holds the value of the property, the enumeration 
+/// implements the [`Property`] trait.
+#[macro_export]
+macro_rules! __enum_entity_properties {
+    (
+        $(
+            $(#[$attr:meta])* 
+            $enum_vis:vis enum $enum_name:ident {
+                $( $property_name:ident ( $property_exposed_id:literal, $property_ty:ty, $property_length:tt ) ),*
+                $(,)?
+            }
+        )*
+    ) => {
+        $(
+            $(#[$attr])* 
+            $enum_vis enum $enum_name {
+                $( $property_name ( $property_ty ),)*
+            }
+
+            impl $crate::net::app::common::entity::Property for $enum_name {
+                fn name(&self) -> &'static str {
+                    match self {
+                        $( Self::$property_name (_) => stringify!($property_name), )*
+                        _ => unreachable!()
+                    }
+                }
+                fn write_length(&self) -> $crate::net::element::ElementLength {
+                    match self {
+                        $( Self::$property_name (_) => $crate::__enum_entity_methods!(__length; $property_length), )*
+                        _ => unreachable!()
+                    }
+                }
+                fn write(&self, write: &mut dyn std::io::Write) -> std::io::Result<u16> {
+                    use $crate::net::codec::Codec;
+                    match self {
+                        $( Self::$property_name (v) => Codec::<()>::write(v, write, &()).map(|()| $property_exposed_id), )*
+                        _ => unreachable!()
+                    }
+                }
+                fn read_length(exposed_id: u16) -> $crate::net::element::ElementLength {
+                    match exposed_id {
+                        $( $property_exposed_id => $crate::__enum_entity_methods!(__length; $property_length), )*
+                        _ => $crate::net::element::ElementLength::Variable16
+                    }
+                }
+                fn read(read: &mut dyn std::io::Read, exposed_id: u16) -> std::io::Result<Self> {
+                    use $crate::net::codec::Codec;
+                    Ok(match exposed_id {
+                        $( $property_exposed_id => Self::$property_name(Codec::<()>::read(read, &())?), )*
+                        _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid property exposed id: 0x{exposed_id:02X}")))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+/// This macro can be used to generate an enumeration of all entity types, each variant
+/// holds the decoded entity, along with the enumerations of the methods of all entity
+/// types on the client, base and cell, so that an entity or method can be decoded from
+/// its type id only known at runtime. The type id of each entity is given.
+#[macro_export]
+macro_rules! __bootstrap_enum_entities {
+    (
+        __methods;
+        $(#[$attr:meta])*;
+        $enum_vis:vis enum $enum_name:ident : $method_ty:ident {
+            $( $entity_name:ident = $entity_type_id:literal ),*
+        }
+    ) => {
+        $(#[$attr])*
+        $enum_vis enum $enum_name {
+            $( $entity_name ( <$entity_name as $crate::net::app::common::entity::Entity>::$method_ty ), )*
+        }
+
+        impl $enum_name {
+            /// Return the type id of the entity this method is called on.
+            pub fn entity_type_id(&self) -> u16 {
+                match self {
+                    $( Self::$entity_name (_) => $entity_type_id, )*
+                    _ => unreachable!()
+                }
+            }
+            /// Return the name of the method.
+            pub fn name(&self) -> &'static str {
+                use $crate::net::app::common::entity::Method;
+                match self {
+                    $( Self::$entity_name (m) => m.name(), )*
+                    _ => unreachable!()
+                }
+            }
+            /// Return the preferred encoding length of this method.
+            pub fn write_length(&self) -> $crate::net::element::ElementLength {
+                use $crate::net::app::common::entity::Method;
+                match self {
+                    $( Self::$entity_name (m) => m.write_length(), )*
+                    _ => unreachable!()
+                }
+            }
+            /// Encode the method call into the given writer, returning its exposed id.
+            pub fn write(&self, write: &mut dyn std::io::Write) -> std::io::Result<u16> {
+                use $crate::net::app::common::entity::Method;
+                match self {
+                    $( Self::$entity_name (m) => m.write(write), )*
+                    _ => unreachable!()
+                }
+            }
+            /// Return the decode length for the given exposed method id of the given
+            /// entity type, none if the entity type is unknown.
+            pub fn read_length(type_id: u16, exposed_id: u16) -> Option<$crate::net::element::ElementLength> {
+                use $crate::net::app::common::entity::Method;
+                match type_id {
+                    $( $entity_type_id => Some(<<$entity_name as $crate::net::app::common::entity::Entity>::$method_ty as Method>::read_length(exposed_id)), )*
+                    _ => None
+                }
+            }
+            /// Decode the given method of the given entity type from the given reader.
+            pub fn read(read: &mut dyn std::io::Read, type_id: u16, exposed_id: u16) -> std::io::Result<Self> {
+                use $crate::net::app::common::entity::Method;
+                Ok(match type_id {
+                    $( $entity_type_id => Self::$entity_name(Method::read(read, exposed_id)?), )*
+                    _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid entity type id: 0x{type_id:02X}")))
+                })
+            }
+        }
+    };
+    (
+        $(#[$attr:meta])* 
+        $enum_vis:vis enum $enum_name:ident : $client_name:ident, $base_name:ident, $cell_name:ident {
+            $( $entity_name:ident = $entity_type_id:literal ),*
+            $(,)?
+        }
+    ) => {
+        $(#[$attr])* 
+        $enum_vis enum $enum_name {
+            $( $entity_name ( Box<$entity_name> ), )*
+        }
+
+        impl $enum_name {
+            /// Return the type id of this entity.
+            pub fn entity_type_id(&self) -> u16 {
+                match self {
+                    $( Self::$entity_name (_) => $entity_type_id, )*
+                    _ => unreachable!()
+                }
+            }
+            /// Return the name of the type of this entity.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Self::$entity_name (_) => stringify!($entity_name), )*
+                    _ => unreachable!()
+                }
+            }
+            /// Return the name of the entity type with the given type id, if known.
+            pub fn type_name(type_id: u16) -> Option<&'static str> {
+                match type_id {
+                    $( $entity_type_id => Some(stringify!($entity_name)), )*
+                    _ => None
+                }
+            }
+            /// Call the given visitor with the entity type of the given type id, none 
+            /// is returned if the entity type is unknown.
+            pub fn visit_type<V: $crate::net::app::common::entity::EntityTypeVisitor>(type_id: u16, visitor: V) -> Option<V::Output> {
+                match type_id {
+                    $( $entity_type_id => Some(visitor.visit::<$entity_name>()), )*
+                    _ => None
+                }
+            }
+            /// Encode the entity into the given writer.
+            pub fn write(&self, write: &mut dyn std::io::Write) -> std::io::Result<()> {
+                use $crate::net::app::common::entity::Entity;
+                match self {
+                    $( Self::$entity_name (e) => Entity::write(&**e, write), )*
+                    _ => unreachable!()
+                }
+            }
+            /// Decode an entity of the given type from the given reader.
+            pub fn read(read: &mut dyn std::io::Read, type_id: u16) -> std::io::Result<Self> {
+                use $crate::net::app::common::entity::Entity;
+                Ok(match type_id {
+                    $( $entity_type_id => Self::$entity_name(Box::new(Entity::read(read)?)), )*
+                    _ => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid entity type id: 0x{type_id:02X}")))
+                })
+            }
+        }
+
+        $crate::__bootstrap_enum_entities! {
+            __methods;
+            $(#[$attr])*;
+            $enum_vis enum $client_name : ClientMethod {
+                $( $entity_name = $entity_type_id ),*
+            }
+        }
+
+        $crate::__bootstrap_enum_entities! {
+            __methods;
+            $(#[$attr])*;
+            $enum_vis enum $base_name : BaseMethod {
+                $( $entity_name = $entity_type_id ),*
+            }
+        }
+
+        $crate::__bootstrap_enum_entities! {
+            __methods;
+            $(#[$attr])*;
+            $enum_vis enum $cell_name : CellMethod {
+                $( $entity_name = $entity_type_id ),*
+            }
+        }
+    };
+}
@@ -2,16 +2,22 @@
 //! definition of how to encode entities, their components/interfaces and the method 
 //! calls.
 
-// pub mod data;
+pub mod data;
 pub mod entity;
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 use std::io;
 
-use crate::net::bundle::{Bundle, ElementReader, NextElementReader, ReplyReader};
+use crate::net::bundle::{Bundle, ElementReader, FragmentError, NextElementReader, ReplyReader};
+use crate::net::bundle::{RequestTracker, RequestPolicy, RequestEvent, RequestTimeoutEvent};
 use crate::net::socket::PacketSocket;
-use crate::net::proto::Protocol;
+use crate::net::element::Element;
+use crate::net::proto::{PacketPolicy, Protocol, ProtocolStats};
+use crate::net::variant::ProtocolVariant;
+
+use super::tick::{is_timeout, TickEvent, TickScheduler};
 
 
 /// Common abstract application that handle bundle messages.
@@ -25,22 +31,139 @@ pub struct App {
     bundle: Bundle,
     /// Mapping of each known peer to its remote address.
     peers: HashMap<SocketAddr, InternalPeer>,
+    /// The optional tick scheduler.
+    tick: Option<TickScheduler>,
+    /// Tracker of the requests sent and waiting for a reply.
+    requests: RequestTracker<()>,
+    /// True when a receive timeout is currently set on the socket.
+    recv_timeout: bool,
 }
 
 impl App {
 
     pub fn new(addr: SocketAddr) -> io::Result<Self> {
-        Ok(Self {
-            socket: PacketSocket::bind(addr)?,
+        Ok(Self::with_socket(PacketSocket::bind(addr)?))
+    }
+
+    /// Create a new application running on the given socket.
+    pub fn with_socket(socket: PacketSocket) -> Self {
+        Self {
+            socket,
             protocol: Protocol::new(),
             bundle: Bundle::new(),
             peers: HashMap::new(),
-        })
+            tick: None,
+            requests: RequestTracker::new(),
+            recv_timeout: false,
+        }
+    }
+
+    /// Set the tick scheduler of this application, when set, [`Handler::handle_tick`]
+    /// is called by [`Self::poll`] when ticks are due.
+    pub fn set_tick_scheduler(&mut self, tick: Option<TickScheduler>) {
+        self.tick = tick;
+    }
+
+    /// Set the timeout and retry policy of requests sent with [`Self::send_request`].
+    pub fn set_request_policy(&mut self, policy: RequestPolicy) {
+        self.requests.set_policy(policy);
+    }
+
+    /// Set the protocol variant used to read and write packets and elements, the PC
+    /// client's variant is used by default.
+    pub fn set_protocol_variant(&mut self, variant: ProtocolVariant) {
+        self.bundle.set_variant(&variant);
+        self.protocol.set_variant(variant);
+    }
+
+    /// Set the policy of outgoing packets for peers without a specific policy.
+    pub fn set_default_packet_policy(&mut self, policy: PacketPolicy) {
+        self.protocol.set_default_policy(policy);
+    }
+
+    /// Set a specific policy of outgoing packets to the given peer, none to use the 
+    /// default policy again.
+    pub fn set_peer_packet_policy(&mut self, addr: SocketAddr, policy: Option<PacketPolicy>) {
+        self.protocol.set_peer_policy(addr, policy);
+    }
+
+    /// Send a request to the given address, the request is kept until its reply is 
+    /// received, and sent again with the same request id if no reply is received 
+    /// before the timeout of the request policy. If all retries are exhausted, 
+    /// [`Handler::handle_request_timeout`] is called. The request id is returned.
+    pub fn send_request<E, C>(&mut self, addr: SocketAddr, element: E, config: C) -> io::Result<u32>
+    where
+        E: Element<C> + Clone + Send + 'static,
+        C: Send + 'static,
+    {
+        let request_id = self.requests.send(addr, element, config, Instant::now());
+        self.send_pending_request(addr, request_id)?;
+        Ok(request_id)
+    }
+
+    /// Return memory usage statistics of this application.
+    pub fn stats(&self) -> AppStats {
+        AppStats {
+            peers: self.peers.len(),
+            pending_requests: self.requests.len(),
+            protocol: self.protocol.stats(),
+        }
+    }
+
+    /// Internal function to write and send a pending request.
+    fn send_pending_request(&mut self, addr: SocketAddr, request_id: u32) -> io::Result<()> {
+        self.bundle.clear();
+        self.requests.write(request_id, &mut self.bundle.element_writer());
+        self.protocol.channel(addr, None).prepare(&mut self.bundle, true);
+        self.socket.send_bundle(&self.bundle, addr)?;
+        Ok(())
     }
 
     pub fn poll<H: Handler>(&mut self, mut handler: H) -> io::Result<()> {
 
-        let (packet, addr) = self.socket.recv()?;
+        let now = Instant::now();
+
+        // Retry or fail all expired requests.
+        while let Some(event) = self.requests.poll(now) {
+            match event {
+                RequestEvent::Retry(retry) => self.send_pending_request(retry.addr, retry.request_id)?,
+                RequestEvent::Timeout(timeout) => return handler.handle_request_timeout(timeout),
+            }
+        }
+
+        // Report bundles that have not been completed in time.
+        if let Some((addr, error)) = self.protocol.poll_fragment_timeout(now) {
+            return handler.handle_fragment_timeout(addr, error);
+        }
+
+        // Drive the tick scheduler, if any.
+        if let Some(tick) = &mut self.tick {
+            if let Some(event) = tick.poll(now) {
+                return handler.handle_tick(event);
+            }
+        }
+
+        // The receive timeout is adjusted so that we wake up in time for the next tick
+        // or request deadline.
+        let deadline = match (self.tick.as_ref().map(TickScheduler::next_deadline), self.requests.next_deadline()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        if let Some(deadline) = deadline {
+            let timeout = deadline.saturating_duration_since(now).max(Duration::from_millis(1));
+            self.socket.set_recv_timeout(Some(timeout))?;
+            self.recv_timeout = true;
+        } else if self.recv_timeout {
+            self.socket.set_recv_timeout(None)?;
+            self.recv_timeout = false;
+        }
+
+        let (packet, addr) = match self.socket.recv() {
+            Ok(ret) => ret,
+            Err(error) if self.recv_timeout && is_timeout(&error) => return Ok(()),
+            Err(error) => return Err(error),
+        };
         
         let peer = self.peers.entry(addr)
             .or_insert_with(|| InternalPeer {
@@ -61,8 +184,12 @@ impl App {
                             bundle: &mut self.bundle,
                         })?;
                     }
-                    NextElementReader::Reply(_reply) => {
-
+                    NextElementReader::Reply(reply) => {
+                        self.requests.handle_reply(addr, reply.request_id());
+                        handler.handle_reply(reply, Peer {
+                            internal: &mut *peer,
+                            bundle: &mut self.bundle,
+                        })?;
                     }
                 }
             }
@@ -74,6 +201,17 @@ impl App {
 
 }
 
+/// Memory usage statistics of the application, see [`App::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppStats {
+    /// Number of known peers.
+    pub peers: usize,
+    /// Number of requests waiting for a reply.
+    pub pending_requests: usize,
+    /// Statistics of the protocol.
+    pub protocol: ProtocolStats,
+}
+
 /// The main handle trait for this application.
 pub trait Handler {
 
@@ -82,6 +220,26 @@ pub trait Handler {
 
     fn handle_reply(&mut self, reply: ReplyReader, peer: Peer) -> io::Result<()>;
 
+    /// Handle a request sent with [`App::send_request`] that has not been answered in
+    /// time, after all retries of the request policy.
+    fn handle_request_timeout(&mut self, timeout: RequestTimeoutEvent) -> io::Result<()> {
+        let _ = timeout;
+        Ok(())
+    }
+
+    /// Handle a tick, only called if a tick scheduler has been set on the application.
+    fn handle_tick(&mut self, tick: TickEvent) -> io::Result<()> {
+        let _ = tick;
+        Ok(())
+    }
+
+    /// Handle a bundle from the given peer that has not been completed in time because
+    /// some of its fragments are missing, the incomplete bundle has been dropped.
+    fn handle_fragment_timeout(&mut self, addr: SocketAddr, error: FragmentError) -> io::Result<()> {
+        let _ = (addr, error);
+        Ok(())
+    }
+
 }
 
 /// A handle to a peer.
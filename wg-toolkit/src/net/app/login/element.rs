@@ -49,7 +49,9 @@ impl SimpleElement for Ping {
 /// server when it wants to log into and gain access to a base app.
 #[derive(Debug, Default, Clone)]
 pub struct LoginRequest {
-    /// The protocol used, currently undocumented.
+    /// The protocol version of the client, it varies across game releases and between
+    /// the Wargaming and Lesta clients, the login app may reject the request with
+    /// [`LoginError::BadProtocolVersion`] if it's not the expected one.
     pub protocol: u32,
     /// The username used to login.
     pub username: String,
@@ -159,6 +161,53 @@ where LoginRequest: Codec<C> {
     const LEN: ElementLength = ElementLength::Variable16;
 }
 
+/// The raw parameters of a login request, as decrypted by the server but not decoded,
+/// this is useful to study the exact login payload of a given client version. Use
+/// [`Self::decode`] to decode the login request from these parameters.
+#[derive(Debug, Default, Clone)]
+pub struct LoginParams {
+    /// The protocol version of the client, see [`LoginRequest::protocol`].
+    pub protocol: u32,
+    /// The decrypted parameters blob.
+    pub data: Vec<u8>,
+}
+
+impl LoginParams {
+
+    /// Decode the login request from these parameters.
+    pub fn decode(&self) -> io::Result<LoginRequest> {
+        read_login_request(&mut &self.data[..], self.protocol)
+    }
+
+}
+
+/// Implementation with server-side decryption, parameters are always written encrypted.
+impl Codec<RsaPrivateKey> for LoginParams {
+
+    fn write(&self, write: &mut dyn Write, config: &RsaPrivateKey) -> io::Result<()> {
+        write.write_u32(self.protocol)?;
+        write.write_bool(true)?;
+        RsaWriter::new(write, &config.to_public_key()).write_all(&self.data)
+    }
+
+    fn read(read: &mut dyn Read, config: &RsaPrivateKey) -> io::Result<Self> {
+        let protocol = read.read_u32()?;
+        let mut data = Vec::new();
+        if read.read_bool()? {
+            RsaReader::new(read, config).read_to_end(&mut data)?;
+        } else {
+            read.read_to_end(&mut data)?;
+        }
+        Ok(Self { protocol, data })
+    }
+
+}
+
+impl SimpleElement<RsaPrivateKey> for LoginParams {
+    const ID: u8 = id::LOGIN_REQUEST;
+    const LEN: ElementLength = ElementLength::Variable16;
+}
+
 
 /// Describe all kinds of responses returned from server to client when
 /// the client attempt to login. This includes challenge or error codes.
@@ -314,7 +363,23 @@ impl LoginResponse {
             65 => LoginError::BadProtocolVersion,
             67 => LoginError::InvalidUser,
             68 => LoginError::InvalidPassword,
-            // TODO: Implement other variants
+            69 => LoginError::AlreadyLoggedIn,
+            70 => LoginError::BadDigest,
+            71 => LoginError::DatabaseGeneralFailure,
+            72 => LoginError::DatabaseNotReady,
+            73 => LoginError::IllegalCharacters,
+            74 => LoginError::ServerNotReady,
+            75 => LoginError::UpdaterNotReady,
+            76 => LoginError::NoBaseApp,
+            77 => LoginError::BaseAppOverload,
+            78 => LoginError::CellAppOverload,
+            79 => LoginError::BaseAppTimeout,
+            80 => LoginError::BaseAppManagerTimeout,
+            81 => LoginError::DatabaseAppOverload,
+            82 => LoginError::LoginNotAllowed,
+            83 => LoginError::RateLimited,
+            84 => LoginError::Banned,
+            85 => LoginError::ChallengeError,
             code => return Ok(LoginResponse::Unknown(code))
         };
 
@@ -3,12 +3,13 @@
 
 pub mod element;
 pub mod proxy;
+pub mod server;
 
 use std::collections::{HashMap, VecDeque};
 use std::net::{SocketAddr, SocketAddrV4};
 use std::time::{Duration, Instant};
 use std::sync::Arc;
-use std::io;
+use std::{fmt, io};
 
 use crypto_common::KeyInit;
 use rsa::RsaPrivateKey;
@@ -20,9 +21,14 @@ use rand::RngCore;
 use tracing::trace;
 
 use crate::net::bundle::{Bundle, NextElementReader, ElementReader};
+use crate::net::packet::Packet;
 use crate::util::cuckoo::CuckooContext;
 use crate::net::socket::PacketSocket;
-use crate::net::proto::Protocol;
+#[cfg(feature = "tokio")]
+use crate::net::socket::AsyncPacketSocket;
+use crate::net::proto::{PacketPolicy, Protocol};
+use crate::net::variant::ProtocolVariant;
+use super::tick::{is_timeout, TickEvent, TickScheduler};
 use super::io_invalid_data;
 
 use element::{
@@ -49,6 +55,17 @@ pub struct App {
     /// implies that the client should use the matching public key when logging in in
     /// order to validate.
     encryption_key: Option<Arc<RsaPrivateKey>>,
+    /// The protocol version expected in login requests, requests with another version
+    /// are rejected with [`LoginError::BadProtocolVersion`], any version is accepted if
+    /// none.
+    protocol_version: Option<u32>,
+    /// The digest of the entity definitions expected in login requests, requests with
+    /// another digest are rejected with [`LoginError::BadDigest`], any digest is
+    /// accepted if none.
+    expected_digest: Option<[u8; 16]>,
+    /// The optional verifier of credentials, requests with rejected credentials are
+    /// answered with the returned error and never reported as events.
+    credentials_verifier: Option<Arc<dyn CredentialsVerifier>>,
     /// Login requests of each client in process with the login app.
     pending_requests: HashMap<SocketAddr, PendingRequest>,
     /// Responses to be sent in response to login or challenge requests.
@@ -57,21 +74,41 @@ pub struct App {
     pending_challenges: HashMap<SocketAddr, PendingChallenge>,
     /// Used for benchmarking performance.
     received_instant: Option<Instant>,
+    /// The optional tick scheduler.
+    tick: Option<TickScheduler>,
 }
 impl App {
 
     pub fn new(addr: SocketAddr) -> io::Result<Self> {
-        Ok(Self {
-            socket: PacketSocket::bind(addr)?,
+        Ok(Self::with_socket(PacketSocket::bind(addr)?))
+    }
+
+    /// Create a new login application on the given address, backed by an async socket, 
+    /// the app should then be polled with [`Self::poll_async`]. This must be called 
+    /// from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn new_async(addr: SocketAddr) -> io::Result<Self> {
+        Ok(Self::with_socket(AsyncPacketSocket::bind(addr)?.into_socket()))
+    }
+
+    /// Create a new login application running on the given socket, this can be used 
+    /// to run the application on another transport than UDP.
+    pub fn with_socket(socket: PacketSocket) -> Self {
+        Self {
+            socket,
             protocol: Protocol::new(),
             events: VecDeque::new(),
             bundle: Bundle::new(),
             encryption_key: None,
+            protocol_version: None,
+            expected_digest: None,
+            credentials_verifier: None,
             pending_requests: HashMap::new(),
             pending_responses: VecDeque::new(),
             pending_challenges: HashMap::new(),
             received_instant: None,
-        })
+            tick: None,
+        }
     }
 
     /// Get the address this app is bound to.
@@ -95,57 +132,195 @@ impl App {
         self.encryption_key.is_some()
     }
 
+    /// Set the protocol variant used to read and write packets and elements, the PC
+    /// client's variant is used by default.
+    pub fn set_protocol_variant(&mut self, variant: ProtocolVariant) {
+        self.bundle.set_variant(&variant);
+        self.protocol.set_variant(variant);
+    }
+
+    /// Set the protocol version expected in login requests, none to accept any version.
+    pub fn set_protocol_version(&mut self, version: Option<u32>) {
+        self.protocol_version = version;
+    }
+
+    /// Return the protocol version expected in login requests, if any.
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.protocol_version
+    }
+
+    /// Set the digest of the entity definitions expected in login requests, none to
+    /// accept any digest.
+    pub fn set_expected_digest(&mut self, digest: Option<[u8; 16]>) {
+        self.expected_digest = digest;
+    }
+
+    /// Return the digest of the entity definitions expected in login requests, if any.
+    pub fn expected_digest(&self) -> Option<[u8; 16]> {
+        self.expected_digest
+    }
+
+    /// Set the verifier of credentials of login requests, none to report all login
+    /// requests as [`Event::Login`] events, without verification.
+    pub fn set_credentials_verifier(&mut self, verifier: Option<Arc<dyn CredentialsVerifier>>) {
+        self.credentials_verifier = verifier;
+    }
+
+    /// Set the tick scheduler of this application, when set, [`Event::Tick`] events
+    /// are returned by [`Self::poll`] when ticks are due.
+    pub fn set_tick_scheduler(&mut self, tick: Option<TickScheduler>) -> io::Result<()> {
+        if tick.is_none() {
+            self.socket.set_recv_timeout(None)?;
+        }
+        self.tick = tick;
+        Ok(())
+    }
+
+    /// Set the policy of outgoing packets for peers without a specific policy.
+    pub fn set_default_packet_policy(&mut self, policy: PacketPolicy) {
+        self.protocol.set_default_policy(policy);
+    }
+
+    /// Set a specific policy of outgoing packets to the given peer, none to use the 
+    /// default policy again.
+    pub fn set_peer_packet_policy(&mut self, addr: SocketAddr, policy: Option<PacketPolicy>) {
+        self.protocol.set_peer_policy(addr, policy);
+    }
+
     /// Poll for the next event of this login app, blocking.
     pub fn poll(&mut self) -> Event {
         loop {
 
-            // Empty the events before.
-            while let Some(event) = self.events.pop_front() {
+            if let Some(event) = self.poll_pending() {
                 return event;
             }
 
-            // Then send pending login responses.
-            while let Some(res) = self.pending_responses.pop_front() {
-                let addr = res.addr;
-                if let Err(error) = self.send_response(res) {
-                    return Event::IoError(IoErrorEvent { error, addr: Some(addr) });
-                }
+            let res = self.socket.recv();
+            if let Some(event) = self.handle_recv(res) {
+                return event;
             }
 
-            let (packet, addr) = match self.socket.recv() {
-                Ok(ret) => ret,
+        }
+    }
+
+    /// Poll for the next event of this login app, asynchronously, the app should have
+    /// been created with [`Self::new_async`], or any non-blocking socket.
+    #[cfg(feature = "tokio")]
+    pub async fn poll_async(&mut self) -> Event {
+        loop {
+
+            if let Some(event) = self.poll_pending() {
+                return event;
+            }
+
+            let timeout = self.tick.as_ref().map(|tick| tick.timeout(Instant::now()));
+            let send = !self.pending_responses.is_empty();
+            match super::recv_ready(&self.socket, send, timeout).await {
+                Ok(true) => {}
+                Ok(false) => continue,
                 Err(error) => return Event::IoError(IoErrorEvent { error, addr: None }),
-            };
-            
-            let Some(mut channel) = self.protocol.accept(packet, addr) else {
-                continue;
-            };
+            }
 
-            let Some(bundle) = channel.next_bundle() else {
-                continue;
+            let res = match self.socket.recv() {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => continue,
+                res => res,
             };
 
-            self.received_instant = Some(Instant::now());
+            if let Some(event) = self.handle_recv(res) {
+                return event;
+            }
 
-            // Fully read the bundle to determine how to handle that client.
-            let mut reader = bundle.element_reader();
-            while let Some(reader) = reader.next() {
-                match reader {
-                    NextElementReader::Element(elt) => {
-                        if let Err(error) = self.handle_element(elt, addr) {
-                            return Event::IoError(IoErrorEvent { error, addr: Some(addr) });
-                        }
-                    }
-                    NextElementReader::Reply(reply) => {
-                        return Event::IoError(IoErrorEvent {
-                            error: io_invalid_data(format_args!("unexpected reply #{}", reply.request_id())),
-                            addr: Some(addr),
-                        });
-                    }
+        }
+    }
+
+    /// Return the next event that is not related to a received packet, if any.
+    fn poll_pending(&mut self) -> Option<Event> {
+
+        // Empty the events before.
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+
+        // Then send pending login responses, a response that would block is kept and
+        // sent again once the socket is ready.
+        while let Some(res) = self.pending_responses.pop_front() {
+            match self.send_response(&res) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    self.pending_responses.push_front(res);
+                    break;
                 }
+                Err(error) => return Some(Event::IoError(IoErrorEvent { error, addr: Some(res.addr) })),
+            }
+        }
+
+        let now = Instant::now();
+
+        // Report bundles that have not been completed in time.
+        if let Some((addr, error)) = self.protocol.poll_fragment_timeout(now) {
+            let error = io::Error::new(io::ErrorKind::InvalidData, error);
+            return Some(Event::IoError(IoErrorEvent { error, addr: Some(addr) }));
+        }
+
+        // Drive the tick scheduler, if any, the receive timeout is adjusted so that
+        // we wake up in time for the next tick.
+        if let Some(tick) = &mut self.tick {
+            if let Some(event) = tick.poll(now) {
+                return Some(Event::Tick(event));
             }
+            if let Err(error) = self.socket.set_recv_timeout(Some(tick.timeout(now))) {
+                return Some(Event::IoError(IoErrorEvent { error, addr: None }));
+            }
+        }
+
+        None
+
+    }
 
+    /// Handle the result of receiving a packet, returning an event if any.
+    fn handle_recv(&mut self, res: io::Result<(Packet, SocketAddr)>) -> Option<Event> {
+
+        let (packet, addr) = match res {
+            Ok(ret) => ret,
+            Err(error) if self.tick.is_some() && is_timeout(&error) => return None,
+            Err(error) => return Some(Event::IoError(IoErrorEvent { error, addr: None })),
+        };
+        
+        let mut channel = self.protocol.accept(packet, addr)?;
+        let bundle = channel.next_bundle()?;
+
+        self.received_instant = Some(Instant::now());
+
+        // The bundle's packets are recycled once handled.
+        let event = self.handle_bundle(&bundle, addr);
+        self.socket.pool().put_all(bundle.into_iter());
+        event
+
+    }
+
+    /// Handle a received bundle, returning an event if any.
+    fn handle_bundle(&mut self, bundle: &Bundle, addr: SocketAddr) -> Option<Event> {
+
+        // Fully read the bundle to determine how to handle that client.
+        let mut reader = bundle.element_reader();
+        while let Some(reader) = reader.next() {
+            match reader {
+                NextElementReader::Element(elt) => {
+                    if let Err(error) = self.handle_element(elt, addr) {
+                        return Some(Event::IoError(IoErrorEvent { error, addr: Some(addr) }));
+                    }
+                }
+                NextElementReader::Reply(reply) => {
+                    return Some(Event::IoError(IoErrorEvent {
+                        error: io_invalid_data(format_args!("unexpected reply #{}", reply.request_id())),
+                        addr: Some(addr),
+                    }));
+                }
+            }
         }
+
+        None
+
     }
 
     /// Handle an element read from the given address.
@@ -202,6 +377,32 @@ impl App {
             request_id,
         });
 
+        // Reject the request without any event if the protocol version or the digest
+        // are not expected, just like the real login app.
+        if let Some(protocol_version) = self.protocol_version {
+            if login.element.protocol != protocol_version {
+                trace!("Rejected login protocol version: {}, expected: {protocol_version}", login.element.protocol);
+                self.answer_login_error(addr, LoginError::BadProtocolVersion, String::new());
+                return Ok(());
+            }
+        }
+
+        if let Some(expected_digest) = self.expected_digest {
+            if login.element.digest != Some(expected_digest) {
+                trace!("Rejected login digest: {:02X?}, expected: {expected_digest:02X?}", login.element.digest);
+                self.answer_login_error(addr, LoginError::BadDigest, String::new());
+                return Ok(());
+            }
+        }
+
+        if let Some(verifier) = self.credentials_verifier.as_deref() {
+            if let Err(error) = verifier.verify(&login.element.username, &login.element.password) {
+                trace!("Rejected login credentials of: {}, error: {error:?}", login.element.username);
+                self.answer_login_error(addr, error, String::new());
+                return Ok(());
+            }
+        }
+
         self.events.push_back(Event::Login(LoginEvent {
             addr,
             request: login.element,
@@ -330,10 +531,10 @@ impl App {
     }
 
     /// Send a challenge to the given client, this only works if a tracker exists.
-    fn send_response(&mut self, response: PendingResponse) -> io::Result<()> {
+    fn send_response(&mut self, response: &PendingResponse) -> io::Result<()> {
 
         self.bundle.clear();
-        self.bundle.element_writer().write_reply(response.inner, response.request.request_id, &*response.request.blowfish);
+        self.bundle.element_writer().write_reply(response.inner.clone(), response.request.request_id, &*response.request.blowfish);
 
         self.protocol.off_channel(response.addr).prepare(&mut self.bundle, false);
         self.socket.send_bundle_without_encryption(&self.bundle, response.addr)?;
@@ -344,10 +545,23 @@ impl App {
 
 }
 
+/// A verifier of the credentials given in login requests, this can be used to reject
+/// unknown users or invalid passwords before the request is reported as an event.
+pub trait CredentialsVerifier: fmt::Debug + Send + Sync {
+
+    /// Verify the given username and password, the password is empty if the client 
+    /// logs in with a token, in such case the token is usually the username. The 
+    /// returned error, usually [`LoginError::InvalidUser`] or 
+    /// [`LoginError::InvalidPassword`], is sent to the client.
+    fn verify(&self, username: &str, password: &str) -> Result<(), LoginError>;
+
+}
+
 /// An event that happened in the login app regarding the login process.
 #[derive(Debug)]
 pub enum Event {
     IoError(IoErrorEvent),
+    Tick(TickEvent),
     Ping(PingEvent),
     Login(LoginEvent),
     Challenge(ChallengeEvent),
@@ -420,3 +634,99 @@ struct PendingChallenge {
     /// The configured max nonce.
     max_nonce: u32,
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crypto_common::KeyInit;
+
+    use crate::net::bundle::{NextElementReader, RequestTracker};
+
+    use super::*;
+
+    pub(super) const LOCALHOST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+    pub(super) const DIGEST: [u8; 16] = [0x42; 16];
+
+    /// Return the localhost address of the given socket address.
+    pub(super) fn localhost(addr: SocketAddr) -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, addr.port()))
+    }
+
+    /// Send a login request with the given protocol version and digest, and return
+    /// the response.
+    pub(super) fn login(client: &PacketSocket, server_addr: SocketAddr, protocol: u32, digest: Option<[u8; 16]>) -> LoginResponse {
+
+        let blowfish_key = vec![0x42; 16];
+        let blowfish = Blowfish::new_from_slice(&blowfish_key).unwrap();
+
+        let mut tracker = RequestTracker::<()>::new();
+        let mut bundle = Bundle::new();
+        bundle.element_writer().add_request(&mut tracker, LoginRequest {
+            protocol,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            blowfish_key,
+            context: String::new(),
+            digest,
+            nonce: 0,
+        });
+
+        let mut protocol = Protocol::new();
+        protocol.off_channel(server_addr).prepare(&mut bundle, false);
+        client.send_bundle_without_encryption(&bundle, server_addr).unwrap();
+
+        let (packet, addr) = client.recv_without_encryption().unwrap();
+        let mut channel = protocol.accept(packet, addr).unwrap();
+        let bundle = channel.next_bundle().unwrap();
+
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Reply(reply)) = reader.next() else { panic!("expected a reply") };
+        reply.read::<LoginResponse, _>(&blowfish).unwrap()
+
+    }
+
+    #[test]
+    fn login_version_and_digest() {
+
+        let client = PacketSocket::bind(LOCALHOST).unwrap();
+        client.set_recv_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut app = App::new(LOCALHOST).unwrap();
+        app.set_protocol_version(Some(5));
+        app.set_expected_digest(Some(DIGEST));
+        let server_addr = localhost(app.addr().unwrap());
+
+        let (sender, logins) = mpsc::channel();
+        thread::spawn(move || loop {
+            if let Event::Login(event) = app.poll() {
+                let request = (event.request.protocol, event.request.digest);
+                app.answer_login_error(event.addr, LoginError::Banned, String::new());
+                if sender.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let res = login(&client, server_addr, 4, Some(DIGEST));
+        assert!(matches!(res, LoginResponse::Error(LoginError::BadProtocolVersion, _)), "{res:?}");
+
+        let res = login(&client, server_addr, 5, None);
+        assert!(matches!(res, LoginResponse::Error(LoginError::BadDigest, _)), "{res:?}");
+
+        let res = login(&client, server_addr, 5, Some([0x43; 16]));
+        assert!(matches!(res, LoginResponse::Error(LoginError::BadDigest, _)), "{res:?}");
+
+        // Rejected requests are not reported.
+        let res = login(&client, server_addr, 5, Some(DIGEST));
+        assert!(matches!(res, LoginResponse::Error(LoginError::Banned, _)), "{res:?}");
+        assert_eq!(logins.recv_timeout(Duration::from_secs(5)).unwrap(), (5, Some(DIGEST)));
+        assert!(logins.try_recv().is_err());
+
+    }
+
+}
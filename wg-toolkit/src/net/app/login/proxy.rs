@@ -10,16 +10,17 @@ use blowfish::Blowfish;
 
 use tracing::{trace, trace_span};
 
-use crate::net::bundle::{Bundle, NextElementReader, ReplyReader, ElementReader};
+use crate::net::bundle::{Bundle, BundleElement, NextElementReader, ReplyReader, ElementReader};
 use crate::net::app::login::element::{ChallengeResponse, CuckooCycleResponse};
-use crate::net::app::proxy::{UNSPECIFIED_ADDR, RECV_TIMEOUT};
+use crate::net::app::proxy::{PacketDirection, UNSPECIFIED_ADDR, RECV_TIMEOUT};
 use crate::net::socket::PacketSocket;
 use crate::net::proto::Protocol;
+use crate::net::variant::ProtocolVariant;
 use crate::net::packet::Packet;
 
 use crate::util::thread::{ThreadPoll, ThreadPollHandle};
 
-use super::element::{self, LoginError, LoginRequest, LoginResponse, Ping};
+use super::element::{self, LoginError, LoginParams, LoginRequest, LoginResponse, Ping};
 use super::io_invalid_data;
 
 
@@ -53,6 +54,17 @@ struct Inner {
     real_addr: SocketAddr,
     /// Encryption key for sending to the real login application.
     real_encryption_key: Option<Arc<RsaPublicKey>>,
+    /// The protocol version forced in login requests forwarded to the real login
+    /// application, the version of the client is kept if none.
+    protocol_version: Option<u32>,
+    /// The protocol version of the last login request received from a client.
+    detected_protocol_version: Option<u32>,
+    /// The digest of the entity definitions expected in login requests, a mismatch is
+    /// reported but the request is still forwarded as-is.
+    expected_digest: Option<[u8; 16]>,
+    /// Enable the bundle events, returning each bundle received from the clients or
+    /// from the real login application.
+    bundle_events: bool,
     /// Protocol for accepting out packets and preparing in packets.
     out_protocol: Protocol,
     /// Protocol for accepting in packets and preparing out packets.
@@ -86,7 +98,7 @@ struct PeerLastRequest {
 #[derive(Debug)]
 enum PeerLastRequestKind {
     Ping {},
-    Login { blowfish: Arc<Blowfish>, },
+    Login { blowfish: Arc<Blowfish>, protocol_version: u32, },
 }
 
 /// Type of return value for our socket poll. 
@@ -101,10 +113,16 @@ struct SocketPollRet {
 impl App {
 
     pub fn new(addr: SocketAddr, real_addr: SocketAddr, real_encryption_key: Option<Arc<RsaPublicKey>>) -> io::Result<Self> {
+        Self::with_socket(PacketSocket::bind(addr)?, real_addr, real_encryption_key)
+    }
+
+    /// Create a new login proxy application listening on the given socket, this can be
+    /// used to run the proxy on another transport than UDP. Note that sockets used to
+    /// communicate with the real login application are still bound with UDP.
+    pub fn with_socket(socket: PacketSocket, real_addr: SocketAddr, real_encryption_key: Option<Arc<RsaPublicKey>>) -> io::Result<Self> {
         
         let socket_poll = ThreadPoll::new();
 
-        let socket = PacketSocket::bind(addr)?;
         socket.set_recv_timeout(Some(RECV_TIMEOUT))?;
 
         let thread_socket = socket.clone();
@@ -122,6 +140,10 @@ impl App {
                 forced_base_app_addr: None,
                 real_addr,
                 real_encryption_key,
+                protocol_version: None,
+                detected_protocol_version: None,
+                expected_digest: None,
+                bundle_events: false,
                 out_protocol: Protocol::new(),
                 in_protocol: Protocol::new(),
                 bundle: Bundle::new(),
@@ -162,6 +184,50 @@ impl App {
         self.inner.forced_base_app_addr = None;
     }
 
+    /// Set the protocol variant used to read and write packets and elements in both
+    /// directions, the PC client's variant is used by default.
+    pub fn set_protocol_variant(&mut self, variant: ProtocolVariant) {
+        self.inner.bundle.set_variant(&variant);
+        self.inner.out_protocol.set_variant(variant.clone());
+        self.inner.in_protocol.set_variant(variant);
+    }
+
+    /// Force the protocol version of login requests forwarded to the real login app,
+    /// none to forward the version of the client as-is.
+    pub fn set_protocol_version(&mut self, version: Option<u32>) {
+        self.inner.protocol_version = version;
+    }
+
+    /// Return the protocol version forced in forwarded login requests, if any.
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.inner.protocol_version
+    }
+
+    /// Return the protocol version detected from the last login request received from
+    /// a client, none if no client has logged in yet.
+    pub fn detected_protocol_version(&self) -> Option<u32> {
+        self.inner.detected_protocol_version
+    }
+
+    /// Set the digest of the entity definitions expected in login requests, when a
+    /// client sends another digest, a [`LoginDigestMismatchEvent`] is returned, but the
+    /// request is still forwarded to the real login app.
+    pub fn set_expected_digest(&mut self, digest: Option<[u8; 16]>) {
+        self.inner.expected_digest = digest;
+    }
+
+    /// Return the digest of the entity definitions expected in login requests, if any.
+    pub fn expected_digest(&self) -> Option<[u8; 16]> {
+        self.inner.expected_digest
+    }
+
+    /// Enable or disable the bundle events, when enabled each bundle received from the
+    /// clients or from the real login application is returned as a [`BundleEvent`]
+    /// after being handled, in order to record the login traffic for example.
+    pub fn set_bundle_events(&mut self, enabled: bool) {
+        self.inner.bundle_events = enabled;
+    }
+
     /// Poll for the next event of this login app, blocking.
     pub fn poll(&mut self) -> Event {
         loop {
@@ -187,7 +253,7 @@ impl App {
 
             let (packet, addr) = match socket_poll_ret.res {
                 Ok(ret) => ret,
-                Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) => continue,
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
                 Err(e) => {
                     return Event::IoError(IoErrorEvent {
                         error: e,
@@ -261,10 +327,20 @@ impl App {
                 continue;
             };
 
-            if socket_poll_ret.peer.is_some() {
-                self.inner.handle_in(bundle, peer).unwrap();
+            let direction = if socket_poll_ret.peer.is_some() {
+                self.inner.handle_in(&bundle, peer).unwrap();
+                PacketDirection::In
             } else {
-                self.inner.handle_out(bundle, peer).unwrap();
+                self.inner.handle_out(&bundle, peer).unwrap();
+                PacketDirection::Out
+            };
+
+            if self.inner.bundle_events {
+                self.inner.events.push_back(Event::Bundle(BundleEvent {
+                    addr: peer.addr,
+                    bundle,
+                    direction,
+                }));
             }
 
         }
@@ -274,7 +350,7 @@ impl App {
 
 impl Inner {
 
-    fn handle_out(&mut self, bundle: Bundle, peer: &mut Peer) -> io::Result<()> {
+    fn handle_out(&mut self, bundle: &Bundle, peer: &mut Peer) -> io::Result<()> {
         
         self.bundle.clear();
 
@@ -331,9 +407,19 @@ impl Inner {
     /// Handle a login request to the login node.
     fn handle_login_request(&mut self, elt: ElementReader, peer: &mut Peer) -> io::Result<()> {
         
-        let login;
+        let mut login;
         if let Some(encryption_key) = self.encryption_key.as_deref() {
-            login = elt.read::<LoginRequest, _>(encryption_key)?;
+            // Keep the decrypted parameters, in order to report them as-is.
+            let params = elt.read::<LoginParams, _>(encryption_key)?;
+            login = BundleElement {
+                element: params.element.decode()?,
+                request_id: params.request_id,
+            };
+            self.events.push_back(Event::LoginRequest(LoginRequestEvent {
+                addr: peer.addr,
+                request: login.element.clone(),
+                params: params.element,
+            }));
         } else {
             login = elt.read_simple::<LoginRequest>()?;
         }
@@ -347,9 +433,24 @@ impl Inner {
         peer.last_request = Some(PeerLastRequest {
             request_id,
             time: Instant::now(),
-            kind: PeerLastRequestKind::Login { blowfish },
+            kind: PeerLastRequestKind::Login { blowfish, protocol_version: login.element.protocol },
         });
 
+        self.detected_protocol_version = Some(login.element.protocol);
+        if let Some(protocol_version) = self.protocol_version {
+            login.element.protocol = protocol_version;
+        }
+
+        if let Some(expected_digest) = self.expected_digest {
+            if login.element.digest != Some(expected_digest) {
+                self.events.push_back(Event::LoginDigestMismatch(LoginDigestMismatchEvent {
+                    addr: peer.addr,
+                    digest: login.element.digest,
+                    expected_digest,
+                }));
+            }
+        }
+
         if let Some(encryption_key) = self.real_encryption_key.as_deref() {
             self.bundle.element_writer().write_request(login.element.clone(), request_id, encryption_key);
         } else {
@@ -366,7 +467,7 @@ impl Inner {
         Ok(())
     }
 
-    fn handle_in(&mut self, bundle: Bundle, peer: &mut Peer) -> io::Result<()> {
+    fn handle_in(&mut self, bundle: &Bundle, peer: &mut Peer) -> io::Result<()> {
 
         self.bundle.clear();
         
@@ -430,7 +531,7 @@ impl Inner {
                 self.bundle.element_writer().write_simple_reply(ping, request_id);
                 
             }
-            PeerLastRequestKind::Login { blowfish } => {
+            PeerLastRequestKind::Login { blowfish, protocol_version } => {
 
                 let mut login = elt.read::<LoginResponse, _>(&*blowfish)?;
                 
@@ -440,6 +541,7 @@ impl Inner {
                     self.events.push_back(Event::LoginSuccess(LoginSuccessEvent {
                         addr: peer.addr,
                         blowfish: Arc::clone(&blowfish),
+                        protocol_version,
                         real_base_app_addr: success.addr,
                         login_key: success.login_key,
                         server_message: success.server_message.clone(),
@@ -477,8 +579,11 @@ impl Inner {
 pub enum Event {
     IoError(IoErrorEvent),
     Ping(PingEvent),
+    LoginRequest(LoginRequestEvent),
+    LoginDigestMismatch(LoginDigestMismatchEvent),
     LoginSuccess(LoginSuccessEvent),
     LoginError(LoginErrorEvent),
+    Bundle(BundleEvent),
 }
 
 /// Some IO error happened internally and optionally related to a client.
@@ -501,6 +606,33 @@ pub struct PingEvent {
     pub latency: Duration,
 }
 
+/// A client has sent a login request that has been decrypted with the private key, this
+/// event is only emitted if encryption is enabled, because the request is otherwise
+/// sent in clear by the client.
+#[derive(Debug)]
+pub struct LoginRequestEvent {
+    /// The address of the client that sent the login request.
+    pub addr: SocketAddr,
+    /// The decrypted login request, including all secrets.
+    pub request: LoginRequest,
+    /// The decrypted parameters of the login request, as sent by the client, which
+    /// also include all secrets.
+    pub params: LoginParams,
+}
+
+/// A client has sent a login request with a digest of its entity definitions that is
+/// not the expected one, the request is still forwarded as-is to the real login app,
+/// which is likely to reject it with [`LoginError::BadDigest`].
+#[derive(Debug)]
+pub struct LoginDigestMismatchEvent {
+    /// The address of the client that sent the login request.
+    pub addr: SocketAddr,
+    /// The digest sent by the client, if any.
+    pub digest: Option<[u8; 16]>,
+    /// The expected digest.
+    pub expected_digest: [u8; 16],
+}
+
 /// A client has successfully logged in the real login application.
 #[derive(Debug)]
 pub struct LoginSuccessEvent {
@@ -509,6 +641,8 @@ pub struct LoginSuccessEvent {
     /// The blowfish key the client sent with its login request and used to decode any
     /// successful response, but also for any input/output packet with the base app.
     pub blowfish: Arc<Blowfish>,
+    /// The protocol version of the client's login request, before being forced.
+    pub protocol_version: u32,
     /// The address of the base app that was answered by the real server, if any base
     /// app address is forced then this value is still the value of the real server.
     pub real_base_app_addr: SocketAddrV4,
@@ -525,3 +659,107 @@ pub struct LoginErrorEvent {
     pub error: LoginError,
     pub data: String,
 }
+
+/// A bundle has been received from a client or from the real login application, this
+/// event is only emitted if bundle events are enabled.
+#[derive(Debug)]
+pub struct BundleEvent {
+    /// The address of the client, regardless of the direction.
+    pub addr: SocketAddr,
+    /// The bundle as received, the login request is still encrypted if encryption is
+    /// enabled.
+    pub bundle: Bundle,
+    /// The direction this bundle was intercepted.
+    pub direction: PacketDirection,
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::super::tests::{login, localhost, DIGEST, LOCALHOST};
+    use super::*;
+
+    #[test]
+    fn digest_mismatch() {
+
+        let client = PacketSocket::bind(LOCALHOST).unwrap();
+        client.set_recv_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        // The real login app rejects the digest.
+        let mut real_app = super::super::App::new(LOCALHOST).unwrap();
+        real_app.set_expected_digest(Some(DIGEST));
+        let real_addr = localhost(real_app.addr().unwrap());
+        thread::spawn(move || loop {
+            real_app.poll();
+        });
+
+        let mut app = App::new(LOCALHOST, real_addr, None).unwrap();
+        app.set_expected_digest(Some(DIGEST));
+        let addr = localhost(app.addr().unwrap());
+
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || loop {
+            let event = match app.poll() {
+                Event::LoginDigestMismatch(mismatch) => (mismatch.digest, None),
+                Event::LoginError(error) => (None, Some(error.error)),
+                _ => continue,
+            };
+            if sender.send(event).is_err() {
+                break;
+            }
+        });
+
+        // The request is still forwarded, and the error of the real login app returned.
+        let res = login(&client, addr, 0, Some([0x43; 16]));
+        assert!(matches!(res, LoginResponse::Error(LoginError::BadDigest, _)), "{res:?}");
+        assert_eq!(events.recv_timeout(Duration::from_secs(5)).unwrap(), (Some([0x43; 16]), None));
+        assert_eq!(events.recv_timeout(Duration::from_secs(5)).unwrap(), (None, Some(LoginError::BadDigest)));
+
+    }
+
+    #[test]
+    fn bundle_events() {
+
+        let client = PacketSocket::bind(LOCALHOST).unwrap();
+        client.set_recv_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        // The real login app rejects the digest, so that it answers on its own.
+        let mut real_app = super::super::App::new(LOCALHOST).unwrap();
+        real_app.set_expected_digest(Some(DIGEST));
+        let real_addr = localhost(real_app.addr().unwrap());
+        thread::spawn(move || loop {
+            real_app.poll();
+        });
+
+        let mut app = App::new(LOCALHOST, real_addr, None).unwrap();
+        app.set_bundle_events(true);
+        let addr = localhost(app.addr().unwrap());
+
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || loop {
+            if let Event::Bundle(bundle) = app.poll() {
+                if sender.send((bundle.addr, bundle.direction, bundle.bundle.len())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let res = login(&client, addr, 0, Some([0x43; 16]));
+        assert!(matches!(res, LoginResponse::Error(LoginError::BadDigest, _)), "{res:?}");
+
+        // Both the request of the client and the response of the real login app.
+        let client_addr = localhost(client.addr().unwrap());
+        let (out_addr, out_direction, out_len) = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((out_addr, out_direction), (client_addr, PacketDirection::Out));
+        assert_ne!(out_len, 0);
+        let (in_addr, in_direction, in_len) = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((in_addr, in_direction), (client_addr, PacketDirection::In));
+        assert_ne!(in_len, 0);
+
+    }
+
+}
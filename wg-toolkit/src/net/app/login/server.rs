@@ -0,0 +1,265 @@
+//! Login server answering login requests on its own, by verifying the credentials of
+//! clients and routing the accepted ones to a base application, this is built on the
+//! lower-level login [`App`](super::App) which leaves login requests to the user.
+
+use std::net::{SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::io;
+
+use rsa::RsaPrivateKey;
+use blowfish::Blowfish;
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+use tracing::trace;
+
+use crate::net::socket::PacketSocket;
+
+use super::element::LoginRequest;
+use super::{CredentialsVerifier, IoErrorEvent, PingEvent};
+use super::super::tick::{TickEvent, TickScheduler};
+
+
+/// The login server application.
+#[derive(Debug)]
+pub struct App {
+    /// The underlying login app, with the credentials verifier.
+    inner: super::App,
+    /// The address of the base app where accepted clients are routed.
+    base_app_addr: SocketAddrV4,
+    /// The message sent to clients on successful login.
+    server_message: String,
+}
+
+impl App {
+
+    /// Create a new login server on the given address, the credentials of all login
+    /// requests are verified with the given verifier and accepted clients are routed
+    /// to the given base app address.
+    pub fn new(addr: SocketAddr, base_app_addr: SocketAddrV4, verifier: Arc<dyn CredentialsVerifier>) -> io::Result<Self> {
+        Ok(Self::with_socket(PacketSocket::bind(addr)?, base_app_addr, verifier))
+    }
+
+    /// Create a new login server running on the given socket, see [`Self::new`].
+    pub fn with_socket(socket: PacketSocket, base_app_addr: SocketAddrV4, verifier: Arc<dyn CredentialsVerifier>) -> Self {
+        let mut inner = super::App::with_socket(socket);
+        inner.set_credentials_verifier(Some(verifier));
+        Self {
+            inner,
+            base_app_addr,
+            server_message: String::new(),
+        }
+    }
+
+    /// Return the address this login server is bound to.
+    #[inline]
+    pub fn addr(&self) -> io::Result<SocketAddr> {
+        self.inner.addr()
+    }
+
+    /// Get the underlying login app, to configure the expected protocol version or
+    /// digest for example.
+    #[inline]
+    pub fn inner(&self) -> &super::App {
+        &self.inner
+    }
+
+    /// Get the underlying login app, mutably, see [`Self::inner`].
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut super::App {
+        &mut self.inner
+    }
+
+    /// Set the private key used to decrypt login requests, clients should then use
+    /// the matching public key.
+    #[inline]
+    pub fn set_encryption(&mut self, key: Arc<RsaPrivateKey>) {
+        self.inner.set_encryption(key);
+    }
+
+    /// Set the tick scheduler of this server, see [`super::App::set_tick_scheduler`].
+    #[inline]
+    pub fn set_tick_scheduler(&mut self, tick: Option<TickScheduler>) -> io::Result<()> {
+        self.inner.set_tick_scheduler(tick)
+    }
+
+    /// Set the address of the base app where accepted clients are routed.
+    #[inline]
+    pub fn set_base_app_addr(&mut self, addr: SocketAddrV4) {
+        self.base_app_addr = addr;
+    }
+
+    /// Set the message sent to clients on successful login.
+    #[inline]
+    pub fn set_server_message(&mut self, message: String) {
+        self.server_message = message;
+    }
+
+    /// Poll for the next event of this login server, blocking. Login requests with
+    /// valid credentials are answered with the base app address and a new login key
+    /// before being returned.
+    pub fn poll(&mut self) -> Event {
+        loop {
+            match self.inner.poll() {
+                super::Event::IoError(event) => return Event::IoError(event),
+                super::Event::Tick(event) => return Event::Tick(event),
+                super::Event::Ping(event) => return Event::Ping(event),
+                super::Event::Login(event) => {
+                    if let Some(event) = self.accept_login(event.addr, event.request) {
+                        return Event::Login(event);
+                    }
+                }
+                // No challenge is ever issued by the server.
+                super::Event::Challenge(_) => {}
+            }
+        }
+    }
+
+    /// Accept the given login request, which credentials have been verified.
+    fn accept_login(&mut self, addr: SocketAddr, request: LoginRequest) -> Option<LoginEvent> {
+
+        let login_key = OsRng.next_u32();
+        let blowfish = self.inner.answer_login_success(addr, self.base_app_addr, login_key, self.server_message.clone())?;
+
+        trace!("Accepted login of: {}, from: {addr}, login key: 0x{login_key:08X}", request.username);
+
+        Some(LoginEvent {
+            addr,
+            request,
+            login_key,
+            blowfish,
+        })
+
+    }
+
+}
+
+/// An event that happened in the login server.
+#[derive(Debug)]
+pub enum Event {
+    IoError(IoErrorEvent),
+    Tick(TickEvent),
+    Ping(PingEvent),
+    Login(LoginEvent),
+}
+
+/// A client has been accepted and routed to the base app, which should expect it to
+/// log in with the given login key, and then encrypt its packets with the given
+/// blowfish cipher.
+#[derive(Debug)]
+pub struct LoginEvent {
+    /// The address of the client that logged in.
+    pub addr: SocketAddr,
+    /// The login request, with verified credentials.
+    pub request: LoginRequest,
+    /// The login key given to the client, to be sent to the base app.
+    pub login_key: u32,
+    /// The session cipher, from the blowfish key of the request.
+    pub blowfish: Arc<Blowfish>,
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::Ipv4Addr;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use std::thread;
+
+    use crypto_common::KeyInit;
+
+    use crate::net::bundle::{Bundle, NextElementReader, RequestTracker};
+    use crate::net::proto::Protocol;
+
+    use super::super::element::{LoginError, LoginResponse};
+    use super::*;
+
+    const LOCALHOST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+    const BASE_APP_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 20016);
+
+    #[derive(Debug)]
+    struct TestVerifier;
+
+    impl CredentialsVerifier for TestVerifier {
+        fn verify(&self, username: &str, password: &str) -> Result<(), LoginError> {
+            match (username, password) {
+                ("user", "pass") => Ok(()),
+                ("user", _) => Err(LoginError::InvalidPassword),
+                _ => Err(LoginError::InvalidUser),
+            }
+        }
+    }
+
+    /// Send a login request with the given credentials and return the response.
+    fn login(client: &PacketSocket, server_addr: SocketAddr, username: &str, password: &str) -> LoginResponse {
+
+        let blowfish_key = vec![0x42; 16];
+        let blowfish = Blowfish::new_from_slice(&blowfish_key).unwrap();
+
+        let mut tracker = RequestTracker::<()>::new();
+        let mut bundle = Bundle::new();
+        bundle.element_writer().add_request(&mut tracker, LoginRequest {
+            protocol: 0,
+            username: username.to_string(),
+            password: password.to_string(),
+            blowfish_key,
+            context: String::new(),
+            digest: None,
+            nonce: 0,
+        });
+
+        let mut protocol = Protocol::new();
+        protocol.off_channel(server_addr).prepare(&mut bundle, false);
+        client.send_bundle_without_encryption(&bundle, server_addr).unwrap();
+
+        let (packet, addr) = client.recv_without_encryption().unwrap();
+        let mut channel = protocol.accept(packet, addr).unwrap();
+        let bundle = channel.next_bundle().unwrap();
+
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Reply(reply)) = reader.next() else { panic!("expected a reply") };
+        reply.read::<LoginResponse, _>(&blowfish).unwrap()
+
+    }
+
+    #[test]
+    fn login_verified() {
+
+        let client = PacketSocket::bind(LOCALHOST).unwrap();
+        client.set_recv_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut app = App::new(LOCALHOST, BASE_APP_ADDR, Arc::new(TestVerifier)).unwrap();
+        app.set_server_message("welcome".to_string());
+        let server_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, app.addr().unwrap().port()));
+
+        let (sender, logins) = mpsc::channel();
+        thread::spawn(move || loop {
+            if let Event::Login(event) = app.poll() {
+                if sender.send((event.request.username, event.login_key)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let res = login(&client, server_addr, "unknown", "pass");
+        assert!(matches!(res, LoginResponse::Error(LoginError::InvalidUser, _)), "{res:?}");
+
+        let res = login(&client, server_addr, "user", "wrong");
+        assert!(matches!(res, LoginResponse::Error(LoginError::InvalidPassword, _)), "{res:?}");
+
+        let res = login(&client, server_addr, "user", "pass");
+        let LoginResponse::Success(success) = res else { panic!("expected a success: {res:?}") };
+        assert_eq!(success.addr, BASE_APP_ADDR);
+        assert_eq!(success.server_message, "welcome");
+
+        // Only the accepted login is reported, with the same login key.
+        let (username, login_key) = logins.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(username, "user");
+        assert_eq!(login_key, success.login_key);
+        assert!(logins.try_recv().is_err());
+
+    }
+
+}
@@ -3,7 +3,8 @@
 //! 
 //! - Login app, the server-side application defining the protocol to establish an
 //!   initial connection request from the client with initial RSA encryption and 
-//!   then transfers the client to the base app once it's successful.
+//!   then transfers the client to the base app once it's successful. A ready-to-use
+//!   login server, verifying credentials on its own, is built on top of it.
 //! 
 //! - Client app, the client-side application defining the protocol understood by 
 //!   the client, where the client sends its entities and method calls to it.
@@ -15,6 +16,7 @@
 //! - Cell app, the server-side application, not directly exposed to the client that
 //!   is receiving requests from client when in-game.
 
+pub mod tick;
 pub mod proxy;
 
 pub mod login;
@@ -25,8 +27,37 @@ pub mod base;
 
 use std::{fmt, io};
 
+#[cfg(feature = "tokio")]
+use crate::net::socket::PacketSocket;
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use std::task::Poll;
+
 
 #[inline]
 fn io_invalid_data(msg: fmt::Arguments<'_>) -> io::Error {
     io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
 }
+
+/// Wait until the given socket is ready to receive a packet, or until the given timeout
+/// elapses, in which case false is returned. If requested, false is also returned when
+/// the socket is ready to send a packet, so that pending packets can be sent again.
+#[cfg(feature = "tokio")]
+async fn recv_ready(socket: &PacketSocket, send: bool, timeout: Option<Duration>) -> io::Result<bool> {
+    let ready = std::future::poll_fn(|cx| {
+        if send {
+            if let Poll::Ready(res) = socket.poll_send_ready(cx) {
+                return Poll::Ready(res.map(|()| false));
+            }
+        }
+        socket.poll_recv_ready(cx).map(|res| res.map(|()| true))
+    });
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, ready).await {
+            Ok(res) => res,
+            Err(_) => Ok(false),
+        },
+        None => ready.await,
+    }
+}
@@ -0,0 +1,199 @@
+//! Parallel bulk decoding of captured packets, for offline analysis of large captures.
+//!
+//! This module is only available with the `parallel` feature.
+
+use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::io;
+
+use rayon::prelude::*;
+
+use tracing::{trace_span, Span};
+
+use crate::net::cipher::PacketCipher;
+use crate::net::packet::Packet;
+
+use super::{BundleEvent, Decoder, PacketDirection};
+
+
+/// A bulk decoder of captured packets, packets are decoded in batches where each peer
+/// is decoded in parallel with its own decoder, while the order of the events of each
+/// peer is preserved. Each peer being independent, this is equivalent to decoding all
+/// the packets of the batch in order with a single [`Decoder`].
+#[derive(Debug, Default)]
+pub struct BulkDecoder {
+    /// Decoding state of each peer.
+    peers: HashMap<SocketAddr, BulkPeer>,
+}
+
+/// A packet to be decoded in bulk.
+#[derive(Debug)]
+pub struct BulkPacket {
+    /// The packet, possibly encrypted.
+    pub packet: Packet,
+    /// The client's address, regardless of the direction.
+    pub addr: SocketAddr,
+    /// The direction this packet has been sent in.
+    pub direction: PacketDirection,
+}
+
+/// Internal decoding state of a peer.
+#[derive(Debug)]
+struct BulkPeer {
+    decoder: Decoder,
+    cipher: Option<Arc<dyn PacketCipher>>,
+}
+
+impl BulkDecoder {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cipher, usually a blowfish key, used to decrypt the packets of the given
+    /// peer.
+    pub fn set_cipher(&mut self, addr: SocketAddr, cipher: Option<Arc<dyn PacketCipher>>) {
+        self.peers.entry(addr).or_insert_with(BulkPeer::new).cipher = cipher;
+    }
+
+    /// Decode a batch of packets, the returned events are associated to the index, in
+    /// the batch, of the packet that completed the bundle or returned the error, and
+    /// they are sorted by this index. The decoding state of each peer is kept for the
+    /// next batch.
+    pub fn decode_batch(&mut self, packets: impl IntoIterator<Item = BulkPacket>) -> Vec<(usize, io::Result<BundleEvent>)> {
+
+        // Group packets by peer, keeping their index in the batch.
+        let mut groups = HashMap::<SocketAddr, Vec<(usize, BulkPacket)>>::new();
+        for (index, packet) in packets.into_iter().enumerate() {
+            groups.entry(packet.addr).or_default().push((index, packet));
+        }
+
+        let mut jobs = groups.into_iter()
+            .map(|(addr, packets)| {
+                let peer = self.peers.remove(&addr).unwrap_or_else(BulkPeer::new);
+                (addr, peer, packets)
+            })
+            .collect::<Vec<_>>();
+
+        // Decoding happens on the thread pool, so the span of each peer is explicitly
+        // attached to the caller's span.
+        let parent_span = Span::current();
+
+        let mut events = jobs.par_iter_mut()
+            .flat_map_iter(|(addr, peer, packets)| {
+                let _span = trace_span!(parent: &parent_span, "bulk", %addr).entered();
+                std::mem::take(packets).into_iter()
+                    .filter_map(|(index, packet)| {
+                        let cipher = peer.cipher.as_deref();
+                        match peer.decoder.decode(packet.packet, packet.addr, packet.direction, cipher) {
+                            Ok(Some(bundle)) => Some((index, Ok(bundle))),
+                            Ok(None) => None,
+                            Err(e) => Some((index, Err(e))),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for (addr, peer, _) in jobs {
+            self.peers.insert(addr, peer);
+        }
+
+        events.sort_unstable_by_key(|(index, _)| *index);
+        events
+
+    }
+
+}
+
+impl BulkPeer {
+
+    fn new() -> Self {
+        Self {
+            decoder: Decoder::new(),
+            cipher: None,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use crate::net::element::DebugElementVariable16;
+    use crate::net::bundle::Bundle;
+    use crate::net::proto::Protocol;
+
+    use super::*;
+
+    /// Return the packets of bundles of 2, 1 and 3 packets sent by the given client.
+    fn peer_packets(addr: SocketAddr, data: u8) -> Vec<BulkPacket> {
+        let mut protocol = Protocol::new();
+        let mut packets = Vec::new();
+        for len in [2, 1, 3] {
+            let mut bundle = Bundle::new();
+            bundle.element_writer().write_simple(DebugElementVariable16::<0x10> { data: vec![data; 1000 * len] });
+            assert_eq!(bundle.len(), len);
+            protocol.off_channel(addr).prepare(&mut bundle, false);
+            packets.extend(bundle.iter().map(|packet| BulkPacket {
+                packet: packet.clone(),
+                addr,
+                direction: PacketDirection::Out,
+            }));
+        }
+        packets
+    }
+
+    /// Return the index, address and packets' data of each decoded bundle.
+    fn summary(events: Vec<(usize, io::Result<BundleEvent>)>) -> Vec<(usize, SocketAddr, Vec<Vec<u8>>)> {
+        events.into_iter()
+            .map(|(index, event)| {
+                let event = event.unwrap();
+                (index, event.addr, event.bundle.iter().map(|packet| packet.slice().to_vec()).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_batch() {
+
+        let addrs = [1234, 1235, 1236].map(|port| SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)));
+
+        // Packets of all peers are interleaved.
+        let mut peers = addrs.iter().enumerate()
+            .map(|(i, &addr)| peer_packets(addr, i as u8).into_iter())
+            .collect::<Vec<_>>();
+        let mut packets = Vec::new();
+        while let Some(packet) = peers.iter_mut().find_map(|peer| peer.next()) {
+            packets.push(packet);
+            peers.rotate_left(1);
+        }
+
+        // Expected events, from a single sequential decoder.
+        let mut decoder = Decoder::new();
+        let expected = packets.iter()
+            .enumerate()
+            .filter_map(|(index, packet)| {
+                decoder.decode(packet.packet.clone(), packet.addr, packet.direction, None).unwrap()
+                    .map(|event| (index, Ok(event)))
+            })
+            .collect::<Vec<_>>();
+        let expected = summary(expected);
+        assert_eq!(expected.len(), 9);
+
+        // The batch is split in the middle of bundles, the state of each peer is kept.
+        let mut bulk = BulkDecoder::new();
+        let second = packets.split_off(packets.len() / 2 + 1);
+        let first_len = packets.len();
+        let mut events = summary(bulk.decode_batch(packets));
+        events.extend(summary(bulk.decode_batch(second)).into_iter()
+            .map(|(index, addr, data)| (first_len + index, addr, data)));
+
+        assert_eq!(events, expected);
+
+    }
+
+}
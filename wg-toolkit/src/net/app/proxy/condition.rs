@@ -0,0 +1,168 @@
+//! Simulation of bad network conditions on the packets forwarded by the proxy, this
+//! can be used to reproduce the behavior of clients under delay, jitter, packet loss,
+//! reordering or duplication.
+
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use smallvec::SmallVec;
+
+use super::PacketDirection;
+
+
+/// The network condition to simulate in one direction, see [`NetworkSimulator`]. The
+/// default condition is a perfect network that doesn't alter any packet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkCondition {
+    /// Fixed delay applied to every forwarded packet.
+    pub delay: Duration,
+    /// Maximum random delay added to the fixed delay, uniformly distributed.
+    pub jitter: Duration,
+    /// Percentage of packets that are lost, never forwarded.
+    pub loss: f64,
+    /// Percentage of packets that are forwarded twice, each copy being delayed
+    /// independently.
+    pub duplicate: f64,
+    /// Percentage of packets that are forwarded immediately, ignoring the delay, so
+    /// that they overtake previously delayed packets. This has no effect without delay
+    /// or jitter.
+    pub reorder: f64,
+}
+
+/// A simulator of network conditions in both directions, all random decisions are
+/// taken from a seeded RNG, so that a given sequence of packets is always altered in
+/// the same way for a given seed. See [`super::App::set_network_simulator`].
+#[derive(Debug, Clone)]
+pub struct NetworkSimulator {
+    /// The seeded RNG used for all random decisions.
+    rng: StdRng,
+    /// Condition of the packets sent by clients.
+    out_condition: NetworkCondition,
+    /// Condition of the packets received by clients.
+    in_condition: NetworkCondition,
+}
+
+impl NetworkSimulator {
+
+    /// Create a new simulator from the given seed, with a perfect network condition in
+    /// both directions.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            out_condition: NetworkCondition::default(),
+            in_condition: NetworkCondition::default(),
+        }
+    }
+
+    /// Get the condition simulated in the given direction.
+    pub fn condition(&self, direction: PacketDirection) -> &NetworkCondition {
+        match direction {
+            PacketDirection::Out => &self.out_condition,
+            PacketDirection::In => &self.in_condition,
+        }
+    }
+
+    /// Set the condition to simulate in the given direction.
+    pub fn set_condition(&mut self, direction: PacketDirection, condition: NetworkCondition) {
+        match direction {
+            PacketDirection::Out => self.out_condition = condition,
+            PacketDirection::In => self.in_condition = condition,
+        }
+    }
+
+    /// Simulate the forwarding of a packet in the given direction, returning the delay
+    /// of each copy of the packet to forward, the returned list is empty if the packet
+    /// is lost.
+    pub(super) fn simulate(&mut self, direction: PacketDirection) -> SmallVec<[Duration; 2]> {
+
+        let condition = match direction {
+            PacketDirection::Out => &self.out_condition,
+            PacketDirection::In => &self.in_condition,
+        };
+
+        let mut delays = SmallVec::new();
+
+        if gen_percent(&mut self.rng, condition.loss) {
+            return delays;
+        }
+
+        let copies = if gen_percent(&mut self.rng, condition.duplicate) { 2 } else { 1 };
+
+        for _ in 0..copies {
+            if gen_percent(&mut self.rng, condition.reorder) {
+                delays.push(Duration::ZERO);
+            } else if condition.jitter.is_zero() {
+                delays.push(condition.delay);
+            } else {
+                delays.push(condition.delay + self.rng.gen_range(Duration::ZERO..=condition.jitter));
+            }
+        }
+
+        delays
+
+    }
+
+}
+
+/// Internal function to randomly return true with the given percentage, the RNG is not
+/// used if the percentage is zero, so that disabled conditions don't change the
+/// decisions taken for the other ones.
+#[inline]
+fn gen_percent(rng: &mut StdRng, percent: f64) -> bool {
+    percent > 0.0 && rng.gen_bool((percent / 100.0).min(1.0))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Simulate the given number of packets, alternating directions, and return the
+    /// delays of all copies of each packet.
+    fn schedule(seed: u64, count: usize) -> Vec<SmallVec<[Duration; 2]>> {
+
+        let mut simulator = NetworkSimulator::new(seed);
+        let condition = NetworkCondition {
+            delay: Duration::from_millis(50),
+            jitter: Duration::from_millis(20),
+            loss: 10.0,
+            duplicate: 10.0,
+            reorder: 10.0,
+        };
+        simulator.set_condition(PacketDirection::Out, condition.clone());
+        simulator.set_condition(PacketDirection::In, condition);
+
+        (0..count)
+            .map(|i| simulator.simulate(if i % 2 == 0 { PacketDirection::Out } else { PacketDirection::In }))
+            .collect()
+
+    }
+
+    #[test]
+    fn fixed_seed() {
+
+        let first = schedule(42, 1000);
+        assert_eq!(first, schedule(42, 1000));
+        assert_ne!(first, schedule(43, 1000));
+
+        // All conditions are actually taken in the schedule.
+        assert!(first.iter().any(|delays| delays.is_empty()));
+        assert!(first.iter().any(|delays| delays.len() == 2));
+        assert!(first.iter().flatten().any(|delay| delay.is_zero()));
+        assert!(first.iter().flatten().all(|delay| delay.is_zero() ||
+            (Duration::from_millis(50)..=Duration::from_millis(70)).contains(delay)));
+
+    }
+
+    #[test]
+    fn perfect() {
+        let mut simulator = NetworkSimulator::new(0);
+        for direction in [PacketDirection::Out, PacketDirection::In] {
+            assert_eq!(simulator.simulate(direction).as_slice(), &[Duration::ZERO]);
+        }
+    }
+
+}
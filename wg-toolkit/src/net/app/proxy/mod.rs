@@ -1,24 +1,35 @@
 //! A special proxy application that can be used to generically forward calls it receives
 //! to another application while providing debugging capabilities to inspect the network
-//! without being blocking by blowfish cipher.
+//! without being blocking by the packet cipher.
 
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+#[cfg(feature = "parallel")]
+pub mod bulk;
+
+pub mod condition;
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::num::NonZero;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::sync::Arc;
-use std::io;
-
-use blowfish::Blowfish;
+use std::{fmt, io};
 
 use tracing::{trace, trace_span};
 
-use crate::net::packet::Packet;
+use crate::net::packet::{Packet, PacketPool};
+use crate::net::seq::Seq;
 use crate::util::thread::ThreadPoll;
-use crate::net::proto::{ChannelIndex, Protocol};
-use crate::net::socket::{PacketSocket, decrypt_packet};
-use crate::net::bundle::Bundle;
+
+use crossbeam_channel::Sender;
+use crate::net::proto::{ChannelIndex, Protocol, ProtocolStats};
+use crate::net::variant::ProtocolVariant;
+use crate::net::socket::{PacketSocket, decrypt_packet, encrypt_packet};
+use crate::net::cipher::PacketCipher;
+use crate::net::bundle::{Bundle, FragmentError};
 use super::io_invalid_data;
 
+use self::condition::NetworkSimulator;
+
 
 /// The unspecified address used to let the socket allocate its own address.
 pub(crate) const UNSPECIFIED_ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
@@ -27,18 +38,26 @@ pub(crate) const UNSPECIFIED_ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new
 /// continue running.
 pub(crate) const RECV_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Duration after which a packet held for the filter, that has not completed any 
+/// bundle, is forwarded unfiltered, such packets are usually retransmissions of 
+/// already forwarded packets, or fragments of a bundle that will never be completed.
+const HOLD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Duration during which retransmissions of packets dropped, delayed or replaced by
+/// the filter are not forwarded, and retransmissions of forwarded packets are 
+/// forwarded immediately instead of being held.
+const SUPPRESS_TIMEOUT: Duration = Duration::from_secs(60);
+
 
 /// The generic proxy application.
 #[derive(Debug)]
 pub struct App {
-    /// Thread poll for socket result.
-    socket_poll: ThreadPoll<SocketPollRet>,
+    /// Thread poll for socket result and injected bundles.
+    socket_poll: ThreadPoll<PollRet>,
     /// The main socket receiving peer packets.
     socket: PacketSocket,
-    /// Channel tracker for out packets.
-    out_protocol: Protocol,
-    /// Channel tracker for in packets.
-    in_protocol: Protocol,
+    /// The decoder for all forwarded packets.
+    decoder: Decoder,
     /// Each peer connected and forwarded. Using an index map because we use the peer's
     /// index as the mio token (-1).
     peers: HashMap<SocketAddr, Arc<Peer>>,
@@ -46,6 +65,22 @@ pub struct App {
     /// handler of that event to bind the missing peer and allow it to be accepted on
     /// next poll. 
     last_rejection: Option<(Packet, SocketAddr)>,
+    /// The optional filter deciding what to do with each bundle before forwarding it.
+    filter: Option<Box<dyn BundleFilter>>,
+    /// The optional simulator of network conditions applied to forwarded packets.
+    simulator: Option<NetworkSimulator>,
+    /// Packets delayed by the filter or the simulator, in the order they have been 
+    /// delayed.
+    delayed: Vec<DelayedPacket>,
+    /// Packets with a sequence number held until the bundle they belong to is complete
+    /// and filtered, only used when a filter is set.
+    held: HashMap<PacketKey, HeldPacket>,
+    /// Sequence numbers of the packets dropped, delayed or replaced by the filter, with
+    /// the time they have been filtered, their retransmissions are not forwarded.
+    suppressed: HashMap<PacketKey, Instant>,
+    /// Sequence numbers of the packets forwarded after being held, with the time they 
+    /// have been forwarded, their retransmissions are forwarded without being held.
+    forwarded: HashMap<PacketKey, Instant>,
 }
 
 /// A registered peer that can forward and receive packets from the real application.
@@ -57,8 +92,61 @@ struct Peer {
     addr: SocketAddr,
     /// Real address of the base server to communicate with.
     real_addr: SocketAddr,
-    /// Encryption key for this peer.
-    blowfish: Option<Arc<Blowfish>>,
+    /// Encryption cipher for this peer, usually a blowfish key.
+    cipher: Option<Arc<dyn PacketCipher>>,
+}
+
+/// A packet that has been delayed by the filter or the simulator, kept encrypted until
+/// its deadline.
+#[derive(Debug)]
+struct DelayedPacket {
+    /// The time when this packet should be forwarded.
+    time: Instant,
+    /// The peer to forward this packet for.
+    peer: Arc<Peer>,
+    /// The direction to forward this packet in.
+    direction: PacketDirection,
+    /// The encrypted packet, ready to be sent.
+    packet: Packet,
+}
+
+/// Identify a packet with a sequence number, sequence numbers are allocated for each
+/// channel of a peer, in each direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PacketKey {
+    addr: SocketAddr,
+    direction: PacketDirection,
+    /// None for off-channel packets, otherwise the index of the channel, if indexed.
+    channel: Option<Option<NonZero<u32>>>,
+    seq: Seq,
+}
+
+/// A packet held until the bundle it belongs to is complete and filtered.
+#[derive(Debug)]
+struct HeldPacket {
+    /// The time this packet has been received.
+    time: Instant,
+    /// The peer to forward this packet for.
+    peer: Arc<Peer>,
+    /// The encrypted packet, ready to be sent.
+    packet: Packet,
+}
+
+/// Type of values polled by the application.
+#[derive(Debug)]
+enum PollRet {
+    /// The result of the main socket or a peer socket.
+    Socket(SocketPollRet),
+    /// A bundle to inject, sent by an [`Injector`].
+    Inject(Injection),
+}
+
+/// A bundle to inject toward a peer.
+#[derive(Debug)]
+struct Injection {
+    addr: SocketAddr,
+    direction: PacketDirection,
+    bundle: Bundle,
 }
 
 /// Type of return value for our socket poll. 
@@ -75,25 +163,52 @@ impl App {
     /// Create a new proxy application with the given listening address and the address
     /// to proxy to and from.
     pub fn new(addr: SocketAddr) -> io::Result<Self> {
+        Self::with_socket(PacketSocket::bind(addr)?)
+    }
+
+    /// Create many proxy applications all listening on the same address, each being
+    /// called a worker, using the `SO_REUSEPORT` socket option. Each worker should be
+    /// polled from its own thread, the kernel ensures that a given peer is always 
+    /// routed to the same worker, so rejection events should be handled by binding the
+    /// peer on the worker that returned the event. If the address' port is zero, all
+    /// workers are bound to the port allocated for the first one.
+    pub fn new_workers(mut addr: SocketAddr, count: usize) -> io::Result<Vec<Self>> {
+        let mut workers = Vec::with_capacity(count);
+        for _ in 0..count {
+            let socket = PacketSocket::bind_reuse_port(addr)?;
+            addr = socket.addr()?;
+            workers.push(Self::with_socket(socket)?);
+        }
+        Ok(workers)
+    }
+
+    /// Create a new proxy application listening on the given socket, this can be used
+    /// to run the proxy on another transport than UDP. Note that peer sockets are still
+    /// bound with UDP, unless given to [`Self::bind_peer`].
+    pub fn with_socket(socket: PacketSocket) -> io::Result<Self> {
         
         let socket_poll = ThreadPoll::new();
 
-        let socket = PacketSocket::bind(addr)?;
         socket.set_recv_timeout(Some(RECV_TIMEOUT))?;
 
         let thread_socket = socket.clone();
-        socket_poll.spawn(move || Some(SocketPollRet {
+        socket_poll.spawn(move || Some(PollRet::Socket(SocketPollRet {
             peer: None,
             res: thread_socket.recv_without_encryption(),
-        }));
+        })));
 
         Ok(Self {
             socket_poll,
             socket,
-            out_protocol: Protocol::new(),
-            in_protocol: Protocol::new(),
+            decoder: Decoder::new(),
             peers: HashMap::new(),
             last_rejection: None,
+            filter: None,
+            simulator: None,
+            delayed: Vec::new(),
+            held: HashMap::new(),
+            suppressed: HashMap::new(),
+            forwarded: HashMap::new(),
         })
 
     }
@@ -103,16 +218,65 @@ impl App {
         self.socket.addr()
     }
 
+    /// Set the protocol variant used to decode packets and bundles in both directions,
+    /// injected bundles should be created with the same variant.
+    pub fn set_protocol_variant(&mut self, variant: ProtocolVariant) {
+        self.decoder.set_variant(variant);
+    }
+
+    /// Set the filter that decides what to do with each bundle before it's forwarded,
+    /// replacing any previous one. When a filter is set, a packet is no longer forwarded
+    /// as soon as it's received, but only after being decoded: all packets of a bundle
+    /// are held until the bundle is complete, so that the filter's action applies to
+    /// the whole bundle.
+    /// 
+    /// Retransmissions of the packets of a bundle that has been dropped, delayed or 
+    /// replaced are not forwarded, so that the receiver doesn't get the original bundle
+    /// anyway, note however that retransmissions piggybacked in other packets can't be
+    /// suppressed.
+    pub fn set_filter(&mut self, filter: Box<dyn BundleFilter>) {
+        self.filter = Some(filter);
+    }
+
+    /// Remove the current filter, if any, and return it. Packets that have already been
+    /// delayed are still forwarded when due.
+    pub fn remove_filter(&mut self) -> Option<Box<dyn BundleFilter>> {
+        self.filter.take()
+    }
+
+    /// Set or remove the simulator of network conditions, applied to all forwarded
+    /// packets, after the filter if any. Injected bundles are not altered.
+    pub fn set_network_simulator(&mut self, simulator: Option<NetworkSimulator>) {
+        self.simulator = simulator;
+    }
+
+    /// Return a mutable reference to the simulator of network conditions, if any, to 
+    /// change its conditions while the proxy is running.
+    #[inline]
+    pub fn network_simulator_mut(&mut self) -> Option<&mut NetworkSimulator> {
+        self.simulator.as_mut()
+    }
+
+    /// Return memory usage statistics of this application.
+    pub fn stats(&self) -> AppStats {
+        AppStats {
+            peers: self.peers.len(),
+            decoder: self.decoder.stats(),
+        }
+    }
+
     pub fn bind_peer(&mut self, 
         addr: SocketAddr, 
         real_addr: SocketAddr, 
-        blowfish: Option<Arc<Blowfish>>,
+        cipher: Option<Arc<dyn PacketCipher>>,
         socket: Option<PacketSocket>, 
     ) -> io::Result<()> {
 
+        // Peer sockets share the pool of the main socket, so that all received packets
+        // can be recycled with a single pool.
         let socket = match socket {
             Some(socket) => socket,
-            None => PacketSocket::bind(UNSPECIFIED_ADDR)?
+            None => PacketSocket::with_pool(UdpSocket::bind(UNSPECIFIED_ADDR)?, self.socket.pool().clone())
         };
 
         socket.set_recv_timeout(Some(RECV_TIMEOUT))?;
@@ -121,14 +285,14 @@ impl App {
             socket,
             addr,
             real_addr,
-            blowfish,
+            cipher,
         });
 
         let thread_peer = Arc::clone(&peer);
-        self.socket_poll.spawn(move || Some(SocketPollRet {
+        self.socket_poll.spawn(move || Some(PollRet::Socket(SocketPollRet {
             peer: Some(Arc::clone(&thread_peer)),
             res: thread_peer.socket.recv_without_encryption(),
-        }));
+        })));
 
         self.peers.insert(addr, peer);
 
@@ -136,10 +300,281 @@ impl App {
         
     }
 
+    /// Get the pool where all received packets are taken from, the packets of bundle
+    /// events should be given back to it once handled, possibly from another thread, in
+    /// order to avoid allocating new packets for every received datagram.
+    #[inline]
+    pub fn packet_pool(&self) -> &PacketPool {
+        self.socket.pool()
+    }
+
+    /// Return a handle that can be used to inject bundles toward peers of this app, from
+    /// any thread.
+    pub fn injector(&self) -> Injector {
+        Injector {
+            sender: self.socket_poll.sender(),
+        }
+    }
+
+    /// Prepare and send an injected bundle toward its peer. The bundle is sent on the
+    /// peer's channel, with its acks, because the receiver may ignore off-channel
+    /// packets once the channel is established. It's sent unreliable because reliable
+    /// sequence numbers of the channel are allocated by the real sender, taking one
+    /// would make the receiver drop the real packet with the same number.
+    fn inject(&mut self, injection: Injection) -> io::Result<()> {
+
+        let Injection { addr, direction, mut bundle } = injection;
+
+        let peer = self.peers.get(&addr)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot inject toward an unknown peer"))?;
+
+        // The protocol accepting packets of the opposite direction is the one tracking
+        // the packets sent in this direction, and the acks of the sender's channel.
+        let protocol = match direction {
+            PacketDirection::Out => &mut self.decoder.in_protocol,
+            PacketDirection::In => &mut self.decoder.out_protocol,
+        };
+
+        protocol.channel(addr, None).prepare(&mut bundle, false);
+
+        for packet in bundle.iter() {
+
+            let packet = match peer.cipher.as_deref() {
+                Some(cipher) => encrypt_packet(packet.clone(), cipher),
+                None => packet.clone(),
+            };
+
+            match direction {
+                PacketDirection::Out => peer.socket.send_without_encryption(&packet, peer.real_addr)?,
+                PacketDirection::In => self.socket.send_without_encryption(&packet, peer.addr)?,
+            };
+
+        }
+
+        Ok(())
+
+    }
+
+    /// Forward a packet, as-is, for the given peer in the given direction.
+    fn forward(&self, peer: &Peer, direction: PacketDirection, packet: &Packet) -> io::Result<()> {
+        match direction {
+            PacketDirection::Out => peer.socket.send_without_encryption(packet, peer.real_addr)?,
+            PacketDirection::In => self.socket.send_without_encryption(packet, peer.addr)?,
+        };
+        Ok(())
+    }
+
+    /// Forward a packet for the given peer in the given direction after the given delay,
+    /// the network simulator, if any, may add more delay, drop or duplicate it.
+    fn schedule(&mut self, peer: &Arc<Peer>, direction: PacketDirection, packet: &Packet, delay: Duration) -> io::Result<()> {
+
+        let Some(simulator) = self.simulator.as_mut() else {
+            if delay.is_zero() {
+                return self.forward(peer, direction, packet);
+            } else {
+                self.delayed.push(DelayedPacket {
+                    time: Instant::now() + delay,
+                    peer: Arc::clone(peer),
+                    direction,
+                    packet: packet.clone(),
+                });
+                return Ok(());
+            }
+        };
+
+        for extra_delay in simulator.simulate(direction) {
+            let delay = delay + extra_delay;
+            if delay.is_zero() {
+                self.forward(peer, direction, packet)?;
+            } else {
+                self.delayed.push(DelayedPacket {
+                    time: Instant::now() + delay,
+                    peer: Arc::clone(peer),
+                    direction,
+                    packet: packet.clone(),
+                });
+            }
+        }
+
+        Ok(())
+
+    }
+
+    /// Hold the given encrypted packet until the bundle it belongs to is complete, the
+    /// decrypted packet is used to find its sequence number. Only packets with a 
+    /// sequence number are held because they are the only ones that can be fragments 
+    /// or reordered, other packets are returned. Retransmissions of held or suppressed
+    /// packets are ignored, retransmissions of forwarded packets are returned.
+    fn hold(&mut self, peer: &Arc<Peer>, direction: PacketDirection, cipher_packet: Packet, packet: &Packet) -> Option<Packet> {
+
+        let Some(key) = self.packet_key(peer.addr, direction, packet) else {
+            return Some(cipher_packet);
+        };
+
+        if self.forwarded.contains_key(&key) {
+            return Some(cipher_packet);
+        }
+
+        if !self.suppressed.contains_key(&key) {
+            self.held.entry(key).or_insert_with(|| HeldPacket {
+                time: Instant::now(),
+                peer: Arc::clone(peer),
+                packet: cipher_packet,
+            });
+        }
+
+        None
+
+    }
+
+    /// Return the key of the given decrypted packet, only if it's reliable or a 
+    /// fragment, because it has a sequence number.
+    fn packet_key(&self, addr: SocketAddr, direction: PacketDirection, packet: &Packet) -> Option<PacketKey> {
+        let flag_layout = self.decoder.out_protocol.variant().flag_layout();
+        let locked = packet.read_config_locked_ref_with_layout(flag_layout).ok()?;
+        let config = locked.config();
+        (config.reliable() || config.sequence_range().is_some()).then(|| PacketKey {
+            addr,
+            direction,
+            channel: config.on_channel().then(|| config.indexed_channel().map(|(index, _)| index)),
+            seq: config.sequence_num(),
+        })
+    }
+
+    /// Apply the filter to the given bundle event, all held packets of the bundle are
+    /// forwarded, delayed or dropped depending on the filter's action. The given packet
+    /// without sequence number, if any, is the one that completed the bundle.
+    fn filter_bundle(&mut self, peer: &Arc<Peer>, event: &BundleEvent, mut packet: Option<Packet>) -> io::Result<()> {
+
+        // Find all encrypted packets of the bundle from their sequence number.
+        let mut keys = Vec::new();
+        let mut packets = Vec::new();
+        for bundle_packet in event.bundle.iter() {
+            match self.packet_key(peer.addr, event.direction, bundle_packet) {
+                Some(key) => {
+                    keys.push(key);
+                    if let Some(held) = self.held.remove(&key) {
+                        packets.push(held.packet);
+                    }
+                }
+                None => packets.extend(packet.take()),
+            }
+        }
+
+        let action = match self.filter.as_mut() {
+            Some(filter) => filter.filter(event),
+            None => FilterAction::Forward,
+        };
+
+        let now = Instant::now();
+        let filtered = match action {
+            FilterAction::Forward => &mut self.forwarded,
+            _ => &mut self.suppressed,
+        };
+
+        for key in keys {
+            filtered.insert(key, now);
+        }
+
+        match action {
+            FilterAction::Forward => {
+                for bundle_packet in &packets {
+                    self.schedule(peer, event.direction, bundle_packet, Duration::ZERO)?;
+                }
+            }
+            FilterAction::Drop => {}
+            FilterAction::Delay(delay) => {
+                for bundle_packet in &packets {
+                    self.schedule(peer, event.direction, bundle_packet, delay)?;
+                }
+            }
+            FilterAction::Replace(bundle) => {
+                self.inject(Injection {
+                    addr: peer.addr,
+                    direction: event.direction,
+                    bundle,
+                })?;
+            }
+        }
+
+        // The packet may not be part of the bundle if the bundle has been completed by
+        // one of its piggybacks.
+        if let Some(packet) = packet {
+            self.schedule(peer, event.direction, &packet, Duration::ZERO)?;
+        }
+
+        Ok(())
+
+    }
+
+    /// Forward all delayed packets that are due, and return the duration until the 
+    /// next delayed packet is due, if any. Held packets that have not completed any 
+    /// bundle in time are forwarded unfiltered, and expired suppressed sequence numbers
+    /// are forgotten.
+    fn forward_delayed(&mut self) -> Result<Option<Duration>, IoErrorEvent> {
+
+        let now = Instant::now();
+        let mut next_timeout = None::<Duration>;
+        let mut index = 0;
+
+        while index < self.delayed.len() {
+            let time = self.delayed[index].time;
+            if time <= now {
+                let delayed = self.delayed.remove(index);
+                self.forward(&delayed.peer, delayed.direction, &delayed.packet)
+                    .map_err(|error| IoErrorEvent {
+                        error,
+                        addr: Some(delayed.peer.addr),
+                    })?;
+            } else {
+                let timeout = time - now;
+                next_timeout = Some(next_timeout.map_or(timeout, |next| next.min(timeout)));
+                index += 1;
+            }
+        }
+
+        let expired = self.held.iter()
+            .filter(|(_, held)| now.duration_since(held.time) >= HOLD_TIMEOUT)
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+
+        for key in expired {
+            let held = self.held.remove(&key).unwrap();
+            self.forwarded.insert(key, now);
+            self.forward(&held.peer, key.direction, &held.packet)
+                .map_err(|error| IoErrorEvent {
+                    error,
+                    addr: Some(held.peer.addr),
+                })?;
+        }
+
+        if let Some(time) = self.held.values().map(|held| held.time).min() {
+            let timeout = (time + HOLD_TIMEOUT).saturating_duration_since(now);
+            next_timeout = Some(next_timeout.map_or(timeout, |next| next.min(timeout)));
+        }
+
+        self.suppressed.retain(|_, time| now.duration_since(*time) < SUPPRESS_TIMEOUT);
+        self.forwarded.retain(|_, time| now.duration_since(*time) < SUPPRESS_TIMEOUT);
+
+        Ok(next_timeout)
+
+    }
+
     /// Poll for the next event of this login app, blocking.
     pub fn poll(&mut self) -> Event {
         loop {
 
+            let timeout = match self.forward_delayed() {
+                Ok(timeout) => timeout,
+                Err(event) => return Event::IoError(event),
+            };
+
+            // Report bundles that have not been completed in time.
+            if let Some((addr, error)) = self.decoder.poll_fragment_timeout(Instant::now()) {
+                let error = io::Error::new(io::ErrorKind::InvalidData, error);
+                return Event::IoError(IoErrorEvent { error, addr: Some(addr) });
+            }
+
             let ignore_rejection;
             let socket_poll_ret;
             if let Some((packet, addr)) = self.last_rejection.take() {
@@ -150,12 +585,32 @@ impl App {
                 };
             } else {
                 ignore_rejection = false;
-                socket_poll_ret = self.socket_poll.poll();
+                // Wake up in time to forward the next delayed packet.
+                let poll_ret = match timeout {
+                    Some(timeout) => match self.socket_poll.poll_timeout(timeout) {
+                        Some(poll_ret) => poll_ret,
+                        None => continue,
+                    },
+                    None => self.socket_poll.poll(),
+                };
+                socket_poll_ret = match poll_ret {
+                    PollRet::Socket(ret) => ret,
+                    PollRet::Inject(injection) => {
+                        let addr = injection.addr;
+                        if let Err(error) = self.inject(injection) {
+                            return Event::IoError(IoErrorEvent {
+                                error,
+                                addr: Some(addr),
+                            });
+                        }
+                        continue;
+                    }
+                };
             }
 
             let (cipher_packet, addr) = match socket_poll_ret.res {
                 Ok(ret) => ret,
-                Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) => continue,
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
                 Err(e) => {
                     return Event::IoError(IoErrorEvent {
                         error: e,
@@ -166,15 +621,12 @@ impl App {
 
             let peer;
             let direction;
-            let res;
-            if let Some(peer_) = &socket_poll_ret.peer {
-                peer = &**peer_;
+            if let Some(peer_) = socket_poll_ret.peer {
+                peer = peer_;
                 direction = PacketDirection::In;
-                res = self.socket.send_without_encryption(&cipher_packet, peer.addr);
             } else if let Some(peer_) = self.peers.get(&addr) {
-                peer = &**peer_;
+                peer = Arc::clone(peer_);
                 direction = PacketDirection::Out;
-                res = peer.socket.send_without_encryption(&cipher_packet, peer.real_addr);
             } else {
                 if ignore_rejection {
                     continue;
@@ -186,71 +638,228 @@ impl App {
                 }
             }
 
-            if let Err(e) = res {
+            // Without filter, the packet is forwarded immediately, otherwise it's held 
+            // until the bundle it belongs to is complete, it's decrypted only once to
+            // find its sequence number and to decode it.
+            let filtered = self.filter.is_some();
+            let res;
+            let unheld_packet;
+            if filtered {
+                let packet = match peer.cipher.as_deref() {
+                    Some(cipher) => decrypt_packet(cipher_packet.clone(), cipher)
+                        .map_err(|_| io_invalid_data(format_args!("invalid packet encryption"))),
+                    None => Ok(cipher_packet.clone()),
+                };
+                unheld_packet = match &packet {
+                    Ok(packet) => self.hold(&peer, direction, cipher_packet, packet),
+                    Err(_) => Some(cipher_packet),
+                };
+                res = packet.and_then(|packet| self.decoder.decode(packet, peer.addr, direction, None));
+            } else {
+                if let Err(e) = self.schedule(&peer, direction, &cipher_packet, Duration::ZERO) {
+                    return Event::IoError(IoErrorEvent {
+                        error: e,
+                        addr: Some(peer.addr),
+                    });
+                }
+                unheld_packet = None;
+                res = self.decoder.decode(cipher_packet, peer.addr, direction, peer.cipher.as_deref());
+            }
+
+            // Packets that are not held and that don't complete a bundle are forwarded
+            // unfiltered, this is the case of packets only carrying acks.
+            let forward_res = match (&res, unheld_packet) {
+                (Ok(Some(event)), packet) if filtered => self.filter_bundle(&peer, event, packet),
+                (_, Some(packet)) => self.schedule(&peer, direction, &packet, Duration::ZERO),
+                (_, None) => Ok(()),
+            };
+
+            if let Err(e) = forward_res {
                 return Event::IoError(IoErrorEvent {
                     error: e,
                     addr: Some(peer.addr),
                 });
             }
 
-            let packet;
-            if let Some(blowfish) = peer.blowfish.as_deref() {
-                packet = match decrypt_packet(cipher_packet, blowfish) {
-                    Ok(ret) => ret,
-                    Err(_cipher_packet) => {
-                        // warn!("invalid encryption, continuing without it...");
-                        // cipher_packet
-                        // warn!(direction = ?direction, "Cipher packet: {:?}", cipher_packet.raw());
-                        return Event::IoError(IoErrorEvent {
-                            error: io_invalid_data(format_args!("invalid packet encryption")),
-                            addr: Some(addr),
-                        });
-                    }
-                };
-            } else {
-                packet = cipher_packet;
+            match res {
+                Ok(Some(event)) => return Event::Bundle(event),
+                Ok(None) => continue,
+                Err(error) => {
+                    return Event::IoError(IoErrorEvent {
+                        error,
+                        addr: Some(addr),
+                    });
+                }
             }
 
-            let (
-                accept_protocol, 
-                accept_protocol_span,
-                accept_out_protocol,
-                accept_out_protocol_span,
-            ) = match direction {
-                PacketDirection::Out => (&mut self.out_protocol, trace_span!("out"), &mut self.in_protocol, trace_span!("in")),
-                PacketDirection::In => (&mut self.in_protocol, trace_span!("in"), &mut self.out_protocol, trace_span!("out")),
-            };
+        }
 
-            let span = accept_protocol_span.enter();
-            trace!(real_addr = %peer.real_addr, "{:width$?}", packet, width = 0);
-            drop(span);
-            
-            let span = accept_out_protocol_span.enter();
-            if !accept_out_protocol.accept_out(&packet, peer.addr) {
-                continue;
-            }
-            drop(span);
-            
-            let _span = accept_protocol_span.enter();
-            let Some(mut channel) = accept_protocol.accept(packet, peer.addr) else {
-                continue;
-            };
+    }
+
+}
+
+/// The decoding logic of the proxy application, tracking the protocol in both 
+/// directions, this can be used without any socket to decode packets that have been
+/// captured elsewhere, for example from a pcap file.
+#[derive(Debug)]
+pub struct Decoder {
+    /// Channel tracker for out packets.
+    out_protocol: Protocol,
+    /// Channel tracker for in packets.
+    in_protocol: Protocol,
+}
+
+impl Decoder {
+
+    pub fn new() -> Self {
+        Self {
+            out_protocol: Protocol::new(),
+            in_protocol: Protocol::new(),
+        }
+    }
+
+    /// Set the protocol variant used to decode packets and bundles in both directions.
+    pub fn set_variant(&mut self, variant: ProtocolVariant) {
+        self.out_protocol.set_variant(variant.clone());
+        self.in_protocol.set_variant(variant);
+    }
+
+    /// Return memory usage statistics of the protocol trackers in both directions.
+    pub fn stats(&self) -> DecoderStats {
+        DecoderStats {
+            out_protocol: self.out_protocol.stats(),
+            in_protocol: self.in_protocol.stats(),
+        }
+    }
 
-            let Some(bundle) = channel.next_bundle() else {
-                continue;
+    /// Return memory usage statistics of the state kept for the given client address.
+    pub fn peer_stats(&self, addr: SocketAddr) -> DecoderStats {
+        DecoderStats {
+            out_protocol: self.out_protocol.peer_stats(addr),
+            in_protocol: self.in_protocol.peer_stats(addr),
+        }
+    }
+
+    /// Poll the next bundle that has not been completed in time in any direction, the
+    /// incomplete bundle is dropped, see [`Protocol::poll_fragment_timeout`]. The 
+    /// returned address is the client's address.
+    pub fn poll_fragment_timeout(&mut self, now: Instant) -> Option<(SocketAddr, FragmentError)> {
+        self.out_protocol.poll_fragment_timeout(now)
+            .or_else(|| self.in_protocol.poll_fragment_timeout(now))
+    }
+
+    /// Decode a packet that has been sent in the given direction, the address should 
+    /// always be the client's address, regardless of the direction. If given, the 
+    /// cipher is used to decrypt the packet before decoding. A bundle event is returned
+    /// if this packet completes a bundle.
+    pub fn decode(&mut self, 
+        cipher_packet: Packet, 
+        addr: SocketAddr, 
+        direction: PacketDirection, 
+        cipher: Option<&dyn PacketCipher>,
+    ) -> io::Result<Option<BundleEvent>> {
+
+        let packet;
+        if let Some(cipher) = cipher {
+            packet = match decrypt_packet(cipher_packet, cipher) {
+                Ok(ret) => ret,
+                Err(_cipher_packet) => {
+                    return Err(io_invalid_data(format_args!("invalid packet encryption")));
+                }
             };
+        } else {
+            packet = cipher_packet;
+        }
 
-            return Event::Bundle(BundleEvent {
-                addr: peer.addr,
-                bundle,
-                direction,
-                channel: channel.is_on().then(|| PacketChannel {
-                    index: channel.index(),
-                }),
-            })
+        let (
+            accept_protocol, 
+            accept_protocol_span,
+            accept_out_protocol,
+            accept_out_protocol_span,
+        ) = match direction {
+            PacketDirection::Out => (&mut self.out_protocol, trace_span!("out"), &mut self.in_protocol, trace_span!("in")),
+            PacketDirection::In => (&mut self.in_protocol, trace_span!("in"), &mut self.out_protocol, trace_span!("out")),
+        };
 
+        let span = accept_protocol_span.enter();
+        trace!(%addr, "{:width$?}", packet, width = 0);
+        drop(span);
+        
+        let span = accept_out_protocol_span.enter();
+        if !accept_out_protocol.accept_out(&packet, addr) {
+            return Ok(None);
         }
+        drop(span);
+        
+        let _span = accept_protocol_span.enter();
+        let Some(mut channel) = accept_protocol.accept(packet, addr) else {
+            return Ok(None);
+        };
+
+        let Some(bundle) = channel.next_bundle() else {
+            return Ok(None);
+        };
+
+        Ok(Some(BundleEvent {
+            addr,
+            bundle,
+            direction,
+            channel: channel.is_on().then(|| PacketChannel {
+                index: channel.index(),
+            }),
+        }))
+
+    }
+
+}
 
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A filter of the bundles received by a proxy app, see [`App::set_filter`], it can be
+/// used to inspect, drop, delay or rewrite bundles before they are forwarded, making
+/// the proxy an active tool for protocol experiments.
+pub trait BundleFilter: fmt::Debug + Send {
+
+    /// Decide what to do with the given bundle, that has been received from the client
+    /// if its direction is out, or from the server if its direction is in. The bundle
+    /// is still returned as an event on poll, regardless of the action.
+    fn filter(&mut self, event: &BundleEvent) -> FilterAction;
+
+}
+
+/// The action to take for a filtered bundle, see [`BundleFilter`].
+#[derive(Debug)]
+pub enum FilterAction {
+    /// Forward the bundle unchanged.
+    Forward,
+    /// Drop the bundle, it's never forwarded.
+    Drop,
+    /// Forward the bundle unchanged after the given delay.
+    Delay(Duration),
+    /// Drop the bundle and send the given one instead, it's prepared and sent like an
+    /// injected bundle, see [`Injector::inject`].
+    Replace(Bundle),
+}
+
+/// A handle to inject bundles toward the peers of a proxy app, from any thread, see
+/// [`App::injector`].
+#[derive(Debug, Clone)]
+pub struct Injector {
+    sender: Sender<PollRet>,
+}
+
+impl Injector {
+
+    /// Inject a bundle toward the given peer in the given direction, the bundle is 
+    /// prepared on the peer's channel, unreliable, and sent on the next poll of the app,
+    /// any error is then returned as an IO error event. This returns false if the app
+    /// has been dropped.
+    pub fn inject(&self, addr: SocketAddr, direction: PacketDirection, bundle: Bundle) -> bool {
+        self.sender.send(PollRet::Inject(Injection { addr, direction, bundle })).is_ok()
     }
 
 }
@@ -281,6 +890,24 @@ pub struct IoErrorEvent {
     pub addr: Option<SocketAddr>,
 }
 
+/// Memory usage statistics of a decoder, see [`Decoder::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Statistics of the protocol tracking packets sent by clients.
+    pub out_protocol: ProtocolStats,
+    /// Statistics of the protocol tracking packets received by clients.
+    pub in_protocol: ProtocolStats,
+}
+
+/// Memory usage statistics of a proxy application, see [`App::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppStats {
+    /// Number of bound peers.
+    pub peers: usize,
+    /// Statistics of the decoder.
+    pub decoder: DecoderStats,
+}
+
 #[derive(Debug)]
 pub struct BundleEvent {
     /// Address of the client that sent this bundle.
@@ -293,7 +920,7 @@ pub struct BundleEvent {
     pub channel: Option<PacketChannel>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PacketDirection {
     Out,
     In,
@@ -303,3 +930,240 @@ pub enum PacketDirection {
 pub struct PacketChannel {
     pub index: Option<ChannelIndex>,
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::Ipv4Addr;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::net::element::DebugElementVariable16;
+    use crate::net::bundle::NextElementReader;
+
+    use super::*;
+
+    const LOCALHOST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+
+    /// A filter always returning the action built by its function.
+    #[derive(Debug)]
+    struct TestFilter(fn() -> FilterAction);
+
+    impl BundleFilter for TestFilter {
+        fn filter(&mut self, _event: &BundleEvent) -> FilterAction {
+            (self.0)()
+        }
+    }
+
+    /// A running proxy between a client and a server socket, with a filter.
+    struct TestProxy {
+        client: PacketSocket,
+        server: PacketSocket,
+        app_addr: SocketAddr,
+        protocol: Protocol,
+        bundles: mpsc::Receiver<usize>,
+        injector: Injector,
+    }
+
+    impl TestProxy {
+
+        fn new(action: fn() -> FilterAction) -> Self {
+
+            let client = PacketSocket::bind(LOCALHOST).unwrap();
+            let server = PacketSocket::bind(LOCALHOST).unwrap();
+            server.set_recv_timeout(Some(Duration::from_millis(200))).unwrap();
+
+            let mut app = App::new(LOCALHOST).unwrap();
+            let app_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, app.addr().unwrap().port()));
+            app.bind_peer(client.addr().unwrap(), server.addr().unwrap(), None, None).unwrap();
+            app.set_filter(Box::new(TestFilter(action)));
+            let injector = app.injector();
+
+            // The app is polled in its own thread, bundle events return the number of
+            // packets of the bundle.
+            let (sender, bundles) = mpsc::channel();
+            thread::spawn(move || loop {
+                if let Event::Bundle(event) = app.poll() {
+                    if sender.send(event.bundle.len()).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Self { client, server, app_addr, protocol: Protocol::new(), bundles, injector }
+
+        }
+
+        /// Send a bundle of the given number of packets, returning its packets.
+        fn send(&mut self, packets: usize, reliable: bool) -> Bundle {
+            let mut bundle = Bundle::new();
+            bundle.element_writer().write_simple(DebugElementVariable16::<0x10> { data: vec![0xAB; 1000 * packets] });
+            assert_eq!(bundle.len(), packets);
+            self.protocol.off_channel(self.app_addr).prepare(&mut bundle, reliable);
+            self.client.send_bundle_without_encryption(&bundle, self.app_addr).unwrap();
+            bundle
+        }
+
+        /// Wait for the next bundle event, returning its number of packets.
+        fn wait_bundle(&self) -> usize {
+            self.bundles.recv_timeout(Duration::from_secs(5)).unwrap()
+        }
+
+        /// Receive all packets forwarded to the server, until nothing is received.
+        fn recv_server(&self) -> Vec<Packet> {
+            let mut packets = Vec::new();
+            while let Ok((packet, _)) = self.server.recv_without_encryption() {
+                packets.push(packet);
+            }
+            packets
+        }
+
+    }
+
+    #[test]
+    fn filter_forward() {
+        let mut proxy = TestProxy::new(|| FilterAction::Forward);
+        let bundle = proxy.send(3, false);
+        assert_eq!(proxy.wait_bundle(), 3);
+        let packets = proxy.recv_server();
+        assert_eq!(packets.len(), 3);
+        for (packet, sent) in packets.iter().zip(bundle.iter()) {
+            assert_eq!(packet.slice(), sent.slice());
+        }
+    }
+
+    #[test]
+    fn filter_forward_retransmission() {
+
+        let mut proxy = TestProxy::new(|| FilterAction::Forward);
+        let bundle = proxy.send(3, true);
+        assert_eq!(proxy.wait_bundle(), 3);
+        assert_eq!(proxy.recv_server().len(), 3);
+
+        // Retransmissions of the forwarded packets are forwarded without being held.
+        proxy.client.send_bundle_without_encryption(&bundle, proxy.app_addr).unwrap();
+        assert_eq!(proxy.recv_server().len(), 3);
+
+    }
+
+    #[test]
+    fn filter_drop() {
+
+        let mut proxy = TestProxy::new(|| FilterAction::Drop);
+        let bundle = proxy.send(3, true);
+        assert_eq!(proxy.wait_bundle(), 3);
+        assert!(proxy.recv_server().is_empty());
+
+        // Retransmissions of the dropped packets are not forwarded.
+        proxy.client.send_bundle_without_encryption(&bundle, proxy.app_addr).unwrap();
+        assert!(proxy.recv_server().is_empty());
+
+    }
+
+    #[test]
+    fn filter_delay() {
+
+        let mut proxy = TestProxy::new(|| FilterAction::Delay(Duration::from_millis(500)));
+        let bundle = proxy.send(3, true);
+        assert_eq!(proxy.wait_bundle(), 3);
+
+        // Retransmissions while the packets are delayed are not forwarded.
+        proxy.client.send_bundle_without_encryption(&bundle, proxy.app_addr).unwrap();
+
+        assert!(proxy.recv_server().is_empty());
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(proxy.recv_server().len(), 3);
+
+    }
+
+    #[test]
+    fn filter_replace() {
+
+        let mut proxy = TestProxy::new(|| {
+            let mut bundle = Bundle::new();
+            bundle.element_writer().write_simple(DebugElementVariable16::<0x20> { data: vec![0xCD; 10] });
+            FilterAction::Replace(bundle)
+        });
+
+        proxy.send(3, false);
+        assert_eq!(proxy.wait_bundle(), 3);
+
+        // Only the replacement bundle is received, no fragment of the original one.
+        let packets = proxy.recv_server();
+        assert_eq!(packets.len(), 1);
+        let bundle = Bundle::new_with_single(packets.into_iter().next().unwrap().read_config_locked().unwrap());
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(elt)) = reader.next() else { panic!("expected an element") };
+        assert_eq!(elt.read_simple::<DebugElementVariable16<0x20>>().unwrap().element.data, vec![0xCD; 10]);
+
+    }
+
+    #[test]
+    fn decoder_fragment_timeout() {
+
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1234));
+        let now = Instant::now();
+
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(DebugElementVariable16::<0x10> { data: vec![0xAB; 2000] });
+        assert_eq!(bundle.len(), 2);
+        Protocol::new().off_channel(addr).prepare(&mut bundle, false);
+
+        // Only the first fragment is received.
+        let mut decoder = Decoder::new();
+        let packet = bundle.iter().next().unwrap().clone();
+        assert!(decoder.decode(packet, addr, PacketDirection::Out, None).unwrap().is_none());
+        assert!(decoder.poll_fragment_timeout(now).is_none());
+
+        let (error_addr, error) = decoder.poll_fragment_timeout(now + Duration::from_secs(60)).unwrap();
+        assert_eq!(error_addr, addr);
+        assert!(matches!(error, FragmentError::TimedOut { .. }), "{error:?}");
+        assert!(decoder.poll_fragment_timeout(now + Duration::from_secs(60)).is_none());
+
+    }
+
+    #[test]
+    fn inject_on_channel() {
+
+        let mut proxy = TestProxy::new(|| FilterAction::Forward);
+        let client_addr = proxy.client.addr().unwrap();
+
+        // The server learns the address of the peer socket from a first bundle, then
+        // sends a reliable bundle on channel to the client, through the proxy.
+        proxy.send(1, false);
+        assert_eq!(proxy.wait_bundle(), 1);
+        let (_, peer_addr) = proxy.server.recv_without_encryption().unwrap();
+
+        let mut server_protocol = Protocol::new();
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(DebugElementVariable16::<0x10> { data: vec![0xAB; 10] });
+        server_protocol.channel(peer_addr, None).prepare(&mut bundle, true);
+        proxy.server.send_bundle_without_encryption(&bundle, peer_addr).unwrap();
+        assert_eq!(proxy.wait_bundle(), 1);
+        let sequence_num = bundle.iter().next().unwrap().read_config_locked_ref().unwrap().config().sequence_num();
+
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(DebugElementVariable16::<0x20> { data: vec![0xCD; 10] });
+        assert!(proxy.injector.inject(client_addr, PacketDirection::Out, bundle));
+
+        // The injected bundle is on channel, unreliable, and acks the server's bundle
+        // on behalf of the client.
+        let packets = proxy.recv_server();
+        assert_eq!(packets.len(), 1);
+        let packet = packets.into_iter().next().unwrap();
+        let locked = packet.read_config_locked_ref().unwrap();
+        assert!(locked.config().on_channel());
+        assert!(!locked.config().reliable());
+        assert_eq!(locked.config().cumulative_ack(), Some(sequence_num + 1));
+
+        let mut channel = server_protocol.accept(packet, peer_addr).unwrap();
+        assert!(channel.is_on());
+        let bundle = channel.next_bundle().unwrap();
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(elt)) = reader.next() else { panic!("expected an element") };
+        assert_eq!(elt.read_simple::<DebugElementVariable16<0x20>>().unwrap().element.data, vec![0xCD; 10]);
+
+    }
+
+}
@@ -0,0 +1,134 @@
+//! Tick scheduler used to drive periodic work in applications.
+
+use std::time::{Duration, Instant};
+
+
+/// The minimum timeout returned by [`TickScheduler::timeout`], because sockets cannot
+/// be given a zero timeout.
+const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// A scheduler of periodic ticks at a fixed frequency, when given to an application,
+/// its poll function no longer only reacts to inbound datagrams but also returns a
+/// tick event when the next tick is due. This can be used to drive keep-alives,
+/// retransmission checks or regular outgoing updates.
+///
+/// When drift compensation is enabled (the default), each tick is scheduled relative
+/// to the previous one's deadline instead of the time it was actually polled, so the
+/// average frequency is preserved even if polling is late. If polling is late by more
+/// than one interval, missed ticks are skipped and counted in the event.
+#[derive(Debug, Clone)]
+pub struct TickScheduler {
+    /// Interval between two ticks.
+    interval: Duration,
+    /// Deadline of the next tick.
+    next: Instant,
+    /// The number of the next tick.
+    tick: u64,
+    /// True to schedule ticks relative to the previous deadline.
+    drift_compensation: bool,
+}
+
+impl TickScheduler {
+
+    /// Create a new tick scheduler ticking at the given frequency, in Hz, the first
+    /// tick is due after one interval.
+    ///
+    /// This function panics if the frequency is zero.
+    pub fn new(frequency: u32) -> Self {
+        assert_ne!(frequency, 0, "tick frequency must not be zero");
+        Self::with_interval(Duration::from_secs(1) / frequency)
+    }
+
+    /// Create a new tick scheduler with the given interval between two ticks, the
+    /// first tick is due after one interval.
+    ///
+    /// This function panics if the interval is zero.
+    pub fn with_interval(interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "tick interval must not be zero");
+        Self {
+            interval,
+            next: Instant::now() + interval,
+            tick: 0,
+            drift_compensation: true,
+        }
+    }
+
+    /// Get the interval between two ticks.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Enable or disable drift compensation, enabled by default.
+    pub fn set_drift_compensation(&mut self, enabled: bool) {
+        self.drift_compensation = enabled;
+    }
+
+    /// Return true if drift compensation is enabled.
+    #[inline]
+    pub fn drift_compensation(&self) -> bool {
+        self.drift_compensation
+    }
+
+    /// Get the deadline of the next tick.
+    #[inline]
+    pub fn next_deadline(&self) -> Instant {
+        self.next
+    }
+
+    /// Get the duration to wait from the given time before the next tick is due, this
+    /// is never zero so that it can directly be used as a socket timeout.
+    pub fn timeout(&self, now: Instant) -> Duration {
+        self.next.saturating_duration_since(now).max(MIN_TIMEOUT)
+    }
+
+    /// Poll the scheduler at the given time, returning a tick event if the next tick
+    /// is due, in which case the following tick is scheduled.
+    pub fn poll(&mut self, now: Instant) -> Option<TickEvent> {
+
+        if now < self.next {
+            return None;
+        }
+
+        let late = now - self.next;
+        let mut missed = 0;
+
+        if self.drift_compensation {
+            // Skip all ticks that should have already happened.
+            missed = (late.as_nanos() / self.interval.as_nanos()) as u64;
+            self.next += self.interval * (missed + 1) as u32;
+        } else {
+            self.next = now + self.interval;
+        }
+
+        let event = TickEvent {
+            tick: self.tick,
+            missed,
+            late,
+        };
+
+        self.tick = self.tick.wrapping_add(missed + 1);
+        Some(event)
+
+    }
+
+}
+
+/// A tick is due.
+#[derive(Debug, Clone)]
+pub struct TickEvent {
+    /// The number of this tick, incremented for each tick including missed ones.
+    pub tick: u64,
+    /// Number of ticks skipped before this one, because polling was too late.
+    pub missed: u64,
+    /// How late this tick has been polled after its deadline.
+    pub late: Duration,
+}
+
+/// Return true if the given IO error is returned by a socket receive timeout, the kind
+/// of the error is the same on all platforms, see [`Transport::recv_from`].
+/// 
+/// [`Transport::recv_from`]: crate::net::transport::Transport::recv_from
+pub(super) fn is_timeout(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::TimedOut
+}
@@ -1,13 +1,19 @@
 //! Structures for managing bundles of packets.
 
+use std::collections::{hash_map, HashMap};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 use std::io::{self, Write, Read};
+use std::borrow::Cow;
 use std::fmt;
 
 use tracing::warn;
 
 use super::packet::{self, PacketConfig, PacketLocked, Packet};
-use super::element::{Element, Reply, REPLY_ID};
-use super::codec::Codec;
+use super::variant::ProtocolVariant;
+use super::element::{Element, BorrowedElement, Reply, REPLY_ID};
+use super::codec::{Codec, SimpleCodec};
+use super::seq::Seq;
 
 use crate::util::io::{WgReadExt, WgWriteExt, IoCounter};
 use crate::net::element::ElementLength;
@@ -46,6 +52,9 @@ pub struct Bundle {
     /// (starting with flags, so value 0 or 1 equals "no next request"). That offset is
     /// in content space.
     last_request_link_offset: Option<u16>,
+    /// The protocol variant used to translate element ids when reading and writing 
+    /// elements, none if it translates nothing.
+    variant: Option<ProtocolVariant>,
 }
 
 impl Bundle {
@@ -55,6 +64,7 @@ impl Bundle {
             packets: Vec::new(),
             free: 0,
             last_request_link_offset: None,
+            variant: None,
         }
     }
 
@@ -77,7 +87,20 @@ impl Bundle {
         self.packets.is_empty()
     }
 
-    /// Clear the bundle by removing all packets.
+    /// The protocol variant used to translate element ids when reading and writing 
+    /// elements, none for the PC client's variant.
+    #[inline]
+    pub fn variant(&self) -> Option<&ProtocolVariant> {
+        self.variant.as_ref()
+    }
+
+    /// Set the protocol variant used to translate element ids when reading and writing
+    /// elements, this is kept when clearing the bundle.
+    pub fn set_variant(&mut self, variant: &ProtocolVariant) {
+        self.variant = (!variant.is_identity()).then(|| variant.clone());
+    }
+
+    /// Clear the bundle by removing all packets, the protocol variant is kept.
     pub fn clear(&mut self) {
         self.packets.clear();
         self.free = 0;
@@ -251,6 +274,204 @@ impl FromIterator<PacketLocked> for Bundle {
 }
 
 
+/// An assembler of bundles from their received packets, a bundle of multiple packets 
+/// is sent as a chain of fragments, each fragment having the sequence range of its 
+/// chain. Fragments can be received in any order, they are buffered until their chain
+/// is complete. Chains that are not updated for the timeout duration are expired.
+/// 
+/// This is used by the protocol for off-channel and non-reliable packets, reliable 
+/// on-channel packets are already reordered before being assembled.
+#[derive(Debug)]
+pub struct FragmentAssembler {
+    /// Chains of fragments being assembled, mapped to their first sequence number.
+    chains: HashMap<Seq, FragmentChain>,
+    /// Duration without a new fragment after which a chain is expired.
+    timeout: Duration,
+}
+
+impl FragmentAssembler {
+
+    /// Create a new assembler with the given timeout of incomplete chains.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            chains: HashMap::new(),
+            timeout,
+        }
+    }
+
+    /// Get the timeout of incomplete chains.
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Set the timeout of incomplete chains.
+    #[inline]
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Return the number of incomplete chains being assembled.
+    #[inline]
+    pub fn chains_count(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// Return the number of fragments buffered in all incomplete chains.
+    pub fn fragments_count(&self) -> usize {
+        self.chains.values().map(|chain| chain.count).sum()
+    }
+
+    /// Push a packet received at the given time, a packet that isn't a fragment is 
+    /// directly returned as a bundle of its own, a fragment returns the bundle if it
+    /// completes its chain. If the fragment's chain has expired, it's discarded and a 
+    /// new chain is started with the fragment, use [`Self::drain_expired`] to regularly
+    /// report expired chains instead.
+    pub fn push(&mut self, packet: PacketLocked, time: Instant) -> Result<Option<Bundle>, FragmentError> {
+
+        let Some((first_seq, last_seq)) = packet.config().sequence_range() else {
+            return Ok(Some(Bundle::new_with_single(packet)));
+        };
+
+        let sequence_num = packet.config().sequence_num();
+
+        // A reversed range wraps to a huge length, so it's also rejected.
+        let len = (last_seq - first_seq) as usize + 1;
+        if len > BUNDLE_MAX_PACKET_COUNT {
+            return Err(FragmentError::InvalidRange { first_seq, last_seq });
+        }
+
+        let index = (sequence_num - first_seq) as usize;
+        if index >= len {
+            return Err(FragmentError::OutOfRange { sequence_num, first_seq, last_seq });
+        }
+
+        let chain = match self.chains.entry(first_seq) {
+            hash_map::Entry::Occupied(o) => {
+                let chain = o.into_mut();
+                if time.saturating_duration_since(chain.last_update) > self.timeout {
+                    *chain = FragmentChain::new(last_seq, len, time);
+                } else if chain.last_seq != last_seq {
+                    return Err(FragmentError::InconsistentRange { first_seq, last_seq, chain_last_seq: chain.last_seq });
+                }
+                chain
+            }
+            hash_map::Entry::Vacant(v) => v.insert(FragmentChain::new(last_seq, len, time)),
+        };
+
+        let fragment = &mut chain.fragments[index];
+        if fragment.is_some() {
+            return Err(FragmentError::Duplicate { sequence_num, first_seq, last_seq });
+        }
+
+        *fragment = Some(packet);
+        chain.count += 1;
+        chain.last_update = time;
+
+        if chain.count < chain.fragments.len() {
+            return Ok(None);
+        }
+
+        let chain = self.chains.remove(&first_seq).unwrap();
+        Ok(Some(chain.fragments.into_iter().map(Option::unwrap).collect()))
+
+    }
+
+    /// Remove all chains that have not been updated for the timeout duration at the 
+    /// given time, returning an error for each of them with its missing fragments.
+    pub fn drain_expired(&mut self, time: Instant) -> Vec<FragmentError> {
+
+        let expired = self.chains.iter()
+            .filter(|(_, chain)| time.saturating_duration_since(chain.last_update) > self.timeout)
+            .map(|(&first_seq, _)| first_seq)
+            .collect::<Vec<_>>();
+
+        expired.into_iter()
+            .map(|first_seq| {
+                let chain = self.chains.remove(&first_seq).unwrap();
+                FragmentError::TimedOut {
+                    first_seq,
+                    last_seq: chain.last_seq,
+                    missing: chain.fragments.iter()
+                        .enumerate()
+                        .filter(|(_, fragment)| fragment.is_none())
+                        .map(|(index, _)| first_seq + index as u32)
+                        .collect(),
+                }
+            })
+            .collect()
+
+    }
+
+}
+
+/// Internal structure of a chain of fragments being assembled.
+#[derive(Debug)]
+struct FragmentChain {
+    /// The last sequence number of the chain.
+    last_seq: Seq,
+    /// All fragments of the chain, indexed from the first sequence number.
+    fragments: Vec<Option<PacketLocked>>,
+    /// Number of fragments received.
+    count: usize,
+    /// Last time a fragment has been received.
+    last_update: Instant,
+}
+
+impl FragmentChain {
+
+    fn new(last_seq: Seq, len: usize, time: Instant) -> Self {
+        Self {
+            last_seq,
+            fragments: (0..len).map(|_| None).collect(),
+            count: 0,
+            last_update: time,
+        }
+    }
+
+}
+
+/// Error while assembling fragments of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FragmentError {
+    /// The sequence range of the fragment is reversed or too long.
+    #[error("invalid fragment range {first_seq}..={last_seq}")]
+    InvalidRange {
+        first_seq: Seq,
+        last_seq: Seq,
+    },
+    /// The sequence number of the fragment is not in its range.
+    #[error("fragment {sequence_num} out of its range {first_seq}..={last_seq}")]
+    OutOfRange {
+        sequence_num: Seq,
+        first_seq: Seq,
+        last_seq: Seq,
+    },
+    /// The sequence range of the fragment is not the one of its chain, with the same
+    /// first sequence number.
+    #[error("fragment range {first_seq}..={last_seq} inconsistent with its chain ending at {chain_last_seq}")]
+    InconsistentRange {
+        first_seq: Seq,
+        last_seq: Seq,
+        chain_last_seq: Seq,
+    },
+    /// The fragment has already been received for its chain.
+    #[error("duplicate fragment {sequence_num} in {first_seq}..={last_seq}")]
+    Duplicate {
+        sequence_num: Seq,
+        first_seq: Seq,
+        last_seq: Seq,
+    },
+    /// The chain has not been completed in time, the missing sequence numbers are given.
+    #[error("fragments {first_seq}..={last_seq} timed out, missing {} fragments", missing.len())]
+    TimedOut {
+        first_seq: Seq,
+        last_seq: Seq,
+        missing: Vec<Seq>,
+    },
+}
+
+
 /// Internal storage structure for a bundle's packet.
 #[derive(Debug)]
 struct BundlePacket {
@@ -400,6 +621,15 @@ impl<'a> BundleReader<'a> {
         self.bundle.packets.get(self.packet_index as usize)
     }
 
+    /// Get the position of the reader in the whole bundle content, this is the total
+    /// length of content that has been read.
+    pub fn pos(&self) -> usize {
+        self.bundle.packets.iter()
+            .take(self.packet_index as usize)
+            .map(|p| p.slice().len())
+            .sum::<usize>() + self.content_offset as usize
+    }
+
     /// Advance the current reader by a given amount. Return true if successful, if not
     /// the reader has been emptied by remaining delta could not be advanced.
     pub fn advance(&mut self, mut delta: usize) -> io::Result<()> {
@@ -460,6 +690,33 @@ impl<E> BundleElement<E> {
         }
     }
 
+    /// Return the handle to reply to this element, if it's a request.
+    #[inline]
+    pub fn request_handle(&self) -> Option<RequestHandle> {
+        self.request_id.map(RequestHandle)
+    }
+
+}
+
+/// A handle to a request element, returned when adding a request with an automatic
+/// id to a bundle, or from a received request, it's used to add the reply to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestHandle(u32);
+
+impl RequestHandle {
+
+    /// Create a handle for a request id that is already known.
+    #[inline]
+    pub fn new(request_id: u32) -> Self {
+        Self(request_id)
+    }
+
+    /// Get the request id of this request.
+    #[inline]
+    pub fn request_id(self) -> u32 {
+        self.0
+    }
+
 }
 
 
@@ -518,6 +775,34 @@ impl<'a> BundleElementWriter<'a> {
         self.write_reply(data, request_id, &())
     }
 
+    /// Add a request element to this bundle, with the next request id allocated by the
+    /// given tracker, the request is linked to the previous requests of the bundle. The
+    /// returned handle can be used to track the reply to this request.
+    pub fn add_request_with<E: Element<C>, C, R>(&mut self, tracker: &mut RequestTracker<R>, element: E, config: &C) -> RequestHandle {
+        let request_id = tracker.next_request_id();
+        self.write_request(element, request_id, config);
+        RequestHandle(request_id)
+    }
+
+    /// Add a request element to this bundle, with the next request id allocated by the
+    /// given tracker. Such elements have no config.
+    #[inline]
+    pub fn add_request<E: Element<()>, R>(&mut self, tracker: &mut RequestTracker<R>, element: E) -> RequestHandle {
+        self.add_request_with(tracker, element, &())
+    }
+
+    /// Add the reply to the request of the given handle.
+    #[inline]
+    pub fn add_reply_with<D: Codec<C>, C>(&mut self, handle: RequestHandle, data: D, config: &C) {
+        self.write_reply(data, handle.0, config)
+    }
+
+    /// Add the reply to the request of the given handle. Such elements have no config.
+    #[inline]
+    pub fn add_reply<D: Codec<()>>(&mut self, handle: RequestHandle, data: D) {
+        self.add_reply_with(handle, data, &())
+    }
+
     /// Raw method to add an element to this bundle, given an ID, the 
     /// element and its config. With an optional request ID.
     pub fn write_raw<E: Element<C>, C>(&mut self, element: BundleElement<E>, config: &C) {
@@ -563,6 +848,10 @@ impl<'a> BundleElementWriter<'a> {
         let mut writer = IoCounter::new(BundleWriter::new(&mut *self.bundle));
         let elt_id = element.element.write(&mut writer, config).unwrap();
         let elt_len = u32::try_from(writer.count()).expect("too many bytes written at once, more that u32::MAX");
+        let elt_id = match &self.bundle.variant {
+            Some(variant) => variant.element_id_to_wire(elt_id),
+            None => elt_id,
+        };
 
         // Finally write id and length, we can unwrap because we know that enough length is available.
         let header_len_slice = &mut self.bundle.packets[init_packet_index].slice_mut()[init_packet_elt_offset..];
@@ -609,6 +898,7 @@ impl<'a> BundleElementWriter<'a> {
 /// This structure can be obtained from [`Bundle::element_reader`].
 pub struct BundleElementReader<'a> {
     bundle_reader: BundleReader<'a>,
+    variant: Option<&'a ProtocolVariant>,
     last_packet_index: u16,
     next_request_offset: Option<u16>,
 }
@@ -624,16 +914,24 @@ impl<'a> BundleElementReader<'a> {
             next_request_offset: bundle_reader.packet().and_then(|p| p.first_request_offset),
             last_packet_index: 0,
             bundle_reader,
+            variant: bundle.variant.as_ref(),
         }
         
     }
 
+    /// Get the position of the reader in the bundle content, in bytes, the difference
+    /// of positions before and after reading an element gives its total length.
+    pub fn pos(&self) -> usize {
+        self.bundle_reader.pos()
+    }
+
     /// Read the current element's identifier. This call return the same result until
     /// you explicitly choose to go to the next element while reading the element. This
     /// method takes self by mutable reference because it may need to go to the next
     /// packet when needed.
     pub fn next_id(&mut self) -> Option<u8> {
-        self.bundle_reader.ensure().map(|content| content[0])
+        let variant = self.variant;
+        self.bundle_reader.ensure().map(|content| element_id_from_wire(variant, content[0]))
     }
 
     /// Read the current element, return a guard that you should use with a 
@@ -659,6 +957,67 @@ impl<'a> BundleElementReader<'a> {
     /// Try to decode the current element using a given codec. You can choose to go
     /// to the next element using the `next` argument.
     pub fn read<E: Element<C>, C>(&mut self, config: &C, next: bool) -> io::Result<BundleElement<E>> {
+        self.read_inner(
+            |elt_id| E::read_length(config, elt_id), 
+            |elt_reader, elt_len, elt_id| E::read(elt_reader, config, elt_len, elt_id), 
+            next)
+    }
+
+    /// Try to decode the current element from its whole data, borrowed from the bundle
+    /// if the element is contiguous in a single packet, copied otherwise. An element of
+    /// undefined length gets all the remaining data of the bundle. You can choose to go
+    /// to the next element using the `next` argument.
+    pub fn read_borrowed<E: BorrowedElement<'a, C>, C>(&mut self, config: &C, next: bool) -> io::Result<BundleElement<E>> {
+        self.read_inner(
+            |elt_id| E::read_length(config, elt_id),
+            |elt_reader, elt_len, elt_id| {
+
+                let (moved_bytes, bundle_reader) = elt_reader.get_ref().get_ref();
+                let content: &'a [u8] = bundle_reader.content;
+                let last_packet = bundle_reader.packet_index as usize + 1 >= bundle_reader.bundle.packets.len();
+
+                // Borrow if not oversized and if contiguous, the reader is then advanced
+                // just like if the data was read.
+                let borrow_len = if !moved_bytes.is_empty() {
+                    None
+                } else if elt_len <= content.len() {
+                    Some(elt_len)
+                } else if elt_len == u32::MAX as usize && last_packet {
+                    Some(content.len())
+                } else {
+                    None
+                };
+
+                let data = match borrow_len {
+                    Some(len) => {
+                        elt_reader.get_mut().get_mut().1.advance(len)?;
+                        elt_reader.set_limit(elt_reader.limit() - len as u64);
+                        Cow::Borrowed(&content[..len])
+                    }
+                    None => {
+                        let mut data = Vec::new();
+                        elt_reader.read_to_end(&mut data)?;
+                        Cow::Owned(data)
+                    }
+                };
+
+                E::decode_borrowed(data, config, elt_id)
+
+            },
+            next)
+    }
+
+    /// Internal function to read the current element's header and then decode the 
+    /// element with the given function, the element's length kind is given by a first
+    /// function depending on the element's id.
+    fn read_inner<E, F>(&mut self, 
+        read_length: impl FnOnce(u8) -> io::Result<ElementLength>,
+        read: F,
+        next: bool,
+    ) -> io::Result<BundleElement<E>>
+    where
+        F: FnOnce(&mut io::Take<io::Chain<&[u8], &mut BundleReader<'a>>>, usize, u8) -> io::Result<E>,
+    {
 
         // Here we ensure that we have some bytes to read the next element from.
         let Some(slice) = self.bundle_reader.ensure() else {
@@ -678,8 +1037,8 @@ impl<'a> BundleElementReader<'a> {
         let request = self.next_request_offset == Some(offset);
 
         // Get the element id ahead of time because we need to get the element length.
-        let elt_id = slice[0];  // Slice should not be empty.
-        let elt_len_kind = E::read_length(config, elt_id)?;
+        let elt_id = element_id_from_wire(self.variant, slice[0]);  // Slice should not be empty.
+        let elt_len_kind = read_length(elt_id)?;
 
         // Compute the required contiguous length of the header, add request header 
         // length if that element is a request.
@@ -694,7 +1053,7 @@ impl<'a> BundleElementReader<'a> {
         let reader_save = self.bundle_reader.clone();
 
         // After length has been checked, we can read all this for sure, so we unwrap.
-        let elt_id = self.bundle_reader.read_u8().unwrap();
+        let elt_id = element_id_from_wire(self.variant, self.bundle_reader.read_u8().unwrap());
         let elt_len = elt_len_kind.read(&mut self.bundle_reader).unwrap();
 
         // If the element is a request, we read the next request offset, if that offset
@@ -732,7 +1091,7 @@ impl<'a> BundleElementReader<'a> {
         // oversized, or empty slice if not necessary.
         let elt_reader = moved_bytes.chain(&mut self.bundle_reader);
         let mut elt_reader = elt_reader.take(elt_len as u64);
-        let element = match E::read(&mut elt_reader, config, elt_len as usize, elt_id) {
+        let element = match read(&mut elt_reader, elt_len as usize, elt_id) {
             Ok(ret) => ret,
             Err(e) => {
                 self.bundle_reader = reader_save;  // Rollback before going further.
@@ -774,6 +1133,15 @@ impl<'a> BundleElementReader<'a> {
 
 }
 
+/// Translate an element id on the wire to the canonical id, if a variant is given.
+#[inline]
+fn element_id_from_wire(variant: Option<&ProtocolVariant>, wire_id: u8) -> u8 {
+    match variant {
+        Some(variant) => variant.element_id_from_wire(wire_id),
+        None => wire_id,
+    }
+}
+
 impl fmt::Debug for BundleElementReader<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BundleElementReader")
@@ -809,7 +1177,7 @@ impl NextElementReader<'_, '_> {
 /// The simple variant of element, provides direct decoding using a codec.
 pub struct ElementReader<'reader, 'bundle>(&'reader mut BundleElementReader<'bundle>, u8);
 
-impl ElementReader<'_, '_> {
+impl<'bundle> ElementReader<'_, 'bundle> {
 
     /// Get the numeric identifier of the element being read.
     #[inline]
@@ -828,6 +1196,13 @@ impl ElementReader<'_, '_> {
         self.read_stable::<E, ()>(&())
     }
 
+    /// Read the element from its data, borrowed from the bundle if possible, see
+    /// [`BundleElementReader::read_borrowed`]. This method take self by value and 
+    /// automatically go the next element if read is successful.
+    pub fn read_borrowed<E: BorrowedElement<'bundle, C>, C>(self, config: &C) -> io::Result<BundleElement<E>> {
+        self.0.read_borrowed(config, true)
+    }
+
     /// Read the element using the given codec. This method take self by value and automatically
     /// go the next element if read is successful, if not successful you will need to call
     /// `Bundle::next_element` again.
@@ -902,3 +1277,430 @@ impl fmt::Debug for ReplyReader<'_, '_> {
         f.debug_struct("ReplyReader").field("request_id", &self.1).finish()
     }
 }
+
+
+/// The default timeout for a request to be answered, see [`RequestPolicy`].
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The policy applied to requests sent with a timeout, see [`RequestTracker::send`].
+#[derive(Debug, Clone)]
+pub struct RequestPolicy {
+    /// Time to wait for the reply to a request before retrying or giving up.
+    pub timeout: Duration,
+    /// Maximum number of times a request is sent again, with the same request id,
+    /// before giving up.
+    pub max_retries: u32,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_retries: 0,
+        }
+    }
+}
+
+/// A tracker of the requests sent to a peer, it's the only allocator of request ids,
+/// so that ids of all requests sent by an application never collide. 
+/// 
+/// Each request id can be registered with the decoder of its expected reply, so that
+/// replies can be decoded into their typed data when received, instead of being 
+/// skipped. All decoded replies are converted to a common type `R`, which is typically
+/// an enumeration of all replies known by the application.
+/// 
+/// Requests can also be sent with the timeout and retry policy of the tracker, in such
+/// case the request is kept until it's answered so that it can be written again when
+/// retried, or reported as failed once the policy's retries are exhausted.
+pub struct RequestTracker<R> {
+    /// The next request id returned by [`Self::next_request_id`].
+    next_request_id: u32,
+    /// The policy applied to requests sent with a timeout.
+    policy: RequestPolicy,
+    /// All requests waiting for a reply.
+    pending: HashMap<u32, PendingRequest<R>>,
+}
+
+/// Internal pending request of a [`RequestTracker`].
+struct PendingRequest<R> {
+    /// Decoder of the expected reply, if registered.
+    decode: Option<Box<dyn FnOnce(ReplyReader) -> io::Result<R> + Send>>,
+    /// Present if the request has been sent with a timeout.
+    timeout: Option<PendingTimeout>,
+}
+
+/// Internal timeout state of a pending request.
+struct PendingTimeout {
+    /// The address the request has been sent to.
+    addr: SocketAddr,
+    /// Deadline for the reply before retrying or giving up.
+    deadline: Instant,
+    /// Number of retries already sent.
+    retries: u32,
+    /// Write the request element with the given request id.
+    writer: Box<dyn Fn(&mut BundleElementWriter<'_>, u32) + Send>,
+}
+
+impl<R> RequestTracker<R> {
+
+    pub fn new() -> Self {
+        Self {
+            next_request_id: 1,
+            policy: RequestPolicy::default(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Get the policy applied to requests sent with a timeout.
+    #[inline]
+    pub fn policy(&self) -> &RequestPolicy {
+        &self.policy
+    }
+
+    /// Set the policy applied to requests sent with a timeout, this only applies to 
+    /// the next deadlines of pending requests.
+    pub fn set_policy(&mut self, policy: RequestPolicy) {
+        self.policy = policy;
+    }
+
+    /// Set the request id that will be allocated next, this can be used to allocate 
+    /// ids in a range that is not used by another peer, such as when injecting requests
+    /// in a proxied stream. Zero is not a valid request id and is replaced by 1.
+    pub fn set_next_request_id(&mut self, request_id: u32) {
+        self.next_request_id = request_id.max(1);
+    }
+
+    /// Allocate a new request id, these ids start at 1 and wraps, zero is never 
+    /// returned and ids of pending requests are skipped. Note that this doesn't 
+    /// register the request.
+    pub fn next_request_id(&mut self) -> u32 {
+        loop {
+            let request_id = self.next_request_id;
+            self.next_request_id = self.next_request_id.checked_add(1).unwrap_or(1);
+            if !self.pending.contains_key(&request_id) {
+                break request_id;
+            }
+        }
+    }
+
+    /// Register a request by its id, the reply to this request will be decoded as the
+    /// given simple data type and then converted to the common reply type. Any 
+    /// previous registration of the same request id is replaced.
+    pub fn register<D>(&mut self, request_id: u32)
+    where
+        D: Codec<()> + Into<R> + 'static,
+        R: 'static,
+    {
+        self.register_with(request_id, |reply| reply.read_simple::<D>().map(Into::into));
+    }
+
+    /// Register a request by its id, the reply to this request will be decoded by the
+    /// given function, this can be used for replies that require a config, or when 
+    /// some context of the request is needed to interpret the reply.
+    pub fn register_with<F>(&mut self, request_id: u32, decode: F)
+    where
+        F: FnOnce(ReplyReader) -> io::Result<R> + Send + 'static,
+    {
+        self.pending.entry(request_id)
+            .or_insert_with(|| PendingRequest { decode: None, timeout: None })
+            .decode = Some(Box::new(decode));
+    }
+
+    /// Allocate a request id for the given request, with the tracker's timeout and 
+    /// retry policy, the request should then be written with [`Self::write`] and sent
+    /// to the given address. Its reply can then be registered, like any other request.
+    pub fn send<E, C>(&mut self, addr: SocketAddr, element: E, config: C, now: Instant) -> u32
+    where
+        E: Element<C> + Clone + Send + 'static,
+        C: Send + 'static,
+    {
+
+        let request_id = self.next_request_id();
+        self.pending.insert(request_id, PendingRequest {
+            decode: None,
+            timeout: Some(PendingTimeout {
+                addr,
+                deadline: now + self.policy.timeout,
+                retries: 0,
+                writer: Box::new(move |writer, request_id| {
+                    writer.write_request(element.clone(), request_id, &config);
+                }),
+            }),
+        });
+
+        request_id
+
+    }
+
+    /// Write the given pending request, sent with [`Self::send`], to the element 
+    /// writer, returning false if the request is not pending.
+    pub fn write(&self, request_id: u32, writer: &mut BundleElementWriter<'_>) -> bool {
+        match self.pending.get(&request_id).and_then(|request| request.timeout.as_ref()) {
+            Some(timeout) => {
+                (timeout.writer)(writer, request_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return true if a reply is expected for the given request id.
+    #[inline]
+    pub fn is_pending(&self, request_id: u32) -> bool {
+        self.pending.contains_key(&request_id)
+    }
+
+    /// Forget the given request id, returning true if it was pending, its reply will
+    /// then be read as unknown.
+    #[inline]
+    pub fn cancel(&mut self, request_id: u32) -> bool {
+        self.pending.remove(&request_id).is_some()
+    }
+
+    /// Forget all pending requests.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Return the number of requests that are waiting for a reply.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Return true if no request is waiting for a reply.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Read the given reply, received from the given address, if its request id has 
+    /// been registered then it's decoded with the registered decoder and the request
+    /// is no longer pending, if not then the raw data of the reply is returned. In both
+    /// cases the reader goes to the next element if successful.
+    pub fn read_reply(&mut self, addr: SocketAddr, reply: ReplyReader) -> io::Result<TrackedReply<R>> {
+        match self.take_pending(addr, reply.request_id()).and_then(|request| request.decode) {
+            Some(decode) => decode(reply).map(TrackedReply::Known),
+            None => reply.read_simple::<RawReplyData>().map(|raw| TrackedReply::Unknown(raw.0)),
+        }
+    }
+
+    /// Handle a reply received from the given address without reading it, returning
+    /// true if it was answering a pending request, which is then forgotten.
+    pub fn handle_reply(&mut self, addr: SocketAddr, request_id: u32) -> bool {
+        self.take_pending(addr, request_id).is_some()
+    }
+
+    /// Internal function to remove the pending request answered by a reply from the
+    /// given address, a reply from another address than the one of a request sent 
+    /// with a timeout doesn't answer it.
+    fn take_pending(&mut self, addr: SocketAddr, request_id: u32) -> Option<PendingRequest<R>> {
+        match self.pending.get(&request_id)?.timeout.as_ref() {
+            Some(timeout) if timeout.addr != addr => None,
+            _ => self.pending.remove(&request_id),
+        }
+    }
+
+    /// Get the earliest deadline of all requests sent with a timeout.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values()
+            .filter_map(|request| request.timeout.as_ref())
+            .map(|timeout| timeout.deadline)
+            .min()
+    }
+
+    /// Poll the next expired request sent with a timeout, it should either be written
+    /// again and sent if it needs to be retried, or it has been forgotten if it timed
+    /// out.
+    pub fn poll(&mut self, now: Instant) -> Option<RequestEvent> {
+
+        let (&request_id, timeout) = self.pending.iter_mut()
+            .filter_map(|(request_id, request)| Some((request_id, request.timeout.as_mut()?)))
+            .find(|(_, timeout)| timeout.deadline <= now)?;
+
+        if timeout.retries < self.policy.max_retries {
+            timeout.retries += 1;
+            timeout.deadline = now + self.policy.timeout;
+            Some(RequestEvent::Retry(RequestRetryEvent {
+                addr: timeout.addr,
+                request_id,
+                retry: timeout.retries,
+            }))
+        } else {
+            let timeout = self.pending.remove(&request_id).unwrap().timeout.unwrap();
+            Some(RequestEvent::Timeout(RequestTimeoutEvent {
+                addr: timeout.addr,
+                request_id,
+                retries: timeout.retries,
+            }))
+        }
+
+    }
+
+}
+
+impl<R> Default for RequestTracker<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> fmt::Debug for RequestTracker<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = self.pending.keys().copied().collect::<Vec<_>>();
+        pending.sort_unstable();
+        f.debug_struct("RequestTracker")
+            .field("next_request_id", &self.next_request_id)
+            .field("policy", &self.policy)
+            .field("pending", &pending)
+            .finish()
+    }
+}
+
+/// An event returned when polling the request tracker.
+#[derive(Debug)]
+pub enum RequestEvent {
+    Retry(RequestRetryEvent),
+    Timeout(RequestTimeoutEvent),
+}
+
+/// A request has not been answered in time and should be sent again.
+#[derive(Debug, Clone)]
+pub struct RequestRetryEvent {
+    /// The address the request should be sent to.
+    pub addr: SocketAddr,
+    /// The request id, unchanged from the initial request.
+    pub request_id: u32,
+    /// The number of this retry, starting at 1.
+    pub retry: u32,
+}
+
+/// A request has not been answered in time and all retries are exhausted, the request
+/// is now forgotten.
+#[derive(Debug, Clone)]
+pub struct RequestTimeoutEvent {
+    /// The address the request was sent to.
+    pub addr: SocketAddr,
+    /// The request id.
+    pub request_id: u32,
+    /// The number of retries that were sent.
+    pub retries: u32,
+}
+
+/// A reply read by a [`RequestTracker`].
+#[derive(Clone, PartialEq, Eq)]
+pub enum TrackedReply<R> {
+    /// The request was registered and its reply has been decoded.
+    Known(R),
+    /// The request was not registered, the raw data of the reply is returned.
+    Unknown(Vec<u8>),
+}
+
+impl<R: fmt::Debug> fmt::Debug for TrackedReply<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Known(reply) => f.debug_tuple("Known").field(reply).finish(),
+            Self::Unknown(data) => f.debug_tuple("Unknown").field(&AsciiFmt(data)).finish(),
+        }
+    }
+}
+
+/// Internal codec for the raw data of unknown replies.
+struct RawReplyData(Vec<u8>);
+
+impl SimpleCodec for RawReplyData {
+
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_all(&self.0)
+    }
+
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        read.read_blob_to_end().map(Self)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::net::element::DebugElementFixed;
+
+    /// A request element used by tests.
+    const REQUEST: DebugElementFixed<0x10, 4> = DebugElementFixed { data: [1, 2, 3, 4] };
+
+    const ADDR: SocketAddr = SocketAddr::V4(std::net::SocketAddrV4::new(std::net::Ipv4Addr::LOCALHOST, 20014));
+
+    /// Write a reply of the given data for the given request id and read it with the
+    /// given tracker.
+    fn read_reply(tracker: &mut RequestTracker<u32>, request_id: u32, data: u32) -> TrackedReply<u32> {
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple_reply(data, request_id);
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Reply(reply)) = reader.next() else { panic!("expected a reply") };
+        tracker.read_reply(ADDR, reply).unwrap()
+    }
+
+    #[test]
+    fn request_ids() {
+
+        let mut tracker = RequestTracker::<u32>::new();
+        let mut bundle = Bundle::new();
+        let first = bundle.element_writer().add_request(&mut tracker, REQUEST).request_id();
+        let second = bundle.element_writer().add_request(&mut tracker, REQUEST).request_id();
+        assert_eq!((first, second), (1, 2));
+
+        // Pending ids are never allocated twice, and zero is skipped.
+        tracker.register::<u32>(4);
+        tracker.set_next_request_id(u32::MAX);
+        tracker.register::<u32>(1);
+        assert_eq!(tracker.next_request_id(), u32::MAX);
+        assert_eq!(tracker.next_request_id(), 2);
+        assert_eq!(tracker.next_request_id(), 3);
+        assert_eq!(tracker.next_request_id(), 5);
+
+    }
+
+    #[test]
+    fn typed_replies() {
+
+        let mut tracker = RequestTracker::<u32>::new();
+        let request_id = tracker.next_request_id();
+        tracker.register::<u32>(request_id);
+
+        assert_eq!(read_reply(&mut tracker, request_id, 42), TrackedReply::Known(42));
+        assert!(tracker.is_empty());
+        assert_eq!(read_reply(&mut tracker, request_id, 42), TrackedReply::Unknown(42u32.to_le_bytes().to_vec()));
+
+    }
+
+    #[test]
+    fn timeouts() {
+
+        let now = Instant::now();
+        let mut tracker = RequestTracker::<u32>::new();
+        tracker.set_policy(RequestPolicy { timeout: Duration::from_secs(1), max_retries: 1 });
+
+        let request_id = tracker.send(ADDR, REQUEST, (), now);
+        assert!(tracker.write(request_id, &mut Bundle::new().element_writer()));
+        assert_eq!(tracker.next_deadline(), Some(now + Duration::from_secs(1)));
+        assert!(tracker.poll(now).is_none());
+
+        let now = now + Duration::from_secs(1);
+        assert!(matches!(tracker.poll(now), Some(RequestEvent::Retry(RequestRetryEvent { retry: 1, .. }))));
+        let now = now + Duration::from_secs(1);
+        assert!(matches!(tracker.poll(now), Some(RequestEvent::Timeout(RequestTimeoutEvent { retries: 1, .. }))));
+        assert!(tracker.is_empty());
+
+        // A reply answers the request, but only from the address it was sent to.
+        let request_id = tracker.send(ADDR, REQUEST, (), now);
+        let other_addr = SocketAddr::V4(std::net::SocketAddrV4::new(std::net::Ipv4Addr::LOCALHOST, 20015));
+        assert!(!tracker.handle_reply(other_addr, request_id));
+        assert!(tracker.handle_reply(ADDR, request_id));
+        assert!(tracker.next_deadline().is_none());
+
+    }
+
+}
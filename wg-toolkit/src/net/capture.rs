@@ -0,0 +1,265 @@
+//! Capture format of decrypted packets, with their time, peer address and direction,
+//! this is used to record proxied sessions and to analyze them again later, offline.
+//!
+//! The capture starts with a magic and a version, followed by packet records, each
+//! record has its time since the start of the capture in microseconds, the application
+//! it has been exchanged with, its direction, the address of the client and the
+//! packet's data.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::util::io::{WgReadExt, WgWriteExt};
+
+use super::app::proxy::PacketDirection;
+use super::packet::{self, Packet};
+
+
+/// Magic of a capture file.
+pub const MAGIC: &[u8; 4] = b"WGCP";
+
+/// Current version of the capture format.
+pub const VERSION: u8 = 2;
+
+
+/// The application a captured packet has been exchanged with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaptureApp {
+    Login,
+    Base,
+}
+
+/// A packet record of a capture.
+#[derive(Debug, Clone)]
+pub struct CaptureRecord {
+    /// Time of the packet since the start of the capture.
+    pub time: Duration,
+    /// The application this packet has been exchanged with.
+    pub app: CaptureApp,
+    /// The client's address, regardless of the direction.
+    pub addr: SocketAddr,
+    /// The direction this packet has been sent in.
+    pub direction: PacketDirection,
+    /// The decrypted packet.
+    pub packet: Packet,
+}
+
+/// A writer of capture, the header is written on creation.
+#[derive(Debug)]
+pub struct CaptureWriter<W> {
+    /// Underlying writer.
+    inner: W,
+}
+
+impl<W: Write> CaptureWriter<W> {
+
+    /// Create a new capture writer, writing the header immediately.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        inner.write_all(MAGIC)?;
+        inner.write_u8(VERSION)?;
+        Ok(Self { inner })
+    }
+
+    /// Write a packet record to the capture.
+    pub fn write_packet(&mut self, time: Duration, app: CaptureApp, addr: SocketAddr, direction: PacketDirection, packet: &Packet) -> io::Result<()> {
+
+        self.inner.write_u64(time.as_micros().try_into().unwrap_or(u64::MAX))?;
+        self.inner.write_u8(match app {
+            CaptureApp::Login => 0,
+            CaptureApp::Base => 1,
+        })?;
+        self.inner.write_u8(match direction {
+            PacketDirection::Out => 0,
+            PacketDirection::In => 1,
+        })?;
+
+        match addr.ip() {
+            IpAddr::V4(ip) => {
+                self.inner.write_u8(4)?;
+                self.inner.write_all(&ip.octets())?;
+            }
+            IpAddr::V6(ip) => {
+                self.inner.write_u8(6)?;
+                self.inner.write_all(&ip.octets())?;
+            }
+        }
+
+        self.inner.write_u16(addr.port())?;
+        // Packets are never larger than their capacity, so it fits.
+        self.inner.write_u16(packet.len() as u16)?;
+        self.inner.write_all(packet.slice())
+
+    }
+
+    /// Write a packet record to the capture.
+    #[inline]
+    pub fn write_record(&mut self, record: &CaptureRecord) -> io::Result<()> {
+        self.write_packet(record.time, record.app, record.addr, record.direction, &record.packet)
+    }
+
+    /// Flush the underlying writer.
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+}
+
+/// A reader of capture, the header is checked on creation.
+#[derive(Debug)]
+pub struct CaptureReader<R> {
+    /// Underlying reader.
+    inner: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+
+    /// Create a new capture reader, reading and checking the header immediately.
+    pub fn new(mut inner: R) -> Result<Self, CaptureError> {
+
+        let mut magic = [0; 4];
+        inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(CaptureError::InvalidMagic);
+        }
+
+        let version = inner.read_u8()?;
+        if version != VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+
+        Ok(Self { inner })
+
+    }
+
+    /// Read the next packet record, none if the end of the capture is reached.
+    pub fn next_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+
+        // The end of the capture is only valid before the first byte of a record.
+        let mut time_buf = [0; 8];
+        match self.inner.read(&mut time_buf[..1])? {
+            0 => return Ok(None),
+            _ => self.inner.read_exact(&mut time_buf[1..])?,
+        }
+
+        let time = Duration::from_micros(u64::from_le_bytes(time_buf));
+        let app = match self.inner.read_u8()? {
+            0 => CaptureApp::Login,
+            1 => CaptureApp::Base,
+            _ => return Err(CaptureError::InvalidRecord),
+        };
+        let direction = match self.inner.read_u8()? {
+            0 => PacketDirection::Out,
+            1 => PacketDirection::In,
+            _ => return Err(CaptureError::InvalidRecord),
+        };
+
+        let ip = match self.inner.read_u8()? {
+            4 => {
+                let mut octets = [0; 4];
+                self.inner.read_exact(&mut octets)?;
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            6 => {
+                let mut octets = [0; 16];
+                self.inner.read_exact(&mut octets)?;
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return Err(CaptureError::InvalidRecord),
+        };
+
+        let addr = SocketAddr::new(ip, self.inner.read_u16()?);
+
+        let len = self.inner.read_u16()? as usize;
+        if !(packet::PACKET_HEADER_LEN..=packet::PACKET_CAP).contains(&len) {
+            return Err(CaptureError::InvalidRecord);
+        }
+
+        let mut packet = Packet::new();
+        self.inner.read_exact(&mut packet.buf_mut()[..len])?;
+        packet.set_len(len);
+
+        Ok(Some(CaptureRecord { time, app, addr, direction, packet }))
+
+    }
+
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+
+}
+
+/// Errors that can happen while reading a capture.
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    /// Invalid magic signature for the file.
+    #[error("invalid magic")]
+    InvalidMagic,
+    /// The capture has been written with an unsupported version of the format.
+    #[error("unsupported version: {0}")]
+    UnsupportedVersion(u8),
+    /// A record has an invalid application, direction, address or length.
+    #[error("invalid record")]
+    InvalidRecord,
+    /// Underlying IO error while reading the file.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+
+        let login_addr = SocketAddr::from(([127, 0, 0, 1], 20013));
+        let base_addr = SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 20014));
+
+        let mut packet = Packet::new();
+        packet.buf_mut()[packet::PACKET_HEADER_LEN..][..3].copy_from_slice(&[1, 2, 3]);
+        packet.set_len(packet::PACKET_HEADER_LEN + 3);
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.write_packet(Duration::from_micros(10), CaptureApp::Login, login_addr, PacketDirection::Out, &packet).unwrap();
+        writer.write_packet(Duration::from_micros(20), CaptureApp::Base, base_addr, PacketDirection::In, &packet).unwrap();
+        let data = writer.into_inner().unwrap();
+
+        let records = CaptureReader::new(Cursor::new(&data)).unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].time, records[0].app, records[0].addr, records[0].direction),
+            (Duration::from_micros(10), CaptureApp::Login, login_addr, PacketDirection::Out));
+        assert_eq!((records[1].time, records[1].app, records[1].addr, records[1].direction),
+            (Duration::from_micros(20), CaptureApp::Base, base_addr, PacketDirection::In));
+        assert_eq!(records[1].packet.slice(), packet.slice());
+
+        // A truncated record is an error, not the end of the capture.
+        let res = CaptureReader::new(Cursor::new(&data[..data.len() - 1])).unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert!(matches!(res, Err(CaptureError::Io(_))));
+
+    }
+
+}
@@ -0,0 +1,174 @@
+//! Packet ciphers used to encrypt and decrypt packets exchanged with a peer, the
+//! default cipher of the protocol is Blowfish, used after the login.
+
+use std::io::{self, Cursor};
+use std::sync::Arc;
+use std::fmt;
+
+use blowfish::Blowfish;
+
+use tracing::trace;
+
+use super::filter::{BlowfishReader, BlowfishWriter, blowfish::BLOCK_SIZE};
+use super::packet::{self, Packet};
+
+
+/// Encryption magic, 0xDEADBEEF in little endian.
+const ENCRYPTION_MAGIC: [u8; 4] = 0xDEADBEEFu32.to_le_bytes();
+/// Encryption footer length, 1 byte for wastage count + 4 bytes magic.
+const ENCRYPTION_FOOTER_LEN: usize = ENCRYPTION_MAGIC.len() + 1;
+
+
+/// A cipher of packets, only the packet's body is encrypted, the prefix is kept clear.
+/// This is implemented for [`Blowfish`], which is the cipher used by the protocol, and
+/// [`NullCipher`] which doesn't encrypt anything, this can be implemented to support
+/// alternative ciphers without modifying applications.
+pub trait PacketCipher: fmt::Debug + Send + Sync {
+
+    /// Encrypt the source packet and write it to the destination packet, its previous
+    /// content is completely erased.
+    fn encrypt(&self, src_packet: &Packet, dst_packet: &mut Packet);
+
+    /// Decrypt the source packet and write it to the destination packet, its previous
+    /// content is completely erased. False is returned if the source packet is not
+    /// valid for this cipher, the destination packet's content is then unspecified.
+    fn decrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) -> bool;
+
+}
+
+impl<C: PacketCipher + ?Sized> PacketCipher for Arc<C> {
+
+    #[inline]
+    fn encrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) {
+        (**self).encrypt(src_packet, dst_packet)
+    }
+
+    #[inline]
+    fn decrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) -> bool {
+        (**self).decrypt(src_packet, dst_packet)
+    }
+
+}
+
+/// The Blowfish cipher, everything except the packet prefix is encrypted, and the
+/// encrypted packet has a size that is a multiple of blowfish's block size (8). The
+/// clear data is also padded to block size, but with additional data at the end:
+/// encryption signature (0xDEADBEEF in little endian) and the wastage count + 1 on the
+/// last byte.
+impl PacketCipher for Blowfish {
+
+    fn encrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) {
+
+        // Get the minimum, unpadded length of this packet with encryption footer appended to it.
+        let mut len = src_packet.len() - packet::PACKET_PREFIX_LEN + ENCRYPTION_FOOTER_LEN;
+
+        // The wastage amount is basically the padding + 1 for the wastage itself.
+        let padding = (BLOCK_SIZE - (len % BLOCK_SIZE)) % BLOCK_SIZE;
+        len += padding;
+
+        // Clone the packet data into a new vec and append the padding and the footer.
+        let mut clear_data = src_packet.slice()[packet::PACKET_PREFIX_LEN..].to_vec();
+        clear_data.reserve_exact(padding + ENCRYPTION_FOOTER_LEN);
+        clear_data.extend_from_slice(&[0u8; BLOCK_SIZE - 1][..padding]); // Padding
+        clear_data.extend_from_slice(&ENCRYPTION_MAGIC); // Magic
+        clear_data.push(padding as u8 + 1); // Wastage count (+1 for it self size)
+
+        debug_assert_eq!(clear_data.len(), len, "incoherent length");
+        debug_assert_eq!(clear_data.len() % 8, 0, "data not padded as expected");
+
+        // +4 for the prefix.
+        dst_packet.set_len(clear_data.len() + 4);
+
+        // Unwrapping because we know that source/destination have the same length.
+        io::copy(
+            &mut Cursor::new(&clear_data[..]),
+            &mut BlowfishWriter::new(Cursor::new(&mut dst_packet.slice_mut()[packet::PACKET_PREFIX_LEN..]), self),
+        ).unwrap();
+
+        // Copy the prefix directly because it is clear.
+        dst_packet.write_prefix(src_packet.read_prefix());
+
+    }
+
+    fn decrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) -> bool {
+
+        let len = src_packet.len();
+
+        dst_packet.set_len(len);
+
+        // Decrypt the incoming packet into the new clear packet.
+        // We don't need to set the length yet because this packet will be synchronized just
+        // after. We don't encrypt the prefix.
+        let src = &src_packet.slice()[packet::PACKET_PREFIX_LEN..];
+        let dst = &mut dst_packet.slice_mut()[packet::PACKET_PREFIX_LEN..];
+
+        // Note that src and dst have the same length, thanks to blowfish encryption.
+        // Then we can already check the length and ensures that it is a multiple of
+        // blowfish block size *and* can contain the wastage and encryption magic.
+        if src.len() % BLOCK_SIZE != 0 {
+            trace!("Invalid source body length: {}, block size: {BLOCK_SIZE}", src.len());
+            return false;
+        } else if src.len() < ENCRYPTION_FOOTER_LEN {
+            trace!("Invalid source body length: {}, min len: {ENCRYPTION_FOOTER_LEN}", src.len());
+            return false;
+        }
+
+        // Unwrapping because we know that source/destination have the same length.
+        io::copy(
+            &mut BlowfishReader::new(Cursor::new(src), self),
+            &mut Cursor::new(&mut *dst),
+        ).unwrap();
+
+        let wastage_begin = src.len() - 1;
+        let magic_begin = wastage_begin - 4;
+
+        // Check invalid magic.
+        if dst[magic_begin..wastage_begin] != ENCRYPTION_MAGIC {
+            trace!("Invalid destination packet magic: {:X}, expected: {:X}",
+                crate::util::BytesFmt(&dst[magic_begin..wastage_begin]),
+                crate::util::BytesFmt(&ENCRYPTION_MAGIC));
+            return false;
+        }
+
+        // Get the wastage count and compute the packet's length.
+        // Note that wastage count also it self length.
+        let wastage = dst[wastage_begin];
+        if wastage == 0 || wastage as usize > BLOCK_SIZE {
+            trace!("Invalid wastage count: {wastage}, block size: {BLOCK_SIZE}");
+            return false;
+        }
+
+        let clear_len = len - wastage as usize - ENCRYPTION_MAGIC.len();
+        if clear_len < packet::PACKET_HEADER_LEN {
+            trace!("Invalid clear packet length: {clear_len}");
+            return false;
+        }
+
+        dst_packet.set_len(clear_len);
+        // Copy the prefix directly because it is clear.
+        dst_packet.write_prefix(src_packet.read_prefix());
+
+        true
+
+    }
+
+}
+
+/// A cipher that doesn't encrypt anything, packets are just copied, this can be used
+/// for offline servers or fuzzing where packets should be kept clear.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullCipher;
+
+impl PacketCipher for NullCipher {
+
+    fn encrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) {
+        dst_packet.set_len(src_packet.len());
+        dst_packet.slice_mut().copy_from_slice(src_packet.slice());
+    }
+
+    fn decrypt(&self, src_packet: &Packet, dst_packet: &mut Packet) -> bool {
+        self.encrypt(src_packet, dst_packet);
+        true
+    }
+
+}
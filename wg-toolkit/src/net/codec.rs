@@ -3,6 +3,7 @@
 
 
 use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddrV4};
 use std::borrow::Cow;
 use std::fmt;
 
@@ -125,6 +126,28 @@ impl<C, D: Codec<C>> Codec<C> for Vec<D> {
 
 }
 
+/// Optional values are prefixed by a presence flag, this is the encoding of BigWorld's
+/// FIXED_DICT declared with 'AllowNone'.
+impl<C, D: Codec<C>> Codec<C> for Option<D> {
+
+    fn write(&self, write: &mut dyn Write, config: &C) -> io::Result<()> {
+        write.write_bool(self.is_some())?;
+        if let Some(value) = self {
+            value.write(&mut *write, config)?;
+        }
+        Ok(())
+    }
+
+    fn read(read: &mut dyn Read, config: &C) -> io::Result<Self> {
+        if read.read_bool()? {
+            D::read(&mut *read, config).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+}
+
 macro_rules! impl_builtin_copy {
     ($ty:ty, $write_method:ident, $read_method:ident) => {
         impl SimpleCodec for $ty {
@@ -161,9 +184,10 @@ impl_builtin_copy!(Vec4, write_vec4, read_vec4);
 
 /// The string data type used by default for all STRING types, it will try to 
 #[derive(Clone)]
+#[cfg_attr(feature = "codec-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AutoString {
     String(String),
-    Python(serde_pickle::Value),
+    Python(#[cfg_attr(feature = "codec-serde", serde(serialize_with = "serialize_pickle_value"))] serde_pickle::Value),
     Raw(Vec<u8>),
 }
 
@@ -205,52 +229,234 @@ impl SimpleCodec for AutoString {
 }
 
 
-/// The Python builtin data type.
+/// The Python builtin data type, the pickle is kept raw and only decoded on demand, so
+/// that pickles that cannot be decoded, such as recursive structures or unsupported
+/// opcodes, are still forwarded unchanged.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Python {
-    /// Internal pickle value.
-    pub value: serde_pickle::Value,
+    raw: Vec<u8>,
+}
+
+impl Python {
+
+    /// Create from the raw pickle data, which is not checked.
+    #[inline]
+    pub fn from_raw(raw: Vec<u8>) -> Self {
+        Self { raw }
+    }
+
+    /// Pickle the given value.
+    pub fn encode(value: &serde_pickle::Value) -> serde_pickle::Result<Self> {
+        serde_pickle::value_to_vec(value, serde_pickle_ser_options()).map(Self::from_raw)
+    }
+
+    /// Unpickle the value.
+    pub fn decode(&self) -> serde_pickle::Result<serde_pickle::Value> {
+        serde_pickle::value_from_slice(&self.raw, serde_pickle_de_options())
+    }
+
+    /// The raw pickle data.
+    #[inline]
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    #[inline]
+    pub fn into_raw(self) -> Vec<u8> {
+        self.raw
+    }
+
 }
 
 impl SimpleCodec for Python {
 
     #[inline(always)]
     fn write(&self, write: &mut dyn Write) -> io::Result<()> {
-        write.write_python_pickle(&self.value)
+        write.write_blob_variable(&self.raw)
     }
 
     #[inline(always)]
     fn read(read: &mut dyn Read) -> io::Result<Self> {
-        read.read_python_pickle().map(|value| Self { value })
+        read.read_blob_variable().map(Self::from_raw)
     }
 
 }
 
 impl fmt::Debug for Python {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("Python")
-            .field(&format_args!("{}", self.value))
-            .finish()
+        let mut tuple = f.debug_tuple("Python");
+        match self.decode() {
+            Ok(value) => tuple.field(&format_args!("{value}")),
+            Err(_) => tuple.field(&AsciiFmt(&self.raw)),
+        };
+        tuple.finish()
+    }
+}
+
+/// The decoded value is serialized, so this fails if the pickle cannot be decoded.
+#[cfg(feature = "codec-serde")]
+impl serde::Serialize for Python {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = self.decode().map_err(serde::ser::Error::custom)?;
+        serialize_pickle_value(&value, serializer)
     }
 }
 
+#[cfg(feature = "codec-serde")]
+impl<'de> serde::Deserialize<'de> for Python {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <serde_pickle::Value as serde::Deserialize>::deserialize(deserializer)?;
+        Self::encode(&value).map_err(serde::de::Error::custom)
+    }
+}
 
-/// The mailbox type used sparingly in method calls.
-#[derive(Debug)]
+/// Pickle values don't implement serialize, so they are converted to the serde data 
+/// model: sets and tuples are serialized as sequences and long integers as strings, 
+/// they are therefore deserialized back as lists and strings.
+#[cfg(feature = "codec-serde")]
+fn serialize_pickle_value<S: serde::Serializer>(value: &serde_pickle::Value, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(&SerdePickleValue(value), serializer)
+}
+
+#[cfg(feature = "codec-serde")]
+struct SerdePickleValue<'a>(&'a serde_pickle::Value);
+
+#[cfg(feature = "codec-serde")]
+impl serde::Serialize for SerdePickleValue<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde_pickle::Value;
+        match self.0 {
+            Value::None => serializer.serialize_none(),
+            &Value::Bool(v) => serializer.serialize_bool(v),
+            &Value::I64(v) => serializer.serialize_i64(v),
+            Value::Int(v) => serializer.collect_str(v),
+            &Value::F64(v) => serializer.serialize_f64(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::List(v) |
+            Value::Tuple(v) => serializer.collect_seq(v.iter().map(SerdePickleValue)),
+            Value::Set(v) |
+            Value::FrozenSet(v) => serializer.collect_seq(v.iter().map(SerdePickleHashableValue)),
+            Value::Dict(v) => serializer.collect_map(v.iter().map(|(k, v)| (SerdePickleHashableValue(k), SerdePickleValue(v)))),
+        }
+    }
+}
+
+#[cfg(feature = "codec-serde")]
+struct SerdePickleHashableValue<'a>(&'a serde_pickle::HashableValue);
+
+#[cfg(feature = "codec-serde")]
+impl serde::Serialize for SerdePickleHashableValue<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde_pickle::HashableValue;
+        match self.0 {
+            HashableValue::None => serializer.serialize_none(),
+            &HashableValue::Bool(v) => serializer.serialize_bool(v),
+            &HashableValue::I64(v) => serializer.serialize_i64(v),
+            HashableValue::Int(v) => serializer.collect_str(v),
+            &HashableValue::F64(v) => serializer.serialize_f64(v),
+            HashableValue::Bytes(v) => serializer.serialize_bytes(v),
+            HashableValue::String(v) => serializer.serialize_str(v),
+            HashableValue::Tuple(v) => serializer.collect_seq(v.iter().map(SerdePickleHashableValue)),
+            HashableValue::FrozenSet(v) => serializer.collect_seq(v.iter().map(SerdePickleHashableValue)),
+        }
+    }
+}
+
+
+/// The mailbox type used sparingly in method calls, this is a reference to an entity
+/// on the app at the given address. The component of the entity targeted by the 
+/// mailbox and the entity type id are packed in the salt of the address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "codec-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mailbox {
     pub entity_id: u32,
-    pub address: (), // TODO: 
+    pub addr: SocketAddrV4,
+    /// The component on the 3 highest bits and the entity type id on the 13 lowest.
+    pub salt: u16,
+}
+
+impl Mailbox {
+
+    /// The size of an encoded mailbox.
+    pub const SIZE: usize = 12;
+
+    /// Create a new mailbox reference, the entity type id is truncated to 13 bits.
+    pub fn new(entity_id: u32, addr: SocketAddrV4, component: MailboxComponent, entity_type_id: u16) -> Self {
+        Self {
+            entity_id,
+            addr,
+            salt: ((component as u16) << 13) | (entity_type_id & 0x1FFF),
+        }
+    }
+
+    /// The component of the entity targeted by the mailbox.
+    pub fn component(&self) -> MailboxComponent {
+        MailboxComponent::from_raw((self.salt >> 13) as u8)
+    }
+
+    /// The type id of the entity.
+    pub fn entity_type_id(&self) -> u16 {
+        self.salt & 0x1FFF
+    }
+
 }
 
 impl SimpleCodec for Mailbox {
 
-    #[inline]
-    fn write(&self, _write: &mut dyn Write) -> io::Result<()> {
-        Err(io::Error::new(io::ErrorKind::InvalidData, "mailbox write not yet supported"))
+    fn write(&self, write: &mut dyn Write) -> io::Result<()> {
+        write.write_u32(self.entity_id)?;
+        // The address is in network order, but not the salt.
+        write.write_all(&self.addr.ip().octets())?;
+        write.write_all(&self.addr.port().to_be_bytes())?;
+        write.write_u16(self.salt)
     }
 
-    #[inline]
-    fn read(_read: &mut dyn Read) -> io::Result<Self> {
-        Err(io::Error::new(io::ErrorKind::InvalidData, "mailbox write not yet supported"))
+    fn read(read: &mut dyn Read) -> io::Result<Self> {
+        let entity_id = read.read_u32()?;
+        let mut addr = [0; 6];
+        read.read_exact(&mut addr)?;
+        let ip = Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]);
+        let port = u16::from_be_bytes([addr[4], addr[5]]);
+        Ok(Self {
+            entity_id,
+            addr: SocketAddrV4::new(ip, port),
+            salt: read.read_u16()?,
+        })
+    }
+
+}
+
+/// The component of an entity targeted by a mailbox, some are forwarded through another
+/// component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "codec-serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum MailboxComponent {
+    Cell = 0,
+    Base = 1,
+    Client = 2,
+    BaseViaCell = 3,
+    ClientViaCell = 4,
+    CellViaBase = 5,
+    ClientViaBase = 6,
+    Service = 7,
+}
+
+impl MailboxComponent {
+
+    /// Return the component from its raw value, only the 3 lowest bits are used.
+    pub fn from_raw(raw: u8) -> Self {
+        match raw & 0b111 {
+            0 => Self::Cell,
+            1 => Self::Base,
+            2 => Self::Client,
+            3 => Self::BaseViaCell,
+            4 => Self::ClientViaCell,
+            5 => Self::CellViaBase,
+            6 => Self::ClientViaBase,
+            _ => Self::Service,
+        }
     }
 
 }
@@ -1,6 +1,7 @@
 //! Definitions for elements contained in bundles (and so in packets).
 
 use std::io::{self, Read, Write};
+use std::borrow::Cow;
 use std::fmt;
 
 use crate::util::AsciiFmt;
@@ -76,6 +77,27 @@ impl<E: SimpleElement<C>, C> Element<C> for E {
 }
 
 
+/// An alternative to [`Element`] for decoding elements from their whole data at once,
+/// the data is borrowed from the bundle when the element is contiguous in a single 
+/// packet, which avoids copying it, this is the case of most elements. See
+/// [`ElementReader::read_borrowed`](super::bundle::ElementReader::read_borrowed).
+/// 
+/// This is only implemented by [`RawElement`], which is the one to use for elements 
+/// that are only logged or forwarded. Typed elements, such as entity methods, own 
+/// their decoded fields and are still decoded through [`Element`].
+pub trait BorrowedElement<'a, C = ()>: Sized {
+
+    /// Provide the configuration of this elements when reading it, possibly depending on
+    /// the configuration and the numeric identifier of the element.
+    fn read_length(config: &C, id: u8) -> io::Result<ElementLength>;
+
+    /// Decode the element from its whole data and the given configuration. The id the
+    /// element is being decoded for is also given.
+    fn decode_borrowed(data: Cow<'a, [u8]>, config: &C, id: u8) -> io::Result<Self>;
+
+}
+
+
 /// Type of length used by a specific message codec.
 /// This describes how the length of an element should be encoded in the packet.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -314,6 +336,74 @@ impl_debug_element_var!(DebugElementVariable24, ElementLength::Variable24);
 impl_debug_element_var!(DebugElementVariable32, ElementLength::Variable32);
 impl_debug_element_var!(DebugElementUndefined, ElementLength::Undefined);
 
+/// An element of any id that just keeps its data, borrowed from the bundle if possible.
+/// The configuration of this element is its length kind, and its id is the one it has
+/// been read for, when written.
+#[derive(Clone)]
+pub struct RawElement<'a> {
+    pub id: u8,
+    pub data: Cow<'a, [u8]>,
+}
+
+impl RawElement<'_> {
+
+    /// Take ownership of the data, if borrowed.
+    pub fn into_owned(self) -> RawElement<'static> {
+        RawElement {
+            id: self.id,
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+
+}
+
+impl<'a> BorrowedElement<'a, ElementLength> for RawElement<'a> {
+
+    #[inline]
+    fn read_length(config: &ElementLength, _id: u8) -> io::Result<ElementLength> {
+        Ok(*config)
+    }
+
+    #[inline]
+    fn decode_borrowed(data: Cow<'a, [u8]>, _config: &ElementLength, id: u8) -> io::Result<Self> {
+        Ok(Self { id, data })
+    }
+
+}
+
+impl Element<ElementLength> for RawElement<'_> {
+
+    #[inline]
+    fn write_length(&self, config: &ElementLength) -> io::Result<ElementLength> {
+        Ok(*config)
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &ElementLength) -> io::Result<u8> {
+        write.write_all(&self.data)?;
+        Ok(self.id)
+    }
+
+    #[inline]
+    fn read_length(config: &ElementLength, _id: u8) -> io::Result<ElementLength> {
+        Ok(*config)
+    }
+
+    fn read(read: &mut dyn Read, _config: &ElementLength, _len: usize, id: u8) -> io::Result<Self> {
+        Ok(Self { id, data: Cow::Owned(read.read_blob_to_end()?) })
+    }
+
+}
+
+impl fmt::Debug for RawElement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawElement")
+            .field("id", &self.id)
+            .field("len", &self.data.len())
+            .field("data", &AsciiFmt(&self.data))
+            .finish()
+    }
+}
+
 /// An utility structure for storing ranges of element's ids. It provides way
 /// of converting between **element id** (with optional **sub-id**) and 
 /// **exposed id**.
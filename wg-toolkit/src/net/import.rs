@@ -0,0 +1,217 @@
+//! Import of pcap or pcapng captures into the decoding pipeline, UDP datagrams that
+//! are exchanged with the game servers, identified by their port, are converted to
+//! packets and reassembled into bundles, this allows decoding traffic that has been
+//! captured with external tools, such as Wireshark, without running the proxy.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::io::{self, Read};
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::util::pcap::{PcapReader, PcapError};
+
+use super::app::proxy::{Decoder, BundleEvent, PacketDirection};
+use super::cipher::PacketCipher;
+use super::packet::{self, Packet};
+
+
+/// Default ports of the game servers, login apps are usually listening on the first
+/// ones and base apps on the following ones.
+pub const DEFAULT_SERVER_PORTS: [u16; 5] = [20014, 20015, 20016, 20017, 20018];
+
+
+/// A bundle that has been imported from a capture.
+#[derive(Debug)]
+pub struct ImportedBundle {
+    /// Capture time of the datagram that completed the bundle, relative to the UNIX epoch.
+    pub time: Duration,
+    /// The reconstructed bundle, with the client's address and its direction.
+    pub event: BundleEvent,
+}
+
+/// A packet that has been imported from a capture, before being decoded, this can be
+/// used to decode the packets with another decoder than the importer's one.
+#[derive(Debug)]
+pub struct ImportedPacket {
+    /// Capture time of the datagram, relative to the UNIX epoch.
+    pub time: Duration,
+    /// The client's address, regardless of the direction.
+    pub addr: SocketAddr,
+    /// The direction of the packet.
+    pub direction: PacketDirection,
+    /// The packet, still encrypted if its server port has a cipher.
+    pub packet: Packet,
+    /// The cipher of the packet's server port, if encrypted.
+    pub cipher: Option<Arc<dyn PacketCipher>>,
+}
+
+/// An importer of captures, datagrams are only imported if their source or destination
+/// port is one of the configured server ports, the other side being the client.
+#[derive(Debug)]
+pub struct PcapImporter<R> {
+    /// Underlying capture reader.
+    reader: PcapReader<R>,
+    /// The decoder tracking the protocol of each client in both directions.
+    decoder: Decoder,
+    /// Server ports to import, associated to the cipher of their packets, if encrypted.
+    servers: HashMap<u16, Option<Arc<dyn PacketCipher>>>,
+}
+
+impl<R: Read> PcapImporter<R> {
+
+    /// Create a new importer from the given capture reader, no server port is imported
+    /// by default, see [`Self::add_server_port`].
+    pub fn new(reader: PcapReader<R>) -> Self {
+        Self {
+            reader,
+            decoder: Decoder::new(),
+            servers: HashMap::new(),
+        }
+    }
+
+    /// Import all datagrams exchanged with a server on the given port, if given the
+    /// cipher is used to decrypt all packets of this port, this is typically the
+    /// session's blowfish cipher for base apps, login apps packets are in clear.
+    pub fn add_server_port(&mut self, port: u16, cipher: Option<Arc<dyn PacketCipher>>) {
+        self.servers.insert(port, cipher);
+    }
+
+    /// Return a mutable reference to the underlying decoder, for example to change its
+    /// protocol variant.
+    #[inline]
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Read datagrams until one is exchanged with a server port, and return it as a
+    /// packet without decoding it, none is returned at the end of the capture. If the
+    /// datagram is not a valid packet, a [`ImportError::Packet`] error is returned and
+    /// the import can be continued with the next datagram.
+    /// 
+    /// Packets returned by this function are not given to the importer's decoder, so
+    /// this should not be mixed with [`Self::next_bundle`].
+    pub fn next_packet(&mut self) -> Result<Option<ImportedPacket>, ImportError> {
+
+        while let Some(datagram) = self.reader.next_datagram()? {
+
+            // The client's address is used regardless of the direction.
+            let (addr, direction, cipher) = if let Some(cipher) = self.servers.get(&datagram.dst.port()) {
+                (datagram.src, PacketDirection::Out, cipher)
+            } else if let Some(cipher) = self.servers.get(&datagram.src.port()) {
+                (datagram.dst, PacketDirection::In, cipher)
+            } else {
+                continue;
+            };
+
+            let len = datagram.data.len();
+            if !(packet::PACKET_HEADER_LEN..=packet::PACKET_CAP).contains(&len) {
+                return Err(ImportError::Packet(addr, io::Error::new(io::ErrorKind::InvalidData,
+                    format!("invalid datagram length: {len}"))));
+            }
+
+            let mut packet = Packet::new();
+            packet.buf_mut()[..len].copy_from_slice(&datagram.data);
+            packet.set_len(len);
+
+            return Ok(Some(ImportedPacket {
+                time: datagram.time,
+                addr,
+                direction,
+                packet,
+                cipher: cipher.clone(),
+            }));
+
+        }
+
+        Ok(None)
+
+    }
+
+    /// Read datagrams until a bundle is reconstructed, none is returned at the end of
+    /// the capture. If a packet cannot be decoded, a [`ImportError::Packet`] error is
+    /// returned and the import can be continued with the next datagram.
+    pub fn next_bundle(&mut self) -> Result<Option<ImportedBundle>, ImportError> {
+
+        while let Some(packet) = self.next_packet()? {
+            match self.decoder.decode(packet.packet, packet.addr, packet.direction, packet.cipher.as_deref()) {
+                Ok(Some(event)) => return Ok(Some(ImportedBundle { time: packet.time, event })),
+                Ok(None) => {}
+                Err(e) => return Err(ImportError::Packet(packet.addr, e)),
+            }
+        }
+
+        Ok(None)
+
+    }
+
+}
+
+impl<R: Read> Iterator for PcapImporter<R> {
+
+    type Item = Result<ImportedBundle, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_bundle().transpose()
+    }
+
+}
+
+/// Errors that can happen while importing a capture.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    /// The capture itself cannot be read, the import cannot continue.
+    #[error("pcap error: {0}")]
+    Pcap(#[from] PcapError),
+    /// A packet exchanged with the given client cannot be decoded, it is ignored.
+    #[error("packet error from {0}: {1}")]
+    Packet(SocketAddr, io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::util::pcap::{PcapWriter, UdpDatagram};
+
+    use super::*;
+
+    #[test]
+    fn next_packet() {
+
+        let client = "127.0.0.1:1234".parse::<SocketAddr>().unwrap();
+        let base_app = "127.0.0.1:20016".parse::<SocketAddr>().unwrap();
+        let other = "127.0.0.1:5000".parse::<SocketAddr>().unwrap();
+
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        for (src, dst, data) in [
+            (client, base_app, vec![0; packet::PACKET_HEADER_LEN]),
+            (client, other, vec![0; packet::PACKET_HEADER_LEN]),
+            (base_app, client, vec![0; 1]),
+            (base_app, client, vec![1; packet::PACKET_HEADER_LEN + 2]),
+        ] {
+            writer.write_datagram(&UdpDatagram { time: Duration::from_secs(1), src, dst, data }).unwrap();
+        }
+
+        let data = writer.into_inner().unwrap();
+        let mut importer = PcapImporter::new(PcapReader::new(&data[..]).unwrap());
+        importer.add_server_port(base_app.port(), None);
+
+        let packet = importer.next_packet().unwrap().unwrap();
+        assert_eq!((packet.addr, packet.direction), (client, PacketDirection::Out));
+        assert_eq!(packet.packet.len(), packet::PACKET_HEADER_LEN);
+
+        // The datagram with another server is skipped, the too short one is an error.
+        assert!(matches!(importer.next_packet(), Err(ImportError::Packet(addr, _)) if addr == client));
+
+        let packet = importer.next_packet().unwrap().unwrap();
+        assert_eq!((packet.addr, packet.direction), (client, PacketDirection::In));
+        assert_eq!(packet.packet.len(), packet::PACKET_HEADER_LEN + 2);
+
+        assert!(importer.next_packet().unwrap().is_none());
+
+    }
+
+}
@@ -23,14 +23,23 @@
 
 pub mod codec;
 pub mod element;
+pub mod sniff;
+pub mod capture;
+pub mod import;
 
 pub mod seq;
 pub mod packet;
 pub mod bundle;
 
 pub mod filter;
+pub mod cipher;
 
+pub mod transport;
 pub mod socket;
 pub mod proto;
+pub mod variant;
 
 pub mod app;
+
+#[cfg(feature = "testing")]
+pub mod testing;
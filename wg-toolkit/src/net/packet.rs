@@ -1,6 +1,7 @@
 //! Packet structure definition with synchronization methods.
 
 use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 use std::io::{Cursor, Read};
 use std::num::NonZero;
 use std::fmt;
@@ -9,6 +10,7 @@ use crate::util::io::{SliceCursor, WgReadExt, WgWriteExt};
 use crate::util::{AsciiFmt, TruncateFmt};
 
 use super::seq::Seq;
+use super::variant::FlagLayout;
 
 
 /// According to disassembly of WoT, outside of a channel, the max size if always
@@ -204,6 +206,7 @@ impl Packet {
     pub fn read_config(&self, config: &mut PacketConfig) -> Result<(), PacketConfigError> {
 
         let mut new = PacketConfig::new();
+        new.flag_layout = config.flag_layout;
         // We temporarily swap the two single acks dequeue, so that when decoding the new
         // single acks will be pushed back after existing ones. If not successful we'll
         // revert any pushed ack using the saved start length.
@@ -230,16 +233,33 @@ impl Packet {
     }
 
     /// Read the configuration of this packet and returns it.
+    #[inline]
     pub fn read_config_locked_ref(&self) -> Result<PacketLockedRef<'_>, PacketConfigError> {
+        self.read_config_locked_ref_with_layout(FlagLayout::IDENTITY)
+    }
+
+    /// Read the configuration of this packet, with the given layout of flags on the 
+    /// wire, and returns it.
+    pub fn read_config_locked_ref_with_layout(&self, flag_layout: FlagLayout) -> Result<PacketLockedRef<'_>, PacketConfigError> {
         let mut config = PacketConfig::new();
+        config.set_flag_layout(flag_layout);
         config.read(self)?;
         Ok(PacketLockedRef { packet: self, config })
     }
 
     /// Read the configuration of this packet, and lock the packet with its configuration
     /// if successful, if not successful the packet and the error are returned.
+    #[inline]
     pub fn read_config_locked(self) -> Result<PacketLocked, (PacketConfigError, Self)> {
+        self.read_config_locked_with_layout(FlagLayout::IDENTITY)
+    }
+
+    /// Read the configuration of this packet, with the given layout of flags on the
+    /// wire, and lock the packet with its configuration if successful, if not 
+    /// successful the packet and the error are returned.
+    pub fn read_config_locked_with_layout(self, flag_layout: FlagLayout) -> Result<PacketLocked, (PacketConfigError, Self)> {
         let mut config = PacketConfig::new();
+        config.set_flag_layout(flag_layout);
         match config.read(&self) {
             Ok(()) => Ok(PacketLocked { packet: self, config }),
             Err(e) => Err((e, self))
@@ -288,6 +308,84 @@ impl fmt::Debug for Packet {
 }
 
 
+/// A thread-safe pool of packets, used to recycle the allocations of packets that are
+/// no longer used instead of allocating new ones, packets can be given back from any
+/// thread. This structure is cheap to clone, clones share the same pool.
+#[derive(Debug, Clone)]
+pub struct PacketPool {
+    inner: Arc<PacketPoolInner>,
+}
+
+#[derive(Debug)]
+struct PacketPoolInner {
+    /// The free packets.
+    packets: Mutex<Vec<Packet>>,
+    /// Maximum number of free packets kept, further packets are dropped.
+    cap: usize,
+}
+
+impl PacketPool {
+
+    /// Default maximum number of free packets kept.
+    pub const DEFAULT_CAP: usize = 256;
+
+    /// Create a new pool keeping at most the given number of free packets.
+    pub fn new(cap: usize) -> Self {
+        Self {
+            inner: Arc::new(PacketPoolInner {
+                packets: Mutex::new(Vec::new()),
+                cap,
+            }),
+        }
+    }
+
+    /// Take a free packet from the pool, or allocate a new one if there is none, the 
+    /// packet is reset and its data is not cleared.
+    pub fn take(&self) -> Packet {
+        self.inner.packets.lock().unwrap().pop().unwrap_or_else(Packet::new)
+    }
+
+    /// Give a packet back to the pool, it's dropped if the pool is full.
+    pub fn put(&self, mut packet: Packet) {
+        let mut packets = self.inner.packets.lock().unwrap();
+        if packets.len() < self.inner.cap {
+            packet.reset();
+            packets.push(packet);
+        }
+    }
+
+    /// Give all the given packets back to the pool, such as the packets of a bundle 
+    /// that is no longer used.
+    pub fn put_all(&self, packets: impl IntoIterator<Item = Packet>) {
+        let mut free_packets = self.inner.packets.lock().unwrap();
+        for mut packet in packets {
+            if free_packets.len() >= self.inner.cap {
+                break;
+            }
+            packet.reset();
+            free_packets.push(packet);
+        }
+    }
+
+    /// Return the number of free packets in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.packets.lock().unwrap().len()
+    }
+
+    /// Return true if there is no free packet in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+}
+
+impl Default for PacketPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAP)
+    }
+}
+
+
 /// Represent a configuration for flags their footer values to write or read on/from a
 /// packet's data. This configuration allows invalid states and parameter combinations,
 /// it's up to the user of this configuration (usually a channel tracker) to properly
@@ -348,6 +446,9 @@ pub struct PacketConfig {
     /// 
     /// Used when `flags::UNK1000` is set.
     last_reliable_sequence_num: Seq,
+    /// Layout of the flags on the wire, flags are translated from and to this layout
+    /// when reading and writing the packet, see [`ProtocolVariant`](super::variant::ProtocolVariant).
+    flag_layout: FlagLayout,
 }
 
 impl PacketConfig {
@@ -367,9 +468,22 @@ impl PacketConfig {
             channel_index: NonZero::new(1).unwrap(),
             channel_version: NonZero::new(1).unwrap(),
             last_reliable_sequence_num: Seq::ZERO,
+            flag_layout: FlagLayout::IDENTITY,
         }
     }
 
+    /// The layout of flags on the wire, identity by default.
+    #[inline]
+    pub fn flag_layout(&self) -> FlagLayout {
+        self.flag_layout
+    }
+
+    /// Set the layout of flags on the wire, used when reading and writing the packet.
+    #[inline]
+    pub fn set_flag_layout(&mut self, flag_layout: FlagLayout) {
+        self.flag_layout = flag_layout;
+    }
+
     #[inline]
     fn has_flags(&self, flags: u16) -> bool {
         self.flags & flags == flags
@@ -615,7 +729,7 @@ impl PacketConfig {
     fn read(&mut self, packet: &Packet) -> Result<(), PacketConfigError> {
 
         // Create a new packet config that we'll push if read is successful.
-        self.flags = packet.read_flags();
+        self.flags = self.flag_layout.from_wire(packet.read_flags());
 
         // Create a cursor to read data from the end, we skip the header so that any
         // read of before the slice is error and so return packet corrupted error.
@@ -658,6 +772,9 @@ impl PacketConfig {
         }
 
         if self.has_flags(flags::HAS_PIGGYBACKS) {
+            // Piggybacks are read backward, so each one is inserted before the previous
+            // one in order to get them in the order they were written.
+            let piggybacks_start = self.piggybacks.len();
             loop {
 
                 let piggyback_len = data.pop_back(2)
@@ -679,7 +796,7 @@ impl PacketConfig {
                 let mut piggyback_packet = Packet::new();
                 piggyback_packet.buf_mut()[PACKET_PREFIX_LEN..][..piggyback_slice.len()].copy_from_slice(piggyback_slice);
                 piggyback_packet.set_len(PACKET_PREFIX_LEN + piggyback_slice.len());
-                self.piggybacks.push_back(piggyback_packet);
+                self.piggybacks.insert(piggybacks_start, piggyback_packet);
 
                 if piggyback_done {
                     break;
@@ -856,32 +973,37 @@ impl PacketConfig {
             cursor.write_u32(id.get()).unwrap();
         }
 
-        // Now add as many piggyback packets as possible!
-        let mut has_piggyback = false;
+        // Now add as many piggyback packets as possible! Their prefix is not copied, 
+        // just like when reading them. The offset of the first written length is kept
+        // because the footer is read backward, so it's the last piggyback to be read.
+        let mut first_piggyback_len_offset = None;
         while let Some(piggyback_packet) = self.piggybacks.front() {
             // Check if the packet can be safely added, ending with its own length (i16),
             // we must also consider checksum!
-            let piggyback_slice = piggyback_packet.slice();
+            let piggyback_slice = &piggyback_packet.slice()[PACKET_PREFIX_LEN..];
             if packet.free() >= piggyback_slice.len() + 2 + if self.has_checksum() { 4 } else { 0 } {
                 packet.grow(piggyback_slice.len()).copy_from_slice(piggyback_slice);
+                first_piggyback_len_offset.get_or_insert(packet.len());
                 packet.grow(2).write_i16(piggyback_slice.len() as i16).unwrap();
                 self.piggybacks.pop_front().unwrap();
-                has_piggyback = true;
             } else {
-                if has_piggyback {
-                    // When breaking, we must mark any previously inserted piggyback to be
-                    // the last one, so we negate of its bits.
-                    for byte in packet.slice_mut().split_last_chunk_mut::<2>().unwrap().1 {
-                        *byte = !*byte;
-                    }
-                }
                 break;
             }
         }
+
+        let has_piggyback = first_piggyback_len_offset.is_some();
+        if let Some(offset) = first_piggyback_len_offset {
+            // We must mark the first inserted piggyback to be the last one read, whether
+            // the queue is empty or the packet is full, so we negate of its length's bits.
+            for byte in &mut packet.slice_mut()[offset..][..2] {
+                *byte = !*byte;
+            }
+        }
+        
         self.switch_flags(flags::HAS_PIGGYBACKS, has_piggyback);
 
         // Write flags just before computing any checksum.
-        packet.write_flags(self.flags);
+        packet.write_flags(self.flag_layout.to_wire(self.flags));
 
         // If checksum enabled, compute the checksum of the whole body of the packet,
         // which range from flags to the end of the footer. The checksum will be
@@ -1185,3 +1307,44 @@ pub enum PacketConfigError {
     #[error("invalid checksum")]
     InvalidChecksum
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Create a packet with the given flags and body, and no footer.
+    fn packet_with_body(flags: u16, body: &[u8]) -> Packet {
+        let mut packet = Packet::new();
+        packet.write_flags(flags);
+        packet.grow(body.len()).copy_from_slice(body);
+        packet
+    }
+
+    #[test]
+    fn piggybacks() {
+
+        let bodies: [&[u8]; 3] = [b"first", b"", b"third piggyback"];
+
+        let mut config = PacketConfig::new();
+        for body in bodies {
+            config.piggybacks_mut().push_back(packet_with_body(0, body));
+        }
+
+        let mut packet = packet_with_body(0, b"main body");
+        packet.write_config(&mut config);
+        assert!(config.piggybacks().is_empty());
+
+        let locked = packet.read_config_locked_ref().unwrap();
+        let piggybacks = locked.config.piggybacks();
+        assert_eq!(piggybacks.len(), bodies.len());
+        for (piggyback, body) in piggybacks.iter().zip(bodies) {
+            assert_eq!(&piggyback.slice()[PACKET_HEADER_LEN..], body);
+        }
+
+        assert_eq!(&packet.slice()[PACKET_HEADER_LEN..][..locked.config.footer_offset() - PACKET_HEADER_LEN], b"main body");
+
+    }
+
+}
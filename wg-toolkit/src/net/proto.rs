@@ -5,16 +5,28 @@ use std::time::{Duration, Instant};
 use std::net::SocketAddr;
 use std::cmp::Ordering;
 use std::num::NonZero;
+use std::sync::Arc;
+use std::io;
 
 use tracing::{instrument, trace, trace_span, warn};
 
 use super::packet::{Packet, PacketConfig, PacketLocked, PacketConfigError};
 use super::seq::{Seq, SeqAlloc};
-use super::bundle::Bundle;
+use super::variant::ProtocolVariant;
+use super::bundle::{Bundle, FragmentAssembler, FragmentError};
+use super::cipher::PacketCipher;
+use super::socket::{PacketSocket, decrypt_packet};
 
 
 /// The (currently hardcoded) timeout on bundle fragments.
 const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The interval between two checks of timed out bundle fragments.
+const FRAGMENT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// The minimum timeout returned by [`Protocol::retransmit_timeout`].
+const MIN_RETRANSMIT_TIMEOUT: Duration = Duration::from_millis(1);
+/// The default number of received sequence numbers remembered by a 
+/// [`ReliabilityTracker`] to detect duplicates, without cumulative ack.
+const DEFAULT_DUPLICATE_WINDOW: usize = 1024;
 
 
 /// A protocol tracker for an interface, providing support for accepting and preparing
@@ -27,6 +39,16 @@ pub struct Protocol {
     off_channels: HashMap<SocketAddr, OffChannel>,
     /// Known channels for each address, with optional channel indexing.
     channels: HashMap<(SocketAddr, Option<NonZero<u32>>), OnChannel>,
+    /// The policy of outgoing packets for peers without a specific policy.
+    default_policy: PacketPolicy,
+    /// Specific policy of outgoing packets for some peers.
+    policies: HashMap<SocketAddr, PacketPolicy>,
+    /// Retransmission events waiting to be returned.
+    retransmit_events: VecDeque<RetransmitEvent>,
+    /// Timed out fragments errors waiting to be returned.
+    fragment_errors: VecDeque<(SocketAddr, FragmentError)>,
+    /// The last time timed out fragments have been checked.
+    fragment_check_time: Instant,
     // /// List of rejected packets.
     // rejected_packets: Vec<(SocketAddr, Packet, PacketRejectionError)>,
 }
@@ -40,6 +62,10 @@ struct ProtocolShared {
     last_accepted_prefix: u32,
     /// The current prefix offset being used for updating all packets' prefixes.
     prefix_offset: u32,
+    /// The protocol variant used to read and write packets and bundles.
+    variant: ProtocolVariant,
+    /// The retransmission policy of prepared reliable packets, disabled if none.
+    retransmit_policy: Option<RetransmitPolicy>,
 }
 
 impl Protocol {
@@ -50,18 +76,38 @@ impl Protocol {
                 off_seq_alloc: SeqAlloc::new(Seq::ZERO + 1),
                 last_accepted_prefix: 0,
                 prefix_offset: 0,
+                variant: ProtocolVariant::pc(),
+                retransmit_policy: None,
             },
             off_channels: HashMap::new(),
             channels: HashMap::new(),
+            default_policy: PacketPolicy::default(),
+            policies: HashMap::new(),
+            retransmit_events: VecDeque::new(),
+            fragment_errors: VecDeque::new(),
+            fragment_check_time: Instant::now(),
             // rejected_packets: Vec::new(),
         }
     }
 
+    /// The protocol variant, the PC client's one by default.
+    pub fn variant(&self) -> &ProtocolVariant {
+        &self.shared.variant
+    }
+
+    /// Set the protocol variant used to read and write packets, bundles returned by 
+    /// channels are also set to this variant so that their elements are translated.
+    pub fn set_variant(&mut self, variant: ProtocolVariant) {
+        self.shared.variant = variant;
+    }
+
     /// Return the off-channel handle, the off-channel isn't a real channel and bundles
     /// prepared with it will be sent out of channel, but this is used as a shared 
     /// interface with in-channel.
     pub fn off_channel(&mut self, addr: SocketAddr) -> Channel<'_> {
 
+        let policy = self.peer_policy(addr);
+        let cipher = self.cipher(addr);
         let channel = self.off_channels.entry(addr)
             .or_insert_with(|| OffChannel {
                 off: OffChannelData::new(),
@@ -70,6 +116,9 @@ impl Protocol {
         Channel {
             inner: GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher,
+                policy,
                 off: &mut channel.off,
                 on: None,
             }
@@ -82,23 +131,248 @@ impl Protocol {
     /// version of 1.
     pub fn channel(&mut self, addr: SocketAddr, index: Option<NonZero<u32>>) -> Channel<'_> {
 
+        let policy = self.peer_policy(addr);
+        let peer_cipher = self.cipher(addr);
         let channel = self.channels.entry((addr, index))
             .or_insert_with(|| OnChannel {
-                off: OffChannelData::new(),
+                off: OffChannelData::new_cumulative(),
                 on: match index {
                     None => OnChannelData::new_without_index(),
                     Some(index) => OnChannelData::new_with_index(index),
                 },
             });
+
+        let cipher = channel.on.cipher.clone().or(peer_cipher);
         
         Channel {
             inner: GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher,
+                policy,
                 off: &mut channel.off,
-                on: None,
+                on: Some(&mut channel.on),
+            }
+        }
+
+    }
+
+    /// Create a new channel associated with the given address, optionally indexed, 
+    /// and return a handle to it. All packets prepared on this channel have the create
+    /// channel flag until the peer sends a packet on it, which acknowledges that it 
+    /// also created the channel.
+    /// 
+    /// If the channel already exists, it is reset and its version is incremented if 
+    /// indexed, so that the peer also resets it.
+    pub fn create_channel(&mut self, addr: SocketAddr, index: Option<NonZero<u32>>) -> Channel<'_> {
+
+        let policy = self.peer_policy(addr);
+        let on = match (self.channels.get(&(addr, index)), index) {
+            (_, None) => OnChannelData::new_without_index(),
+            (None, Some(index)) => OnChannelData::new_with_index(index),
+            (Some(channel), Some(index)) => {
+                // Unwrap because the channel should have index.
+                let version = channel.on.index.unwrap().version;
+                OnChannelData::new_with_index_version(index, version.checked_add(1).unwrap_or(NonZero::<u32>::MIN))
+            }
+        };
+
+        trace!("Create channel: {addr} {:?}", on.index);
+        self.channels.insert((addr, index), OnChannel {
+            off: OffChannelData::new_cumulative(),
+            on: OnChannelData { creating: true, ..on },
+        });
+
+        // The peer cipher is forgotten when the non-indexed channel is created again.
+        let cipher = self.cipher(addr);
+
+        // Unwrap because it has just been inserted.
+        let channel = self.channels.get_mut(&(addr, index)).unwrap();
+
+        Channel {
+            inner: GenericChannel {
+                shared: &mut self.shared,
+                addr,
+                cipher,
+                policy,
+                off: &mut channel.off,
+                on: Some(&mut channel.on),
+            }
+        }
+
+    }
+
+    /// Close the channel associated with the given address and optional index, all of
+    /// its state is forgotten, including unacknowledged packets, buffered bundles and
+    /// its cipher. Return false if the channel did not exist.
+    pub fn close_channel(&mut self, addr: SocketAddr, index: Option<NonZero<u32>>) -> bool {
+        trace!("Close channel: {addr} {index:?}");
+        self.channels.remove(&(addr, index)).is_some()
+    }
+
+    /// Return the cipher of the given peer, this is the cipher of its non-indexed 
+    /// channel, used to encrypt and decrypt all packets exchanged with this peer that
+    /// are not on a channel with its own cipher.
+    pub fn cipher(&self, addr: SocketAddr) -> Option<Arc<dyn PacketCipher>> {
+        self.channels.get(&(addr, None))
+            .and_then(|channel| channel.on.cipher.clone())
+    }
+
+    /// Get the policy of outgoing packets used for peers without a specific policy.
+    #[inline]
+    pub fn default_policy(&self) -> PacketPolicy {
+        self.default_policy
+    }
+
+    /// Set the policy of outgoing packets used for peers without a specific policy.
+    #[inline]
+    pub fn set_default_policy(&mut self, policy: PacketPolicy) {
+        self.default_policy = policy;
+    }
+
+    /// Get the policy of outgoing packets to the given peer.
+    pub fn peer_policy(&self, addr: SocketAddr) -> PacketPolicy {
+        self.policies.get(&addr).copied().unwrap_or(self.default_policy)
+    }
+
+    /// Set a specific policy of outgoing packets to the given peer, none to use the 
+    /// default policy again.
+    pub fn set_peer_policy(&mut self, addr: SocketAddr, policy: Option<PacketPolicy>) {
+        match policy {
+            Some(policy) => self.policies.insert(addr, policy),
+            None => self.policies.remove(&addr),
+        };
+    }
+
+    /// Get the retransmission policy of reliable packets, none if disabled.
+    #[inline]
+    pub fn retransmit_policy(&self) -> Option<RetransmitPolicy> {
+        self.shared.retransmit_policy
+    }
+
+    /// Set the retransmission policy of reliable packets prepared by channels, none to
+    /// disable retransmission, which is the default. When enabled, the content of 
+    /// reliable packets is kept until they are acknowledged by the peer, and they must
+    /// be resent when returned by [`Self::poll_retransmit`].
+    /// 
+    /// Packets that have been prepared while disabled are never retransmitted, just 
+    /// like packets simulated with [`Self::accept_out`].
+    #[inline]
+    pub fn set_retransmit_policy(&mut self, policy: Option<RetransmitPolicy>) {
+        self.shared.retransmit_policy = policy;
+    }
+
+    /// Poll the next retransmission event at the given time, this should be called
+    /// regularly, with the help of [`Self::retransmit_timeout`], when retransmission
+    /// is enabled. Each returned packet to resend has been prepared already and should
+    /// be sent as-is to its peer.
+    pub fn poll_retransmit(&mut self, now: Instant) -> Option<RetransmitEvent> {
+
+        if self.retransmit_events.is_empty() {
+
+            let policy = self.shared.retransmit_policy?;
+
+            // Packets are resent with the cipher of their channel, or the peer cipher.
+            let peer_ciphers = self.channels.iter()
+                .filter(|(&(_, index), _)| index.is_none())
+                .filter_map(|(&(addr, _), channel)| Some((addr, channel.on.cipher.clone()?)))
+                .collect::<HashMap<_, _>>();
+            
+            let channels = self.off_channels.iter_mut()
+                .map(|(&addr, channel)| (addr, None, &mut channel.off))
+                .chain(self.channels.iter_mut()
+                    .map(|(&(addr, _), channel)| (addr, channel.on.cipher.as_ref(), &mut channel.off)));
+
+            for (addr, cipher, off) in channels {
+                let cipher = cipher.or_else(|| peer_ciphers.get(&addr));
+                off.retransmit(addr, cipher, now, policy, &mut self.retransmit_events);
+            }
+
+        }
+
+        self.retransmit_events.pop_front()
+
+    }
+
+    /// Get the duration to wait from the given time before the next retransmission is 
+    /// due, none if there is no packet to retransmit. This is never zero so that it 
+    /// can directly be used as a socket timeout.
+    pub fn retransmit_timeout(&self, now: Instant) -> Option<Duration> {
+
+        if !self.retransmit_events.is_empty() {
+            return Some(MIN_RETRANSMIT_TIMEOUT);
+        }
+
+        let policy = self.shared.retransmit_policy?;
+
+        self.off_channels.values().map(|channel| &channel.off)
+            .chain(self.channels.values().map(|channel| &channel.off))
+            .flat_map(|off| &off.out_reliable_packets)
+            .filter(|packet| packet.packet.is_some())
+            .map(|packet| (packet.time + policy.timeout).saturating_duration_since(now))
+            .min()
+            .map(|timeout| timeout.max(MIN_RETRANSMIT_TIMEOUT))
+
+    }
+
+    /// Poll the next bundle that has not been completed in time because some of its
+    /// fragments are missing, the incomplete bundle is dropped. This should be called 
+    /// regularly, incomplete bundles are otherwise kept until one of their fragments
+    /// is received again.
+    pub fn poll_fragment_timeout(&mut self, now: Instant) -> Option<(SocketAddr, FragmentError)> {
+
+        if self.fragment_errors.is_empty() && now.saturating_duration_since(self.fragment_check_time) >= FRAGMENT_CHECK_INTERVAL {
+
+            self.fragment_check_time = now;
+
+            let channels = self.off_channels.iter_mut()
+                .map(|(&addr, channel)| (addr, &mut channel.off))
+                .chain(self.channels.iter_mut()
+                    .map(|(&(addr, _), channel)| (addr, &mut channel.off)));
+
+            for (addr, off) in channels {
+                self.fragment_errors.extend(off.in_fragments.drain_expired(now)
+                    .into_iter()
+                    .map(|error| (addr, error)));
+            }
+
+        }
+
+        self.fragment_errors.pop_front()
+
+    }
+
+    /// Return memory usage statistics of the whole protocol tracker.
+    pub fn stats(&self) -> ProtocolStats {
+        self.collect_stats(|_| true)
+    }
+
+    /// Return memory usage statistics of the state kept for the given peer address.
+    pub fn peer_stats(&self, addr: SocketAddr) -> ProtocolStats {
+        self.collect_stats(|peer_addr| peer_addr == addr)
+    }
+
+    fn collect_stats(&self, filter: impl Fn(SocketAddr) -> bool) -> ProtocolStats {
+
+        let mut stats = ProtocolStats::default();
+
+        for (&addr, channel) in &self.off_channels {
+            if filter(addr) {
+                stats.off_channels += 1;
+                channel.off.add_stats(&mut stats);
+            }
+        }
+
+        for (&(addr, _), channel) in &self.channels {
+            if filter(addr) {
+                stats.channels += 1;
+                channel.off.add_stats(&mut stats);
+                channel.on.add_stats(&mut stats);
             }
         }
 
+        stats
+
     }
 
     /// Reset the prefix offset to zero.
@@ -127,17 +401,34 @@ impl Protocol {
     /// If the packet is rejected for any reason listed in [`PacketRejectionError`], none
     /// is also returned but the packet is internally queued and can later be retrieved 
     /// with the error using [`Self::take_rejected_packets()`].
+    /// 
+    /// If the peer has a cipher, see [`Self::cipher`], the packet is first decrypted 
+    /// with it, the flags being encrypted the packet cannot be decrypted with the 
+    /// cipher of its channel.
     #[instrument(name = "accept", level = "trace", skip(self, packet))]
     #[inline(always)]
     pub fn accept(&mut self, packet: Packet, addr: SocketAddr) -> Option<Channel<'_>> {
+        
+        let packet = match self.cipher(addr) {
+            Some(cipher) => match decrypt_packet(packet, &*cipher) {
+                Ok(packet) => packet,
+                Err(_packet) => {
+                    warn!("Failed to decrypt packet");
+                    return None;
+                }
+            }
+            None => packet,
+        };
+
         self.accept_inner(packet, addr)
+
     }
 
     /// Internal wrapper used to improve tracing of recursive span with piggybacks.
     fn accept_inner(&mut self, packet: Packet, addr: SocketAddr) -> Option<Channel<'_>> {
 
         let time = Instant::now();
-        let mut packet = match packet.read_config_locked() {
+        let mut packet = match packet.read_config_locked_with_layout(self.shared.variant.flag_layout()) {
             Ok(packet) => packet,
             Err((error, _packet)) => {
                 warn!("Failed to read config: {error}");
@@ -147,49 +438,40 @@ impl Protocol {
         };
 
         // Immediately process any piggyback packet, because they must have been 
-        // initially sent way before the current packet we are decoding.
+        // initially sent way before the current packet we are decoding. Piggybacks are
+        // usually resent reliable packets that we may have already received, so the 
+        // current packet is processed even if they complete no bundle, any completed 
+        // bundle is queued in its channel.
         for piggyback in std::mem::take(packet.piggybacks_mut()) {
             trace!("Processing piggyback packet: {piggyback:?}");
             let _span = trace_span!("pigb").entered();
-            self.accept_inner(piggyback, addr)?;
+            let _ = self.accept_inner(piggyback, addr);
         }
 
         self.shared.last_accepted_prefix = packet.packet().read_prefix();
 
         // Start by finding the appropriate channel for this packet regarding the local
         // socket address and channel-related flags on this packet.
+        let policy = self.peer_policy(addr);
+        let peer_cipher = self.cipher(addr);
         let mut channel;
         if packet.config().on_channel() {
 
-            let on_channel;
-            if let Some((index, version)) = packet.config().indexed_channel() {
-                
-                trace!("Is on-channel: {index} v{version}");
-                on_channel = self.channels.entry((addr, Some(index)))
-                    .or_insert_with(|| OnChannel {
-                        off: OffChannelData::new(),
-                        on: OnChannelData::new_with_index_version(index, version),
-                    });
-
-                // Unwrap because the channel should have index.
-                let current_version = on_channel.on.index.unwrap().version;
-                if version < current_version {
-                    trace!("Outdated, expected v{current_version}");
-                    // TODO: outdated packet
-                    return None;
-                }
+            let on_channel = Self::packet_on_channel(&mut self.channels, addr, packet.config().indexed_channel())?;
+            let cipher = on_channel.on.cipher.clone().or(peer_cipher);
 
-            } else {
-                trace!("Is on-channel: not indexed");
-                on_channel = self.channels.entry((addr, None))
-                    .or_insert_with(|| OnChannel {
-                        off: OffChannelData::new(),
-                        on: OnChannelData::new_without_index(),
-                    });
+            // Any packet from the peer on this channel means that it has been created
+            // by the peer, so we no longer need to request its creation.
+            if on_channel.on.creating {
+                trace!("Channel created by peer");
+                on_channel.on.creating = false;
             }
 
             channel = GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher,
+                policy,
                 off: &mut on_channel.off,
                 on: Some(&mut on_channel.on),
             };
@@ -202,6 +484,9 @@ impl Protocol {
 
             channel = GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher: peer_cipher,
+                policy,
                 off: &mut off_channel.off,
                 on: None,
             };
@@ -235,12 +520,18 @@ impl Protocol {
                 return None;
             }
 
-            channel.off.add_in_reliable_packet(packet.config().sequence_num());
+            // Duplicate packets are acknowledged again, because the peer may have 
+            // lost our previous ack, but they must not be processed twice.
+            if !channel.off.in_reliable.receive(packet.config().sequence_num()) {
+                return Some(Channel { inner: channel });
+            }
 
-            // When on-channel with reliable packets, we must track the cumulative ack
-            // and buffer any packet that is received out-of-order!
+            // When on-channel with reliable packets, we must buffer any packet that is
+            // received out-of-order until the cumulative ack reaches it!
             if let Some(on) = channel.on.as_deref_mut() {
-                on.add_in_reliable_packet(packet);
+                let cumulative_ack = channel.off.in_reliable.cumulative_ack()
+                    .expect("on-channel should track cumulative ack");
+                on.add_in_reliable_packet(packet, cumulative_ack);
                 while let Some(bundle) = on.pop_in_reliable_bundle() {
                     channel.off.in_bundles.push_back(bundle);
                 }
@@ -250,12 +541,12 @@ impl Protocol {
 
         } else if let Some(last_reliable_sequence_num) = packet.config().last_reliable_sequence_num() {
 
-            // In this case we must ensure that current expected sequence is equal to
-            // this given sequence number + 1.
-            if let Some(on) = channel.on.as_deref_mut() {
-                if last_reliable_sequence_num != on.in_reliable_expected_seq - 1 {
+            // In this case we must ensure that current cumulative ack is equal to
+            // this given sequence number + 1, it's only tracked on-channel.
+            if let Some(cumulative_ack) = channel.off.in_reliable.cumulative_ack() {
+                if last_reliable_sequence_num != cumulative_ack - 1 {
                     warn!("Invalid last reliable sequence number, expected: {}, got: {}",
-                        on.in_reliable_expected_seq - 1, last_reliable_sequence_num);
+                        cumulative_ack - 1, last_reliable_sequence_num);
                     return None;
                 }
             } else {
@@ -284,7 +575,7 @@ impl Protocol {
     pub fn accept_out(&mut self, packet: &Packet, addr: SocketAddr) -> bool {
 
         let time = Instant::now();
-        let locked = match packet.read_config_locked_ref() {
+        let locked = match packet.read_config_locked_ref_with_layout(self.shared.variant.flag_layout()) {
             Ok(locked) => locked,
             Err(error) => {
                 warn!("Failed to read config: {error}");
@@ -292,28 +583,28 @@ impl Protocol {
             }
         };
 
+        // Piggybacks are processed like in accept, their acks and reliability must be
+        // tracked too, but they never reject the current packet.
+        for piggyback in locked.config().piggybacks() {
+            let _span = trace_span!("pigb").entered();
+            self.accept_out(piggyback, addr);
+        }
+
+        let policy = self.peer_policy(addr);
+        let peer_cipher = self.cipher(addr);
         let channel;
         if locked.config().on_channel() {
 
-            let on_channel;
-            if let Some((index, version)) = locked.config().indexed_channel() {
-                trace!("Is on-channel: {index} v{version}");
-                on_channel = self.channels.entry((addr, Some(index)))
-                    .or_insert_with(|| OnChannel {
-                        off: OffChannelData::new(),
-                        on: OnChannelData::new_with_index_version(index, version),
-                    });
-            } else {
-                trace!("Is on-channel: not indexed");
-                on_channel = self.channels.entry((addr, None))
-                    .or_insert_with(|| OnChannel {
-                        off: OffChannelData::new(),
-                        on: OnChannelData::new_without_index(),
-                    });
-            }
-
+            let Some(on_channel) = Self::packet_on_channel(&mut self.channels, addr, locked.config().indexed_channel()) else {
+                return false;
+            };
+            let cipher = on_channel.on.cipher.clone().or(peer_cipher);
+            
             channel = GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher,
+                policy,
                 off: &mut on_channel.off,
                 on: Some(&mut on_channel.on),
             };
@@ -326,6 +617,9 @@ impl Protocol {
 
             channel = GenericChannel {
                 shared: &mut self.shared,
+                addr,
+                cipher: peer_cipher,
+                policy,
                 off: &mut off_channel.off,
                 on: None,
             };
@@ -334,7 +628,7 @@ impl Protocol {
 
         if let Some(cumulative_ack) = locked.config().cumulative_ack() {
             if channel.on.is_some() {
-                channel.off.ack_in_reliable_packet_cumulative(cumulative_ack);
+                channel.off.in_reliable.ack_cumulative(cumulative_ack);
             } else {
                 warn!("Cumulative ack is not supported off-channel");
                 return false;
@@ -342,7 +636,7 @@ impl Protocol {
         }
 
         for &single_ack in locked.config().single_acks() {
-            channel.off.ack_in_reliable_packet(single_ack);
+            channel.off.in_reliable.ack(single_ack);
         }
 
         if locked.config().reliable() {
@@ -353,6 +647,53 @@ impl Protocol {
 
     }
 
+    /// Internal function to get the channel of an on-channel packet, given its optional
+    /// indexed channel, the channel is created if not existing. An indexed channel is
+    /// reset if the packet has a newer version, because the peer has recreated the 
+    /// channel, and none is returned if the packet has an outdated version.
+    fn packet_on_channel(
+        channels: &mut HashMap<(SocketAddr, Option<NonZero<u32>>), OnChannel>,
+        addr: SocketAddr,
+        indexed_channel: Option<(NonZero<u32>, NonZero<u32>)>,
+    ) -> Option<&mut OnChannel> {
+
+        let Some((index, version)) = indexed_channel else {
+            trace!("Is on-channel: not indexed");
+            return Some(channels.entry((addr, None))
+                .or_insert_with(|| OnChannel {
+                    off: OffChannelData::new_cumulative(),
+                    on: OnChannelData::new_without_index(),
+                }));
+        };
+
+        trace!("Is on-channel: {index} v{version}");
+        let new_channel = || OnChannel {
+            off: OffChannelData::new_cumulative(),
+            on: OnChannelData::new_with_index_version(index, version),
+        };
+
+        let on_channel = match channels.entry((addr, Some(index))) {
+            hash_map::Entry::Occupied(o) => o.into_mut(),
+            hash_map::Entry::Vacant(v) => return Some(v.insert(new_channel())),
+        };
+
+        // Unwrap because the channel should have index.
+        let current_version = on_channel.on.index.unwrap().version;
+        match version.cmp(&current_version) {
+            Ordering::Less => {
+                trace!("Outdated, expected v{current_version}");
+                None
+            }
+            Ordering::Equal => Some(on_channel),
+            Ordering::Greater => {
+                trace!("Newer version, reset from v{current_version}");
+                *on_channel = new_channel();
+                Some(on_channel)
+            }
+        }
+
+    }
+
 }
 
 /// A handle to a channel or to the special off-channel fake channel.
@@ -360,7 +701,7 @@ impl Protocol {
 /// Represent a channel between the app and a client with specific socket address. A 
 /// channel is a way to create multiple communication channels between the same pair
 /// of addresses and also provides support for reliable communication with sequence
-/// number acks and resend logic (not yet implemented).
+/// number acks and resend logic (see [`Protocol::set_retransmit_policy`]).
 #[derive(Debug)]
 pub struct Channel<'a> {
     inner: GenericChannel<'a>,
@@ -380,10 +721,48 @@ impl Channel<'_> {
         self.inner.on.as_deref().and_then(|on| on.index)
     }
 
+    /// Return true if this channel has been created with [`Protocol::create_channel`]
+    /// and the peer has not yet sent any packet on it.
+    pub fn is_creating(&self) -> bool {
+        self.inner.on.as_deref().is_some_and(|on| on.creating)
+    }
+
+    /// Return the address of the peer of this channel.
+    #[inline]
+    pub fn addr(&self) -> SocketAddr {
+        self.inner.addr
+    }
+
+    /// Return the cipher used to encrypt packets sent on this channel, this is the
+    /// cipher of the channel if set, or the cipher of the peer, see [`Protocol::cipher`].
+    #[inline]
+    pub fn cipher(&self) -> Option<&Arc<dyn PacketCipher>> {
+        self.inner.cipher.as_ref()
+    }
+
+    /// Set the cipher of this channel, this has no effect off-channel. When set on the
+    /// non-indexed channel, this becomes the cipher of the peer and it is used to
+    /// decrypt all packets received from it, and to encrypt all packets sent to it.
+    pub fn set_cipher(&mut self, cipher: Option<Arc<dyn PacketCipher>>) {
+        if let Some(on) = self.inner.on.as_deref_mut() {
+            on.cipher = cipher.clone();
+            self.inner.cipher = cipher;
+        }
+    }
+
+    /// Prepare the bundle, see [`Self::prepare`], and send it to the peer of this
+    /// channel on the given socket, encrypted with the cipher of this channel if any.
+    pub fn send(&mut self, socket: &PacketSocket, bundle: &mut Bundle, reliable: bool) -> io::Result<usize> {
+        self.prepare(bundle, reliable);
+        socket.send_bundle_with_cipher(bundle, self.inner.addr, self.inner.cipher.as_deref())
+    }
+
     /// Pop the next bundle able to be received, if any, this ensures that bundles are
     /// received in the correct order!
     pub fn next_bundle(&mut self) -> Option<Bundle> {
-        self.inner.off.in_bundles.pop_front()
+        let mut bundle = self.inner.off.in_bundles.pop_front()?;
+        bundle.set_variant(&self.inner.shared.variant);
+        Some(bundle)
     }
 
     /// Prepare a bundle to be sent, adding acks and other configuration required by this
@@ -398,12 +777,18 @@ impl Channel<'_> {
         let time = Instant::now();
         let bundle_len = bundle.len() as u32;
         trace!("Count: {bundle_len}");
+
+        let policy = self.inner.policy;
+        let reliable = policy.reliable.unwrap_or(reliable);
         
         // Create a common packet config for all the bundle.
         let mut packet_config = PacketConfig::new();
 
+        packet_config.set_flag_layout(self.inner.shared.variant.flag_layout());
         packet_config.set_reliable(reliable);
+        packet_config.set_has_checksum(policy.checksum);
 
+        let mut retransmit = false;
         if bundle_len > 1 || reliable {
             let sequence_num = self.inner.alloc_sequence_num(bundle_len, reliable);
             trace!("Allocated sequence numbers: {}..{}", sequence_num, sequence_num + bundle_len);
@@ -412,12 +797,16 @@ impl Channel<'_> {
                 for i in 0..bundle_len {
                     self.inner.off.add_out_reliable_packet(sequence_num + i, time);
                 }
+                retransmit = self.inner.shared.retransmit_policy.is_some();
             }
         }
         
         if let Some(on) = self.inner.on.as_deref_mut() {
             packet_config.set_on_channel(true);
-            packet_config.set_cumulative_ack(on.in_reliable_expected_seq);
+            packet_config.set_create_channel(on.creating);
+            if let Some(cumulative_ack) = self.inner.off.in_reliable.cumulative_ack() {
+                packet_config.set_cumulative_ack(cumulative_ack);
+            }
             if let Some(index) = on.index {
                 packet_config.set_indexed_channel(index.index, index.version);
                 trace!("Is on-channel: {} v{}", index.index, index.version);
@@ -428,8 +817,12 @@ impl Channel<'_> {
             trace!("Is off-channel");
         }
 
-        if !self.inner.off.in_reliable_packets.is_empty() {
-            trace!("Pending single acks: {:?}", self.inner.off.in_reliable_packets);
+        if let Some(on_channel) = policy.on_channel {
+            packet_config.set_on_channel(on_channel);
+        }
+
+        if !self.inner.off.in_reliable.pending_acks.is_empty() {
+            trace!("Pending single acks: {:?}", self.inner.off.in_reliable.pending_acks);
         }
         
         trace!("Using prefix offset: 0x{:08X}", self.inner.shared.prefix_offset);
@@ -438,24 +831,91 @@ impl Channel<'_> {
         // and their sequence numbers into the packet config's acks queue. We must 
         // remember after this to transfer back the remaining sequence numbers that
         // have not been sent from the packet config.
-        std::mem::swap(&mut self.inner.off.in_reliable_packets, packet_config.single_acks_mut());
-        debug_assert!(self.inner.off.in_reliable_packets.is_empty(), "packet config acks were not empty");
+        std::mem::swap(&mut self.inner.off.in_reliable.pending_acks, packet_config.single_acks_mut());
+        debug_assert!(self.inner.off.in_reliable.pending_acks.is_empty(), "packet config acks were not empty");
 
         bundle.write_config(&mut packet_config);
         bundle.update_prefix(self.inner.shared.prefix_offset);
 
         // Now we need to restore acks that have not been sent: swap back (read above).
-        std::mem::swap(&mut self.inner.off.in_reliable_packets, packet_config.single_acks_mut());
+        std::mem::swap(&mut self.inner.off.in_reliable.pending_acks, packet_config.single_acks_mut());
         debug_assert!(packet_config.single_acks().is_empty(), "packet config acks should be empty");
 
-        if !self.inner.off.in_reliable_packets.is_empty() {
-            trace!("Remaining single acks: {:?}", self.inner.off.in_reliable_packets)
+        if !self.inner.off.in_reliable.pending_acks.is_empty() {
+            trace!("Remaining single acks: {:?}", self.inner.off.in_reliable.pending_acks)
+        }
+
+        // The packets are now ready to be sent, keep them for retransmission, they are
+        // the last reliable packets added.
+        if retransmit {
+            let out_reliable_packets = &mut self.inner.off.out_reliable_packets;
+            let first_index = out_reliable_packets.len() - bundle_len as usize;
+            for (out_packet, packet) in out_reliable_packets[first_index..].iter_mut().zip(bundle.iter()) {
+                out_packet.packet = Some(packet.clone());
+            }
         }
 
     }
 
 }
 
+/// The policy of flags set on outgoing packets to a peer, this can be used to match the
+/// conventions expected by the remote peer. The default policy doesn't force anything
+/// and adds no checksum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketPolicy {
+    /// Add a checksum to all outgoing packets.
+    pub checksum: bool,
+    /// Force the reliability of all outgoing bundles, instead of the one requested when
+    /// preparing the bundle.
+    pub reliable: Option<bool>,
+    /// Force the on-channel flag on all outgoing packets, instead of depending on the 
+    /// channel being used.
+    pub on_channel: Option<bool>,
+}
+
+/// The policy of retransmission of reliable packets that are not acknowledged by their 
+/// peer, see [`Protocol::set_retransmit_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetransmitPolicy {
+    /// Duration to wait for an acknowledgment before resending a packet.
+    pub timeout: Duration,
+    /// Maximum number of times a packet is resent, if the last resend isn't 
+    /// acknowledged before the timeout, the packet is reported as failed.
+    pub max_retries: u32,
+}
+
+impl Default for RetransmitPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            max_retries: 5,
+        }
+    }
+}
+
+/// A retransmission event returned by [`Protocol::poll_retransmit`].
+#[derive(Debug)]
+pub enum RetransmitEvent {
+    /// A reliable packet has not been acknowledged in time and must be resent as-is.
+    Resend {
+        /// Address of the peer to resend the packet to.
+        addr: SocketAddr,
+        /// The packet to resend.
+        packet: Packet,
+        /// The cipher of the channel the packet has been prepared on, to encrypt the 
+        /// packet with, see [`Channel::cipher`].
+        cipher: Option<Arc<dyn PacketCipher>>,
+    },
+    /// A reliable packet has not been acknowledged after all retries, it's forgotten.
+    Failed {
+        /// Address of the peer the packet was sent to.
+        addr: SocketAddr,
+        /// Sequence number of the packet.
+        sequence_num: Seq,
+    },
+}
+
 /// Documented from BigWorld source code (programming\bigworld\lib\network\udp_channel.hpp).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ChannelIndex {
@@ -474,6 +934,28 @@ pub struct ChannelIndex {
     pub version: NonZero<u32>,
 }
 
+/// Memory usage statistics of a protocol tracker, this can be used to detect leaks of
+/// peer states in long-running applications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolStats {
+    /// Number of off-channels, one for each peer address.
+    pub off_channels: usize,
+    /// Number of on-channels, indexed or not.
+    pub channels: usize,
+    /// Number of bundles being reassembled from their fragments.
+    pub fragmented_bundles: usize,
+    /// Number of fragment packets buffered for reassembling bundles.
+    pub buffered_fragments: usize,
+    /// Number of reliable packets received out of order and buffered on channels.
+    pub buffered_reliable_packets: usize,
+    /// Number of sent reliable packets waiting for an acknowledgment.
+    pub unacked_packets: usize,
+    /// Number of received reliable packets waiting to be acknowledged.
+    pub pending_acks: usize,
+    /// Number of completed bundles waiting to be retrieved.
+    pub buffered_bundles: usize,
+}
+
 ///  Kind of error that caused a packet to be rejected from this socket and not received.
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum PacketRejectionError {
@@ -486,17 +968,157 @@ pub enum PacketRejectionError {
     Config(#[from] PacketConfigError),
 }
 
+/// A tracker of the reliable packets received on a channel, recording their sequence
+/// numbers in order to generate the acks to send back to the peer and to detect
+/// duplicates, which are common because the peer resends any reliable packet it 
+/// doesn't know to be acknowledged.
+/// 
+/// When tracking the cumulative ack (on-channel), all sequence numbers before it have
+/// been received and those received out-of-order are remembered until the gap is 
+/// filled, this is the same as `inSeqAt_` in BW source. Without cumulative ack 
+/// (off-channel), only a bounded window of the last received sequence numbers is 
+/// remembered to detect duplicates.
+#[derive(Debug, Clone)]
+pub struct ReliabilityTracker {
+    /// The first sequence number not yet received, if the cumulative ack is tracked.
+    cumulative_ack: Option<Seq>,
+    /// Received sequence numbers that are not covered by the cumulative ack.
+    received: VecDeque<Seq>,
+    /// Maximum number of received sequence numbers remembered without cumulative ack.
+    window: usize,
+    /// Single acks of received packets that should be sent back, unsorted.
+    pending_acks: VecDeque<Seq>,
+}
+
+impl ReliabilityTracker {
+
+    /// Create a tracker without cumulative ack, remembering a default window of 
+    /// received sequence numbers.
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_DUPLICATE_WINDOW)
+    }
+
+    /// Create a tracker without cumulative ack, remembering the given number of 
+    /// received sequence numbers to detect duplicates.
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            cumulative_ack: None,
+            received: VecDeque::new(),
+            window,
+            pending_acks: VecDeque::new(),
+        }
+    }
+
+    /// Create a tracker of the cumulative ack, starting at the given first sequence 
+    /// number expected from the peer.
+    pub fn new_cumulative(first_seq: Seq) -> Self {
+        Self {
+            cumulative_ack: Some(first_seq),
+            received: VecDeque::new(),
+            window: DEFAULT_DUPLICATE_WINDOW,
+            pending_acks: VecDeque::new(),
+        }
+    }
+
+    /// The cumulative ack to send back, all sequence numbers before (excluding) it 
+    /// have been received, none if not tracked.
+    #[inline]
+    pub fn cumulative_ack(&self) -> Option<Seq> {
+        self.cumulative_ack
+    }
+
+    /// The single acks that should be sent back, unsorted.
+    #[inline]
+    pub fn pending_acks(&self) -> &VecDeque<Seq> {
+        &self.pending_acks
+    }
+
+    /// Take all the single acks that should be sent back, they are considered sent.
+    pub fn take_pending_acks(&mut self) -> VecDeque<Seq> {
+        std::mem::take(&mut self.pending_acks)
+    }
+
+    /// Return true if the given sequence number has already been received.
+    pub fn is_duplicate(&self, sequence_num: Seq) -> bool {
+        if let Some(cumulative_ack) = self.cumulative_ack {
+            if sequence_num.wrapping_cmp(cumulative_ack).is_lt() {
+                return true;
+            }
+        }
+        self.received.contains(&sequence_num)
+    }
+
+    /// Record a received reliable packet with the given sequence number, returning 
+    /// false if it's a duplicate that should not be processed. A single ack is queued
+    /// in both cases, because a duplicate means that the peer lost our ack.
+    pub fn receive(&mut self, sequence_num: Seq) -> bool {
+
+        if !self.pending_acks.contains(&sequence_num) {
+            self.pending_acks.push_back(sequence_num);
+        }
+
+        if self.is_duplicate(sequence_num) {
+            trace!("Duplicate reliable packet: {sequence_num}");
+            return false;
+        }
+
+        trace!("Received reliable packet: {sequence_num}");
+
+        if let Some(cumulative_ack) = &mut self.cumulative_ack {
+            if sequence_num == *cumulative_ack {
+                // Received in-order, it may fill a gap with previously received ones.
+                *cumulative_ack += 1;
+                while let Some(pos) = self.received.iter().position(|&num| num == *cumulative_ack) {
+                    self.received.remove(pos);
+                    *cumulative_ack += 1;
+                }
+            } else {
+                self.received.push_back(sequence_num);
+            }
+        } else {
+            self.received.push_back(sequence_num);
+            if self.received.len() > self.window {
+                self.received.pop_front();
+            }
+        }
+
+        true
+
+    }
+
+    /// Forget the single ack of the given sequence number, because it has been sent by
+    /// other means, this is used to simulate outgoing packets.
+    pub fn ack(&mut self, sequence_num: Seq) {
+        // Swap remove because order don't matter.
+        if let Some(pos) = self.pending_acks.iter().position(|&num| num == sequence_num) {
+            self.pending_acks.swap_remove_back(pos);
+        }
+    }
+
+    /// Forget all single acks before (excluding) the given cumulative ack, because it
+    /// has been sent by other means, this is used to simulate outgoing packets.
+    pub fn ack_cumulative(&mut self, sequence_num: Seq) {
+        self.pending_acks.retain(|&num| num.wrapping_cmp(sequence_num).is_ge());
+    }
+
+}
+
+impl Default for ReliabilityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Common data for off-channel and on-channel communication.
 #[derive(Debug)]
 struct OffChannelData {
     /// All sequences marked as reliable, so that we can ensure that they are received.
     /// It should be naturally sorted (debug_asserted).
     out_reliable_packets: Vec<OutReliablePacket>,
-    /// A dequeue containing all received reliable packets for which we should send ack.
-    /// It doesn't need to be sorted.
-    in_reliable_packets: VecDeque<Seq>,
-    /// Bundle fragments tracking, mapped to the first sequence.
-    in_fragments: HashMap<Seq, Fragments>,
+    /// Tracking of received reliable packets, for acks and duplicates.
+    in_reliable: ReliabilityTracker,
+    /// Bundle fragments tracking.
+    in_fragments: FragmentAssembler,
     /// Buffered bundles that can be retrieved by the client!
     in_bundles: VecDeque<Bundle>,
 }
@@ -506,23 +1128,44 @@ struct OffChannelData {
 struct OutReliablePacket {
     /// The sequence number.
     sequence_num: Seq,
-    /// The time this sequence has been sent.
+    /// The time this sequence has been sent, or resent.
     time: Instant,
+    /// The prepared packet kept for retransmission, if enabled when preparing it.
+    packet: Option<Packet>,
+    /// Number of times this packet has been resent.
+    retries: u32,
 }
 
 impl OffChannelData {
 
-    fn new() -> Self {
+    fn new_with_reliable(in_reliable: ReliabilityTracker) -> Self {
         Self {
             out_reliable_packets: Vec::new(),
-            in_reliable_packets: VecDeque::new(),
-            in_fragments: HashMap::new(),
+            in_reliable,
+            in_fragments: FragmentAssembler::new(FRAGMENT_TIMEOUT),
             in_bundles: VecDeque::new(),
         }
     }
 
-    /// TODO: We'll also need to automatically resend the packet's content after some 
-    /// time.
+    fn new() -> Self {
+        Self::new_with_reliable(ReliabilityTracker::new())
+    }
+
+    /// Used on-channel, where the cumulative ack is tracked.
+    fn new_cumulative() -> Self {
+        Self::new_with_reliable(ReliabilityTracker::new_cumulative(Seq::ZERO))
+    }
+
+    fn add_stats(&self, stats: &mut ProtocolStats) {
+        stats.fragmented_bundles += self.in_fragments.chains_count();
+        stats.buffered_fragments += self.in_fragments.fragments_count();
+        stats.unacked_packets += self.out_reliable_packets.len();
+        stats.pending_acks += self.in_reliable.pending_acks.len();
+        stats.buffered_bundles += self.in_bundles.len();
+    }
+
+    /// Register a reliable packet we are sending, the packet's content can be attached
+    /// afterward for retransmission.
     fn add_out_reliable_packet(&mut self, sequence_num: Seq, time: Instant) {
     
         // We are keeping reliable packets ordered by their sequence number and also by
@@ -536,6 +1179,8 @@ impl OffChannelData {
         self.out_reliable_packets.push(OutReliablePacket {
             sequence_num,
             time,
+            packet: None,
+            retries: 0,
         });
 
     }
@@ -563,10 +1208,40 @@ impl OffChannelData {
         self.out_reliable_packets.insert(insert_index, OutReliablePacket {
             sequence_num,
             time,
+            packet: None,
+            retries: 0,
         });
 
     }
 
+    /// Push retransmission events for all reliable packets that have not been 
+    /// acknowledged in time, packets without content are never retransmitted.
+    fn retransmit(&mut self, addr: SocketAddr, cipher: Option<&Arc<dyn PacketCipher>>, now: Instant, policy: RetransmitPolicy, events: &mut VecDeque<RetransmitEvent>) {
+        self.out_reliable_packets.retain_mut(|out_packet| {
+
+            let Some(packet) = &out_packet.packet else {
+                return true;
+            };
+
+            if now.saturating_duration_since(out_packet.time) < policy.timeout {
+                return true;
+            }
+
+            if out_packet.retries >= policy.max_retries {
+                warn!("Reliable packet not acknowledged after {} retries: {}", out_packet.retries, out_packet.sequence_num);
+                events.push_back(RetransmitEvent::Failed { addr, sequence_num: out_packet.sequence_num });
+                return false;
+            }
+
+            trace!("Resend reliable packet: {}", out_packet.sequence_num);
+            out_packet.retries += 1;
+            out_packet.time = now;
+            events.push_back(RetransmitEvent::Resend { addr, packet: packet.clone(), cipher: cipher.cloned() });
+            true
+
+        });
+    }
+
     /// When a single ack is received on a packet, this can be called to 
     fn ack_out_reliable_packet(&mut self, sequence_num: Seq) {
 
@@ -600,75 +1275,21 @@ impl OffChannelData {
 
     }
 
-    /// Register a simple reliable packet to be acknowledged in the future.
-    fn add_in_reliable_packet(&mut self, sequence_num: Seq) {
-        // It's unsorted so we don't care of duplicate entries!
-        self.in_reliable_packets.push_back(sequence_num);
-        trace!("Received reliable packet: {sequence_num}");
-    }
-
-    /// Force acknowledgement of a received reliable packet, used with `accept_out` only.
-    fn ack_in_reliable_packet(&mut self, sequence_num: Seq) {
-        // Swap remove because order don't matter.
-        if let Some(pos) = self.in_reliable_packets.iter().position(|&num| num == sequence_num) {
-            self.in_reliable_packets.swap_remove_back(pos);
-        }
-    }
-    
-    /// For proxy and accept_out...
-    fn ack_in_reliable_packet_cumulative(&mut self, sequence_num: Seq) {
-        // Same logic as above in 'pop_received_reliable_packet_cumulative'.
-        self.in_reliable_packets.retain(|&num| num.wrapping_cmp(sequence_num).is_ge());
-    }
-
     /// Push a packet that may be a bundle's fragment, if a bundle is completed, it is 
     /// added to the internal bundles queue, there is no ordering guaranteed with such
     /// packet, see [`OnChannelData::add_in_reliable_packet`] for reordering.
     fn add_in_packet(&mut self, packet: PacketLocked, time: Instant) {
 
-        let bundle = match packet.config().sequence_range() {
-            Some((first_seq, last_seq)) => {
-
-                let relative_num = packet.config().sequence_num() - first_seq;
-                trace!("Fragment: {} ({}..={})", 
-                    packet.config().sequence_num(), first_seq.get(), last_seq.get());
-
-                match self.in_fragments.entry(first_seq) {
-                    hash_map::Entry::Occupied(mut o) => {
-
-                        // If this fragments is too old, timeout every packet in it
-                        // and start again with the packet.
-                        // FIXME: Maybe dumb?
-                        if o.get().is_old(time, FRAGMENT_TIMEOUT) {
-                            // let mut fragments = o.remove();
-                            // self.rejected_packets.extend(fragments.drain()
-                            //     .map(|packet| (addr, packet, PacketRejectionError::TimedOut)));
-                            return;
-                        }
-
-                        o.get_mut().set(relative_num, packet);
-
-                        // When all fragments are collected, remove entry and return.
-                        if !o.get().is_full() {
-                            return;
-                        }
-
-                        o.remove().into_bundle()
-
-                    },
-                    hash_map::Entry::Vacant(v) => {
-                        let mut fragments = Fragments::new(last_seq - first_seq + 1);
-                        fragments.set(relative_num, packet);
-                        v.insert(fragments);
-                        return;
-                    }
-                }
-
-            }
-            _ => Bundle::new_with_single(packet)
-        };
+        if let Some((first_seq, last_seq)) = packet.config().sequence_range() {
+            trace!("Fragment: {} ({}..={})", 
+                packet.config().sequence_num(), first_seq.get(), last_seq.get());
+        }
 
-        self.in_bundles.push_back(bundle);
+        match self.in_fragments.push(packet, time) {
+            Ok(Some(bundle)) => self.in_bundles.push_back(bundle),
+            Ok(None) => {}
+            Err(error) => warn!("Failed to assemble fragment: {error}"),
+        }
 
     }
 
@@ -682,14 +1303,17 @@ struct OnChannelData {
     /// The next sequence number to return for this channel, **only for reliable 
     /// packets**, non-reliable packets sent on-channel are using the off-channel .
     seq_alloc: SeqAlloc,
-    /// Most of the time, reliable sequence numbers are received in order, and so we can
-    /// just increment this counter in order to know that all packets up to (but 
-    /// excluding) this number has been received.
-    /// 
-    /// NOTE: This is the same as `inSeqAt_` in BW source.
-    in_reliable_expected_seq: Seq,
+    /// True when the channel has been created locally and the peer should create it,
+    /// the create channel flag is set on prepared packets until the peer answers.
+    creating: bool,
+    /// Reliable packets before the cumulative ack of the channel, therefore contiguous,
+    /// that have not yet been popped as bundles.
     in_reliable_contiguous_packets: VecDeque<PacketLocked>,
+    /// Reliable packets received out-of-order after the cumulative ack, sorted.
     in_reliable_packets: VecDeque<PacketLocked>,
+    /// The cipher used to encrypt packets sent on this channel, for the non-indexed 
+    /// channel this is also used to decrypt all packets received from the peer.
+    cipher: Option<Arc<dyn PacketCipher>>,
 }
 
 impl OnChannelData {
@@ -698,9 +1322,10 @@ impl OnChannelData {
         Self {
             index,
             seq_alloc: SeqAlloc::new(Seq::ZERO),
-            in_reliable_expected_seq: Seq::ZERO,
+            creating: false,
             in_reliable_contiguous_packets: VecDeque::new(),
             in_reliable_packets: VecDeque::new(),
+            cipher: None,
         }
     }
 
@@ -708,6 +1333,10 @@ impl OnChannelData {
         Self::new(None)
     }
 
+    fn add_stats(&self, stats: &mut ProtocolStats) {
+        stats.buffered_reliable_packets += self.in_reliable_contiguous_packets.len() + self.in_reliable_packets.len();
+    }
+
     fn new_with_index_version(index: NonZero<u32>, version: NonZero<u32>) -> Self {
         Self::new(Some(ChannelIndex { index, version }))
     }
@@ -718,75 +1347,52 @@ impl OnChannelData {
 
     /// Add a received (in) reliable packet to the internal re-ordering logic of this 
     /// channel, this will automatically construct an ordered bundle when completed.
-    /// This also updates the cumulative ack that can be sent back.
+    /// The packet should not be a duplicate, and the given cumulative ack should be 
+    /// the channel's one after receiving it: any buffered packet before it is now
+    /// contiguous.
     /// 
     /// After this function has filled contiguous and buffered packets, you may want to
     /// user [`Self::pop_in_reliable_bundle`] to pop any completed contiguous bundle.
-    fn add_in_reliable_packet(&mut self, packet: PacketLocked) {
+    fn add_in_reliable_packet(&mut self, packet: PacketLocked, cumulative_ack: Seq) {
 
         debug_assert!(packet.config().reliable(), "given packet should be reliable");
 
         let sequence_num = packet.config().sequence_num();
 
-        match sequence_num.wrapping_cmp(self.in_reliable_expected_seq) {
-            Ordering::Equal => {
-
-                // This is the best scenario, packet is received in-order, so we push the
-                // packet after the currently contiguous sequence.
-                self.in_reliable_expected_seq += 1;
-                self.in_reliable_contiguous_packets.push_back(packet);
-
-                // By inserting this packet, we may a filled a gap in the sequences, so
-                // we increment it while it's possible.
-                while let Some(packet) = self.in_reliable_packets.front() {
-                    if packet.config().sequence_num() == self.in_reliable_expected_seq {
-                        trace!("Unbuffered reliable packet: {}", packet.config().sequence_num());
-                        self.in_reliable_expected_seq += 1;
-                        self.in_reliable_contiguous_packets.push_back(self.in_reliable_packets.pop_front().unwrap());
-                    } else {
-                        break;  // Not contiguous.
-                    }
-                }
-
-            }
-            Ordering::Less => {
-                // Do nothing, the sequence number may have been already received...
-            }
-            Ordering::Greater => {
-
-                // Warning if we get many buffered packets which indicate that we probably
-                // lost track of one of the 
-                if self.in_reliable_packets.len() > 50 {
-                    warn!("Buffered too many in reliable packets: {}", self.in_reliable_packets.len());
-                }
+        // Warning if we get many buffered packets which indicate that we probably
+        // lost track of one of the sequence numbers.
+        if self.in_reliable_packets.len() > 50 {
+            warn!("Buffered too many in reliable packets: {}", self.in_reliable_packets.len());
+        }
 
-                // We search where we can insert the packet, starting from the end because
-                // it's still likely to receive packets in order.
-                let mut insert_index = 0;
-                for (i, buffered_packet) in self.in_reliable_packets.iter().enumerate().rev() {
-                    match sequence_num.wrapping_cmp(buffered_packet.config().sequence_num()) {
-                        Ordering::Equal => return,  // Duplicate packet, just abort.
-                        Ordering::Less => continue,
-                        Ordering::Greater => {
-                            insert_index = i + 1;
-                            break;
-                        }
-                    }
+        // We search where we can insert the packet, starting from the end because
+        // it's still likely to receive packets in order.
+        let mut insert_index = 0;
+        for (i, buffered_packet) in self.in_reliable_packets.iter().enumerate().rev() {
+            match sequence_num.wrapping_cmp(buffered_packet.config().sequence_num()) {
+                Ordering::Equal => return,  // Duplicate packet, just abort.
+                Ordering::Less => continue,
+                Ordering::Greater => {
+                    insert_index = i + 1;
+                    break;
                 }
+            }
+        }
 
-                self.in_reliable_packets.insert(insert_index, packet);
-                trace!("Buffered reliable packet at: {insert_index}");
-
-                // let debug_seqs = self.in_reliable_packets.iter()
-                //     .map(|packet| packet.config().sequence_num().get())
-                //     .collect::<Vec<_>>();
-                // trace!("Buffered packets: {debug_seqs:?}");
+        self.in_reliable_packets.insert(insert_index, packet);
 
+        // By inserting this packet, we may have filled a gap in the sequences, so all
+        // packets before the cumulative ack are now contiguous.
+        while let Some(packet) = self.in_reliable_packets.front() {
+            if packet.config().sequence_num().wrapping_cmp(cumulative_ack).is_lt() {
+                self.in_reliable_contiguous_packets.push_back(self.in_reliable_packets.pop_front().unwrap());
+            } else {
+                break;  // Not contiguous.
             }
         }
 
         trace!("Received reliable packet cumulative: {}, contiguous: {}, buffered: {} (first: {:?})", 
-            self.in_reliable_expected_seq, 
+            cumulative_ack, 
             self.in_reliable_contiguous_packets.len(),
             self.in_reliable_packets.len(),
             self.in_reliable_packets.front().map(|packet| packet.config().sequence_num().get()));
@@ -871,6 +1477,10 @@ struct OnChannel {
 #[derive(Debug)]
 struct GenericChannel<'a> {
     shared: &'a mut ProtocolShared,
+    addr: SocketAddr,
+    /// The cipher of the channel, or the one of the peer if this channel has none.
+    cipher: Option<Arc<dyn PacketCipher>>,
+    policy: PacketPolicy,
     off: &'a mut OffChannelData,
     on: Option<&'a mut OnChannelData>,
 }
@@ -889,52 +1499,151 @@ impl GenericChannel<'_> {
 
 }
 
-/// Internal structure to keep fragments from a given sequence.
-#[derive(Debug)]
-struct Fragments {
-    fragments: Vec<Option<PacketLocked>>,
-    seq_count: u32,
-    last_update: Instant,
-}
 
-impl Fragments {
+#[cfg(test)]
+mod tests {
 
-    /// Create from sequence length.
-    fn new(seq_len: u32) -> Self {
-        Self {
-            fragments: (0..seq_len).map(|_| None).collect(),
-            seq_count: 0,
-            last_update: Instant::now()
-        }
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::time::Duration;
+
+    use blowfish::Blowfish;
+    use crypto_common::KeyInit;
+
+    use crate::net::bundle::NextElementReader;
+    use crate::net::element::DebugElementVariable32;
+
+    use super::*;
+
+    const LOCALHOST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+
+    type TestElement = DebugElementVariable32<0x42>;
+
+    fn bind() -> (PacketSocket, SocketAddr) {
+        let socket = PacketSocket::bind(LOCALHOST).unwrap();
+        socket.set_recv_timeout(Some(Duration::from_secs(5))).unwrap();
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, socket.addr().unwrap().port()));
+        (socket, addr)
+    }
+
+    fn cipher(key: u8) -> Arc<dyn PacketCipher> {
+        Arc::new(Blowfish::new_from_slice(&[key; 16]).unwrap())
     }
 
-    /// Set a fragment.
-    fn set(&mut self, num: u32, packet: PacketLocked) {
-        let frag = &mut self.fragments[num as usize];
-        if frag.is_none() {
-            self.seq_count += 1;
+    /// Receive the given number of packets and accept them, returning the last bundle.
+    fn recv_bundle(socket: &PacketSocket, protocol: &mut Protocol, count: usize) -> Option<Bundle> {
+        let mut bundle = None;
+        for _ in 0..count {
+            let (packet, addr) = socket.recv_without_encryption().unwrap();
+            if let Some(mut channel) = protocol.accept(packet, addr) {
+                bundle = channel.next_bundle().or(bundle);
+            }
         }
-        self.last_update = Instant::now();
-        *frag = Some(packet);
+        bundle
     }
 
-    #[inline]
-    fn is_old(&self, time: Instant, timeout: Duration) -> bool {
-        time - self.last_update > timeout
+    fn read_element(bundle: &Bundle) -> TestElement {
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(element)) = reader.next() else { panic!("expected an element") };
+        element.read_simple::<TestElement>().unwrap().element
     }
 
-    #[inline]
-    fn is_full(&self) -> bool {
-        self.seq_count as usize == self.fragments.len()
+    #[test]
+    fn encrypted_channel() {
+
+        let (client, client_addr) = bind();
+        let (server, server_addr) = bind();
+
+        let mut client_protocol = Protocol::new();
+        let mut server_protocol = Protocol::new();
+        server_protocol.create_channel(client_addr, None).set_cipher(Some(cipher(0x42)));
+
+        let mut channel = client_protocol.create_channel(server_addr, None);
+        channel.set_cipher(Some(cipher(0x42)));
+        assert!(channel.cipher().is_some());
+        assert_eq!(channel.addr(), server_addr);
+
+        // The element is big enough to be fragmented in multiple packets.
+        let data = (0..5000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(TestElement { data: data.clone() });
+        assert!(bundle.len() > 1);
+        channel.send(&client, &mut bundle, true).unwrap();
+        
+        // Off-channel bundles are encrypted with the peer cipher too.
+        assert!(client_protocol.cipher(server_addr).is_some());
+        let mut small_bundle = Bundle::new();
+        small_bundle.element_writer().write_simple(TestElement { data: vec![1, 2, 3] });
+        client_protocol.off_channel(server_addr).send(&client, &mut small_bundle, false).unwrap();
+
+        let received = recv_bundle(&server, &mut server_protocol, bundle.len()).expect("expected a bundle");
+        assert_eq!(read_element(&received).data, data);
+        let received = recv_bundle(&server, &mut server_protocol, 1).expect("expected a bundle");
+        assert_eq!(read_element(&received).data, [1, 2, 3]);
+
+        // Closing the channel forgets its cipher.
+        assert!(server_protocol.close_channel(client_addr, None));
+        assert!(server_protocol.cipher(client_addr).is_none());
+
     }
 
-    /// Convert this structure to a bundle, **safe to call only if `is_full() == true`**.
-    #[inline]
-    fn into_bundle(self) -> Bundle {
-        assert!(self.is_full());
-        self.fragments.into_iter()
-            .map(|o| o.unwrap())
-            .collect()
+    #[test]
+    fn encrypted_channel_rejects() {
+
+        let (client, client_addr) = bind();
+        let (server, server_addr) = bind();
+
+        let mut server_protocol = Protocol::new();
+        server_protocol.create_channel(client_addr, None).set_cipher(Some(cipher(0x42)));
+
+        // A packet that is not encrypted is rejected.
+        let mut client_protocol = Protocol::new();
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(TestElement { data: vec![1, 2, 3] });
+        client_protocol.off_channel(server_addr).send(&client, &mut bundle, false).unwrap();
+        assert!(recv_bundle(&server, &mut server_protocol, 1).is_none());
+
+        // A packet encrypted with another key is rejected.
+        let mut client_protocol = Protocol::new();
+        client_protocol.create_channel(server_addr, None).set_cipher(Some(cipher(0x43)));
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(TestElement { data: vec![1, 2, 3] });
+        client_protocol.channel(server_addr, None).send(&client, &mut bundle, true).unwrap();
+        assert!(recv_bundle(&server, &mut server_protocol, 1).is_none());
+
+    }
+
+    #[test]
+    fn retransmit_cipher() {
+
+        let (client, _) = bind();
+        let (_server, server_addr) = bind();
+        let index = NonZero::new(1).unwrap();
+
+        let mut protocol = Protocol::new();
+        protocol.set_retransmit_policy(Some(RetransmitPolicy { timeout: Duration::ZERO, max_retries: 1 }));
+        let peer_cipher = cipher(0x42);
+        let channel_cipher = cipher(0x43);
+        protocol.create_channel(server_addr, None).set_cipher(Some(Arc::clone(&peer_cipher)));
+        protocol.create_channel(server_addr, Some(index)).set_cipher(Some(Arc::clone(&channel_cipher)));
+
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write_simple(TestElement { data: vec![1, 2, 3] });
+        protocol.channel(server_addr, Some(index)).send(&client, &mut bundle, true).unwrap();
+        protocol.off_channel(server_addr).send(&client, &mut bundle, true).unwrap();
+
+        // Each packet is resent with the cipher of its channel, or the peer cipher.
+        let mut ciphers = Vec::new();
+        while let Some(event) = protocol.poll_retransmit(Instant::now()) {
+            if let RetransmitEvent::Resend { addr, cipher, .. } = event {
+                assert_eq!(addr, server_addr);
+                ciphers.push(cipher.unwrap());
+            }
+        }
+
+        assert_eq!(ciphers.len(), 2);
+        assert!(ciphers.iter().any(|cipher| Arc::ptr_eq(cipher, &peer_cipher)));
+        assert!(ciphers.iter().any(|cipher| Arc::ptr_eq(cipher, &channel_cipher)));
+
     }
 
 }
@@ -0,0 +1,143 @@
+//! Heuristic sniffer of elements with unknown ids, this tries plausible interpretations
+//! of the raw element data and reports the best guesses, this is only intended to help
+//! reverse engineering of new elements.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::util::io::{serde_pickle_de_options, WgReadExt};
+use crate::util::AsciiFmt;
+
+use super::element::ElementLength;
+
+
+/// Maximum number of guesses returned by [`sniff`].
+pub const MAX_GUESSES: usize = 4;
+
+/// A plausible interpretation of an element's data.
+#[derive(Debug, Clone)]
+pub struct SniffGuess {
+    /// The guessed length of the element.
+    pub length: ElementLength,
+    /// The guessed payload of the element, without the length header.
+    pub payload: Vec<u8>,
+    /// The guessed content of the payload.
+    pub content: SniffContent,
+    /// The score of this guess, the higher is the more plausible.
+    pub score: u32,
+}
+
+/// The guessed content of an element's payload.
+#[derive(Debug, Clone)]
+pub enum SniffContent {
+    /// The payload is empty.
+    Empty,
+    /// The payload is an UTF-8 string prefixed by its packed length.
+    String(String),
+    /// The payload is a python pickle, possibly prefixed by its packed length.
+    Pickle(serde_pickle::Value),
+    /// The payload has no recognized content.
+    Raw,
+}
+
+/// Sniff the given element data, this data should be the remaining data of the bundle
+/// after the element id, because the element's length is also unknown. The returned
+/// guesses are sorted by descending score and there are at most [`MAX_GUESSES`].
+pub fn sniff(data: &[u8]) -> Vec<SniffGuess> {
+
+    let mut guesses = Vec::new();
+
+    // The element may be the last one of the bundle, with a fixed length.
+    guesses.push(guess(ElementLength::Fixed(data.len() as u32), data, 1));
+
+    for (length, header_len) in [
+        (ElementLength::Variable8, 1),
+        (ElementLength::Variable16, 2),
+        (ElementLength::Variable24, 3),
+        (ElementLength::Variable32, 4),
+    ] {
+
+        let Ok(Some(payload_len)) = length.read(data) else {
+            continue;
+        };
+
+        let Some(payload) = data.get(header_len..).and_then(|data| data.get(..payload_len as usize)) else {
+            continue;
+        };
+
+        // A variable length that exactly consumes the remaining data is more plausible
+        // than one leaving data that may or may not be other elements.
+        let length_score = if header_len + payload.len() == data.len() { 3 } else { 2 };
+        guesses.push(guess(length, payload, length_score));
+
+    }
+
+    guesses.sort_by_key(|guess| Reverse(guess.score));
+    guesses.truncate(MAX_GUESSES);
+    guesses
+
+}
+
+/// Guess the content of the given payload and return the complete guess.
+fn guess(length: ElementLength, payload: &[u8], length_score: u32) -> SniffGuess {
+
+    let (content, content_score) = if payload.is_empty() {
+        (SniffContent::Empty, 0)
+    } else if let Some(value) = sniff_pickle(payload) {
+        (SniffContent::Pickle(value), 4)
+    } else if let Some(string) = sniff_string(payload) {
+        (SniffContent::String(string), 3)
+    } else {
+        (SniffContent::Raw, 0)
+    };
+
+    SniffGuess {
+        length,
+        payload: payload.to_vec(),
+        content,
+        score: length_score + content_score,
+    }
+
+}
+
+/// Try to decode a python pickle, that may be prefixed by its packed length.
+fn sniff_pickle(payload: &[u8]) -> Option<serde_pickle::Value> {
+
+    // Pickles always start with the protocol opcode.
+    let decode = |data: &[u8]| {
+        if data.first() == Some(&0x80) {
+            serde_pickle::value_from_reader(data, serde_pickle_de_options()).ok()
+        } else {
+            None
+        }
+    };
+
+    decode(payload).or_else(|| decode(strip_packed_len(payload)?))
+
+}
+
+/// Try to decode an UTF-8 string prefixed by its packed length, the string must not
+/// contain control characters.
+fn sniff_string(payload: &[u8]) -> Option<String> {
+    let string = std::str::from_utf8(strip_packed_len(payload)?).ok()?;
+    (!string.chars().any(char::is_control)).then(|| string.to_string())
+}
+
+/// Read a packed length from the payload and return the remaining data only if it has
+/// exactly this length.
+fn strip_packed_len(mut payload: &[u8]) -> Option<&[u8]> {
+    let len = payload.read_packed_u24().ok()?;
+    (payload.len() == len as usize).then_some(payload)
+}
+
+impl fmt::Display for SniffGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} of {} bytes (score: {}): ", self.length, self.payload.len(), self.score)?;
+        match &self.content {
+            SniffContent::Empty => write!(f, "empty"),
+            SniffContent::String(string) => write!(f, "string {string:?}"),
+            SniffContent::Pickle(value) => write!(f, "pickle {value}"),
+            SniffContent::Raw => write!(f, "raw {:?}", AsciiFmt(&self.payload)),
+        }
+    }
+}
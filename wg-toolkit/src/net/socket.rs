@@ -1,29 +1,25 @@
-//! Providing an bundle-oriented socket, backed by an UDP socket.
+//! Providing an bundle-oriented socket, backed by an UDP socket by default, or any
+//! other [`Transport`] implementation.
 
 use std::net::{SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use std::io::{self, Cursor};
+use std::io;
 use std::time::Duration;
 
-use blowfish::Blowfish;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
 
-use tracing::trace;
-
-use super::filter::{BlowfishReader, BlowfishWriter, blowfish::BLOCK_SIZE};
-use super::packet::{self, Packet};
+use super::transport::Transport;
+use super::cipher::PacketCipher;
+use super::packet::{self, Packet, PacketPool};
 use super::bundle::Bundle;
 
 
-/// Encryption magic, 0xDEADBEEF in little endian.
-const ENCRYPTION_MAGIC: [u8; 4] = 0xDEADBEEFu32.to_le_bytes();
-/// Encryption footer length, 1 byte for wastage count + 4 bytes magic.
-const ENCRYPTION_FOOTER_LEN: usize = ENCRYPTION_MAGIC.len() + 1;
-
-
 /// A tiny wrapper around UDP socket that allows sending and receiving raw packets, with
-/// support for encryption of specific socket addresses.
+/// support for encryption of specific socket addresses. The underlying UDP socket can
+/// be replaced by any other [`Transport`] when constructed with [`Self::new`].
 /// 
 /// This can be used as a MIO source to know when to receive and send packets, because
 /// it is non-blocking by default and it cannot be changed.
@@ -35,11 +31,13 @@ pub struct PacketSocket {
 
 #[derive(Debug)]
 struct Inner {
-    /// The inner socket.
-    socket: UdpSocket,
+    /// The inner transport, UDP socket by default.
+    socket: Box<dyn Transport>,
     /// Possible symmetric encryption on given socket addresses. Behind a shared 
     /// read/write lock because most of the time we don't modify it.
-    encryption: RwLock<HashMap<SocketAddr, Arc<Blowfish>>>,
+    encryption: RwLock<HashMap<SocketAddr, Arc<dyn PacketCipher>>>,
+    /// The pool where received packets are taken from.
+    pool: PacketPool,
     total_send_size: AtomicUsize,
     total_send_count: AtomicUsize,
     total_recv_size: AtomicUsize,
@@ -48,17 +46,54 @@ struct Inner {
 
 impl PacketSocket {
 
+    /// Bind a new packet socket on the given address, backed by an UDP socket.
     pub fn bind(addr: SocketAddr) -> io::Result<Self> {
-        Ok(Self {
+        Ok(Self::new(UdpSocket::bind(addr)?))
+    }
+
+    /// Bind a new packet socket on the given address, backed by an UDP socket with the
+    /// `SO_REUSEPORT` option, allowing many sockets to be bound to the same address. 
+    /// The kernel then dispatches datagrams to the sockets by hashing the peer address,
+    /// so a given peer is consistently routed to the same socket as long as the set of
+    /// bound sockets doesn't change. This is only supported on unix platforms.
+    pub fn bind_reuse_port(addr: SocketAddr) -> io::Result<Self> {
+
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        {
+            use socket2::{Domain, Protocol, Socket, Type};
+            let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+            socket.set_reuse_port(true)?;
+            socket.bind(&addr.into())?;
+            Ok(Self::new(UdpSocket::from(socket)))
+        }
+
+        #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+        {
+            let _ = addr;
+            Err(io::Error::new(io::ErrorKind::Unsupported, "reuse port is not supported on this platform"))
+        }
+
+    }
+
+    /// Create a new packet socket backed by the given transport.
+    pub fn new(transport: impl Transport) -> Self {
+        Self::with_pool(transport, PacketPool::default())
+    }
+
+    /// Create a new packet socket backed by the given transport, taking received
+    /// packets from the given pool, which can be shared with other sockets.
+    pub fn with_pool(transport: impl Transport, pool: PacketPool) -> Self {
+        Self {
             inner: Arc::new(Inner {
-                socket: UdpSocket::bind(addr)?,
+                socket: Box::new(transport),
                 encryption: RwLock::new(HashMap::new()),
+                pool,
                 total_send_size: AtomicUsize::new(0),
                 total_send_count: AtomicUsize::new(0),
                 total_recv_size: AtomicUsize::new(0),
                 total_recv_count: AtomicUsize::new(0),
             }),
-        })
+        }
     }
     
     pub fn addr(&self) -> io::Result<SocketAddr> {
@@ -66,16 +101,18 @@ impl PacketSocket {
     }
 
     pub fn set_recv_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
-        self.inner.socket.set_read_timeout(dur)
+        self.inner.socket.set_recv_timeout(dur)
     }
 
     pub fn set_send_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
-        self.inner.socket.set_write_timeout(dur)
+        self.inner.socket.set_send_timeout(dur)
     }
 
+    /// Set the cipher used to encrypt and decrypt packets exchanged with the given 
+    /// address, usually a [`Blowfish`](blowfish::Blowfish) key.
     #[inline]
-    pub fn set_encryption(&mut self, addr: SocketAddr, blowfish: Arc<Blowfish>) {
-        self.inner.encryption.write().unwrap().insert(addr, blowfish);
+    pub fn set_encryption(&mut self, addr: SocketAddr, cipher: Arc<dyn PacketCipher>) {
+        self.inner.encryption.write().unwrap().insert(addr, cipher);
     }
 
     #[inline]
@@ -83,6 +120,13 @@ impl PacketSocket {
         self.inner.encryption.write().unwrap().remove(&addr);
     }
 
+    /// Get the pool where received packets are taken from, packets and bundles that are
+    /// no longer used should be given back to it.
+    #[inline]
+    pub fn pool(&self) -> &PacketPool {
+        &self.inner.pool
+    }
+
     /// Get a snapshot of this socket's statistics.
     pub fn stat(&self) -> PacketSocketStat {
         PacketSocketStat {
@@ -93,13 +137,36 @@ impl PacketSocket {
         }
     }
 
+    /// Poll the readiness of the underlying transport for receiving a packet, see
+    /// [`Transport::poll_recv_ready`].
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub fn poll_recv_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.socket.poll_recv_ready(cx)
+    }
+
+    /// Poll the readiness of the underlying transport for sending a packet, see
+    /// [`Transport::poll_send_ready`].
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub fn poll_send_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.socket.poll_send_ready(cx)
+    }
+
     /// Receive a packet from some peer, without encryption if set for the address.
     pub fn recv_without_encryption(&self) -> io::Result<(Packet, SocketAddr)> {
         
-        let mut packet = Packet::new();
-        let (len, addr) = self.inner.socket.recv_from(packet.buf_mut())?;
+        let mut packet = self.inner.pool.take();
+        let (len, addr) = match self.inner.socket.recv_from(packet.buf_mut()) {
+            Ok(ret) => ret,
+            Err(e) => {
+                self.inner.pool.put(packet);
+                return Err(e);
+            }
+        };
 
         if len < packet::PACKET_HEADER_LEN {
+            self.inner.pool.put(packet);
             return Err(io::Error::new(io::ErrorKind::InvalidData, "received packet is too small"));
         }
 
@@ -119,8 +186,8 @@ impl PacketSocket {
         
         let (mut packet, addr) = self.recv_without_encryption()?;
 
-        if let Some(blowfish) = self.inner.encryption.read().unwrap().get(&addr) {
-            packet = decrypt_packet(packet, &blowfish)
+        if let Some(cipher) = self.inner.encryption.read().unwrap().get(&addr) {
+            packet = decrypt_packet(packet, &**cipher)
                 .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid encryption"))?;
         }
     
@@ -137,20 +204,23 @@ impl PacketSocket {
 
     /// Send a packet to the given peer.
     pub fn send(&self, packet: &Packet, addr: SocketAddr) -> io::Result<usize> {
+        let encryption = self.inner.encryption.read().unwrap();
+        self.send_with_cipher(packet, addr, encryption.get(&addr).map(|cipher| &**cipher))
+    }
 
-        let size;
-
-        if let Some(blowfish) = self.inner.encryption.read().unwrap().get(&addr) {
+    /// Send a packet to the given peer, encrypted with the given cipher if any, 
+    /// instead of the one set for the address, usually the cipher of the peer's 
+    /// channel, see [`Channel::cipher`](super::proto::Channel::cipher).
+    pub fn send_with_cipher(&self, packet: &Packet, addr: SocketAddr, cipher: Option<&dyn PacketCipher>) -> io::Result<usize> {
+        if let Some(cipher) = cipher {
             let mut dst_packet = encryption_packet::take();
-            encrypt_packet_raw(packet, &blowfish, &mut dst_packet);
-            size = self.send_without_encryption(&dst_packet, addr)?;
+            cipher.encrypt(packet, &mut dst_packet);
+            let res = self.send_without_encryption(&dst_packet, addr);
             encryption_packet::put(dst_packet);
+            res
         } else {
-            size = self.send_without_encryption(packet, addr)?;
+            self.send_without_encryption(packet, addr)
         }
-
-        Ok(size)
-
     }
 
     /// Send all packets in a bundle to the given peer, without encryption if set for the address.
@@ -164,15 +234,28 @@ impl PacketSocket {
 
     /// Send all packets in a bundle to the given peer.
     pub fn send_bundle(&self, bundle: &Bundle, addr: SocketAddr) -> io::Result<usize> {
-        if let Some(blowfish) = self.inner.encryption.read().unwrap().get(&addr) {
+        let encryption = self.inner.encryption.read().unwrap();
+        self.send_bundle_with_cipher(bundle, addr, encryption.get(&addr).map(|cipher| &**cipher))
+    }
+
+    /// Send all packets in a bundle to the given peer, encrypted with the given cipher
+    /// if any, see [`Self::send_with_cipher`].
+    pub fn send_bundle_with_cipher(&self, bundle: &Bundle, addr: SocketAddr, cipher: Option<&dyn PacketCipher>) -> io::Result<usize> {
+        if let Some(cipher) = cipher {
             
             let mut dst_packet = encryption_packet::take();
             let mut size = 0;
 
             for packet in bundle.iter() {
                 dst_packet.reset();
-                encrypt_packet_raw(packet, &blowfish, &mut dst_packet);
-                size += self.send_without_encryption(&dst_packet, addr)?;
+                cipher.encrypt(packet, &mut dst_packet);
+                match self.send_without_encryption(&dst_packet, addr) {
+                    Ok(len) => size += len,
+                    Err(e) => {
+                        encryption_packet::put(dst_packet);
+                        return Err(e);
+                    }
+                }
             }
 
             encryption_packet::put(dst_packet);
@@ -186,113 +269,143 @@ impl PacketSocket {
 
 }
 
-/// A snapshot of packet socket statistics.
-#[derive(Debug)]
-pub struct PacketSocketStat {
-    pub total_send_size: usize,
-    pub total_send_count: usize,
-    pub total_recv_size: usize,
-    pub total_recv_count: usize,
+/// An async front-end of the packet socket, backed by a tokio UDP socket by default,
+/// or any other non-blocking [`Transport`] reporting its readiness. Receiving and 
+/// sending wait for the readiness of the transport instead of blocking the thread.
+/// 
+/// The underlying packet socket can be retrieved to build applications that are then
+/// polled asynchronously, its methods never block but may return an error of kind
+/// [`io::ErrorKind::WouldBlock`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct AsyncPacketSocket {
+    /// The underlying packet socket, with a non-blocking transport.
+    socket: PacketSocket,
 }
 
-/// Decrypt a packet of a given length with a blowfish key. Note that the destination 
-/// packet will be completely erased, so the inner data is not relevant.
-fn decrypt_packet_raw(src_packet: &Packet, bf: &Blowfish, dst_packet: &mut Packet) -> bool {
+#[cfg(feature = "tokio")]
+impl AsyncPacketSocket {
 
-    let len = src_packet.len();
+    /// Bind a new async packet socket on the given address, backed by a tokio UDP 
+    /// socket, this must be called from within a tokio runtime.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self::new(tokio::net::UdpSocket::from_std(socket)?))
+    }
 
-    dst_packet.set_len(len);
+    /// Create a new async packet socket backed by the given transport, which should be
+    /// non-blocking and report its readiness.
+    pub fn new(transport: impl Transport) -> Self {
+        Self { socket: PacketSocket::new(transport) }
+    }
 
-    // Decrypt the incoming packet into the new clear packet.
-    // We don't need to set the length yet because this packet will be synchronized just 
-    // after. We don't encrypt the prefix.
-    let src = &src_packet.slice()[packet::PACKET_PREFIX_LEN..];
-    let dst = &mut dst_packet.slice_mut()[packet::PACKET_PREFIX_LEN..];
-    
-    // Note that src and dst have the same length, thanks to blowfish encryption.
-    // Then we can already check the length and ensures that it is a multiple of
-    // blowfish block size *and* can contain the wastage and encryption magic.
-    if src.len() % BLOCK_SIZE != 0 {
-        trace!("Invalid source body length: {}, block size: {BLOCK_SIZE}", src.len());
-        return false;
-    } else if src.len() < ENCRYPTION_FOOTER_LEN {
-        trace!("Invalid source body length: {}, min len: {ENCRYPTION_FOOTER_LEN}", src.len());
-        return false;
+    /// Get the underlying packet socket.
+    #[inline]
+    pub fn socket(&self) -> &PacketSocket {
+        &self.socket
     }
 
-    // Unwrapping because we know that source/destination have the same length.
-    io::copy(
-        &mut BlowfishReader::new(Cursor::new(src), &bf), 
-        &mut Cursor::new(&mut *dst),
-    ).unwrap();
+    /// Get the underlying packet socket, mutably, to set encryption.
+    #[inline]
+    pub fn socket_mut(&mut self) -> &mut PacketSocket {
+        &mut self.socket
+    }
 
-    let wastage_begin = src.len() - 1;
-    let magic_begin = wastage_begin - 4;
+    /// Take the underlying packet socket.
+    #[inline]
+    pub fn into_socket(self) -> PacketSocket {
+        self.socket
+    }
 
-    // Check invalid magic.
-    if &dst[magic_begin..wastage_begin] != &ENCRYPTION_MAGIC {
-        trace!("Invalid destination packet magic: {:X}, expected: {:X}", 
-            crate::util::BytesFmt(&dst[magic_begin..wastage_begin]),
-            crate::util::BytesFmt(&ENCRYPTION_MAGIC));
-        return false;
+    /// Wait until a packet can be received.
+    pub async fn recv_ready(&self) -> io::Result<()> {
+        std::future::poll_fn(|cx| self.socket.poll_recv_ready(cx)).await
     }
 
-    // Get the wastage count and compute the packet's length.
-    // Note that wastage count also it self length.
-    let wastage = dst[wastage_begin];
-    assert!(wastage <= BLOCK_SIZE as u8, "temporary check that wastage is not greater than block size");
+    /// Wait until a packet can be sent.
+    pub async fn send_ready(&self) -> io::Result<()> {
+        std::future::poll_fn(|cx| self.socket.poll_send_ready(cx)).await
+    }
 
-    dst_packet.set_len(len - wastage as usize - ENCRYPTION_MAGIC.len());
-    // Copy the prefix directly because it is clear.
-    dst_packet.write_prefix(src_packet.read_prefix());
+    /// Receive a packet from some peer, without encryption if set for the address.
+    pub async fn recv_without_encryption(&self) -> io::Result<(Packet, SocketAddr)> {
+        loop {
+            self.recv_ready().await?;
+            match self.socket.recv_without_encryption() {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+    }
 
-    true
+    /// Receive a packet from some peer.
+    pub async fn recv(&self) -> io::Result<(Packet, SocketAddr)> {
+        loop {
+            self.recv_ready().await?;
+            match self.socket.recv() {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+    }
 
-}
+    /// Send a packet to the given peer, without encryption if set for the address.
+    pub async fn send_without_encryption(&self, packet: &Packet, addr: SocketAddr) -> io::Result<usize> {
+        loop {
+            self.send_ready().await?;
+            match self.socket.send_without_encryption(packet, addr) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+    }
 
-/// Encrypt source packet with the given blowfish key and write it to the destination
-/// raw packet. Everything except the packet prefix is encrypted, and the destination
-/// packet will have a size that is a multiple of blowfish's block size (8). The clear
-/// data is also padded to block size, but with additional data at the end: encryption
-/// signature (0xDEADBEEF in little endian) and the wastage count + 1 on the last byte.
-fn encrypt_packet_raw(src_packet: &Packet, bf: &Blowfish, dst_packet: &mut Packet) {
-    
-    // Get the minimum, unpadded length of this packet with encryption footer appended to it.
-    let mut len = src_packet.len() - packet::PACKET_PREFIX_LEN + ENCRYPTION_FOOTER_LEN;
-
-    // The wastage amount is basically the padding + 1 for the wastage itself.
-    let padding = (BLOCK_SIZE - (len % BLOCK_SIZE)) % BLOCK_SIZE;
-    len += padding;
-
-    // Clone the packet data into a new vec and append the padding and the footer.
-    let mut clear_data = src_packet.slice()[packet::PACKET_PREFIX_LEN..].to_vec();
-    clear_data.reserve_exact(padding + ENCRYPTION_FOOTER_LEN);
-    clear_data.extend_from_slice(&[0u8; BLOCK_SIZE - 1][..padding]); // Padding
-    clear_data.extend_from_slice(&ENCRYPTION_MAGIC); // Magic
-    clear_data.push(padding as u8 + 1); // Wastage count (+1 for it self size)
-
-    debug_assert_eq!(clear_data.len(), len, "incoherent length");
-    debug_assert_eq!(clear_data.len() % 8, 0, "data not padded as expected");
-    
-    // +4 for the prefix.
-    dst_packet.set_len(clear_data.len() + 4);
-
-    // Unwrapping because we know that source/destination have the same length.
-    io::copy(
-        &mut Cursor::new(&clear_data[..]), 
-        &mut BlowfishWriter::new(Cursor::new(&mut dst_packet.slice_mut()[packet::PACKET_PREFIX_LEN..]), bf),
-    ).unwrap();
-    
-    // Copy the prefix directly because it is clear.
-    dst_packet.write_prefix(src_packet.read_prefix());
+    /// Send a packet to the given peer.
+    pub async fn send(&self, packet: &Packet, addr: SocketAddr) -> io::Result<usize> {
+        loop {
+            self.send_ready().await?;
+            match self.socket.send(packet, addr) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                res => return res,
+            }
+        }
+    }
 
+    /// Send all packets in a bundle to the given peer, without encryption if set for the address.
+    pub async fn send_bundle_without_encryption(&self, bundle: &Bundle, addr: SocketAddr) -> io::Result<usize> {
+        let mut size = 0;
+        for packet in bundle.iter() {
+            size += self.send_without_encryption(packet, addr).await?;
+        }
+        Ok(size)
+    }
+
+    /// Send all packets in a bundle to the given peer.
+    pub async fn send_bundle(&self, bundle: &Bundle, addr: SocketAddr) -> io::Result<usize> {
+        let mut size = 0;
+        for packet in bundle.iter() {
+            size += self.send(packet, addr).await?;
+        }
+        Ok(size)
+    }
+
+}
+
+/// A snapshot of packet socket statistics.
+#[derive(Debug)]
+pub struct PacketSocketStat {
+    pub total_send_size: usize,
+    pub total_send_count: usize,
+    pub total_recv_size: usize,
+    pub total_recv_count: usize,
 }
 
-/// Decrypt a source packet given a blowfish key, return the clear packet if success,
-/// if the decryption fails it return the source packet not touched.
-pub fn decrypt_packet(src_packet: Packet, bf: &Blowfish) -> Result<Packet, Packet> {
+/// Decrypt a source packet given a cipher, usually a blowfish key, return the clear
+/// packet if success, if the decryption fails it return the source packet not touched.
+pub fn decrypt_packet(src_packet: Packet, cipher: &(impl PacketCipher + ?Sized)) -> Result<Packet, Packet> {
     let mut dst_packet = encryption_packet::take();
-    if decrypt_packet_raw(&src_packet, bf, &mut dst_packet) {
+    if cipher.decrypt(&src_packet, &mut dst_packet) {
         encryption_packet::put(src_packet);
         Ok(dst_packet)
     } else {
@@ -301,10 +414,11 @@ pub fn decrypt_packet(src_packet: Packet, bf: &Blowfish) -> Result<Packet, Packe
     }
 }
 
-/// Encrypt a source packet given a blowfish key, return the encrypted packet.
-pub fn encrypt_packet(src_packet: Packet, bf: &Blowfish) -> Packet {
+/// Encrypt a source packet given a cipher, usually a blowfish key, return the 
+/// encrypted packet.
+pub fn encrypt_packet(src_packet: Packet, cipher: &(impl PacketCipher + ?Sized)) -> Packet {
     let mut dst_packet = encryption_packet::take();
-    encrypt_packet_raw(&src_packet, bf, &mut dst_packet);
+    cipher.encrypt(&src_packet, &mut dst_packet);
     encryption_packet::put(src_packet);
     dst_packet
 }
@@ -0,0 +1,173 @@
+//! Arbitrary and proptest generation of packet configs, bundles and core elements,
+//! intended for property-testing encode/decode round-trips and for fuzzing.
+//!
+//! This module is only available with the `testing` feature.
+
+use std::io::{self, Read, Write};
+use std::num::NonZero;
+use std::fmt;
+
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::prelude::*;
+
+use crate::util::io::WgReadExt;
+
+use super::element::{Element, ElementLength, REPLY_ID};
+use super::packet::PacketConfig;
+use super::bundle::Bundle;
+use super::seq::Seq;
+use super::app::base::element::DisconnectReason;
+use super::app::client::element::{LoggedOffReason, QueueType};
+
+
+/// Maximum number of fragments in a sequence range of an arbitrary packet config.
+const MAX_FRAGMENTS: u32 = 64;
+/// Maximum number of single acks in an arbitrary packet config.
+const MAX_SINGLE_ACKS: usize = 16;
+/// Maximum number of elements in an arbitrary bundle.
+const MAX_ELEMENTS: usize = 32;
+/// Maximum length of the data of an arbitrary raw element.
+const MAX_ELEMENT_LEN: usize = 2048;
+/// Maximum number of random bytes used to generate a value in proptest strategies.
+const MAX_STRATEGY_BYTES: usize = 16384;
+
+impl<'a> Arbitrary<'a> for Seq {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Seq::new(u.int_in_range(0..=0x0FFF_FFFF)?).unwrap())
+    }
+}
+
+impl<'a> Arbitrary<'a> for PacketConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+
+        // The config is built through its setters so that flags are consistent.
+        let mut config = PacketConfig::new();
+        config.set_reliable(u.arbitrary()?);
+        config.set_create_channel(u.arbitrary()?);
+        config.set_on_channel(u.arbitrary()?);
+        config.set_has_checksum(u.arbitrary()?);
+        config.set_sequence_num(u.arbitrary()?);
+
+        if u.arbitrary()? {
+            let first: Seq = u.arbitrary()?;
+            config.set_sequence_range(first, first + u.int_in_range(1..=MAX_FRAGMENTS)?);
+        }
+
+        if u.arbitrary()? {
+            config.set_cumulative_ack(u.arbitrary()?);
+        }
+
+        for _ in 0..u.int_in_range(0..=MAX_SINGLE_ACKS)? {
+            config.single_acks_mut().push_back(u.arbitrary()?);
+        }
+
+        if u.arbitrary()? {
+            config.set_indexed_channel(u.arbitrary::<NonZero<u32>>()?, u.arbitrary::<NonZero<u32>>()?);
+        }
+
+        if u.arbitrary()? {
+            config.set_last_reliable_sequence_num(u.arbitrary()?);
+        }
+
+        Ok(config)
+
+    }
+}
+
+/// A raw element with an arbitrary id and data, that can be written to and read from a
+/// bundle in order to compare its elements before and after encoding. Its length is
+/// always encoded on 16 bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawElement {
+    /// The element id, never [`REPLY_ID`].
+    pub id: u8,
+    /// The request id, if this element is a request.
+    pub request_id: Option<u32>,
+    /// The element's data.
+    pub data: Vec<u8>,
+}
+
+impl<'a> Arbitrary<'a> for RawElement {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_ELEMENT_LEN)?;
+        Ok(Self {
+            id: u.int_in_range(0..=REPLY_ID - 1)?,
+            request_id: u.arbitrary()?,
+            data: u.bytes(len)?.to_vec(),
+        })
+    }
+}
+
+impl Element<()> for RawElement {
+
+    fn write_length(&self, _config: &()) -> io::Result<ElementLength> {
+        Ok(ElementLength::Variable16)
+    }
+
+    fn write(&self, write: &mut dyn Write, _config: &()) -> io::Result<u8> {
+        write.write_all(&self.data)?;
+        Ok(self.id)
+    }
+
+    fn read_length(_config: &(), _id: u8) -> io::Result<ElementLength> {
+        Ok(ElementLength::Variable16)
+    }
+
+    fn read(read: &mut dyn Read, _config: &(), _len: usize, id: u8) -> io::Result<Self> {
+        Ok(Self {
+            id,
+            request_id: None,  // Set by the bundle reader.
+            data: read.read_blob_to_end()?,
+        })
+    }
+
+}
+
+impl<'a> Arbitrary<'a> for Bundle {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+
+        let mut bundle = Bundle::new();
+        let mut writer = bundle.element_writer();
+
+        for _ in 0..u.int_in_range(0..=MAX_ELEMENTS)? {
+            let element = RawElement::arbitrary(u)?;
+            match element.request_id {
+                Some(request_id) => writer.write_simple_request(element, request_id),
+                None => writer.write_simple(element),
+            }
+        }
+
+        Ok(bundle)
+
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisconnectReason {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u8::arbitrary(u).map(Self::from)
+    }
+}
+
+impl<'a> Arbitrary<'a> for LoggedOffReason {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u8::arbitrary(u).map(Self::from)
+    }
+}
+
+impl<'a> Arbitrary<'a> for QueueType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u8::arbitrary(u).map(Self::from)
+    }
+}
+
+/// Return a proptest strategy generating values from their [`Arbitrary`] implementation,
+/// the values are generated from random bytes, so shrinking is done on these bytes.
+pub fn arbitrary<T>() -> impl Strategy<Value = T>
+where
+    T: for<'a> Arbitrary<'a> + fmt::Debug,
+{
+    proptest::collection::vec(any::<u8>(), 0..MAX_STRATEGY_BYTES)
+        .prop_filter_map("not enough arbitrary data", |bytes| {
+            T::arbitrary_take_rest(Unstructured::new(&bytes)).ok()
+        })
+}
@@ -0,0 +1,297 @@
+//! Transport abstraction under the packet socket, allowing applications to run over
+//! alternative carriers than UDP, the UDP transport being the default one.
+
+use std::net::{SocketAddr, UdpSocket};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{fmt, io};
+
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+
+/// A datagram-oriented transport that is used by packet sockets to send and receive 
+/// raw packets. All methods take a shared reference because a transport is shared
+/// between threads behind the packet socket.
+pub trait Transport: fmt::Debug + Send + Sync + 'static {
+
+    /// Get the local address of this transport.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    /// Set the receive timeout, none meaning that receiving is blocking indefinitely.
+    fn set_recv_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+
+    /// Set the send timeout, none meaning that sending is blocking indefinitely.
+    fn set_send_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+
+    /// Receive a single datagram in the given buffer, returning its length and the
+    /// address of the sender. If the datagram is larger than the buffer, the excess
+    /// bytes are discarded.
+    /// 
+    /// When the receive timeout elapses, a [`io::ErrorKind::TimedOut`] error must be
+    /// returned, whatever the platform. The [`io::ErrorKind::WouldBlock`] error is 
+    /// reserved to non-blocking transports that are not ready to receive.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+
+    /// Send a single datagram to the given address, returning the number of bytes sent.
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize>;
+
+    /// Poll the readiness of this transport for receiving a datagram, this is used by
+    /// async packet sockets. Blocking transports are always ready, receiving then 
+    /// blocks until a datagram is received.
+    #[cfg(feature = "tokio")]
+    fn poll_recv_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = cx;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Poll the readiness of this transport for sending a datagram, this is used by
+    /// async packet sockets. Blocking transports are always ready.
+    #[cfg(feature = "tokio")]
+    fn poll_send_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = cx;
+        Poll::Ready(Ok(()))
+    }
+
+}
+
+impl Transport for UdpSocket {
+
+    #[inline]
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+
+    #[inline]
+    fn set_recv_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(dur)
+    }
+
+    #[inline]
+    fn set_send_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.set_write_timeout(dur)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match UdpSocket::recv_from(self, buf) {
+            // On unix, an elapsed receive timeout is reported as would block, this is
+            // only the case in blocking mode, non-blocking sockets are just not ready.
+            #[cfg(unix)]
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock 
+                && self.read_timeout()?.is_some() 
+                && !socket2::SockRef::from(self).nonblocking()? => {
+                Err(io::ErrorKind::TimedOut.into())
+            }
+            res => res,
+        }
+    }
+
+    #[inline]
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, addr)
+    }
+
+}
+
+
+/// The tokio UDP socket is a non-blocking transport, receiving and sending return a
+/// [`io::ErrorKind::WouldBlock`] error when the socket is not ready, its readiness
+/// should be awaited, which is what async packet sockets do. Timeouts are therefore 
+/// ignored.
+#[cfg(feature = "tokio")]
+impl Transport for tokio::net::UdpSocket {
+
+    #[inline]
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        tokio::net::UdpSocket::local_addr(self)
+    }
+
+    #[inline]
+    fn set_recv_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn set_send_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.try_recv_from(buf)
+    }
+
+    #[inline]
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        self.try_send_to(buf, addr)
+    }
+
+    #[inline]
+    fn poll_recv_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::net::UdpSocket::poll_recv_ready(self, cx)
+    }
+
+    #[inline]
+    fn poll_send_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::net::UdpSocket::poll_send_ready(self, cx)
+    }
+
+}
+
+
+/// An in-memory network where memory transports can be bound, this is mostly useful
+/// for testing applications against each other without any real socket. Datagrams
+/// sent to an address that is not bound are silently dropped, like UDP.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryNetwork {
+    /// Each bound address associated to the sender of its transport.
+    routes: Arc<Mutex<HashMap<SocketAddr, Sender<(Vec<u8>, SocketAddr)>>>>,
+}
+
+impl MemoryNetwork {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind a new memory transport on the given address, if the port is zero, then a 
+    /// free port is allocated on the given IP.
+    pub fn bind(&self, mut addr: SocketAddr) -> io::Result<MemoryTransport> {
+
+        let mut routes = self.routes.lock().unwrap();
+
+        if addr.port() == 0 {
+            let port = (49152..=u16::MAX)
+                .find(|&port| !routes.contains_key(&SocketAddr::new(addr.ip(), port)))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::AddrNotAvailable, "no free port"))?;
+            addr.set_port(port);
+        } else if routes.contains_key(&addr) {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "address already in use"));
+        }
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        routes.insert(addr, tx);
+
+        Ok(MemoryTransport {
+            network: self.clone(),
+            addr,
+            rx,
+            recv_timeout: Mutex::new(None),
+        })
+
+    }
+
+}
+
+/// A transport bound to a [`MemoryNetwork`], the address is released when dropped.
+#[derive(Debug)]
+pub struct MemoryTransport {
+    /// The network this transport is bound to.
+    network: MemoryNetwork,
+    /// The address this transport is bound to.
+    addr: SocketAddr,
+    /// Receiving end of the datagrams sent to this transport.
+    rx: Receiver<(Vec<u8>, SocketAddr)>,
+    /// The receive timeout.
+    recv_timeout: Mutex<Option<Duration>>,
+}
+
+impl Transport for MemoryTransport {
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn set_recv_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        if dur == Some(Duration::ZERO) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot set a 0 duration timeout"));
+        }
+        *self.recv_timeout.lock().unwrap() = dur;
+        Ok(())
+    }
+
+    fn set_send_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        // Sending is never blocking.
+        Ok(())
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let recv_timeout = *self.recv_timeout.lock().unwrap();
+        let (data, addr) = match recv_timeout {
+            Some(timeout) => match self.rx.recv_timeout(timeout) {
+                Ok(ret) => ret,
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::TimedOut.into()),
+                Err(RecvTimeoutError::Disconnected) => return Err(io::ErrorKind::NotConnected.into()),
+            },
+            None => match self.rx.recv() {
+                Ok(ret) => ret,
+                Err(_) => return Err(io::ErrorKind::NotConnected.into()),
+            }
+        };
+
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok((len, addr))
+
+    }
+
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        if let Some(tx) = self.network.routes.lock().unwrap().get(&addr) {
+            // Ignore disconnected receiver, the datagram is just lost.
+            let _ = tx.send((buf.to_vec(), self.addr));
+        }
+        Ok(buf.len())
+    }
+
+}
+
+impl Drop for MemoryTransport {
+    fn drop(&mut self) {
+        self.network.routes.lock().unwrap().remove(&self.addr);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use super::*;
+
+    const LOCALHOST: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+
+    /// Check that an elapsed receive timeout is reported as timed out.
+    fn check_recv_timeout(transport: &dyn Transport) {
+        transport.set_recv_timeout(Some(Duration::from_millis(10))).unwrap();
+        let error = transport.recv_from(&mut [0; 16]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn udp_recv_timeout() {
+        check_recv_timeout(&UdpSocket::bind(LOCALHOST).unwrap());
+    }
+
+    #[test]
+    fn memory_recv_timeout() {
+        check_recv_timeout(&MemoryNetwork::new().bind(LOCALHOST).unwrap());
+    }
+
+    #[test]
+    fn udp_would_block() {
+        // With or without timeout, a non-blocking socket reports that it would block.
+        let socket = UdpSocket::bind(LOCALHOST).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let error = Transport::recv_from(&socket, &mut [0; 16]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        socket.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+        let error = Transport::recv_from(&socket, &mut [0; 16]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+    }
+
+}
@@ -0,0 +1,389 @@
+//! Protocol variants, covering the differences between the PC client's protocol and
+//! other branches of the engine, such as console builds.
+//!
+//! The PC client's layout is the canonical one used by the whole crate: packet flags
+//! are always those of the [`flags`](super::packet::flags) module and elements ids are
+//! the ones defined by each application. A variant only translates them from and to
+//! the wire when reading or writing packets and bundles.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use super::element::ElementIdRange;
+use super::packet::flags;
+
+
+/// A protocol variant, the default variant is the PC client's one, translating
+/// nothing. A variant is selected per application, see `set_protocol_variant` on
+/// applications.
+///
+/// This structure is cheap to clone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolVariant {
+    /// Layout of packet flags.
+    flag_layout: FlagLayout,
+    /// Shifts of element ids, the first shift matching the canonical id is used.
+    element_id_shifts: Arc<[ElementIdShift]>,
+}
+
+impl ProtocolVariant {
+
+    /// The variant of the PC client, this is the default one.
+    pub fn pc() -> Self {
+        Self::default()
+    }
+
+    /// The variant of console builds, where the on-channel flag uses the unknown 
+    /// 0x0800 bit of the PC client, and where element ids 0x80 to 0x8F are shifted 
+    /// by 2 because of two additional elements before them.
+    pub fn console() -> Self {
+        Self::pc()
+            .with_flag(flags::ON_CHANNEL, flags::UNK_0800)
+            .with_element_id_shift(ElementIdRange::new(0x80, 0x8F), 2)
+    }
+
+    /// Return true if this variant translates nothing.
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        self.flag_layout.is_identity() && self.element_id_shifts.is_empty()
+    }
+
+    /// Move a canonical packet flag (a single bit of the [`flags`](super::packet::flags)
+    /// module) to another bit on the wire, both flags must be a single bit.
+    pub fn with_flag(mut self, flag: u16, wire_flag: u16) -> Self {
+        self.flag_layout.set(flag, wire_flag);
+        self
+    }
+
+    /// Shift all canonical element ids in the given range by the given signed offset
+    /// on the wire. The shifted range must not overflow the ids.
+    pub fn with_element_id_shift(mut self, range: ElementIdRange, shift: i8) -> Self {
+
+        assert!(range.first.checked_add_signed(shift).is_some(), "shifted range overflow");
+        assert!(range.last.checked_add_signed(shift).is_some(), "shifted range overflow");
+
+        let mut shifts = self.element_id_shifts.to_vec();
+        shifts.push(ElementIdShift { range, shift });
+        self.element_id_shifts = Arc::from(shifts);
+        self
+
+    }
+
+    /// The layout of packet flags.
+    #[inline]
+    pub fn flag_layout(&self) -> FlagLayout {
+        self.flag_layout
+    }
+
+    /// Translate a canonical element id to the id on the wire.
+    pub fn element_id_to_wire(&self, id: u8) -> u8 {
+        self.element_id_shifts.iter()
+            .find(|shift| shift.range.contains(id))
+            .map(|shift| id.wrapping_add_signed(shift.shift))
+            .unwrap_or(id)
+    }
+
+    /// Translate an element id on the wire to the canonical id.
+    pub fn element_id_from_wire(&self, wire_id: u8) -> u8 {
+        self.element_id_shifts.iter()
+            .find(|shift| shift.range.contains(wire_id.wrapping_add_signed(shift.shift.wrapping_neg())))
+            .map(|shift| wire_id.wrapping_add_signed(shift.shift.wrapping_neg()))
+            .unwrap_or(wire_id)
+    }
+
+}
+
+/// Parse a variant from its textual description, 'pc' for the PC client's variant, 
+/// 'console' for the console variant, or a comma-separated list of translations 
+/// applied to the PC client's variant:
+/// 
+/// - `flag:<flag>=<wire flag>` moves a packet flag, see [`ProtocolVariant::with_flag`];
+/// - `ids:<first>-<last>:<shift>` shifts element ids, see 
+///   [`ProtocolVariant::with_element_id_shift`].
+/// 
+/// Flags and ids are hex-encoded with an optional '0x' prefix, the shift is a signed 
+/// decimal number, for example: `flag:0x0100=0x0200,ids:0x80-0xF0:+2`.
+impl FromStr for ProtocolVariant {
+
+    type Err = ProtocolVariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+
+        let mut variant = Self::pc();
+        match s.trim() {
+            "pc" => return Ok(variant),
+            "console" => return Ok(Self::console()),
+            _ => {}
+        }
+
+        for part in s.split(',').map(str::trim) {
+
+            let invalid = || ProtocolVariantError(part.to_string());
+            let parse_hex = |s: &str| u16::from_str_radix(s.trim().trim_start_matches("0x"), 16);
+
+            if let Some(flags) = part.strip_prefix("flag:") {
+
+                let (flag, wire_flag) = flags.split_once('=').ok_or_else(invalid)?;
+                let flag = parse_hex(flag).map_err(|_| invalid())?;
+                let wire_flag = parse_hex(wire_flag).map_err(|_| invalid())?;
+                if !flag.is_power_of_two() || !wire_flag.is_power_of_two() {
+                    return Err(invalid());
+                }
+
+                variant = variant.with_flag(flag, wire_flag);
+
+            } else if let Some(ids) = part.strip_prefix("ids:") {
+
+                let (range, shift) = ids.split_once(':').ok_or_else(invalid)?;
+                let (first, last) = range.split_once('-').ok_or_else(invalid)?;
+                let first = parse_hex(first).ok().and_then(|id| u8::try_from(id).ok()).ok_or_else(invalid)?;
+                let last = parse_hex(last).ok().and_then(|id| u8::try_from(id).ok()).ok_or_else(invalid)?;
+                let shift = shift.trim().trim_start_matches('+').parse::<i8>().map_err(|_| invalid())?;
+                if first > last || first.checked_add_signed(shift).is_none() || last.checked_add_signed(shift).is_none() {
+                    return Err(invalid());
+                }
+
+                variant = variant.with_element_id_shift(ElementIdRange::new(first, last), shift);
+
+            } else {
+                return Err(invalid());
+            }
+
+        }
+
+        Ok(variant)
+
+    }
+
+}
+
+impl Default for ProtocolVariant {
+    fn default() -> Self {
+        Self {
+            flag_layout: FlagLayout::IDENTITY,
+            element_id_shifts: Arc::new([]),
+        }
+    }
+}
+
+/// Error returned when parsing an invalid protocol variant, with the invalid part.
+#[derive(Debug, Clone, Error)]
+#[error("invalid protocol variant: {0}")]
+pub struct ProtocolVariantError(pub String);
+
+/// A shift of element ids on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ElementIdShift {
+    /// The range of canonical ids.
+    range: ElementIdRange,
+    /// The offset added to canonical ids on the wire.
+    shift: i8,
+}
+
+/// The layout of packet flags on the wire, for each canonical flag it gives the bit
+/// used on the wire. The default layout is the identity.
+///
+/// This structure is small and therefore can be copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagLayout {
+    /// The wire bit index of each canonical bit index.
+    wire_bits: [u8; 16],
+}
+
+impl FlagLayout {
+
+    /// The identity layout, used by the PC client.
+    pub const IDENTITY: Self = Self {
+        wire_bits: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    };
+
+    /// Return true if this layout translates nothing.
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Move the given canonical flag to the given wire flag, the canonical flag that
+    /// was previously using that wire flag takes its previous place, so that the
+    /// layout remains a permutation.
+    pub fn set(&mut self, flag: u16, wire_flag: u16) {
+
+        assert!(flag.is_power_of_two(), "flag must be a single bit");
+        assert!(wire_flag.is_power_of_two(), "wire flag must be a single bit");
+
+        let bit = flag.trailing_zeros() as usize;
+        let wire_bit = wire_flag.trailing_zeros() as u8;
+
+        let other_bit = self.wire_bits.iter().position(|&b| b == wire_bit).unwrap();
+        self.wire_bits.swap(bit, other_bit);
+
+    }
+
+    /// Translate canonical flags to the flags on the wire.
+    pub fn to_wire(&self, flags: u16) -> u16 {
+        let mut wire_flags = 0;
+        for (bit, &wire_bit) in self.wire_bits.iter().enumerate() {
+            if flags & (1 << bit) != 0 {
+                wire_flags |= 1 << wire_bit;
+            }
+        }
+        wire_flags
+    }
+
+    /// Translate flags on the wire to canonical flags.
+    pub fn from_wire(&self, wire_flags: u16) -> u16 {
+        let mut flags = 0;
+        for (bit, &wire_bit) in self.wire_bits.iter().enumerate() {
+            if wire_flags & (1 << wire_bit) != 0 {
+                flags |= 1 << bit;
+            }
+        }
+        flags
+    }
+
+}
+
+impl Default for FlagLayout {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+    use crate::net::bundle::{Bundle, NextElementReader};
+    use crate::net::element::DebugElementFixed;
+    use crate::net::packet::Packet;
+    use crate::net::proto::Protocol;
+
+    use super::*;
+
+    const ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 20013));
+
+    type TestElement = DebugElementFixed<0x81, 4>;
+
+    fn variant() -> ProtocolVariant {
+        "flag:0x0008=0x0800, ids:0x80-0x8F:+2".parse().unwrap()
+    }
+
+    #[test]
+    fn flag_layout() {
+
+        let mut layout = FlagLayout::IDENTITY;
+        layout.set(0x0100, 0x0800);
+        assert!(!layout.is_identity());
+
+        // The layout is a permutation, the flag previously at the wire bit is swapped.
+        assert_eq!(layout.to_wire(0x0100), 0x0800);
+        assert_eq!(layout.to_wire(0x0800), 0x0100);
+        assert_eq!(layout.to_wire(0x0101), 0x0801);
+        for flags in [0x0000, 0x0100, 0x0900, 0xFFFF, 0x1234] {
+            assert_eq!(layout.from_wire(layout.to_wire(flags)), flags);
+        }
+
+    }
+
+    #[test]
+    fn element_ids() {
+
+        let variant = variant();
+        assert_eq!(variant.element_id_to_wire(0x81), 0x83);
+        assert_eq!(variant.element_id_from_wire(0x83), 0x81);
+        assert_eq!(variant.element_id_to_wire(0x90), 0x90);
+        assert_eq!(variant.element_id_from_wire(0x10), 0x10);
+
+        let mut bundle = Bundle::new();
+        bundle.set_variant(&variant);
+        bundle.element_writer().write_simple(TestElement { data: [1, 2, 3, 4] });
+
+        // Find the element id on the wire, just after the header.
+        let packet = bundle.iter().next().unwrap();
+        assert_eq!(packet.slice()[crate::net::packet::PACKET_HEADER_LEN], 0x83);
+
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(element)) = reader.next() else { panic!("expected an element") };
+        assert_eq!(element.id(), 0x81);
+        assert_eq!(element.read_simple::<TestElement>().unwrap().element.data, [1, 2, 3, 4]);
+
+    }
+
+    #[test]
+    fn protocol() {
+
+        let mut out_protocol = Protocol::new();
+        out_protocol.set_variant(variant());
+
+        let mut bundle = Bundle::new();
+        bundle.set_variant(out_protocol.variant());
+        bundle.element_writer().write_simple(TestElement { data: [1, 2, 3, 4] });
+        out_protocol.channel(ADDR, None).prepare(&mut bundle, true);
+
+        // The on-channel flag is moved on the wire.
+        let packet = bundle.iter().next().unwrap();
+        let wire_flags = packet.read_flags();
+        assert_eq!(wire_flags & flags::ON_CHANNEL, 0);
+        assert_ne!(wire_flags & 0x0800, 0);
+
+        let mut in_protocol = Protocol::new();
+        in_protocol.set_variant(variant());
+        let mut packet_copy = Packet::new();
+        packet_copy.buf_mut()[..packet.len()].copy_from_slice(packet.slice());
+        packet_copy.set_len(packet.len());
+        let mut channel = in_protocol.accept(packet_copy, ADDR).unwrap();
+        assert!(channel.is_on());
+
+        let bundle = channel.next_bundle().unwrap();
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(element)) = reader.next() else { panic!("expected an element") };
+        assert_eq!(element.id(), 0x81);
+
+    }
+
+    #[test]
+    fn console() {
+
+        // A reliable on-channel packet with a single element, in console layout.
+        let mut raw = vec![0; crate::net::packet::PACKET_PREFIX_LEN];
+        raw.extend_from_slice(&(flags::UNK_0800 | flags::IS_RELIABLE | flags::HAS_SEQUENCE_NUMBER).to_le_bytes());
+        raw.extend_from_slice(&[0x83, 1, 2, 3, 4]);
+        raw.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut packet = Packet::new();
+        packet.buf_mut()[..raw.len()].copy_from_slice(&raw);
+        packet.set_len(raw.len());
+
+        let mut protocol = Protocol::new();
+        protocol.set_variant(ProtocolVariant::console());
+        let mut channel = protocol.accept(packet, ADDR).unwrap();
+        assert!(channel.is_on());
+
+        let bundle = channel.next_bundle().unwrap();
+        let mut reader = bundle.element_reader();
+        let Some(NextElementReader::Element(element)) = reader.next() else { panic!("expected an element") };
+        assert_eq!(element.id(), 0x81);
+        assert_eq!(element.read_simple::<TestElement>().unwrap().element.data, [1, 2, 3, 4]);
+
+    }
+
+    #[test]
+    fn parse() {
+
+        assert!("pc".parse::<ProtocolVariant>().unwrap().is_identity());
+        assert_eq!("console".parse::<ProtocolVariant>().unwrap(), ProtocolVariant::console());
+        assert_eq!(variant(), ProtocolVariant::pc()
+            .with_flag(0x0008, 0x0800)
+            .with_element_id_shift(ElementIdRange::new(0x80, 0x8F), 2));
+
+        for invalid in ["", "flag:0x0100", "flag:0x0300=0x0800", "ids:0x80-0x8F", "ids:0x8F-0x80:1", "ids:0xF0-0xFF:+16", "other:1"] {
+            assert!(invalid.parse::<ProtocolVariant>().is_err(), "{invalid}");
+        }
+
+    }
+
+}
@@ -2,33 +2,57 @@
 
 use std::io::{self, Read, Seek, Cursor};
 
+use byteorder::{ByteOrder, BigEndian, LittleEndian, ReadBytesExt};
 use smallvec::SmallVec;
 use thiserror::Error;
 use base64::Engine;
 
 use crate::util::io::WgReadExt;
 
-use super::{DataType, Element, Value, Vector, MAGIC};
+use super::{DataType, Element, Endianness, Value, Vector, RawValue, MAGIC, MAGIC_BIG_ENDIAN};
+use super::{ElementLayout, DocumentLayout, ValueLayout};
 
 
-/// Read a packed XML data from an readable and seek-able object.
+/// Read a packed XML data from an readable and seek-able object, the endianness is
+/// detected from the magic, see [`from_reader_endianness`].
 /// 
 /// *The content will be read starting from the initial position of the reader.*
-pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Box<Element>, DeError> {
+#[inline]
+pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Box<Element>, DeError> {
+    from_reader_endianness(reader).map(|(element, _)| element)
+}
+
+/// Read a packed XML data from an readable and seek-able object, also returning the
+/// endianness of the data, big-endian data being used by console versions.
+/// 
+/// *The content will be read starting from the initial position of the reader.*
+pub fn from_reader_endianness<R: Read + Seek>(mut reader: R) -> Result<(Box<Element>, Endianness), DeError> {
 
     // Validate file's magic
-    if !reader.check_exact(MAGIC)? {
-        return Err(DeError::InvalidMagic);
-    }
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    let endianness = match &magic {
+        MAGIC => Endianness::Little,
+        MAGIC_BIG_ENDIAN => Endianness::Big,
+        _ => return Err(DeError::InvalidMagic),
+    };
 
-    // Unknown byte
-    reader.skip::<1>()?;
+    // Unknown byte, kept to write it back.
+    let header = WgReadExt::read_u8(&mut reader)?;
 
     // Parsing
     let dict = read_dictionary(&mut reader)?;
     let mut element = Box::new(Element::new());
-    read_element(&mut reader, &mut *element, &dict[..])?;
-    Ok(element)
+    match endianness {
+        Endianness::Little => read_element::<LittleEndian, _>(&mut reader, &mut *element, &dict[..])?,
+        Endianness::Big => read_element::<BigEndian, _>(&mut reader, &mut *element, &dict[..])?,
+    }
+
+    if let Some(layout) = &mut element.layout {
+        layout.document = Some(DocumentLayout { endianness, header, dict });
+    }
+
+    Ok((element, endianness))
 
 }
 
@@ -55,78 +79,94 @@ fn read_dictionary<R: Read + Seek>(reader: &mut R) -> Result<Vec<String>, DeErro
 
 
 /// Internal function to read a data descriptor.
-fn read_data_descriptor<R: Read>(reader: &mut R) -> Result<DataDescriptor, DeError> {
-    let data_descriptor = reader.read_u32()?;
-    let raw_data_type = data_descriptor >> 28;
+fn read_data_descriptor<E: ByteOrder, R: Read>(reader: &mut R) -> Result<DataDescriptor, DeError> {
+    let data_descriptor = ReadBytesExt::read_u32::<E>(reader)?;
     Ok(DataDescriptor {
-        ty: DataType::from_raw(raw_data_type)
-            .ok_or(DeError::InvalidDataType(raw_data_type))?,
+        data_type: data_descriptor >> 28,
         end_offset: data_descriptor & 0x00FFFFFFF,
     })
 }
 
 
 /// Internal function to read a child descriptor (data + name).
-fn read_child_descriptor<R: Read>(reader: &mut R) -> Result<ChildDescriptor, DeError> {
+fn read_child_descriptor<E: ByteOrder, R: Read>(reader: &mut R) -> Result<ChildDescriptor, DeError> {
     Ok(ChildDescriptor {
-        name_index: reader.read_u16()? as usize,
-        data: read_data_descriptor(&mut *reader)?,
+        name_index: ReadBytesExt::read_u16::<E>(reader)? as usize,
+        data: read_data_descriptor::<E, _>(&mut *reader)?,
     })
 }
 
 
 /// Internal function that reads the current's element descriptor
 /// and its children.
-fn read_element<R: Read>(reader: &mut R, element: &mut Element, dict: &[String]) -> Result<(), DeError> {
+fn read_element<E: ByteOrder, R: Read>(reader: &mut R, element: &mut Element, dict: &[String]) -> Result<(), DeError> {
     
-    let children_count = reader.read_u16()? as usize;
-    let self_descriptor = read_data_descriptor(&mut *reader)?;
+    let children_count = ReadBytesExt::read_u16::<E>(reader)? as usize;
+    let self_descriptor = read_data_descriptor::<E, _>(&mut *reader)?;
     let mut children_descriptors = SmallVec::<[ChildDescriptor; 16]>::new();
     
     for _ in 0..children_count {
-        children_descriptors.push(read_child_descriptor(&mut *reader)?);
+        children_descriptors.push(read_child_descriptor::<E, _>(&mut *reader)?);
     }
 
-    read_data(&mut *reader, &mut element.value, &self_descriptor, dict, 0)?;
+    let mut layout = Box::new(ElementLayout::default());
+
+    layout.value = read_data::<E, _>(&mut *reader, &mut element.value, &self_descriptor, dict, 0)?;
     let mut offset = self_descriptor.end_offset;
 
     for child in children_descriptors {
         let mut value = Value::Boolean(false);
-        read_data(&mut *reader, &mut value, &child.data, dict, offset)?;
+        let mut value_layout = read_data::<E, _>(&mut *reader, &mut value, &child.data, dict, offset)?;
+        value_layout.name_index = child.name_index as u16;
         offset = child.data.end_offset;
         element.add_children(&dict[child.name_index], value);
+        layout.children.push(value_layout);
     }
 
+    element.layout = Some(layout);
     Ok(())
 
 }
 
 
-/// Internal function to read a value.
-fn read_data<R: Read>(reader: &mut R, value: &mut Value, desc: &DataDescriptor, dict: &[String], offset: u32) -> Result<(), DeError> {
+/// Internal function to read a value, returning its layout. Values of unknown data
+/// types, or that can't be decoded, are read as raw values.
+fn read_data<E: ByteOrder, R: Read>(reader: &mut R, value: &mut Value, desc: &DataDescriptor, dict: &[String], offset: u32) -> Result<ValueLayout, DeError> {
     let len = (desc.end_offset - offset) as usize;
-    *value = match desc.ty {
-        DataType::Element => {
+    *value = match DataType::from_raw(desc.data_type) {
+        Some(DataType::Element) => {
             let mut element = Box::new(Element::new());
-            read_element(reader, &mut *element, dict)?;
+            read_element::<E, _>(reader, &mut *element, dict)?;
             Value::Element(element)
         },
-        DataType::String => Value::String(read_string(reader, len)?),
-        DataType::Integer => Value::Integer(read_integer(reader, len)?),
-        DataType::Boolean => Value::Boolean(read_bool(reader, len)?),
-        DataType::CompressedString => Value::String(read_compressed_string(reader, len)?),
-        DataType::Vector => Value::Vector(Vector(read_vector(reader, len)?)),
+        Some(DataType::String) => read_string(reader, len)?,
+        Some(DataType::Integer) => Value::Integer(read_integer::<E, _>(reader, len)?),
+        Some(DataType::Boolean) => read_bool(reader, len)?,
+        Some(DataType::CompressedString) => Value::String(read_compressed_string(reader, len)?),
+        Some(DataType::Vector) => Value::Vector(Vector(read_vector::<E, _>(reader, len)?)),
+        None => Value::Raw(RawValue { 
+            data_type: desc.data_type as u8, 
+            data: reader.read_blob(len)?,
+        }),
     };
-    Ok(())
+    Ok(ValueLayout {
+        data_type: desc.data_type,
+        len: len as u32,
+        name_index: 0,
+        parsed: true,
+    })
 }
 
 
-/// Internal function to read a string of specific length.
-fn read_string<R: Read>(reader: &mut R, len: usize) -> Result<String, DeError> {
-    if len == 0 {
-        Ok("".to_string())
-    } else {
-        reader.read_string(len as usize).map_err(Into::into)
+/// Internal function to read a string of specific length, the string is kept raw if
+/// it's not valid UTF-8.
+fn read_string<R: Read>(reader: &mut R, len: usize) -> Result<Value, DeError> {
+    match String::from_utf8(reader.read_blob(len)?) {
+        Ok(s) => Ok(Value::String(s)),
+        Err(e) => Ok(Value::Raw(RawValue { 
+            data_type: DataType::String.to_raw() as u8, 
+            data: e.into_bytes(),
+        })),
     }
 }
 
@@ -139,39 +179,47 @@ fn read_compressed_string<R: Read>(reader: &mut R, len: usize) -> Result<String,
 
 
 /// Internal function to read a data from its descriptor and a reader.
-fn read_integer<R: Read>(reader: &mut R, len: usize) -> Result<i64, DeError> {
+fn read_integer<E: ByteOrder, R: Read>(reader: &mut R, len: usize) -> Result<i64, DeError> {
     match len {
         0 => Ok(0),
-        1 => Ok(reader.read_i8()? as i64),
-        2 => Ok(reader.read_i16()? as i64),
-        4 => Ok(reader.read_i32()? as i64),
-        8 => Ok(reader.read_i64()?),
+        1 => Ok(WgReadExt::read_i8(reader)? as i64),
+        2 => Ok(ReadBytesExt::read_i16::<E>(reader)? as i64),
+        4 => Ok(ReadBytesExt::read_i32::<E>(reader)? as i64),
+        8 => Ok(ReadBytesExt::read_i64::<E>(reader)?),
         _ => Err(DeError::InvalidIntegerLen(len))
     }
 }
 
 
-/// Internal function to read a boolean data.
-fn read_bool<R: Read>(reader: &mut R, len: usize) -> Result<bool, DeError> {
+/// Internal function to read a boolean data, the boolean is kept raw if its byte is
+/// neither 0 nor 1.
+fn read_bool<R: Read>(reader: &mut R, len: usize) -> Result<Value, DeError> {
     match len {
-        0 => Ok(false),
-        1 => Ok(reader.read_u8()? == 1),
+        0 => Ok(Value::Boolean(false)),
+        1 => match WgReadExt::read_u8(reader)? {
+            0 => Ok(Value::Boolean(false)),
+            1 => Ok(Value::Boolean(true)),
+            n => Ok(Value::Raw(RawValue { 
+                data_type: DataType::Boolean.to_raw() as u8, 
+                data: vec![n],
+            })),
+        }
         _ => Err(DeError::InvalidBoolLen(len))
     }
 }
 
 
 /// Internal function to read a 
-fn read_vector<R: Read>(reader: &mut R, len: usize) -> Result<SmallVec<[f32; 3]>, DeError> {
+fn read_vector<E: ByteOrder, R: Read>(reader: &mut R, len: usize) -> Result<SmallVec<[f32; 3]>, DeError> {
     
-    if len % 4 != 0 {
+    if !len.is_multiple_of(4) {
         return Err(DeError::InvalidVectorLen(len))
     }
 
     let n = len / 4;
     let mut res = SmallVec::new();
     for _ in 0..n {
-        res.push(reader.read_f32()?);
+        res.push(ReadBytesExt::read_f32::<E>(reader)?);
     }
 
     Ok(res)
@@ -181,8 +229,8 @@ fn read_vector<R: Read>(reader: &mut R, len: usize) -> Result<SmallVec<[f32; 3]>
 
 /// Internal data descriptor.
 struct DataDescriptor {
-    /// Type of data.
-    ty: DataType,
+    /// Raw type of data, on 4 bits.
+    data_type: u32,
     /// Offset of the end of the data, this can be used to
     /// compute data length if start address is known.
     end_offset: u32,
@@ -204,7 +252,8 @@ pub enum DeError {
     /// Invalid magic signature for the file.
     #[error("invalid magic")]
     InvalidMagic,
-    /// Invalid data type while parsing.
+    /// Invalid data type while parsing, no longer returned because unknown data types
+    /// are read as raw values.
     #[error("invalid data type id {0}")]
     InvalidDataType(u32),
     /// Invalid data size for a number.
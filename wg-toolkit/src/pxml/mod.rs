@@ -4,36 +4,85 @@
 //! some pattern can't be reproduced into. This is why a custom set of
 //! structures are introduced in this module to handle this case, such as
 //! [`Value`] and [`Element`].
+//! 
+//! Parsing is lossless: the layout of the parsed document (dictionary order, width of
+//! integers, string compression and unknown values) is kept alongside the elements,
+//! so that writing an untouched document gives the exact same bytes. This layout is
+//! only a hint for values that are not modified, see [`verify_round_trip`].
+//! 
+//! Elements can also be converted to and from clear XML documents, with their types
+//! being inferred from the text, see [`to_xml_writer`] and [`from_xml_reader`].
+//! 
+//! Typed values can be deserialized from elements, and serialized to elements, through
+//! serde, see [`from_reader_as`] and [`to_element`].
+//! 
+//! Nested values can be selected with slash-separated paths, see [`Element::query`].
 
+use std::borrow::Cow;
 use std::ops::Index;
 use std::slice;
 
 use glam::{Affine3A, Vec2, Vec3, Vec4};
 use smallvec::SmallVec;
+use base64::Engine;
 
 mod de;
 mod ser;
+mod xml;
+mod typed;
+mod query;
 
-pub use de::{from_reader, from_bytes, DeError};
-pub use ser::to_writer;
+pub use de::{from_reader, from_reader_endianness, from_bytes, DeError};
+pub use ser::{to_writer, to_writer_endianness, to_bytes};
+pub use xml::{to_xml_writer, to_xml_string, from_xml_reader, from_xml_str, XmlError};
+pub use typed::{from_element, from_value, from_reader_as, to_element, to_value, SerdeError, PROPER_VALUE_FIELD};
+pub use query::QueryError;
 
 
 /// Magic of a packed XML file.
 pub const MAGIC: &[u8; 4] = b"\x45\x4E\xA1\x62";
 
+/// Magic of a big-endian packed XML file, as used by console versions of the engine,
+/// this is the byte-swapped little-endian magic.
+pub const MAGIC_BIG_ENDIAN: &[u8; 4] = b"\x62\xA1\x4E\x45";
+
+
+/// Byte order of the numbers in a packed XML file, all numbers including descriptors
+/// are stored in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    /// Used by all desktop versions.
+    #[default]
+    Little,
+    /// Used by console versions.
+    Big,
+}
+
 
 /// A packed XML untyped value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Element(Box<Element>),
     String(String),
     Integer(i64),
     Boolean(bool),
     Vector(Vector),
+    /// A value of an unknown data type, or that can't be decoded with its data type,
+    /// such as non UTF-8 strings. This is kept as-is when writing.
+    Raw(RawValue),
+}
+
+/// The raw payload of a value that can't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawValue {
+    /// The raw data type, on 4 bits.
+    pub data_type: u8,
+    /// The payload.
+    pub data: Vec<u8>,
 }
 
 /// A packed XML f32 vector of values, this may contains one value or more.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vector(SmallVec<[f32; 3]>);
 
 /// A packed element.
@@ -44,6 +93,8 @@ pub struct Element {
     /// Children values, each value is mapped to a name that
     /// is not guaranteed to be unique.
     children: SmallVec<[(String, Value); 8]>,
+    /// Layout of the element when parsed, not considered when comparing elements.
+    layout: Option<Box<ElementLayout>>,
 }
 
 impl Element {
@@ -52,6 +103,7 @@ impl Element {
         Self { 
             value: Value::default(), 
             children: SmallVec::new(),
+            layout: None,
         }
     }
 
@@ -68,7 +120,7 @@ impl Element {
     }
 
     pub fn add_children<S: Into<String>>(&mut self, key: S, value: Value) {
-        self.children.push((key.into(), value));
+        self.push_child(key.into(), value);
     }
 
     pub fn iter_children<'k, 's: 'k>(&'s self, key: &'k str) -> impl Iterator<Item = &'s Value> + DoubleEndedIterator + 'k {
@@ -89,6 +141,9 @@ impl Element {
 
     pub fn insert_child(&mut self, index: usize, name: String, value: Value) -> &'_ mut Value {
         self.children.insert(index, (name, value));
+        if let Some(layout) = &mut self.layout {
+            layout.children.insert(index, ValueLayout::default());
+        }
         &mut self.children[index].1
     }
 
@@ -96,6 +151,79 @@ impl Element {
         self.insert_child(self.children.len(), name, value)
     }
 
+    /// Return the type of the proper value as it was parsed, none if the element has
+    /// not been parsed. This is not updated if the value is modified.
+    pub fn parsed_type(&self) -> Option<ParsedType> {
+        ParsedType::from_layout(&self.layout.as_ref()?.value)
+    }
+
+    /// Return the type of the child at the given index as it was parsed, none if the
+    /// child has not been parsed, such as inserted children. This is not updated if the
+    /// child's value is modified.
+    pub fn child_parsed_type(&self, index: usize) -> Option<ParsedType> {
+        ParsedType::from_layout(self.layout.as_ref()?.children.get(index)?)
+    }
+
+    /// Get the first child with the given key as an element.
+    #[inline]
+    pub fn get_element(&self, key: &str) -> Option<&Element> {
+        self.get_child(key)?.as_element()
+    }
+
+    /// Get the first child with the given key as a string.
+    #[inline]
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        self.get_child(key)?.as_string()
+    }
+
+    /// Get the first child with the given key as an integer.
+    #[inline]
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get_child(key)?.as_integer()
+    }
+
+    /// Get the first child with the given key as a boolean.
+    #[inline]
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_child(key)?.as_boolean()
+    }
+
+    /// Get the first child with the given key as a float, see [`Value::as_float`].
+    #[inline]
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_child(key)?.as_float()
+    }
+
+    /// Get the first child with the given key as a vec2, see [`Value::as_vec2`].
+    #[inline]
+    pub fn get_vec2(&self, key: &str) -> Option<Vec2> {
+        self.get_child(key)?.as_vec2()
+    }
+
+    /// Get the first child with the given key as a vec3, see [`Value::as_vec3`].
+    #[inline]
+    pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+        self.get_child(key)?.as_vec3()
+    }
+
+    /// Get the first child with the given key as a vec4, see [`Value::as_vec4`].
+    #[inline]
+    pub fn get_vec4(&self, key: &str) -> Option<Vec4> {
+        self.get_child(key)?.as_vec4()
+    }
+
+    /// Get the first child with the given key as an affine3.
+    #[inline]
+    pub fn get_affine3(&self, key: &str) -> Option<Affine3A> {
+        self.get_child(key)?.as_affine3()
+    }
+
+    /// Get the first child with the given key as binary data, see [`Value::as_blob`].
+    #[inline]
+    pub fn get_blob(&self, key: &str) -> Option<Cow<'_, [u8]>> {
+        self.get_child(key)?.as_blob()
+    }
+
 }
 
 impl Value {
@@ -189,6 +317,26 @@ impl Value {
         self.as_vector()?.as_affine3()
     }
 
+    /// Try to get this value as binary data if possible.
+    /// 
+    /// Binary data is stored as a compressed string, which is read as its base64
+    /// encoding, so strings are decoded from base64. The data of raw values is also
+    /// returned as-is.
+    pub fn as_blob(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            Self::String(s) => base64::prelude::BASE64_STANDARD.decode(s.as_bytes()).ok().map(Cow::Owned),
+            Self::Raw(raw) => Some(Cow::Borrowed(&raw.data)),
+            _ => None
+        }
+    }
+
+}
+
+/// Elements are compared without their parsed layout.
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.children == other.children
+    }
 }
 
 /// Default value is an empty string, which do no allocation.
@@ -278,6 +426,102 @@ impl Index<usize> for Vector {
 }
 
 
+/// Check that the given packed XML data is written back to the exact same bytes once
+/// parsed, returning the offset of the first differing byte if not.
+pub fn verify_round_trip<B: AsRef<[u8]>>(data: B) -> Result<Option<usize>, DeError> {
+
+    let data = data.as_ref();
+    let element = from_bytes(data)?;
+
+    let written = to_bytes(&element);
+
+    if written == data {
+        return Ok(None);
+    }
+
+    let offset = data.iter().zip(&written)
+        .position(|(a, b)| a != b)
+        .unwrap_or(data.len().min(written.len()));
+
+    Ok(Some(offset))
+
+}
+
+
+/// The type of a value as it was parsed, including details that are not kept in the
+/// value itself, see [`Element::child_parsed_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsedType {
+    Element,
+    String,
+    /// Binary data, read as a base64 string, see [`Value::as_blob`].
+    Blob,
+    /// An integer stored on the given number of bytes, zero being stored on no byte.
+    Integer(u8),
+    Boolean,
+    /// A vector of the given number of floats.
+    Vector(u8),
+    /// An unknown data type, read as a raw value.
+    Raw,
+}
+
+impl ParsedType {
+
+    fn from_layout(layout: &ValueLayout) -> Option<Self> {
+        if !layout.parsed {
+            return None;
+        }
+        Some(match DataType::from_raw(layout.data_type) {
+            Some(DataType::Element) => Self::Element,
+            Some(DataType::String) => Self::String,
+            Some(DataType::CompressedString) => Self::Blob,
+            Some(DataType::Integer) => Self::Integer(layout.len as u8),
+            Some(DataType::Boolean) => Self::Boolean,
+            Some(DataType::Vector) => Self::Vector((layout.len / 4) as u8),
+            None => Self::Raw,
+        })
+    }
+
+}
+
+
+/// Layout of a parsed element, used to write it back identically.
+#[derive(Debug, Clone, Default)]
+struct ElementLayout {
+    /// Layout of the element's proper value.
+    value: ValueLayout,
+    /// Layout of each child, kept in the same order as the children.
+    children: SmallVec<[ValueLayout; 8]>,
+    /// Only present on the root element.
+    document: Option<DocumentLayout>,
+}
+
+/// Layout of a parsed document.
+#[derive(Debug, Clone)]
+struct DocumentLayout {
+    /// Endianness of the document.
+    endianness: Endianness,
+    /// The byte following the magic, its meaning is unknown.
+    header: u8,
+    /// The names dictionary in its original order, possibly with unused names.
+    dict: Vec<String>,
+}
+
+/// Layout of a parsed value, the default layout is used for values that have not been
+/// parsed, and therefore written with the most compact encoding.
+#[derive(Debug, Clone, Copy, Default)]
+struct ValueLayout {
+    /// The raw data type, on 4 bits.
+    data_type: u32,
+    /// Length of the value's data.
+    len: u32,
+    /// The dictionary index of the child's name, unused for proper values.
+    name_index: u16,
+    /// True if this layout comes from a parsed value.
+    parsed: bool,
+}
+
+
 /// Internally used data types for values.
 #[derive(Debug, Clone, Copy)]
 enum DataType {
@@ -0,0 +1,237 @@
+//! Path queries over Packed XML elements.
+
+use thiserror::Error;
+
+use super::{Element, Value};
+
+
+impl Element {
+
+    /// Return all values matching the given path, in document order. A path is a list
+    /// of segments separated by '/', each segment selects children of the elements
+    /// matched by the previous segments:
+    /// - `name` selects all children with the given name;
+    /// - `name[N]` selects the Nth child with the given name, starting at 0;
+    /// - `*` selects all children, whatever their name;
+    /// - `^` selects the proper value of the element, only as the last segment.
+    /// 
+    /// An empty list is returned if no value matches, an error is only returned if the
+    /// path is invalid.
+    pub fn query(&self, path: &str) -> Result<Vec<&Value>, QueryError> {
+        let segments = parse_path(path)?;
+        let mut values = Vec::new();
+        walk(self, &segments, &mut values);
+        Ok(values)
+    }
+
+    /// Return the first value matching the given path, see [`Self::query`]. If no value
+    /// matches, the returned error tells the first segment that doesn't match.
+    pub fn query_one(&self, path: &str) -> Result<&Value, QueryError> {
+
+        let segments = parse_path(path)?;
+        let mut values = Vec::new();
+        walk(self, &segments, &mut values);
+
+        if let Some(value) = values.first() {
+            return Ok(value);
+        }
+
+        // Find the longest prefix that still matches some element, to tell which
+        // segment is not found. The empty prefix always matches this element.
+        let mut len = segments.len() - 1;
+        while len > 0 {
+            values.clear();
+            walk(self, &segments[..len], &mut values);
+            if values.iter().any(|value| matches!(value, Value::Element(_))) {
+                break;
+            }
+            len -= 1;
+        }
+
+        Err(QueryError::NotFound {
+            parent: segments[..len].iter().map(|segment| segment.raw).collect::<Vec<_>>().join("/"),
+            segment: segments[len].raw.to_string(),
+        })
+
+    }
+
+    /// Return the first element matching the given path, see [`Self::query_one`].
+    pub fn query_element(&self, path: &str) -> Result<&Element, QueryError> {
+        self.query_one(path)?
+            .as_element()
+            .ok_or_else(|| QueryError::NotElement(path.to_string()))
+    }
+
+}
+
+
+/// A parsed segment of a query path.
+#[derive(Debug)]
+struct Segment<'a> {
+    /// The raw segment, for errors.
+    raw: &'a str,
+    /// The kind of selection.
+    kind: SegmentKind<'a>,
+}
+
+#[derive(Debug)]
+enum SegmentKind<'a> {
+    /// Children with the given name, optionally only the Nth one.
+    Child(&'a str, Option<usize>),
+    /// All children.
+    Any,
+    /// The proper value of the element.
+    Proper,
+}
+
+/// Internal function to parse a path into its segments.
+fn parse_path(path: &str) -> Result<Vec<Segment<'_>>, QueryError> {
+
+    let invalid = || QueryError::InvalidPath(path.to_string());
+
+    let mut segments = Vec::new();
+    for raw in path.trim_matches('/').split('/') {
+
+        let kind = match raw {
+            "" => return Err(invalid()),
+            "*" => SegmentKind::Any,
+            "^" => SegmentKind::Proper,
+            _ => {
+                if let Some((name, index)) = raw.strip_suffix(']').and_then(|raw| raw.split_once('[')) {
+                    let index = index.parse::<usize>().map_err(|_| invalid())?;
+                    if name.is_empty() {
+                        return Err(invalid());
+                    }
+                    SegmentKind::Child(name, Some(index))
+                } else if raw.contains(['[', ']', '*', '^']) {
+                    return Err(invalid());
+                } else {
+                    SegmentKind::Child(raw, None)
+                }
+            }
+        };
+
+        segments.push(Segment { raw, kind });
+
+    }
+
+    // The proper value has no children, so it can only be selected last.
+    if segments.iter().rev().skip(1).any(|segment| matches!(segment.kind, SegmentKind::Proper)) {
+        return Err(invalid());
+    }
+
+    Ok(segments)
+
+}
+
+/// Internal function to collect all values matching the given segments.
+fn walk<'a>(element: &'a Element, segments: &[Segment], values: &mut Vec<&'a Value>) {
+
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+
+    let mut visit = |value: &'a Value| {
+        if rest.is_empty() {
+            values.push(value);
+        } else if let Value::Element(child) = value {
+            walk(child, rest, values);
+        }
+    };
+
+    match segment.kind {
+        SegmentKind::Child(name, None) => element.iter_children(name).for_each(&mut visit),
+        SegmentKind::Child(name, Some(index)) => element.iter_children(name).nth(index).into_iter().for_each(&mut visit),
+        SegmentKind::Any => element.iter_children_all().map(|(_, value)| value).for_each(&mut visit),
+        SegmentKind::Proper => visit(&element.value),
+    }
+
+}
+
+
+/// Error that can happen while querying an element.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// The path has an invalid syntax.
+    #[error("invalid query path '{0}'")]
+    InvalidPath(String),
+    /// No value matches the path, the first segment that doesn't match is given with
+    /// the path of its parent.
+    #[error("no '{segment}' in '/{parent}'")]
+    NotFound {
+        parent: String,
+        segment: String,
+    },
+    /// The value matching the path is not an element.
+    #[error("'{0}' is not an element")]
+    NotElement(String),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn element() -> Element {
+
+        let vehicle = |name: &str, crew: i64| {
+            let mut gun = Element::new();
+            gun.value = Value::Integer(crew * 10);
+            let mut vehicle = Element::new();
+            vehicle.value = Value::String(name.to_string());
+            vehicle.add_children("gun", Value::Element(Box::new(gun)));
+            vehicle.add_children("crew", Value::Integer(crew));
+            Value::Element(Box::new(vehicle))
+        };
+
+        let mut element = Element::new();
+        element.add_children("vehicle", vehicle("T-34", 4));
+        element.add_children("name", Value::String("tanks".to_string()));
+        element.add_children("vehicle", vehicle("KV-1", 5));
+        element
+
+    }
+
+    #[test]
+    fn query() {
+
+        let element = element();
+
+        assert_eq!(element.query("vehicle/crew").unwrap(), [&Value::Integer(4), &Value::Integer(5)]);
+        assert_eq!(element.query("/vehicle[1]/crew/").unwrap(), [&Value::Integer(5)]);
+        assert_eq!(element.query("vehicle/^").unwrap(), [&Value::String("T-34".to_string()), &Value::String("KV-1".to_string())]);
+        assert_eq!(element.query("vehicle/gun/^").unwrap(), [&Value::Integer(40), &Value::Integer(50)]);
+        assert_eq!(element.query("*").unwrap().len(), 3);
+        assert_eq!(element.query("*/crew").unwrap().len(), 2);
+        assert!(element.query("vehicle[2]").unwrap().is_empty());
+        assert!(element.query("name/crew").unwrap().is_empty());
+
+    }
+
+    #[test]
+    fn query_invalid() {
+        let element = element();
+        for path in ["", "/", "vehicle//crew", "vehicle[x]", "[0]", "vehicle[0", "veh*", "^/crew"] {
+            assert!(matches!(element.query(path), Err(QueryError::InvalidPath(p)) if p == path), "{path}");
+        }
+    }
+
+    #[test]
+    fn query_one() {
+
+        let element = element();
+
+        assert_eq!(element.query_one("vehicle/crew").unwrap(), &Value::Integer(4));
+        assert_eq!(element.query_element("vehicle[1]").unwrap().value, Value::String("KV-1".to_string()));
+
+        let Err(QueryError::NotFound { parent, segment }) = element.query_one("vehicle/gun/barrel") else { panic!("expected not found") };
+        assert_eq!((parent.as_str(), segment.as_str()), ("vehicle/gun", "barrel"));
+        let Err(QueryError::NotFound { parent, segment }) = element.query_one("turret/gun") else { panic!("expected not found") };
+        assert_eq!((parent.as_str(), segment.as_str()), ("", "turret"));
+
+        assert!(matches!(element.query_element("name"), Err(QueryError::NotElement(p)) if p == "name"));
+
+    }
+
+}
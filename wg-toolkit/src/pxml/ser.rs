@@ -1,53 +1,123 @@
 //! Serialization module for Packed XML.
 
-use std::io::{self, Write, Seek, SeekFrom};
-use std::collections::hash_map::Entry;
+use std::io::{self, Write, Seek, SeekFrom, Cursor};
 use std::collections::HashMap;
 
+use byteorder::{ByteOrder, BigEndian, LittleEndian, WriteBytesExt};
 use smallvec::SmallVec;
 use base64::Engine;
 
 use crate::util::io::WgWriteExt;
 
-use super::{MAGIC, Element, Value, DataType};
+use super::{MAGIC, MAGIC_BIG_ENDIAN, Element, Endianness, Value, DataType, ValueLayout};
 
 
-/// Write the given Packed XML element to the given seekable writer.
+/// Write the given Packed XML element to the given seekable writer, with the 
+/// endianness of the parsed document, or little-endian if the element was not parsed.
 /// 
 /// *The content will be written starting from the inital position
 /// of the writer.*
-pub fn to_writer<W: Write + Seek>(mut writer: W, element: &Element) -> io::Result<()> {
+#[inline]
+pub fn to_writer<W: Write + Seek>(writer: W, element: &Element) -> io::Result<()> {
+    let endianness = element.layout.as_ref()
+        .and_then(|layout| layout.document.as_ref())
+        .map(|document| document.endianness)
+        .unwrap_or_default();
+    to_writer_endianness(writer, element, endianness)
+}
 
-    // Write the magic number.
-    writer.write_all(MAGIC)?;
-    writer.write_u8(0)?;
+/// Write the given Packed XML element to the given seekable writer, with the given 
+/// endianness, big-endian being used by console versions.
+/// 
+/// *The content will be written starting from the inital position
+/// of the writer.*
+pub fn to_writer_endianness<W: Write + Seek>(mut writer: W, element: &Element, endianness: Endianness) -> io::Result<()> {
 
-    let mut dict = HashMap::new();
+    // Write the magic number.
+    writer.write_all(match endianness {
+        Endianness::Little => MAGIC,
+        Endianness::Big => MAGIC_BIG_ENDIAN,
+    })?;
+
+    let document = element.layout.as_ref().and_then(|layout| layout.document.as_ref());
+    WgWriteExt::write_u8(&mut writer, document.map(|document| document.header).unwrap_or(0))?;
+
+    let mut dict = Dictionary::default();
+    // The dictionary of a parsed document is written first, in its original order.
+    if let Some(document) = document {
+        for name in &document.dict {
+            dict.push(&mut writer, name)?;
+        }
+    }
     // Walk the entire tree to map each child name to a unique index,
     // each new index is also written to the writer as a cstring.
-    write_and_fill_dict(&mut writer, element, &mut dict, &mut 0)?;
+    write_and_fill_dict(&mut writer, element, &mut dict)?;
     // Write a last empty cstring to mark the end.
     writer.write_cstring("")?;
 
     // Finally write the root element.
-    write_element(&mut writer, element, &dict).map(|_| ())
+    match endianness {
+        Endianness::Little => write_element::<LittleEndian, _>(&mut writer, element, &dict).map(|_| ()),
+        Endianness::Big => write_element::<BigEndian, _>(&mut writer, element, &dict).map(|_| ()),
+    }
 
 }
 
 
+/// Write the given Packed XML element to raw bytes, see [`to_writer`].
+pub fn to_bytes(element: &Element) -> Vec<u8> {
+    let mut data = Vec::new();
+    // Unwrapping because writing to an in-memory cursor cannot fail.
+    to_writer(Cursor::new(&mut data), element).unwrap();
+    data
+}
+
+
+/// Internal dictionary of names being written.
+#[derive(Default)]
+struct Dictionary<'a> {
+    /// All names, in order, possibly with duplicates if parsed so.
+    names: Vec<&'a str>,
+    /// Index of the first occurrence of each name.
+    indices: HashMap<&'a str, u16>,
+}
+
+impl<'a> Dictionary<'a> {
+
+    /// Write the name and add it to the dictionary.
+    fn push<W: Write>(&mut self, writer: &mut W, name: &'a str) -> io::Result<()> {
+        writer.write_cstring(name)?;
+        let index = self.names.len() as u16;
+        self.names.push(name);
+        self.indices.entry(name).or_insert(index);
+        Ok(())
+    }
+
+    /// Get the index of the given name, the parsed index of the child is used if
+    /// it still points to the same name.
+    fn index(&self, name: &str, layout: &ValueLayout) -> u16 {
+        if layout.parsed && self.names.get(layout.name_index as usize) == Some(&name) {
+            layout.name_index
+        } else {
+            // NOTE: Dictionary fetching should not panic since we constructed the 
+            // dictionary from all children.
+            self.indices[name]
+        }
+    }
+
+}
+
 /// Internal function to analyze and fill the node's name dictionary.
-fn write_and_fill_dict<'a, W: Write + Seek>(writer: &mut W, element: &'a Element, dict: &mut HashMap<&'a String, u16>, next_index: &mut u16) -> io::Result<()> {
+fn write_and_fill_dict<'a, W: Write + Seek>(writer: &mut W, element: &'a Element, dict: &mut Dictionary<'a>) -> io::Result<()> {
     
     for (k, v) in &element.children {
 
-        if let Entry::Vacant(v) = dict.entry(k) {
-            writer.write_cstring(k)?;
-            v.insert(*next_index);
-            *next_index += 1;
+        if !dict.indices.contains_key(k.as_str()) {
+            dict.push(&mut *writer, k)?;
         }
 
         if let Value::Element(child_element) = v {
-            write_and_fill_dict(&mut *writer, &*child_element, &mut *dict, &mut *next_index)?;
+            write_and_fill_dict(&mut *writer, &*child_element, &mut *dict)?;
         }
 
     }
@@ -57,22 +127,26 @@ fn write_and_fill_dict<'a, W: Write + Seek>(writer: &mut W, element: &'a Element
 }
 
 
-fn write_element<W: Write + Seek>(writer: &mut W, element: &Element, dict: &HashMap<&String, u16>) -> io::Result<usize> {
+fn write_element<E: ByteOrder, W: Write + Seek>(writer: &mut W, element: &Element, dict: &Dictionary) -> io::Result<usize> {
 
     let self_start_offset = writer.stream_position()?;
 
-    writer.write_u16(element.children.len() as u16)?;
+    WriteBytesExt::write_u16::<E>(writer, element.children.len() as u16)?;
 
     // Here we write placeholder descriptors, that will be later written.
     // Save the start offset of the element.
-    writer.write_u32(0)?;
+    WriteBytesExt::write_u32::<E>(writer, 0)?;
     for _ in 0..element.children.len() {
-        writer.write_u16(0)?;
-        writer.write_u32(0)?;
+        WriteBytesExt::write_u16::<E>(writer, 0)?;
+        WriteBytesExt::write_u32::<E>(writer, 0)?;
     }
 
+    // Layout of the parsed element, if any.
+    let layout = element.layout.as_deref();
+    let self_layout = layout.map(|layout| layout.value).unwrap_or_default();
+
     // Write element's value.
-    let (self_ty, self_len) = write_value(&mut *writer, &element.value, dict)?;
+    let (self_ty, self_len) = write_value::<E, _>(&mut *writer, &element.value, &self_layout, dict)?;
     let mut offset = self_len;
 
     // Save descriptors for future writes.
@@ -80,23 +154,22 @@ fn write_element<W: Write + Seek>(writer: &mut W, element: &Element, dict: &Hash
     let mut children_descriptors = SmallVec::<[(u16, u32); 16]>::new();
 
     // Write element's children.
-    for (k, child_value) in &element.children {
-        let (child_ty, child_len) = write_value(&mut *writer, &child_value, dict)?;
+    for (i, (k, child_value)) in element.children.iter().enumerate() {
+        let child_layout = layout.and_then(|layout| layout.children.get(i)).copied().unwrap_or_default();
+        let (child_ty, child_len) = write_value::<E, _>(&mut *writer, &child_value, &child_layout, dict)?;
         offset += child_len;
         let child_descriptor = calc_data_descriptor(child_ty, offset);
-        // NOTE: Dictionary fetching should not panic since we constructed the 
-        // dictionary depending on 
-        children_descriptors.push((dict[k], child_descriptor));
+        children_descriptors.push((dict.index(k, &child_layout), child_descriptor));
     }
 
     let self_end_offset = writer.stream_position()?;
 
     // Finally write descriptors 2 octets (children count) after start.
     writer.seek(SeekFrom::Start(self_start_offset + 2))?;
-    writer.write_u32(self_descriptor)?;
+    WriteBytesExt::write_u32::<E>(writer, self_descriptor)?;
     for (name_index, data) in children_descriptors {
-        writer.write_u16(name_index)?;
-        writer.write_u32(data)?;
+        WriteBytesExt::write_u16::<E>(writer, name_index)?;
+        WriteBytesExt::write_u32::<E>(writer, data)?;
     }
 
     // Jump back to the end of the element.
@@ -109,52 +182,81 @@ fn write_element<W: Write + Seek>(writer: &mut W, element: &Element, dict: &Hash
 
 
 /// Internal function to write a value and return the size used to write it.
-/// The returned data type is used to compute the data descriptor.
-fn write_value<W: Write + Seek>(writer: &mut W, value: &Value, dict: &HashMap<&String, u16>) -> io::Result<(DataType, usize)> {
+/// The returned raw data type is used to compute the data descriptor. The layout
+/// of the parsed value is followed if it's still compatible with the value.
+fn write_value<E: ByteOrder, W: Write + Seek>(writer: &mut W, value: &Value, layout: &ValueLayout, dict: &Dictionary) -> io::Result<(u32, usize)> {
 
     // Returned length should perfectly match written data.
 
+    let parsed_as = |ty: DataType| layout.parsed && layout.data_type == ty.to_raw();
+
     match value {
         Value::Element(child_element) => {
-            write_element(writer, &*child_element, dict).map(|len| (DataType::Element, len))
+            write_element::<E, _>(writer, &*child_element, dict).map(|len| (DataType::Element.to_raw(), len))
         }
         Value::String(s) => {
-            // Here we check if the input can possibly be compressed.
-            if !s.is_empty() && s.len() % 4 == 0 {
+            // Here we check if the input can possibly be compressed, strings that were
+            // parsed keep their original compression.
+            let compress = if parsed_as(DataType::String) {
+                false
+            } else if parsed_as(DataType::CompressedString) {
+                true
+            } else {
+                !s.is_empty() && s.len() % 4 == 0
+            };
+            if compress {
                 if let Ok(compressed) = base64::prelude::BASE64_STANDARD.decode(s.as_bytes()) {
                     writer.write_all(&compressed[..])?;
-                    return Ok((DataType::CompressedString, compressed.len()))
+                    return Ok((DataType::CompressedString.to_raw(), compressed.len()))
                 }
             }
             writer.write_string(s)?;
-            Ok((DataType::String, s.len()))
+            Ok((DataType::String.to_raw(), s.len()))
         }
         &Value::Integer(n) => {
-            let len = if n == 0 {
+            let min_len = if n == 0 {
                 0 // Zero is optimized out.
-            } else if let Ok(n) = i8::try_from(n) {
-                writer.write_i8(n)?; 1
-            } else if let Ok(n) = i16::try_from(n) {
-                writer.write_i16(n)?; 2
-            } else if let Ok(n) = i32::try_from(n) {
-                writer.write_i32(n)?; 4
+            } else if i8::try_from(n).is_ok() {
+                1
+            } else if i16::try_from(n).is_ok() {
+                2
+            } else if i32::try_from(n).is_ok() {
+                4
             } else {
-                writer.write_i64(n)?; 8
+                8
+            };
+            // Parsed integers may be wider than needed.
+            let len = match layout.len as usize {
+                len @ (1 | 2 | 4 | 8) if parsed_as(DataType::Integer) && len >= min_len => len,
+                _ => min_len,
             };
-            Ok((DataType::Integer, len))
+            match len {
+                0 => {}
+                1 => WgWriteExt::write_i8(writer, n as i8)?,
+                2 => WriteBytesExt::write_i16::<E>(writer, n as i16)?,
+                4 => WriteBytesExt::write_i32::<E>(writer, n as i32)?,
+                _ => WriteBytesExt::write_i64::<E>(writer, n)?,
+            }
+            Ok((DataType::Integer.to_raw(), len))
         },
         &Value::Boolean(b) => {
-            // Only write an octet if true.
-            if b {
-                writer.write_u8(1)?;
+            // Only write an octet if true, or if parsed with an octet.
+            if b || (parsed_as(DataType::Boolean) && layout.len == 1) {
+                WgWriteExt::write_u8(writer, b as u8)?;
+                Ok((DataType::Boolean.to_raw(), 1))
+            } else {
+                Ok((DataType::Boolean.to_raw(), 0))
             }
-            Ok((DataType::Boolean, if b { 1 } else { 0 }))
         }
         Value::Vector(v) => {
             for &comp in &v.0 {
-                writer.write_f32(comp)?;
+                WriteBytesExt::write_f32::<E>(writer, comp)?;
             }
-            Ok((DataType::Vector, 4 * v.len()))
+            Ok((DataType::Vector.to_raw(), 4 * v.len()))
+        }
+        Value::Raw(raw) => {
+            writer.write_all(&raw.data)?;
+            Ok((raw.data_type as u32 & 0xF, raw.data.len()))
         }
     }
 
@@ -162,6 +264,169 @@ fn write_value<W: Write + Seek>(writer: &mut W, value: &Value, dict: &HashMap<&S
 
 
 #[inline]
-fn calc_data_descriptor(ty: DataType, offset: usize) -> u32 {
-    (ty.to_raw() << 28) | (offset as u32 & 0x00FFFFFFF)
+fn calc_data_descriptor(ty: u32, offset: usize) -> u32 {
+    (ty << 28) | (offset as u32 & 0x00FFFFFFF)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use smallvec::smallvec;
+
+    use super::*;
+    use super::super::{from_bytes, from_reader_endianness, verify_round_trip, RawValue, Vector};
+
+    /// A hand-made document with layouts that are not the most compact ones: unused
+    /// and reordered dictionary names, a small integer on 4 bytes, a false boolean on
+    /// 1 byte, a raw value of an unknown type and duplicated child names.
+    fn document() -> Vec<u8> {
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.push(0);
+        data.extend_from_slice(b"b\0unused\0a\0\0");
+
+        let desc = |ty: u32, end: u32| (ty << 28) | end;
+
+        // Root element, 4 children.
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&desc(1, 2).to_le_bytes());
+        for (name_index, data_desc) in [(2u16, desc(2, 6)), (0, desc(4, 7)), (2, desc(7, 10)), (0, desc(0, 16))] {
+            data.extend_from_slice(&name_index.to_le_bytes());
+            data.extend_from_slice(&data_desc.to_le_bytes());
+        }
+        data.extend_from_slice(b"hi");
+        data.extend_from_slice(&5i32.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&[1, 2, 3]);
+        // Nested element, no children and a zero integer on no byte.
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&desc(2, 0).to_le_bytes());
+
+        data
+
+    }
+
+    fn element() -> Element {
+
+        let mut child = Element::new();
+        child.value = Value::Vector(Vector(smallvec![1.0, -2.5, 3.0]));
+        child.add_children("name", Value::String("hello world".to_string()));
+        child.add_children("blob", Value::String("AAECAw==".to_string()));
+        child.add_children("empty", Value::String(String::new()));
+
+        let mut element = Element::new();
+        element.value = Value::Integer(-1);
+        element.add_children("child", Value::Element(Box::new(child)));
+        element.add_children("small", Value::Integer(12));
+        element.add_children("large", Value::Integer(1 << 40));
+        element.add_children("zero", Value::Integer(0));
+        element.add_children("true", Value::Boolean(true));
+        element.add_children("false", Value::Boolean(false));
+        element.add_children("small", Value::Integer(-300));
+        element.add_children("raw", Value::Raw(RawValue { data_type: 7, data: vec![0xFF, 0x00] }));
+        element
+
+    }
+
+    #[test]
+    fn round_trip_parsed() {
+
+        let data = document();
+        let element = from_bytes(&data).unwrap();
+
+        assert_eq!(element.value, Value::String("hi".to_string()));
+        let children = element.iter_children_all().cloned().collect::<Vec<_>>();
+        let mut nested = Element::new();
+        nested.value = Value::Integer(0);
+        assert_eq!(children, [
+            ("a".to_string(), Value::Integer(5)),
+            ("b".to_string(), Value::Boolean(false)),
+            ("a".to_string(), Value::Raw(RawValue { data_type: 7, data: vec![1, 2, 3] })),
+            ("b".to_string(), Value::Element(Box::new(nested))),
+        ]);
+
+        assert_eq!(to_bytes(&element), data);
+        assert_eq!(verify_round_trip(&data).unwrap(), None);
+
+    }
+
+    #[test]
+    fn round_trip_modified() {
+
+        let mut element = from_bytes(document()).unwrap();
+        *element.get_child_mut("a").unwrap() = Value::Integer(100_000);
+        element.add_children("c", Value::String("new".to_string()));
+
+        let data = to_bytes(&element);
+        let read_element = from_bytes(&data).unwrap();
+        assert_eq!(read_element, element);
+        // Once written, the layout is stable.
+        assert_eq!(to_bytes(&read_element), data);
+
+    }
+
+    #[test]
+    fn round_trip_new() {
+
+        let element = element();
+        let data = to_bytes(&element);
+
+        let read_element = from_bytes(&data).unwrap();
+        assert_eq!(*read_element, element);
+        assert_eq!(to_bytes(&read_element), data);
+
+    }
+
+    #[test]
+    fn round_trip_big_endian() {
+
+        let element = element();
+        let mut data = Vec::new();
+        to_writer_endianness(Cursor::new(&mut data), &element, Endianness::Big).unwrap();
+        assert_eq!(&data[..4], MAGIC_BIG_ENDIAN);
+
+        let (read_element, endianness) = from_reader_endianness(Cursor::new(&data)).unwrap();
+        assert_eq!(endianness, Endianness::Big);
+        assert_eq!(*read_element, element);
+        // The endianness of the parsed document is kept.
+        assert_eq!(to_bytes(&read_element), data);
+
+    }
+
+    #[test]
+    fn round_trip_big_endian_parsed() {
+
+        // A hand-made big-endian document, as found in console versions, with an
+        // integer on 2 bytes and a vector whose components must be swapped.
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC_BIG_ENDIAN);
+        data.push(0);
+        data.extend_from_slice(b"a\0v\0\0");
+
+        let desc = |ty: u32, end: u32| (ty << 28) | end;
+
+        data.extend_from_slice(&2u16.to_be_bytes());
+        data.extend_from_slice(&desc(1, 2).to_be_bytes());
+        for (name_index, data_desc) in [(0u16, desc(2, 4)), (1, desc(3, 12))] {
+            data.extend_from_slice(&name_index.to_be_bytes());
+            data.extend_from_slice(&data_desc.to_be_bytes());
+        }
+        data.extend_from_slice(b"hi");
+        data.extend_from_slice(&258i16.to_be_bytes());
+        data.extend_from_slice(&1.0f32.to_be_bytes());
+        data.extend_from_slice(&(-2.5f32).to_be_bytes());
+
+        let (element, endianness) = from_reader_endianness(Cursor::new(&data)).unwrap();
+        assert_eq!(endianness, Endianness::Big);
+        assert_eq!(element.value, Value::String("hi".to_string()));
+        assert_eq!(element.get_child("a"), Some(&Value::Integer(258)));
+        assert_eq!(element.get_child("v"), Some(&Value::Vector(Vector(smallvec![1.0, -2.5]))));
+
+        assert_eq!(to_bytes(&element), data);
+        assert_eq!(verify_round_trip(&data).unwrap(), None);
+
+    }
+
 }
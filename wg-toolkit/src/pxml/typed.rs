@@ -0,0 +1,963 @@
+//! Typed access to Packed XML elements through serde.
+//!
+//! Elements are deserialized as maps or structures, where each child is a field, all
+//! children with the same name are grouped so that a sequence field can be read from
+//! repeated children. The proper value of an element can be accessed through the
+//! special '^' field. Vectors are sequences of floats, and a vector of one component
+//! is a float. Values are loosely converted, for example a string containing an
+//! integer can be read as an integer, because packed XML doesn't enforce types.
+//!
+//! When serializing, fields with no value (such as [`None`]) are not written, and
+//! sequences of structures are written as repeated children.
+
+use std::io::{Read, Seek};
+use std::fmt;
+
+use indexmap::IndexMap;
+use smallvec::SmallVec;
+use thiserror::Error;
+use base64::Engine;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, SeqDeserializer};
+use serde::ser::{self, Serialize};
+use serde::Deserialize;
+
+use super::{from_reader, DeError, Element, Value, Vector};
+
+
+/// Name of the special field used to access the proper value of an element.
+pub const PROPER_VALUE_FIELD: &str = "^";
+
+/// Name of the children written when serializing a sequence that is not a field.
+const SEQ_ITEM_NAME: &str = "item";
+
+/// Maximum number of components of a vector.
+const VECTOR_MAX_LEN: usize = 12;
+
+
+/// Deserialize a typed value from the given element.
+pub fn from_element<'de, T: Deserialize<'de>>(element: &'de Element) -> Result<T, SerdeError> {
+    T::deserialize(ElementDeserializer(element))
+}
+
+/// Deserialize a typed value from the given value.
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de Value) -> Result<T, SerdeError> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+/// Read a packed XML from the given reader and deserialize it to a typed value, see
+/// [`from_reader`] and [`from_element`].
+pub fn from_reader_as<T: DeserializeOwned, R: Read + Seek>(reader: R) -> Result<T, SerdeError> {
+    let element = from_reader(reader)?;
+    from_element(&element)
+}
+
+/// Serialize a typed value to an element, the value should be a structure or a map.
+pub fn to_element<T: Serialize + ?Sized>(value: &T) -> Result<Box<Element>, SerdeError> {
+    match to_value(value)? {
+        Value::Element(element) => Ok(element),
+        _ => Err(SerdeError::NotElement),
+    }
+}
+
+/// Serialize a typed value to a value.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, SerdeError> {
+    Ok(value.serialize(ValueSerializer)?.into_value())
+}
+
+
+/// Error that can happen while deserializing or serializing typed values.
+#[derive(Debug, Error)]
+pub enum SerdeError {
+    /// A custom error from the typed value.
+    #[error("{0}")]
+    Custom(String),
+    /// Map keys must be strings, or integers.
+    #[error("map key must be a string")]
+    KeyNotString,
+    /// Only structures and maps can be serialized to an element.
+    #[error("value is not an element")]
+    NotElement,
+    /// The packed XML cannot be read.
+    #[error("packed xml error: {0}")]
+    De(#[from] DeError),
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+
+/// Internal function to describe a value in errors.
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Element(_) => de::Unexpected::Map,
+        Value::String(s) => de::Unexpected::Str(s),
+        &Value::Integer(n) => de::Unexpected::Signed(n),
+        &Value::Boolean(b) => de::Unexpected::Bool(b),
+        Value::Vector(_) => de::Unexpected::Seq,
+        Value::Raw(raw) => de::Unexpected::Bytes(&raw.data),
+    }
+}
+
+/// Deserializer of an element, as a map of its children.
+struct ElementDeserializer<'de>(&'de Element);
+
+impl<'de> de::Deserializer<'de> for ElementDeserializer<'de> {
+
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(ChildrenAccess::new(self.0))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut seq = SeqDeserializer::new(self.0.iter_children_all().map(|(_, value)| ValueDeserializer(value)));
+        let ret = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(ret)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.iter_children_all().next() {
+            Some((variant, value)) if self.0.len() == 1 => visitor.visit_enum(EnumAccess { variant, value: Some(value) }),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &"element with exactly one child")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct tuple_struct map struct identifier
+    }
+
+}
+
+/// Deserializer of a single value.
+struct ValueDeserializer<'de>(&'de Value);
+
+impl<'de> ValueDeserializer<'de> {
+
+    /// Return the value to use for scalars, an element without children is the same
+    /// as its proper value.
+    fn scalar(&self) -> &'de Value {
+        match self.0 {
+            Value::Element(element) if element.len() == 0 => &element.value,
+            value => value,
+        }
+    }
+
+    fn integer(&self) -> Result<i64, SerdeError> {
+        match self.scalar() {
+            &Value::Integer(n) => Ok(n),
+            &Value::Boolean(b) => Ok(b as i64),
+            Value::String(s) => s.trim().parse()
+                .map_err(|_| de::Error::invalid_type(de::Unexpected::Str(s), &"integer")),
+            value => Err(de::Error::invalid_type(unexpected(value), &"integer")),
+        }
+    }
+
+    fn float(&self) -> Result<f32, SerdeError> {
+        match self.scalar() {
+            Value::Vector(v) if v.len() == 1 => Ok(v[0]),
+            &Value::Integer(n) => Ok(n as f32),
+            Value::String(s) => s.trim().parse()
+                .map_err(|_| de::Error::invalid_type(de::Unexpected::Str(s), &"float")),
+            value => Err(de::Error::invalid_type(unexpected(value), &"float")),
+        }
+    }
+
+}
+
+macro_rules! deserialize_integer {
+    ( $( $method:ident => $visit:ident ($ty:ty) ),* ) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let n = self.integer()?;
+                let n = <$ty>::try_from(n)
+                    .map_err(|_| <SerdeError as de::Error>::invalid_value(de::Unexpected::Signed(n), &stringify!($ty)))?;
+                visitor.$visit(n)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.scalar() {
+            Value::Element(element) => ElementDeserializer(element).deserialize_any(visitor),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            &Value::Integer(n) => visitor.visit_i64(n),
+            &Value::Boolean(b) => visitor.visit_bool(b),
+            Value::Vector(v) if v.len() == 1 => visitor.visit_f32(v[0]),
+            Value::Vector(v) => {
+                let mut seq = SeqDeserializer::<_, SerdeError>::new(v.iter().copied());
+                let ret = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(ret)
+            }
+            Value::Raw(raw) => visitor.visit_borrowed_bytes(&raw.data),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.scalar() {
+            &Value::Boolean(b) => visitor.visit_bool(b),
+            &Value::Integer(n) => visitor.visit_bool(n != 0),
+            Value::String(s) => match s.trim() {
+                "true" => visitor.visit_bool(true),
+                "false" => visitor.visit_bool(false),
+                _ => Err(de::Error::invalid_type(de::Unexpected::Str(s), &"boolean")),
+            }
+            value => Err(de::Error::invalid_type(unexpected(value), &"boolean")),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_f32(self.float()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_f64(self.float()? as f64)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.scalar() {
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            &Value::Integer(n) => visitor.visit_string(n.to_string()),
+            &Value::Boolean(b) => visitor.visit_string(b.to_string()),
+            value => Err(de::Error::invalid_type(unexpected(value), &"string")),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.scalar() {
+            Value::Raw(raw) => visitor.visit_borrowed_bytes(&raw.data),
+            // Strings that are valid base64 are compressed by the writer.
+            Value::String(s) => match base64::prelude::BASE64_STANDARD.decode(s.as_bytes()) {
+                Ok(data) => visitor.visit_byte_buf(data),
+                Err(_) => visitor.visit_borrowed_bytes(s.as_bytes()),
+            }
+            value => Err(de::Error::invalid_type(unexpected(value), &"bytes")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Element(element) => ElementDeserializer(element).deserialize_seq(visitor),
+            Value::Vector(v) => {
+                let mut seq = SeqDeserializer::<_, SerdeError>::new(v.iter().copied());
+                let ret = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(ret)
+            }
+            // An empty element is parsed as an empty string.
+            Value::String(s) if s.is_empty() => visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<f32>())),
+            value => Err(de::Error::invalid_type(unexpected(value), &"sequence")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Element(element) => visitor.visit_map(ChildrenAccess::new(element)),
+            Value::String(s) if s.is_empty() => visitor.visit_map(ChildrenAccess::empty()),
+            value => Err(de::Error::invalid_type(unexpected(value), &"element")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Element(element) if element.len() != 0 => ElementDeserializer(element).deserialize_enum(name, variants, visitor),
+            _ => match self.scalar() {
+                Value::String(variant) => visitor.visit_enum(EnumAccess { variant: variant.trim(), value: None }),
+                value => Err(de::Error::invalid_type(unexpected(value), &"enum variant")),
+            }
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for ValueDeserializer<'de> {
+
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+
+}
+
+/// Deserializer of all children with the same name, a sequence is read from all of
+/// them, any other type is read from the first one.
+struct ChildrenDeserializer<'de>(SmallVec<[&'de Value; 1]>);
+
+impl<'de> de::Deserializer<'de> for ChildrenDeserializer<'de> {
+
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // A single vector is a sequence of floats.
+        if let [value] = self.0[..] {
+            if let Value::Vector(_) = value {
+                return ValueDeserializer(value).deserialize_seq(visitor);
+            }
+        }
+        let mut seq = SeqDeserializer::new(self.0.into_iter().map(ValueDeserializer));
+        let ret = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(ret)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_bool(visitor)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_u64(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_f64(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_char(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_string(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ValueDeserializer(self.0[0]).deserialize_identifier(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+}
+
+/// Map access to the children of an element, grouped by name, the proper value of
+/// the element is the first entry if it's not an empty string.
+struct ChildrenAccess<'de> {
+    groups: indexmap::map::IntoIter<&'de str, SmallVec<[&'de Value; 1]>>,
+    value: Option<SmallVec<[&'de Value; 1]>>,
+}
+
+impl<'de> ChildrenAccess<'de> {
+
+    fn new(element: &'de Element) -> Self {
+
+        let mut groups = IndexMap::<&'de str, SmallVec<[&'de Value; 1]>>::new();
+
+        match &element.value {
+            Value::String(s) if s.is_empty() => {}
+            value => { groups.insert(PROPER_VALUE_FIELD, SmallVec::from_elem(value, 1)); }
+        }
+
+        for (key, value) in element.iter_children_all() {
+            groups.entry(key.as_str()).or_default().push(value);
+        }
+
+        Self { groups: groups.into_iter(), value: None }
+
+    }
+
+    fn empty() -> Self {
+        Self { groups: IndexMap::new().into_iter(), value: None }
+    }
+
+}
+
+impl<'de> de::MapAccess<'de> for ChildrenAccess<'de> {
+
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.groups.next() {
+            Some((key, values)) => {
+                self.value = Some(values);
+                seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let values = self.value.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(ChildrenDeserializer(values))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.groups.len())
+    }
+
+}
+
+/// Enum access, the variant is either a string, for unit variants, or the name of the
+/// single child of an element.
+struct EnumAccess<'de> {
+    variant: &'de str,
+    value: Option<&'de Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+
+    type Error = SerdeError;
+    type Variant = VariantAccess<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<SerdeError>::new(self.variant))?;
+        Ok((variant, VariantAccess(self.value)))
+    }
+
+}
+
+struct VariantAccess<'de>(Option<&'de Value>);
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'de> {
+
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+        match self.0 {
+            Some(value) => seed.deserialize(ValueDeserializer(value)),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_seq(ValueDeserializer(value), visitor),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"tuple variant")),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(value) => de::Deserializer::deserialize_map(ValueDeserializer(value), visitor),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"struct variant")),
+        }
+    }
+
+}
+
+
+/// Internal result of the serialization of a value.
+enum Node {
+    /// No value, the field is not written.
+    Absent,
+    /// A single value.
+    Value(Value),
+    /// A sequence of values, written as repeated children.
+    Seq(Vec<Value>),
+}
+
+impl Node {
+
+    /// Convert this node to a single value, sequences are wrapped in an element.
+    fn into_value(self) -> Value {
+        match self {
+            Node::Absent => Value::default(),
+            Node::Value(value) => value,
+            Node::Seq(values) => {
+                let mut element = Element::new();
+                for value in values {
+                    element.push_child(SEQ_ITEM_NAME.to_string(), value);
+                }
+                Value::Element(Box::new(element))
+            }
+        }
+    }
+
+    /// Write this node as a child of the given element, sequences are written as
+    /// repeated children.
+    fn write_child(self, element: &mut Element, key: &str) {
+        if key == PROPER_VALUE_FIELD {
+            element.value = self.into_value();
+            return;
+        }
+        match self {
+            Node::Absent => {}
+            Node::Value(value) => {
+                element.push_child(key.to_string(), value);
+            }
+            Node::Seq(values) => {
+                for value in values {
+                    element.push_child(key.to_string(), value);
+                }
+            }
+        }
+    }
+
+}
+
+/// Serializer of a single value.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = ElementSerializer;
+    type SerializeStruct = ElementSerializer;
+    type SerializeStructVariant = VariantSerializer<ElementSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::Boolean(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::Integer(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        let v = i64::try_from(v).map_err(|_| <SerdeError as ser::Error>::custom(format_args!("integer {v} is too large")))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::Vector(Vector(SmallVec::from_elem(v, 1)))))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f32(v as f32)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        // Base64 strings are compressed to their bytes by the writer.
+        Ok(Node::Value(Value::String(base64::prelude::BASE64_STANDARD.encode(v))))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Absent)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::default()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, Self::Error> {
+        Ok(wrap_variant(variant, value.serialize(ValueSerializer)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(ElementSerializer { element: Element::new(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(VariantSerializer { variant, inner: self.serialize_map(Some(len))? })
+    }
+
+}
+
+/// Serializer of sequences, a sequence of at most 12 floats is a vector.
+struct SeqSerializer(Vec<Value>);
+
+impl SeqSerializer {
+
+    fn finish(self) -> Node {
+
+        let floats = self.0.iter()
+            .map(|value| match value {
+                Value::Vector(v) if v.len() == 1 => Some(v[0]),
+                _ => None,
+            })
+            .collect::<Option<SmallVec<[f32; 3]>>>();
+
+        match floats {
+            Some(floats) if !floats.is_empty() && floats.len() <= VECTOR_MAX_LEN =>
+                Node::Value(Value::Vector(Vector(floats))),
+            _ => Node::Seq(self.0),
+        }
+
+    }
+
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.push(value.serialize(ValueSerializer)?.into_value());
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+
+}
+
+/// Serializer of maps and structures to an element.
+struct ElementSerializer {
+    element: Element,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for ElementSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let key = match key.serialize(ValueSerializer)? {
+            Node::Value(Value::String(key)) => key,
+            Node::Value(Value::Integer(key)) => key.to_string(),
+            _ => return Err(SerdeError::KeyNotString),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        value.serialize(ValueSerializer)?.write_child(&mut self.element, &key);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Node::Value(Value::Element(Box::new(self.element))))
+    }
+
+}
+
+impl ser::SerializeStruct for ElementSerializer {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        value.serialize(ValueSerializer)?.write_child(&mut self.element, key);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeMap::end(self)
+    }
+
+}
+
+/// Serializer of tuple and struct variants, wrapped in an element with a single child
+/// named after the variant.
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+/// Internal function to wrap a serialized variant in an element.
+fn wrap_variant(variant: &str, node: Node) -> Node {
+    let mut element = Element::new();
+    node.write_child(&mut element, variant);
+    Node::Value(Value::Element(Box::new(element)))
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(wrap_variant(self.variant, self.inner.finish()))
+    }
+
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<ElementSerializer> {
+
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let node = ser::SerializeMap::end(self.inner)?;
+        Ok(wrap_variant(self.variant, node))
+    }
+
+}
@@ -0,0 +1,566 @@
+//! Conversion between Packed XML elements and clear XML documents.
+//!
+//! Clear XML has no data type, so values are written as text and their type is
+//! inferred back from it: booleans are 'true' or 'false', integers have no fractional
+//! part, vectors are whitespace-separated floats with a fractional part, and anything
+//! else is a string. A string that looks like another type is therefore not read back
+//! as a string, this is also how the engine interprets clear XML.
+//! 
+//! Raw values, of unknown data types, are written as base64 with a 'raw' attribute on
+//! their tag giving their data type, so that they are read back as-is.
+
+use std::io::{self, Read, Write};
+
+use base64::Engine;
+use smallvec::SmallVec;
+use thiserror::Error;
+
+use super::{Element, RawValue, Value, Vector};
+
+
+/// Maximum number of components of a vector, used by 3x4 matrices.
+const VECTOR_MAX_LEN: usize = 12;
+/// Name of the attribute giving the data type of raw values.
+const RAW_ATTRIBUTE: &str = "raw";
+
+
+/// Write the given Packed XML element to the given writer as a clear XML document,
+/// its children being wrapped in the given root tag, the root tag is not significant
+/// and is usually the file name. Values of unknown data types are written as base64
+/// with their data type in a 'raw' attribute.
+pub fn to_xml_writer<W: Write>(mut writer: W, element: &Element, root_tag: &str) -> io::Result<()> {
+    write_open_tag(&mut writer, root_tag, &element.value)?;
+    write_element(&mut writer, element, 1)?;
+    writeln!(writer, "</{root_tag}>")
+}
+
+/// Write the given Packed XML element to a clear XML string, see [`to_xml_writer`].
+pub fn to_xml_string(element: &Element, root_tag: &str) -> String {
+    let mut data = Vec::new();
+    // Unwrapping because writing to a vector cannot fail, and only UTF-8 is written.
+    to_xml_writer(&mut data, element, root_tag).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+/// Read a Packed XML element from the given clear XML document, the root tag is
+/// ignored, comments, declarations and attributes other than 'raw' are ignored.
+pub fn from_xml_reader<R: Read>(mut reader: R) -> Result<Box<Element>, XmlError> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    from_xml_str(&content)
+}
+
+/// Read a Packed XML element from the given clear XML string, see [`from_xml_reader`].
+pub fn from_xml_str(content: &str) -> Result<Box<Element>, XmlError> {
+
+    let mut parser = Parser { content, pos: 0 };
+
+    parser.skip_misc()?;
+    let element = match parser.parse_open_tag()? {
+        (name, raw, false) => parser.parse_element(name, raw)?,
+        (_, raw, true) => {
+            let mut element = Box::new(Element::new());
+            element.value = parse_value("", raw);
+            element
+        }
+    };
+
+    parser.skip_misc()?;
+    if parser.pos != content.len() {
+        return Err(XmlError::InvalidSyntax(parser.line()));
+    }
+
+    Ok(element)
+
+}
+
+
+/// Internal function to write the proper value and the children of an element, after
+/// its opening tag, children are indented with the given number of tabs.
+fn write_element<W: Write>(writer: &mut W, element: &Element, indent: usize) -> io::Result<()> {
+
+    match &element.value {
+        // Empty strings, and elements that can't be represented, are not written.
+        Value::String(s) if s.is_empty() => {}
+        Value::Element(_) => {}
+        value => {
+            writer.write_all(b"\t")?;
+            write_value(writer, value)?;
+        }
+    }
+
+    writer.write_all(b"\n")?;
+
+    for (key, value) in element.iter_children_all() {
+
+        write_indent(writer, indent)?;
+        write_open_tag(writer, key, value)?;
+
+        if let Value::Element(child) = value {
+            write_element(writer, child, indent + 1)?;
+            write_indent(writer, indent)?;
+        } else {
+            writer.write_all(b"\t")?;
+            write_value(writer, value)?;
+            writer.write_all(b"\t")?;
+        }
+
+        writeln!(writer, "</{key}>")?;
+
+    }
+
+    Ok(())
+
+}
+
+/// Internal function to write the opening tag of an element with the given value, 
+/// with the data type of raw values.
+fn write_open_tag<W: Write>(writer: &mut W, name: &str, value: &Value) -> io::Result<()> {
+    let value = match value {
+        Value::Element(element) => &element.value,
+        value => value,
+    };
+    match value {
+        Value::Raw(raw) => write!(writer, "<{name} {RAW_ATTRIBUTE}=\"{}\">", raw.data_type),
+        _ => write!(writer, "<{name}>"),
+    }
+}
+
+/// Internal function to write a non-element value as text.
+fn write_value<W: Write>(writer: &mut W, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Element(_) => Ok(()),
+        Value::String(s) => write_escaped(writer, s),
+        Value::Integer(n) => write!(writer, "{n}"),
+        Value::Boolean(b) => write!(writer, "{b}"),
+        Value::Vector(v) => {
+            for (i, comp) in v.iter().enumerate() {
+                if i != 0 {
+                    writer.write_all(b" ")?;
+                }
+                // Always write a fractional part, so that it's not read as an integer.
+                let comp = comp.to_string();
+                writer.write_all(comp.as_bytes())?;
+                if !comp.contains(['.', 'i', 'N']) {
+                    writer.write_all(b".0")?;
+                }
+            }
+            Ok(())
+        }
+        Value::Raw(raw) => write!(writer, "{}", base64::prelude::BASE64_STANDARD.encode(&raw.data)),
+    }
+}
+
+/// Internal function to write text, escaping XML special characters.
+fn write_escaped<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    let mut rest = text;
+    while let Some(index) = rest.find(['&', '<', '>']) {
+        writer.write_all(&rest.as_bytes()[..index])?;
+        writer.write_all(match rest.as_bytes()[index] {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            _ => b"&gt;",
+        })?;
+        rest = &rest[index + 1..];
+    }
+    writer.write_all(rest.as_bytes())
+}
+
+#[inline]
+fn write_indent<W: Write>(writer: &mut W, indent: usize) -> io::Result<()> {
+    for _ in 0..indent {
+        writer.write_all(b"\t")?;
+    }
+    Ok(())
+}
+
+
+/// Internal clear XML parser.
+struct Parser<'a> {
+    /// The whole document.
+    content: &'a str,
+    /// Current byte position in the document.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    /// Return the line of the current position, for errors.
+    fn line(&self) -> usize {
+        self.content[..self.pos].matches('\n').count() + 1
+    }
+
+    #[inline]
+    fn rest(&self) -> &'a str {
+        &self.content[self.pos..]
+    }
+
+    /// Skip the rest of the document until after the given pattern.
+    fn skip_after(&mut self, pattern: &str) -> Result<(), XmlError> {
+        let index = self.rest().find(pattern).ok_or(XmlError::UnexpectedEnd)?;
+        self.pos += index + pattern.len();
+        Ok(())
+    }
+
+    /// Skip whitespaces, comments, declarations and processing instructions.
+    fn skip_misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("<!--") {
+                self.skip_after("-->")?;
+            } else if trimmed.starts_with("<?") {
+                self.skip_after("?>")?;
+            } else if trimmed.starts_with("<!") {
+                self.skip_after(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse an opening tag, returning its name, the data type given by its 'raw' 
+    /// attribute, if any, and if it's self-closing.
+    fn parse_open_tag(&mut self) -> Result<(&'a str, Option<u8>, bool), XmlError> {
+
+        let rest = self.rest();
+        let Some(tag) = rest.strip_prefix('<') else {
+            return Err(XmlError::InvalidSyntax(self.line()));
+        };
+
+        let name_len = tag.find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .ok_or(XmlError::UnexpectedEnd)?;
+
+        if name_len == 0 {
+            return Err(XmlError::InvalidSyntax(self.line()));
+        }
+
+        let name = &tag[..name_len];
+
+        // Skip attributes, which may contain '>' in their quoted values.
+        let mut quote = None;
+        for (index, c) in tag.char_indices().filter(|&(index, _)| index >= name_len) {
+            match (quote, c) {
+                (Some(q), c) if q == c => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    self.pos += 1 + index + 1;
+                    let attributes = tag[name_len..index].trim_end_matches('/');
+                    return Ok((name, parse_raw_attribute(attributes), tag[..index].ends_with('/')));
+                }
+                (None, _) => {}
+            }
+        }
+
+        Err(XmlError::UnexpectedEnd)
+
+    }
+
+    /// Parse the content of an element after its opening tag, until its closing tag,
+    /// the value is raw if a data type is given.
+    fn parse_element(&mut self, name: &str, raw: Option<u8>) -> Result<Box<Element>, XmlError> {
+
+        let mut element = Box::new(Element::new());
+        let mut text = String::new();
+
+        loop {
+
+            let rest = self.rest();
+            let index = rest.find('<').ok_or(XmlError::UnexpectedEnd)?;
+            unescape(&rest[..index], &mut text);
+            self.pos += index;
+
+            let rest = self.rest();
+            if rest.starts_with("<!--") {
+                self.skip_after("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let len = cdata.find("]]>").ok_or(XmlError::UnexpectedEnd)?;
+                text.push_str(&cdata[..len]);
+                self.pos += 9 + len + 3;
+            } else if rest.starts_with("<?") || rest.starts_with("<!") {
+                self.skip_after(">")?;
+            } else if let Some(closing) = rest.strip_prefix("</") {
+
+                let len = closing.find('>').ok_or(XmlError::UnexpectedEnd)?;
+                let closing_name = closing[..len].trim_end();
+                if closing_name != name {
+                    return Err(XmlError::MismatchedTag {
+                        line: self.line(),
+                        expected: name.to_string(),
+                        found: closing_name.to_string(),
+                    });
+                }
+
+                self.pos += 2 + len + 1;
+                break;
+
+            } else {
+
+                let (child_name, child_raw, self_closing) = self.parse_open_tag()?;
+                let child_value = if self_closing {
+                    parse_value("", child_raw)
+                } else {
+                    let child = self.parse_element(child_name, child_raw)?;
+                    // Elements without children are simple values.
+                    if child.len() == 0 {
+                        child.value
+                    } else {
+                        Value::Element(child)
+                    }
+                };
+
+                element.push_child(child_name.to_string(), child_value);
+
+            }
+
+        }
+
+        element.value = parse_value(text.trim(), raw);
+        Ok(element)
+
+    }
+
+}
+
+/// Internal function to parse the data type of the 'raw' attribute, if any, among the
+/// given attributes of a tag. Invalid data types are ignored.
+fn parse_raw_attribute(attributes: &str) -> Option<u8> {
+
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|&c| c == '"' || c == '\'')?;
+        let (value, after) = after[1..].split_once(quote)?;
+
+        if name.trim() == RAW_ATTRIBUTE {
+            return value.trim().parse::<u8>().ok().filter(|&data_type| data_type < 16);
+        }
+
+        rest = after.trim_start();
+
+    }
+
+    None
+
+}
+
+/// Internal function to parse the value of the given text, raw if a data type is 
+/// given and the text is valid base64, else inferred from the text.
+fn parse_value(text: &str, raw: Option<u8>) -> Value {
+
+    if let Some(data_type) = raw {
+        if let Ok(data) = base64::prelude::BASE64_STANDARD.decode(text) {
+            return Value::Raw(RawValue { data_type, data });
+        }
+    }
+
+    infer_value(text)
+
+}
+
+/// Internal function to infer the value of the given text.
+fn infer_value(text: &str) -> Value {
+
+    match text {
+        "true" => return Value::Boolean(true),
+        "false" => return Value::Boolean(false),
+        _ => {}
+    }
+
+    if let Ok(n) = text.parse::<i64>() {
+        return Value::Integer(n);
+    }
+
+    let is_float = |comp: &str| {
+        comp.contains('.') &&
+        comp.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+' | b'e' | b'E'))
+    };
+
+    if !text.is_empty() && text.split_ascii_whitespace().all(is_float) {
+        let comps = text.split_ascii_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<SmallVec<[f32; 3]>, _>>();
+        if let Ok(comps) = comps {
+            if comps.len() <= VECTOR_MAX_LEN {
+                return Value::Vector(Vector(comps));
+            }
+        }
+    }
+
+    Value::String(text.to_string())
+
+}
+
+/// Internal function to unescape XML text and append it to the given string, unknown
+/// entities are kept as-is.
+fn unescape(text: &str, dst: &mut String) {
+
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+
+        dst.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let entity = rest[1..].find(';')
+            .map(|len| &rest[1..1 + len])
+            .and_then(|entity| {
+                let c = match entity {
+                    "lt" => '<',
+                    "gt" => '>',
+                    "amp" => '&',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    _ => {
+                        let code = if let Some(hex) = entity.strip_prefix("#x") {
+                            u32::from_str_radix(hex, 16).ok()?
+                        } else {
+                            entity.strip_prefix('#')?.parse().ok()?
+                        };
+                        char::from_u32(code)?
+                    }
+                };
+                Some((c, entity.len()))
+            });
+
+        if let Some((c, len)) = entity {
+            dst.push(c);
+            rest = &rest[1 + len + 1..];
+        } else {
+            dst.push('&');
+            rest = &rest[1..];
+        }
+
+    }
+
+    dst.push_str(rest);
+
+}
+
+
+/// Error that can happen while reading a clear XML document.
+#[derive(Debug, Error)]
+pub enum XmlError {
+    /// The document ends before its root element is closed.
+    #[error("unexpected end of document")]
+    UnexpectedEnd,
+    /// Invalid syntax at the given line.
+    #[error("invalid syntax at line {0}")]
+    InvalidSyntax(usize),
+    /// A closing tag doesn't match the opened element.
+    #[error("mismatched closing tag at line {line}: expected </{expected}>, found </{found}>")]
+    MismatchedTag {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+    /// IO error while reading the document, also returned if it's not UTF-8.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use smallvec::smallvec;
+
+    use super::*;
+
+    fn raw(data_type: u8, data: &[u8]) -> Value {
+        Value::Raw(RawValue { data_type, data: data.to_vec() })
+    }
+
+    fn round_trip(element: &Element) -> Box<Element> {
+        let xml = to_xml_string(element, "root.xml");
+        from_xml_str(&xml).unwrap_or_else(|e| panic!("{e}: {xml}"))
+    }
+
+    #[test]
+    fn round_trip_values() {
+
+        let mut element = Element::new();
+        element.push_child("string".to_string(), Value::String("hello world".to_string()));
+        element.push_child("integer".to_string(), Value::Integer(-42));
+        element.push_child("boolean".to_string(), Value::Boolean(true));
+        element.push_child("vector".to_string(), Value::Vector(Vector(smallvec![1.0, -2.5, 3.0])));
+        element.push_child("empty".to_string(), Value::default());
+
+        assert_eq!(*round_trip(&element), element);
+
+    }
+
+    #[test]
+    fn round_trip_raw() {
+
+        let mut child = Box::new(Element::new());
+        child.value = raw(7, b"\x00\x01\xFF");
+        child.push_child("inner".to_string(), raw(15, b""));
+
+        let mut element = Element::new();
+        element.value = raw(6, b"root");
+        element.push_child("raw".to_string(), raw(7, b"\x00\x01\xFF"));
+        element.push_child("empty_raw".to_string(), raw(9, b""));
+        element.push_child("child".to_string(), Value::Element(child));
+
+        let xml = to_xml_string(&element, "root.xml");
+        assert!(xml.starts_with("<root.xml raw=\"6\">"), "{xml}");
+        assert!(xml.contains("<raw raw=\"7\">\tAAH/\t</raw>"), "{xml}");
+        assert_eq!(*round_trip(&element), element);
+
+        // Self-closing and invalid raw values.
+        let element = from_xml_str("<root><a raw='3'/><b raw=\"16\">AAH/</b><c raw=\"1\">not base64!</c></root>").unwrap();
+        assert_eq!(element.get_child("a"), Some(&raw(3, b"")));
+        assert_eq!(element.get_child("b"), Some(&Value::String("AAH/".to_string())));
+        assert_eq!(element.get_child("c"), Some(&Value::String("not base64!".to_string())));
+
+    }
+
+    #[test]
+    fn round_trip_escapes() {
+
+        let mut element = Element::new();
+        element.push_child("text".to_string(), Value::String("a < b && c > d".to_string()));
+
+        let xml = to_xml_string(&element, "root");
+        assert!(xml.contains("a &lt; b &amp;&amp; c &gt; d"), "{xml}");
+        assert_eq!(*round_trip(&element), element);
+
+        let element = from_xml_str("<root><a>&quot;&apos;&#x41;&#66;&unknown;&amp</a><b><![CDATA[<not a tag>]]></b></root>").unwrap();
+        assert_eq!(element.get_string("a"), Some("\"'AB&unknown;&amp"));
+        assert_eq!(element.get_string("b"), Some("<not a tag>"));
+
+    }
+
+    #[test]
+    fn round_trip_nested() {
+
+        let mut grandchild = Box::new(Element::new());
+        grandchild.push_child("value".to_string(), Value::Integer(1));
+        grandchild.push_child("value".to_string(), Value::Integer(2));
+
+        let mut child = Box::new(Element::new());
+        child.value = Value::String("child value".to_string());
+        child.push_child("grandchild".to_string(), Value::Element(grandchild));
+        child.push_child("flag".to_string(), Value::Boolean(false));
+
+        let mut element = Element::new();
+        element.value = Value::Integer(3);
+        element.push_child("child".to_string(), Value::Element(child));
+        element.push_child("after".to_string(), Value::String("after".to_string()));
+
+        assert_eq!(*round_trip(&element), element);
+
+        // Comments, declarations and other attributes are ignored.
+        let element = from_xml_str("<?xml version=\"1.0\"?>\n<!-- comment -->\n<root a=\"x > y\">\n\t<!-- comment --><child b='1'>\t<value>1</value>\t</child>\n</root>\n").unwrap();
+        assert_eq!(element.get_element("child").and_then(|child| child.get_i64("value")), Some(1));
+
+        assert!(matches!(from_xml_str("<root><a></b></root>"), Err(XmlError::MismatchedTag { .. })));
+        assert!(matches!(from_xml_str("<root><a>"), Err(XmlError::UnexpectedEnd)));
+
+    }
+
+}
@@ -0,0 +1,288 @@
+//! Reader and writer for World of Tanks replays (.wotreplay).
+//!
+//! A replay starts with a few JSON blocks describing the battle, followed by the
+//! packet stream recorded by the client, encrypted with a well-known blowfish key
+//! and compressed with zlib.
+//! 
+//! Packets of the stream are not network packets, but they can be wrapped into bundles
+//! in order to be read with the same element readers, see [`ReplayPacket::to_bundle`].
+
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use blowfish::cipher::KeyInit;
+use blowfish::Blowfish;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use thiserror::Error;
+
+use crate::net::filter::{BlowfishReader, BlowfishWriter};
+use crate::net::element::{ElementLength, RawElement};
+use crate::net::app::client::element::id as client_id;
+use crate::net::bundle::Bundle;
+
+
+/// Magic of a replay file.
+pub const MAGIC: &[u8; 4] = b"\x12\x32\x34\x11";
+
+/// The blowfish key used to encrypt the packet stream of all replays.
+pub const BLOWFISH_KEY: &[u8; 16] = b"\xDE\x72\xBE\xA0\xDE\x04\xBE\xB1\xDE\xFE\xBE\xEF\xDE\xAD\xBE\xEF";
+
+/// Length of the header of each packet of the stream.
+const PACKET_HEADER_LEN: usize = 12;
+
+/// The length of the single element of bundles returned by [`ReplayPacket::to_bundle`].
+pub const ELEMENT_LENGTH: ElementLength = ElementLength::Variable32;
+
+
+/// Known kinds of packets in the replay stream.
+pub mod kind {
+    pub const BASE_PLAYER_CREATE: u32   = 0x00;
+    pub const CELL_PLAYER_CREATE: u32   = 0x01;
+    pub const ENTITY_CONTROL: u32       = 0x02;
+    pub const ENTITY_ENTER: u32         = 0x03;
+    pub const ENTITY_LEAVE: u32         = 0x04;
+    pub const ENTITY_CREATE: u32        = 0x05;
+    /// Entity id, property id, length and data.
+    pub const ENTITY_PROPERTY: u32      = 0x07;
+    /// Entity id, method id, length and data.
+    pub const ENTITY_METHOD: u32        = 0x08;
+    /// Entity id, space id, vehicle id and position, followed by other fields.
+    pub const POSITION: u32             = 0x0A;
+}
+
+
+/// A replay read from a file.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// The raw JSON blocks, the first one describes the battle at its start and the
+    /// second one, if any, contains the battle results.
+    pub blocks: Vec<String>,
+    /// The 8 bytes before the encrypted stream, their meaning is unknown and they are
+    /// written back as-is.
+    pub unknown: [u8; 8],
+    /// The decrypted and decompressed packet stream.
+    pub stream: Vec<u8>,
+}
+
+/// A packet of the replay stream.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayPacket<'a> {
+    /// The type of packet.
+    pub kind: u32,
+    /// The clock of the packet, in seconds since the start of the recording.
+    pub clock: f32,
+    /// The full packet, including its header.
+    pub raw: &'a [u8],
+    /// The payload of the packet.
+    pub data: &'a [u8],
+}
+
+impl Replay {
+
+    /// Iterate over all packets of the stream, the iteration stops at the first
+    /// truncated packet.
+    pub fn packets(&self) -> ReplayPackets<'_> {
+        ReplayPackets { stream: &self.stream }
+    }
+
+    /// Iterate over all packets of the stream wrapped into bundles, with their time 
+    /// since the start of the recording, see [`ReplayPacket::to_bundle`]. Packets with
+    /// a kind that has no equivalent client element are skipped.
+    pub fn bundles(&self) -> impl Iterator<Item = (Duration, Bundle)> + '_ {
+        self.packets().filter_map(|packet| Some((packet.time(), packet.to_bundle()?)))
+    }
+
+}
+
+impl ReplayPacket<'_> {
+
+    /// The time of the packet since the start of the recording, zero if the clock is
+    /// invalid.
+    pub fn time(&self) -> Duration {
+        Duration::try_from_secs_f32(self.clock).unwrap_or_default()
+    }
+
+    /// Return the id of the client element equivalent to this packet, see 
+    /// [`client::element::id`](crate::net::app::client::element::id). None is 
+    /// returned if the kind is unknown, or for entity methods and properties because
+    /// their element id depends on the entity type's definitions.
+    pub fn element_id(&self) -> Option<u8> {
+        Some(match self.kind {
+            kind::BASE_PLAYER_CREATE => client_id::CREATE_BASE_PLAYER,
+            kind::CELL_PLAYER_CREATE => client_id::CREATE_CELL_PLAYER,
+            kind::ENTITY_CONTROL => client_id::CONTROL_ENTITY,
+            kind::ENTITY_ENTER => client_id::ENTER_AOI,
+            kind::ENTITY_LEAVE => client_id::LEAVE_AOI,
+            kind::ENTITY_CREATE => client_id::CREATE_ENTITY,
+            _ => return None,
+        })
+    }
+
+    /// Wrap this packet into a bundle with a single element, its id is the equivalent
+    /// client element id, see [`Self::element_id`], and its content is the packet's 
+    /// payload, with a [`ELEMENT_LENGTH`] length. The element can then be read like 
+    /// any other, for example as a [`RawElement`] or with any element codec having 
+    /// the same layout as the payload. None is returned if the packet has no 
+    /// equivalent client element.
+    pub fn to_bundle(&self) -> Option<Bundle> {
+        let id = self.element_id()?;
+        let mut bundle = Bundle::new();
+        bundle.element_writer().write(RawElement { id, data: self.data.into() }, &ELEMENT_LENGTH);
+        Some(bundle)
+    }
+
+}
+
+/// Iterator over the packets of a replay stream.
+#[derive(Debug, Clone)]
+pub struct ReplayPackets<'a> {
+    stream: &'a [u8],
+}
+
+impl<'a> Iterator for ReplayPackets<'a> {
+
+    type Item = ReplayPacket<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        let header = self.stream.get(..PACKET_HEADER_LEN)?;
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let kind = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let clock = f32::from_le_bytes(header[8..12].try_into().unwrap());
+
+        let Some(raw) = self.stream.get(..PACKET_HEADER_LEN + len) else {
+            self.stream = &[];
+            return None;
+        };
+
+        self.stream = &self.stream[raw.len()..];
+        Some(ReplayPacket { kind, clock, raw, data: &raw[PACKET_HEADER_LEN..] })
+
+    }
+
+}
+
+/// Read a replay from the given reader.
+pub fn from_reader<R: Read>(mut reader: R) -> Result<Replay, ReplayError> {
+
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(ReplayError::InvalidMagic);
+    }
+
+    let blocks_count = read_u32(&mut reader)?;
+    let mut blocks = Vec::with_capacity(blocks_count.min(16) as usize);
+    for _ in 0..blocks_count {
+        let len = read_u32(&mut reader)?;
+        let mut block = Vec::new();
+        (&mut reader).take(len as u64).read_to_end(&mut block)?;
+        if block.len() != len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        blocks.push(String::from_utf8(block).map_err(|_| ReplayError::InvalidBlock)?);
+    }
+
+    let mut unknown = [0; 8];
+    reader.read_exact(&mut unknown)?;
+
+    // Unwrap because the key has a valid length.
+    let blowfish = Blowfish::new_from_slice(BLOWFISH_KEY).unwrap();
+    let mut stream = Vec::new();
+    ZlibDecoder::new(BlowfishReader::new(reader, &blowfish)).read_to_end(&mut stream)?;
+
+    Ok(Replay { blocks, unknown, stream })
+
+}
+
+/// Write a replay to the given writer, the packet stream is compressed and encrypted.
+pub fn to_writer<W: Write>(mut writer: W, replay: &Replay) -> io::Result<()> {
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(replay.blocks.len() as u32).to_le_bytes())?;
+    for block in &replay.blocks {
+        writer.write_all(&(block.len() as u32).to_le_bytes())?;
+        writer.write_all(block.as_bytes())?;
+    }
+
+    writer.write_all(&replay.unknown)?;
+
+    // Unwrap because the key has a valid length.
+    let blowfish = Blowfish::new_from_slice(BLOWFISH_KEY).unwrap();
+    let mut blowfish_writer = BlowfishWriter::new(&mut writer, &blowfish);
+    let mut encoder = ZlibEncoder::new(&mut blowfish_writer, Compression::default());
+    encoder.write_all(&replay.stream)?;
+    encoder.finish()?;
+
+    // Flush the last partial block, padded with zeros.
+    blowfish_writer.flush()?;
+    drop(blowfish_writer);
+    writer.flush()
+
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Errors that can happen while reading a replay.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    /// Invalid magic signature for the file.
+    #[error("invalid magic")]
+    InvalidMagic,
+    /// A JSON block is not valid UTF-8.
+    #[error("invalid block")]
+    InvalidBlock,
+    /// Underlying IO error while reading the file, also returned if the packet stream
+    /// can't be decompressed.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::net::bundle::NextElementReader;
+
+    use super::*;
+
+    /// Build a replay stream packet of the given kind and payload.
+    fn packet(kind: u32, data: &[u8]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        raw.extend_from_slice(&kind.to_le_bytes());
+        raw.extend_from_slice(&1.5f32.to_le_bytes());
+        raw.extend_from_slice(data);
+        raw
+    }
+
+    #[test]
+    fn bundles() {
+
+        let mut stream = packet(kind::ENTITY_LEAVE, &[1, 2, 3, 4]);
+        stream.extend(packet(kind::ENTITY_METHOD, &[0; 12]));
+        stream.extend(packet(kind::POSITION, &[0; 24]));
+        stream.extend(packet(kind::ENTITY_ENTER, &[5, 6]));
+
+        let replay = Replay { blocks: Vec::new(), unknown: [0; 8], stream };
+        let bundles = replay.bundles().collect::<Vec<_>>();
+        assert_eq!(bundles.len(), 2);
+
+        for ((time, bundle), (id, data)) in bundles.iter().zip([(client_id::LEAVE_AOI, &[1, 2, 3, 4][..]), (client_id::ENTER_AOI, &[5, 6])]) {
+            assert_eq!(*time, Duration::from_millis(1500));
+            let mut reader = bundle.element_reader();
+            let Some(NextElementReader::Element(elt)) = reader.next() else { panic!("expected an element") };
+            assert_eq!(elt.id(), id);
+            let raw = elt.read::<RawElement, _>(&ELEMENT_LENGTH).unwrap();
+            assert_eq!(&raw.element.data[..], data);
+        }
+
+    }
+
+}
@@ -0,0 +1,170 @@
+//! DVPL file codec.
+//!
+//! DVPL files are used by the resource trees of World of Tanks Blitz and console
+//! editions, each resource file is individually wrapped, optionally compressed with
+//! LZ4, and followed by a footer. The wrapped file has the name of the original file
+//! with an additional '.dvpl' extension.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::util::io::{WgReadExt, WgWriteExt};
+
+
+/// Extension added to the name of wrapped files.
+pub const DVPL_EXTENSION: &str = "dvpl";
+
+/// Magic at the end of the footer.
+const FOOTER_MAGIC: &[u8; 4] = b"DVPL";
+
+/// Length of the footer at the end of the file.
+pub const FOOTER_LEN: usize = 20;
+
+
+/// The compression of a DVPL file's data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DvplCompression {
+    /// Data is stored without compression.
+    None,
+    /// LZ4 block compression, using the high compression mode.
+    Lz4Hc,
+    /// LZ4 block compression.
+    Lz4,
+}
+
+/// The footer at the end of each DVPL file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DvplFooter {
+    /// Size of the original data, once decompressed.
+    pub original_size: u32,
+    /// Size of the stored data, before the footer.
+    pub compressed_size: u32,
+    /// CRC32 of the stored data.
+    pub crc32: u32,
+    /// Compression of the stored data.
+    pub compression: DvplCompression,
+}
+
+impl DvplFooter {
+
+    /// Read the footer from the last bytes of the given reader, the reader's cursor is
+    /// left after the footer.
+    pub fn read_from_end<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+        reader.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+        Self::read(reader)
+    }
+
+    /// Read the footer from the given reader.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+
+        let original_size = reader.read_u32()?;
+        let compressed_size = reader.read_u32()?;
+        let crc32 = reader.read_u32()?;
+        let compression = match reader.read_u32()? {
+            0 => DvplCompression::None,
+            1 => DvplCompression::Lz4Hc,
+            2 => DvplCompression::Lz4,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported dvpl compression")),
+        };
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != FOOTER_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid dvpl magic"));
+        }
+
+        Ok(Self { original_size, compressed_size, crc32, compression })
+
+    }
+
+    /// Write the footer to the given writer.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32(self.original_size)?;
+        writer.write_u32(self.compressed_size)?;
+        writer.write_u32(self.crc32)?;
+        writer.write_u32(match self.compression {
+            DvplCompression::None => 0,
+            DvplCompression::Lz4Hc => 1,
+            DvplCompression::Lz4 => 2,
+        })?;
+        writer.write_all(FOOTER_MAGIC)
+    }
+
+}
+
+/// Decode the whole content of a DVPL file, checking its CRC32 and returning the
+/// original data.
+pub fn decode(data: &[u8]) -> io::Result<Vec<u8>> {
+
+    let footer_offset = data.len().checked_sub(FOOTER_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "dvpl file too short"))?;
+
+    let footer = DvplFooter::read(&data[footer_offset..])?;
+    let stored = &data[..footer_offset];
+
+    if stored.len() != footer.compressed_size as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid dvpl compressed size"));
+    }
+
+    if crc32fast::hash(stored) != footer.crc32 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid dvpl crc32"));
+    }
+
+    let original = match footer.compression {
+        DvplCompression::None => stored.to_vec(),
+        DvplCompression::Lz4Hc |
+        DvplCompression::Lz4 => {
+            lz4_flex::block::decompress(stored, footer.original_size as usize)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        }
+    };
+
+    if original.len() != footer.original_size as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid dvpl original size"));
+    }
+
+    Ok(original)
+
+}
+
+/// Read and decode a whole DVPL file from the given reader.
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+/// Encode the given original data to the content of a DVPL file. If compression is
+/// requested but the compressed data is not smaller than the original data, the data
+/// is stored without compression, like the game's tools do.
+pub fn encode(data: &[u8], compress: bool) -> io::Result<Vec<u8>> {
+
+    let original_size = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "data too large for dvpl"))?;
+
+    let compressed = compress
+        .then(|| lz4_flex::block::compress(data))
+        .filter(|compressed| compressed.len() < data.len());
+
+    let (stored, compression) = match &compressed {
+        Some(compressed) => (&compressed[..], DvplCompression::Lz4),
+        None => (data, DvplCompression::None),
+    };
+
+    let footer = DvplFooter {
+        original_size,
+        compressed_size: stored.len() as u32,
+        crc32: crc32fast::hash(stored),
+        compression,
+    };
+
+    let mut encoded = Vec::with_capacity(stored.len() + FOOTER_LEN);
+    encoded.extend_from_slice(stored);
+    footer.write(&mut encoded)?;
+    Ok(encoded)
+
+}
+
+/// Encode and write a whole DVPL file to the given writer.
+pub fn write<W: Write>(mut writer: W, data: &[u8], compress: bool) -> io::Result<()> {
+    writer.write_all(&encode(data, compress)?)
+}
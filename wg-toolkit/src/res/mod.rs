@@ -1,23 +1,40 @@
 //! Game's resources fetching and indexing.
 
 pub mod package;
+pub mod paths;
+pub mod dvpl;
 
 use core::fmt;
 use std::collections::{BTreeMap, HashSet};
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Write, Seek, SeekFrom, Cursor, BufWriter};
 use std::fs::{File, ReadDir};
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{fs, io};
 
 use indexmap::IndexMap;
 
-use package::{PackageReader, PackageFileReader};
+use package::{PackageReader, PackageFileReader, PackageWriter};
+use paths::{ResPaths, ResPackagePath};
+use dvpl::DvplFooter;
 
 
 /// Name of the directory storing packages in the "res/" directory.
 const PACKAGES_DIR_NAME: &'static str = "packages";
 
+/// Reader of the packages opened by the filesystem, packages are memory-mapped if the
+/// mmap feature is enabled.
+#[cfg(feature = "mmap")]
+type FsPackageReader = package::MmapPackageReader;
+#[cfg(not(feature = "mmap"))]
+type FsPackageReader = PackageReader<File>;
+
+/// Owned reader of a package file opened by the filesystem.
+#[cfg(feature = "mmap")]
+type FsPackageFileReader = PackageFileReader<Cursor<package::PackageMmap>>;
+#[cfg(not(feature = "mmap"))]
+type FsPackageFileReader = PackageFileReader<File>;
+
 
 /// A virtual read-only filesystem you can use to walk through the game's resources. This
 /// filesystem is designed to work really fast on systems where it will run for a long
@@ -26,6 +43,12 @@ const PACKAGES_DIR_NAME: &'static str = "packages";
 /// 
 /// This filesystem is made to be shared between threads and immutably accessed.
 /// 
+/// Native files wrapped in DVPL files, as found in World of Tanks Blitz and console
+/// resource trees, are transparently decoded and listed without their extension.
+/// 
+/// Packages are memory-mapped if the `mmap` feature is enabled, they should then not be
+/// modified in place while the filesystem is alive.
+/// 
 /// Internally, this filesystem has a cache to improve response delay. The challenge is
 /// that directories may reside in many packages, but files are present only in one
 /// package.
@@ -39,8 +62,8 @@ pub struct ResFilesystem {
 /// Immutable shared data 
 #[derive(Debug)]
 struct Shared {
-    /// Path to the "res/" directory.
-    dir_path: PathBuf,
+    /// Native directories, by decreasing priority.
+    dir_paths: Vec<PathBuf>,
     /// Mutable part of the shared data, behind mutex.
     mutable: Mutex<SharedMut>,
 }
@@ -48,10 +71,11 @@ struct Shared {
 /// Mutex shared part of the resource filesystem.
 #[derive(Debug)]
 struct SharedMut {
-    /// Pending packages to be opened and cached.
-    pending_package_path: Vec<PathBuf>,
+    /// Pending packages to be opened and cached, by increasing priority so that the 
+    /// package with the highest priority is popped first.
+    pending_packages: Vec<ResPackagePath>,
     /// Cache for opened package files.
-    package_reader_cache: IndexMap<PathBuf, PackageReader<File>>,
+    package_reader_cache: IndexMap<PathBuf, FsPackageReader>,
     /// Package open errors are silently ignored when reading files and directories, so
     /// this vector contains the errors that may happen and can later be retrieved.
     package_open_errors: Vec<(PathBuf, io::Error)>,
@@ -61,41 +85,106 @@ struct SharedMut {
 
 impl ResFilesystem {
 
-    /// Create a new resources filesystem with the given options. This function is
-    /// blocking while it is doing a rudimentary early indexing, so this may take some
-    /// time.
+    /// Create a new resources filesystem from the given directory, which is either the
+    /// game's directory containing a 'paths.xml' file, in which case the mounted 
+    /// directories and packages are resolved exactly like the game does, or directly
+    /// the resources (res/) directory. See [`ResPaths`] for the resolution.
     pub fn new<P: Into<PathBuf>>(dir_path: P) -> io::Result<Self> {
 
-        let dir_path = dir_path.into();
-        let mut pending_package_cache = Vec::new();
-
-        for entry in fs::read_dir(dir_path.join(PACKAGES_DIR_NAME))? {
-            
-            let entry = entry?;
-            let entry_type = entry.file_type()?;
-            if !entry_type.is_file() {
-                continue;
-            }
+        let paths = ResPaths::from_dir(&dir_path.into())?;
+        Ok(Self::with_paths(paths))
 
-            if !entry.file_name().as_encoded_bytes().ends_with(b".pkg") {
-                continue;
-            }
+    }
 
-            pending_package_cache.push(entry.path());
+    /// Create a new resources filesystem mounting the given directories and packages,
+    /// in their priority order. Packages are lazily opened and indexed.
+    pub fn with_paths(paths: ResPaths) -> Self {
 
-        }
+        // Remove duplicate packages, keeping the one with highest priority, and then
+        // reverse because the next pending package is popped.
+        let mut seen_packages = HashSet::new();
+        let mut pending_packages = paths.packages.into_iter()
+            .filter(|package| seen_packages.insert(package.path.clone()))
+            .collect::<Vec<_>>();
+        pending_packages.reverse();
 
-        Ok(Self { 
+        Self { 
             shared: Arc::new(Shared {
-                dir_path,
+                dir_paths: paths.dirs,
                 mutable: Mutex::new(SharedMut {
-                    pending_package_path: pending_package_cache,
+                    pending_packages,
                     package_reader_cache: IndexMap::new(),
                     package_open_errors: Vec::new(),
                     node_cache: NodeCache::new(),
                 }),
             }),
-        })
+        }
+
+    }
+
+    /// Same as [`Self::new`] but all packages are immediately opened and indexed in
+    /// parallel, instead of being lazily opened, this is faster when most of the
+    /// packages will be needed, such as when walking the whole filesystem.
+    #[cfg(feature = "parallel")]
+    pub fn new_parallel<P: Into<PathBuf>>(dir_path: P) -> io::Result<Self> {
+
+        let paths = ResPaths::from_dir(&dir_path.into())?;
+        Ok(Self::with_paths_parallel(paths))
+
+    }
+
+    /// Same as [`Self::with_paths`] but all packages are immediately opened and indexed
+    /// in parallel, each package is indexed in its own node cache, and all caches are
+    /// then merged by decreasing priority. Errors are kept like for lazily opened
+    /// packages.
+    #[cfg(feature = "parallel")]
+    pub fn with_paths_parallel(paths: ResPaths) -> Self {
+
+        use rayon::prelude::*;
+
+        let mut seen_packages = HashSet::new();
+        let packages = paths.packages.into_iter()
+            .filter(|package| seen_packages.insert(package.path.clone()))
+            .collect::<Vec<_>>();
+
+        // The order of packages is kept, so they are still by decreasing priority.
+        let opened_packages = packages.into_par_iter()
+            .map(|package| {
+                let res = open_package_reader(&package.path)
+                    .map(|package_reader| {
+                        let mut node_cache = NodeCache::new();
+                        node_cache.index_package(0, &package_reader, &package.root);
+                        (package_reader, node_cache)
+                    });
+                (package.path, res)
+            })
+            .collect::<Vec<_>>();
+
+        let mut package_reader_cache = IndexMap::new();
+        let mut package_open_errors = Vec::new();
+        let mut node_cache = NodeCache::new();
+
+        for (package_path, res) in opened_packages {
+            match res {
+                Ok((package_reader, package_node_cache)) => {
+                    let (package_index, _) = package_reader_cache.insert_full(package_path, package_reader);
+                    node_cache.merge(package_node_cache, package_index);
+                }
+                Err(e) => package_open_errors.push((package_path, e)),
+            }
+        }
+
+        Self { 
+            shared: Arc::new(Shared {
+                dir_paths: paths.dirs,
+                mutable: Mutex::new(SharedMut {
+                    pending_packages: Vec::new(),
+                    package_reader_cache,
+                    package_open_errors,
+                    node_cache,
+                }),
+            }),
+        }
 
     }
 
@@ -108,15 +197,18 @@ impl ResFilesystem {
             return Err(io::ErrorKind::NotFound.into());
         }
 
-        let native_file_path = self.shared.dir_path.join(node_path);
-        match native_file_path.metadata() {
-            Ok(metadata) => {
+        for dir_path in &self.shared.dir_paths {
+            if let Ok(metadata) = dir_path.join(node_path).metadata() {
                 return Ok(ResStat {
                     is_dir: metadata.is_dir(),
                     size: if metadata.is_dir() { 0 } else { metadata.len() },
                 });
+            } else if let Ok(file) = File::open(dvpl_path(dir_path, node_path)) {
+                return Ok(ResStat {
+                    is_dir: false,
+                    size: DvplFooter::read_from_end(file)?.original_size as u64,
+                });
             }
-            Err(_) => {}
         }
 
         self.shared.mutable.lock().unwrap().stat(node_path)
@@ -131,11 +223,17 @@ impl ResFilesystem {
             return Err(io::ErrorKind::NotFound.into());
         }
 
-        let native_file_path = self.shared.dir_path.join(file_path);
-        if native_file_path.is_file() {
-            match File::open(native_file_path) {
-                Ok(file) => return Ok(ResReadFile(ReadFileInner::Native(file))),
-                Err(_) => (), // For now we skip this.
+        for dir_path in &self.shared.dir_paths {
+            let native_file_path = dir_path.join(file_path);
+            if native_file_path.is_file() {
+                match File::open(native_file_path) {
+                    Ok(file) => return Ok(ResReadFile(ReadFileInner::Native(file))),
+                    Err(_) => (), // For now we skip this.
+                }
+            } else if let Ok(file) = File::open(dvpl_path(dir_path, file_path)) {
+                // DVPL files are fully decoded because they are compressed as a whole.
+                let data = dvpl::read(file)?;
+                return Ok(ResReadFile(ReadFileInner::Memory(Cursor::new(data))));
             }
         }
 
@@ -145,6 +243,29 @@ impl ResFilesystem {
 
     }
 
+    /// Find where a file of the resource filesystem is stored, the file is not opened.
+    pub fn locate<P: AsRef<str>>(&self, file_path: P) -> io::Result<ResLocation> {
+
+        let file_path = file_path.as_ref();
+        if file_path.starts_with('/') || file_path.ends_with('/') {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        for dir_path in &self.shared.dir_paths {
+            let native_file_path = dir_path.join(file_path);
+            if native_file_path.is_file() {
+                return Ok(ResLocation::Native { path: native_file_path });
+            }
+            let dvpl_file_path = dvpl_path(dir_path, file_path);
+            if dvpl_file_path.is_file() {
+                return Ok(ResLocation::Dvpl { path: dvpl_file_path });
+            }
+        }
+
+        self.shared.mutable.lock().unwrap().locate(file_path)
+
+    }
+
     /// Read a directory's entries in the resource filesystem. This function may be 
     /// blocking a short time because it needs to find the first node of that directory.
     /// 
@@ -161,8 +282,12 @@ impl ResFilesystem {
         // Remove an possible trailing separator.
         let dir_path = dir_path.strip_suffix('/').unwrap_or(dir_path);
 
-        let native_dir_path = self.shared.dir_path.join(dir_path);
-        let native_read_dir = fs::read_dir(native_dir_path).ok();
+        // Reversed because the native directory with the highest priority is iterated
+        // first, and it's the last one.
+        let mut native_read_dirs = self.shared.dir_paths.iter()
+            .filter_map(|native_dir_path| fs::read_dir(native_dir_path.join(dir_path)).ok())
+            .collect::<Vec<_>>();
+        native_read_dirs.reverse();
         
         let mut mutable = self.shared.mutable.lock().unwrap();
         let mut dir_index = None;
@@ -175,7 +300,7 @@ impl ResFilesystem {
             } else if !mutable.try_open_pending_package() {
                 // No package contains this directory, only error if native read dir 
                 // also returned an error.
-                if native_read_dir.is_none() {
+                if native_read_dirs.is_empty() {
                     return Err(io::ErrorKind::NotFound.into()); 
                 } else {
                     break;
@@ -186,11 +311,11 @@ impl ResFilesystem {
         Ok(ResReadDir {
             dir_path: Arc::from(dir_path),
             common: Box::new(CommonReadDir {
-                native_read_dir,
+                native_read_dirs,
+                native_names: HashSet::new(),
                 package_read_dir: dir_index.map(|dir_index| PackageReadDir {
                     shared: Arc::clone(&self.shared),
                     dir_index,
-                    native_names: HashSet::new(),
                     remaining_names: Vec::new(),
                     last_children_count: 0,
                     last_children_last_node_index: 0,
@@ -201,28 +326,386 @@ impl ResFilesystem {
 
 }
 
+/// A staging layer over a resource filesystem, files can be written or removed without
+/// modifying the underlying filesystem, the staged changes are visible when reading
+/// through this layer and can later be committed into a package, see [`Self::commit`].
+/// 
+/// Note that directories listing is not affected by the staged changes, and that the
+/// underlying filesystem doesn't see committed changes because packages are indexed
+/// only once, a new filesystem should be created.
+#[derive(Debug)]
+pub struct ResFilesystemMut {
+    /// The underlying filesystem.
+    fs: ResFilesystem,
+    /// Staged changes by file path, the data is none for removed files.
+    changes: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl ResFilesystemMut {
+
+    /// Create a new staging layer over the given filesystem, without any change.
+    pub fn new(fs: ResFilesystem) -> Self {
+        Self {
+            fs,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Return the underlying filesystem.
+    #[inline]
+    pub fn fs(&self) -> &ResFilesystem {
+        &self.fs
+    }
+
+    /// Return true if there is any staged change.
+    #[inline]
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Return an iterator over all staged changes by file path, in path order, the 
+    /// data is none if the file is removed.
+    pub fn changes(&self) -> impl Iterator<Item = (&str, Option<&[u8]>)> {
+        self.changes.iter().map(|(path, data)| (path.as_str(), data.as_deref()))
+    }
+
+    /// Stage the addition of a file, or the replacement of an existing one, with the 
+    /// given data.
+    pub fn write<P: AsRef<str>>(&mut self, file_path: P, data: Vec<u8>) -> io::Result<()> {
+
+        let file_path = file_path.as_ref();
+        if file_path.is_empty() || file_path.starts_with('/') || file_path.ends_with('/') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid file path: {file_path}")));
+        }
+
+        if self.fs.stat(file_path).is_ok_and(|stat| stat.is_dir()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("path is a directory: {file_path}")));
+        }
+
+        self.changes.insert(file_path.to_string(), Some(data));
+        Ok(())
+
+    }
+
+    /// Stage the removal of a file, a [`io::ErrorKind::NotFound`] error is returned if
+    /// the file doesn't exist, or has already been removed. If the file was only added
+    /// by a staged change, this change is just reverted.
+    pub fn remove<P: AsRef<str>>(&mut self, file_path: P) -> io::Result<()> {
+
+        let file_path = file_path.as_ref();
+        let exists = self.fs.stat(file_path).is_ok_and(|stat| stat.is_file());
+
+        match self.changes.get(file_path) {
+            Some(None) => Err(io::ErrorKind::NotFound.into()),
+            Some(Some(_)) if !exists => {
+                self.changes.remove(file_path);
+                Ok(())
+            }
+            _ if exists => {
+                self.changes.insert(file_path.to_string(), None);
+                Ok(())
+            }
+            _ => Err(io::ErrorKind::NotFound.into()),
+        }
+
+    }
+
+    /// Revert any staged change of the given file, returning true if there was one.
+    pub fn revert<P: AsRef<str>>(&mut self, file_path: P) -> bool {
+        self.changes.remove(file_path.as_ref()).is_some()
+    }
+
+    /// Get information about a given path, like [`ResFilesystem::stat`] but with the
+    /// staged changes of files.
+    pub fn stat<P: AsRef<str>>(&self, node_path: P) -> io::Result<ResStat> {
+        let node_path = node_path.as_ref();
+        match self.changes.get(node_path) {
+            Some(Some(data)) => Ok(ResStat { is_dir: false, size: data.len() as u64 }),
+            Some(None) => Err(io::ErrorKind::NotFound.into()),
+            None => self.fs.stat(node_path),
+        }
+    }
+
+    /// Read a file, like [`ResFilesystem::read`] but with the staged changes.
+    pub fn read<P: AsRef<str>>(&self, file_path: P) -> io::Result<ResReadFile> {
+        let file_path = file_path.as_ref();
+        match self.changes.get(file_path) {
+            Some(Some(data)) => Ok(ResReadFile(ReadFileInner::Memory(Cursor::new(data.clone())))),
+            Some(None) => Err(io::ErrorKind::NotFound.into()),
+            None => self.fs.read(file_path),
+        }
+    }
+
+    /// Commit all staged changes into the given package, the package's root is the
+    /// directory of the package where files are written. If the package already exists,
+    /// it's rewritten with all its files, except the removed or replaced ones, else it
+    /// is created. Removed files that are not stored in this package cannot be removed
+    /// and are ignored. Staged changes are cleared once successfully committed.
+    /// 
+    /// The package is first written to a temporary file next to it, which then replaces
+    /// the package, so that the package is never left partially written.
+    pub fn commit(&mut self, package: &ResPackagePath) -> io::Result<()> {
+
+        let mut tmp_path = package.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let res = self.write_package(package, &tmp_path)
+            .and_then(|()| fs::rename(&tmp_path, &package.path));
+
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        } else {
+            self.changes.clear();
+        }
+
+        res
+
+    }
+
+    /// Internal function to write the committed package to the given path.
+    fn write_package(&self, package: &ResPackagePath, path: &Path) -> io::Result<()> {
+
+        let root = package.root.trim_matches('/');
+        let prefix = if root.is_empty() { String::new() } else { format!("{root}/") };
+
+        let mut writer = PackageWriter::new(BufWriter::new(File::create(path)?));
+
+        match File::open(&package.path) {
+            Ok(file) => {
+
+                let mut reader = PackageReader::new(file)?;
+                for file_index in 0..reader.len() {
+
+                    let name = reader.info_by_index(file_index).unwrap().name.to_string();
+
+                    // Files that are replaced or removed are not copied.
+                    if let Some(file_path) = name.strip_prefix(&prefix) {
+                        if self.changes.contains_key(file_path) {
+                            continue;
+                        }
+                    }
+
+                    writer.add_file(&name, reader.read_by_index(file_index)?)?;
+
+                }
+
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        for (file_path, data) in &self.changes {
+            if let Some(data) = data {
+                writer.add_file(&format!("{prefix}{file_path}"), &data[..])?;
+            }
+        }
+
+        writer.finish()?.flush()
+
+    }
+
+}
+
+/// A union of resource filesystems, layered by decreasing precedence: a file is taken
+/// from the first layer containing it, and directories list the entries of all layers.
+/// 
+/// Note that a single [`ResFilesystem`] created from the game's directory already
+/// resolves its 'paths.xml', so that 'res_mods/<version>' overlays the packages like in
+/// the game, this overlay is useful to add more layers on top of it, such as loose mod
+/// directories or the resources of another game version.
+#[derive(Debug, Clone, Default)]
+pub struct OverlayFilesystem {
+    /// All layers, by decreasing precedence.
+    layers: Vec<ResFilesystem>,
+}
+
+impl OverlayFilesystem {
+
+    /// Create a new overlay without any layer, where no file exists.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a filesystem as a new layer, with a lower precedence than all layers 
+    /// already added.
+    pub fn push_fs(&mut self, fs: ResFilesystem) {
+        self.layers.push(fs);
+    }
+
+    /// Add a loose native directory as a new layer, with a lower precedence than all
+    /// layers already added. The directory is mounted as-is, its packages are ignored.
+    pub fn push_dir<P: Into<PathBuf>>(&mut self, dir_path: P) {
+        self.layers.push(ResFilesystem::with_paths(ResPaths {
+            dirs: vec![dir_path.into()],
+            packages: Vec::new(),
+        }));
+    }
+
+    /// Return all layers, by decreasing precedence.
+    #[inline]
+    pub fn layers(&self) -> &[ResFilesystem] {
+        &self.layers
+    }
+
+    /// Get information about a given path, from the first layer containing it.
+    pub fn stat<P: AsRef<str>>(&self, node_path: P) -> io::Result<ResStat> {
+        let node_path = node_path.as_ref();
+        self.first_layer(|fs| fs.stat(node_path))
+    }
+
+    /// Read a file, from the first layer containing it.
+    pub fn read<P: AsRef<str>>(&self, file_path: P) -> io::Result<ResReadFile> {
+        let file_path = file_path.as_ref();
+        self.first_layer(|fs| fs.read(file_path))
+    }
+
+    /// Find where a file is stored, from the first layer containing it.
+    pub fn locate<P: AsRef<str>>(&self, file_path: P) -> io::Result<ResLocation> {
+        let file_path = file_path.as_ref();
+        self.first_layer(|fs| fs.locate(file_path))
+    }
+
+    /// Read a directory's entries in all layers, entries of a layer are hidden by the
+    /// entries with the same name of layers with higher precedence. A not found error
+    /// is returned if no layer contains this directory.
+    pub fn read_dir<P: AsRef<str>>(&self, dir_path: P) -> io::Result<OverlayReadDir> {
+
+        let dir_path = dir_path.as_ref();
+        let mut read_dirs = Vec::new();
+
+        for fs in &self.layers {
+            match fs.read_dir(dir_path) {
+                Ok(read_dir) => read_dirs.push(read_dir),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if read_dirs.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        // Reversed because the layer with the highest precedence is iterated first.
+        read_dirs.reverse();
+
+        Ok(OverlayReadDir {
+            read_dirs,
+            names: HashSet::new(),
+        })
+
+    }
+
+    /// Internal function to return the result of the first layer that doesn't return 
+    /// a not found error.
+    fn first_layer<T>(&self, mut func: impl FnMut(&ResFilesystem) -> io::Result<T>) -> io::Result<T> {
+        for fs in &self.layers {
+            match func(fs) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                res => return res,
+            }
+        }
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+}
+
+impl From<ResFilesystem> for OverlayFilesystem {
+    fn from(fs: ResFilesystem) -> Self {
+        Self { layers: vec![fs] }
+    }
+}
+
+/// A directory read iterator over all layers of an overlay filesystem, returned by 
+/// [`OverlayFilesystem::read_dir()`].
+#[derive(Debug)]
+pub struct OverlayReadDir {
+    /// Read dirs of each layer containing the directory, by increasing precedence, the
+    /// last one is iterated first.
+    read_dirs: Vec<ResReadDir>,
+    /// Names already returned by layers of higher precedence.
+    names: HashSet<Arc<str>>,
+}
+
+impl Iterator for OverlayReadDir {
+
+    type Item = io::Result<ResDirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        while let Some(read_dir) = self.read_dirs.last_mut() {
+            match read_dir.next() {
+                Some(Ok(entry)) => {
+                    if self.names.insert(Arc::clone(&entry.name)) {
+                        return Some(Ok(entry));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.read_dirs.pop();
+                }
+            }
+        }
+
+        None
+
+    }
+
+}
+
+/// Open the package at the given path with the reader used by the filesystem.
+#[cfg(feature = "mmap")]
+fn open_package_reader(path: &Path) -> io::Result<FsPackageReader> {
+    PackageReader::open_mmap(path)
+}
+
+/// Open the package at the given path with the reader used by the filesystem.
+#[cfg(not(feature = "mmap"))]
+fn open_package_reader(path: &Path) -> io::Result<FsPackageReader> {
+    File::open(path).and_then(PackageReader::new)
+}
+
+/// Return the path of the DVPL file wrapping the given file in a native directory.
+fn dvpl_path(dir_path: &Path, file_path: &str) -> PathBuf {
+    dir_path.join(format!("{file_path}.{}", dvpl::DVPL_EXTENSION))
+}
+
 impl SharedMut {
 
-    fn try_read(&mut self, file_path: &str) -> io::Result<Option<PackageFileReader<File>>> {
+    fn try_read(&mut self, file_path: &str) -> io::Result<Option<FsPackageFileReader>> {
         
         if let Some((_, file_info)) = self.node_cache.find_file(file_path) {
-            
-            let (
-                package_path, 
-                package_reader,
-            ) = self.package_reader_cache.get_index_mut(file_info.package_index).unwrap();
-            let mut file_reader = package_reader.read_by_index(file_info.file_index)?;
-
-            // Now that we have the reader, we want to make it owned, to do that we clone
-            // it with a new handle to the underlying package file.
-            return file_reader.try_clone_with(File::open(package_path)?).map(Some);
-
+            let (package_index, file_index) = (file_info.package_index, file_info.file_index);
+            self.read_package_file(package_index, file_index).map(Some)
         } else {
             Ok(None)
         }
 
     }
 
+    /// Open an owned reader of a file in a cached package, it directly shares the 
+    /// mapping of the package.
+    #[cfg(feature = "mmap")]
+    fn read_package_file(&mut self, package_index: usize, file_index: usize) -> io::Result<FsPackageFileReader> {
+        self.package_reader_cache[package_index].read_shared_by_index(file_index)
+    }
+
+    /// Open an owned reader of a file in a cached package.
+    #[cfg(not(feature = "mmap"))]
+    fn read_package_file(&mut self, package_index: usize, file_index: usize) -> io::Result<FsPackageFileReader> {
+
+        let (
+            package_path, 
+            package_reader,
+        ) = self.package_reader_cache.get_index_mut(package_index).unwrap();
+        let mut file_reader = package_reader.read_by_index(file_index)?;
+
+        // Now that we have the reader, we want to make it owned, to do that we clone
+        // it with a new handle to the underlying package file.
+        file_reader.try_clone_with(File::open(package_path)?)
+
+    }
+
     /// Open the next pending package and index it into the cache. This returns true if a
     /// pending package have been opened and cached, false if there are no more package.
     /// 
@@ -232,20 +715,12 @@ impl SharedMut {
     /// Errors considered critical are ones that happen on already opened packages.
     fn try_open_pending_package(&mut self) -> bool {
 
-        while let Some(package_path) = self.pending_package_path.pop() {
+        while let Some(package) = self.pending_packages.pop() {
 
-            let package_file = match File::open(&package_path) {
-                Ok(file) => file,
-                Err(e) => {
-                    self.package_open_errors.push((package_path, e));
-                    continue;
-                }
-            };
-
-            let package_reader = match PackageReader::new(package_file) {
+            let package_reader = match open_package_reader(&package.path) {
                 Ok(reader) => reader,
                 Err(e) => {
-                    self.package_open_errors.push((package_path, e));
+                    self.package_open_errors.push((package.path, e));
                     continue;
                 }
             };
@@ -253,10 +728,10 @@ impl SharedMut {
             let (
                 package_index, 
                 prev_package,
-            ) = self.package_reader_cache.insert_full(package_path, package_reader);
+            ) = self.package_reader_cache.insert_full(package.path, package_reader);
             debug_assert!(prev_package.is_none(), "duplicate package reader");
             
-            self.node_cache.index_package(package_index, &self.package_reader_cache[package_index]);
+            self.node_cache.index_package(package_index, &self.package_reader_cache[package_index], &package.root);
             // println!("  cache size: {}", self.node_cache.nodes.len());
             // println!("  dir count: {}", self.node_cache.dir_count);
             // println!("  dir children max count: {}", self.node_cache.dir_children_max_count);
@@ -272,7 +747,7 @@ impl SharedMut {
     }
 
     /// See [`ResFilesystem::read()`].
-    fn read(&mut self, file_path: &str) -> io::Result<PackageFileReader<File>> {
+    fn read(&mut self, file_path: &str) -> io::Result<FsPackageFileReader> {
 
         loop {
 
@@ -288,6 +763,37 @@ impl SharedMut {
 
     }
 
+    /// See [`ResFilesystem::locate()`].
+    fn locate(&mut self, file_path: &str) -> io::Result<ResLocation> {
+
+        loop {
+
+            if let Some((_, file_info)) = self.node_cache.find_file(file_path) {
+
+                let (package_path, package_reader) = self.package_reader_cache
+                    .get_index_mut(file_info.package_index)
+                    .unwrap();
+
+                let data_offset = package_reader.data_offset(file_info.file_index)?;
+                let info = package_reader.info_by_index(file_info.file_index).unwrap();
+                return Ok(ResLocation::Package { 
+                    path: package_path.clone(), 
+                    name: info.name.to_string(), 
+                    header_offset: info.header_offset as u64, 
+                    data_offset, 
+                    crc32: info.crc32,
+                });
+
+            }
+
+            if !self.try_open_pending_package() {
+                return Err(io::ErrorKind::NotFound.into());
+            }
+
+        }
+
+    }
+
     /// See [`ResFilesystem::stat()`].
     fn stat(&mut self, node_path: &str) -> io::Result<ResStat> {
 
@@ -327,8 +833,10 @@ pub struct ResReadFile(ReadFileInner);
 /// Inner handle to
 #[derive(Debug)]
 enum ReadFileInner {
-    Package(PackageFileReader<File>),
+    Package(FsPackageFileReader),
     Native(File),
+    /// A decoded native DVPL file.
+    Memory(Cursor<Vec<u8>>),
 }
 
 impl Read for ResReadFile {
@@ -337,6 +845,7 @@ impl Read for ResReadFile {
         match &mut self.0 {
             ReadFileInner::Package(package) => package.read(buf),
             ReadFileInner::Native(file) => file.read(buf),
+            ReadFileInner::Memory(cursor) => cursor.read(buf),
         }
     }
 
@@ -344,6 +853,7 @@ impl Read for ResReadFile {
         match &mut self.0 {
             ReadFileInner::Package(package) => package.read_exact(buf),
             ReadFileInner::Native(file) => file.read_exact(buf),
+            ReadFileInner::Memory(cursor) => cursor.read_exact(buf),
         }
     }
 
@@ -355,6 +865,7 @@ impl Seek for ResReadFile {
         match &mut self.0 {
             ReadFileInner::Package(package) => package.seek(pos),
             ReadFileInner::Native(file) => file.seek(pos),
+            ReadFileInner::Memory(cursor) => cursor.seek(pos),
         }
     }
 
@@ -362,6 +873,7 @@ impl Seek for ResReadFile {
         match &mut self.0 {
             ReadFileInner::Package(package) => package.stream_position(),
             ReadFileInner::Native(file) => file.stream_position(),
+            ReadFileInner::Memory(cursor) => cursor.stream_position(),
         }
     }
 
@@ -380,8 +892,12 @@ pub struct ResReadDir {
 
 #[derive(Debug)]
 struct CommonReadDir {
-    /// The native read dir result that maybe used for iteration before the package part.
-    native_read_dir: Option<ReadDir>,
+    /// The native read dirs that are used for iteration before the package part, by
+    /// increasing priority, the last one is iterated first.
+    native_read_dirs: Vec<ReadDir>,
+    /// Names already returned by native read dirs, these should not be duplicated when
+    /// returned by native directories of lower priority or by packages.
+    native_names: HashSet<Arc<str>>,
     /// The package read dir mode, yielded after the native read dir if present.
     package_read_dir: Option<PackageReadDir>,
 }
@@ -392,9 +908,6 @@ struct PackageReadDir {
     shared: Arc<Shared>,
     /// Directory index in the node cache.
     dir_index: usize,
-    /// If a native read dir is being used, then this contains names that should not be
-    /// duplicated when returned.
-    native_names: HashSet<Arc<str>>,
     /// A vector containing all names to return on next iterations. Name is associated to
     /// the node index in the cache, this
     remaining_names: Vec<(Arc<str>, usize)>,
@@ -420,7 +933,7 @@ impl Iterator for ResReadDir {
 
     fn next(&mut self) -> Option<Self::Item> {
 
-        if let Some(native_read_dir) = &mut self.common.native_read_dir {
+        while let Some(native_read_dir) = self.common.native_read_dirs.last_mut() {
             match native_read_dir.next() {
                 Some(Ok(entry)) => {
                     
@@ -430,14 +943,32 @@ impl Iterator for ResReadDir {
                         Err(e) => return Some(Err(e)),
                     };
 
-                    let file_name = match file_name.to_str() {
+                    let mut file_name = match file_name.to_str() {
                         Some(res) => res,
                         None => return Some(Err(io::ErrorKind::InvalidData.into())),
                     };
 
+                    let mut size = if metadata.is_dir() { 0 } else { metadata.len() };
+
+                    // DVPL files are listed as the file they wrap.
+                    if metadata.is_file() {
+                        if let Some(dvpl_file_name) = file_name.strip_suffix(dvpl::DVPL_EXTENSION)
+                            .and_then(|name| name.strip_suffix('.'))
+                        {
+                            let footer = File::open(entry.path())
+                                .and_then(DvplFooter::read_from_end);
+                            match footer {
+                                Ok(footer) => size = footer.original_size as u64,
+                                Err(e) => return Some(Err(e)),
+                            }
+                            file_name = dvpl_file_name;
+                        }
+                    }
+
+                    // Skip names already returned by a directory of higher priority.
                     let name = Arc::<str>::from(file_name);
-                    if let Some(package_read_dir) = &mut self.common.package_read_dir {
-                        package_read_dir.native_names.insert(Arc::clone(&name));
+                    if !self.common.native_names.insert(Arc::clone(&name)) {
+                        continue;
                     }
 
                     return Some(Ok(ResDirEntry { 
@@ -445,17 +976,20 @@ impl Iterator for ResReadDir {
                         name,
                         stat: ResStat {
                             is_dir: metadata.is_dir(),
-                            size: if metadata.is_dir() { 0 } else { metadata.len() },
+                            size,
                         },
                     }))
 
                 },
                 Some(Err(e)) => return Some(Err(e)),
-                None => self.common.native_read_dir = None,
+                None => {
+                    self.common.native_read_dirs.pop();
+                }
             }
         }
 
-        if let Some(package_read_dir) = &mut self.common.package_read_dir {
+        let CommonReadDir { native_names, package_read_dir, .. } = &mut *self.common;
+        if let Some(package_read_dir) = package_read_dir {
 
             // Then we search the directory iteratively, and loop over if a pending package
             // has been opened.
@@ -477,7 +1011,7 @@ impl Iterator for ResReadDir {
                         max_child_index = max_child_index.max(child_index);
                         if child_index >= package_read_dir.last_children_last_node_index {
                             // Don't return names that already have been by native iter.
-                            if !package_read_dir.native_names.contains(child_name) {
+                            if !native_names.contains(child_name) {
                                 package_read_dir.remaining_names.push((Arc::clone(child_name), child_index));
                             }
                         }
@@ -560,6 +1094,34 @@ impl ResDirEntry {
 
 }
 
+/// Where a resource file is stored, returned by [`ResFilesystem::locate()`].
+#[derive(Debug, Clone)]
+pub enum ResLocation {
+    /// A file of a native directory.
+    Native {
+        /// Path to the native file.
+        path: PathBuf,
+    },
+    /// A file of a native directory, wrapped in a DVPL file.
+    Dvpl {
+        /// Path to the native DVPL file.
+        path: PathBuf,
+    },
+    /// A file stored in a package.
+    Package {
+        /// Path to the package file.
+        path: PathBuf,
+        /// Name of the file within the package, including the package's root.
+        name: String,
+        /// Offset within the package of the file's local header.
+        header_offset: u64,
+        /// Offset within the package of the file's data.
+        data_offset: u64,
+        /// CRC32 of the file's data, as declared in the package.
+        crc32: u32,
+    },
+}
+
 /// Various informations about a file, wether it's a directory or a file and its size on
 /// disk (not compressed, package file are not compressed anyway...).
 #[derive(Debug)]
@@ -652,15 +1214,24 @@ impl NodeCache {
     }
 
     /// Index a package in this node cache, note that the caller should avoid calling 
-    /// this twice for the same packages.
-    fn index_package(&mut self, package_index: usize, package_reader: &PackageReader<File>) {
+    /// this twice for the same packages. Packages must be indexed by decreasing 
+    /// priority because files already indexed are never replaced. Only the files in
+    /// the given root directory of the package are indexed, relative to it.
+    fn index_package<R: Read + Seek>(&mut self, package_index: usize, package_reader: &PackageReader<R>, root: &str) {
 
         let mut last_dir_index = 0;
         let mut last_dir_path = ""; // This contains the end slash when relevant.
 
         for (file_index, file_info) in package_reader.infos().enumerate() {
             
-            let file_name = file_info.name;
+            let file_name = if root.is_empty() {
+                file_info.name
+            } else {
+                match file_info.name.strip_prefix(root).and_then(|name| name.strip_prefix('/')) {
+                    Some(name) => name,
+                    None => continue,
+                }
+            };
 
             // Always split the file name from the rest of the directory path.
             // NOTE: It is valid to split at 'index == file_path.len()', in this
@@ -725,9 +1296,13 @@ impl NodeCache {
                 .as_dir_mut()
                 .expect("current directory should effectively be a directory");
 
-            let prev_child = dir.children.insert(Arc::from(file_name), inner_len);
+            // The file is already provided by a package of higher priority.
+            if dir.children.contains_key(file_name) {
+                continue;
+            }
+
+            dir.children.insert(Arc::from(file_name), inner_len);
             self.dir_children_max_count = self.dir_children_max_count.max(dir.children.len());
-            debug_assert!(prev_child.is_none(), "overwriting a file");
             self.nodes.push(NodeInfo::File(FileInfo {
                 package_index,
                 file_index,
@@ -737,6 +1312,77 @@ impl NodeCache {
 
     }
 
+    /// Merge another node cache into this one, the nodes already present have priority
+    /// so the caches must be merged by decreasing priority, just like packages are 
+    /// indexed. The package indices of the other cache are offset by the given value,
+    /// so that it can be indexed on its own.
+    #[cfg_attr(not(feature = "parallel"), allow(unused))]
+    fn merge(&mut self, other: NodeCache, package_index_offset: usize) {
+        self.merge_dir(0, &other, 0, package_index_offset);
+        self.node_name_max_len = self.node_name_max_len.max(other.node_name_max_len);
+    }
+
+    /// Merge the children of a directory of another cache into a directory of this one.
+    fn merge_dir(&mut self, dir_index: usize, other: &NodeCache, other_dir_index: usize, package_index_offset: usize) {
+
+        let other_dir = other.nodes[other_dir_index].as_dir()
+            .expect("other node should effectively be a directory");
+
+        for (name, &other_child_index) in &other_dir.children {
+
+            let dir = self.nodes[dir_index].as_dir()
+                .expect("current node should effectively be a directory");
+
+            if let Some(&child_index) = dir.children.get(name) {
+                // Directories existing in both are merged, otherwise the node already 
+                // present has priority.
+                if self.nodes[child_index].as_dir().is_some() && other.nodes[other_child_index].as_dir().is_some() {
+                    self.merge_dir(child_index, other, other_child_index, package_index_offset);
+                }
+                continue;
+            }
+
+            let child_index = self.copy_node(other, other_child_index, package_index_offset);
+            let dir = self.nodes[dir_index].as_dir_mut().unwrap();
+            dir.children.insert(Arc::clone(name), child_index);
+            self.dir_children_max_count = self.dir_children_max_count.max(dir.children.len());
+
+        }
+
+    }
+
+    /// Copy a node of another cache, and all its children, into this cache and return
+    /// the index of the copied node.
+    fn copy_node(&mut self, other: &NodeCache, other_index: usize, package_index_offset: usize) -> usize {
+
+        let index = self.nodes.len();
+
+        match &other.nodes[other_index] {
+            NodeInfo::File(file_info) => {
+                self.nodes.push(NodeInfo::File(FileInfo {
+                    package_index: file_info.package_index + package_index_offset,
+                    file_index: file_info.file_index,
+                }));
+            }
+            NodeInfo::Dir(other_dir) => {
+
+                self.nodes.push(NodeInfo::Dir(DirInfo::default()));
+                self.dir_count += 1;
+
+                for (name, &other_child_index) in &other_dir.children {
+                    let child_index = self.copy_node(other, other_child_index, package_index_offset);
+                    self.nodes[index].as_dir_mut().unwrap().children.insert(Arc::clone(name), child_index);
+                }
+
+                self.dir_children_max_count = self.dir_children_max_count.max(other_dir.children.len());
+
+            }
+        }
+
+        index
+
+    }
+
     /// Find a node info in cache from the given path. In general it should not have 
     /// leading nor trailing directory separator. The index of the node within internal
     /// nodes array is already returned.
@@ -832,3 +1478,153 @@ impl fmt::Debug for NodeCache {
             .finish()
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn read_to_string(fs: &ResFilesystem, file_path: &str) -> io::Result<String> {
+        let mut data = String::new();
+        fs.read(file_path)?.read_to_string(&mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn commit_round_trip() {
+
+        let dir = std::env::temp_dir().join(format!("wgtk-res-commit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let package = ResPackagePath {
+            path: dir.join("shared_content.pkg"),
+            root: "res".to_string(),
+        };
+
+        let mut writer = PackageWriter::new(File::create(&package.path).unwrap());
+        writer.add_file("res/kept.txt", &b"kept"[..]).unwrap();
+        writer.add_file("res/removed.txt", &b"removed"[..]).unwrap();
+        writer.add_file("res/replaced.txt", &b"old"[..]).unwrap();
+        writer.add_file("other/outside.txt", &b"outside"[..]).unwrap();
+        writer.finish().unwrap();
+
+        let paths = || ResPaths { dirs: Vec::new(), packages: vec![package.clone()] };
+
+        let mut fs_mut = ResFilesystemMut::new(ResFilesystem::with_paths(paths()));
+        fs_mut.remove("removed.txt").unwrap();
+        fs_mut.write("replaced.txt", b"new".to_vec()).unwrap();
+        fs_mut.write("dir/added.txt", b"added".to_vec()).unwrap();
+        fs_mut.commit(&package).unwrap();
+        assert!(!fs_mut.has_changes());
+
+        let fs = ResFilesystem::with_paths(paths());
+        assert_eq!(read_to_string(&fs, "kept.txt").unwrap(), "kept");
+        assert_eq!(read_to_string(&fs, "replaced.txt").unwrap(), "new");
+        assert_eq!(read_to_string(&fs, "dir/added.txt").unwrap(), "added");
+        assert_eq!(read_to_string(&fs, "removed.txt").unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        // Files outside of the package root are kept.
+        let mut reader = PackageReader::new(File::open(&package.path).unwrap()).unwrap();
+        let mut data = String::new();
+        reader.read_by_name("other/outside.txt").unwrap().read_to_string(&mut data).unwrap();
+        assert_eq!(data, "outside");
+
+        fs::remove_dir_all(&dir).unwrap();
+
+    }
+
+    #[test]
+    fn overlay_layers() {
+
+        let dir = std::env::temp_dir().join(format!("wgtk-res-overlay-{}", std::process::id()));
+        let high_dir = dir.join("high");
+        fs::create_dir_all(high_dir.join("dir")).unwrap();
+        fs::write(high_dir.join("both.txt"), "high").unwrap();
+        fs::write(high_dir.join("dir/high.txt"), "high").unwrap();
+
+        let package = ResPackagePath {
+            path: dir.join("low.pkg"),
+            root: "res".to_string(),
+        };
+
+        let mut writer = PackageWriter::new(File::create(&package.path).unwrap());
+        writer.add_file("res/both.txt", &b"low"[..]).unwrap();
+        writer.add_file("res/low.txt", &b"low"[..]).unwrap();
+        writer.add_file("res/dir/low.txt", &b"low"[..]).unwrap();
+        writer.finish().unwrap();
+
+        let mut overlay = OverlayFilesystem::new();
+        overlay.push_dir(&high_dir);
+        overlay.push_fs(ResFilesystem::with_paths(ResPaths { dirs: Vec::new(), packages: vec![package] }));
+        assert_eq!(overlay.layers().len(), 2);
+
+        let read_to_string = |file_path: &str| -> io::Result<String> {
+            let mut data = String::new();
+            overlay.read(file_path)?.read_to_string(&mut data)?;
+            Ok(data)
+        };
+
+        // Files are taken from the first layer containing them.
+        assert_eq!(read_to_string("both.txt").unwrap(), "high");
+        assert_eq!(read_to_string("low.txt").unwrap(), "low");
+        assert_eq!(read_to_string("dir/low.txt").unwrap(), "low");
+        assert_eq!(read_to_string("missing.txt").unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(matches!(overlay.locate("both.txt").unwrap(), ResLocation::Native { path } if path == high_dir.join("both.txt")));
+        assert!(matches!(overlay.locate("low.txt").unwrap(), ResLocation::Package { .. }));
+        assert_eq!(overlay.stat("both.txt").unwrap().size(), 4);
+
+        // Directories list the entries of all layers, only once.
+        let read_dir = |dir_path: &str| -> io::Result<Vec<String>> {
+            let mut names = overlay.read_dir(dir_path)?
+                .map(|entry| entry.map(|entry| entry.name().to_string()))
+                .collect::<io::Result<Vec<_>>>()?;
+            names.sort();
+            Ok(names)
+        };
+
+        assert_eq!(read_dir("").unwrap(), ["both.txt", "dir", "low.txt"]);
+        assert_eq!(read_dir("dir").unwrap(), ["high.txt", "low.txt"]);
+        assert_eq!(read_dir("missing").unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+    }
+
+    #[test]
+    fn native_dvpl() {
+
+        let dir = std::env::temp_dir().join(format!("wgtk-res-dvpl-{}", std::process::id()));
+        fs::create_dir_all(dir.join("dir")).unwrap();
+
+        let compressed = "compressed ".repeat(100);
+        fs::write(dir.join("compressed.txt.dvpl"), dvpl::encode(compressed.as_bytes(), true).unwrap()).unwrap();
+        fs::write(dir.join("dir/raw.txt.dvpl"), dvpl::encode(b"raw", false).unwrap()).unwrap();
+        fs::write(dir.join("native.txt"), "native").unwrap();
+
+        let fs = ResFilesystem::with_paths(ResPaths { dirs: vec![dir.clone()], packages: Vec::new() });
+
+        // DVPL files are transparently decoded, under the name of the file they wrap.
+        assert_eq!(read_to_string(&fs, "compressed.txt").unwrap(), compressed);
+        assert_eq!(read_to_string(&fs, "dir/raw.txt").unwrap(), "raw");
+        assert_eq!(read_to_string(&fs, "native.txt").unwrap(), "native");
+        assert_eq!(fs.stat("compressed.txt").unwrap().size(), compressed.len() as u64);
+        assert!(matches!(fs.locate("dir/raw.txt").unwrap(), ResLocation::Dvpl { path } if path == dir.join("dir/raw.txt.dvpl")));
+        assert!(matches!(fs.locate("native.txt").unwrap(), ResLocation::Native { .. }));
+
+        let mut entries = fs.read_dir("").unwrap()
+            .map(|entry| entry.map(|entry| (entry.name().to_string(), entry.stat().size())))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        entries.sort();
+        assert_eq!(entries, [
+            ("compressed.txt".to_string(), compressed.len() as u64),
+            ("dir".to_string(), 0),
+            ("native.txt".to_string(), 6),
+        ]);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+    }
+
+}
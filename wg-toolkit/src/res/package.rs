@@ -6,15 +6,23 @@
 //! Following official specification: 
 //! https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
 
-use std::io::{self, Seek, Read, SeekFrom, BufReader};
+use std::io::{self, Seek, Read, Write, SeekFrom, BufReader, Cursor};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::fmt;
 
-use crate::util::io::WgReadExt;
+#[cfg(feature = "mmap")]
+use std::path::Path;
+#[cfg(feature = "mmap")]
+use std::fs::File;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use crate::util::io::{WgReadExt, WgWriteExt};
 
 
 /// Signature for the Local File Header structure.
-#[allow(unused)]
 const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
 
 /// Signature for the Central Directory Header structure.
@@ -23,6 +31,17 @@ const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014b50;
 /// Signature for the end of central directory.
 const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
 
+/// Version made by and needed to extract written in headers, 2.0 on MS-DOS, which is
+/// enough for stored files and directories.
+const WRITER_VERSION: u16 = 20;
+
+/// Modification date written in headers, packages don't care of it so the earliest
+/// DOS date is used: 1980-01-01, the time being 00:00:00.
+const WRITER_DATE: u16 = 0x0021;
+
+/// External attributes of directories, this is the MS-DOS directory attribute.
+const WRITER_DIR_ATTRIBUTES: u32 = 0x10;
+
 
 /// A package-specialized ZIP reader that is optimized for reading all file names as fast
 /// as possible. This reader only accesses file immutably. This reader ignores folders.
@@ -56,6 +75,8 @@ struct PackageFileInternalInfo {
     /// so the compressed size should be equal, but this will be checked later if the
     /// file is actually opened.
     size: u32,
+    /// CRC32 of the file's data, as declared in the central directory.
+    crc32: u32,
 }
 
 impl<R: Read + Seek> PackageReader<R> {
@@ -138,8 +159,10 @@ impl<R: Read + Seek> PackageReader<R> {
             }
 
             // Skip most of the header that we don't care at this point.
-            reader.seek_relative(20)?;
-            // Uncompressed size is used as 
+            reader.seek_relative(12)?;
+            let crc32 = reader.read_u32()?;
+            // Skip compressed size, uncompressed size is used as 
+            reader.seek_relative(4)?;
             let uncompressed_size = reader.read_u32()?;
             // Then we read all variable lengths.
             let file_name_len = reader.read_u16()?;
@@ -175,6 +198,7 @@ impl<R: Read + Seek> PackageReader<R> {
                 name_len: file_name_len,
                 header_offset: relative_offset,
                 size: uncompressed_size,
+                crc32,
             });
 
         }
@@ -208,6 +232,12 @@ impl<R: Read + Seek> PackageReader<R> {
         self.file_infos.len()
     }
 
+    /// Return true if the package has no file.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.file_infos.is_empty()
+    }
+
     /// Return an iterator over all file info in the package. The position of files in 
     /// this iterator is their index that can be used when reading from index, using
     /// the [`Self::read_by_index()`] method.
@@ -216,6 +246,8 @@ impl<R: Read + Seek> PackageReader<R> {
             PackageFileInfo {
                 name: &self.name_buffer[info.name_offset as usize..][..info.name_len as usize],
                 size: info.size,
+                crc32: info.crc32,
+                header_offset: info.header_offset,
             }
         })
     }
@@ -226,6 +258,8 @@ impl<R: Read + Seek> PackageReader<R> {
             PackageFileInfo {
                 name: &self.name_buffer[info.name_offset as usize..][..info.name_len as usize],
                 size: info.size,
+                crc32: info.crc32,
+                header_offset: info.header_offset,
             }
         })
     }
@@ -249,6 +283,28 @@ impl<R: Read + Seek> PackageReader<R> {
     /// construction, you should handle buffering if necessary.
     pub fn read_by_index(&mut self, file_index: usize) -> io::Result<PackageFileReader<&'_ mut R>> {
 
+        let (_, size) = self.read_local_header(file_index)?;
+        
+        // Now the reader's cursor is at data start, return the file reader.
+        Ok(PackageFileReader {
+            inner: &mut self.inner,
+            initial_len: size,
+            remaining_len: size,
+        })
+
+    }
+
+    /// Return the offset within the package of a file's data by its index, the local 
+    /// header of the file is read because its name and extra field lengths may differ
+    /// from the central directory.
+    pub fn data_offset(&mut self, file_index: usize) -> io::Result<u64> {
+        self.read_local_header(file_index).map(|(data_offset, _)| data_offset)
+    }
+
+    /// Read and check the local header of a file by its index, returning the offset of
+    /// the file's data and its size, the reader's cursor is left at the data start.
+    fn read_local_header(&mut self, file_index: usize) -> io::Result<(u64, u32)> {
+
         let info = self.file_infos.get(file_index)
             .ok_or(io::Error::from(io::ErrorKind::NotFound))?;
 
@@ -266,35 +322,189 @@ impl<R: Read + Seek> PackageReader<R> {
         self.inner.seek(SeekFrom::Current(2 + 2 + 4))?;
         let compressed_size = self.inner.read_u32()?;
         let uncompressed_size = self.inner.read_u32()?;
-        // Skip file name len + extra field length because it has already been checked.
-        self.inner.seek(SeekFrom::Current(4 + info.name_len as i64))?;
+        let name_len = self.inner.read_u16()?;
+        let extra_len = self.inner.read_u16()?;
+        // Skip file name, already known from the central directory, and extra field.
+        let data_offset = self.inner.seek(SeekFrom::Current(name_len as i64 + extra_len as i64))?;
 
-        // Incoherent uncompressed size, different from central directory header!
-        if uncompressed_size != info.size {
-            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        check_local_header(info, flags, compression_method, compressed_size, uncompressed_size)?;
+
+        Ok((data_offset, compressed_size))
+
+    }
+
+    /// Verify the integrity of a package file by its index, its data is fully read and
+    /// its size and CRC32 are checked against the central directory. An invalid data
+    /// error is returned if the file is corrupted.
+    pub fn verify(&mut self, file_index: usize) -> io::Result<()> {
+
+        let info = self.file_infos.get(file_index)
+            .ok_or(io::Error::from(io::ErrorKind::NotFound))?;
+
+        let expected_size = info.size;
+        let expected_crc32 = info.crc32;
+
+        let mut reader = self.read_by_index(file_index)?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut size = 0u32;
+        let mut buf = [0; 8192];
+
+        loop {
+            let len = reader.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            hasher.update(&buf[..len]);
+            size += len as u32;
         }
 
-        // Packages has no flag, no delayed crc32/size, no compression, no encryption.
-        if flags != 0 {
-            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        if size != expected_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, 
+                format!("size mismatch: expected {expected_size}, got {size}")));
         }
 
-        // Packages don't compress files.
-        if compression_method != 0 || compressed_size != uncompressed_size {
-            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        let crc32 = hasher.finalize();
+        if crc32 != expected_crc32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, 
+                format!("crc32 mismatch: expected {expected_crc32:08x}, got {crc32:08x}")));
         }
+
+        Ok(())
+
+    }
+
+}
+
+
+/// A package reader over a memory-mapped package file.
+#[cfg(feature = "mmap")]
+pub type MmapPackageReader = PackageReader<Cursor<PackageMmap>>;
+
+#[cfg(feature = "mmap")]
+impl PackageReader<Cursor<PackageMmap>> {
+
+    /// Open the package at the given path and map it in memory, files can then be read
+    /// as slices with [`Self::read_to_slice()`], without any system call.
+    /// 
+    /// The package file should not be modified while mapped, reads would then return
+    /// unspecified data, or even crash the process if it's truncated.
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read-only, and packages are not expected to be 
+        // modified while being read, see the documentation above.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::new(Cursor::new(PackageMmap(Arc::new(mmap))))
+    }
+
+    /// Open a package file by its index and return an owned reader sharing the mapping
+    /// of this package, unlike [`Self::read_by_index()`] this package reader is not
+    /// borrowed by the returned reader.
+    pub fn read_shared_by_index(&self, file_index: usize) -> io::Result<PackageFileReader<Cursor<PackageMmap>>> {
         
-        // Now the reader's cursor is at data start, return the file reader.
+        let mut reader = self.clone_with(Cursor::new(self.inner.get_ref().clone()));
+        let (_, size) = reader.read_local_header(file_index)?;
+
         Ok(PackageFileReader {
-            inner: &mut self.inner,
-            initial_len: compressed_size,
-            remaining_len: compressed_size,
+            inner: reader.inner,
+            initial_len: size,
+            remaining_len: size,
         })
 
     }
 
 }
 
+/// A shared memory mapping of a package file, cheap to clone.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone)]
+pub struct PackageMmap(Arc<Mmap>);
+
+#[cfg(feature = "mmap")]
+impl AsRef<[u8]> for PackageMmap {
+
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+
+}
+
+impl<T: AsRef<[u8]>> PackageReader<Cursor<T>> {
+
+    /// Read a package file by its index directly as a slice of the in-memory package, 
+    /// such as a memory-mapped package, see [`Self::open_mmap()`].
+    pub fn read_to_slice(&self, file_index: usize) -> io::Result<&[u8]> {
+
+        let info = self.file_infos.get(file_index)
+            .ok_or(io::Error::from(io::ErrorKind::NotFound))?;
+
+        let data = self.inner.get_ref().as_ref();
+        let header = data.get(info.header_offset as usize..)
+            .and_then(|header| header.get(..30))
+            .ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+        let read_u16 = |offset: usize| u16::from_le_bytes([header[offset], header[offset + 1]]);
+        let read_u32 = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+
+        if read_u32(0) != LOCAL_FILE_HEADER_SIGNATURE {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        // Same layout as read in [`Self::read_local_header()`].
+        let flags = read_u16(6);
+        let compression_method = read_u16(8);
+        let compressed_size = read_u32(18);
+        let uncompressed_size = read_u32(22);
+        let name_len = read_u16(26);
+        let extra_len = read_u16(28);
+
+        check_local_header(info, flags, compression_method, compressed_size, uncompressed_size)?;
+
+        let data_offset = info.header_offset as usize + 30 + name_len as usize + extra_len as usize;
+        data.get(data_offset..)
+            .and_then(|file_data| file_data.get(..compressed_size as usize))
+            .ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))
+
+    }
+
+    /// Read a package file by its name directly as a slice of the in-memory package.
+    pub fn read_to_slice_by_name(&self, file_name: &str) -> io::Result<&[u8]> {
+        let file_index = self.index_by_name(file_name)
+            .ok_or(io::Error::from(io::ErrorKind::NotFound))?;
+        self.read_to_slice(file_index)
+    }
+
+}
+
+/// Internal function to check the fields of a local header against the central 
+/// directory.
+fn check_local_header(
+    info: &PackageFileInternalInfo,
+    flags: u16,
+    compression_method: u16,
+    compressed_size: u32,
+    uncompressed_size: u32,
+) -> io::Result<()> {
+
+    // Incoherent uncompressed size, different from central directory header!
+    if uncompressed_size != info.size {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    // Packages has no flag, no delayed crc32/size, no compression, no encryption.
+    if flags != 0 {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    // Packages don't compress files.
+    if compression_method != 0 || compressed_size != uncompressed_size {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    Ok(())
+
+}
+
 
 /// Information about a package file that can be read.
 #[derive(Debug, Clone)]
@@ -303,6 +513,10 @@ pub struct PackageFileInfo<'a> {
     pub name: &'a str,
     /// The size of this file when read.
     pub size: u32,
+    /// CRC32 of the file's data, as declared in the package.
+    pub crc32: u32,
+    /// Offset within the package of the local header of this file.
+    pub header_offset: u32,
 }
 
 /// A handle for reading a file in a package.
@@ -422,3 +636,373 @@ impl<R: Read + Seek + fmt::Debug> fmt::Debug for PackageReader<R> {
             .field("file_infos", &self.file_infos.len()).finish()
     }
 }
+
+
+/// A package-specialized ZIP writer, files are stored without compression, flags nor
+/// extra fields, so that the written package can be read by the game and by
+/// [`PackageReader`]. Parent directories of each file are automatically added as
+/// directory entries, like in the game's packages.
+/// 
+/// The central directory is only written when [`Self::finish`] is called, the package
+/// is invalid until then.
+pub struct PackageWriter<W: Write + Seek> {
+    /// Underlying writer.
+    inner: W,
+    /// Entries already written, in order, with their local header offset.
+    entries: Vec<PackageWriterEntry>,
+    /// Names of all entries already written, used to reject duplicates.
+    names: HashSet<String>,
+}
+
+/// Internal metadata about a written file or directory.
+#[derive(Debug)]
+struct PackageWriterEntry {
+    /// Full name of the entry, directories have a trailing slash.
+    name: String,
+    /// Offset within the file of the local header of this entry.
+    header_offset: u32,
+    /// Size of the entry's data, zero for directories.
+    size: u32,
+    /// CRC32 of the entry's data, zero for directories.
+    crc32: u32,
+}
+
+impl<W: Write + Seek> PackageWriter<W> {
+
+    /// Create a package writer with the underlying write+seek implementor, the package
+    /// is written from the current position of the writer.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            entries: Vec::new(),
+            names: HashSet::new(),
+        }
+    }
+
+    /// Return the number of entries written so far, including directories.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if no entry has been written so far.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add a file to the package with the given name, its data is entirely read from
+    /// the given reader. The name should be a relative path with slash separators, an
+    /// [`io::ErrorKind::AlreadyExists`] error is returned if it's already written.
+    pub fn add_file<R: Read>(&mut self, file_name: &str, mut reader: R) -> io::Result<()> {
+
+        if file_name.is_empty() 
+        || file_name.starts_with('/') 
+        || file_name.ends_with('/') 
+        || file_name.contains('\\') 
+        || file_name.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid file name: {file_name}")));
+        }
+
+        if self.names.contains(file_name) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("file already written: {file_name}")));
+        }
+
+        // Add all parent directories that are not yet written.
+        for (index, _) in file_name.match_indices('/') {
+            let dir_name = &file_name[..index + 1];
+            if !self.names.contains(dir_name) {
+                self.write_local_header(dir_name, 0, 0)?;
+            }
+        }
+
+        // The CRC32 and size are not known before reading the whole file, so the local
+        // header is written first and then patched, because no data descriptor is used.
+        let header_offset = self.write_local_header(file_name, 0, 0)?;
+        let data_offset = self.inner.stream_position()?;
+
+        let mut writer = Crc32Writer {
+            inner: &mut self.inner,
+            hasher: crc32fast::Hasher::new(),
+        };
+
+        let size = io::copy(&mut reader, &mut writer)?;
+        let crc32 = writer.hasher.finalize();
+        let size = u32::try_from(size)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("file too large: {file_name}")))?;
+
+        // Patch CRC32 and sizes, located after signature, versions, flags, method, time
+        // and date.
+        self.inner.seek(SeekFrom::Start(header_offset as u64 + 14))?;
+        self.inner.write_u32(crc32)?;
+        self.inner.write_u32(size)?;
+        self.inner.write_u32(size)?;
+        self.inner.seek(SeekFrom::Start(data_offset + size as u64))?;
+
+        let entry = self.entries.last_mut().unwrap();
+        entry.size = size;
+        entry.crc32 = crc32;
+
+        Ok(())
+
+    }
+
+    /// Write the local header of an entry and register it, returning its offset.
+    fn write_local_header(&mut self, name: &str, size: u32, crc32: u32) -> io::Result<u32> {
+
+        if self.entries.len() >= u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many files in package"));
+        }
+
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("file name too long: {name}")))?;
+        let header_offset = u32::try_from(self.inner.stream_position()?)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "package too large"))?;
+
+        self.inner.write_u32(LOCAL_FILE_HEADER_SIGNATURE)?;
+        self.inner.write_u16(WRITER_VERSION)?;
+        self.inner.write_u16(0)?; // Flags
+        self.inner.write_u16(0)?; // Compression method
+        self.inner.write_u16(0)?; // Time
+        self.inner.write_u16(WRITER_DATE)?;
+        self.inner.write_u32(crc32)?;
+        self.inner.write_u32(size)?; // Compressed size
+        self.inner.write_u32(size)?; // Uncompressed size
+        self.inner.write_u16(name_len)?;
+        self.inner.write_u16(0)?; // Extra field length
+        self.inner.write_all(name.as_bytes())?;
+
+        self.names.insert(name.to_string());
+        self.entries.push(PackageWriterEntry {
+            name: name.to_string(),
+            header_offset,
+            size,
+            crc32,
+        });
+
+        Ok(header_offset)
+
+    }
+
+    /// Write the central directory and the end of central directory, then return the
+    /// underlying writer, which is not flushed.
+    pub fn finish(mut self) -> io::Result<W> {
+
+        let central_directory_offset = u32::try_from(self.inner.stream_position()?)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "package too large"))?;
+
+        for entry in &self.entries {
+
+            let is_dir = entry.name.ends_with('/');
+
+            self.inner.write_u32(CENTRAL_DIRECTORY_HEADER_SIGNATURE)?;
+            self.inner.write_u16(WRITER_VERSION)?; // Version made by
+            self.inner.write_u16(WRITER_VERSION)?; // Version needed to extract
+            self.inner.write_u16(0)?; // Flags
+            self.inner.write_u16(0)?; // Compression method
+            self.inner.write_u16(0)?; // Time
+            self.inner.write_u16(WRITER_DATE)?;
+            self.inner.write_u32(entry.crc32)?;
+            self.inner.write_u32(entry.size)?; // Compressed size
+            self.inner.write_u32(entry.size)?; // Uncompressed size
+            // Length has been checked when writing the local header.
+            self.inner.write_u16(entry.name.len() as u16)?;
+            self.inner.write_u16(0)?; // Extra field length
+            self.inner.write_u16(0)?; // File comment length
+            self.inner.write_u16(0)?; // Disk number start
+            self.inner.write_u16(0)?; // Internal attributes
+            self.inner.write_u32(if is_dir { WRITER_DIR_ATTRIBUTES } else { 0 })?;
+            self.inner.write_u32(entry.header_offset)?;
+            self.inner.write_all(entry.name.as_bytes())?;
+
+        }
+
+        let central_directory_size = u32::try_from(self.inner.stream_position()?)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "package too large"))?
+            - central_directory_offset;
+
+        // Number of entries has been checked when writing local headers.
+        let number_of_files = self.entries.len() as u16;
+
+        self.inner.write_u32(END_OF_CENTRAL_DIRECTORY_SIGNATURE)?;
+        self.inner.write_u16(0)?; // Disk number
+        self.inner.write_u16(0)?; // Disk with central directory
+        self.inner.write_u16(number_of_files)?;
+        self.inner.write_u16(number_of_files)?;
+        self.inner.write_u32(central_directory_size)?;
+        self.inner.write_u32(central_directory_offset)?;
+        self.inner.write_u16(0)?; // Comment length
+
+        Ok(self.inner)
+
+    }
+
+}
+
+impl<W: Write + Seek + fmt::Debug> fmt::Debug for PackageWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PackageWriter")
+            .field("inner", &self.inner)
+            .field("entries", &self.entries.len()).finish()
+    }
+}
+
+/// Internal writer computing the CRC32 of all written data.
+struct Crc32Writer<'a, W> {
+    inner: &'a mut W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Write for Crc32Writer<'_, W> {
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn writer_round_trip() {
+
+        let mut writer = PackageWriter::new(Cursor::new(Vec::new()));
+        assert!(writer.is_empty());
+        writer.add_file("root.txt", &b"root"[..]).unwrap();
+        writer.add_file("scripts/item_defs/vehicles.xml", &b"<vehicles/>"[..]).unwrap();
+        writer.add_file("scripts/empty.bin", &b""[..]).unwrap();
+        // Parent directories are written once, before the first file.
+        assert_eq!(writer.len(), 5);
+        
+        let err = writer.add_file("root.txt", &b""[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = writer.add_file("../escape.txt", &b""[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let data = writer.finish().unwrap().into_inner();
+        let mut reader = PackageReader::new(Cursor::new(data)).unwrap();
+        // Directories are not listed by the reader.
+        assert_eq!(reader.len(), 3);
+        assert!(!reader.is_empty());
+
+        let names = reader.infos().map(|info| info.name.to_string()).collect::<Vec<_>>();
+        assert_eq!(names, [
+            "root.txt", 
+            "scripts/item_defs/vehicles.xml", 
+            "scripts/empty.bin",
+        ].map(str::to_string));
+
+        for (name, expected) in [
+            ("root.txt", &b"root"[..]), 
+            ("scripts/item_defs/vehicles.xml", &b"<vehicles/>"[..]), 
+            ("scripts/empty.bin", &b""[..]),
+        ] {
+            let mut data = Vec::new();
+            reader.read_by_name(name).unwrap().read_to_end(&mut data).unwrap();
+            assert_eq!(data, expected, "{name}");
+            assert_eq!(reader.read_to_slice_by_name(name).unwrap(), expected, "{name}");
+            let index = reader.index_by_name(name).unwrap();
+            reader.verify(index).unwrap();
+        }
+
+    }
+
+    #[test]
+    fn local_extra_field() {
+
+        let name = "root.txt";
+        let extra = [0xAA; 6];
+        let data = b"root";
+        let crc32 = crc32fast::hash(data);
+
+        // A local header with an extra field, which is absent from the central directory.
+        let mut package = Vec::new();
+        package.write_u32(LOCAL_FILE_HEADER_SIGNATURE).unwrap();
+        package.write_u16(WRITER_VERSION).unwrap();
+        package.write_all(&[0; 2 + 2 + 2 + 2]).unwrap(); // Flags, compression, time, date
+        package.write_u32(crc32).unwrap();
+        package.write_u32(data.len() as u32).unwrap();
+        package.write_u32(data.len() as u32).unwrap();
+        package.write_u16(name.len() as u16).unwrap();
+        package.write_u16(extra.len() as u16).unwrap();
+        package.write_all(name.as_bytes()).unwrap();
+        package.write_all(&extra).unwrap();
+        let expected_data_offset = package.len() as u64;
+        package.write_all(data).unwrap();
+
+        let central_directory_offset = package.len() as u32;
+        package.write_u32(CENTRAL_DIRECTORY_HEADER_SIGNATURE).unwrap();
+        package.write_u16(WRITER_VERSION).unwrap();
+        package.write_u16(WRITER_VERSION).unwrap();
+        package.write_all(&[0; 2 + 2 + 2 + 2]).unwrap(); // Flags, compression, time, date
+        package.write_u32(crc32).unwrap();
+        package.write_u32(data.len() as u32).unwrap();
+        package.write_u32(data.len() as u32).unwrap();
+        package.write_u16(name.len() as u16).unwrap();
+        package.write_all(&[0; 2 + 2 + 2 + 2 + 4]).unwrap(); // Extra, comment, disk, attributes
+        package.write_u32(0).unwrap(); // Local header offset
+        package.write_all(name.as_bytes()).unwrap();
+        let central_directory_size = package.len() as u32 - central_directory_offset;
+
+        package.write_u32(END_OF_CENTRAL_DIRECTORY_SIGNATURE).unwrap();
+        package.write_all(&[0; 2 + 2]).unwrap(); // Disk numbers
+        package.write_u16(1).unwrap();
+        package.write_u16(1).unwrap();
+        package.write_u32(central_directory_size).unwrap();
+        package.write_u32(central_directory_offset).unwrap();
+        package.write_u16(0).unwrap(); // Comment length
+
+        let mut reader = PackageReader::new(Cursor::new(package)).unwrap();
+        assert_eq!(reader.data_offset(0).unwrap(), expected_data_offset);
+
+        let mut read_data = Vec::new();
+        reader.read_by_index(0).unwrap().read_to_end(&mut read_data).unwrap();
+        assert_eq!(read_data, data);
+        assert_eq!(reader.read_to_slice(0).unwrap(), data);
+        reader.verify(0).unwrap();
+
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn read_shared() {
+
+        let path = std::env::temp_dir().join(format!("wgtk-package-mmap-{}.pkg", std::process::id()));
+
+        let mut writer = PackageWriter::new(File::create(&path).unwrap());
+        writer.add_file("root.txt", &b"root"[..]).unwrap();
+        writer.add_file("scripts/empty.bin", &b""[..]).unwrap();
+        writer.finish().unwrap();
+
+        let reader = PackageReader::open_mmap(&path).unwrap();
+        for (index, expected) in [&b"root"[..], &b""[..]].into_iter().enumerate() {
+            let mut file_reader = reader.read_shared_by_index(index).unwrap();
+            let mut data = Vec::new();
+            file_reader.read_to_end(&mut data).unwrap();
+            assert_eq!(data, expected);
+            assert_eq!(reader.read_to_slice(index).unwrap(), expected);
+        }
+
+        drop(reader);
+        std::fs::remove_file(&path).unwrap();
+
+    }
+
+    #[test]
+    fn writer_empty() {
+        let data = PackageWriter::new(Cursor::new(Vec::new())).finish().unwrap().into_inner();
+        let reader = PackageReader::new(Cursor::new(data)).unwrap();
+        assert!(reader.is_empty());
+    }
+
+}
@@ -0,0 +1,286 @@
+//! Resolution of the directories and packages mounted in the resource filesystem, by
+//! parsing the game's 'paths.xml' file.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use super::PACKAGES_DIR_NAME;
+
+
+/// Name of the file, in the game's directory, describing the resources paths.
+pub const PATHS_FILE_NAME: &str = "paths.xml";
+
+/// Extension of package files.
+const PACKAGE_EXTENSION: &str = "pkg";
+
+
+/// The directories and packages to mount in the resource filesystem, all native
+/// directories have priority over packages.
+#[derive(Debug, Clone, Default)]
+pub struct ResPaths {
+    /// Native directories, by decreasing priority.
+    pub dirs: Vec<PathBuf>,
+    /// Packages, by decreasing priority.
+    pub packages: Vec<ResPackagePath>,
+}
+
+/// A package to mount in the resource filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResPackagePath {
+    /// Path to the package file.
+    pub path: PathBuf,
+    /// The directory within the package that is mounted as the root of the resource
+    /// filesystem, without leading or trailing separator, empty for the whole package.
+    pub root: String,
+}
+
+impl ResPaths {
+
+    /// Paths of the given directory, which is either the game's directory containing a
+    /// 'paths.xml' file, see [`Self::from_game_dir`], or directly the resources (res/)
+    /// directory, see [`Self::from_res_dir`].
+    pub fn from_dir(dir: &Path) -> io::Result<Self> {
+        if dir.join(PATHS_FILE_NAME).is_file() {
+            Self::from_game_dir(dir)
+        } else {
+            Self::from_res_dir(dir)
+        }
+    }
+
+    /// Paths of a single resources (res/) directory, the directory is mounted first,
+    /// followed by all of its packages ordered by name.
+    pub fn from_res_dir(dir: &Path) -> io::Result<Self> {
+        let mut paths = Self::default();
+        paths.dirs.push(dir.to_path_buf());
+        paths.packages = read_packages_dir(&dir.join(PACKAGES_DIR_NAME))?;
+        Ok(paths)
+    }
+
+    /// Read the paths of the game's directory from its 'paths.xml' file.
+    pub fn from_game_dir(game_dir: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(game_dir.join(PATHS_FILE_NAME))?;
+        Self::parse(game_dir, &content)
+    }
+
+    /// Parse the content of a 'paths.xml' file, relative paths are resolved from the
+    /// given game's directory. Paths are mounted in their order of appearance:
+    ///
+    /// - `<Path>dir</Path>` mounts a native directory, such as 'res_mods/<version>';
+    /// - `<Path mode="recursive" mask="*.wotmod" root="res">dir</Path>` mounts all
+    ///   packages matching the mask in the directory and its sub-directories, only the
+    ///   root directory of the packages is mounted;
+    /// - `<Package>name.pkg</Package>` mounts a package, a bare file name is resolved
+    ///   in the 'res/packages' directory.
+    ///
+    /// If no package is explicitly listed, the packages of all native directories are
+    /// mounted after, in the order of their directory and then by name.
+    pub fn parse(game_dir: &Path, content: &str) -> io::Result<Self> {
+
+        let mut paths = Self::default();
+        let mut explicit_packages = false;
+
+        for element in text_elements(content) {
+            match element.name {
+                "Path" if element.attr("mode") == Some("recursive") => {
+
+                    let mask = element.attr("mask").unwrap_or("*.pkg");
+                    let root = element.attr("root").unwrap_or("").trim_matches('/');
+
+                    let mut package_paths = Vec::new();
+                    find_packages(&game_dir.join(element.text), mask, &mut package_paths);
+                    package_paths.sort();
+
+                    explicit_packages = true;
+                    paths.packages.extend(package_paths.into_iter().map(|path| ResPackagePath {
+                        path,
+                        root: root.to_string(),
+                    }));
+
+                }
+                "Path" => {
+                    paths.dirs.push(game_dir.join(element.text));
+                }
+                "Package" => {
+
+                    let path = if element.text.contains(['/', '\\']) {
+                        game_dir.join(element.text)
+                    } else {
+                        game_dir.join("res").join(PACKAGES_DIR_NAME).join(element.text)
+                    };
+
+                    explicit_packages = true;
+                    paths.packages.push(ResPackagePath { path, root: String::new() });
+
+                }
+                _ => {}
+            }
+        }
+
+        if paths.dirs.is_empty() && paths.packages.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no path in paths.xml"));
+        }
+
+        if !explicit_packages {
+            for dir in &paths.dirs {
+                match read_packages_dir(&dir.join(PACKAGES_DIR_NAME)) {
+                    Ok(packages) => paths.packages.extend(packages),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(paths)
+
+    }
+
+}
+
+/// Return all packages of the given directory, ordered by name.
+fn read_packages_dir(dir: &Path) -> io::Result<Vec<ResPackagePath>> {
+
+    let mut package_paths = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == PACKAGE_EXTENSION) {
+            package_paths.push(path);
+        }
+
+    }
+
+    package_paths.sort();
+    Ok(package_paths.into_iter()
+        .map(|path| ResPackagePath { path, root: String::new() })
+        .collect())
+
+}
+
+/// Recursively find all files matching the given mask in the directory, the mask is
+/// either an exact file name or a '*' followed by a file name suffix. Directories that
+/// can't be read are ignored, just like the game does for absent mods directories.
+fn find_packages(dir: &Path, mask: &str, package_paths: &mut Vec<PathBuf>) {
+
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in read_dir.flatten() {
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            find_packages(&entry.path(), mask, package_paths);
+        } else if file_type.is_file() {
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            let matching = match mask.strip_prefix('*') {
+                Some(suffix) => file_name.ends_with(suffix),
+                None => file_name == mask,
+            };
+            if matching {
+                package_paths.push(entry.path());
+            }
+        }
+
+    }
+
+}
+
+/// An element of a plain XML document that only contains text.
+#[derive(Debug)]
+struct TextElement<'a> {
+    name: &'a str,
+    attrs: &'a str,
+    text: &'a str,
+}
+
+impl<'a> TextElement<'a> {
+
+    /// Get the value of an attribute from its name.
+    fn attr(&self, key: &str) -> Option<&'a str> {
+
+        let mut rest = self.attrs;
+        while let Some(eq_index) = rest.find('=') {
+
+            let name = rest[..eq_index].trim();
+            let value = rest[eq_index + 1..].trim_start();
+
+            let quote = value.chars().next().filter(|&c| c == '"' || c == '\'')?;
+            let value_len = value[1..].find(quote)?;
+            if name == key {
+                return Some(&value[1..1 + value_len]);
+            }
+
+            rest = &value[value_len + 2..];
+
+        }
+
+        None
+
+    }
+
+}
+
+/// Return all elements of a plain XML document that only contain text, in document
+/// order. This is not a conforming XML parser, but it's enough for 'paths.xml' which
+/// is not packed.
+fn text_elements(content: &str) -> Vec<TextElement<'_>> {
+
+    let mut elements = Vec::new();
+    let mut rest = content;
+
+    while let Some(start_index) = rest.find('<') {
+
+        rest = &rest[start_index + 1..];
+
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map(|end_index| &comment[end_index + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let Some(end_index) = rest.find('>') else {
+            break;
+        };
+
+        let tag = &rest[..end_index];
+        rest = &rest[end_index + 1..];
+
+        // Ignore closing tags, declarations and empty elements.
+        if tag.starts_with(['/', '?', '!']) || tag.ends_with('/') {
+            continue;
+        }
+
+        let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+
+        // Only keep the element if its text is directly followed by its closing tag.
+        let Some(text_len) = rest.find('<') else {
+            break;
+        };
+
+        let after_text = &rest[text_len..];
+        let closing_len = after_text.strip_prefix("</")
+            .and_then(|after_text| after_text.strip_prefix(name))
+            .and_then(|after_name| after_name.trim_start().strip_prefix('>'))
+            .map(|after_closing| after_text.len() - after_closing.len());
+
+        if let Some(closing_len) = closing_len {
+            elements.push(TextElement { name, attrs, text: rest[..text_len].trim() });
+            rest = &after_text[closing_len..];
+        }
+
+    }
+
+    elements
+
+}
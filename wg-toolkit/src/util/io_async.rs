@@ -0,0 +1,494 @@
+//! This module provides async counterparts of the [`WgReadExt`] and [`WgWriteExt`]
+//! traits, for tokio's [`AsyncRead`] and [`AsyncWrite`].
+//!
+//! Methods have the same names and semantics as their blocking counterparts, but
+//! they return futures. Note that importing both these traits and tokio's own
+//! extension traits makes methods such as `read_u8` ambiguous.
+//!
+//! [`WgReadExt`]: super::io::WgReadExt
+//! [`WgWriteExt`]: super::io::WgWriteExt
+
+use std::future::Future;
+use std::net::{SocketAddrV4, Ipv4Addr};
+use std::io;
+
+use tokio::io::{self as tio, AsyncRead, AsyncWrite};
+use glam::{Vec2, Vec3, Vec4};
+
+use super::io::{serde_pickle_de_options, serde_pickle_ser_options};
+
+
+/// An extension to the [`AsyncRead`] trait specifically used to decode WG formats
+/// and used for network protocol.
+pub trait WgAsyncReadExt: AsyncRead + Unpin + Send {
+
+    /// Reads an unsigned 8 bit integer from the underlying reader.
+    #[inline]
+    fn read_u8(&mut self) -> impl Future<Output = io::Result<u8>> + Send {
+        tio::AsyncReadExt::read_u8(self)
+    }
+
+    /// Reads a signed 8 bit integer from the underlying reader.
+    #[inline]
+    fn read_i8(&mut self) -> impl Future<Output = io::Result<i8>> + Send {
+        tio::AsyncReadExt::read_i8(self)
+    }
+
+    /// Skip the given number of u8 integers.
+    fn skip<const N: usize>(&mut self) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            let mut buf = [0; N];
+            tio::AsyncReadExt::read_exact(self, &mut buf).await?;
+            Ok(())
+        }
+    }
+
+    /// Reads an unsigned 16 bit integer from the underlying reader.
+    #[inline]
+    fn read_u16(&mut self) -> impl Future<Output = io::Result<u16>> + Send {
+        tio::AsyncReadExt::read_u16_le(self)
+    }
+
+    /// Reads a signed 16 bit integer from the underlying reader.
+    #[inline]
+    fn read_i16(&mut self) -> impl Future<Output = io::Result<i16>> + Send {
+        tio::AsyncReadExt::read_i16_le(self)
+    }
+
+    /// Reads an unsigned 24 bit integer from the underlying reader.
+    fn read_u24(&mut self) -> impl Future<Output = io::Result<u32>> + Send {
+        async move {
+            let mut buf = [0; 4];
+            tio::AsyncReadExt::read_exact(self, &mut buf[..3]).await?;
+            Ok(u32::from_le_bytes(buf))
+        }
+    }
+
+    /// Reads a signed 24 bit integer from the underlying reader.
+    fn read_i24(&mut self) -> impl Future<Output = io::Result<i32>> + Send {
+        async move {
+            let n = self.read_u24().await?;
+            // Sign-extend from the 24th bit.
+            Ok(((n << 8) as i32) >> 8)
+        }
+    }
+
+    /// Reads an unsigned 32 bit integer from the underlying reader.
+    #[inline]
+    fn read_u32(&mut self) -> impl Future<Output = io::Result<u32>> + Send {
+        tio::AsyncReadExt::read_u32_le(self)
+    }
+
+    /// Reads a signed 32 bit integer from the underlying reader.
+    #[inline]
+    fn read_i32(&mut self) -> impl Future<Output = io::Result<i32>> + Send {
+        tio::AsyncReadExt::read_i32_le(self)
+    }
+
+    /// Read a packed unsigned 24 bit integer from the underlying reader.
+    fn read_packed_u24(&mut self) -> impl Future<Output = io::Result<u32>> + Send {
+        async move {
+            match self.read_u8().await? {
+                255 => self.read_u24().await,
+                n => Ok(n as u32)
+            }
+        }
+    }
+
+    /// Reads an unsigned 64 bit integer from the underlying reader.
+    #[inline]
+    fn read_u64(&mut self) -> impl Future<Output = io::Result<u64>> + Send {
+        tio::AsyncReadExt::read_u64_le(self)
+    }
+
+    /// Reads a signed 64 bit integer from the underlying reader.
+    #[inline]
+    fn read_i64(&mut self) -> impl Future<Output = io::Result<i64>> + Send {
+        tio::AsyncReadExt::read_i64_le(self)
+    }
+
+    /// Reads a IEEE754 single-precision (4 bytes) floating point number
+    /// from the underlying reader.
+    #[inline]
+    fn read_f32(&mut self) -> impl Future<Output = io::Result<f32>> + Send {
+        tio::AsyncReadExt::read_f32_le(self)
+    }
+
+    /// Reads a IEEE754 double-precision (8 bytes) floating point number
+    /// from the underlying reader.
+    #[inline]
+    fn read_f64(&mut self) -> impl Future<Output = io::Result<f64>> + Send {
+        tio::AsyncReadExt::read_f64_le(self)
+    }
+
+    /// Read a single boolean from the underlying reader.
+    fn read_bool(&mut self) -> impl Future<Output = io::Result<bool>> + Send {
+        async move {
+            Ok(self.read_u8().await? != 0)
+        }
+    }
+
+    /// Check that the next `N` bytes are the exact same as the given array.
+    fn check_exact<const N: usize>(&mut self, bytes: &[u8; N]) -> impl Future<Output = io::Result<bool>> + Send {
+        async move {
+            let mut buf = [0; N];
+            tio::AsyncReadExt::read_exact(self, &mut buf[..]).await?;
+            Ok(&buf == bytes)
+        }
+    }
+
+    fn read_blob_to_end(&mut self) -> impl Future<Output = io::Result<Vec<u8>>> + Send {
+        async move {
+            let mut buf = Vec::new();
+            tio::AsyncReadExt::read_to_end(self, &mut buf).await?;
+            Ok(buf)
+        }
+    }
+
+    /// Read a blob of the given length.
+    fn read_blob(&mut self, len: usize) -> impl Future<Output = io::Result<Vec<u8>>> + Send {
+        async move {
+            let mut buf = vec![0; len];
+            tio::AsyncReadExt::read_exact(self, &mut buf[..]).await?;
+            Ok(buf)
+        }
+    }
+
+    /// Read a blob of a length that is specified with a packed u32 before the
+    /// actual vector.
+    fn read_blob_variable(&mut self) -> impl Future<Output = io::Result<Vec<u8>>> + Send {
+        async move {
+            let len = self.read_packed_u24().await? as usize;
+            self.read_blob(len).await
+        }
+    }
+
+    /// Read an UTF-8 string of the given length.
+    fn read_string(&mut self, len: usize) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            String::from_utf8(self.read_blob(len).await?)
+                .map_err(|_| io::ErrorKind::InvalidData.into())
+        }
+    }
+
+    fn read_string_lossy(&mut self, len: usize) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            Ok(String::from_utf8_lossy(&self.read_blob(len).await?).into_owned())
+        }
+    }
+
+    /// Read an UTF-8 string of a length that is specified with a packed u32
+    /// before the actual vector.
+    fn read_string_variable(&mut self) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            let blob = self.read_blob_variable().await?;
+            match String::from_utf8(blob) {
+                Ok(s) => Ok(s),
+                Err(_) => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid utf8 string"))
+            }
+        }
+    }
+
+    fn read_string_variable_lossy(&mut self) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            let blob = self.read_blob_variable().await?;
+            Ok(String::from_utf8_lossy(&blob).into_owned())
+        }
+    }
+
+    /// Read a null-terminated string of a fixed length, trailing zeros
+    /// are ignored and if no zero is encountered, an invalid data error
+    /// is returned.
+    fn read_cstring(&mut self, len: usize) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            let mut buf = self.read_blob(len).await?;
+            let pos = buf.iter().position(|&o| o == 0)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+            buf.truncate(pos); // Truncate trailing zeros.
+            String::from_utf8(buf).map_err(|_| io::ErrorKind::InvalidData.into())
+        }
+    }
+
+    /// Read a null-terminated string of unknown length.
+    fn read_cstring_variable(&mut self) -> impl Future<Output = io::Result<String>> + Send {
+        async move {
+            // Just like the blocking version, this should be used on buffered readers.
+            let mut buf = Vec::new();
+            loop {
+                let b = self.read_u8().await?;
+                if b == 0 {
+                    break
+                }
+                buf.push(b);
+            }
+            String::from_utf8(buf).map_err(|_| io::ErrorKind::InvalidData.into())
+        }
+    }
+
+    fn read_sock_addr_v4(&mut self) -> impl Future<Output = io::Result<SocketAddrV4>> + Send {
+        async move {
+            let mut ip_raw = [0; 4];
+            tio::AsyncReadExt::read_exact(self, &mut ip_raw[..]).await?;
+            let port = tio::AsyncReadExt::read_u16(self).await?;
+            let _salt = self.read_u16().await?;
+            Ok(SocketAddrV4::new(Ipv4Addr::from(ip_raw), port))
+        }
+    }
+
+    fn read_vec2(&mut self) -> impl Future<Output = io::Result<Vec2>> + Send {
+        async move {
+            Ok(Vec2::new(
+                self.read_f32().await?,
+                self.read_f32().await?,
+            ))
+        }
+    }
+
+    fn read_vec3(&mut self) -> impl Future<Output = io::Result<Vec3>> + Send {
+        async move {
+            Ok(Vec3::new(
+                self.read_f32().await?,
+                self.read_f32().await?,
+                self.read_f32().await?,
+            ))
+        }
+    }
+
+    fn read_vec4(&mut self) -> impl Future<Output = io::Result<Vec4>> + Send {
+        async move {
+            Ok(Vec4::new(
+                self.read_f32().await?,
+                self.read_f32().await?,
+                self.read_f32().await?,
+                self.read_f32().await?,
+            ))
+        }
+    }
+
+    /// Read a Python Pickle, this also reads the length of the pickle's data in the
+    /// packed header. The pickle is decoded once fully read.
+    fn read_python_pickle(&mut self) -> impl Future<Output = io::Result<serde_pickle::Value>> + Send {
+        async move {
+            let data = self.read_blob_variable().await?;
+            serde_pickle::value_from_slice(&data, serde_pickle_de_options())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+    }
+
+    /// Read the size header for a single structure. To read the header of
+    /// a vector, see `read_vector_head`.
+    fn read_single_head(&mut self) -> impl Future<Output = io::Result<usize>> + Send {
+        async move {
+            Ok(self.read_u32().await? as usize)
+        }
+    }
+
+    /// Read header for vector of structure, returns `(size, count)` with the
+    /// number of structure of the given size, total size is `size * count`.
+    fn read_vector_head(&mut self) -> impl Future<Output = io::Result<(usize, usize)>> + Send {
+        async move {
+            let sec_size = self.read_u32().await? as usize;
+            let sec_count = self.read_u32().await? as usize;
+            Ok((sec_size, sec_count))
+        }
+    }
+
+}
+
+/// An extension to the [`AsyncWrite`] trait specifically used to decode WG formats
+/// and used for network protocol.
+pub trait WgAsyncWriteExt: AsyncWrite + Unpin + Send {
+
+    /// Writes an unsigned 8 bit integer to the underlying writer.
+    #[inline]
+    fn write_u8(&mut self, n: u8) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_u8(self, n)
+    }
+
+    /// Writes a signed 8 bit integer to the underlying writer.
+    #[inline]
+    fn write_i8(&mut self, n: i8) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_i8(self, n)
+    }
+
+    /// Writes an unsigned 16 bit integer to the underlying writer.
+    #[inline]
+    fn write_u16(&mut self, n: u16) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_u16_le(self, n)
+    }
+
+    /// Writes a signed 16 bit integer to the underlying writer.
+    #[inline]
+    fn write_i16(&mut self, n: i16) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_i16_le(self, n)
+    }
+
+    /// Writes an unsigned 24 bit integer to the underlying writer.
+    fn write_u24(&mut self, n: u32) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            tio::AsyncWriteExt::write_all(self, &n.to_le_bytes()[..3]).await
+        }
+    }
+
+    /// Writes a signed 24 bit integer to the underlying writer.
+    fn write_i24(&mut self, n: i32) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            tio::AsyncWriteExt::write_all(self, &n.to_le_bytes()[..3]).await
+        }
+    }
+
+    /// Writes an unsigned 32 bit integer to the underlying writer.
+    #[inline]
+    fn write_u32(&mut self, n: u32) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_u32_le(self, n)
+    }
+
+    /// Writes a signed 32 bit integer to the underlying writer.
+    #[inline]
+    fn write_i32(&mut self, n: i32) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_i32_le(self, n)
+    }
+
+    /// Writes a packed unsigned 24 bit integer to the underlying writer.
+    fn write_packed_u24(&mut self, n: u32) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            if n >= 255 {
+                self.write_u8(255).await?;
+                self.write_u24(n).await
+            } else {
+                self.write_u8(n as u8).await
+            }
+        }
+    }
+
+    /// Writes an unsigned 64 bit integer to the underlying writer.
+    #[inline]
+    fn write_u64(&mut self, n: u64) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_u64_le(self, n)
+    }
+
+    /// Writes a signed 64 bit integer to the underlying writer.
+    #[inline]
+    fn write_i64(&mut self, n: i64) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_i64_le(self, n)
+    }
+
+    /// Writes a IEEE754 single-precision (4 bytes) floating point number
+    /// to the underlying writer.
+    #[inline]
+    fn write_f32(&mut self, n: f32) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_f32_le(self, n)
+    }
+
+    /// Writes a IEEE754 double-precision (8 bytes) floating point number
+    /// to the underlying writer.
+    #[inline]
+    fn write_f64(&mut self, n: f64) -> impl Future<Output = io::Result<()>> + Send {
+        tio::AsyncWriteExt::write_f64_le(self, n)
+    }
+
+    /// Write a single boolean to the underlying writer.
+    #[inline]
+    fn write_bool(&mut self, b: bool) -> impl Future<Output = io::Result<()>> + Send {
+        self.write_u8(b as _)
+    }
+
+    #[inline]
+    fn write_blob(&mut self, data: &[u8]) -> impl Future<Output = io::Result<()>> + Send {
+        async move { tio::AsyncWriteExt::write_all(self, data).await }
+    }
+
+    /// Write a blob with its packed length before the actual data.
+    fn write_blob_variable(&mut self, data: &[u8]) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_packed_u24(data.len() as u32).await?;
+            self.write_blob(data).await
+        }
+    }
+
+    /// Writes a string to the underlying writer. Note that the length of
+    /// the string is not written.
+    #[inline]
+    fn write_string(&mut self, s: &str) -> impl Future<Output = io::Result<()>> + Send {
+        self.write_blob(s.as_bytes())
+    }
+
+    /// Write a string with its packed length before.
+    #[inline]
+    fn write_string_variable(&mut self, s: &str) -> impl Future<Output = io::Result<()>> + Send {
+        self.write_blob_variable(s.as_bytes())
+    }
+
+    /// Writes a null-terminated string to the underlying writer.
+    fn write_cstring(&mut self, s: &str) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_string(s).await?;
+            self.write_u8(0).await
+        }
+    }
+
+    /// Write the size header for a single structure. To write the header of
+    /// a vector, see `write_vector_head`.
+    #[inline]
+    fn write_single_head(&mut self, n: usize) -> impl Future<Output = io::Result<()>> + Send {
+        self.write_u32(n as u32)
+    }
+
+    fn write_sock_addr_v4(&mut self, addr: SocketAddrV4) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_blob(&addr.ip().octets()[..]).await?;
+            tio::AsyncWriteExt::write_u16(self, addr.port()).await?;
+            self.write_u16(0).await?; // Salt
+            Ok(())
+        }
+    }
+
+    fn write_vec2(&mut self, vec: Vec2) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_f32(vec.x).await?;
+            self.write_f32(vec.y).await?;
+            Ok(())
+        }
+    }
+
+    fn write_vec3(&mut self, vec: Vec3) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_f32(vec.x).await?;
+            self.write_f32(vec.y).await?;
+            self.write_f32(vec.z).await?;
+            Ok(())
+        }
+    }
+
+    fn write_vec4(&mut self, vec: Vec4) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_f32(vec.x).await?;
+            self.write_f32(vec.y).await?;
+            self.write_f32(vec.z).await?;
+            self.write_f32(vec.w).await?;
+            Ok(())
+        }
+    }
+
+    /// Write a Python Pickle from the given value, the pickle's data is prefixed with
+    /// the variable length of the data (like a variable blob or string).
+    fn write_python_pickle(&mut self, value: &serde_pickle::Value) -> impl Future<Output = io::Result<()>> + Send {
+        let data = serde_pickle::value_to_vec(value, serde_pickle_ser_options());
+        async move {
+            let data = data.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            self.write_blob_variable(&data).await
+        }
+    }
+
+    /// Write header for vector of structure.
+    fn write_vector_head(&mut self, size: usize, count: usize) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.write_u32(size as u32).await?;
+            self.write_u32(count as u32).await
+        }
+    }
+
+}
+
+impl<R: AsyncRead + Unpin + Send + ?Sized> WgAsyncReadExt for R {}
+impl<W: AsyncWrite + Unpin + Send + ?Sized> WgAsyncWriteExt for W {}
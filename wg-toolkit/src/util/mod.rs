@@ -3,9 +3,13 @@
 use std::fmt::{self, Write};
 
 pub mod io;
+#[cfg(feature = "tokio")]
+pub mod io_async;
 pub mod fnv;
 pub mod cuckoo;
 pub mod thread;
+pub mod pcap;
+pub mod pickle;
 
 
 /// A helper structure for pretty printing of bytes. It provides format implementations 
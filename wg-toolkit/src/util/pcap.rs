@@ -0,0 +1,674 @@
+//! Minimal reader for pcap and pcapng capture files, only extracting UDP datagrams
+//! over IPv4 and IPv6, this is used to import captures into the network decoding
+//! pipeline. A minimal pcapng writer of UDP datagrams is also provided.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use thiserror::Error;
+
+
+/// Pcap magic for microsecond timestamps.
+const PCAP_MAGIC_US: u32 = 0xA1B2C3D4;
+/// Pcap magic for nanosecond timestamps.
+const PCAP_MAGIC_NS: u32 = 0xA1B23C4D;
+/// Pcapng section header block type, also the magic of the file.
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+/// Pcapng byte order magic, in the section header block.
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x00000001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x00000003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x00000006;
+
+/// Pcapng option code for the interface timestamp resolution.
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IP_PROTO_UDP: u8 = 17;
+
+/// Maximum captured length of a packet, this is the largest snap length used by the
+/// capture tools, larger lengths are considered invalid instead of being allocated.
+const MAX_PACKET_LEN: u32 = 262144;
+/// Maximum length of a pcapng block, this leaves room for a packet of the maximum
+/// length and its options.
+const MAX_BLOCK_LEN: u32 = 16 * 1024 * 1024;
+
+
+/// A reader for pcap or pcapng capture, the format is detected from the magic.
+#[derive(Debug)]
+pub struct PcapReader<R> {
+    /// Underlying reader.
+    inner: R,
+    /// The detected format and its state.
+    format: Format,
+    /// True if integers should be read in big endian.
+    big_endian: bool,
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        /// Link type of all packets.
+        link_type: u32,
+        /// True if timestamp sub-second part is in nanoseconds.
+        nanos: bool,
+    },
+    Pcapng {
+        /// All interfaces of the current section, in order of description.
+        interfaces: Vec<Interface>,
+    },
+}
+
+#[derive(Debug)]
+struct Interface {
+    /// Link type of all packets of this interface.
+    link_type: u32,
+    /// Number of timestamp units per second.
+    units_per_sec: u64,
+}
+
+/// An UDP datagram extracted from the capture.
+#[derive(Debug, Clone)]
+pub struct UdpDatagram {
+    /// Capture time, relative to the UNIX epoch.
+    pub time: Duration,
+    /// Source address of the datagram.
+    pub src: SocketAddr,
+    /// Destination address of the datagram.
+    pub dst: SocketAddr,
+    /// The UDP payload.
+    pub data: Vec<u8>,
+}
+
+impl<R: Read> PcapReader<R> {
+
+    /// Create a new capture reader, reading the file header and detecting the format.
+    pub fn new(mut inner: R) -> Result<Self, PcapError> {
+
+        let mut magic_buf = [0; 4];
+        inner.read_exact(&mut magic_buf)?;
+
+        let magic_le = u32::from_le_bytes(magic_buf);
+        let magic_be = u32::from_be_bytes(magic_buf);
+
+        let mut reader;
+        if magic_le == PCAPNG_SECTION_HEADER {
+            reader = Self {
+                inner,
+                format: Format::Pcapng { interfaces: Vec::new() },
+                big_endian: false,
+            };
+            reader.read_pcapng_section_header()?;
+        } else {
+
+            let (big_endian, nanos) = match (magic_le, magic_be) {
+                (PCAP_MAGIC_US, _) => (false, false),
+                (PCAP_MAGIC_NS, _) => (false, true),
+                (_, PCAP_MAGIC_US) => (true, false),
+                (_, PCAP_MAGIC_NS) => (true, true),
+                _ => return Err(PcapError::InvalidMagic(magic_le)),
+            };
+
+            reader = Self {
+                inner,
+                format: Format::Pcap { link_type: 0, nanos },
+                big_endian,
+            };
+
+            // Version major, minor, time zone, sig figs, snap len.
+            let mut header = [0; 16];
+            reader.inner.read_exact(&mut header)?;
+            let link_type = reader.read_u32()?;
+
+            if let Format::Pcap { link_type: format_link_type, .. } = &mut reader.format {
+                *format_link_type = link_type;
+            }
+
+        }
+
+        Ok(reader)
+
+    }
+
+    /// Read the next UDP datagram in the capture, any other packet that is not UDP or
+    /// that has an unsupported link type is skipped. Fragmented IP packets are also
+    /// skipped. None is returned at the end of the capture.
+    pub fn next_datagram(&mut self) -> Result<Option<UdpDatagram>, PcapError> {
+        loop {
+
+            let Some((link_type, time, data)) = self.next_packet()? else {
+                return Ok(None);
+            };
+
+            if let Some(datagram) = parse_link(link_type, &data, self.big_endian) {
+                return Ok(Some(UdpDatagram { time, ..datagram }));
+            }
+
+        }
+    }
+
+    /// Read the next raw captured packet, returning its link type, capture time and
+    /// raw data.
+    fn next_packet(&mut self) -> Result<Option<(u32, Duration, Vec<u8>)>, PcapError> {
+        match self.format {
+            Format::Pcap { link_type, nanos } => {
+
+                let time_sec = match self.read_u32() {
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                };
+
+                let time_frac = self.read_u32()?;
+                let incl_len = self.read_u32()?;
+                let _orig_len = self.read_u32()?;
+
+                if incl_len > MAX_PACKET_LEN {
+                    return Err(PcapError::InvalidPacketLength(incl_len));
+                }
+
+                let time = Duration::from_secs(time_sec as u64) + if nanos {
+                    Duration::from_nanos(time_frac as u64)
+                } else {
+                    Duration::from_micros(time_frac as u64)
+                };
+
+                let mut data = vec![0; incl_len as usize];
+                self.inner.read_exact(&mut data)?;
+                Ok(Some((link_type, time, data)))
+
+            }
+            Format::Pcapng { .. } => self.next_pcapng_packet(),
+        }
+    }
+
+    fn next_pcapng_packet(&mut self) -> Result<Option<(u32, Duration, Vec<u8>)>, PcapError> {
+        loop {
+
+            let mut block_type_buf = [0; 4];
+            match self.inner.read_exact(&mut block_type_buf) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+
+            // The section header block has the same representation in both endianness.
+            if u32::from_le_bytes(block_type_buf) == PCAPNG_SECTION_HEADER {
+                self.read_pcapng_section_header()?;
+                continue;
+            }
+
+            let block_type = if self.big_endian {
+                u32::from_be_bytes(block_type_buf)
+            } else {
+                u32::from_le_bytes(block_type_buf)
+            };
+
+            let block_len = self.read_u32()? as usize;
+            if block_len < 12 || !block_len.is_multiple_of(4) || block_len > MAX_BLOCK_LEN as usize {
+                return Err(PcapError::InvalidBlockLength(block_len as u32));
+            }
+
+            // Block body, followed by the trailing length.
+            let mut body = vec![0; block_len - 12];
+            self.inner.read_exact(&mut body)?;
+            let _trailing_len = self.read_u32()?;
+
+            let Format::Pcapng { interfaces } = &mut self.format else { unreachable!() };
+            let big_endian = self.big_endian;
+            let read_u16 = |buf: &[u8]| if big_endian { u16::from_be_bytes([buf[0], buf[1]]) } else { u16::from_le_bytes([buf[0], buf[1]]) };
+            let read_u32 = |buf: &[u8]| if big_endian { u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) } else { u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) };
+
+            match block_type {
+                PCAPNG_INTERFACE_DESCRIPTION => {
+
+                    if body.len() < 8 {
+                        return Err(PcapError::InvalidBlockLength(block_len as u32));
+                    }
+
+                    let link_type = read_u16(&body[0..]) as u32;
+                    let mut units_per_sec = 1_000_000;
+
+                    // Parse options to find the timestamp resolution.
+                    let mut options = &body[8..];
+                    while options.len() >= 4 {
+                        let code = read_u16(&options[0..]);
+                        let len = read_u16(&options[2..]) as usize;
+                        let padded_len = (len + 3) & !3;
+                        let Some(value) = options.get(4..4 + len) else { break };
+                        if code == PCAPNG_OPT_IF_TSRESOL && len >= 1 {
+                            let resol = value[0];
+                            units_per_sec = if resol & 0x80 != 0 {
+                                1u64.checked_shl((resol & 0x7F) as u32).unwrap_or(u64::MAX)
+                            } else {
+                                10u64.checked_pow((resol & 0x7F) as u32).unwrap_or(u64::MAX)
+                            };
+                        } else if code == 0 {
+                            break;
+                        }
+                        options = options.get(4 + padded_len..).unwrap_or(&[]);
+                    }
+
+                    interfaces.push(Interface { link_type, units_per_sec });
+
+                }
+                PCAPNG_ENHANCED_PACKET => {
+
+                    if body.len() < 20 {
+                        return Err(PcapError::InvalidBlockLength(block_len as u32));
+                    }
+
+                    let interface_id = read_u32(&body[0..]);
+                    let time_high = read_u32(&body[4..]) as u64;
+                    let time_low = read_u32(&body[8..]) as u64;
+                    let cap_len = read_u32(&body[12..]);
+                    if cap_len > MAX_PACKET_LEN {
+                        return Err(PcapError::InvalidPacketLength(cap_len));
+                    }
+
+                    let interface = interfaces.get(interface_id as usize)
+                        .ok_or(PcapError::UnknownInterface(interface_id))?;
+
+                    let data = body.get(20..20 + cap_len as usize)
+                        .ok_or(PcapError::InvalidBlockLength(block_len as u32))?
+                        .to_vec();
+
+                    let units = (time_high << 32) | time_low;
+                    let secs = units / interface.units_per_sec;
+                    let frac = units % interface.units_per_sec;
+                    let time = Duration::from_secs(secs)
+                        + Duration::from_nanos((frac as u128 * 1_000_000_000 / interface.units_per_sec as u128) as u64);
+
+                    return Ok(Some((interface.link_type, time, data)));
+
+                }
+                PCAPNG_SIMPLE_PACKET => {
+
+                    let interface = interfaces.first()
+                        .ok_or(PcapError::UnknownInterface(0))?;
+
+                    if body.len() < 4 {
+                        return Err(PcapError::InvalidBlockLength(block_len as u32));
+                    }
+
+                    // The original length is the captured length unless truncated by
+                    // the snap length, so we just take the remaining body.
+                    let orig_len = read_u32(&body[0..]) as usize;
+                    let data = body[4..].get(..orig_len).unwrap_or(&body[4..]).to_vec();
+                    return Ok(Some((interface.link_type, Duration::ZERO, data)));
+
+                }
+                _ => {}  // Ignore any other block.
+            }
+
+        }
+    }
+
+    /// Read the section header block after its block type, this resets the interfaces.
+    fn read_pcapng_section_header(&mut self) -> Result<(), PcapError> {
+
+        let mut len_buf = [0; 4];
+        self.inner.read_exact(&mut len_buf)?;
+        let mut magic_buf = [0; 4];
+        self.inner.read_exact(&mut magic_buf)?;
+
+        if u32::from_le_bytes(magic_buf) == PCAPNG_BYTE_ORDER_MAGIC {
+            self.big_endian = false;
+        } else if u32::from_be_bytes(magic_buf) == PCAPNG_BYTE_ORDER_MAGIC {
+            self.big_endian = true;
+        } else {
+            return Err(PcapError::InvalidMagic(u32::from_le_bytes(magic_buf)));
+        }
+
+        let block_len = if self.big_endian {
+            u32::from_be_bytes(len_buf)
+        } else {
+            u32::from_le_bytes(len_buf)
+        } as usize;
+
+        if block_len < 28 || !block_len.is_multiple_of(4) {
+            return Err(PcapError::InvalidBlockLength(block_len as u32));
+        }
+
+        // Skip the remaining of the block: version, section length, options and
+        // trailing length.
+        io::copy(&mut (&mut self.inner).take(block_len as u64 - 12), &mut io::sink())?;
+
+        self.format = Format::Pcapng { interfaces: Vec::new() };
+        Ok(())
+
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0; 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(if self.big_endian { u32::from_be_bytes(buf) } else { u32::from_le_bytes(buf) })
+    }
+
+}
+
+/// A writer for pcapng capture, only writing UDP datagrams with synthetic IP and UDP
+/// headers, on a single raw IP interface with microsecond timestamps.
+#[derive(Debug)]
+pub struct PcapWriter<W> {
+    /// Underlying writer.
+    inner: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+
+    /// Create a new capture writer, writing the section header and interface blocks.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+
+        // Section header: byte order magic, version 1.0 and unknown section length.
+        let mut body = Vec::with_capacity(16);
+        body.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&u64::MAX.to_le_bytes());
+        write_pcapng_block(&mut inner, PCAPNG_SECTION_HEADER, &body)?;
+
+        // Interface description: link type, reserved and no snap length limit.
+        let mut body = Vec::with_capacity(8);
+        body.extend_from_slice(&(LINKTYPE_RAW as u16).to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        write_pcapng_block(&mut inner, PCAPNG_INTERFACE_DESCRIPTION, &body)?;
+
+        Ok(Self { inner })
+
+    }
+
+    /// Write an UDP datagram, the source and destination addresses must be of the 
+    /// same IP version, the capture time is relative to the UNIX epoch.
+    pub fn write_datagram(&mut self, datagram: &UdpDatagram) -> io::Result<()> {
+
+        let mut packet = Vec::with_capacity(48 + datagram.data.len());
+        let udp_len = u16::try_from(8 + datagram.data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "datagram too large"))?;
+
+        match (datagram.src.ip(), datagram.dst.ip()) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => {
+                let total_len = udp_len.checked_add(20)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "datagram too large"))?;
+                packet.extend_from_slice(&[0x45, 0]);
+                packet.extend_from_slice(&total_len.to_be_bytes());
+                // Identification, flags, fragment offset, ttl, protocol and checksum.
+                packet.extend_from_slice(&[0, 0, 0, 0, 64, IP_PROTO_UDP, 0, 0]);
+                packet.extend_from_slice(&src.octets());
+                packet.extend_from_slice(&dst.octets());
+            }
+            (IpAddr::V6(src), IpAddr::V6(dst)) => {
+                packet.extend_from_slice(&[0x60, 0, 0, 0]);
+                packet.extend_from_slice(&udp_len.to_be_bytes());
+                packet.extend_from_slice(&[IP_PROTO_UDP, 64]);
+                packet.extend_from_slice(&src.octets());
+                packet.extend_from_slice(&dst.octets());
+            }
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "mismatched ip versions")),
+        }
+
+        // Checksum is left to zero, which means no checksum for IPv4.
+        packet.extend_from_slice(&datagram.src.port().to_be_bytes());
+        packet.extend_from_slice(&datagram.dst.port().to_be_bytes());
+        packet.extend_from_slice(&udp_len.to_be_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        packet.extend_from_slice(&datagram.data);
+
+        let time = datagram.time.as_micros() as u64;
+        let mut body = Vec::with_capacity(20 + packet.len() + 3);
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&((time >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(time as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&packet);
+        body.resize((body.len() + 3) & !3, 0);
+
+        write_pcapng_block(&mut self.inner, PCAPNG_ENHANCED_PACKET, &body)
+
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+}
+
+/// Write a pcapng block with the given body, which length must be a multiple of 4.
+fn write_pcapng_block<W: Write>(writer: &mut W, block_type: u32, body: &[u8]) -> io::Result<()> {
+    debug_assert!(body.len().is_multiple_of(4));
+    let block_len = (body.len() + 12) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&block_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&block_len.to_le_bytes())
+}
+
+/// Parse the link layer of a packet and return the UDP datagram, if any. The capture
+/// endianness is only used for the null/loopback link type.
+fn parse_link(link_type: u32, data: &[u8], big_endian: bool) -> Option<UdpDatagram> {
+    match link_type {
+        LINKTYPE_NULL | LINKTYPE_LOOP => {
+            let family_buf = data.get(..4)?.try_into().unwrap();
+            let family = if link_type == LINKTYPE_LOOP || big_endian {
+                u32::from_be_bytes(family_buf)
+            } else {
+                u32::from_le_bytes(family_buf)
+            };
+            match family {
+                2 => parse_ipv4(&data[4..]),
+                // IPv6 family differs between operating systems.
+                10 | 24 | 28 | 30 => parse_ipv6(&data[4..]),
+                _ => None,
+            }
+        }
+        LINKTYPE_ETHERNET => {
+            let mut ether_type = u16::from_be_bytes(data.get(12..14)?.try_into().unwrap());
+            let mut offset = 14;
+            while ether_type == ETHERTYPE_VLAN {
+                ether_type = u16::from_be_bytes(data.get(offset + 2..offset + 4)?.try_into().unwrap());
+                offset += 4;
+            }
+            parse_ether_type(ether_type, &data[offset..])
+        }
+        LINKTYPE_RAW => match data.first()? >> 4 {
+            4 => parse_ipv4(data),
+            6 => parse_ipv6(data),
+            _ => None,
+        }
+        LINKTYPE_IPV4 => parse_ipv4(data),
+        LINKTYPE_IPV6 => parse_ipv6(data),
+        LINKTYPE_LINUX_SLL => {
+            let ether_type = u16::from_be_bytes(data.get(14..16)?.try_into().unwrap());
+            parse_ether_type(ether_type, &data[16..])
+        }
+        LINKTYPE_LINUX_SLL2 => {
+            let ether_type = u16::from_be_bytes(data.get(0..2)?.try_into().unwrap());
+            parse_ether_type(ether_type, data.get(20..)?)
+        }
+        _ => None,
+    }
+}
+
+fn parse_ether_type(ether_type: u16, data: &[u8]) -> Option<UdpDatagram> {
+    match ether_type {
+        ETHERTYPE_IPV4 => parse_ipv4(data),
+        ETHERTYPE_IPV6 => parse_ipv6(data),
+        _ => None,
+    }
+}
+
+fn parse_ipv4(data: &[u8]) -> Option<UdpDatagram> {
+
+    let header_len = (*data.first()? & 0x0F) as usize * 4;
+    let total_len = u16::from_be_bytes(data.get(2..4)?.try_into().unwrap()) as usize;
+    let fragment = u16::from_be_bytes(data.get(6..8)?.try_into().unwrap());
+
+    // More fragments flag or non-zero fragment offset, not supported.
+    if fragment & 0x3FFF != 0 {
+        return None;
+    }
+
+    if *data.get(9)? != IP_PROTO_UDP {
+        return None;
+    }
+
+    let src = Ipv4Addr::from(<[u8; 4]>::try_from(data.get(12..16)?).unwrap());
+    let dst = Ipv4Addr::from(<[u8; 4]>::try_from(data.get(16..20)?).unwrap());
+
+    // Total length may be zero with TCP segmentation offload, just ignore it.
+    let data = if total_len >= header_len {
+        data.get(header_len..total_len.min(data.len()))?
+    } else {
+        data.get(header_len..)?
+    };
+
+    parse_udp(IpAddr::V4(src), IpAddr::V4(dst), data)
+
+}
+
+fn parse_ipv6(data: &[u8]) -> Option<UdpDatagram> {
+
+    // We don't support extension headers.
+    if *data.get(6)? != IP_PROTO_UDP {
+        return None;
+    }
+
+    let payload_len = u16::from_be_bytes(data.get(4..6)?.try_into().unwrap()) as usize;
+    let src = Ipv6Addr::from(<[u8; 16]>::try_from(data.get(8..24)?).unwrap());
+    let dst = Ipv6Addr::from(<[u8; 16]>::try_from(data.get(24..40)?).unwrap());
+    let data = data.get(40..(40 + payload_len).min(data.len()))?;
+
+    parse_udp(IpAddr::V6(src), IpAddr::V6(dst), data)
+
+}
+
+fn parse_udp(src_ip: IpAddr, dst_ip: IpAddr, data: &[u8]) -> Option<UdpDatagram> {
+
+    let src_port = u16::from_be_bytes(data.get(0..2)?.try_into().unwrap());
+    let dst_port = u16::from_be_bytes(data.get(2..4)?.try_into().unwrap());
+    let len = u16::from_be_bytes(data.get(4..6)?.try_into().unwrap()) as usize;
+
+    let payload = data.get(8..len.max(8).min(data.len()))?;
+
+    Some(UdpDatagram {
+        time: Duration::ZERO,
+        src: SocketAddr::new(src_ip, src_port),
+        dst: SocketAddr::new(dst_ip, dst_port),
+        data: payload.to_vec(),
+    })
+
+}
+
+/// Errors that can happen while reading a capture.
+#[derive(Debug, Error)]
+pub enum PcapError {
+    /// Invalid magic signature for the file or a pcapng section.
+    #[error("invalid magic: 0x{0:08X}")]
+    InvalidMagic(u32),
+    /// Invalid length of a pcapng block.
+    #[error("invalid block length: {0}")]
+    InvalidBlockLength(u32),
+    /// Invalid captured length of a packet, larger than the maximum snap length.
+    #[error("invalid packet length: {0}")]
+    InvalidPacketLength(u32),
+    /// A pcapng packet references an interface that has not been described.
+    #[error("unknown interface: {0}")]
+    UnknownInterface(u32),
+    /// Underlying IO error while reading the file.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Return a pcap file header with the raw IPv4 link type.
+    fn pcap_header() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&PCAP_MAGIC_US.to_le_bytes());
+        data.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&LINKTYPE_IPV4.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn round_trip() {
+
+        let datagram = UdpDatagram {
+            time: Duration::from_micros(1_500_000),
+            src: "127.0.0.1:1234".parse().unwrap(),
+            dst: "127.0.0.1:20016".parse().unwrap(),
+            data: b"hello".to_vec(),
+        };
+
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        writer.write_datagram(&datagram).unwrap();
+        let data = writer.into_inner().unwrap();
+
+        let mut reader = PcapReader::new(&data[..]).unwrap();
+        let read = reader.next_datagram().unwrap().unwrap();
+        assert_eq!(read.time, datagram.time);
+        assert_eq!(read.src, datagram.src);
+        assert_eq!(read.dst, datagram.dst);
+        assert_eq!(read.data, datagram.data);
+        assert!(reader.next_datagram().unwrap().is_none());
+
+    }
+
+    #[test]
+    fn pcap_packet_too_large() {
+
+        let mut data = pcap_header();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut reader = PcapReader::new(&data[..]).unwrap();
+        assert!(matches!(reader.next_datagram(), Err(PcapError::InvalidPacketLength(u32::MAX))));
+
+    }
+
+    #[test]
+    fn pcapng_block_too_large() {
+
+        let mut data = PcapWriter::new(Vec::new()).unwrap().into_inner().unwrap();
+        data.extend_from_slice(&PCAPNG_ENHANCED_PACKET.to_le_bytes());
+        data.extend_from_slice(&0xFFFF_FFFCu32.to_le_bytes());
+
+        let mut reader = PcapReader::new(&data[..]).unwrap();
+        assert!(matches!(reader.next_datagram(), Err(PcapError::InvalidBlockLength(0xFFFF_FFFC))));
+
+        // A valid block declaring a too large captured length.
+        let mut data = PcapWriter::new(Vec::new()).unwrap().into_inner().unwrap();
+        let mut body = vec![0; 20];
+        body[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        write_pcapng_block(&mut data, PCAPNG_ENHANCED_PACKET, &body).unwrap();
+
+        let mut reader = PcapReader::new(&data[..]).unwrap();
+        assert!(matches!(reader.next_datagram(), Err(PcapError::InvalidPacketLength(u32::MAX))));
+
+    }
+
+}
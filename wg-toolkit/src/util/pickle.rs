@@ -0,0 +1,1056 @@
+//! Purpose-built reader of Python pickles, unlike serde-pickle it supports recursive
+//! structures, containers being shared between all their references, and objects of
+//! any class, which are kept with their construction arguments and state. Some
+//! well-known classes, such as `collections.deque`, are mapped to their builtin
+//! equivalent.
+//!
+//! All opcodes of protocols 0 to 4 are supported, except persistent ids and the
+//! extension registry.
+
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::fmt::{self, Write};
+use std::io::{self, Read};
+use std::rc::Rc;
+
+use byteorder::{ReadBytesExt, BE, LE};
+use thiserror::Error;
+
+
+const MARK: u8 = b'(';
+const STOP: u8 = b'.';
+const POP: u8 = b'0';
+const POP_MARK: u8 = b'1';
+const DUP: u8 = b'2';
+const FLOAT: u8 = b'F';
+const INT: u8 = b'I';
+const BININT: u8 = b'J';
+const BININT1: u8 = b'K';
+const LONG: u8 = b'L';
+const BININT2: u8 = b'M';
+const NONE: u8 = b'N';
+const REDUCE: u8 = b'R';
+const STRING: u8 = b'S';
+const BINSTRING: u8 = b'T';
+const SHORT_BINSTRING: u8 = b'U';
+const UNICODE: u8 = b'V';
+const BINUNICODE: u8 = b'X';
+const APPEND: u8 = b'a';
+const BUILD: u8 = b'b';
+const GLOBAL: u8 = b'c';
+const DICT: u8 = b'd';
+const EMPTY_DICT: u8 = b'}';
+const APPENDS: u8 = b'e';
+const GET: u8 = b'g';
+const BINGET: u8 = b'h';
+const INST: u8 = b'i';
+const LONG_BINGET: u8 = b'j';
+const LIST: u8 = b'l';
+const EMPTY_LIST: u8 = b']';
+const OBJ: u8 = b'o';
+const PUT: u8 = b'p';
+const BINPUT: u8 = b'q';
+const LONG_BINPUT: u8 = b'r';
+const SETITEM: u8 = b's';
+const TUPLE: u8 = b't';
+const EMPTY_TUPLE: u8 = b')';
+const SETITEMS: u8 = b'u';
+const BINFLOAT: u8 = b'G';
+// Protocol 2
+const PROTO: u8 = 0x80;
+const NEWOBJ: u8 = 0x81;
+const TUPLE1: u8 = 0x85;
+const TUPLE2: u8 = 0x86;
+const TUPLE3: u8 = 0x87;
+const NEWTRUE: u8 = 0x88;
+const NEWFALSE: u8 = 0x89;
+const LONG1: u8 = 0x8A;
+const LONG4: u8 = 0x8B;
+// Protocol 3
+const BINBYTES: u8 = b'B';
+const SHORT_BINBYTES: u8 = b'C';
+// Protocol 4
+const SHORT_BINUNICODE: u8 = 0x8C;
+const BINUNICODE8: u8 = 0x8D;
+const BINBYTES8: u8 = 0x8E;
+const EMPTY_SET: u8 = 0x8F;
+const ADDITEMS: u8 = 0x90;
+const FROZENSET: u8 = 0x91;
+const NEWOBJ_EX: u8 = 0x92;
+const STACK_GLOBAL: u8 = 0x93;
+const MEMOIZE: u8 = 0x94;
+const FRAME: u8 = 0x95;
+
+/// Highest protocol that can be announced by a pickle.
+const HIGHEST_PROTOCOL: u8 = 5;
+
+
+/// Read a pickle from the given reader, up to its stop opcode.
+pub fn from_reader<R: Read>(reader: R) -> Result<PyValue, PickleError> {
+    Unpickler {
+        reader,
+        stack: Vec::new(),
+        marks: Vec::new(),
+        memo: HashMap::new(),
+    }.load()
+}
+
+/// Read a pickle from the given slice, up to its stop opcode.
+pub fn from_slice(data: &[u8]) -> Result<PyValue, PickleError> {
+    from_reader(data)
+}
+
+
+/// A Python value read from a pickle. Mutable containers and objects are shared
+/// between all their references, and may therefore form cycles. Because they are
+/// reference counted, such cycles are never freed and their memory is leaked, this 
+/// is acceptable for the few recursive values found in practice, but values from 
+/// untrusted pickles should not be kept around in a long-running process.
+///
+/// The display implementation prints the value like Python's `repr`, with recursive
+/// references printed as `[...]`, `{...}` or `<...>` for objects.
+#[derive(Clone)]
+pub enum PyValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    /// An integer that doesn't fit in 64 bits, as little-endian two's complement bytes.
+    Long(Vec<u8>),
+    Float(f64),
+    /// A unicode string, or a Python 2 byte string that is valid UTF-8.
+    Str(String),
+    Bytes(Vec<u8>),
+    Tuple(Rc<[PyValue]>),
+    List(Rc<RefCell<Vec<PyValue>>>),
+    /// A dict, as a list of its items in order of insertion, because keys may not be
+    /// hashable.
+    Dict(Rc<RefCell<Vec<(PyValue, PyValue)>>>),
+    /// A set or frozen set.
+    Set(Rc<RefCell<Vec<PyValue>>>),
+    /// A reference to a class or function.
+    Global(Rc<PyGlobal>),
+    /// An object that is not mapped to a builtin value.
+    Object(Rc<RefCell<PyObject>>),
+}
+
+/// A reference to a class or function, by its module and name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyGlobal {
+    pub module: String,
+    pub name: String,
+}
+
+/// An object, as the call of a class or function that the pickle reduced it to, and
+/// its state and items that have been set afterward.
+#[derive(Debug)]
+pub struct PyObject {
+    /// The class or function called to create this object, usually a global.
+    pub class: PyValue,
+    /// Arguments of the call, usually a tuple.
+    pub args: PyValue,
+    /// State given to `__setstate__`, or used to update the object's dict.
+    pub state: Option<PyValue>,
+    /// Items appended to the object, if it's a list-like object.
+    pub list_items: Vec<PyValue>,
+    /// Items set on the object, if it's a dict-like object.
+    pub dict_items: Vec<(PyValue, PyValue)>,
+}
+
+impl PyValue {
+
+    /// Create a new list value.
+    pub fn new_list(items: Vec<PyValue>) -> Self {
+        Self::List(Rc::new(RefCell::new(items)))
+    }
+
+    /// Create a new dict value.
+    pub fn new_dict(items: Vec<(PyValue, PyValue)>) -> Self {
+        Self::Dict(Rc::new(RefCell::new(items)))
+    }
+
+    /// Create a new set value.
+    pub fn new_set(items: Vec<PyValue>) -> Self {
+        Self::Set(Rc::new(RefCell::new(items)))
+    }
+
+    /// Create a new object value, without state nor items.
+    pub fn new_object(class: PyValue, args: PyValue) -> Self {
+        Self::Object(Rc::new(RefCell::new(PyObject {
+            class,
+            args,
+            state: None,
+            list_items: Vec::new(),
+            dict_items: Vec::new(),
+        })))
+    }
+
+    /// Return a copy of the items of this value if it's a tuple, list or set.
+    fn to_items(&self) -> Option<Vec<PyValue>> {
+        match self {
+            PyValue::Tuple(items) => Some(items.to_vec()),
+            PyValue::List(items) |
+            PyValue::Set(items) => Some(items.borrow().clone()),
+            _ => None,
+        }
+    }
+
+    /// Return a copy of the items of this value if it's a dict, or a sequence of pairs.
+    fn to_dict_items(&self) -> Option<Vec<(PyValue, PyValue)>> {
+        if let PyValue::Dict(items) = self {
+            return Some(items.borrow().clone());
+        }
+        self.to_items()?
+            .into_iter()
+            .map(|item| match item.to_items()?.as_slice() {
+                [key, value] => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn fmt_repr(&self, f: &mut fmt::Formatter<'_>, parents: &mut Vec<*const ()>) -> fmt::Result {
+        match self {
+            PyValue::None => f.write_str("None"),
+            PyValue::Bool(true) => f.write_str("True"),
+            PyValue::Bool(false) => f.write_str("False"),
+            PyValue::Int(n) => write!(f, "{n}"),
+            PyValue::Long(bytes) => fmt_long(bytes, f),
+            PyValue::Float(n) => write!(f, "{n:?}"),
+            PyValue::Str(s) => write!(f, "{s:?}"),
+            PyValue::Bytes(bytes) => write!(f, "b\"{}\"", bytes.escape_ascii()),
+            PyValue::Tuple(items) => {
+                f.write_char('(')?;
+                fmt_seq(items, f, parents)?;
+                if items.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')
+            }
+            PyValue::List(items) => {
+                fmt_shared(Rc::as_ptr(items) as _, "[...]", f, parents, |f, parents| {
+                    f.write_char('[')?;
+                    fmt_seq(&items.borrow(), f, parents)?;
+                    f.write_char(']')
+                })
+            }
+            PyValue::Dict(items) => {
+                fmt_shared(Rc::as_ptr(items) as _, "{...}", f, parents, |f, parents| {
+                    f.write_char('{')?;
+                    fmt_items(&items.borrow(), f, parents)?;
+                    f.write_char('}')
+                })
+            }
+            PyValue::Set(items) => {
+                fmt_shared(Rc::as_ptr(items) as _, "{...}", f, parents, |f, parents| {
+                    let items = items.borrow();
+                    if items.is_empty() {
+                        return f.write_str("set()");
+                    }
+                    f.write_char('{')?;
+                    fmt_seq(&items, f, parents)?;
+                    f.write_char('}')
+                })
+            }
+            PyValue::Global(global) => write!(f, "{}.{}", global.module, global.name),
+            PyValue::Object(object) => {
+                fmt_shared(Rc::as_ptr(object) as _, "<...>", f, parents, |f, parents| {
+
+                    let object = object.borrow();
+                    object.class.fmt_repr(f, parents)?;
+                    f.write_char('(')?;
+
+                    let mut sep = match &object.args {
+                        PyValue::Tuple(args) => {
+                            fmt_seq(args, f, parents)?;
+                            !args.is_empty()
+                        }
+                        args => {
+                            f.write_str("*")?;
+                            args.fmt_repr(f, parents)?;
+                            true
+                        }
+                    };
+
+                    if let Some(state) = &object.state {
+                        f.write_str(if sep { ", state=" } else { "state=" })?;
+                        state.fmt_repr(f, parents)?;
+                        sep = true;
+                    }
+
+                    if !object.list_items.is_empty() {
+                        f.write_str(if sep { ", items=[" } else { "items=[" })?;
+                        fmt_seq(&object.list_items, f, parents)?;
+                        f.write_char(']')?;
+                        sep = true;
+                    }
+
+                    if !object.dict_items.is_empty() {
+                        f.write_str(if sep { ", dict_items={" } else { "dict_items={" })?;
+                        fmt_items(&object.dict_items, f, parents)?;
+                        f.write_char('}')?;
+                    }
+
+                    f.write_char(')')
+
+                })
+            }
+        }
+    }
+
+}
+
+impl fmt::Display for PyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_repr(f, &mut Vec::new())
+    }
+}
+
+impl fmt::Debug for PyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_repr(f, &mut Vec::new())
+    }
+}
+
+/// Format a shared container, if it's not already being formatted by a parent.
+fn fmt_shared<F>(ptr: *const (), recursive: &str, f: &mut fmt::Formatter<'_>, parents: &mut Vec<*const ()>, func: F) -> fmt::Result
+where
+    F: FnOnce(&mut fmt::Formatter<'_>, &mut Vec<*const ()>) -> fmt::Result,
+{
+    if parents.contains(&ptr) {
+        return f.write_str(recursive);
+    }
+    parents.push(ptr);
+    let res = func(f, parents);
+    parents.pop();
+    res
+}
+
+fn fmt_seq(items: &[PyValue], f: &mut fmt::Formatter<'_>, parents: &mut Vec<*const ()>) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i != 0 {
+            f.write_str(", ")?;
+        }
+        item.fmt_repr(f, parents)?;
+    }
+    Ok(())
+}
+
+fn fmt_items(items: &[(PyValue, PyValue)], f: &mut fmt::Formatter<'_>, parents: &mut Vec<*const ()>) -> fmt::Result {
+    for (i, (key, value)) in items.iter().enumerate() {
+        if i != 0 {
+            f.write_str(", ")?;
+        }
+        key.fmt_repr(f, parents)?;
+        f.write_str(": ")?;
+        value.fmt_repr(f, parents)?;
+    }
+    Ok(())
+}
+
+/// Format a long integer in decimal if it fits in 128 bits, or as two's complement
+/// hexadecimal otherwise.
+fn fmt_long(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if bytes.len() <= 16 {
+        let mut buf = [sign_fill(bytes); 16];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        write!(f, "{}", i128::from_le_bytes(buf))
+    } else {
+        f.write_str("0x")?;
+        for byte in bytes.iter().rev() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Return the byte used to sign-extend the given little-endian two's complement bytes.
+#[inline]
+fn sign_fill(bytes: &[u8]) -> u8 {
+    match bytes.last() {
+        Some(byte) if byte & 0x80 != 0 => 0xFF,
+        _ => 0x00,
+    }
+}
+
+
+/// Internal state of the pickle virtual machine.
+struct Unpickler<R> {
+    reader: R,
+    /// The stack of values.
+    stack: Vec<PyValue>,
+    /// Stack length at each mark.
+    marks: Vec<usize>,
+    /// Memoized values, containers are shared with the ones on the stack.
+    memo: HashMap<u32, PyValue>,
+}
+
+impl<R: Read> Unpickler<R> {
+
+    fn load(mut self) -> Result<PyValue, PickleError> {
+        loop {
+            let op = self.reader.read_u8()?;
+            match op {
+                PROTO => {
+                    let proto = self.reader.read_u8()?;
+                    if proto > HIGHEST_PROTOCOL {
+                        return Err(PickleError::UnsupportedProtocol(proto));
+                    }
+                }
+                FRAME => {
+                    // Frames are only a hint for buffering the reader.
+                    self.reader.read_u64::<LE>()?;
+                }
+                STOP => return self.pop(),
+                MARK => self.marks.push(self.stack.len()),
+                POP => {
+                    if self.marks.last() == Some(&self.stack.len()) {
+                        self.marks.pop();
+                    } else {
+                        self.pop()?;
+                    }
+                }
+                POP_MARK => {
+                    self.pop_mark()?;
+                }
+                DUP => {
+                    let value = self.top()?.clone();
+                    self.stack.push(value);
+                }
+                NONE => self.stack.push(PyValue::None),
+                NEWTRUE => self.stack.push(PyValue::Bool(true)),
+                NEWFALSE => self.stack.push(PyValue::Bool(false)),
+                INT => {
+                    let line = self.read_line()?;
+                    let value = match &line[..] {
+                        b"00" => PyValue::Bool(false),
+                        b"01" => PyValue::Bool(true),
+                        _ => PyValue::Int(parse_line(&line)?),
+                    };
+                    self.stack.push(value);
+                }
+                BININT => {
+                    let n = self.reader.read_i32::<LE>()?;
+                    self.stack.push(PyValue::Int(n as i64));
+                }
+                BININT1 => {
+                    let n = self.reader.read_u8()?;
+                    self.stack.push(PyValue::Int(n as i64));
+                }
+                BININT2 => {
+                    let n = self.reader.read_u16::<LE>()?;
+                    self.stack.push(PyValue::Int(n as i64));
+                }
+                LONG => {
+                    let mut line = self.read_line()?;
+                    if line.last() == Some(&b'L') {
+                        line.pop();
+                    }
+                    let n = parse_line::<i128>(&line)?;
+                    self.stack.push(long_from_bytes(n.to_le_bytes().to_vec()));
+                }
+                LONG1 => {
+                    let len = self.reader.read_u8()?;
+                    let bytes = self.read_blob(len as u64)?;
+                    self.stack.push(long_from_bytes(bytes));
+                }
+                LONG4 => {
+                    let len = self.reader.read_i32::<LE>()?;
+                    if len < 0 {
+                        return Err(PickleError::InvalidLength(len as i64));
+                    }
+                    let bytes = self.read_blob(len as u64)?;
+                    self.stack.push(long_from_bytes(bytes));
+                }
+                FLOAT => {
+                    let line = self.read_line()?;
+                    self.stack.push(PyValue::Float(parse_line(&line)?));
+                }
+                BINFLOAT => {
+                    let n = self.reader.read_f64::<BE>()?;
+                    self.stack.push(PyValue::Float(n));
+                }
+                STRING => {
+                    let line = self.read_line()?;
+                    let bytes = decode_string_escape(&line)
+                        .ok_or_else(|| PickleError::InvalidLine(String::from_utf8_lossy(&line).into_owned()))?;
+                    self.stack.push(str_or_bytes(bytes));
+                }
+                BINSTRING => {
+                    let len = self.reader.read_i32::<LE>()?;
+                    if len < 0 {
+                        return Err(PickleError::InvalidLength(len as i64));
+                    }
+                    let bytes = self.read_blob(len as u64)?;
+                    self.stack.push(str_or_bytes(bytes));
+                }
+                SHORT_BINSTRING => {
+                    let len = self.reader.read_u8()?;
+                    let bytes = self.read_blob(len as u64)?;
+                    self.stack.push(str_or_bytes(bytes));
+                }
+                UNICODE => {
+                    let line = self.read_line()?;
+                    self.stack.push(PyValue::Str(decode_raw_unicode_escape(&line)));
+                }
+                BINUNICODE | SHORT_BINUNICODE | BINUNICODE8 => {
+                    let len = self.read_len(op)?;
+                    let bytes = self.read_blob(len)?;
+                    self.stack.push(PyValue::Str(String::from_utf8_lossy(&bytes).into_owned()));
+                }
+                BINBYTES | SHORT_BINBYTES | BINBYTES8 => {
+                    let len = self.read_len(op)?;
+                    let bytes = self.read_blob(len)?;
+                    self.stack.push(PyValue::Bytes(bytes));
+                }
+                EMPTY_TUPLE => self.stack.push(PyValue::Tuple(Rc::from([]))),
+                TUPLE => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(PyValue::Tuple(Rc::from(items)));
+                }
+                TUPLE1 | TUPLE2 | TUPLE3 => {
+                    let len = (op - TUPLE1 + 1) as usize;
+                    let start = self.stack.len().checked_sub(len).ok_or(PickleError::StackUnderflow)?;
+                    let items = self.stack.split_off(start);
+                    self.stack.push(PyValue::Tuple(Rc::from(items)));
+                }
+                EMPTY_LIST => self.stack.push(PyValue::new_list(Vec::new())),
+                LIST => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(PyValue::new_list(items));
+                }
+                EMPTY_DICT => self.stack.push(PyValue::new_dict(Vec::new())),
+                DICT => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(PyValue::new_dict(into_pairs(op, items)?));
+                }
+                EMPTY_SET => self.stack.push(PyValue::new_set(Vec::new())),
+                FROZENSET => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(PyValue::new_set(items));
+                }
+                APPEND => {
+                    let item = self.pop()?;
+                    self.append_items(op, vec![item])?;
+                }
+                APPENDS | ADDITEMS => {
+                    let items = self.pop_mark()?;
+                    self.append_items(op, items)?;
+                }
+                SETITEM => {
+                    let value = self.pop()?;
+                    let key = self.pop()?;
+                    self.set_items(op, vec![(key, value)])?;
+                }
+                SETITEMS => {
+                    let items = self.pop_mark()?;
+                    let items = into_pairs(op, items)?;
+                    self.set_items(op, items)?;
+                }
+                GLOBAL => {
+                    let module = self.read_line()?;
+                    let name = self.read_line()?;
+                    self.stack.push(new_global(
+                        String::from_utf8_lossy(&module).into_owned(),
+                        String::from_utf8_lossy(&name).into_owned()));
+                }
+                STACK_GLOBAL => {
+                    let name = self.pop()?;
+                    let module = self.pop()?;
+                    let (PyValue::Str(module), PyValue::Str(name)) = (module, name) else {
+                        return Err(PickleError::InvalidValue(op));
+                    };
+                    self.stack.push(new_global(module, name));
+                }
+                REDUCE | NEWOBJ => {
+                    let args = self.pop()?;
+                    let class = self.pop()?;
+                    self.stack.push(reduce(class, args));
+                }
+                NEWOBJ_EX => {
+                    // Keyword arguments are ignored, objects only have positional ones.
+                    let _kwargs = self.pop()?;
+                    let args = self.pop()?;
+                    let class = self.pop()?;
+                    self.stack.push(reduce(class, args));
+                }
+                OBJ => {
+                    let mut items = self.pop_mark()?;
+                    if items.is_empty() {
+                        return Err(PickleError::StackUnderflow);
+                    }
+                    let class = items.remove(0);
+                    self.stack.push(reduce(class, PyValue::Tuple(Rc::from(items))));
+                }
+                INST => {
+                    let module = self.read_line()?;
+                    let name = self.read_line()?;
+                    let args = self.pop_mark()?;
+                    let class = new_global(
+                        String::from_utf8_lossy(&module).into_owned(),
+                        String::from_utf8_lossy(&name).into_owned());
+                    self.stack.push(reduce(class, PyValue::Tuple(Rc::from(args))));
+                }
+                BUILD => {
+                    let state = self.pop()?;
+                    // Classes mapped to builtin values only have instance attributes in
+                    // their state, which are dropped.
+                    if let PyValue::Object(object) = self.top()? {
+                        object.borrow_mut().state = Some(state);
+                    }
+                }
+                PUT => {
+                    let line = self.read_line()?;
+                    let index = parse_line(&line)?;
+                    self.put(index)?;
+                }
+                BINPUT => {
+                    let index = self.reader.read_u8()?;
+                    self.put(index as u32)?;
+                }
+                LONG_BINPUT => {
+                    let index = self.reader.read_u32::<LE>()?;
+                    self.put(index)?;
+                }
+                MEMOIZE => {
+                    let index = self.memo.len() as u32;
+                    self.put(index)?;
+                }
+                GET => {
+                    let line = self.read_line()?;
+                    let index = parse_line(&line)?;
+                    self.get(index)?;
+                }
+                BINGET => {
+                    let index = self.reader.read_u8()?;
+                    self.get(index as u32)?;
+                }
+                LONG_BINGET => {
+                    let index = self.reader.read_u32::<LE>()?;
+                    self.get(index)?;
+                }
+                _ => return Err(PickleError::UnsupportedOpcode(op)),
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Result<PyValue, PickleError> {
+        if self.marks.last() == Some(&self.stack.len()) {
+            return Err(PickleError::StackUnderflow);
+        }
+        self.stack.pop().ok_or(PickleError::StackUnderflow)
+    }
+
+    fn top(&self) -> Result<&PyValue, PickleError> {
+        self.stack.last().ok_or(PickleError::StackUnderflow)
+    }
+
+    /// Pop all values up to the last mark, and the mark itself.
+    fn pop_mark(&mut self) -> Result<Vec<PyValue>, PickleError> {
+        let mark = self.marks.pop().ok_or(PickleError::MissingMark)?;
+        if mark > self.stack.len() {
+            return Err(PickleError::StackUnderflow);
+        }
+        Ok(self.stack.split_off(mark))
+    }
+
+    fn put(&mut self, index: u32) -> Result<(), PickleError> {
+        let value = self.top()?.clone();
+        self.memo.insert(index, value);
+        Ok(())
+    }
+
+    fn get(&mut self, index: u32) -> Result<(), PickleError> {
+        let value = self.memo.get(&index).ok_or(PickleError::MissingMemo(index))?.clone();
+        self.stack.push(value);
+        Ok(())
+    }
+
+    /// Append items to the list, set or object on top of the stack.
+    fn append_items(&mut self, op: u8, items: Vec<PyValue>) -> Result<(), PickleError> {
+        match self.top()? {
+            PyValue::List(list) |
+            PyValue::Set(list) => list.borrow_mut().extend(items),
+            PyValue::Object(object) => object.borrow_mut().list_items.extend(items),
+            _ => return Err(PickleError::InvalidValue(op)),
+        }
+        Ok(())
+    }
+
+    /// Set items of the dict or object on top of the stack.
+    fn set_items(&mut self, op: u8, items: Vec<(PyValue, PyValue)>) -> Result<(), PickleError> {
+        match self.top()? {
+            PyValue::Dict(dict) => dict.borrow_mut().extend(items),
+            PyValue::Object(object) => object.borrow_mut().dict_items.extend(items),
+            _ => return Err(PickleError::InvalidValue(op)),
+        }
+        Ok(())
+    }
+
+    /// Read a line argument, without its line feed.
+    fn read_line(&mut self) -> Result<Vec<u8>, PickleError> {
+        let mut line = Vec::new();
+        loop {
+            match self.reader.read_u8()? {
+                b'\n' => break,
+                byte => line.push(byte),
+            }
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(line)
+    }
+
+    /// Read the length prefix of the given string or bytes opcode.
+    fn read_len(&mut self, op: u8) -> Result<u64, PickleError> {
+        Ok(match op {
+            SHORT_BINUNICODE | SHORT_BINBYTES => self.reader.read_u8()? as u64,
+            BINUNICODE | BINBYTES => self.reader.read_u32::<LE>()? as u64,
+            _ => self.reader.read_u64::<LE>()?,
+        })
+    }
+
+    fn read_blob(&mut self, len: u64) -> Result<Vec<u8>, PickleError> {
+        // Not allocating the length upfront, because it may be invalid.
+        let mut buf = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != len {
+            return Err(PickleError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        Ok(buf)
+    }
+
+}
+
+fn new_global(module: String, name: String) -> PyValue {
+    PyValue::Global(Rc::new(PyGlobal { module, name }))
+}
+
+/// Call the given class or function with the given arguments, well-known classes are
+/// mapped to their builtin value and all others create an object.
+fn reduce(class: PyValue, args: PyValue) -> PyValue {
+
+    if let PyValue::Global(global) = &class {
+
+        let first_arg = match &args {
+            PyValue::Tuple(args) => args.first(),
+            _ => None,
+        };
+
+        let value = match (global.module.as_str(), global.name.as_str()) {
+            ("__builtin__" | "builtins", "list") |
+            ("collections", "deque") => match first_arg {
+                Some(items) => items.to_items().map(PyValue::new_list),
+                None => Some(PyValue::new_list(Vec::new())),
+            },
+            ("__builtin__" | "builtins", "set" | "frozenset") => match first_arg {
+                Some(items) => items.to_items().map(PyValue::new_set),
+                None => Some(PyValue::new_set(Vec::new())),
+            },
+            ("__builtin__" | "builtins", "dict") |
+            ("collections", "OrderedDict" | "Counter") => match first_arg {
+                Some(items) => items.to_dict_items().map(PyValue::new_dict),
+                None => Some(PyValue::new_dict(Vec::new())),
+            },
+            // The first argument is the default factory, items are set afterward.
+            ("collections", "defaultdict") => Some(PyValue::new_dict(Vec::new())),
+            // Default reduction of objects of new-style classes in Python 2, the
+            // arguments are the class, its builtin base and the state of that base.
+            ("copy_reg" | "copyreg", "_reconstructor") => match &args {
+                PyValue::Tuple(args) if args.len() == 3 => {
+                    let base_args = match &args[2] {
+                        PyValue::None => PyValue::Tuple(Rc::from([])),
+                        base_state => PyValue::Tuple(Rc::from([base_state.clone()])),
+                    };
+                    Some(PyValue::new_object(args[0].clone(), base_args))
+                }
+                _ => None,
+            },
+            // Bytes pickled by Python 3 with protocols prior to 3.
+            ("_codecs", "encode") => match &args {
+                PyValue::Tuple(args) => match &args[..] {
+                    [PyValue::Str(s), PyValue::Str(encoding)] if encoding == "latin1" => {
+                        s.chars()
+                            .map(|c| u8::try_from(c).ok())
+                            .collect::<Option<Vec<_>>>()
+                            .map(PyValue::Bytes)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            return value;
+        }
+
+    }
+
+    PyValue::new_object(class, args)
+
+}
+
+/// Return an integer value from little-endian two's complement bytes.
+fn long_from_bytes(bytes: Vec<u8>) -> PyValue {
+    if bytes.len() <= 8 {
+        let mut buf = [sign_fill(&bytes); 8];
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        PyValue::Int(i64::from_le_bytes(buf))
+    } else {
+        PyValue::Long(bytes)
+    }
+}
+
+/// Python 2 strings are decoded if valid UTF-8, and kept as bytes otherwise.
+fn str_or_bytes(bytes: Vec<u8>) -> PyValue {
+    match String::from_utf8(bytes) {
+        Ok(s) => PyValue::Str(s),
+        Err(e) => PyValue::Bytes(e.into_bytes()),
+    }
+}
+
+fn into_pairs(op: u8, items: Vec<PyValue>) -> Result<Vec<(PyValue, PyValue)>, PickleError> {
+
+    if !items.len().is_multiple_of(2) {
+        return Err(PickleError::InvalidValue(op));
+    }
+
+    let mut pairs = Vec::with_capacity(items.len() / 2);
+    let mut items = items.into_iter();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        pairs.push((key, value));
+    }
+
+    Ok(pairs)
+
+}
+
+fn parse_line<T: std::str::FromStr>(line: &[u8]) -> Result<T, PickleError> {
+    std::str::from_utf8(line).ok()
+        .and_then(|s| s.trim().parse().ok())
+        .ok_or_else(|| PickleError::InvalidLine(String::from_utf8_lossy(line).into_owned()))
+}
+
+/// Decode the quoted and escaped representation of a Python 2 string.
+fn decode_string_escape(line: &[u8]) -> Option<Vec<u8>> {
+
+    let quote = *line.first()?;
+    if !matches!(quote, b'\'' | b'"') || line.len() < 2 || line[line.len() - 1] != quote {
+        return None;
+    }
+
+    let inner = &line[1..line.len() - 1];
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut i = 0;
+
+    while i < inner.len() {
+
+        let byte = inner[i];
+        i += 1;
+
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let escape = *inner.get(i)?;
+        i += 1;
+
+        match escape {
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'\\' | b'\'' | b'"' => bytes.push(escape),
+            b'x' => {
+                let hex = std::str::from_utf8(inner.get(i..i + 2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 2;
+            }
+            b'0'..=b'7' => {
+                let mut n = (escape - b'0') as u32;
+                for _ in 0..2 {
+                    match inner.get(i) {
+                        Some(&digit @ b'0'..=b'7') => {
+                            n = n * 8 + (digit - b'0') as u32;
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(n as u8);
+            }
+            _ => {
+                bytes.push(b'\\');
+                bytes.push(escape);
+            }
+        }
+
+    }
+
+    Some(bytes)
+
+}
+
+/// Decode a string with the raw unicode escape encoding, where only unicode escapes
+/// are decoded and other bytes are Latin-1.
+fn decode_raw_unicode_escape(line: &[u8]) -> String {
+
+    let mut s = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < line.len() {
+
+        if line[i] == b'\\' {
+            let len = match line.get(i + 1) {
+                Some(b'u') => 4,
+                Some(b'U') => 8,
+                _ => 0,
+            };
+            let c = line.get(i + 2..i + 2 + len)
+                .filter(|_| len != 0)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32);
+            if let Some(c) = c {
+                s.push(c);
+                i += 2 + len;
+                continue;
+            }
+        }
+
+        s.push(line[i] as char);
+        i += 1;
+
+    }
+
+    s
+
+}
+
+
+/// Errors that can happen while reading a pickle.
+#[derive(Debug, Error)]
+pub enum PickleError {
+    /// The opcode is unknown or not supported, such as persistent ids.
+    #[error("unsupported opcode: 0x{0:02X}")]
+    UnsupportedOpcode(u8),
+    /// The protocol announced by the pickle is too recent.
+    #[error("unsupported protocol: {0}")]
+    UnsupportedProtocol(u8),
+    /// An opcode needs more values than there are on the stack since the last mark.
+    #[error("stack underflow")]
+    StackUnderflow,
+    /// An opcode needs a mark but there is none.
+    #[error("missing mark")]
+    MissingMark,
+    /// A memoized value is requested but has not been put.
+    #[error("missing memo entry: {0}")]
+    MissingMemo(u32),
+    /// A value on the stack has an invalid type for the opcode.
+    #[error("invalid value for opcode 0x{0:02X}")]
+    InvalidValue(u8),
+    /// Invalid negative length of a string or integer.
+    #[error("invalid length: {0}")]
+    InvalidLength(i64),
+    /// Invalid line argument of a text opcode.
+    #[error("invalid line argument: {0:?}")]
+    InvalidLine(String),
+    /// Underlying IO error while reading the pickle.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn recursive() {
+
+        // l = []; l.append(l)
+        let value = from_slice(b"\x80\x02]q\x00h\x00a.").unwrap();
+        let PyValue::List(list) = &value else { panic!("expected a list: {value}") };
+        let PyValue::List(inner) = &list.borrow()[0] else { panic!("expected a list") };
+        assert!(Rc::ptr_eq(list, inner));
+        assert_eq!(value.to_string(), "[[...]]");
+
+        // d = {}; d["self"] = d
+        let value = from_slice(b"\x80\x02}q\x00X\x04\x00\x00\x00selfq\x01h\x00s.").unwrap();
+        let PyValue::Dict(dict) = &value else { panic!("expected a dict: {value}") };
+        let PyValue::Dict(inner) = &dict.borrow()[0].1 else { panic!("expected a dict") };
+        assert!(Rc::ptr_eq(dict, inner));
+        assert_eq!(value.to_string(), "{\"self\": {...}}");
+
+    }
+
+    #[test]
+    fn reconstructor() {
+
+        // Python 2, protocol 0: class Foo(object) with foo.a = 1
+        let value = from_slice(b"ccopy_reg\n_reconstructor\np0\n(c__main__\nFoo\np1\n\
+            c__builtin__\nobject\np2\nNtp3\nRp4\n(dp5\nS'a'\np6\nI1\nsb.").unwrap();
+
+        let PyValue::Object(object) = &value else { panic!("expected an object: {value}") };
+        let object = object.borrow();
+        let PyValue::Global(class) = &object.class else { panic!("expected a global") };
+        assert_eq!(**class, PyGlobal { module: "__main__".to_string(), name: "Foo".to_string() });
+        assert!(matches!(&object.args, PyValue::Tuple(args) if args.is_empty()));
+        drop(object);
+        
+        assert_eq!(value.to_string(), "__main__.Foo(state={\"a\": 1})");
+
+    }
+
+    #[test]
+    fn text_opcodes() {
+
+        let value = from_slice(b"(S'a\\n\\x41\\102\\'b\"'\nVcaf\\u00e9 \\x\nI01\nI-42\n\
+            L-12345678901234567890L\nF1.5\nt.").unwrap();
+
+        assert_eq!(value.to_string(), 
+            "(\"a\\nAB'b\\\"\", \"café \\\\x\", True, -42, -12345678901234567890, 1.5)");
+
+        assert!(matches!(from_slice(b"S'unterminated\n."), Err(PickleError::InvalidLine(_))));
+        assert!(matches!(from_slice(b"Inan\n."), Err(PickleError::InvalidLine(_))));
+
+    }
+
+    #[test]
+    fn long4() {
+
+        let value = from_slice(b"\x80\x02\x8b\x09\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01.").unwrap();
+        assert!(matches!(&value, PyValue::Long(bytes) if bytes.len() == 9));
+        assert_eq!(value.to_string(), "18446744073709551616");
+
+        let value = from_slice(b"\x80\x02\x8b\x02\x00\x00\x00\x00\xff.").unwrap();
+        assert!(matches!(value, PyValue::Int(-256)));
+
+        let value = from_slice(b"\x80\x02\x8b\x00\x00\x00\x00.").unwrap();
+        assert!(matches!(value, PyValue::Int(0)));
+
+    }
+
+    #[test]
+    fn malformed() {
+
+        assert!(matches!(from_slice(b"\x80\x02\x8b\xff\xff\xff\xff."), Err(PickleError::InvalidLength(-1))));
+        assert!(matches!(from_slice(b"T\x00\x00\x00\x80."), Err(PickleError::InvalidLength(_))));
+        assert!(matches!(from_slice(b"\x80\x02h\x05."), Err(PickleError::MissingMemo(5))));
+        assert!(matches!(from_slice(b"g3\n."), Err(PickleError::MissingMemo(3))));
+        assert!(matches!(from_slice(b"\x80\x06N."), Err(PickleError::UnsupportedProtocol(6))));
+        assert!(matches!(from_slice(b"\x80\x02a."), Err(PickleError::StackUnderflow)));
+        assert!(matches!(from_slice(b"\x80\x02t."), Err(PickleError::MissingMark)));
+        assert!(matches!(from_slice(b"\x80\x02K\x01K\x02a."), Err(PickleError::InvalidValue(APPEND))));
+        assert!(matches!(from_slice(b"P0\n."), Err(PickleError::UnsupportedOpcode(b'P'))));
+
+        // Lengths larger than the remaining data must not be allocated upfront.
+        let res = from_slice(b"\x80\x04\x8e\xff\xff\xff\xff\xff\xff\xff\x7fabc.");
+        assert!(matches!(res, Err(PickleError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+    }
+
+}
@@ -2,6 +2,7 @@
 
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use std::thread;
 
 use crossbeam_channel::{Receiver, Sender};
@@ -71,12 +72,24 @@ impl<T: Send + 'static> ThreadPoll<T> {
         ThreadPollHandle(alive)
     }
 
+    /// Return a sender that can be used to manually push values to the queue, from any
+    /// thread, in addition to the values of the spawned producers.
+    pub fn sender(&self) -> Sender<T> {
+        self.tx.clone()
+    }
+
     /// Block until a new value is available.
     pub fn poll(&self) -> T {
         // Unwrap because we own both ends so it should not disconnect.
         self.rx.recv().unwrap()
     }
 
+    /// Block until a new value is available, or the timeout is reached.
+    pub fn poll_timeout(&self, timeout: Duration) -> Option<T> {
+        // Don't care of the "disconnected" error because it should not happen.
+        self.rx.recv_timeout(timeout).ok()
+    }
+
     /// Non-blocking poll.
     pub fn try_poll(&self) -> Option<T> {
         // Don't care of the "disconnected" error because it should not happen.